@@ -0,0 +1,156 @@
+//! Medidas da busca, do filtro do histórico, do agrupamento por dia dos
+//! gráficos e da gravação/leitura do `estoque.json`, sobre históricos
+//! sintéticos de 1 mil, 10 mil e 100 mil entradas (um código para cada 10).
+//!
+//! Roda no criterion: `cargo bench` mede tudo e compara com a rodada
+//! anterior; `cargo bench -- busca` só as medidas cujo nome contém `busca`.
+//! Os relatórios ficam em `target/criterion`.
+
+use chrono::{Duration, NaiveDate};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use relogio::dominio::Quantidade;
+use relogio::persistencia::{self, Registros};
+use relogio::{App, Historico, Relogio};
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::{env, fs};
+
+const TAMANHOS: [usize; 3] = [1_000, 10_000, 100_000];
+
+/// Estoque e histórico com `entradas` lançamentos em dois anos, sempre os
+/// mesmos para o mesmo tamanho.
+fn dados(entradas: usize) -> (HashMap<String, Relogio>, Vec<Historico>) {
+    let mut rng = StdRng::seed_from_u64(entradas as u64);
+    let codigos: Vec<String> = (0..(entradas / 10).max(1))
+        .map(|i| format!("R{:05}", i))
+        .collect();
+    let relogios = codigos
+        .iter()
+        .map(|c| {
+            let mut r = Relogio::novo(c.clone(), Quantidade::nova(rng.gen_range(0..50)).unwrap());
+            r.custo = Some(rng.gen_range(50.0..500.0));
+            r.preco = Some(rng.gen_range(100.0..1000.0));
+            r.descricao = format!("Relógio {}", c);
+            (c.clone(), r)
+        })
+        .collect();
+    let inicio = NaiveDate::from_ymd_opt(2023, 1, 1).unwrap();
+    let historico = (0..entradas)
+        .map(|_| {
+            let dia = inicio + Duration::days(rng.gen_range(0..730));
+            let operacao = if rng.gen_bool(0.7) { "VENDA" } else { "COMPRA" };
+            Historico {
+                codigo: codigos[rng.gen_range(0..codigos.len())].clone(),
+                quantidade: rng.gen_range(1..5),
+                operacao: operacao.into(),
+                timestamp: format!(
+                    "{} {:02}:{:02}:00",
+                    dia,
+                    rng.gen_range(9..19),
+                    rng.gen_range(0..60)
+                ),
+                ..Default::default()
+            }
+        })
+        .collect();
+    (relogios, historico)
+}
+
+fn tecla(app: &mut App, code: KeyCode) {
+    app.handle_key(KeyEvent::new(code, KeyModifiers::NONE));
+}
+
+fn digita(app: &mut App, texto: &str) {
+    for c in texto.chars() {
+        tecla(app, KeyCode::Char(c));
+    }
+}
+
+/// Enter roda a busca na hora, sem esperar a pausa na digitação.
+fn busca(c: &mut Criterion) {
+    let mut grupo = c.benchmark_group("busca");
+    for n in TAMANHOS {
+        let (relogios, historico) = dados(n);
+        let mut app = App::com_estoque(relogios, historico);
+        let consultas = ["R001", "r12", "R0999", "9"];
+        let mut i = 0;
+        grupo.bench_function(BenchmarkId::from_parameter(n), |b| {
+            b.iter(|| {
+                tecla(&mut app, KeyCode::Char('b'));
+                digita(&mut app, consultas[i % consultas.len()]);
+                tecla(&mut app, KeyCode::Enter);
+                tecla(&mut app, KeyCode::Esc);
+                i += 1;
+            })
+        });
+    }
+    grupo.finish();
+}
+
+fn filtro_historico(c: &mut Criterion) {
+    let mut grupo = c.benchmark_group("filtro_historico");
+    for n in TAMANHOS {
+        let (relogios, historico) = dados(n);
+        let mut app = App::com_estoque(relogios, historico);
+        grupo.bench_function(BenchmarkId::from_parameter(n), |b| {
+            b.iter(|| {
+                tecla(&mut app, KeyCode::Char('h'));
+                tecla(&mut app, KeyCode::Char('p'));
+                digita(&mut app, "R00042");
+                tecla(&mut app, KeyCode::Enter);
+                tecla(&mut app, KeyCode::Esc);
+                tecla(&mut app, KeyCode::Esc);
+            })
+        });
+    }
+    grupo.finish();
+}
+
+/// O gráfico de barras por dia agrupa o histórico a cada desenho.
+fn agrupamento_por_dia(c: &mut Criterion) {
+    let mut grupo = c.benchmark_group("agrupamento_por_dia");
+    for n in TAMANHOS {
+        let (relogios, historico) = dados(n);
+        let mut app = App::com_estoque(relogios, historico);
+        tecla(&mut app, KeyCode::Char('g'));
+        grupo.bench_function(BenchmarkId::from_parameter(n), |b| {
+            b.iter(|| black_box(app.render_to_buffer(100, 32)))
+        });
+    }
+    grupo.finish();
+}
+
+/// Gravação e leitura do `estoque.json` numa pasta temporária.
+fn gravacao_e_leitura(c: &mut Criterion) {
+    let anterior = env::current_dir().unwrap();
+    let pasta = env::temp_dir().join(format!("relogio_bench_{}", std::process::id()));
+    fs::create_dir_all(&pasta).unwrap();
+    env::set_current_dir(&pasta).unwrap();
+    let registros = Registros::default();
+    for n in TAMANHOS {
+        let (relogios, historico) = dados(n);
+        persistencia::save_to_file(&relogios, &historico, &registros);
+        c.benchmark_group("gravacao")
+            .bench_function(BenchmarkId::from_parameter(n), |b| {
+                b.iter(|| persistencia::save_to_file(&relogios, &historico, &registros))
+            });
+        c.benchmark_group("leitura")
+            .bench_function(BenchmarkId::from_parameter(n), |b| {
+                b.iter(|| black_box(persistencia::load_from_file()))
+            });
+    }
+    env::set_current_dir(anterior).unwrap();
+    let _ = fs::remove_dir_all(&pasta);
+}
+
+criterion_group!(
+    desempenho,
+    busca,
+    filtro_historico,
+    agrupamento_por_dia,
+    gravacao_e_leitura
+);
+criterion_main!(desempenho);
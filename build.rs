@@ -1,3 +1,5 @@
 fn main() {
-    embed_resource::compile("app_icon.rc", std::iter::empty::<&str>());
+    embed_resource::compile("app_icon.rc", std::iter::empty::<&str>())
+        .manifest_optional()
+        .unwrap();
 }
@@ -0,0 +1,107 @@
+use crate::analise;
+use crate::{Historico, Relogio};
+use chrono::{Duration, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Lembrete com data (revisões agendadas, prazos de consignação etc.).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Lembrete {
+    pub data: String,
+    pub titulo: String,
+}
+
+struct Evento {
+    uid: String,
+    data: NaiveDate,
+    titulo: String,
+    descricao: String,
+}
+
+fn escapa(texto: &str) -> String {
+    texto
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Eventos de ruptura prevista e de data limite para repor (ruptura menos
+/// o prazo de reposição), a partir das vendas dos últimos 30 dias.
+fn eventos_estoque(
+    relogios: &HashMap<String, Relogio>,
+    historico: &[Historico],
+    prazo_reposicao: i64,
+    hoje: NaiveDate,
+) -> Vec<Evento> {
+    let velocidades = analise::velocidade_vendas(historico, 30, hoje);
+    let mut eventos = vec![];
+    let mut codigos: Vec<&String> = velocidades.keys().collect();
+    codigos.sort();
+    for codigo in codigos {
+        let (Some(r), Some(v)) = (relogios.get(codigo), velocidades.get(codigo)) else {
+            continue;
+        };
+        let Some(ruptura) = analise::previsao_ruptura(r, *v, hoje) else {
+            continue;
+        };
+        let descricao = format!(
+            "Estoque atual: {} un. Média de vendas: {:.2} un/dia.",
+            r.quantidade(),
+            v
+        );
+        eventos.push(Evento {
+            uid: format!("ruptura-{}", codigo),
+            data: ruptura,
+            titulo: format!("Ruptura prevista: {}", codigo),
+            descricao: descricao.clone(),
+        });
+        eventos.push(Evento {
+            uid: format!("repor-{}", codigo),
+            data: (ruptura - Duration::days(prazo_reposicao)).max(hoje),
+            titulo: format!("Repor {}", codigo),
+            descricao,
+        });
+    }
+    eventos
+}
+
+/// Calendário .ics com os lembretes cadastrados e as previsões de estoque.
+pub fn gera_ics(
+    relogios: &HashMap<String, Relogio>,
+    historico: &[Historico],
+    lembretes: &[Lembrete],
+    prazo_reposicao: i64,
+) -> String {
+    let agora = Local::now();
+    let hoje = agora.date_naive();
+    let mut eventos = eventos_estoque(relogios, historico, prazo_reposicao, hoje);
+    for (i, l) in lembretes.iter().enumerate() {
+        if let Ok(data) = NaiveDate::parse_from_str(&l.data, "%Y-%m-%d") {
+            eventos.push(Evento {
+                uid: format!("lembrete-{}", i),
+                data,
+                titulo: l.titulo.clone(),
+                descricao: String::new(),
+            });
+        }
+    }
+
+    let stamp = agora.naive_utc().format("%Y%m%dT%H%M%SZ");
+    let mut ics = String::from(
+        "BEGIN:VCALENDAR\r\nVERSION:2.0\r\nPRODID:-//WatchManager//Relogio//PT-BR\r\n",
+    );
+    for e in eventos {
+        ics.push_str(&format!(
+            "BEGIN:VEVENT\r\nUID:{}@watchmanager\r\nDTSTAMP:{}\r\nDTSTART;VALUE=DATE:{}\r\n\
+             SUMMARY:{}\r\nDESCRIPTION:{}\r\nEND:VEVENT\r\n",
+            e.uid,
+            stamp,
+            e.data.format("%Y%m%d"),
+            escapa(&e.titulo),
+            escapa(&e.descricao)
+        ));
+    }
+    ics.push_str("END:VCALENDAR\r\n");
+    ics
+}
@@ -0,0 +1,153 @@
+use crate::dominio::{ErroDominio, Inventario, ProdutoId, Quantidade};
+use crate::{Historico, Relogio};
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Tipo {
+    Venda,
+    Compra,
+}
+
+/// Venda ou compra com data de efeito futura (encomenda que sai na semana
+/// que vem, entrega combinada). Fica pendente, sem mexer no estoque, até o
+/// dia chegar.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Agendada {
+    pub numero: u32,
+    pub tipo: Tipo,
+    pub codigo: String,
+    pub quantidade: i32,
+    /// Data de efeito (AAAA-MM-DD).
+    pub data: String,
+    /// Forma de pagamento das vendas.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pagamento: Option<String>,
+    /// Quando foi registrada.
+    pub criada: String,
+}
+
+impl Agendada {
+    /// Efeito no saldo: positivo nas compras, negativo nas vendas.
+    pub fn efeito(&self) -> i32 {
+        match self.tipo {
+            Tipo::Venda => -self.quantidade,
+            Tipo::Compra => self.quantidade,
+        }
+    }
+}
+
+/// Registra a operação para `data` e devolve o número.
+pub fn agenda(
+    lista: &mut Vec<Agendada>,
+    tipo: Tipo,
+    codigo: &str,
+    quantidade: i32,
+    pagamento: Option<String>,
+    data: NaiveDate,
+    agora: NaiveDateTime,
+) -> u32 {
+    let numero = lista.iter().map(|a| a.numero).max().unwrap_or(0) + 1;
+    lista.push(Agendada {
+        numero,
+        tipo,
+        codigo: codigo.to_string(),
+        quantidade,
+        data: data.format("%Y-%m-%d").to_string(),
+        pagamento,
+        criada: agora.format("%Y-%m-%d %H:%M:%S").to_string(),
+    });
+    numero
+}
+
+/// Soma dos efeitos pendentes de um código.
+pub fn pendente(lista: &[Agendada], codigo: &str) -> i32 {
+    lista
+        .iter()
+        .filter(|a| a.codigo == codigo)
+        .map(Agendada::efeito)
+        .sum()
+}
+
+/// Lança as operações com data de efeito até `agora`, no horário do
+/// processamento: primeiro as compras, depois as vendas, cada grupo da mais
+/// antiga à mais nova. As que falham (ex.: venda sem saldo)
+/// continuam pendentes para a próxima abertura. Não grava.
+pub fn processa(
+    lista: &mut Vec<Agendada>,
+    relogios: &mut HashMap<String, Relogio>,
+    historico: &mut Vec<Historico>,
+    agora: NaiveDateTime,
+) -> Vec<(Agendada, Result<(), ErroDominio>)> {
+    let hoje = agora.format("%Y-%m-%d").to_string();
+    let mut vencidas: Vec<Agendada> = lista.iter().filter(|a| a.data <= hoje).cloned().collect();
+    vencidas.sort_by_key(|a| (a.tipo == Tipo::Venda, a.data.clone(), a.numero));
+    let mut resultados = vec![];
+    for a in vencidas {
+        let resultado = ProdutoId::novo(&a.codigo).and_then(|c| {
+            let q = Quantidade::movimento(a.quantidade)?;
+            let mut inventario = Inventario::new(relogios, historico);
+            match a.tipo {
+                Tipo::Venda => inventario.vende(&c, q, agora)?.pagamento = a.pagamento.clone(),
+                Tipo::Compra => {
+                    inventario.compra(&c, q, agora)?;
+                }
+            }
+            Ok(())
+        });
+        if resultado.is_ok() {
+            lista.retain(|x| x.numero != a.numero);
+        }
+        resultados.push((a, resultado));
+    }
+    resultados
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn so_mexe_no_estoque_quando_a_data_chega() {
+        let mut relogios = HashMap::new();
+        relogios.insert(
+            "R1".to_string(),
+            Relogio::novo("R1", Quantidade::nova(1).unwrap()),
+        );
+        let dia = |d| NaiveDate::from_ymd_opt(2025, 3, d).unwrap();
+        let agora = dia(1).and_hms_opt(10, 0, 0).unwrap();
+        let mut lista = vec![];
+        agenda(
+            &mut lista,
+            Tipo::Venda,
+            "R1",
+            2,
+            Some("pix".into()),
+            dia(5),
+            agora,
+        );
+        agenda(&mut lista, Tipo::Compra, "R1", 3, None, dia(8), agora);
+        assert_eq!(pendente(&lista, "R1"), 1);
+
+        let mut historico = vec![];
+        assert!(processa(&mut lista, &mut relogios, &mut historico, agora).is_empty());
+        assert_eq!(relogios["R1"].quantidade(), 1);
+
+        // A venda vence antes da compra chegar: falta saldo e ela espera
+        let dia6 = dia(6).and_hms_opt(9, 0, 0).unwrap();
+        let feitas = processa(&mut lista, &mut relogios, &mut historico, dia6);
+        assert!(matches!(
+            feitas[0].1,
+            Err(ErroDominio::EstoqueInsuficiente { .. })
+        ));
+        assert_eq!(lista.len(), 2);
+
+        let dia8 = dia(8).and_hms_opt(9, 0, 0).unwrap();
+        let feitas = processa(&mut lista, &mut relogios, &mut historico, dia8);
+        assert_eq!(feitas.len(), 2);
+        assert!(lista.is_empty());
+        assert_eq!(relogios["R1"].quantidade(), 2);
+        assert_eq!(historico[1].pagamento.as_deref(), Some("pix"));
+    }
+}
@@ -0,0 +1,213 @@
+/// Uma tela na ajuda (`?`): o nome, com a tecla que leva a ela, e o que
+/// cada tecla faz lá, com o formato do que se digita.
+pub struct Secao {
+    pub tela: &'static str,
+    pub teclas: &'static [(&'static str, &'static str)],
+}
+
+/// Telas na ordem em que aparecem na ajuda.
+pub const SECOES: &[Secao] = &[
+    Secao {
+        tela: "Estoque (tela inicial)",
+        teclas: &[
+            ("↑/↓", "move a seleção"),
+            (
+                "Enter",
+                "seleciona o relógio para comprar (A) ou vender (V)",
+            ),
+            ("C B H G", "cadastro, busca, histórico, gráficos"),
+            ("A / V", "comprar / vender"),
+            ("1–9 / P", "vende um favorito / prende ou solta a linha"),
+            ("+ / - / =", "ajusta uma unidade / informa a contagem"),
+            ("D / F2", "exclui / renomeia o código da linha"),
+            ("Espaço / O", "marca / compara os marcados"),
+            ("| / Tab", "estoque + histórico / troca o foco"),
+            ("~", "mostra ou esconde os arquivados"),
+            ("T", "estoque em data passada"),
+            ("F / Q", "destaque / quiosque"),
+            ("L / Shift+L", "etiqueta da linha / das pendentes"),
+            ("N / E", "notificações / recado"),
+            ("R / U / J", "recentes / fornecedores / receber mercadoria"),
+            ("Y / Z / @", "pedidos / orçamentos / clientes"),
+            (
+                "I / S / W",
+                "importar catálogo / configurações / Google Sheets",
+            ),
+            ("M / K / :", "preço de mercado / admin da API / console"),
+            ("?", "esta ajuda"),
+            ("Esc / X", "cancela a seleção / sai"),
+        ],
+    },
+    Secao {
+        tela: "Cadastro (C)",
+        teclas: &[
+            (
+                "codigo quantidade",
+                "cadastra ou redefine o saldo, ex.: R010 5",
+            ),
+            (
+                "... [un/caixa]",
+                "unidades por caixa, para comprar em caixas",
+            ),
+            ("custo= preco=", "ex.: R010 5 custo=120 preco=249,90"),
+            ("codigo preco=", "só troca o preço, sem mexer no saldo"),
+            ("kit", "KIT01 kit R001:1 PULS:1"),
+        ],
+    },
+    Secao {
+        tela: "Compra (A)",
+        teclas: &[
+            ("codigo quantidade", "ex.: R010 3 ou R010 2 caixas"),
+            ("fornecedor=nome", "ou forn=seiko: de quem veio o lote"),
+            ("@DD/MM", "data futura (@AAAA-MM-DD ou @N dias)"),
+            ("Tab", "troca pelo código sugerido"),
+        ],
+    },
+    Secao {
+        tela: "Venda (V)",
+        teclas: &[
+            ("codigo quantidade", "ex.: R002 1 pix"),
+            ("pagamento", "dinheiro (padrão), cartao, pix ou credito"),
+            ("!senha", "libera venda abaixo do custo ou acima do limite"),
+            ("@DD/MM", "pré-venda com data futura"),
+            ("Tab", "troca pelo código sugerido"),
+        ],
+    },
+    Secao {
+        tela: "Busca (B)",
+        teclas: &[
+            ("texto", "código por aproximação, ou o EAN lido"),
+            ("Enter / ↑/↓", "fecha a digitação / percorre os resultados"),
+        ],
+    },
+    Secao {
+        tela: "Histórico (H)",
+        teclas: &[
+            ("←/→ / ↑/↓", "abas / entradas"),
+            ("Enter", "detalhes; numa venda, N emite a nota"),
+            ("P", "filtra por código (ou fornecedor=nome)"),
+            ("Y", "anos arquivados"),
+            ("Espaço / *", "marca a entrada / todas da aba"),
+            ("T", "etiqueta as marcadas (-etiqueta remove)"),
+            ("F / E", "filtra por etiqueta / exporta CSV"),
+        ],
+    },
+    Secao {
+        tela: "Gráfico (G)",
+        teclas: &[
+            ("←/→", "troca o gráfico"),
+            ("↑/↓", "período ou variação do gráfico"),
+            ("E", "exporta CSV, SVG e PNG"),
+        ],
+    },
+    Secao {
+        tela: "Contagem (=)",
+        teclas: &[
+            ("quantidade [motivo]", "ex.: 7 quebra na vitrine"),
+            (
+                "arquivo.csv",
+                "balanço: C completo, Enter lança, Esc cancela",
+            ),
+        ],
+    },
+    Secao {
+        tela: "Importar catálogo (I)",
+        teclas: &[
+            (
+                "arquivo.csv [limite%]",
+                "alta de custo acima do limite fica desmarcada",
+            ),
+            ("S / E / C", "separador / codificação / cabeçalho"),
+            ("↑/↓ / ←/→", "campo / coluna de onde ele vem"),
+            ("Espaço / Enter", "marca a linha / aplica"),
+        ],
+    },
+    Secao {
+        tela: "Estoque em data passada (T)",
+        teclas: &[("DD/MM/AAAA", "ou AAAA-MM-DD; T ou Esc volta ao atual")],
+    },
+    Secao {
+        tela: "Recado (E)",
+        teclas: &[("autor;recado", "sem ;, assina com o usuário do sistema")],
+    },
+    Secao {
+        tela: "Renomear (F2)",
+        teclas: &[("novo código", "troca no cadastro, histórico e kits")],
+    },
+    Secao {
+        tela: "Notificações (N)",
+        teclas: &[("D / Esc", "apaga a selecionada / sai marcando como lidas")],
+    },
+    Secao {
+        tela: "Comparação (Espaço/O)",
+        teclas: &[("Esc", "volta ao estoque")],
+    },
+    Secao {
+        tela: "Usados recentemente (R)",
+        teclas: &[("Enter / Esc", "seleciona o relógio / volta")],
+    },
+    Secao {
+        tela: "Clientes (@)",
+        teclas: &[
+            ("N", "nome;telefone;observações (só o nome é obrigatório)"),
+            ("D / Enter", "remove / atende o cliente nas vendas"),
+        ],
+    },
+    Secao {
+        tela: "Fornecedores (U)",
+        teclas: &[
+            ("Tab", "cadastro ou códigos dos fornecedores"),
+            ("N (cadastro)", "nome;contato;observações"),
+            ("N (códigos)", "fornecedor codigo_do_fornecedor codigo"),
+            ("D", "remove o selecionado"),
+        ],
+    },
+    Secao {
+        tela: "Receber mercadoria (J)",
+        teclas: &[
+            ("fornecedor", "opcional; Enter continua"),
+            ("codigo [qtd]", "uma linha por leitura; qtd negativa desfaz"),
+            ("Enter vazio", "revisão: Del tira a linha, Enter lança tudo"),
+        ],
+    },
+    Secao {
+        tela: "Pedidos pendentes (Y)",
+        teclas: &[
+            ("N", "fornecedor codigo:qtd [codigo:qtd ...]"),
+            ("D / Tab", "cancela o que falta / sugestão de reposição"),
+        ],
+    },
+    Secao {
+        tela: "Orçamentos (Z)",
+        teclas: &[
+            (
+                "N",
+                "cliente;contato;codigo:qtd ...;retorno (dias ou DD/MM)",
+            ),
+            (
+                "G / P / A",
+                "ganho / perdido (pede o motivo) / adia o retorno",
+            ),
+        ],
+    },
+    Secao {
+        tela: "Console (:)",
+        teclas: &[
+            ("list | count", "sum/avg/min/max de qty ou value"),
+            ("where ... by ...", "ex.: sum qty where op=VENDA by code"),
+            ("> arquivo.csv", "também exporta o resultado"),
+        ],
+    },
+    Secao {
+        tela: "Venda no crédito, lista de espera e nota",
+        teclas: &[
+            ("cliente", "nome de quem paga com o crédito da loja"),
+            ("cliente;contato", "avisado quando o modelo chegar"),
+            ("nome;CPF;DD/MM", "dados da nota, todos opcionais"),
+        ],
+    },
+    Secao {
+        tela: "Configurações (S)",
+        teclas: &[("↑/↓ / Enter", "escolhe a chave / edita e grava")],
+    },
+];
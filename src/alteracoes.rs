@@ -0,0 +1,145 @@
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// Resume o que mudou entre duas versões do `estoque.json`: produtos
+/// incluídos, removidos ou com campos alterados, entradas do histórico a
+/// mais ou a menos e os demais cadastros que mudaram.
+pub fn resumo(antes: &Value, depois: &Value) -> Vec<String> {
+    let mut linhas = produtos(campo(antes, "relogios"), campo(depois, "relogios"));
+    linhas.extend(historico(
+        campo(antes, "historico"),
+        campo(depois, "historico"),
+    ));
+    let vazio = Map::new();
+    let (a, d) = (
+        antes.as_object().unwrap_or(&vazio),
+        depois.as_object().unwrap_or(&vazio),
+    );
+    let chaves: BTreeSet<&String> = a.keys().chain(d.keys()).collect();
+    for chave in chaves {
+        if chave == "relogios" || chave == "historico" || a.get(chave) == d.get(chave) {
+            continue;
+        }
+        let itens = |v: Option<&Value>| v.and_then(Value::as_array).map_or(0, Vec::len);
+        let (n_antes, n_depois) = (itens(a.get(chave)), itens(d.get(chave)));
+        linhas.push(if n_antes == n_depois {
+            format!("{}: alterado ({} itens).", chave, n_depois)
+        } else {
+            format!("{}: {} → {} itens.", chave, n_antes, n_depois)
+        });
+    }
+    linhas
+}
+
+fn campo<'a>(v: &'a Value, nome: &str) -> &'a [Value] {
+    v.get(nome)
+        .and_then(Value::as_array)
+        .map_or(&[], Vec::as_slice)
+}
+
+fn texto(v: Option<&Value>) -> String {
+    match v {
+        None | Some(Value::Null) => "—".into(),
+        Some(Value::String(s)) => s.clone(),
+        Some(outro) => outro.to_string(),
+    }
+}
+
+fn produtos(antes: &[Value], depois: &[Value]) -> Vec<String> {
+    let por_codigo = |lista: &[Value]| -> BTreeMap<String, Map<String, Value>> {
+        lista
+            .iter()
+            .filter_map(Value::as_object)
+            .map(|r| (texto(r.get("codigo")), r.clone()))
+            .collect()
+    };
+    let (a, d) = (por_codigo(antes), por_codigo(depois));
+    let mut linhas = vec![];
+    for (codigo, novo) in &d {
+        let Some(velho) = a.get(codigo) else {
+            linhas.push(format!("Produto {} incluído.", codigo));
+            continue;
+        };
+        let campos: BTreeSet<&String> = velho.keys().chain(novo.keys()).collect();
+        let mudancas: Vec<String> = campos
+            .into_iter()
+            .filter(|c| velho.get(*c) != novo.get(*c))
+            .map(|c| format!("{} {} → {}", c, texto(velho.get(c)), texto(novo.get(c))))
+            .collect();
+        if !mudancas.is_empty() {
+            linhas.push(format!("{}: {}.", codigo, mudancas.join(", ")));
+        }
+    }
+    for codigo in a.keys().filter(|c| !d.contains_key(*c)) {
+        linhas.push(format!("Produto {} removido.", codigo));
+    }
+    linhas
+}
+
+/// Entradas comparadas como conjunto (com repetição): a ordem não conta.
+fn historico(antes: &[Value], depois: &[Value]) -> Option<String> {
+    let mut contagem: BTreeMap<String, i64> = BTreeMap::new();
+    for h in antes {
+        *contagem.entry(h.to_string()).or_default() -= 1;
+    }
+    for h in depois {
+        *contagem.entry(h.to_string()).or_default() += 1;
+    }
+    let incluidas: i64 = contagem.values().filter(|n| **n > 0).sum();
+    let removidas: i64 = -contagem.values().filter(|n| **n < 0).sum::<i64>();
+    match (incluidas, removidas) {
+        (0, 0) => None,
+        (i, 0) => Some(format!("Histórico: {} entradas incluídas.", i)),
+        (0, r) => Some(format!("Histórico: {} entradas removidas.", r)),
+        (i, r) => Some(format!(
+            "Histórico: {} entradas incluídas e {} removidas (ou alteradas).",
+            i, r
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn resume_produtos_historico_e_cadastros() {
+        let antes = json!({
+            "relogios": [
+                {"codigo": "R001", "quantidade": 12, "preco": 249.9},
+                {"codigo": "R002", "quantidade": 0},
+            ],
+            "historico": [
+                {"codigo": "R001", "quantidade": 12, "operacao": "CADASTRO"},
+                {"codigo": "R002", "quantidade": 1, "operacao": "VENDA"},
+            ],
+            "promocoes": [],
+            "favoritos": ["R001"],
+        });
+        let depois = json!({
+            "relogios": [
+                {"codigo": "R001", "quantidade": 15},
+                {"codigo": "R003", "quantidade": 1},
+            ],
+            "historico": [
+                {"codigo": "R001", "quantidade": 12, "operacao": "CADASTRO"},
+                {"codigo": "R002", "quantidade": 2, "operacao": "VENDA"},
+            ],
+            "promocoes": [{"nome": "natal"}],
+            "favoritos": ["R002"],
+        });
+        assert_eq!(
+            resumo(&antes, &depois),
+            [
+                "R001: preco 249.9 → —, quantidade 12 → 15.",
+                "Produto R003 incluído.",
+                "Produto R002 removido.",
+                "Histórico: 1 entradas incluídas e 1 removidas (ou alteradas).",
+                "favoritos: alterado (1 itens).",
+                "promocoes: 0 → 1 itens.",
+            ]
+        );
+        assert!(resumo(&antes, &antes).is_empty());
+    }
+}
@@ -0,0 +1,224 @@
+use crate::{contabil, Historico, Relogio};
+use chrono::{Datelike, Duration, Months, NaiveDate, NaiveDateTime, Timelike};
+use std::collections::{BTreeMap, HashMap};
+
+/// Data (AAAA-MM-DD) de uma entrada do histórico.
+pub fn data_de(h: &Historico) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(h.timestamp.get(..10)?, "%Y-%m-%d").ok()
+}
+
+/// Unidades vendidas por dia de cada código nos últimos `dias` até `hoje`.
+pub fn velocidade_vendas(
+    historico: &[Historico],
+    dias: i64,
+    hoje: NaiveDate,
+) -> HashMap<String, f64> {
+    let inicio = hoje - Duration::days(dias);
+    let mut mapa: HashMap<String, f64> = HashMap::new();
+    for h in historico.iter().filter(|h| h.operacao == "VENDA") {
+        if data_de(h).is_some_and(|d| d > inicio && d <= hoje) {
+            *mapa.entry(h.codigo.clone()).or_insert(0.0) += h.quantidade as f64;
+        }
+    }
+    for v in mapa.values_mut() {
+        *v /= dias as f64;
+    }
+    mapa
+}
+
+/// Data prevista em que o estoque zera no ritmo de vendas atual.
+pub fn previsao_ruptura(r: &Relogio, velocidade: f64, hoje: NaiveDate) -> Option<NaiveDate> {
+    if velocidade <= 0.0 {
+        return None;
+    }
+    let dias = (r.quantidade().max(0) as f64 / velocidade).floor() as i64;
+    Some(hoje + Duration::days(dias))
+}
+
+/// Resumo diário das vendas: data, nº de vendas, unidades e receita.
+pub fn vendas_por_dia(
+    historico: &[Historico],
+    relogios: &HashMap<String, Relogio>,
+) -> Vec<(String, usize, i32, f64)> {
+    let mut mapa: BTreeMap<String, (usize, i32, f64)> = BTreeMap::new();
+    for h in historico.iter().filter(|h| h.operacao == "VENDA") {
+        let data = h.timestamp.get(..10).unwrap_or(&h.timestamp).to_string();
+        let preco = h.preco_tabela(relogios.get(&h.codigo));
+        let e = mapa.entry(data).or_insert((0, 0, 0.0));
+        e.0 += 1;
+        e.1 += h.quantidade;
+        e.2 += preco * h.quantidade as f64 * h.fator_preco();
+    }
+    mapa.into_iter()
+        .map(|(d, (n, u, r))| (d, n, u, r))
+        .collect()
+}
+
+/// Fim dos últimos `n` períodos até `hoje`, do mais antigo ao mais recente:
+/// fins de mês (o atual termina hoje) ou semanas terminadas hoje.
+pub fn fins_de_periodo(hoje: NaiveDate, mensal: bool, n: u32) -> Vec<NaiveDate> {
+    let mut fins: Vec<NaiveDate> = (0..n)
+        .filter_map(|i| {
+            if !mensal {
+                return Some(hoje - Duration::weeks(i as i64));
+            }
+            if i == 0 {
+                return Some(hoje);
+            }
+            hoje.with_day(1)?
+                .checked_sub_months(Months::new(i - 1))?
+                .pred_opt()
+        })
+        .collect();
+    fins.reverse();
+    fins
+}
+
+/// Receita de vendas em cada um dos 12 meses até o de `hoje` e no mesmo mês
+/// do ano anterior, do mais antigo ao mais recente: (1º dia do mês, receita,
+/// receita um ano antes).
+pub fn vendas_mes_a_mes(
+    historico: &[Historico],
+    relogios: &HashMap<String, Relogio>,
+    hoje: NaiveDate,
+) -> Vec<(NaiveDate, f64, f64)> {
+    let mut por_mes: HashMap<(i32, u32), f64> = HashMap::new();
+    for h in historico.iter().filter(|h| h.operacao == "VENDA") {
+        if let Some(d) = data_de(h) {
+            *por_mes.entry((d.year(), d.month())).or_insert(0.0) +=
+                contabil::valor(h, relogios.get(&h.codigo)).unwrap_or(0.0);
+        }
+    }
+    let receita = |d: NaiveDate| por_mes.get(&(d.year(), d.month())).copied().unwrap_or(0.0);
+    let Some(inicio) = hoje.with_day(1) else {
+        return vec![];
+    };
+    (0..12)
+        .rev()
+        .filter_map(|i| inicio.checked_sub_months(Months::new(i)))
+        .map(|mes| {
+            (
+                mes,
+                receita(mes),
+                receita(mes.with_year(mes.year() - 1).unwrap_or(mes)),
+            )
+        })
+        .collect()
+}
+
+/// Unidades e receita acumuladas até cada dia do mês.
+pub type Acumulado = Vec<(i32, f64)>;
+
+/// Vendas acumuladas dia a dia (unidades, receita) no mês de `hoje`, até
+/// hoje, e no mês anterior inteiro; a posição é o dia do mês menos um, para
+/// comparar com o mesmo dia do mês passado.
+pub fn acumulado_do_mes(
+    historico: &[Historico],
+    relogios: &HashMap<String, Relogio>,
+    hoje: NaiveDate,
+) -> (Acumulado, Acumulado) {
+    let Some(inicio) = hoje.with_day(1) else {
+        return (vec![], vec![]);
+    };
+    let anterior = inicio.checked_sub_months(Months::new(1)).unwrap_or(inicio);
+    let dias_anterior = (inicio - anterior).num_days() as usize;
+    let mut atual = vec![(0, 0.0); hoje.day() as usize];
+    let mut passado = vec![(0, 0.0); dias_anterior];
+    for h in historico.iter().filter(|h| h.operacao == "VENDA") {
+        let Some(d) = data_de(h).filter(|d| *d >= anterior && *d <= hoje) else {
+            continue;
+        };
+        let dias = if d >= inicio {
+            &mut atual
+        } else {
+            &mut passado
+        };
+        let dia = &mut dias[d.day0() as usize];
+        dia.0 += h.quantidade;
+        dia.1 += contabil::valor(h, relogios.get(&h.codigo)).unwrap_or(0.0);
+    }
+    for dias in [&mut atual, &mut passado] {
+        for i in 1..dias.len() {
+            dias[i].0 += dias[i - 1].0;
+            dias[i].1 += dias[i - 1].1;
+        }
+    }
+    (atual, passado)
+}
+
+/// Receita de vendas desde `desde` (inclusive; `None` = todo o histórico)
+/// agrupada pela chave de `grupo`, da maior para a menor.
+pub fn vendas_por_grupo(
+    historico: &[Historico],
+    relogios: &HashMap<String, Relogio>,
+    grupo: impl Fn(&Relogio) -> Option<String>,
+    desde: Option<NaiveDate>,
+) -> Vec<(String, f64)> {
+    let mut mapa: HashMap<String, f64> = HashMap::new();
+    for h in historico.iter().filter(|h| h.operacao == "VENDA") {
+        if desde.is_some_and(|d| data_de(h).is_none_or(|dh| dh < d)) {
+            continue;
+        }
+        let r = relogios.get(&h.codigo);
+        let chave = r.and_then(&grupo).unwrap_or_else(|| "(sem)".into());
+        *mapa.entry(chave).or_insert(0.0) += contabil::valor(h, r).unwrap_or(0.0);
+    }
+    let mut grupos: Vec<(String, f64)> = mapa.into_iter().collect();
+    grupos.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    grupos
+}
+
+/// Unidades vendidas desde `desde` por dia da semana (segunda = 0) e por
+/// hora do dia, para ver quando a loja realmente vende.
+pub fn padrao_de_vendas(
+    historico: &[Historico],
+    desde: Option<NaiveDate>,
+) -> ([u64; 7], [u64; 24]) {
+    let mut semana = [0; 7];
+    let mut horas = [0; 24];
+    for h in historico.iter().filter(|h| h.operacao == "VENDA") {
+        let Ok(momento) = NaiveDateTime::parse_from_str(&h.timestamp, "%Y-%m-%d %H:%M:%S") else {
+            continue;
+        };
+        if desde.is_some_and(|d| momento.date() < d) {
+            continue;
+        }
+        let qtd = h.quantidade.max(0) as u64;
+        semana[momento.weekday().num_days_from_monday() as usize] += qtd;
+        horas[momento.hour() as usize] += qtd;
+    }
+    (semana, horas)
+}
+
+/// Unidades de `codigo` vendidas em cada uma das últimas `n` semanas até
+/// `hoje`, da mais antiga à mais recente.
+pub fn vendas_semanais(
+    historico: &[Historico],
+    codigo: &str,
+    hoje: NaiveDate,
+    n: usize,
+) -> Vec<u64> {
+    let mut semanas = vec![0; n];
+    for h in historico
+        .iter()
+        .filter(|h| h.operacao == "VENDA" && h.codigo == codigo)
+    {
+        let Some(atras) = data_de(h).map(|d| (hoje - d).num_days() / 7) else {
+            continue;
+        };
+        if (0..n as i64).contains(&atras) {
+            semanas[n - 1 - atras as usize] += h.quantidade.max(0) as u64;
+        }
+    }
+    semanas
+}
+
+/// Preço unitário efetivamente cobrado em cada venda do produto, em ordem
+/// (o preço de tabela menos as promoções da época).
+pub fn precos_praticados(historico: &[Historico], r: &Relogio) -> Vec<f64> {
+    historico
+        .iter()
+        .filter(|h| h.operacao == "VENDA" && h.codigo == r.codigo && h.quantidade > 0)
+        .filter_map(|h| Some(contabil::valor(h, Some(r))? / h.quantidade as f64))
+        .collect()
+}
@@ -0,0 +1,201 @@
+use crate::dominio::Quantidade;
+use crate::persistencia::{self, na_pasta_dados, Registros};
+use crate::{Historico, Relogio};
+use chrono::{Datelike, Local};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// Operação usada nas linhas de resumo que substituem o histórico arquivado.
+pub const OP_SALDO_INICIAL: &str = "SALDO_INICIAL";
+
+fn ano_de(h: &Historico) -> Option<i32> {
+    h.timestamp.get(..4).and_then(|a| a.parse().ok())
+}
+
+fn caminho_ano(ano: i32) -> PathBuf {
+    na_pasta_dados(format!("historico_{}.json", ano))
+}
+
+/// Lista os anos que já possuem arquivo de histórico na pasta de dados.
+pub fn anos_arquivados() -> Vec<i32> {
+    let mut anos: Vec<i32> = fs::read_dir(na_pasta_dados("."))
+        .map(|dir| {
+            dir.filter_map(|e| e.ok())
+                .filter_map(|e| {
+                    let nome = e.file_name().into_string().ok()?;
+                    nome.strip_prefix("historico_")?
+                        .strip_suffix(".json")?
+                        .parse()
+                        .ok()
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    anos.sort();
+    anos
+}
+
+/// Carrega o histórico arquivado de um ano (vazio se não existir).
+pub fn carrega_ano(ano: i32) -> Vec<Historico> {
+    fs::read_to_string(caminho_ano(ano))
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+/// Mescla as entradas no arquivo do ano, sem repetir as que já estão lá
+/// (uma gravação interrompida é refeita na próxima vez).
+fn salva_ano(ano: i32, entradas: Vec<Historico>) -> io::Result<()> {
+    let chave = |h: &Historico| serde_json::to_string(h).unwrap_or_default();
+    let mut existentes = carrega_ano(ano);
+    let mut vistas: HashSet<String> = existentes.iter().map(chave).collect();
+    existentes.extend(entradas.into_iter().filter(|h| vistas.insert(chave(h))));
+    existentes.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+    let j = serde_json::to_string_pretty(&existentes)?;
+    persistencia::grava_atomico(&caminho_ano(ano), j.as_bytes())
+}
+
+/// Tira do histórico tudo o que tiver `anos` anos ou mais, deixando-o em
+/// `pendentes` até ir para `historico_<ano>.json` (ver [`grava`]), e
+/// substitui essas entradas por uma linha `SALDO_INICIAL` por código, datada
+/// do primeiro dia do ano mais antigo mantido. Não grava nada.
+///
+/// Retorna quantas entradas foram arquivadas.
+pub fn arquivar(
+    historico: &mut Vec<Historico>,
+    pendentes: &mut Vec<Historico>,
+    relogios: &HashMap<String, Relogio>,
+    anos: i32,
+) -> usize {
+    let limite = Local::now().year() - anos;
+
+    let (antigos, mantidos): (Vec<Historico>, Vec<Historico>) =
+        historico.drain(..).partition(|h| arquivavel(h, limite));
+
+    if antigos.is_empty() {
+        *historico = mantidos;
+        return 0;
+    }
+
+    let saldos = saldos(&antigos, relogios);
+    let timestamp = format!("{}-01-01 00:00:00", limite + 1);
+    let mut novo: Vec<Historico> = saldos
+        .into_iter()
+        .map(|r| Historico {
+            quantidade: r.quantidade(),
+            codigo: r.codigo,
+            operacao: OP_SALDO_INICIAL.into(),
+            timestamp: timestamp.clone(),
+            ..Default::default()
+        })
+        .collect();
+    novo.sort_by(|a, b| a.codigo.cmp(&b.codigo));
+    novo.extend(mantidos);
+    *historico = novo;
+
+    let n = antigos.len();
+    pendentes.extend(antigos);
+    n
+}
+
+/// Grava o estoque e, só depois de ele estar no disco, os arquivos dos anos
+/// com as entradas pendentes de [`arquivar`]; por fim grava o estoque de
+/// novo, já sem as pendentes. Se algo falhar no meio, as pendentes seguem no
+/// estoque e a próxima chamada termina o serviço sem duplicar nada.
+pub fn grava(
+    relogios: &HashMap<String, Relogio>,
+    historico: &[Historico],
+    registros: &mut Registros,
+) -> io::Result<()> {
+    persistencia::salva(relogios, historico, registros)?;
+    if registros.historico_a_arquivar.is_empty() {
+        return Ok(());
+    }
+    let mut por_ano: BTreeMap<i32, Vec<Historico>> = BTreeMap::new();
+    for h in &registros.historico_a_arquivar {
+        if let Some(a) = ano_de(h) {
+            por_ano.entry(a).or_default().push(h.clone());
+        }
+    }
+    for (ano, entradas) in por_ano {
+        salva_ano(ano, entradas)?;
+    }
+    registros.historico_a_arquivar.clear();
+    persistencia::salva(relogios, historico, registros)
+}
+
+fn arquivavel(h: &Historico, limite: i32) -> bool {
+    ano_de(h).is_some_and(|a| a <= limite)
+}
+
+/// O que `arquivar` faria, sem gravar nada: entradas por ano (e se o arquivo
+/// do ano já existe e seria mesclado) e quantas linhas `SALDO_INICIAL` entram.
+pub fn simula(
+    historico: &[Historico],
+    relogios: &HashMap<String, Relogio>,
+    anos: i32,
+) -> (Vec<(i32, usize, bool)>, usize) {
+    let limite = Local::now().year() - anos;
+    let antigos: Vec<Historico> = historico
+        .iter()
+        .filter(|h| arquivavel(h, limite))
+        .cloned()
+        .collect();
+    let mut por_ano: BTreeMap<i32, usize> = BTreeMap::new();
+    for a in antigos.iter().filter_map(ano_de) {
+        *por_ano.entry(a).or_default() += 1;
+    }
+    let existentes = anos_arquivados();
+    let linhas = por_ano
+        .into_iter()
+        .map(|(ano, n)| (ano, n, existentes.contains(&ano)))
+        .collect();
+    (linhas, saldos(&antigos, relogios).len())
+}
+
+/// Reproduz um trecho de histórico e devolve o saldo resultante por código.
+///
+/// Kit não tem saldo próprio: a `VENDA` dele baixa os componentes pelas
+/// `BAIXA_KIT` que a acompanham ou, em vendas anteriores a elas, pela
+/// composição atual do kit em `relogios`.
+pub fn saldos(historico: &[Historico], relogios: &HashMap<String, Relogio>) -> Vec<Relogio> {
+    let mut ordenado: Vec<&Historico> = historico.iter().collect();
+    ordenado.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+    let com_baixa: HashSet<(&str, &str)> = historico
+        .iter()
+        .filter(|h| h.operacao == "BAIXA_KIT")
+        .filter_map(|h| Some((h.kit.as_deref()?, h.timestamp.as_str())))
+        .collect();
+
+    let mut mapa: HashMap<String, i32> = HashMap::new();
+    for h in ordenado {
+        let componentes = relogios
+            .get(&h.codigo)
+            .map(|r| r.componentes.as_slice())
+            .unwrap_or_default();
+        if h.operacao == "VENDA" && !componentes.is_empty() {
+            if !com_baixa.contains(&(h.codigo.as_str(), h.timestamp.as_str())) {
+                for c in componentes {
+                    *mapa.entry(c.codigo.clone()).or_insert(0) -= c.quantidade * h.quantidade;
+                }
+            }
+            continue;
+        }
+        let qtd = mapa.entry(h.codigo.clone()).or_insert(0);
+        match h.operacao.as_str() {
+            "CADASTRO" | OP_SALDO_INICIAL => *qtd = h.quantidade,
+            "COMPRA" | "AJUSTE" => *qtd += h.quantidade,
+            "VENDA" | "BAIXA_KIT" => *qtd -= h.quantidade,
+            _ => {}
+        }
+    }
+    mapa.into_iter()
+        .map(|(codigo, quantidade)| {
+            let mut r = Relogio::novo(codigo, Quantidade::ZERO);
+            r.define_quantidade(quantidade);
+            r
+        })
+        .collect()
+}
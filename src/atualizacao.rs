@@ -0,0 +1,154 @@
+use crate::persistencia;
+use self_update::backends::github::Update;
+use self_update::Status;
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::{env, fs, io};
+
+const REPO_DONO: &str = "Gaok1";
+const REPO_NOME: &str = "WatchManager";
+const NOME_BINARIO: &str = "Relogio";
+
+/// Consulta a última release no GitHub e, se for mais nova, troca o
+/// executável pelo pacote da plataforma atual.
+pub fn atualizar() -> Result<(), Box<dyn std::error::Error>> {
+    let status = Update::configure()
+        .repo_owner(REPO_DONO)
+        .repo_name(REPO_NOME)
+        .bin_name(NOME_BINARIO)
+        .current_version(self_update::cargo_crate_version!())
+        .show_download_progress(true)
+        .no_confirm(true)
+        .build()?
+        .update()?;
+    match status {
+        Status::UpToDate(v) => println!("Já está na versão mais recente ({}).", v),
+        Status::Updated(v) => println!("Atualizado para a versão {}.", v),
+    }
+    Ok(())
+}
+
+/// Pasta de dados da plataforma: %APPDATA%\WatchManager no Windows,
+/// ~/Library/Application Support/WatchManager no macOS e
+/// ~/.local/share/WatchManager no Linux.
+pub fn diretorio_dados() -> Option<PathBuf> {
+    dirs::data_dir().map(|d| d.join("WatchManager"))
+}
+
+/// Configuração do usuário, fora da pasta de dados (o `config.toml` de lá
+/// é o da loja): `~/.config/watchmanager/config.toml` no Linux.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ConfigUsuario {
+    /// Pasta fixa com o `estoque.json` (ex.: numa pasta sincronizada).
+    pasta_dados: Option<String>,
+}
+
+fn caminho_config_usuario() -> Option<PathBuf> {
+    dirs::config_dir().map(|d| d.join("watchmanager").join("config.toml"))
+}
+
+/// `~/` no começo vira a pasta do usuário (no arquivo de configuração não
+/// há shell para expandir).
+fn expande_home(caminho: &str) -> PathBuf {
+    match (caminho.strip_prefix("~/"), dirs::home_dir()) {
+        (Some(resto), Some(home)) => home.join(resto),
+        _ => PathBuf::from(caminho),
+    }
+}
+
+/// Tira `--data <pasta>` dos argumentos, em qualquer posição.
+pub fn separa_pasta_dados(args: &[String]) -> Result<(Option<PathBuf>, Vec<String>), String> {
+    let mut pasta = None;
+    let mut resto = vec![];
+    let mut it = args.iter();
+    while let Some(a) = it.next() {
+        match a.strip_prefix("--data") {
+            Some("") => {
+                let p = it.next().ok_or("Uso: --data <pasta dos dados>")?;
+                pasta = Some(expande_home(p));
+            }
+            Some(p) if p.starts_with('=') => pasta = Some(expande_home(&p[1..])),
+            _ => resto.push(a.clone()),
+        }
+    }
+    Ok((pasta, resto))
+}
+
+/// Pasta em que trabalhar: a de `--data`, a `pasta_dados` da configuração
+/// do usuário, a atual se já tiver um `estoque.json` (instalações que
+/// guardam os dados ao lado do executável continuam como estão) ou a da
+/// plataforma. `None` fica na atual.
+fn escolhe_pasta(
+    pasta: Option<PathBuf>,
+    config_usuario: Option<&str>,
+    atual_tem_estoque: bool,
+) -> Result<Option<PathBuf>, String> {
+    if pasta.is_some() {
+        return Ok(pasta);
+    }
+    if let Some(texto) = config_usuario {
+        let config: ConfigUsuario =
+            toml::from_str(texto).map_err(|e| format!("Configuração do usuário: {}", e))?;
+        if let Some(p) = config.pasta_dados.filter(|p| !p.trim().is_empty()) {
+            return Ok(Some(expande_home(p.trim())));
+        }
+    }
+    if atual_tem_estoque {
+        return Ok(None);
+    }
+    Ok(diretorio_dados())
+}
+
+/// Passa a ler e gravar os dados na pasta escolhida (ver `escolhe_pasta`),
+/// criando-a se preciso. A pasta atual não muda: arquivos passados na linha
+/// de comando e exportações continuam relativos a ela.
+pub fn usa_diretorio_dados(pasta: Option<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
+    let config_usuario = caminho_config_usuario().and_then(|c| fs::read_to_string(c).ok());
+    let escolhida = escolhe_pasta(
+        pasta,
+        config_usuario.as_deref(),
+        Path::new("estoque.json").exists(),
+    )?;
+    let Some(dir) = escolhida else {
+        return Ok(());
+    };
+    fs::create_dir_all(&dir).map_err(|e| erro_pasta(&dir, e))?;
+    let dir = fs::canonicalize(&dir).map_err(|e| erro_pasta(&dir, e))?;
+    persistencia::define_pasta_dados(dir);
+    Ok(())
+}
+
+fn erro_pasta(dir: &Path, e: io::Error) -> String {
+    format!("Pasta de dados {}: {}", dir.display(), e)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flag_vence_a_configuracao_que_vence_a_pasta_atual() {
+        let args = |v: &[&str]| v.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        let (pasta, resto) =
+            separa_pasta_dados(&args(&["export", "--data", "/dados/loja", "--csv"])).unwrap();
+        assert_eq!(pasta, Some(PathBuf::from("/dados/loja")));
+        assert_eq!(resto, ["export", "--csv"]);
+        let (pasta, resto) = separa_pasta_dados(&args(&["--data=/x"])).unwrap();
+        assert_eq!((pasta, resto.len()), (Some(PathBuf::from("/x")), 0));
+        assert!(separa_pasta_dados(&args(&["--data"])).is_err());
+
+        let config = Some("pasta_dados = \"/mnt/sync/relogios\"\n");
+        assert_eq!(
+            escolhe_pasta(Some("/a".into()), config, true).unwrap(),
+            Some(PathBuf::from("/a"))
+        );
+        assert_eq!(
+            escolhe_pasta(None, config, true).unwrap(),
+            Some(PathBuf::from("/mnt/sync/relogios"))
+        );
+        assert_eq!(escolhe_pasta(None, Some(""), true).unwrap(), None);
+        assert_eq!(escolhe_pasta(None, None, false).unwrap(), diretorio_dados());
+        assert!(escolhe_pasta(None, Some("pasta_dados = "), true).is_err());
+    }
+}
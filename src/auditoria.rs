@@ -0,0 +1,46 @@
+use crate::persistencia::na_pasta_dados;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+
+const CAMINHO_ACESSOS: &str = "acessos_api.jsonl";
+/// Quantos acessos a tela Admin carrega do fim do log.
+pub const MAXIMO_RECENTES: usize = 500;
+
+/// Uma chamada à API ou ao WebSocket, gravada como uma linha JSON.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Acesso {
+    pub timestamp: String,
+    /// Nome do token usado; `None` quando ausente ou inválido.
+    pub token: Option<String>,
+    pub ip: String,
+    pub metodo: String,
+    /// Caminho sem a query string, que pode trazer o próprio token.
+    pub caminho: String,
+    pub status: u16,
+}
+
+pub fn registra(acesso: &Acesso) {
+    let Ok(linha) = serde_json::to_string(acesso) else {
+        return;
+    };
+    if let Ok(mut f) = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(na_pasta_dados(CAMINHO_ACESSOS))
+    {
+        // Uma escrita só por linha: as conexões do servidor registram em paralelo
+        let _ = f.write_all(format!("{}\n", linha).as_bytes());
+    }
+}
+
+/// Últimos `n` acessos, do mais recente para o mais antigo.
+pub fn recentes(n: usize) -> Vec<Acesso> {
+    let conteudo = fs::read_to_string(na_pasta_dados(CAMINHO_ACESSOS)).unwrap_or_default();
+    conteudo
+        .lines()
+        .rev()
+        .filter_map(|l| serde_json::from_str(l).ok())
+        .take(n)
+        .collect()
+}
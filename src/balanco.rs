@@ -0,0 +1,250 @@
+use crate::dominio::{ErroDominio, Inventario, ProdutoId, Quantidade};
+use crate::numeros::FormatoNumero;
+use crate::{codigo_por_ean, csv, Historico, Relogio};
+use chrono::NaiveDateTime;
+use std::collections::{BTreeMap, HashMap};
+
+/// Motivo gravado nos AJUSTEs do balanço.
+pub const MOTIVO: &str = "balanço (contagem por arquivo)";
+
+/// Lê o CSV do app de contagem: `codigo,quantidade` por linha, com ou sem
+/// cabeçalho, com os números no `formato` configurado. O código pode ser o
+/// EAN lido pelo celular; leituras do mesmo código em pontos diferentes da
+/// loja somam.
+pub fn le(
+    bytes: &[u8],
+    relogios: &HashMap<String, Relogio>,
+    formato: FormatoNumero,
+) -> Result<BTreeMap<String, i32>, String> {
+    let texto = csv::decodifica(bytes, csv::detecta_codificacao(bytes));
+    let mut linhas = texto
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .peekable();
+    let separador = csv::detecta_separador(linhas.peek().ok_or("Arquivo vazio.")?.1);
+    let mut contagem = BTreeMap::new();
+    for (j, (i, linha)) in linhas.enumerate() {
+        let numero = i + 1;
+        let campos = csv::divide_linha(linha, separador);
+        let (Some(codigo), Some(qtd)) = (campos.first(), campos.get(1)) else {
+            return Err(format!("Linha {}: faltam código e quantidade.", numero));
+        };
+        let Some(qtd) = formato.interpreta(qtd) else {
+            if j == 0 {
+                continue; // cabeçalho
+            }
+            return Err(format!("Linha {}: quantidade inválida '{}'.", numero, qtd));
+        };
+        if qtd.fract() != 0.0 || qtd < 0.0 || qtd > i32::MAX as f64 {
+            return Err(format!("Linha {}: quantidade inválida '{}'.", numero, qtd));
+        }
+        let codigo = codigo_por_ean(relogios, codigo).unwrap_or(codigo);
+        let total: &mut i32 = contagem.entry(codigo.to_string()).or_insert(0);
+        *total = total.checked_add(qtd as i32).ok_or_else(|| {
+            format!(
+                "Linha {}: a contagem de {} passa do máximo possível.",
+                numero, codigo
+            )
+        })?;
+    }
+    Ok(contagem)
+}
+
+/// Arquivo de contagem em conferência na tela do balanço.
+#[derive(Debug, Clone)]
+pub struct Balanco {
+    pub arquivo: String,
+    pub contagem: BTreeMap<String, i32>,
+    /// Zera o que tem saldo e não foi contado.
+    pub completo: bool,
+}
+
+/// Diferença entre o contado e o sistema num código.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Divergencia {
+    pub codigo: String,
+    pub descricao: String,
+    pub sistema: i32,
+    pub contado: i32,
+    /// Diferença a custo (positiva quando sobrou).
+    pub valor: f64,
+}
+
+impl Divergencia {
+    pub fn diferenca(&self) -> i32 {
+        self.contado - self.sistema
+    }
+}
+
+/// Resultado da conferência do arquivo com o estoque.
+#[derive(Debug, Default)]
+pub struct Conciliacao {
+    /// Códigos contados (e, no balanço completo, os não contados com saldo),
+    /// batendo ou não.
+    pub itens: Vec<Divergencia>,
+    /// Códigos do arquivo que não existem no cadastro.
+    pub desconhecidos: Vec<String>,
+    /// Códigos com saldo que ficaram fora de um balanço parcial.
+    pub nao_contados: Vec<String>,
+}
+
+impl Conciliacao {
+    pub fn divergentes(&self) -> impl Iterator<Item = &Divergencia> {
+        self.itens.iter().filter(|d| d.diferenca() != 0)
+    }
+}
+
+/// Compara a contagem com o estoque. Kits ficam de fora (o saldo deles vem
+/// dos componentes). No balanço `completo`, o que tem saldo e não aparece no
+/// arquivo conta como zero.
+pub fn concilia(
+    contagem: &BTreeMap<String, i32>,
+    relogios: &HashMap<String, Relogio>,
+    completo: bool,
+) -> Conciliacao {
+    let mut c = Conciliacao::default();
+    for codigo in contagem.keys().filter(|k| !relogios.contains_key(*k)) {
+        c.desconhecidos.push(codigo.clone());
+    }
+    let mut produtos: Vec<&Relogio> = relogios
+        .values()
+        .filter(|r| r.componentes.is_empty())
+        .collect();
+    produtos.sort_by(|a, b| a.codigo.cmp(&b.codigo));
+    for r in produtos {
+        let contado = match contagem.get(&r.codigo) {
+            Some(q) => *q,
+            None if completo => 0,
+            None => {
+                if r.quantidade() != 0 {
+                    c.nao_contados.push(r.codigo.clone());
+                }
+                continue;
+            }
+        };
+        c.itens.push(Divergencia {
+            codigo: r.codigo.clone(),
+            descricao: r.descricao.clone(),
+            sistema: r.quantidade(),
+            contado,
+            valor: (contado - r.quantidade()) as f64 * r.custo.unwrap_or(0.0),
+        });
+    }
+    c
+}
+
+/// Lança um AJUSTE por código divergente, todos no mesmo instante. Não grava.
+pub fn ajusta(
+    conciliacao: &Conciliacao,
+    relogios: &mut HashMap<String, Relogio>,
+    historico: &mut Vec<Historico>,
+    quando: NaiveDateTime,
+) -> Result<usize, ErroDominio> {
+    let mut inventario = Inventario::new(relogios, historico);
+    let mut n = 0;
+    for d in conciliacao.divergentes() {
+        inventario.ajusta(
+            &ProdutoId::novo(&d.codigo)?,
+            Quantidade::nova(d.contado)?,
+            MOTIVO,
+            quando,
+        )?;
+        n += 1;
+    }
+    Ok(n)
+}
+
+/// Relatório de divergências em CSV, com o total a custo no fim.
+pub fn relatorio_csv(conciliacao: &Conciliacao, formato: FormatoNumero) -> String {
+    let mut out = String::from("Código;Descrição;Sistema;Contado;Diferença;Valor a custo\n");
+    for d in conciliacao.divergentes() {
+        out.push_str(&format!(
+            "{};{};{};{};{:+};{}\n",
+            d.codigo,
+            d.descricao.replace(';', ","),
+            d.sistema,
+            d.contado,
+            d.diferenca(),
+            formato.numero(d.valor, 2)
+        ));
+    }
+    for codigo in &conciliacao.desconhecidos {
+        out.push_str(&format!("{};(não cadastrado);;;;\n", codigo));
+    }
+    for codigo in &conciliacao.nao_contados {
+        out.push_str(&format!("{};(não contado);;;;\n", codigo));
+    }
+    let total: f64 = conciliacao.divergentes().map(|d| d.valor).sum();
+    out.push_str(&format!("Total;;;;;{}\n", formato.numero(total, 2)));
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Local;
+
+    #[test]
+    fn concilia_e_ajusta_so_as_diferencas() {
+        let mut relogios = HashMap::new();
+        for (codigo, qtd, ean) in [
+            ("R001", 12, None),
+            ("R002", 0, Some("4006381333931")),
+            ("R003", 3, None),
+        ] {
+            relogios.insert(codigo.to_string(), {
+                let mut r = Relogio::novo(codigo, Quantidade::nova(qtd).unwrap());
+                r.custo = Some(100.0);
+                r.ean = ean.map(str::to_string);
+                r
+            });
+        }
+        let arquivo = b"code,counted_qty\nR001,10\n4006381333931,1\nR001,1\nR404,2\n";
+        let contagem = le(arquivo, &relogios, FormatoNumero::PtBr).unwrap();
+        assert_eq!(contagem["R001"], 11);
+        assert_eq!(contagem["R002"], 1);
+        assert!(le(b"R001;1.5\n", &relogios, FormatoNumero::PtBr).is_err());
+        // Linhas em branco contam no número da linha do erro
+        assert_eq!(
+            le(
+                b"codigo;qtd\n\nR001;2\n\nR001;x\n",
+                &relogios,
+                FormatoNumero::PtBr
+            ),
+            Err("Linha 5: quantidade inválida 'x'.".into())
+        );
+        assert_eq!(
+            le(
+                b"R001;2000000000\nR001;2000000000\n",
+                &relogios,
+                FormatoNumero::PtBr
+            ),
+            Err("Linha 2: a contagem de R001 passa do máximo possível.".into())
+        );
+
+        let parcial = concilia(&contagem, &relogios, false);
+        assert_eq!(parcial.divergentes().count(), 2);
+        assert_eq!(parcial.desconhecidos, ["R404"]);
+        assert_eq!(parcial.nao_contados, ["R003"]);
+        let completo = concilia(&contagem, &relogios, true);
+        assert_eq!(completo.divergentes().count(), 3);
+        let relatorio = relatorio_csv(&completo, FormatoNumero::default());
+        assert!(relatorio.contains("R003;;3;0;-3;-300,00"));
+        assert!(relatorio.ends_with("Total;;;;;-300,00\n"));
+
+        let mut historico = vec![];
+        let agora = Local::now().naive_local();
+        assert_eq!(
+            ajusta(&parcial, &mut relogios, &mut historico, agora),
+            Ok(2)
+        );
+        assert_eq!(
+            (relogios["R001"].quantidade(), relogios["R003"].quantidade()),
+            (11, 3)
+        );
+        assert!(historico
+            .iter()
+            .all(|h| h.motivo.as_deref() == Some(MOTIVO)));
+    }
+}
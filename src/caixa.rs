@@ -0,0 +1,54 @@
+use crate::{contabil, Historico, Relogio};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+pub const SANGRIA: &str = "SANGRIA";
+pub const SUPRIMENTO: &str = "SUPRIMENTO";
+
+/// Entrada ou saída de dinheiro da gaveta que não é venda: sangria
+/// (retirada) ou suprimento (troco/fundo de caixa).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MovimentoCaixa {
+    pub tipo: String,
+    pub valor: f64,
+    pub motivo: String,
+    pub timestamp: String,
+}
+
+/// Fechamento do caixa de um dia.
+#[derive(Debug, Clone, Default)]
+pub struct Fechamento {
+    pub vendas_dinheiro: f64,
+    pub suprimentos: f64,
+    pub sangrias: f64,
+}
+
+impl Fechamento {
+    /// Valor que deve estar na gaveta ao fim do dia.
+    pub fn esperado(&self) -> f64 {
+        self.vendas_dinheiro + self.suprimentos - self.sangrias
+    }
+}
+
+/// Soma as vendas em dinheiro e os movimentos de caixa da data (AAAA-MM-DD).
+pub fn fechamento(
+    historico: &[Historico],
+    relogios: &HashMap<String, Relogio>,
+    movimentos: &[MovimentoCaixa],
+    data: &str,
+) -> Fechamento {
+    let mut f = Fechamento::default();
+    for h in historico.iter().filter(|h| h.timestamp.starts_with(data)) {
+        if h.operacao == "VENDA" && h.pagamento.as_deref().unwrap_or("dinheiro") == "dinheiro" {
+            f.vendas_dinheiro += contabil::valor(h, relogios.get(&h.codigo)).unwrap_or(0.0);
+        }
+    }
+    for m in movimentos.iter().filter(|m| m.timestamp.starts_with(data)) {
+        match m.tipo.as_str() {
+            SANGRIA => f.sangrias += m.valor,
+            SUPRIMENTO => f.suprimentos += m.valor,
+            _ => {}
+        }
+    }
+    f
+}
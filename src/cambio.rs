@@ -0,0 +1,63 @@
+use crate::persistencia::na_pasta_dados;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+
+const CAMINHO_CACHE: &str = "cambio.json";
+/// Campos reconhecidos como taxa de câmbio na resposta do provedor.
+const CAMPOS_TAXA: &[&str] = &["bid", "rate", "taxa", "cotacao", "valor"];
+
+/// Última cotação obtida para uma moeda, em reais.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cotacao {
+    pub data: String,
+    pub taxa: f64,
+}
+
+pub fn carrega_cache() -> HashMap<String, Cotacao> {
+    fs::read_to_string(na_pasta_dados(CAMINHO_CACHE))
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+pub fn salva_cache(cache: &HashMap<String, Cotacao>) {
+    if let Ok(j) = serde_json::to_string_pretty(cache) {
+        let _ = fs::write(na_pasta_dados(CAMINHO_CACHE), j);
+    }
+}
+
+fn extrai_taxa(v: &Value) -> Option<f64> {
+    match v {
+        Value::Object(obj) => obj
+            .iter()
+            .find_map(|(chave, valor)| {
+                CAMPOS_TAXA
+                    .contains(&chave.to_lowercase().as_str())
+                    .then(|| {
+                        valor
+                            .as_f64()
+                            .or_else(|| valor.as_str().and_then(|s| s.parse().ok()))
+                    })
+                    .flatten()
+            })
+            .or_else(|| obj.values().find_map(extrai_taxa)),
+        Value::Array(itens) => itens.iter().find_map(extrai_taxa),
+        _ => None,
+    }
+}
+
+/// Consulta o provedor; `{moeda}` no modelo de URL vira o código (ex.: USD).
+pub fn busca(url_modelo: &str, moeda: &str) -> Result<f64, String> {
+    let url = url_modelo.replace("{moeda}", moeda);
+    let json: Value = ureq::get(&url)
+        .timeout(std::time::Duration::from_secs(10))
+        .call()
+        .map_err(|e| e.to_string())?
+        .into_json()
+        .map_err(|e| e.to_string())?;
+    extrai_taxa(&json)
+        .filter(|t| *t > 0.0)
+        .ok_or_else(|| "resposta sem taxa de câmbio".into())
+}
@@ -0,0 +1,173 @@
+use crate::dominio::{ErroDominio, Inventario, ProdutoId, Quantidade};
+use crate::numeros::FormatoNumero;
+use crate::{csv, Historico, Relogio};
+use chrono::NaiveDateTime;
+use std::collections::HashMap;
+
+/// Produto lido da planilha de carga inicial.
+#[derive(Debug, PartialEq)]
+pub struct Item {
+    pub codigo: ProdutoId,
+    pub quantidade: Quantidade,
+    pub preco: Option<f64>,
+}
+
+/// Linhas aceitas e os erros das demais (com o número da linha no arquivo).
+#[derive(Debug, Default)]
+pub struct Carga {
+    pub itens: Vec<Item>,
+    pub erros: Vec<String>,
+}
+
+/// Lê o CSV exportado do sistema antigo: `codigo,quantidade[,preco]` por
+/// linha, com ou sem cabeçalho, em qualquer separador aceito e com os números
+/// no `formato` configurado. Códigos já cadastrados ou repetidos no arquivo
+/// são erro: a carga não mexe em saldo existente (para isso há o balanço).
+/// Quantidades acima de `maximo` (0 = sem limite) também, como na digitação.
+pub fn le(
+    bytes: &[u8],
+    relogios: &HashMap<String, Relogio>,
+    formato: FormatoNumero,
+    maximo: i32,
+) -> Result<Carga, String> {
+    let texto = csv::decodifica(bytes, csv::detecta_codificacao(bytes));
+    let mut linhas = texto
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .peekable();
+    let separador = csv::detecta_separador(linhas.peek().ok_or("Arquivo vazio.")?.1);
+    let mut carga = Carga::default();
+    for (j, (i, linha)) in linhas.enumerate() {
+        let campos = csv::divide_linha(linha, separador);
+        let numero = i + 1;
+        if j == 0
+            && campos
+                .get(1)
+                .is_some_and(|q| formato.interpreta(q).is_none())
+        {
+            continue; // cabeçalho
+        }
+        let item = le_item(&campos, formato, maximo).and_then(|item| {
+            if relogios.contains_key(item.codigo.as_str()) {
+                Err(format!("{} já está cadastrado.", item.codigo))
+            } else if carga.itens.iter().any(|i| i.codigo == item.codigo) {
+                Err(format!("{} repetido no arquivo.", item.codigo))
+            } else {
+                Ok(item)
+            }
+        });
+        match item {
+            Ok(item) => carga.itens.push(item),
+            Err(e) => carga.erros.push(format!("Linha {}: {}", numero, e)),
+        }
+    }
+    Ok(carga)
+}
+
+fn le_item(campos: &[String], formato: FormatoNumero, maximo: i32) -> Result<Item, String> {
+    let codigo = ProdutoId::novo(&campos[0]).map_err(|e| e.to_string())?;
+    let Some(qtd) = campos.get(1) else {
+        return Err("falta a quantidade.".into());
+    };
+    let quantidade = formato
+        .interpreta(qtd)
+        .filter(|q| q.fract() == 0.0 && *q >= 0.0)
+        .and_then(|q| Quantidade::nova(q as i32).ok())
+        .ok_or_else(|| format!("quantidade inválida '{}'.", qtd))?
+        .ate(maximo)
+        .map_err(|e| e.to_string())?;
+    let preco = match campos.get(2).filter(|p| !p.is_empty()) {
+        None => None,
+        Some(p) => Some(
+            formato
+                .interpreta(p)
+                .filter(|p| *p > 0.0)
+                .ok_or_else(|| format!("preço inválido '{}'.", p))?,
+        ),
+    };
+    Ok(Item {
+        codigo,
+        quantidade,
+        preco,
+    })
+}
+
+/// Cadastra cada item com um CADASTRO no histórico, todos no mesmo instante.
+/// Não grava.
+pub fn aplica(
+    carga: &Carga,
+    relogios: &mut HashMap<String, Relogio>,
+    historico: &mut Vec<Historico>,
+    quando: NaiveDateTime,
+) -> Result<usize, ErroDominio> {
+    let mut inventario = Inventario::new(relogios, historico);
+    for item in &carga.itens {
+        inventario
+            .cadastra(&item.codigo, item.quantidade, quando)?
+            .preco = item.preco;
+    }
+    for item in &carga.itens {
+        if let Some(r) = relogios.get_mut(item.codigo.as_str()) {
+            r.preco = item.preco;
+        }
+    }
+    Ok(carga.itens.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Local;
+
+    #[test]
+    fn valida_linhas_e_cadastra_com_historico() {
+        let mut relogios = HashMap::new();
+        relogios.insert(
+            "R001".to_string(),
+            Relogio::novo("R001", Quantidade::nova(4).unwrap()),
+        );
+        let arquivo = "Código;Qtd;Preço\nR010;3;1.299,90\nR011;0\n\nR001;2\nR012;-1\nR013;2;abc\nR010;1\nR 14;1\nR015\nR016;20000\n";
+        let carga = le(arquivo.as_bytes(), &relogios, FormatoNumero::PtBr, 10_000).unwrap();
+        assert_eq!(carga.itens.len(), 2);
+        assert_eq!(carga.itens[0].preco, Some(1299.9));
+        assert_eq!(
+            carga.erros,
+            [
+                "Linha 5: R001 já está cadastrado.",
+                "Linha 6: quantidade inválida '-1'.",
+                "Linha 7: preço inválido 'abc'.",
+                "Linha 8: R010 repetido no arquivo.",
+                "Linha 9: Código com espaço: 'R 14'.",
+                "Linha 10: falta a quantidade.",
+                "Linha 11: 20000 unidades passam do limite de 10000 por operação.",
+            ]
+        );
+        assert!(le(b"\n", &relogios, FormatoNumero::PtBr, 0).is_err());
+        let en_us = le(
+            b"codigo;qtd;preco\nR020;1,200;1,234.56\n",
+            &relogios,
+            FormatoNumero::EnUs,
+            0,
+        )
+        .unwrap();
+        assert_eq!(en_us.itens[0].quantidade.valor(), 1200);
+        assert_eq!(en_us.itens[0].preco, Some(1234.56));
+
+        let mut historico = vec![];
+        let n = aplica(
+            &carga,
+            &mut relogios,
+            &mut historico,
+            Local::now().naive_local(),
+        )
+        .unwrap();
+        assert_eq!(n, 2);
+        assert_eq!(relogios["R010"].quantidade(), 3);
+        assert_eq!(relogios["R010"].preco, Some(1299.9));
+        assert_eq!(relogios["R001"].quantidade(), 4);
+        assert_eq!(historico.len(), 2);
+        assert!(historico.iter().all(|h| h.operacao == "CADASTRO"));
+        assert_eq!(historico[0].preco, Some(1299.9));
+    }
+}
@@ -0,0 +1,322 @@
+use crate::dominio::Quantidade;
+use crate::numeros::FormatoNumero;
+use crate::{csv, Relogio};
+use std::collections::HashMap;
+
+/// Uma linha do catálogo do distribuidor: `codigo, descricao, custo[, moeda[, categoria[, marca]]]`.
+#[derive(Debug, Clone)]
+pub struct LinhaCatalogo {
+    pub codigo: String,
+    pub descricao: String,
+    pub custo: f64,
+    /// `None` sem coluna de moeda no mapeamento; `Some(None)` é real.
+    pub moeda: Option<Option<String>>,
+    pub categoria: Option<String>,
+    pub marca: Option<String>,
+}
+
+/// Mudança proposta para um produto ao importar o catálogo.
+#[derive(Debug, Clone)]
+pub struct Alteracao {
+    pub codigo: String,
+    pub descricao: String,
+    pub custo_anterior: Option<f64>,
+    pub custo_novo: f64,
+    /// `None` mantém a moeda do produto (catálogo sem coluna de moeda).
+    pub moeda: Option<Option<String>>,
+    pub categoria: Option<String>,
+    pub marca: Option<String>,
+    pub novo: bool,
+    /// Custo subiu acima do limite: precisa de revisão antes de aceitar.
+    pub sinalizado: bool,
+    pub aceito: bool,
+}
+
+impl Alteracao {
+    /// Variação percentual do custo, quando havia custo anterior.
+    pub fn variacao(&self) -> Option<f64> {
+        self.custo_anterior
+            .filter(|c| *c > 0.0)
+            .map(|c| (self.custo_novo - c) / c * 100.0)
+    }
+}
+
+/// Campos do catálogo, na ordem das colunas de `Mapeamento::colunas`.
+pub const CAMPOS: [&str; 6] = [
+    "codigo",
+    "descricao",
+    "custo",
+    "moeda",
+    "categoria",
+    "marca",
+];
+
+/// Nomes de cabeçalho reconhecidos para cada campo, para o palpite inicial.
+const SINONIMOS: [&[&str]; 6] = [
+    &[
+        "codigo",
+        "código",
+        "cod",
+        "sku",
+        "ref",
+        "referencia",
+        "referência",
+    ],
+    &[
+        "descricao",
+        "descrição",
+        "desc",
+        "produto",
+        "nome",
+        "modelo",
+    ],
+    &["custo", "preco", "preço", "valor", "cost", "price"],
+    &["moeda", "currency"],
+    &["categoria", "category", "linha"],
+    &["marca", "brand", "fabricante"],
+];
+
+/// Como ler um CSV do distribuidor: separador, codificação, formato dos
+/// números, se a primeira linha é cabeçalho e de qual coluna vem cada campo.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mapeamento {
+    pub separador: char,
+    pub codificacao: csv::Codificacao,
+    pub formato: FormatoNumero,
+    pub cabecalho: bool,
+    pub colunas: [Option<usize>; 6],
+}
+
+impl Mapeamento {
+    /// Palpite a partir do arquivo: codificação e separador detectados, colunas
+    /// pelos nomes do cabeçalho ou, sem cabeçalho, na ordem padrão
+    /// `codigo, descricao, custo, moeda, categoria, marca`. Os custos são lidos
+    /// no `formato` configurado.
+    pub fn detecta(bytes: &[u8], formato: FormatoNumero) -> Mapeamento {
+        let codificacao = csv::detecta_codificacao(bytes);
+        let texto = csv::decodifica(bytes, codificacao);
+        let primeira = texto.lines().find(|l| !l.trim().is_empty()).unwrap_or("");
+        let separador = csv::detecta_separador(primeira);
+        let campos = csv::divide_linha(primeira, separador);
+        let cabecalho = campos
+            .get(2)
+            .is_none_or(|c| formato.interpreta(c).is_none());
+        let padrao = [Some(0), Some(1), Some(2), Some(3), Some(4), Some(5)];
+        let mut colunas = padrao.map(|c| c.filter(|i| *i < campos.len().max(3)));
+        if cabecalho {
+            let nomes: Vec<String> = campos.iter().map(|c| c.to_lowercase()).collect();
+            let achados = SINONIMOS.map(|s| nomes.iter().position(|n| s.contains(&n.as_str())));
+            if achados[0].is_some() && achados[2].is_some() {
+                colunas = achados;
+            }
+        }
+        Mapeamento {
+            separador,
+            codificacao,
+            formato,
+            cabecalho,
+            colunas,
+        }
+    }
+
+    /// Próximo separador da lista (`;`, `,`, tab).
+    pub fn alterna_separador(&mut self) {
+        let i = csv::SEPARADORES
+            .iter()
+            .position(|s| *s == self.separador)
+            .map_or(0, |i| i + 1);
+        self.separador = csv::SEPARADORES[i % csv::SEPARADORES.len()];
+    }
+
+    pub fn alterna_codificacao(&mut self) {
+        self.codificacao = match self.codificacao {
+            csv::Codificacao::Utf8 => csv::Codificacao::Latin1,
+            csv::Codificacao::Latin1 => csv::Codificacao::Utf8,
+        };
+    }
+
+    /// Muda a coluna do campo `i` em `passo`, passando por "nenhuma" (só para
+    /// campos opcionais) entre a última coluna e a primeira.
+    pub fn move_coluna(&mut self, i: usize, passo: isize, total_colunas: usize) {
+        let obrigatorio = i == 0 || i == 2;
+        let mut opcoes: Vec<Option<usize>> = (0..total_colunas).map(Some).collect();
+        if !obrigatorio || opcoes.is_empty() {
+            opcoes.push(None);
+        }
+        let atual = opcoes
+            .iter()
+            .position(|o| *o == self.colunas[i])
+            .unwrap_or(0);
+        let n = opcoes.len() as isize;
+        self.colunas[i] = opcoes[(atual as isize + passo).rem_euclid(n) as usize];
+    }
+}
+
+/// Arquivo aberto na etapa de mapeamento, antes da revisão.
+#[derive(Debug, Clone)]
+pub struct ArquivoCsv {
+    pub caminho: String,
+    pub bytes: Vec<u8>,
+    pub mapeamento: Mapeamento,
+    /// Aumento de custo (%) a partir do qual o item fica sinalizado.
+    pub limite: f64,
+}
+
+/// Linhas não vazias do arquivo já divididas em campos, com o número da
+/// linha no arquivo.
+pub fn linhas_brutas(bytes: &[u8], m: &Mapeamento) -> Vec<(usize, Vec<String>)> {
+    csv::decodifica(bytes, m.codificacao)
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(i, l)| (i + 1, csv::divide_linha(l, m.separador)))
+        .collect()
+}
+
+/// Lê o CSV pelo mapeamento. Linhas inválidas viram mensagens de erro com o
+/// número da linha.
+pub fn le_mapeado(bytes: &[u8], m: &Mapeamento) -> (Vec<LinhaCatalogo>, Vec<String>) {
+    let mut linhas = vec![];
+    let mut erros = vec![];
+    let pula = usize::from(m.cabecalho);
+    for (n, campos) in linhas_brutas(bytes, m).into_iter().skip(pula) {
+        let campo = |i: usize| {
+            m.colunas[i]
+                .and_then(|c| campos.get(c))
+                .map(|c| c.as_str())
+                .unwrap_or("")
+        };
+        let codigo = campo(0);
+        match m.formato.interpreta(campo(2)) {
+            Some(custo) if custo >= 0.0 && !codigo.is_empty() => linhas.push(LinhaCatalogo {
+                codigo: codigo.to_string(),
+                descricao: campo(1).to_string(),
+                custo,
+                moeda: m.colunas[3]
+                    .map(|_| Some(campo(3).to_uppercase()).filter(|m| !m.is_empty() && m != "BRL")),
+                categoria: Some(campo(4).to_string()).filter(|c| !c.is_empty()),
+                marca: Some(campo(5).to_string()).filter(|c| !c.is_empty()),
+            }),
+            _ if codigo.is_empty() => erros.push(format!("Linha {}: código vazio", n)),
+            _ => erros.push(format!("Linha {}: custo inválido '{}'", n, campo(2))),
+        }
+    }
+    (linhas, erros)
+}
+
+/// Compara o catálogo com o estoque atual. Aumentos de custo acima de
+/// `limite_pct` ficam sinalizados e desmarcados até serem revisados.
+pub fn compara(
+    relogios: &HashMap<String, Relogio>,
+    linhas: Vec<LinhaCatalogo>,
+    limite_pct: f64,
+) -> Vec<Alteracao> {
+    let mut alteracoes: Vec<Alteracao> = linhas
+        .into_iter()
+        .map(|l| {
+            let atual = relogios.get(&l.codigo);
+            let mut alt = Alteracao {
+                novo: atual.is_none(),
+                custo_anterior: atual.and_then(|r| r.custo),
+                codigo: l.codigo,
+                descricao: l.descricao,
+                custo_novo: l.custo,
+                moeda: l.moeda,
+                categoria: l.categoria,
+                marca: l.marca,
+                sinalizado: false,
+                aceito: true,
+            };
+            alt.sinalizado = alt.variacao().is_some_and(|v| v > limite_pct);
+            alt.aceito = !alt.sinalizado;
+            alt
+        })
+        .collect();
+    alteracoes.sort_by(|a, b| {
+        b.sinalizado
+            .cmp(&a.sinalizado)
+            .then_with(|| a.codigo.cmp(&b.codigo))
+    });
+    alteracoes
+}
+
+/// Aplica as alterações aceitas; produtos novos entram com quantidade zero.
+/// Retorna (atualizados, criados).
+pub fn aplica(relogios: &mut HashMap<String, Relogio>, alteracoes: &[Alteracao]) -> (usize, usize) {
+    let mut atualizados = 0;
+    let mut criados = 0;
+    for alt in alteracoes.iter().filter(|a| a.aceito) {
+        let r = relogios.entry(alt.codigo.clone()).or_insert_with(|| {
+            criados += 1;
+            Relogio::novo(alt.codigo.clone(), Quantidade::ZERO)
+        });
+        if !alt.novo {
+            atualizados += 1;
+        }
+        r.descricao = alt.descricao.clone();
+        r.custo = Some(alt.custo_novo);
+        if let Some(moeda) = &alt.moeda {
+            r.moeda = moeda.clone();
+        }
+        if alt.categoria.is_some() {
+            r.categoria = alt.categoria.clone();
+        }
+        if alt.marca.is_some() {
+            r.marca = alt.marca.clone();
+        }
+    }
+    (atualizados, criados)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn le_csv_latin1_com_cabecalho_fora_de_ordem() {
+        // "Descrição;Preço;Código" em Latin-1, como sai do Excel antigo
+        let mut bytes = b"Descri\xe7\xe3o;Pre\xe7o;C\xf3digo\n".to_vec();
+        bytes.extend_from_slice(b"Rel\xf3gio a\xe7o;1.234,50;R001\n;abc;R002\n");
+        let m = Mapeamento::detecta(&bytes, FormatoNumero::PtBr);
+        assert_eq!(m.codificacao, csv::Codificacao::Latin1);
+        assert_eq!(m.separador, ';');
+        assert!(m.cabecalho);
+        assert_eq!(m.colunas, [Some(2), Some(0), Some(1), None, None, None]);
+
+        let (linhas, erros) = le_mapeado(&bytes, &m);
+        assert_eq!(linhas.len(), 1);
+        assert_eq!(linhas[0].codigo, "R001");
+        assert_eq!(linhas[0].descricao, "Relógio aço");
+        assert_eq!(linhas[0].custo, 1234.5);
+        assert_eq!(erros, vec!["Linha 3: custo inválido 'abc'"]);
+
+        let bytes = b"R001;Seiko;1,234.56\n";
+        let m = Mapeamento::detecta(bytes, FormatoNumero::EnUs);
+        assert!(!m.cabecalho);
+        assert_eq!(le_mapeado(bytes, &m).0[0].custo, 1234.56);
+    }
+
+    #[test]
+    fn catalogo_sem_coluna_de_moeda_mantem_a_do_produto() {
+        let mut relogios = HashMap::new();
+        let mut r = Relogio::novo("R001", Quantidade::ZERO);
+        r.custo = Some(100.0);
+        r.moeda = Some("USD".into());
+        relogios.insert("R001".to_string(), r);
+
+        let bytes = b"codigo;descricao;custo\nR001;Seiko;110,00\n";
+        let m = Mapeamento::detecta(bytes, FormatoNumero::PtBr);
+        assert_eq!(m.colunas[3], None);
+        let alteracoes = compara(&relogios, le_mapeado(bytes, &m).0, 50.0);
+        aplica(&mut relogios, &alteracoes);
+        assert_eq!(relogios["R001"].custo, Some(110.0));
+        assert_eq!(relogios["R001"].moeda.as_deref(), Some("USD"));
+
+        // Com a coluna, BRL (ou vazio) volta o custo para reais
+        let bytes = b"codigo;descricao;custo;moeda\nR001;Seiko;500,00;BRL\n";
+        let m = Mapeamento::detecta(bytes, FormatoNumero::PtBr);
+        let alteracoes = compara(&relogios, le_mapeado(bytes, &m).0, 500.0);
+        aplica(&mut relogios, &alteracoes);
+        assert_eq!(relogios["R001"].moeda, None);
+    }
+}
@@ -0,0 +1,115 @@
+use crate::{contabil, credito, Historico, Relogio};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Cliente cadastrado, identificado pelo nome (sem caixa e espaços extras,
+/// como no crédito da loja).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Cliente {
+    pub nome: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub telefone: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub observacoes: String,
+}
+
+/// Lê `nome;telefone;observações`; só o nome é obrigatório.
+pub fn interpreta(texto: &str) -> Result<Cliente, String> {
+    let mut campos = texto.splitn(3, ';').map(str::trim);
+    let nome = campos.next().unwrap_or_default();
+    if nome.is_empty() {
+        return Err("Formato: nome;telefone;observações (só o nome é obrigatório)".into());
+    }
+    Ok(Cliente {
+        nome: nome.split_whitespace().collect::<Vec<_>>().join(" "),
+        telefone: campos.next().unwrap_or_default().to_string(),
+        observacoes: campos.next().unwrap_or_default().to_string(),
+    })
+}
+
+/// Inclui o cliente ou atualiza o de mesmo nome, mantendo a ordem
+/// alfabética. Devolve `true` quando é novo.
+pub fn define(clientes: &mut Vec<Cliente>, novo: Cliente) -> bool {
+    let antes = clientes.len();
+    clientes.retain(|c| credito::chave(&c.nome) != credito::chave(&novo.nome));
+    let eh_novo = clientes.len() == antes;
+    clientes.push(novo);
+    clientes.sort_by_key(|c| credito::chave(&c.nome));
+    eh_novo
+}
+
+/// Vendas lançadas no nome do cliente, na ordem do histórico.
+pub fn compras<'a>(historico: &'a [Historico], nome: &str) -> Vec<&'a Historico> {
+    let chave = credito::chave(nome);
+    historico
+        .iter()
+        .filter(|h| h.operacao == "VENDA")
+        .filter(|h| {
+            h.cliente
+                .as_deref()
+                .is_some_and(|c| credito::chave(c) == chave)
+        })
+        .collect()
+}
+
+/// Resumo das compras: vendas, valor total e data da última (AAAA-MM-DD).
+pub fn resumo(
+    historico: &[Historico],
+    relogios: &HashMap<String, Relogio>,
+    nome: &str,
+) -> (usize, f64, Option<String>) {
+    let compras = compras(historico, nome);
+    let total = compras
+        .iter()
+        .filter_map(|h| contabil::valor(h, relogios.get(&h.codigo)))
+        .sum();
+    let ultima = compras
+        .iter()
+        .map(|h| h.timestamp.get(..10).unwrap_or(&h.timestamp))
+        .max()
+        .map(str::to_string);
+    (compras.len(), total, ultima)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cadastra_pelo_nome_e_acha_as_compras() {
+        let mut clientes = vec![];
+        assert!(define(
+            &mut clientes,
+            interpreta("Maria  Souza;11 98888-0000;prefere pulseira de aço").unwrap()
+        ));
+        assert!(define(&mut clientes, interpreta("Ana").unwrap()));
+        assert!(!define(
+            &mut clientes,
+            interpreta("maria souza;11 97777-0000").unwrap()
+        ));
+        assert!(interpreta(" ;123").is_err());
+        assert_eq!(clientes.len(), 2);
+        assert_eq!(clientes[0].nome, "Ana");
+        assert_eq!(clientes[1].telefone, "11 97777-0000");
+        assert_eq!(clientes[1].observacoes, "");
+
+        let venda = |cliente: Option<&str>, timestamp: &str| Historico {
+            codigo: "R001".into(),
+            quantidade: 1,
+            operacao: "VENDA".into(),
+            timestamp: timestamp.into(),
+            preco: Some(100.0),
+            cliente: cliente.map(str::to_string),
+            ..Default::default()
+        };
+        let historico = vec![
+            venda(Some("Maria Souza"), "2024-03-01 10:00:00"),
+            venda(None, "2024-03-02 10:00:00"),
+            venda(Some("MARIA SOUZA"), "2024-04-10 15:00:00"),
+        ];
+        assert_eq!(compras(&historico, "maria souza").len(), 2);
+        let (vendas, total, ultima) = resumo(&historico, &HashMap::new(), "Maria Souza");
+        assert_eq!((vendas, total), (2, 200.0));
+        assert_eq!(ultima.as_deref(), Some("2024-04-10"));
+    }
+}
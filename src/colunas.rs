@@ -0,0 +1,141 @@
+/// Tabelas com colunas configuráveis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Tabela {
+    Estoque,
+    Historico,
+}
+
+impl Tabela {
+    /// Campos disponíveis (nome na configuração, título na tela).
+    fn campos(self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            Tabela::Estoque => &[
+                ("codigo", "CÓDIGO"),
+                ("qtd", "QTD"),
+                ("pedido", "PEDIDO"),
+                ("descricao", "DESCRIÇÃO"),
+                ("preco", "PREÇO"),
+                ("custo", "CUSTO"),
+                ("categoria", "CATEGORIA"),
+                ("marca", "MARCA"),
+                ("ean", "EAN"),
+            ],
+            Tabela::Historico => &[
+                ("data", "TIMESTAMP"),
+                ("operacao", "OPERACAO"),
+                ("qtd", "QTD"),
+                ("codigo", "CÓDIGO"),
+                ("etiquetas", "ETIQUETAS"),
+                ("descricao", "DESCRIÇÃO"),
+                ("pagamento", "PAGAMENTO"),
+                ("fornecedor", "FORNECEDOR"),
+                ("motivo", "MOTIVO"),
+                ("cliente", "CLIENTE"),
+            ],
+        }
+    }
+
+    /// Colunas de fábrica.
+    pub fn padrao(self) -> &'static str {
+        match self {
+            Tabela::Estoque => "codigo:30 qtd:24 pedido:10 custo:18 preco:18",
+            Tabela::Historico => "data:34 operacao:16 qtd:8 codigo:14 etiquetas:28",
+        }
+    }
+}
+
+/// Coluna escolhida: campo, título e largura (% da tabela).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Coluna {
+    pub campo: &'static str,
+    pub titulo: &'static str,
+    pub largura: u16,
+}
+
+/// Lê `campo[:largura] ...` na ordem em que as colunas aparecem. Colunas
+/// sem largura dividem igualmente o que sobra dos 100%.
+pub fn interpreta(tabela: Tabela, texto: &str) -> Result<Vec<Coluna>, String> {
+    let nomes: Vec<&str> = tabela.campos().iter().map(|(c, _)| *c).collect();
+    let mut colunas: Vec<(Coluna, bool)> = vec![];
+    for termo in texto.split_whitespace() {
+        let (nome, largura) = termo.split_once(':').unwrap_or((termo, ""));
+        let Some((campo, titulo)) = tabela
+            .campos()
+            .iter()
+            .find(|(c, _)| c.eq_ignore_ascii_case(nome))
+        else {
+            return Err(format!(
+                "Coluna {} não existe. Use: {}",
+                nome,
+                nomes.join(", ")
+            ));
+        };
+        if colunas.iter().any(|(c, _)| c.campo == *campo) {
+            return Err(format!("Coluna {} repetida.", campo));
+        }
+        let largura = match largura {
+            "" => None,
+            l => Some(
+                l.trim_end_matches('%')
+                    .parse::<u16>()
+                    .ok()
+                    .filter(|l| (1..=100).contains(l))
+                    .ok_or_else(|| format!("Largura inválida em {}.", termo))?,
+            ),
+        };
+        colunas.push((
+            Coluna {
+                campo,
+                titulo,
+                largura: largura.unwrap_or(0),
+            },
+            largura.is_some(),
+        ));
+    }
+    if colunas.is_empty() {
+        return Err(format!("Informe ao menos uma coluna: {}", nomes.join(", ")));
+    }
+    let fixas: u16 = colunas
+        .iter()
+        .filter(|(_, f)| *f)
+        .map(|(c, _)| c.largura)
+        .sum();
+    if fixas > 100 {
+        return Err("As larguras passam de 100%.".into());
+    }
+    let livres = colunas.iter().filter(|(_, f)| !*f).count() as u16;
+    for (c, fixa) in &mut colunas {
+        if !*fixa {
+            c.largura = ((100 - fixas) / livres).max(1);
+        }
+    }
+    Ok(colunas.into_iter().map(|(c, _)| c).collect())
+}
+
+/// Colunas configuradas ou, se o texto for inválido, as de fábrica.
+pub fn ou_padrao(tabela: Tabela, texto: &str) -> Vec<Coluna> {
+    interpreta(tabela, texto)
+        .or_else(|_| interpreta(tabela, tabela.padrao()))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ordem_larguras_e_erros() {
+        let colunas = interpreta(Tabela::Estoque, "codigo:40 preco qtd:20 marca").unwrap();
+        let resumo: Vec<(&str, u16)> = colunas.iter().map(|c| (c.campo, c.largura)).collect();
+        assert_eq!(
+            resumo,
+            [("codigo", 40), ("preco", 20), ("qtd", 20), ("marca", 20)]
+        );
+        assert_eq!(colunas[1].titulo, "PREÇO");
+        assert!(interpreta(Tabela::Estoque, "codigo dist").is_err());
+        assert!(interpreta(Tabela::Estoque, "codigo qtd codigo").is_err());
+        assert!(interpreta(Tabela::Estoque, "codigo:80 qtd:30").is_err());
+        assert!(interpreta(Tabela::Historico, "").is_err());
+        assert_eq!(ou_padrao(Tabela::Historico, "data:x").len(), 5);
+    }
+}
@@ -0,0 +1,1276 @@
+use crate::dominio::{codigo_por_ean, Ean, Relogio, Situacao};
+use crate::persistencia::{load_from_file, save_to_file};
+use crate::ui::App;
+use crate::{
+    agenda, arquivo, balanco, caixa, carga, config, consulta, contabil, credito, despesas,
+    etiquetas, expurgo, fidelidade, financeiro, gaveta, gs1, kardex, modelos, nfe, orcamentos,
+    pedidos, promocoes, recebimento, relatorios, sincronia, sqlite, tokens,
+};
+use chrono::Local;
+use std::fs;
+
+/// Comandos de linha de comando (`Relogio <comando> ...`).
+pub(crate) fn executa(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    let numeros = config::Config::carregar().formato_numeros;
+    match args[0].as_str() {
+        "arquivar" => {
+            let simular = args.iter().any(|a| a == "--simular");
+            let anos: i32 = match args.iter().skip(1).find(|a| *a != "--simular") {
+                Some(a) => a.parse().map_err(|_| "Uso: arquivar <anos> [--simular]")?,
+                None => 1,
+            };
+            let (relogios, mut historico, mut registros) = load_from_file();
+            if simular {
+                let (por_ano, saldos) = arquivo::simula(&historico, &relogios, anos);
+                let total: usize = por_ano.iter().map(|(_, n, _)| n).sum();
+                for (ano, n, existe) in por_ano {
+                    let mescla = if existe {
+                        " (já existe: mesclado)"
+                    } else {
+                        ""
+                    };
+                    println!(
+                        "{}: {} entradas -> historico_{}.json{}",
+                        ano, n, ano, mescla
+                    );
+                }
+                println!(
+                    "Simulação: {} entradas seriam arquivadas e {} linhas {} criadas. Nada foi gravado.",
+                    total,
+                    saldos,
+                    arquivo::OP_SALDO_INICIAL
+                );
+                return Ok(());
+            }
+            let n = arquivo::arquivar(
+                &mut historico,
+                &mut registros.historico_a_arquivar,
+                &relogios,
+                anos,
+            );
+            arquivo::grava(&relogios, &historico, &mut registros)?;
+            println!("{} entradas arquivadas.", n);
+            Ok(())
+        }
+        "expurgar" => {
+            let Some(padrao) = args.get(1).filter(|a| !a.starts_with("--")) else {
+                return Err("Uso: expurgar <padrão, ex.: TESTE*> [--simular]".into());
+            };
+            let simular = args.iter().any(|a| a == "--simular");
+            let (mut relogios, mut historico, mut registros) = load_from_file();
+            let plano = expurgo::planeja(padrao, &relogios, &historico);
+            for (codigo, entradas) in &plano.codigos {
+                let cadastro = relogios.get(codigo).map_or_else(
+                    || "(fora do cadastro)".to_string(),
+                    |r| format!("{:>5} un  {}", r.quantidade(), r.descricao),
+                );
+                println!("{:<14} {:>5} entradas  {}", codigo, entradas, cadastro);
+            }
+            for (codigo, kit) in &plano.em_kits {
+                println!("{} fica: é componente do kit {}.", codigo, kit);
+            }
+            if plano.codigos.is_empty() {
+                println!("Nenhum código casa com {}.", padrao);
+                return Ok(());
+            }
+            if simular {
+                println!(
+                    "Simulação: {} códigos e {} entradas do histórico seriam apagados. Nada foi gravado.",
+                    plano.codigos.len(),
+                    plano.entradas()
+                );
+                return Ok(());
+            }
+            print!(
+                "Apagar {} códigos e {} entradas do histórico? Digite SIM para confirmar: ",
+                plano.codigos.len(),
+                plano.entradas()
+            );
+            std::io::Write::flush(&mut std::io::stdout())?;
+            let mut resposta = String::new();
+            std::io::stdin().read_line(&mut resposta)?;
+            if resposta.trim() != "SIM" {
+                println!("Nada foi apagado.");
+                return Ok(());
+            }
+            expurgo::aplica(
+                &plano,
+                &mut relogios,
+                &mut historico,
+                &mut registros,
+                Local::now().naive_local(),
+            );
+            save_to_file(&relogios, &historico, &registros);
+            println!(
+                "{} códigos e {} entradas apagados.",
+                plano.codigos.len(),
+                plano.entradas()
+            );
+            Ok(())
+        }
+        "contabil" => {
+            const USO: &str =
+                "Uso: contabil <ofx|qif> <inicio AAAA-MM-DD> <fim AAAA-MM-DD> [arquivo]";
+            let (Some(formato), Some(inicio), Some(fim)) = (
+                args.get(1).and_then(|f| contabil::Formato::parse(f)),
+                args.get(2),
+                args.get(3),
+            ) else {
+                return Err(USO.into());
+            };
+            for data in [inicio, fim] {
+                chrono::NaiveDate::parse_from_str(data, "%Y-%m-%d")
+                    .map_err(|_| format!("Data inválida: {}", data))?;
+            }
+            let (relogios, historico, _) = load_from_file();
+            let (conteudo, n) = contabil::exporta(formato, &historico, &relogios, inicio, fim);
+            let destino = args
+                .get(4)
+                .cloned()
+                .unwrap_or_else(|| format!("contabil_{}_{}.{}", inicio, fim, formato.extensao()));
+            fs::write(&destino, conteudo)?;
+            println!("{} lançamentos exportados para {}.", n, destino);
+            Ok(())
+        }
+        "lembrete" => {
+            let (Some(data), true) = (args.get(1), args.len() > 2) else {
+                return Err("Uso: lembrete <AAAA-MM-DD> <texto>".into());
+            };
+            chrono::NaiveDate::parse_from_str(data, "%Y-%m-%d")
+                .map_err(|_| format!("Data inválida: {}", data))?;
+            let (relogios, historico, mut registros) = load_from_file();
+            registros.lembretes.push(agenda::Lembrete {
+                data: data.clone(),
+                titulo: args[2..].join(" "),
+            });
+            save_to_file(&relogios, &historico, &registros);
+            println!("Lembrete registrado para {}.", data);
+            Ok(())
+        }
+        "agenda" => {
+            let destino = args.get(1).map_or("agenda.ics", |a| a.as_str());
+            let (relogios, historico, registros) = load_from_file();
+            let config = config::Config::carregar();
+            let ics = agenda::gera_ics(
+                &relogios,
+                &historico,
+                &registros.lembretes,
+                config.prazo_reposicao_dias,
+            );
+            fs::write(destino, ics)?;
+            println!("Calendário gravado em {}.", destino);
+            Ok(())
+        }
+        "promocao" => {
+            const USO: &str =
+                "Uso: promocao <nome> <categoria|tag> <desconto%> <inicio AAAA-MM-DD> <fim AAAA-MM-DD>";
+            let [_, nome, alvo, desconto, inicio, fim] = args else {
+                return Err(USO.into());
+            };
+            let desconto = numeros
+                .interpreta(desconto)
+                .filter(|d| *d > 0.0 && *d < 100.0)
+                .ok_or("Desconto deve estar entre 0 e 100%.")?;
+            for data in [inicio, fim] {
+                chrono::NaiveDate::parse_from_str(data, "%Y-%m-%d")
+                    .map_err(|_| format!("Data inválida: {}", data))?;
+            }
+            let (relogios, historico, mut registros) = load_from_file();
+            registros.promocoes.retain(|p| &p.nome != nome);
+            registros.promocoes.push(promocoes::Promocao {
+                nome: nome.clone(),
+                alvo: alvo.trim_start_matches('#').to_string(),
+                desconto,
+                inicio: inicio.clone(),
+                fim: fim.clone(),
+            });
+            save_to_file(&relogios, &historico, &registros);
+            println!("Promoção {} registrada de {} a {}.", nome, inicio, fim);
+            Ok(())
+        }
+        "limite" => {
+            let (Some(codigo), Some(Ok(limite))) =
+                (args.get(1), args.get(2).map(|l| l.parse::<i32>()))
+            else {
+                return Err("Uso: limite <codigo> <unidades por venda, 0 remove>".into());
+            };
+            let (mut relogios, historico, registros) = load_from_file();
+            let r = relogios
+                .get_mut(codigo)
+                .ok_or_else(|| format!("Relógio não encontrado: {}", codigo))?;
+            r.limite_por_venda = (limite > 0).then_some(limite);
+            save_to_file(&relogios, &historico, &registros);
+            println!("Limite por venda de {} atualizado.", codigo);
+            Ok(())
+        }
+        "alvo" => {
+            let (Some(codigo), Some(Some(alvo))) = (
+                args.get(1),
+                args.get(2).map(|a| numeros.interpreta_inteiro(a)),
+            ) else {
+                return Err(
+                    "Uso: alvo <codigo> <unidades desejadas, 0 remove> [ponto de pedido]".into(),
+                );
+            };
+            let minimo = match args.get(3).map(|m| numeros.interpreta_inteiro(m)) {
+                None => None,
+                Some(Some(m)) if m >= 0 && m < alvo => Some(m),
+                Some(_) => return Err("O ponto de pedido fica entre 0 e o alvo.".into()),
+            };
+            let (mut relogios, historico, registros) = load_from_file();
+            let r = relogios
+                .get_mut(codigo)
+                .ok_or_else(|| format!("Relógio não encontrado: {}", codigo))?;
+            r.estoque_alvo = (alvo > 0).then_some(alvo);
+            r.estoque_minimo = minimo.filter(|_| alvo > 0);
+            save_to_file(&relogios, &historico, &registros);
+            println!("Estoque alvo de {} atualizado.", codigo);
+            Ok(())
+        }
+        "situacao" => {
+            let (Some(codigo), Some(Some(situacao))) =
+                (args.get(1), args.get(2).map(|s| Situacao::interpreta(s)))
+            else {
+                return Err(
+                    "Uso: situacao <codigo> <ativo|encomenda|descontinuado|arquivado>".into(),
+                );
+            };
+            let (mut relogios, historico, registros) = load_from_file();
+            let r = relogios
+                .get_mut(codigo)
+                .ok_or_else(|| format!("Relógio não encontrado: {}", codigo))?;
+            r.situacao = situacao;
+            save_to_file(&relogios, &historico, &registros);
+            println!("{} agora está {}.", codigo, situacao.rotulo());
+            Ok(())
+        }
+        "ean" => {
+            let (Some(codigo), Some(texto)) = (args.get(1), args.get(2)) else {
+                return Err("Uso: ean <codigo> <codigo de barras, 0 remove>".into());
+            };
+            let (mut relogios, historico, registros) = load_from_file();
+            let ean = match texto.as_str() {
+                "0" => None,
+                t => Some(Ean::novo(t)?.as_str().to_string()),
+            };
+            if let Some(outro) = ean
+                .as_deref()
+                .and_then(|e| codigo_por_ean(&relogios, e))
+                .filter(|c| c != codigo)
+            {
+                return Err(format!("Esse EAN já pertence a {}.", outro).into());
+            }
+            let r = relogios
+                .get_mut(codigo)
+                .ok_or_else(|| format!("Relógio não encontrado: {}", codigo))?;
+            r.ean = ean;
+            save_to_file(&relogios, &historico, &registros);
+            println!("EAN de {} atualizado.", codigo);
+            Ok(())
+        }
+        "limites" => {
+            let (relogios, _, registros) = load_from_file();
+            let mut limitados: Vec<&Relogio> = relogios
+                .values()
+                .filter(|r| r.limite_por_venda.is_some())
+                .collect();
+            limitados.sort_by(|a, b| a.codigo.cmp(&b.codigo));
+            for r in limitados {
+                println!(
+                    "{}: até {} un. por venda",
+                    r.codigo,
+                    r.limite_por_venda.unwrap_or(0)
+                );
+            }
+            println!("\nData;Código;Quantidade;Limite;Situação");
+            for t in &registros.tentativas_limite {
+                println!(
+                    "{};{};{};{};{}",
+                    t.timestamp,
+                    t.codigo,
+                    t.quantidade,
+                    t.limite,
+                    if t.liberada { "liberada" } else { "bloqueada" }
+                );
+            }
+            Ok(())
+        }
+        "espera" => {
+            let (relogios, historico, mut registros) = load_from_file();
+            if let (Some("limpar"), Some(codigo)) = (args.get(1).map(|a| a.as_str()), args.get(2)) {
+                registros.lista_espera.retain(|e| &e.codigo != codigo);
+                save_to_file(&relogios, &historico, &registros);
+                println!("Lista de espera de {} limpa.", codigo);
+                return Ok(());
+            }
+            println!("Código;Cliente;Contato;Desde");
+            for e in registros
+                .lista_espera
+                .iter()
+                .filter(|e| args.get(1).is_none_or(|c| &e.codigo == c))
+            {
+                println!("{};{};{};{}", e.codigo, e.cliente, e.contato, e.timestamp);
+            }
+            Ok(())
+        }
+        "recados" => {
+            let (_, _, registros) = load_from_file();
+            let todos = args.get(1).map(|a| a.as_str()) == Some("todos");
+            println!("Data;Autor;Lido;Recado");
+            for r in registros.recados.iter().filter(|r| todos || !r.lido) {
+                println!(
+                    "{};{};{};{}",
+                    r.timestamp,
+                    r.autor,
+                    if r.lido { "sim" } else { "não" },
+                    r.mensagem
+                );
+            }
+            Ok(())
+        }
+        "credito" => {
+            let (relogios, historico, mut registros) = load_from_file();
+            if args.get(1).map(|a| a.as_str()) == Some("emitir") {
+                let (Some(valor), true) = (
+                    args.get(2)
+                        .and_then(|v| numeros.interpreta(v))
+                        .filter(|v| *v > 0.0),
+                    args.len() > 3,
+                ) else {
+                    return Err("Uso: credito emitir <valor> <cliente> [-- motivo]".into());
+                };
+                let resto = args[3..].join(" ");
+                let (cliente, motivo) = resto.split_once(" -- ").unwrap_or((&resto, "Crédito"));
+                registros.creditos.push(credito::MovimentoCredito {
+                    cliente: cliente.trim().to_string(),
+                    valor,
+                    motivo: motivo.trim().to_string(),
+                    timestamp: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+                });
+                save_to_file(&relogios, &historico, &registros);
+                println!(
+                    "Crédito de {} emitido para {}. Saldo: {}",
+                    numeros.moeda(valor),
+                    cliente.trim(),
+                    numeros.moeda(credito::saldo(&registros.creditos, cliente))
+                );
+                return Ok(());
+            }
+            if args.len() > 1 {
+                let cliente = args[1..].join(" ");
+                for m in credito::extrato(&registros.creditos, &cliente) {
+                    println!("{};{};{}", m.timestamp, numeros.moeda(m.valor), m.motivo);
+                }
+                println!(
+                    "Saldo: {}",
+                    numeros.moeda(credito::saldo(&registros.creditos, &cliente))
+                );
+                return Ok(());
+            }
+            for (cliente, saldo) in credito::saldos(&registros.creditos) {
+                println!("{};{}", cliente, numeros.moeda(saldo));
+            }
+            println!(
+                "Total em aberto: {}",
+                numeros.moeda(credito::passivo(&registros.creditos))
+            );
+            Ok(())
+        }
+        "caixa" => {
+            const USO: &str = "Uso: caixa <sangria|suprimento> <valor> [motivo]";
+            let tipo = match args.get(1).map(|a| a.to_lowercase()).as_deref() {
+                Some("sangria") => caixa::SANGRIA,
+                Some("suprimento") => caixa::SUPRIMENTO,
+                _ => return Err(USO.into()),
+            };
+            let valor = args
+                .get(2)
+                .and_then(|v| numeros.interpreta(v))
+                .filter(|v| *v > 0.0)
+                .ok_or(USO)?;
+            let (relogios, historico, mut registros) = load_from_file();
+            registros.caixa.push(caixa::MovimentoCaixa {
+                tipo: tipo.to_string(),
+                valor,
+                motivo: args[3..].join(" "),
+                timestamp: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            });
+            save_to_file(&relogios, &historico, &registros);
+            if let Some(porta) = config::Config::carregar().gaveta_porta {
+                if let Err(e) = gaveta::abrir(&porta) {
+                    eprintln!("Falha ao abrir a gaveta em {}: {}", porta, e);
+                }
+            }
+            println!(
+                "Movimento de caixa registrado: {} {}",
+                tipo,
+                numeros.moeda(valor)
+            );
+            Ok(())
+        }
+        "fechamento" => {
+            let hoje = Local::now().format("%Y-%m-%d").to_string();
+            let data = args.get(1).unwrap_or(&hoje);
+            chrono::NaiveDate::parse_from_str(data, "%Y-%m-%d")
+                .map_err(|_| format!("Data inválida: {}", data))?;
+            let (relogios, historico, registros) = load_from_file();
+            let f = caixa::fechamento(&historico, &relogios, &registros.caixa, data);
+            println!("Fechamento de caixa {}", data);
+            println!("Vendas em dinheiro: {}", numeros.moeda(f.vendas_dinheiro));
+            println!("Suprimentos:        {}", numeros.moeda(f.suprimentos));
+            println!("Sangrias:           {}", numeros.moeda(f.sangrias));
+            println!("Esperado na gaveta: {}", numeros.moeda(f.esperado()));
+            if let Some(contado) = args.get(2).and_then(|c| numeros.interpreta(c)) {
+                println!("Contado:            {}", numeros.moeda(contado));
+                println!(
+                    "Diferença:          {}",
+                    numeros.moeda(contado - f.esperado())
+                );
+            }
+            Ok(())
+        }
+        "despesa" => {
+            const USO: &str = "Uso: despesa <AAAA-MM-DD> <categoria> <valor> [descrição]";
+            let (Some(data), Some(categoria), Some(valor)) = (
+                args.get(1),
+                args.get(2),
+                args.get(3)
+                    .and_then(|v| numeros.interpreta(v))
+                    .filter(|v| *v > 0.0),
+            ) else {
+                return Err(USO.into());
+            };
+            chrono::NaiveDate::parse_from_str(data, "%Y-%m-%d")
+                .map_err(|_| format!("Data inválida: {}", data))?;
+            let (relogios, historico, mut registros) = load_from_file();
+            registros.despesas.push(despesas::Despesa {
+                data: data.clone(),
+                categoria: categoria.to_lowercase(),
+                valor,
+                descricao: args[4..].join(" "),
+            });
+            save_to_file(&relogios, &historico, &registros);
+            println!(
+                "Despesa de {} em {} registrada.",
+                numeros.moeda(valor),
+                categoria
+            );
+            Ok(())
+        }
+        "despesas" => {
+            let (_, _, registros) = load_from_file();
+            let mes = args.get(1).map_or("", |m| m.as_str());
+            println!("Data;Categoria;Valor;Descrição");
+            for d in registros
+                .despesas
+                .iter()
+                .filter(|d| d.data.starts_with(mes))
+            {
+                println!(
+                    "{};{};{};{}",
+                    d.data,
+                    d.categoria,
+                    numeros.moeda(d.valor),
+                    d.descricao
+                );
+            }
+            Ok(())
+        }
+        "financeiro" => {
+            const USO: &str = "Uso: financeiro <AAAA-MM> | <inicio AAAA-MM-DD> <fim AAAA-MM-DD>";
+            let (inicio, fim) = match (args.get(1), args.get(2)) {
+                (Some(inicio), Some(fim)) => (inicio.clone(), fim.clone()),
+                (Some(mes), None) => {
+                    let primeiro =
+                        chrono::NaiveDate::parse_from_str(&format!("{}-01", mes), "%Y-%m-%d")
+                            .map_err(|_| USO)?;
+                    let ultimo = primeiro
+                        .checked_add_months(chrono::Months::new(1))
+                        .and_then(|d| d.pred_opt())
+                        .ok_or(USO)?;
+                    (primeiro.to_string(), ultimo.to_string())
+                }
+                _ => return Err(USO.into()),
+            };
+            for data in [&inicio, &fim] {
+                chrono::NaiveDate::parse_from_str(data, "%Y-%m-%d")
+                    .map_err(|_| format!("Data inválida: {}", data))?;
+            }
+            let (relogios, historico, registros) = load_from_file();
+            print!(
+                "{}",
+                financeiro::relatorio(&historico, &relogios, &registros, &inicio, &fim, numeros)
+            );
+            Ok(())
+        }
+        "consulta" => {
+            let Some(texto) = args.get(1) else {
+                return Err("Uso: consulta \"<consulta>\" [arquivo.csv]".into());
+            };
+            let (relogios, historico, _) = load_from_file();
+            let tabela = consulta::executa(texto, &historico, &relogios)?;
+            match args.get(2) {
+                Some(destino) => {
+                    fs::write(destino, tabela.csv())?;
+                    println!(
+                        "{} linhas exportadas para {}.",
+                        tabela.linhas.len(),
+                        destino
+                    );
+                }
+                None => {
+                    println!("{}", tabela.colunas.join("\t"));
+                    for l in &tabela.linhas {
+                        let celulas: Vec<String> = l.iter().map(|c| c.exibe(numeros)).collect();
+                        println!("{}", celulas.join("\t"));
+                    }
+                }
+            }
+            Ok(())
+        }
+        "export" if args.get(1).map(|a| a.as_str()) == Some("--csv") => {
+            let pasta = args.get(2).map(|d| d.as_str()).unwrap_or(".");
+            let (relogios, historico, _) = load_from_file();
+            fs::create_dir_all(pasta)?;
+            let pasta = std::path::Path::new(pasta);
+            fs::write(
+                pasta.join("estoque.csv"),
+                relatorios::estoque_csv(&relogios, numeros),
+            )?;
+            fs::write(
+                pasta.join("historico.csv"),
+                relatorios::historico_csv(&historico, &relogios, numeros),
+            )?;
+            println!(
+                "{} produtos e {} lançamentos exportados para {}.",
+                relogios.len(),
+                historico.len(),
+                pasta.display()
+            );
+            Ok(())
+        }
+        "export" => {
+            if args.get(1).map(|a| a.as_str()) != Some("--sqlite") {
+                return Err("Uso: export --sqlite [arquivo] | export --csv [pasta]".into());
+            }
+            let destino = args.get(2).map(|d| d.as_str()).unwrap_or("relogios.sqlite");
+            let (relogios, historico, registros) = load_from_file();
+            let n = sqlite::exporta(destino, &relogios, &historico, &registros)?;
+            println!(
+                "{} produtos e {} lançamentos exportados para {}.",
+                relogios.len(),
+                n,
+                destino
+            );
+            Ok(())
+        }
+        "agendadas" => {
+            let (relogios, historico, mut registros) = load_from_file();
+            match (args.get(1).map(String::as_str), args.get(2)) {
+                (None, _) => {
+                    let mut lista = registros.operacoes_agendadas.clone();
+                    lista.sort_by(|a, b| a.data.cmp(&b.data).then(a.numero.cmp(&b.numero)));
+                    if lista.is_empty() {
+                        println!("Nenhuma operação agendada.");
+                    }
+                    for a in lista {
+                        println!(
+                            "{:>4}  {}  {:<7}{:<12}{:>6}  {}",
+                            a.numero,
+                            App::formata_data_ddmm(&a.data),
+                            format!("{:?}", a.tipo),
+                            a.codigo,
+                            a.quantidade,
+                            a.pagamento.unwrap_or_default()
+                        );
+                    }
+                }
+                (Some("cancelar"), Some(n)) => {
+                    let numero: u32 = n.parse().map_err(|_| "Número inválido.")?;
+                    let antes = registros.operacoes_agendadas.len();
+                    registros.operacoes_agendadas.retain(|a| a.numero != numero);
+                    if registros.operacoes_agendadas.len() == antes {
+                        return Err(
+                            format!("Operação agendada nº {} não encontrada.", numero).into()
+                        );
+                    }
+                    save_to_file(&relogios, &historico, &registros);
+                    println!("Operação agendada nº {} cancelada.", numero);
+                }
+                _ => return Err("Uso: agendadas [cancelar <número>]".into()),
+            }
+            Ok(())
+        }
+        "pedido" => {
+            let (relogios, historico, mut registros) = load_from_file();
+            if args.len() > 1 {
+                let texto = args[1..].join(" ");
+                let recorrente = pedidos::interpreta_recorrente(&texto)?;
+                let (fornecedor, itens) = match &recorrente {
+                    Some((_, fornecedor, itens)) => (fornecedor.clone(), itens.clone()),
+                    None => pedidos::interpreta(&texto)?,
+                };
+                if let Some(i) = itens.iter().find(|i| !relogios.contains_key(&i.codigo)) {
+                    return Err(format!("Relógio {} não encontrado!", i.codigo).into());
+                }
+                if let Some((cadencia, _, _)) = recorrente {
+                    let numero = pedidos::cria_recorrente(
+                        &mut registros.pedidos_recorrentes,
+                        cadencia,
+                        &fornecedor,
+                        itens,
+                        Local::now().date_naive(),
+                    );
+                    save_to_file(&relogios, &historico, &registros);
+                    println!(
+                        "Pedido recorrente nº {} ({}) registrado para {}.",
+                        numero,
+                        cadencia.rotulo(),
+                        fornecedor
+                    );
+                    return Ok(());
+                }
+                let numero = pedidos::abre(
+                    &mut registros.pedidos_compra,
+                    &fornecedor,
+                    itens,
+                    &Local::now().format("%Y-%m-%d").to_string(),
+                );
+                save_to_file(&relogios, &historico, &registros);
+                println!("Pedido nº {} registrado para {}.", numero, fornecedor);
+                return Ok(());
+            }
+            let lista = pedidos::pendencias(&registros.pedidos_compra, Local::now().date_naive());
+            if lista.is_empty() {
+                println!("Nenhum pedido com itens em falta.");
+            }
+            for p in lista {
+                println!(
+                    "nº {:<4} {:<20} {:<10} chegou {}/{}  falta {}  aberto há {} dias",
+                    p.numero,
+                    p.fornecedor,
+                    p.codigo,
+                    numeros.inteiro(p.recebido as i64),
+                    numeros.inteiro(p.quantidade as i64),
+                    numeros.inteiro((p.quantidade - p.recebido) as i64),
+                    p.dias
+                );
+            }
+            Ok(())
+        }
+        "recorrentes" => {
+            let (relogios, historico, mut registros) = load_from_file();
+            match (args.get(1).map(String::as_str), args.get(2)) {
+                (None, _) => {
+                    if registros.pedidos_recorrentes.is_empty() {
+                        println!("Nenhum pedido recorrente.");
+                    }
+                    for r in &registros.pedidos_recorrentes {
+                        let itens: Vec<String> = r
+                            .itens
+                            .iter()
+                            .map(|i| format!("{}:{}", i.codigo, i.quantidade))
+                            .collect();
+                        println!(
+                            "nº {:<4} {:<10} {:<20} próximo {}  {}",
+                            r.numero,
+                            r.cadencia.rotulo(),
+                            r.fornecedor,
+                            App::formata_data_ddmm(&r.proxima),
+                            itens.join(" ")
+                        );
+                    }
+                }
+                (Some("remover"), Some(n)) => {
+                    let numero: u32 = n.parse().map_err(|_| "Número inválido.")?;
+                    let antes = registros.pedidos_recorrentes.len();
+                    registros.pedidos_recorrentes.retain(|r| r.numero != numero);
+                    if registros.pedidos_recorrentes.len() == antes {
+                        return Err(
+                            format!("Pedido recorrente nº {} não encontrado.", numero).into()
+                        );
+                    }
+                    registros
+                        .rascunhos_pedido
+                        .retain(|r| r.recorrente != numero);
+                    save_to_file(&relogios, &historico, &registros);
+                    println!("Pedido recorrente nº {} removido.", numero);
+                }
+                _ => return Err("Uso: recorrentes [remover <número>]".into()),
+            }
+            Ok(())
+        }
+        "clientes" => {
+            const USO: &str = "Uso: clientes [dias] | clientes inativos [dias] [arquivo.csv]";
+            let exportar = args.get(1).is_some_and(|a| a == "inativos");
+            let resto = &args[1 + usize::from(exportar)..];
+            let inativo = match resto.first() {
+                Some(d) => d.parse().map_err(|_| USO)?,
+                None => fidelidade::DIAS_INATIVO,
+            };
+            let (_, _, registros) = load_from_file();
+            let lista = fidelidade::clientes(
+                &registros.notas,
+                &registros.lista_espera,
+                Local::now().date_naive(),
+            );
+            if !exportar {
+                print!("{}", fidelidade::resumo(&lista, inativo, numeros));
+                return Ok(());
+            }
+            let destino = resto
+                .get(1)
+                .cloned()
+                .unwrap_or_else(|| "clientes_inativos.csv".into());
+            let csv = fidelidade::inativos_csv(&lista, inativo, numeros);
+            fs::write(&destino, &csv)?;
+            println!(
+                "{} clientes sem comprar há {} dias ou mais em {}",
+                csv.lines().count() - 1,
+                inativo,
+                destino
+            );
+            Ok(())
+        }
+        "aniversario" => {
+            const USO: &str = "Uso: aniversario <DD/MM|0> <cliente>";
+            let (Some(dia), true) = (args.get(1), args.len() > 2) else {
+                return Err(USO.into());
+            };
+            let cliente = args[2..].join(" ");
+            let dia = match dia.as_str() {
+                "0" => None,
+                d => Some(fidelidade::interpreta_aniversario(d).ok_or(USO)?),
+            };
+            let (relogios, historico, mut registros) = load_from_file();
+            let apagar = dia.is_none();
+            fidelidade::define_aniversario(&mut registros.aniversarios, &cliente, dia);
+            save_to_file(&relogios, &historico, &registros);
+            if apagar {
+                println!("Aniversário de {} apagado.", cliente.trim());
+            } else {
+                println!("Aniversário de {} registrado.", cliente.trim());
+            }
+            Ok(())
+        }
+        "aniversarios" => {
+            let dias = match args.get(1) {
+                Some(d) => d.parse().map_err(|_| "Uso: aniversarios [dias]")?,
+                None => 30,
+            };
+            let config = config::Config::carregar();
+            let (_, _, registros) = load_from_file();
+            let hoje = Local::now().date_naive();
+            let clientes = fidelidade::clientes(&registros.notas, &registros.lista_espera, hoje);
+            let lista = fidelidade::proximos_aniversarios(
+                &registros.aniversarios,
+                &clientes,
+                hoje,
+                dias,
+                config.aniversario_gasto_minimo,
+            );
+            if lista.is_empty() {
+                println!(
+                    "Nenhum aniversário nos próximos {} dias entre clientes que gastaram {} ou mais.",
+                    dias,
+                    numeros.moeda(config.aniversario_gasto_minimo)
+                );
+            }
+            for (data, c) in lista {
+                println!(
+                    "{}  {:<28}{:>16}  {}",
+                    data.format("%d/%m"),
+                    c.nome,
+                    numeros.moeda(c.valor),
+                    c.contato
+                );
+            }
+            Ok(())
+        }
+        "orcamentos" => {
+            let (_, _, registros) = load_from_file();
+            let hoje = Local::now().format("%Y-%m-%d").to_string();
+            let fila: Vec<&orcamentos::Orcamento> = orcamentos::fila(&registros.orcamentos)
+                .into_iter()
+                .filter(|o| o.retorno <= hoje)
+                .collect();
+            println!("Para ligar hoje ({})", fila.len());
+            for o in fila {
+                println!(
+                    "  nº {:<4} {:<24} {:<16} {:>14}  retorno {}",
+                    o.numero,
+                    o.cliente,
+                    o.contato,
+                    numeros.moeda(o.valor),
+                    o.retorno
+                );
+            }
+            println!(
+                "\n{:<10}{:>8}{:>8}{:>10}{:>12}{:>16}",
+                "Mês", "criados", "ganhos", "perdidos", "conversão", "valor ganho"
+            );
+            for (mes, m) in orcamentos::por_mes(&registros.orcamentos) {
+                println!(
+                    "{:<10}{:>8}{:>8}{:>10}{:>12}{:>16}",
+                    mes,
+                    m.criados,
+                    m.ganhos,
+                    m.perdidos,
+                    m.conversao()
+                        .map_or("—".into(), |c| format!("{}%", numeros.numero(c, 1))),
+                    numeros.moeda(m.valor_ganho)
+                );
+            }
+            let motivos = orcamentos::motivos_de_perda(&registros.orcamentos);
+            if !motivos.is_empty() {
+                println!("\nMotivos de perda");
+                for (motivo, n) in motivos {
+                    println!("  {:<30}{:>6}", motivo, n);
+                }
+            }
+            Ok(())
+        }
+        "balanco" => {
+            let Some(arquivo) = args.get(1).filter(|a| !a.starts_with("--")) else {
+                return Err("Uso: balanco <arquivo.csv> [--completo] [--simular]".into());
+            };
+            let completo = args.iter().any(|a| a == "--completo");
+            let simular = args.iter().any(|a| a == "--simular");
+            let (mut relogios, mut historico, registros) = load_from_file();
+            let contagem = balanco::le(&fs::read(arquivo)?, &relogios, numeros)?;
+            let conciliacao = balanco::concilia(&contagem, &relogios, completo);
+            let relatorio = balanco::relatorio_csv(&conciliacao, numeros);
+            let destino = std::path::Path::new(arquivo).with_file_name(format!(
+                "divergencias_{}.csv",
+                Local::now().format("%Y-%m-%d_%H%M")
+            ));
+            fs::write(&destino, &relatorio)?;
+            for d in conciliacao.divergentes() {
+                println!(
+                    "{:<12} sistema {:>6}  contado {:>6}  {:+}",
+                    d.codigo,
+                    d.sistema,
+                    d.contado,
+                    d.diferenca()
+                );
+            }
+            println!(
+                "{} conferidos, {} divergentes; relatório em {}",
+                conciliacao.itens.len(),
+                conciliacao.divergentes().count(),
+                destino.display()
+            );
+            if !conciliacao.desconhecidos.is_empty() {
+                println!("Fora do cadastro: {}", conciliacao.desconhecidos.join(", "));
+            }
+            if simular {
+                println!("Simulação: nenhum ajuste lançado.");
+            } else {
+                let n = balanco::ajusta(
+                    &conciliacao,
+                    &mut relogios,
+                    &mut historico,
+                    Local::now().naive_local(),
+                )?;
+                save_to_file(&relogios, &historico, &registros);
+                println!("{} ajustes lançados.", n);
+            }
+            Ok(())
+        }
+        "carga" => {
+            let Some(arquivo) = args.get(1).filter(|a| !a.starts_with("--")) else {
+                return Err("Uso: carga <arquivo.csv> [--simular]".into());
+            };
+            let simular = args.iter().any(|a| a == "--simular");
+            let (mut relogios, mut historico, registros) = load_from_file();
+            let maximo = config::Config::carregar().quantidade_maxima;
+            let carga = carga::le(&fs::read(arquivo)?, &relogios, numeros, maximo)?;
+            for erro in &carga.erros {
+                println!("{}", erro);
+            }
+            if !carga.erros.is_empty() {
+                return Err(format!(
+                    "{} linhas com erro; nada foi importado. Corrija o arquivo e rode de novo.",
+                    carga.erros.len()
+                )
+                .into());
+            }
+            if simular {
+                println!(
+                    "Simulação: {} códigos seriam cadastrados. Nada foi gravado.",
+                    carga.itens.len()
+                );
+                return Ok(());
+            }
+            let n = carga::aplica(
+                &carga,
+                &mut relogios,
+                &mut historico,
+                Local::now().naive_local(),
+            )?;
+            save_to_file(&relogios, &historico, &registros);
+            println!("{} códigos cadastrados.", n);
+            Ok(())
+        }
+        "etiquetas" => {
+            let numero = match (args.get(1).map(String::as_str), args.get(2)) {
+                (Some("recebimento"), Some(n)) => n.parse::<u32>().ok(),
+                _ => None,
+            }
+            .ok_or("Uso: etiquetas recebimento <número> [--imprimir]")?;
+            let config = config::Config::carregar();
+            let (relogios, historico, _) = load_from_file();
+            let itens = recebimento::itens(&historico, numero);
+            if itens.is_empty() {
+                return Err(format!("Recebimento nº {} não encontrado.", numero).into());
+            }
+            let lote: Vec<(&Relogio, i32)> = itens
+                .iter()
+                .filter_map(|(c, q)| Some((relogios.get(c)?, *q)))
+                .collect();
+            let payload = etiquetas::lote(&lote, config.formato_etiqueta, numeros);
+            let unidades: i32 = lote.iter().map(|(_, q)| q).sum();
+            if args.iter().any(|a| a == "--imprimir") {
+                let endereco = config
+                    .impressora_etiquetas
+                    .ok_or("Configure impressora_etiquetas em config.toml para imprimir.")?;
+                etiquetas::envia(&endereco, &payload)?;
+                println!("{} etiqueta(s) enviada(s) para {}", unidades, endereco);
+            } else {
+                let caminho = etiquetas::grava_lote(
+                    &format!("recebimento_{}", numero),
+                    &payload,
+                    config.formato_etiqueta,
+                )?;
+                println!("{} etiqueta(s) em {}", unidades, caminho);
+            }
+            Ok(())
+        }
+        "fornecedores" => {
+            let (_, historico, registros) = load_from_file();
+            print!(
+                "{}",
+                pedidos::desempenho(&registros.pedidos_compra, &historico, numeros)
+            );
+            Ok(())
+        }
+        "nfe" => {
+            let Some(arquivo) = args.get(1) else {
+                return Err("Uso: nfe <arquivo.xml> [--simular]".into());
+            };
+            let simular = args.iter().any(|a| a == "--simular");
+            let nota = nfe::le(&fs::read_to_string(arquivo)?)?;
+            let (mut relogios, mut historico, mut registros) = load_from_file();
+            let resultado = nfe::importa(
+                &nota,
+                &mut relogios,
+                &mut historico,
+                &registros.referencias_fornecedor,
+                Local::now().naive_local(),
+            )?;
+            println!(
+                "NF-e {} de {} ({} itens)",
+                nota.identificacao(),
+                nota.emitente,
+                nota.itens.len()
+            );
+            for (codigo, qtd, custo) in &resultado.lancados {
+                println!("  COMPRA {} x{} a {}", codigo, qtd, numeros.moeda(*custo));
+            }
+            for (item, motivo) in &resultado.ignorados {
+                println!(
+                    "  fora: {} {} ({})",
+                    item.codigo_fornecedor, item.descricao, motivo
+                );
+            }
+            if !resultado.ignorados.is_empty() {
+                println!("Ligue os códigos do fornecedor aos internos na tela Fornecedores (U) e importe de novo.");
+            }
+            let hoje = Local::now().format("%Y-%m-%d").to_string();
+            let origem = format!("{} {}", nota.emitente, nota.cnpj);
+            for (codigo, qtd, _) in &resultado.lancados {
+                for numero in pedidos::recebe(
+                    &mut registros.pedidos_compra,
+                    Some(&origem),
+                    codigo,
+                    *qtd,
+                    &hoje,
+                ) {
+                    println!("  pedido nº {} recebido por completo", numero);
+                }
+            }
+            if simular {
+                println!("Simulação: nada foi gravado.");
+            } else if !resultado.lancados.is_empty() {
+                save_to_file(&relogios, &historico, &registros);
+            }
+            Ok(())
+        }
+        "kardex" => {
+            const USO: &str =
+                "Uso: kardex <codigo|todos> <inicio AAAA-MM-DD> <fim AAAA-MM-DD> [arquivo]";
+            let (Some(codigo), Some(inicio), Some(fim)) = (args.get(1), args.get(2), args.get(3))
+            else {
+                return Err(USO.into());
+            };
+            for data in [inicio, fim] {
+                chrono::NaiveDate::parse_from_str(data, "%Y-%m-%d")
+                    .map_err(|_| format!("Data inválida: {}", data))?;
+            }
+            let (_, historico, _) = load_from_file();
+            let mut k = kardex::Kardex::carrega();
+            k.atualiza(&historico);
+            k.salva();
+            let filtro = (codigo != "todos").then_some(codigo.as_str());
+            let (csv, n) = k.extrato_csv(filtro, inicio, fim);
+            let destino = args
+                .get(4)
+                .cloned()
+                .unwrap_or_else(|| format!("kardex_{}_{}_{}.csv", codigo, inicio, fim));
+            fs::write(&destino, csv)?;
+            println!("{} dias de movimento exportados para {}.", n, destino);
+            Ok(())
+        }
+        "relatorios" if args.get(1).map(|a| a.as_str()) == Some("ajustes") => {
+            const USO: &str =
+                "Uso: relatorios ajustes <inicio AAAA-MM-DD> <fim AAAA-MM-DD> [arquivo]";
+            let (Some(inicio), Some(fim)) = (args.get(2), args.get(3)) else {
+                return Err(USO.into());
+            };
+            let data = |texto: &String| {
+                chrono::NaiveDate::parse_from_str(texto, "%Y-%m-%d")
+                    .map_err(|_| format!("Data inválida: {}", texto))
+            };
+            let (de, ate) = (data(inicio)?, data(fim)?);
+            let (relogios, historico, registros) = load_from_file();
+            let csv = relatorios::ajustes_csv(
+                &historico,
+                &relogios,
+                &registros.exclusoes,
+                de,
+                ate,
+                numeros,
+            );
+            let destino = args
+                .get(4)
+                .cloned()
+                .unwrap_or_else(|| format!("ajustes_{}_{}.csv", inicio, fim));
+            fs::write(&destino, &csv)?;
+            // Fora o cabeçalho e o total
+            println!(
+                "{} lançamentos exportados para {}.",
+                csv.lines().count() - 2,
+                destino
+            );
+            Ok(())
+        }
+        "relatorios" => {
+            let config = config::Config::carregar();
+            let pasta = args
+                .get(1)
+                .cloned()
+                .or(config.relatorios_pasta)
+                .ok_or("Uso: relatorios [pasta] (ou configure relatorios_pasta)")?;
+            let (relogios, historico, registros) = load_from_file();
+            let hoje = Local::now().date_naive();
+            let criados = relatorios::gera_pendentes(
+                &pasta, &historico, &relogios, &registros, hoje, numeros,
+            )?;
+            for nome in &criados {
+                println!("{}", nome);
+            }
+            println!("{} relatórios gravados em {}.", criados.len(), pasta);
+            Ok(())
+        }
+        "modelo" => {
+            const USO: &str = "Uso: modelo <nome> [inicio AAAA-MM-DD fim AAAA-MM-DD] [arquivo]";
+            let Some(nome) = args.get(1) else {
+                let nomes = modelos::lista();
+                if nomes.is_empty() {
+                    println!("Nenhum modelo em {}/.", modelos::PASTA_MODELOS);
+                }
+                for n in nomes {
+                    println!("{}", n);
+                }
+                return Ok(());
+            };
+            let data = |i: usize| {
+                args.get(i)
+                    .filter(|a| chrono::NaiveDate::parse_from_str(a, "%Y-%m-%d").is_ok())
+            };
+            let hoje = Local::now().date_naive().to_string();
+            let (inicio, fim, resto) = match (data(2), data(3)) {
+                (Some(inicio), Some(fim)) => (inicio.clone(), fim.clone(), 4),
+                (None, None) => (hoje.clone(), hoje.clone(), 2),
+                _ => return Err(USO.into()),
+            };
+            let (relogios, historico, registros) = load_from_file();
+            let ctx = modelos::contexto(&relogios, &historico, &registros, &hoje, &inicio, &fim);
+            let texto = modelos::renderiza(nome, &ctx, numeros)?;
+            let destino = args
+                .get(resto)
+                .cloned()
+                .unwrap_or_else(|| nome.trim_end_matches(".tera").to_string());
+            fs::write(&destino, texto)?;
+            println!(
+                "Modelo {} ({} a {}) gravado em {}.",
+                nome, inicio, fim, destino
+            );
+            Ok(())
+        }
+        "sincronizar" => {
+            const USO: &str = "Uso: sincronizar exportar [arquivo] | importar <arquivo> | conflitos | resolver <codigo>";
+            let loja = config::Config::carregar()
+                .loja
+                .ok_or("Defina o nome desta loja (chave loja) antes de sincronizar.")?;
+            let (mut relogios, mut historico, mut registros) = load_from_file();
+            match (args.get(1).map(|a| a.as_str()), args.get(2)) {
+                (Some("exportar"), destino) => {
+                    let agora = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
+                    let pacote = sincronia::exporta(&loja, &relogios, &historico, &agora);
+                    let destino = destino
+                        .cloned()
+                        .unwrap_or_else(|| format!("sincronia_{}.json", loja));
+                    fs::write(&destino, serde_json::to_string_pretty(&pacote)?)?;
+                    println!(
+                        "{} lançamentos de {} exportados para {}.",
+                        pacote.historico.len(),
+                        loja,
+                        destino
+                    );
+                }
+                (Some("importar"), Some(arquivo)) => {
+                    let pacote: sincronia::Pacote =
+                        serde_json::from_str(&fs::read_to_string(arquivo)?)?;
+                    if pacote.loja == loja {
+                        return Err(format!("{} é um pacote desta mesma loja.", arquivo).into());
+                    }
+                    let r = sincronia::mescla(&loja, &mut relogios, &mut historico, &pacote);
+                    for c in &r.conflitos {
+                        registros
+                            .conflitos_sincronia
+                            .retain(|x| x.codigo != c.codigo);
+                        registros.conflitos_sincronia.push(c.clone());
+                    }
+                    save_to_file(&relogios, &historico, &registros);
+                    println!(
+                        "{}: {} lançamentos e {} produtos novos (pacote de {}).",
+                        pacote.loja, r.lancamentos_novos, r.produtos_novos, pacote.gerado
+                    );
+                    if !r.conflitos.is_empty() {
+                        println!(
+                            "{} conflito(s); veja `sincronizar conflitos`.",
+                            r.conflitos.len()
+                        );
+                    }
+                }
+                (Some("conflitos"), _) => {
+                    for c in &registros.conflitos_sincronia {
+                        println!("{}: saldo {}", c.codigo, c.saldo);
+                        for v in &c.vendas {
+                            println!("  venda sem saldo: {}", v);
+                        }
+                    }
+                    println!(
+                        "{} conflito(s). Corrija com uma contagem (=) ou compra e rode `sincronizar resolver <codigo>`.",
+                        registros.conflitos_sincronia.len()
+                    );
+                }
+                (Some("resolver"), Some(codigo)) => {
+                    let antes = registros.conflitos_sincronia.len();
+                    registros
+                        .conflitos_sincronia
+                        .retain(|c| &c.codigo != codigo);
+                    if registros.conflitos_sincronia.len() == antes {
+                        return Err(format!("Nenhum conflito para {}.", codigo).into());
+                    }
+                    save_to_file(&relogios, &historico, &registros);
+                    println!("Conflito de {} marcado como resolvido.", codigo);
+                }
+                _ => return Err(USO.into()),
+            }
+            Ok(())
+        }
+        "gs1" => {
+            const USO: &str = "Uso: gs1 exportar [arquivo] | gs1 importar <arquivo>";
+            let (mut relogios, historico, registros) = load_from_file();
+            match (args.get(1).map(|a| a.as_str()), args.get(2)) {
+                (Some("exportar"), destino) => {
+                    let destino = destino.map(|d| d.as_str()).unwrap_or("produtos_gs1.json");
+                    let json = gs1::exporta(&relogios);
+                    fs::write(destino, serde_json::to_string_pretty(&json)?)?;
+                    println!("{} produtos exportados para {}.", relogios.len(), destino);
+                }
+                (Some("importar"), Some(arquivo)) => {
+                    let json: serde_json::Value =
+                        serde_json::from_str(&fs::read_to_string(arquivo)?)?;
+                    let produtos = gs1::le(&json);
+                    if produtos.is_empty() {
+                        return Err(
+                            format!("Nenhum produto com sku ou GTIN em {}.", arquivo).into()
+                        );
+                    }
+                    let (atualizados, criados) = gs1::aplica(&mut relogios, &produtos);
+                    save_to_file(&relogios, &historico, &registros);
+                    println!("{} atualizados, {} criados.", atualizados, criados);
+                }
+                _ => return Err(USO.into()),
+            }
+            Ok(())
+        }
+        "token" => {
+            const USO: &str =
+                "Uso: token criar <nome> <leitura|escrita> | token listar | token revogar <nome>";
+            let mut lista = tokens::carrega();
+            match (args.get(1).map(|a| a.as_str()), args.get(2)) {
+                (Some("criar"), Some(nome)) => {
+                    let escopo = args
+                        .get(3)
+                        .and_then(|e| tokens::Escopo::parse(e))
+                        .ok_or(USO)?;
+                    let valor = tokens::cria(&mut lista, nome, escopo)?;
+                    tokens::salva(&lista)?;
+                    println!("Token {} ({}): {}", nome, escopo.nome(), valor);
+                    println!("Guarde-o agora; ele não será mostrado de novo.");
+                }
+                (Some("revogar"), Some(nome)) => {
+                    let antes = lista.len();
+                    lista.retain(|t| &t.nome != nome);
+                    if lista.len() == antes {
+                        return Err(format!("Token não encontrado: {}", nome).into());
+                    }
+                    tokens::salva(&lista)?;
+                    println!("Token {} revogado.", nome);
+                }
+                (Some("listar") | None, _) => {
+                    for t in &lista {
+                        println!("{};{};{}", t.nome, t.escopo.nome(), t.criado);
+                    }
+                }
+                _ => return Err(USO.into()),
+            }
+            Ok(())
+        }
+        "promocoes" => {
+            let (relogios, historico, registros) = load_from_file();
+            for p in &registros.promocoes {
+                println!(
+                    "{}: -{}% em {} de {} a {}",
+                    p.nome, p.desconto, p.alvo, p.inicio, p.fim
+                );
+            }
+            println!("\nPromoção;Vendas;Unidades;Descontos");
+            for (nome, vendas, unidades, concedido) in promocoes::relatorio(&historico, &relogios) {
+                println!(
+                    "{};{};{};{}",
+                    nome,
+                    vendas,
+                    unidades,
+                    numeros.moeda(concedido)
+                );
+            }
+            Ok(())
+        }
+        outro => Err(format!("Comando desconhecido: {}", outro).into()),
+    }
+}
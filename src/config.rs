@@ -0,0 +1,491 @@
+use crate::colunas::{self, Tabela};
+use crate::i18n::Idioma;
+use crate::numeros::FormatoNumero;
+use crate::persistencia::na_pasta_dados;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+
+const CAMINHO_CONFIG: &str = "config.toml";
+
+/// Configurações da loja, lidas de `config.toml` no diretório atual.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Impressora de etiquetas na rede, no formato `host:porta` (porta 9100).
+    pub impressora_etiquetas: Option<String>,
+    /// Linguagem da impressora: "zpl" (Zebra) ou "epl" (Eltron).
+    pub formato_etiqueta: FormatoEtiqueta,
+    /// Porta serial da impressora ESC/POS que aciona a gaveta (ex.: /dev/ttyUSB0, COM3).
+    pub gaveta_porta: Option<String>,
+    /// Alíquota aproximada de tributos (%) impressa nas notas de venda.
+    pub aliquota_imposto: f64,
+    /// Planilha do Google Sheets que recebe estoque e resumo de vendas.
+    pub sheets_planilha_id: Option<String>,
+    pub sheets_client_id: Option<String>,
+    pub sheets_client_secret: Option<String>,
+    pub sheets_refresh_token: Option<String>,
+    /// Envio automático para o Sheets a cada N minutos (0 = só pela hotkey).
+    pub sheets_intervalo_min: u64,
+    /// Dias entre o pedido e a chegada da mercadoria, para prever a reposição.
+    pub prazo_reposicao_dias: i64,
+    /// API de preços de mercado; `{codigo}` é substituído pelo modelo.
+    pub preco_mercado_url: Option<String>,
+    /// Provedor de câmbio; `{moeda}` é substituído pelo código (ex.: USD).
+    pub cambio_url: String,
+    /// Margem (%) sobre o custo em reais para o preço sugerido.
+    pub margem_sugerida: f64,
+    /// O que fazer com vendas abaixo do custo: permitir, avisar ou bloquear.
+    pub venda_abaixo_custo: RegraMargem,
+    /// Custo que a regra acima compara: "medio" das compras ou o da
+    /// "ultima" compra.
+    pub custo_referencia: CustoReferencia,
+    /// Senha que libera operações bloqueadas (`!senha` na venda).
+    pub senha_admin: Option<String>,
+    /// Requisições por minuto aceitas de cada token da API (0 = sem limite).
+    pub api_limite_por_minuto: u32,
+    /// Pasta (ex.: sincronizada pelo Dropbox) que recebe o CSV diário de
+    /// vendas e o PDF mensal automaticamente.
+    pub relatorios_pasta: Option<String>,
+    /// Separadores de milhar e decimal em telas, relatórios e digitação:
+    /// "pt-BR" (1.234,56) ou "en-US" (1,234.56).
+    pub formato_numeros: FormatoNumero,
+    /// Idioma das telas: "pt-BR" ou "en-US".
+    pub idioma: Idioma,
+    /// Cores das telas: "escuro", "claro" ou "alto_contraste".
+    pub paleta: Paleta,
+    /// Cores de entradas/saídas: "padrao" (as da paleta), "daltonico"
+    /// (azul/laranja) ou "monocromatico".
+    pub tema: Tema,
+    /// Segundos entre as telas do modo quiosque.
+    pub quiosque_intervalo_seg: u64,
+    /// Segundos sem teclas até a demonstração começar e entre as telas
+    /// dela (0 desativa).
+    pub demonstracao_seg: u64,
+    /// Colunas do Estoque e do Histórico, na ordem, com a largura em %
+    /// (ex.: "codigo:40 qtd:20 preco:20 categoria:20").
+    pub colunas_estoque: String,
+    pub colunas_historico: String,
+    /// Medidores de estoque × alvo dos favoritos abaixo da tabela do Estoque.
+    pub painel_medidores: bool,
+    /// Nome desta máquina na sincronização entre lojas (ex.: "centro").
+    pub loja: Option<String>,
+    /// Gasto acumulado a partir do qual o aniversário do cliente é lembrado.
+    pub aniversario_gasto_minimo: f64,
+    /// Notificações e recados já lidos saem ao abrir depois de N dias
+    /// (0 guarda tudo).
+    pub retencao_avisos_dias: i64,
+    /// Ao abrir, arquiva o histórico com N anos ou mais (0 desativa).
+    pub retencao_historico_anos: i32,
+    /// Linhas guardadas no log de mensagens da sessão (0 = sem limite).
+    pub limite_mensagens: usize,
+    /// Maior quantidade aceita num cadastro, compra ou venda (0 = sem
+    /// limite); acima disso é quase sempre um erro de digitação.
+    pub quantidade_maxima: i32,
+    /// Trocas de cor por papel sobre a paleta (tabela `[cores]`, ex.:
+    /// `destaque = "#ff8800"`); os papéis estão em `tema::PAPEIS`.
+    pub cores: BTreeMap<String, String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            impressora_etiquetas: None,
+            formato_etiqueta: FormatoEtiqueta::default(),
+            gaveta_porta: None,
+            aliquota_imposto: 0.0,
+            sheets_planilha_id: None,
+            sheets_client_id: None,
+            sheets_client_secret: None,
+            sheets_refresh_token: None,
+            sheets_intervalo_min: 0,
+            prazo_reposicao_dias: 7,
+            preco_mercado_url: None,
+            cambio_url: "https://economia.awesomeapi.com.br/json/last/{moeda}-BRL".into(),
+            margem_sugerida: 100.0,
+            venda_abaixo_custo: RegraMargem::default(),
+            custo_referencia: CustoReferencia::default(),
+            senha_admin: None,
+            api_limite_por_minuto: 120,
+            relatorios_pasta: None,
+            formato_numeros: FormatoNumero::default(),
+            idioma: Idioma::default(),
+            paleta: Paleta::default(),
+            tema: Tema::default(),
+            quiosque_intervalo_seg: 10,
+            demonstracao_seg: 0,
+            colunas_estoque: Tabela::Estoque.padrao().into(),
+            colunas_historico: Tabela::Historico.padrao().into(),
+            painel_medidores: false,
+            loja: None,
+            aniversario_gasto_minimo: 1000.0,
+            retencao_avisos_dias: 0,
+            retencao_historico_anos: 0,
+            limite_mensagens: 0,
+            quantidade_maxima: 10_000,
+            cores: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FormatoEtiqueta {
+    #[default]
+    Zpl,
+    Epl,
+}
+
+/// Conjunto de cores das telas (ver `tema::Cores`).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Paleta {
+    /// As cores de sempre, sobre o fundo do terminal.
+    #[default]
+    Escuro,
+    /// Texto escuro sobre fundo branco.
+    Claro,
+    /// Cores vivas sobre fundo preto.
+    AltoContraste,
+}
+
+/// Paleta de entradas/saídas; os símbolos ▲/▼ e os sinais +/- aparecem em
+/// todas, para não depender só da cor.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Tema {
+    #[default]
+    Padrao,
+    /// Azul e laranja, distinguíveis com deuteranopia e protanopia.
+    Daltonico,
+    Monocromatico,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RegraMargem {
+    Permitir,
+    #[default]
+    Avisar,
+    Bloquear,
+}
+
+/// Custo de compra contra o qual o preço de venda é conferido.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CustoReferencia {
+    /// Média das compras, ponderada pelas unidades.
+    #[default]
+    Medio,
+    Ultimo,
+}
+
+/// Chaves editáveis na tela de configurações, na ordem exibida.
+pub const CHAVES: &[&str] = &[
+    "impressora_etiquetas",
+    "formato_etiqueta",
+    "gaveta_porta",
+    "aliquota_imposto",
+    "sheets_planilha_id",
+    "sheets_client_id",
+    "sheets_client_secret",
+    "sheets_refresh_token",
+    "sheets_intervalo_min",
+    "prazo_reposicao_dias",
+    "preco_mercado_url",
+    "cambio_url",
+    "margem_sugerida",
+    "venda_abaixo_custo",
+    "custo_referencia",
+    "senha_admin",
+    "api_limite_por_minuto",
+    "relatorios_pasta",
+    "formato_numeros",
+    "idioma",
+    "paleta",
+    "tema",
+    "quiosque_intervalo_seg",
+    "demonstracao_seg",
+    "colunas_estoque",
+    "colunas_historico",
+    "painel_medidores",
+    "loja",
+    "aniversario_gasto_minimo",
+    "retencao_avisos_dias",
+    "retencao_historico_anos",
+    "limite_mensagens",
+    "quantidade_maxima",
+];
+
+/// Chaves cujo valor a tela de Configurações nunca mostra.
+pub fn secreta(chave: &str) -> bool {
+    matches!(
+        chave,
+        "senha_admin" | "sheets_client_secret" | "sheets_refresh_token"
+    )
+}
+
+fn opcional(valor: &str) -> Option<String> {
+    let valor = valor.trim();
+    (!valor.is_empty()).then(|| valor.to_string())
+}
+
+impl Config {
+    pub fn carregar() -> Config {
+        fs::read_to_string(na_pasta_dados(CAMINHO_CONFIG))
+            .ok()
+            .and_then(|data| toml::from_str(&data).ok())
+            .unwrap_or_default()
+    }
+
+    /// Destino do Sheets, quando todas as credenciais estão preenchidas.
+    pub fn destino_planilha(&self) -> Option<crate::planilha::Destino> {
+        Some(crate::planilha::Destino {
+            planilha_id: self.sheets_planilha_id.clone()?,
+            client_id: self.sheets_client_id.clone()?,
+            client_secret: self.sheets_client_secret.clone()?,
+            refresh_token: self.sheets_refresh_token.clone()?,
+        })
+    }
+
+    pub fn salvar(&self) -> Result<(), String> {
+        let data = toml::to_string_pretty(self).map_err(|e| e.to_string())?;
+        fs::write(na_pasta_dados(CAMINHO_CONFIG), data).map_err(|e| e.to_string())
+    }
+
+    /// Chaves de texto livre opcionais.
+    fn texto(&mut self, chave: &str) -> Option<&mut Option<String>> {
+        match chave {
+            "impressora_etiquetas" => Some(&mut self.impressora_etiquetas),
+            "gaveta_porta" => Some(&mut self.gaveta_porta),
+            "sheets_planilha_id" => Some(&mut self.sheets_planilha_id),
+            "sheets_client_id" => Some(&mut self.sheets_client_id),
+            "sheets_client_secret" => Some(&mut self.sheets_client_secret),
+            "sheets_refresh_token" => Some(&mut self.sheets_refresh_token),
+            "preco_mercado_url" => Some(&mut self.preco_mercado_url),
+            "senha_admin" => Some(&mut self.senha_admin),
+            "relatorios_pasta" => Some(&mut self.relatorios_pasta),
+            "loja" => Some(&mut self.loja),
+            _ => None,
+        }
+    }
+
+    /// Valor atual de uma chave, formatado para exibição/edição.
+    pub fn valor(&self, chave: &str) -> String {
+        let mut copia = self.clone();
+        if let Some(v) = copia.texto(chave) {
+            return v.take().unwrap_or_default();
+        }
+        match chave {
+            "formato_etiqueta" => match self.formato_etiqueta {
+                FormatoEtiqueta::Zpl => "zpl".into(),
+                FormatoEtiqueta::Epl => "epl".into(),
+            },
+            "venda_abaixo_custo" => match self.venda_abaixo_custo {
+                RegraMargem::Permitir => "permitir".into(),
+                RegraMargem::Avisar => "avisar".into(),
+                RegraMargem::Bloquear => "bloquear".into(),
+            },
+            "custo_referencia" => match self.custo_referencia {
+                CustoReferencia::Medio => "medio".into(),
+                CustoReferencia::Ultimo => "ultimo".into(),
+            },
+            "aliquota_imposto" => self.formato_numeros.numero(self.aliquota_imposto, 2),
+            "sheets_intervalo_min" => self.sheets_intervalo_min.to_string(),
+            "prazo_reposicao_dias" => self.prazo_reposicao_dias.to_string(),
+            "retencao_avisos_dias" => self.retencao_avisos_dias.to_string(),
+            "retencao_historico_anos" => self.retencao_historico_anos.to_string(),
+            "limite_mensagens" => self.limite_mensagens.to_string(),
+            "quantidade_maxima" => self.quantidade_maxima.to_string(),
+            "cambio_url" => self.cambio_url.clone(),
+            "margem_sugerida" => self.formato_numeros.numero(self.margem_sugerida, 2),
+            "aniversario_gasto_minimo" => self
+                .formato_numeros
+                .numero(self.aniversario_gasto_minimo, 2),
+            "api_limite_por_minuto" => self.api_limite_por_minuto.to_string(),
+            "quiosque_intervalo_seg" => self.quiosque_intervalo_seg.to_string(),
+            "demonstracao_seg" => self.demonstracao_seg.to_string(),
+            "colunas_estoque" => self.colunas_estoque.clone(),
+            "colunas_historico" => self.colunas_historico.clone(),
+            "painel_medidores" => if self.painel_medidores { "sim" } else { "nao" }.into(),
+            "formato_numeros" => self.formato_numeros.nome().into(),
+            "idioma" => self.idioma.nome().into(),
+            "paleta" => match self.paleta {
+                Paleta::Escuro => "escuro".into(),
+                Paleta::Claro => "claro".into(),
+                Paleta::AltoContraste => "alto_contraste".into(),
+            },
+            "tema" => match self.tema {
+                Tema::Padrao => "padrao".into(),
+                Tema::Daltonico => "daltonico".into(),
+                Tema::Monocromatico => "monocromatico".into(),
+            },
+            _ => String::new(),
+        }
+    }
+
+    /// Altera uma chave a partir do texto digitado (vazio limpa opcionais).
+    pub fn define(&mut self, chave: &str, valor: &str) -> Result<(), String> {
+        if let Some(v) = self.texto(chave) {
+            *v = opcional(valor);
+            return Ok(());
+        }
+        match chave {
+            "formato_etiqueta" => {
+                self.formato_etiqueta = match valor.trim().to_lowercase().as_str() {
+                    "zpl" => FormatoEtiqueta::Zpl,
+                    "epl" => FormatoEtiqueta::Epl,
+                    _ => return Err("Use zpl ou epl.".into()),
+                }
+            }
+            "venda_abaixo_custo" => {
+                self.venda_abaixo_custo = match valor.trim().to_lowercase().as_str() {
+                    "permitir" => RegraMargem::Permitir,
+                    "avisar" => RegraMargem::Avisar,
+                    "bloquear" => RegraMargem::Bloquear,
+                    _ => return Err("Use permitir, avisar ou bloquear.".into()),
+                }
+            }
+            "custo_referencia" => {
+                self.custo_referencia = match valor.trim().to_lowercase().as_str() {
+                    "medio" | "médio" => CustoReferencia::Medio,
+                    "ultimo" | "último" => CustoReferencia::Ultimo,
+                    _ => return Err("Use medio ou ultimo.".into()),
+                }
+            }
+            "sheets_intervalo_min" => {
+                self.sheets_intervalo_min = valor
+                    .trim()
+                    .parse()
+                    .map_err(|_| "Informe os minutos (0 desativa).")?
+            }
+            "api_limite_por_minuto" => {
+                self.api_limite_por_minuto = valor
+                    .trim()
+                    .parse()
+                    .map_err(|_| "Informe as requisições por minuto (0 desativa).")?
+            }
+            "quiosque_intervalo_seg" => {
+                self.quiosque_intervalo_seg = valor
+                    .trim()
+                    .parse()
+                    .ok()
+                    .filter(|s| *s > 0)
+                    .ok_or("Informe os segundos entre as telas.")?
+            }
+            "colunas_estoque" | "colunas_historico" => {
+                let tabela = if chave == "colunas_estoque" {
+                    Tabela::Estoque
+                } else {
+                    Tabela::Historico
+                };
+                let texto = match valor.trim() {
+                    "" => tabela.padrao(),
+                    t => t,
+                };
+                colunas::interpreta(tabela, texto)?;
+                let destino = if tabela == Tabela::Estoque {
+                    &mut self.colunas_estoque
+                } else {
+                    &mut self.colunas_historico
+                };
+                *destino = texto.to_string();
+            }
+            "demonstracao_seg" => {
+                self.demonstracao_seg = valor
+                    .trim()
+                    .parse()
+                    .map_err(|_| "Informe os segundos de espera (0 desativa).")?
+            }
+            "painel_medidores" => {
+                self.painel_medidores = match valor.trim().to_lowercase().as_str() {
+                    "sim" | "s" | "true" => true,
+                    "nao" | "não" | "n" | "false" => false,
+                    _ => return Err("Use sim ou nao.".into()),
+                }
+            }
+            "prazo_reposicao_dias" => {
+                self.prazo_reposicao_dias = valor
+                    .trim()
+                    .parse()
+                    .ok()
+                    .filter(|d| *d >= 0)
+                    .ok_or("Informe o prazo em dias.")?
+            }
+            "retencao_avisos_dias" => {
+                self.retencao_avisos_dias = valor
+                    .trim()
+                    .parse()
+                    .ok()
+                    .filter(|d| *d >= 0)
+                    .ok_or("Informe os dias (0 guarda tudo).")?
+            }
+            "retencao_historico_anos" => {
+                self.retencao_historico_anos = valor
+                    .trim()
+                    .parse()
+                    .ok()
+                    .filter(|a| *a >= 0)
+                    .ok_or("Informe os anos (0 desativa).")?
+            }
+            "limite_mensagens" => {
+                self.limite_mensagens = valor
+                    .trim()
+                    .parse()
+                    .map_err(|_| "Informe as linhas do log (0 = sem limite).")?
+            }
+            "quantidade_maxima" => {
+                self.quantidade_maxima = self
+                    .formato_numeros
+                    .interpreta_inteiro(valor)
+                    .filter(|q| *q >= 0)
+                    .ok_or("Informe o máximo de unidades por operação (0 = sem limite).")?
+            }
+            "paleta" => {
+                self.paleta = match valor.trim().to_lowercase().replace('-', "_").as_str() {
+                    "escuro" => Paleta::Escuro,
+                    "claro" => Paleta::Claro,
+                    "alto_contraste" => Paleta::AltoContraste,
+                    _ => return Err("Use escuro, claro ou alto_contraste.".into()),
+                }
+            }
+            "tema" => {
+                self.tema = match valor.trim().to_lowercase().as_str() {
+                    "padrao" | "padrão" => Tema::Padrao,
+                    "daltonico" | "daltônico" => Tema::Daltonico,
+                    "monocromatico" | "monocromático" => Tema::Monocromatico,
+                    _ => return Err("Use padrao, daltonico ou monocromatico.".into()),
+                }
+            }
+            "idioma" => self.idioma = Idioma::parse(valor).ok_or("Use pt-BR ou en-US.")?,
+            "formato_numeros" => {
+                self.formato_numeros = FormatoNumero::parse(valor).ok_or("Use pt-BR ou en-US.")?
+            }
+            "cambio_url" if valor.contains("{moeda}") => self.cambio_url = valor.trim().into(),
+            "cambio_url" => return Err("A URL precisa conter {moeda}.".into()),
+            "margem_sugerida" => {
+                self.margem_sugerida = self
+                    .formato_numeros
+                    .interpreta(valor)
+                    .filter(|m| *m >= 0.0)
+                    .ok_or("Margem inválida.")?
+            }
+            "aniversario_gasto_minimo" => {
+                self.aniversario_gasto_minimo = self
+                    .formato_numeros
+                    .interpreta(valor)
+                    .filter(|m| *m >= 0.0)
+                    .ok_or("Valor inválido.")?
+            }
+            "aliquota_imposto" => {
+                self.aliquota_imposto = self
+                    .formato_numeros
+                    .interpreta(valor)
+                    .filter(|a| (0.0..=100.0).contains(a))
+                    .ok_or("Alíquota deve estar entre 0 e 100.")?
+            }
+            _ => return Err(format!("Chave desconhecida: {}", chave)),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,476 @@
+use crate::numeros::FormatoNumero;
+use crate::{contabil, Historico, Relogio};
+use std::collections::{BTreeMap, HashMap};
+
+/// Valor de uma célula do resultado; números são formatados só na exibição.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Celula {
+    Texto(String),
+    /// Valor e casas decimais.
+    Numero(f64, usize),
+}
+
+impl Celula {
+    pub fn exibe(&self, formato: FormatoNumero) -> String {
+        match self {
+            Celula::Texto(t) => t.clone(),
+            Celula::Numero(v, casas) => formato.numero(*v, *casas),
+        }
+    }
+
+    fn csv(&self) -> String {
+        match self {
+            Celula::Texto(t) => t.replace(';', ","),
+            Celula::Numero(v, casas) => format!("{:.*}", casas, v),
+        }
+    }
+}
+
+/// Resultado de uma consulta.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tabela {
+    pub colunas: Vec<String>,
+    pub linhas: Vec<Vec<Celula>>,
+}
+
+impl Tabela {
+    /// CSV separado por `;`, como os demais relatórios, com ponto decimal.
+    pub fn csv(&self) -> String {
+        let mut out = self.colunas.join(";");
+        out.push('\n');
+        for l in &self.linhas {
+            out.push_str(&l.iter().map(Celula::csv).collect::<Vec<_>>().join(";"));
+            out.push('\n');
+        }
+        out
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Palavra(String),
+    /// Entre aspas: nunca é palavra-chave.
+    Texto(String),
+    Operador(String),
+}
+
+fn tokens(texto: &str) -> Result<Vec<Token>, String> {
+    let mut out = vec![];
+    let mut chars = texto.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' || c == '\'' {
+            chars.next();
+            let mut t = String::new();
+            loop {
+                match chars.next() {
+                    Some(f) if f == c => break,
+                    Some(ch) => t.push(ch),
+                    None => return Err("Aspas sem fechamento.".into()),
+                }
+            }
+            out.push(Token::Texto(t));
+        } else if "=!<>".contains(c) {
+            let mut op = String::new();
+            while let Some(&ch) = chars.peek().filter(|ch| "=!<>".contains(**ch)) {
+                op.push(ch);
+                chars.next();
+            }
+            if !["=", "!=", "<", ">", "<=", ">="].contains(&op.as_str()) {
+                return Err(format!("Operador desconhecido: {}", op));
+            }
+            out.push(Token::Operador(op));
+        } else {
+            let mut p = String::new();
+            while let Some(&ch) = chars
+                .peek()
+                .filter(|ch| !ch.is_whitespace() && !"=!<>\"'".contains(**ch))
+            {
+                p.push(ch);
+                chars.next();
+            }
+            out.push(Token::Palavra(p));
+        }
+    }
+    Ok(out)
+}
+
+/// Campo de uma entrada do histórico, pelos nomes em inglês ou português.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Campo {
+    Operacao,
+    Codigo,
+    Data,
+    Mes,
+    Ano,
+    Pagamento,
+    Tag,
+    Origem,
+    Fornecedor,
+    Cliente,
+    Marca,
+    Categoria,
+    Quantidade,
+    Valor,
+}
+
+impl Campo {
+    fn parse(nome: &str) -> Result<Campo, String> {
+        Ok(match nome.to_lowercase().as_str() {
+            "op" | "operacao" => Campo::Operacao,
+            "code" | "codigo" => Campo::Codigo,
+            "date" | "data" => Campo::Data,
+            "month" | "mes" => Campo::Mes,
+            "year" | "ano" => Campo::Ano,
+            "pay" | "pagamento" => Campo::Pagamento,
+            "tag" => Campo::Tag,
+            "shop" | "loja" | "origem" => Campo::Origem,
+            "supplier" | "fornecedor" => Campo::Fornecedor,
+            "customer" | "cliente" => Campo::Cliente,
+            "brand" | "marca" => Campo::Marca,
+            "category" | "categoria" => Campo::Categoria,
+            "qty" | "qtd" | "quantidade" => Campo::Quantidade,
+            "value" | "valor" => Campo::Valor,
+            _ => return Err(format!("Campo desconhecido: {}", nome)),
+        })
+    }
+
+    fn nome(self) -> &'static str {
+        match self {
+            Campo::Operacao => "operacao",
+            Campo::Codigo => "codigo",
+            Campo::Data => "data",
+            Campo::Mes => "mes",
+            Campo::Ano => "ano",
+            Campo::Pagamento => "pagamento",
+            Campo::Tag => "tag",
+            Campo::Origem => "origem",
+            Campo::Fornecedor => "fornecedor",
+            Campo::Cliente => "cliente",
+            Campo::Marca => "marca",
+            Campo::Categoria => "categoria",
+            Campo::Quantidade => "quantidade",
+            Campo::Valor => "valor",
+        }
+    }
+
+    fn numerico(self) -> bool {
+        matches!(self, Campo::Quantidade | Campo::Valor)
+    }
+
+    /// Valores do campo na entrada (tags podem ser várias, os demais um só).
+    fn valores(self, h: &Historico, r: Option<&Relogio>) -> Vec<String> {
+        let ts = |n: usize| h.timestamp.get(..n).unwrap_or(&h.timestamp).to_string();
+        let opcional = |v: Option<&String>| v.cloned().into_iter().collect();
+        match self {
+            Campo::Operacao => vec![h.operacao.clone()],
+            Campo::Codigo => vec![h.codigo.clone()],
+            Campo::Data => vec![ts(10)],
+            Campo::Mes => vec![ts(7)],
+            Campo::Ano => vec![ts(4)],
+            Campo::Pagamento => opcional(h.pagamento.as_ref()),
+            Campo::Tag => h.tags.clone(),
+            Campo::Origem => opcional(h.origem.as_ref()),
+            Campo::Fornecedor => opcional(h.fornecedor.as_ref()),
+            Campo::Cliente => opcional(h.cliente.as_ref()),
+            Campo::Marca => opcional(r.and_then(|r| r.marca.as_ref())),
+            Campo::Categoria => opcional(r.and_then(|r| r.categoria.as_ref())),
+            Campo::Quantidade | Campo::Valor => vec![self.numero(h, r).to_string()],
+        }
+    }
+
+    fn numero(self, h: &Historico, r: Option<&Relogio>) -> f64 {
+        match self {
+            Campo::Quantidade => h.quantidade as f64,
+            Campo::Valor => contabil::valor(h, r).unwrap_or(0.0),
+            _ => 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Condicao {
+    campo: Campo,
+    operador: String,
+    valor: String,
+}
+
+/// `*` vale qualquer sequência; sem caixa.
+fn casa_curinga(texto: &str, padrao: &str) -> bool {
+    let (texto, padrao) = (texto.to_lowercase(), padrao.to_lowercase());
+    let partes: Vec<&str> = padrao.split('*').collect();
+    let mut resto = texto.as_str();
+    for (i, p) in partes.iter().enumerate() {
+        if i == 0 {
+            let Some(r) = resto.strip_prefix(p) else {
+                return false;
+            };
+            resto = r;
+        } else if i == partes.len() - 1 {
+            return resto.ends_with(p);
+        } else {
+            let Some(pos) = resto.find(p) else {
+                return false;
+            };
+            resto = &resto[pos + p.len()..];
+        }
+    }
+    resto.is_empty()
+}
+
+impl Condicao {
+    fn aceita(&self, h: &Historico, r: Option<&Relogio>) -> bool {
+        let valores = self.campo.valores(h, r);
+        let casa = |v: &String| {
+            if self.operador == "like" {
+                return casa_curinga(v, &self.valor);
+            }
+            let ordem = match (v.parse::<f64>(), self.valor.parse::<f64>()) {
+                (Ok(a), Ok(b)) if self.campo.numerico() => a.total_cmp(&b),
+                _ => v.to_lowercase().cmp(&self.valor.to_lowercase()),
+            };
+            match self.operador.as_str() {
+                "=" => ordem.is_eq(),
+                "!=" => ordem.is_ne(),
+                "<" => ordem.is_lt(),
+                ">" => ordem.is_gt(),
+                "<=" => ordem.is_le(),
+                _ => ordem.is_ge(),
+            }
+        };
+        if self.operador == "!=" {
+            valores.iter().all(casa)
+        } else {
+            valores.iter().any(casa)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Agregacao {
+    Lista,
+    Contagem,
+    Soma,
+    Media,
+    Minimo,
+    Maximo,
+}
+
+/// Consulta já interpretada.
+#[derive(Debug, Clone, PartialEq)]
+struct Consulta {
+    agregacao: Agregacao,
+    campo: Option<Campo>,
+    condicoes: Vec<Condicao>,
+    grupo: Option<Campo>,
+}
+
+fn interpreta(texto: &str) -> Result<Consulta, String> {
+    let mut t = tokens(texto)?.into_iter().peekable();
+    let palavra = |t: &Option<Token>| match t {
+        Some(Token::Palavra(p)) => Some(p.to_lowercase()),
+        _ => None,
+    };
+    let agregacao = match palavra(&t.peek().cloned()).as_deref() {
+        Some("list" | "lista") => Agregacao::Lista,
+        Some("count" | "conta") => Agregacao::Contagem,
+        Some("sum" | "soma") => Agregacao::Soma,
+        Some("avg" | "media") => Agregacao::Media,
+        Some("min") => Agregacao::Minimo,
+        Some("max") => Agregacao::Maximo,
+        _ => return Err("Comece com list, count, sum, avg, min ou max.".into()),
+    };
+    t.next();
+    let campo = match agregacao {
+        Agregacao::Lista | Agregacao::Contagem => None,
+        _ => {
+            let nome = palavra(&t.next()).ok_or("Informe o campo a agregar (qty ou value).")?;
+            let campo = Campo::parse(&nome)?;
+            if !campo.numerico() {
+                return Err(format!("{} não é numérico: use qty ou value.", nome));
+            }
+            Some(campo)
+        }
+    };
+    let mut consulta = Consulta {
+        agregacao,
+        campo,
+        condicoes: vec![],
+        grupo: None,
+    };
+    while let Some(tok) = t.next() {
+        match palavra(&Some(tok.clone())).as_deref() {
+            Some("where" | "onde" | "and" | "e") => {
+                let nome = palavra(&t.next()).ok_or("Esperava um campo depois de where/and.")?;
+                let campo = Campo::parse(&nome)?;
+                let operador = match t.next() {
+                    Some(Token::Operador(op)) => op,
+                    Some(Token::Palavra(p)) if p.eq_ignore_ascii_case("like") => "like".into(),
+                    _ => {
+                        return Err(format!(
+                            "Esperava =, !=, <, >, <=, >= ou like após {}.",
+                            nome
+                        ))
+                    }
+                };
+                let valor = match t.next() {
+                    Some(Token::Palavra(v) | Token::Texto(v)) => v,
+                    _ => return Err(format!("Falta o valor de {}.", nome)),
+                };
+                consulta.condicoes.push(Condicao {
+                    campo,
+                    operador,
+                    valor,
+                });
+            }
+            Some("by" | "por") => {
+                let nome = palavra(&t.next()).ok_or("Esperava um campo depois de by.")?;
+                consulta.grupo = Some(Campo::parse(&nome)?);
+            }
+            _ => return Err(format!("Não entendi: {:?}", tok)),
+        }
+    }
+    if consulta.agregacao == Agregacao::Lista && consulta.grupo.is_some() {
+        return Err("list não agrupa: use count, sum, avg, min ou max com by.".into());
+    }
+    Ok(consulta)
+}
+
+/// Interpreta e executa a consulta sobre o histórico em memória, por exemplo
+/// `sum qty where op=VENDA and code like "SEIKO*" and month=2024-05 by code`.
+pub fn executa(
+    texto: &str,
+    historico: &[Historico],
+    relogios: &HashMap<String, Relogio>,
+) -> Result<Tabela, String> {
+    let c = interpreta(texto)?;
+    let entradas = historico.iter().filter(|h| {
+        let r = relogios.get(&h.codigo);
+        c.condicoes.iter().all(|cond| cond.aceita(h, r))
+    });
+    if c.agregacao == Agregacao::Lista {
+        let linhas = entradas
+            .map(|h| {
+                let r = relogios.get(&h.codigo);
+                vec![
+                    Celula::Texto(h.timestamp.clone()),
+                    Celula::Texto(h.operacao.clone()),
+                    Celula::Texto(h.codigo.clone()),
+                    Celula::Numero(h.quantidade as f64, 0),
+                    Celula::Numero(Campo::Valor.numero(h, r), 2),
+                ]
+            })
+            .collect();
+        return Ok(Tabela {
+            colunas: ["timestamp", "operacao", "codigo", "quantidade", "valor"]
+                .map(String::from)
+                .to_vec(),
+            linhas,
+        });
+    }
+    // Por grupo: contagem, soma, mínimo e máximo do campo
+    let mut grupos: BTreeMap<String, (usize, f64, f64, f64)> = BTreeMap::new();
+    for h in entradas {
+        let r = relogios.get(&h.codigo);
+        let v = c.campo.map_or(0.0, |campo| campo.numero(h, r));
+        let chaves = match c.grupo {
+            Some(g) => g.valores(h, r),
+            None => vec![String::new()],
+        };
+        for chave in chaves {
+            let e = grupos
+                .entry(chave)
+                .or_insert((0, 0.0, f64::INFINITY, f64::NEG_INFINITY));
+            e.0 += 1;
+            e.1 += v;
+            e.2 = e.2.min(v);
+            e.3 = e.3.max(v);
+        }
+    }
+    if c.grupo.is_none() && grupos.is_empty() {
+        grupos.insert(String::new(), (0, 0.0, 0.0, 0.0));
+    }
+    let casas = if c.campo == Some(Campo::Valor) { 2 } else { 0 };
+    let rotulo = match (c.agregacao, c.campo) {
+        (Agregacao::Contagem, _) | (_, None) => "count".to_string(),
+        (a, Some(campo)) => {
+            let nome = match a {
+                Agregacao::Soma => "sum",
+                Agregacao::Media => "avg",
+                Agregacao::Minimo => "min",
+                _ => "max",
+            };
+            format!("{}({})", nome, campo.nome())
+        }
+    };
+    let linhas = grupos
+        .into_iter()
+        .map(|(chave, (n, soma, min, max))| {
+            let valor = match c.agregacao {
+                Agregacao::Contagem => Celula::Numero(n as f64, 0),
+                Agregacao::Soma => Celula::Numero(soma, casas),
+                Agregacao::Media => Celula::Numero(if n > 0 { soma / n as f64 } else { 0.0 }, 2),
+                Agregacao::Minimo => Celula::Numero(min, casas),
+                _ => Celula::Numero(max, casas),
+            };
+            match c.grupo {
+                Some(_) => vec![Celula::Texto(chave), valor],
+                None => vec![valor],
+            }
+        })
+        .collect();
+    let mut colunas: Vec<String> = c.grupo.map(|g| g.nome().to_string()).into_iter().collect();
+    colunas.push(rotulo);
+    Ok(Tabela { colunas, linhas })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn h(codigo: &str, operacao: &str, quantidade: i32, timestamp: &str) -> Historico {
+        Historico {
+            codigo: codigo.into(),
+            quantidade,
+            operacao: operacao.into(),
+            timestamp: timestamp.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn soma_filtra_e_agrupa() {
+        let historico = vec![
+            h("SEIKO-5", "VENDA", 2, "2024-05-03 10:00:00"),
+            h("SEIKO-PRESAGE", "VENDA", 1, "2024-05-20 11:00:00"),
+            h("SEIKO-5", "VENDA", 4, "2024-06-01 09:00:00"),
+            h("CASIO-1", "VENDA", 3, "2024-05-04 12:00:00"),
+            h("SEIKO-5", "COMPRA", 10, "2024-05-01 08:00:00"),
+        ];
+        let relogios = HashMap::new();
+        let t = executa(
+            r#"sum qty where op=VENDA and code like "seiko*" and month=2024-05"#,
+            &historico,
+            &relogios,
+        )
+        .unwrap();
+        assert_eq!(t.colunas, ["sum(quantidade)"]);
+        assert_eq!(t.linhas, [[Celula::Numero(3.0, 0)]]);
+
+        let t = executa(
+            "count where op = VENDA and qty>=2 by month",
+            &historico,
+            &relogios,
+        )
+        .unwrap();
+        assert_eq!(t.csv(), "mes;count\n2024-05;2\n2024-06;1\n");
+
+        let t = executa("list where code != SEIKO-5", &historico, &relogios).unwrap();
+        assert_eq!(t.linhas.len(), 2);
+
+        assert!(executa("sum code", &historico, &relogios).is_err());
+        assert!(executa("count where preco > 3", &historico, &relogios).is_err());
+        assert!(executa("count where code like \"X", &historico, &relogios).is_err());
+    }
+}
@@ -0,0 +1,137 @@
+use crate::{Historico, Relogio};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Formato {
+    Ofx,
+    Qif,
+}
+
+impl Formato {
+    pub fn parse(s: &str) -> Option<Formato> {
+        match s.to_lowercase().as_str() {
+            "ofx" => Some(Formato::Ofx),
+            "qif" => Some(Formato::Qif),
+            _ => None,
+        }
+    }
+
+    pub fn extensao(self) -> &'static str {
+        match self {
+            Formato::Ofx => "ofx",
+            Formato::Qif => "qif",
+        }
+    }
+}
+
+/// Lançamento financeiro derivado de uma VENDA (crédito) ou COMPRA (débito).
+struct Lancamento<'a> {
+    h: &'a Historico,
+    valor: f64,
+}
+
+/// Valor financeiro de uma entrada: vendas entram pelo preço de venda (menos
+/// o desconto de promoção) e compras saem pelo custo pago na nota, ou pelo
+/// custo do produto.
+pub fn valor(h: &Historico, r: Option<&Relogio>) -> Option<f64> {
+    match h.operacao.as_str() {
+        "VENDA" => Some(h.preco_tabela(r) * h.quantidade as f64 * h.fator_preco()),
+        "COMPRA" => {
+            let custo = h.custo.or(r.and_then(|r| r.custo)).unwrap_or(0.0);
+            Some(-(custo * h.quantidade as f64))
+        }
+        _ => None,
+    }
+}
+
+fn lancamentos<'a>(
+    historico: &'a [Historico],
+    relogios: &HashMap<String, Relogio>,
+    inicio: &str,
+    fim: &str,
+) -> Vec<Lancamento<'a>> {
+    historico
+        .iter()
+        .filter(|h| {
+            let data = h.timestamp.get(..10).unwrap_or("");
+            data >= inicio && data <= fim
+        })
+        .filter_map(|h| {
+            let valor = valor(h, relogios.get(&h.codigo))?;
+            Some(Lancamento { h, valor })
+        })
+        .collect()
+}
+
+fn data_compacta(timestamp: &str) -> String {
+    timestamp.chars().filter(|c| c.is_ascii_digit()).collect()
+}
+
+fn ofx(lancamentos: &[Lancamento], inicio: &str, fim: &str) -> String {
+    let mut out = String::from(
+        "OFXHEADER:100\nDATA:OFXSGML\nVERSION:102\nSECURITY:NONE\nENCODING:USASCII\n\
+         CHARSET:1252\nCOMPRESSION:NONE\nOLDFILEUID:NONE\nNEWFILEUID:NONE\n\n",
+    );
+    out.push_str("<OFX>\n<SIGNONMSGSRSV1><SONRS><STATUS><CODE>0<SEVERITY>INFO</STATUS>");
+    out.push_str(&format!(
+        "<DTSERVER>{}<LANGUAGE>POR</SONRS></SIGNONMSGSRSV1>\n",
+        data_compacta(&chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string())
+    ));
+    out.push_str("<BANKMSGSRSV1><STMTTRNRS><TRNUID>1<STATUS><CODE>0<SEVERITY>INFO</STATUS>\n");
+    out.push_str("<STMTRS><CURDEF>BRL<BANKACCTFROM><BANKID>0000<ACCTID>ESTOQUE");
+    out.push_str("<ACCTTYPE>CHECKING</BANKACCTFROM>\n");
+    out.push_str(&format!(
+        "<BANKTRANLIST><DTSTART>{}<DTEND>{}\n",
+        data_compacta(inicio),
+        data_compacta(fim)
+    ));
+    for (i, l) in lancamentos.iter().enumerate() {
+        out.push_str(&format!(
+            "<STMTTRN><TRNTYPE>{}<DTPOSTED>{}<TRNAMT>{:.2}<FITID>{}{:04}<MEMO>{} {} x {}</STMTTRN>\n",
+            if l.valor >= 0.0 { "CREDIT" } else { "DEBIT" },
+            data_compacta(&l.h.timestamp),
+            l.valor,
+            data_compacta(&l.h.timestamp),
+            i,
+            l.h.operacao,
+            l.h.quantidade,
+            l.h.codigo
+        ));
+    }
+    out.push_str("</BANKTRANLIST></STMTRS></STMTTRNRS></BANKMSGSRSV1>\n</OFX>\n");
+    out
+}
+
+fn qif(lancamentos: &[Lancamento]) -> String {
+    let mut out = String::from("!Type:Cash\n");
+    for l in lancamentos {
+        let data = l.h.timestamp.get(..10).unwrap_or("");
+        let partes: Vec<&str> = data.split('-').collect();
+        let data = match partes.as_slice() {
+            [a, m, d] => format!("{}/{}/{}", d, m, a),
+            _ => data.to_string(),
+        };
+        out.push_str(&format!(
+            "D{}\nT{:.2}\nP{} {}\nM{} x {}\n^\n",
+            data, l.valor, l.h.operacao, l.h.codigo, l.h.quantidade, l.h.codigo
+        ));
+    }
+    out
+}
+
+/// Gera o arquivo contábil do período `[inicio, fim]` (datas AAAA-MM-DD).
+/// Retorna o conteúdo e quantos lançamentos entraram.
+pub fn exporta(
+    formato: Formato,
+    historico: &[Historico],
+    relogios: &HashMap<String, Relogio>,
+    inicio: &str,
+    fim: &str,
+) -> (String, usize) {
+    let l = lancamentos(historico, relogios, inicio, fim);
+    let conteudo = match formato {
+        Formato::Ofx => ofx(&l, inicio, fim),
+        Formato::Qif => qif(&l),
+    };
+    (conteudo, l.len())
+}
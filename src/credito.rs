@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Movimento de crédito da loja: emissão (positivo, ex.: devolução ou
+/// vale-presente) ou resgate numa venda (negativo).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MovimentoCredito {
+    pub cliente: String,
+    pub valor: f64,
+    pub motivo: String,
+    pub timestamp: String,
+}
+
+/// Chave do cliente: nomes são comparados sem caixa e sem espaços extras.
+pub fn chave(cliente: &str) -> String {
+    cliente
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Movimentos de um cliente, na ordem em que ocorreram.
+pub fn extrato<'a>(movimentos: &'a [MovimentoCredito], cliente: &str) -> Vec<&'a MovimentoCredito> {
+    let c = chave(cliente);
+    movimentos
+        .iter()
+        .filter(|m| chave(&m.cliente) == c)
+        .collect()
+}
+
+pub fn saldo(movimentos: &[MovimentoCredito], cliente: &str) -> f64 {
+    extrato(movimentos, cliente).iter().map(|m| m.valor).sum()
+}
+
+/// Saldo de cada cliente com crédito em aberto.
+pub fn saldos(movimentos: &[MovimentoCredito]) -> BTreeMap<String, f64> {
+    let mut mapa: BTreeMap<String, (String, f64)> = BTreeMap::new();
+    for m in movimentos {
+        let e = mapa
+            .entry(chave(&m.cliente))
+            .or_insert_with(|| (m.cliente.trim().to_string(), 0.0));
+        e.1 += m.valor;
+    }
+    mapa.into_values()
+        .filter(|(_, v)| v.abs() >= 0.005)
+        .collect()
+}
+
+/// Total devido aos clientes em créditos ainda não resgatados.
+pub fn passivo(movimentos: &[MovimentoCredito]) -> f64 {
+    saldos(movimentos).values().sum()
+}
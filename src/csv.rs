@@ -0,0 +1,68 @@
+/// Separadores aceitos; planilhas em pt-BR costumam exportar com `;`.
+pub const SEPARADORES: [char; 3] = [';', ',', '\t'];
+
+/// Codificação do arquivo: exportações antigas do Windows vêm em Latin-1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Codificacao {
+    Utf8,
+    Latin1,
+}
+
+impl Codificacao {
+    pub fn nome(self) -> &'static str {
+        match self {
+            Codificacao::Utf8 => "UTF-8",
+            Codificacao::Latin1 => "Latin-1",
+        }
+    }
+}
+
+/// UTF-8 quando os bytes são válidos nessa codificação; senão Latin-1.
+pub fn detecta_codificacao(bytes: &[u8]) -> Codificacao {
+    match std::str::from_utf8(bytes) {
+        Ok(_) => Codificacao::Utf8,
+        Err(_) => Codificacao::Latin1,
+    }
+}
+
+/// Converte o arquivo para texto, descartando o BOM do UTF-8.
+pub fn decodifica(bytes: &[u8], codificacao: Codificacao) -> String {
+    match codificacao {
+        Codificacao::Utf8 => {
+            let texto = String::from_utf8_lossy(bytes);
+            texto.strip_prefix('\u{feff}').unwrap_or(&texto).to_string()
+        }
+        // Cada byte Latin-1 é o code point de mesmo valor
+        Codificacao::Latin1 => bytes.iter().map(|&b| b as char).collect(),
+    }
+}
+
+/// Escolhe o separador que mais aparece na primeira linha.
+pub fn detecta_separador(linha: &str) -> char {
+    SEPARADORES
+        .iter()
+        .copied()
+        .max_by_key(|s| linha.matches(*s).count())
+        .unwrap_or(',')
+}
+
+/// Divide uma linha CSV respeitando campos entre aspas (`""` vira `"`).
+pub fn divide_linha(linha: &str, sep: char) -> Vec<String> {
+    let mut campos = vec![];
+    let mut atual = String::new();
+    let mut aspas = false;
+    let mut chars = linha.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if aspas && chars.peek() == Some(&'"') => {
+                atual.push('"');
+                chars.next();
+            }
+            '"' => aspas = !aspas,
+            c if c == sep && !aspas => campos.push(std::mem::take(&mut atual)),
+            c => atual.push(c),
+        }
+    }
+    campos.push(atual);
+    campos.iter().map(|c| c.trim().to_string()).collect()
+}
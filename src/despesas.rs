@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Despesa operacional (aluguel, frete, taxas...) com data AAAA-MM-DD.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Despesa {
+    pub data: String,
+    pub categoria: String,
+    pub valor: f64,
+    #[serde(default)]
+    pub descricao: String,
+}
+
+/// Total por categoria das despesas com data em `[inicio, fim]`.
+pub fn por_categoria(despesas: &[Despesa], inicio: &str, fim: &str) -> BTreeMap<String, f64> {
+    let mut mapa = BTreeMap::new();
+    for d in despesas
+        .iter()
+        .filter(|d| d.data.as_str() >= inicio && d.data.as_str() <= fim)
+    {
+        *mapa.entry(d.categoria.to_lowercase()).or_insert(0.0) += d.valor;
+    }
+    mapa
+}
@@ -0,0 +1,1219 @@
+use crate::config::{Config, CustoReferencia, RegraMargem};
+use crate::i18n::{self, Idioma};
+use crate::numeros::FormatoNumero;
+use chrono::{Local, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Formato dos timestamps gravados no histórico.
+pub const FORMATO_TIMESTAMP: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Produto do estoque: um relógio, ou um kit montado a partir de outros.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Relogio {
+    pub codigo: String,
+    quantidade: i32,
+    #[serde(default)]
+    pub descricao: String,
+    #[serde(default)]
+    pub custo: Option<f64>,
+    #[serde(default)]
+    pub preco: Option<f64>,
+    /// Moeda do custo (ex.: USD) para importados; ausente = reais.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub moeda: Option<String>,
+    /// Categoria usada pelas promoções (tags ficam na descrição como `#tag`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub categoria: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub marca: Option<String>,
+    /// Unidades por caixa/pacote, para produtos comprados em embalagens.
+    #[serde(default)]
+    pub unidades_por_pacote: Option<i32>,
+    /// Componentes, quando o código é um kit; o estoque vem deles.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub componentes: Vec<Componente>,
+    #[serde(default)]
+    pub etiqueta_impressa: bool,
+    /// Máximo de unidades por venda (edições limitadas).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limite_por_venda: Option<i32>,
+    /// Exibido no modo quiosque.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub destaque: bool,
+    /// Código de barras de fábrica (GTIN/EAN).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ean: Option<String>,
+    /// Estoque desejado, comparado ao atual no painel de medidores; a
+    /// sugestão de reposição pede até ele.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estoque_alvo: Option<i32>,
+    /// Ponto de pedido: com o saldo mais o já pedido nele ou abaixo, a
+    /// reposição é sugerida.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estoque_minimo: Option<i32>,
+    #[serde(default, skip_serializing_if = "Situacao::eh_ativo")]
+    pub situacao: Situacao,
+}
+
+impl Relogio {
+    /// Produto com o saldo de partida. Depois disso o saldo só muda pelo
+    /// [`Inventario`], que lança a entrada do histórico junto.
+    pub fn novo(codigo: impl Into<String>, quantidade: Quantidade) -> Relogio {
+        Relogio {
+            codigo: codigo.into(),
+            quantidade: quantidade.valor(),
+            ..Default::default()
+        }
+    }
+
+    /// Saldo próprio; num kit é zero (ver [`quantidade_disponivel`]).
+    pub fn quantidade(&self) -> i32 {
+        self.quantidade
+    }
+
+    /// Troca o saldo sem lançar nada, para quem o refaz a partir do
+    /// histórico já gravado.
+    pub(crate) fn define_quantidade(&mut self, quantidade: i32) {
+        self.quantidade = quantidade;
+    }
+}
+
+/// Fase do produto no catálogo.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Situacao {
+    #[default]
+    Ativo,
+    /// Em pré-venda: vende também o que ainda vai chegar dos pedidos.
+    Encomenda,
+    /// Saiu de linha: a compra avisa.
+    Descontinuado,
+    /// Fora das listas e da busca, a menos que se peça para mostrar.
+    Arquivado,
+}
+
+impl Situacao {
+    pub fn interpreta(texto: &str) -> Option<Situacao> {
+        match texto.trim().to_lowercase().as_str() {
+            "ativo" => Some(Situacao::Ativo),
+            "encomenda" | "pre-venda" | "pré-venda" => Some(Situacao::Encomenda),
+            "descontinuado" => Some(Situacao::Descontinuado),
+            "arquivado" => Some(Situacao::Arquivado),
+            _ => None,
+        }
+    }
+
+    pub fn rotulo(self) -> &'static str {
+        match self {
+            Situacao::Ativo => "ativo",
+            Situacao::Encomenda => "encomenda",
+            Situacao::Descontinuado => "descontinuado",
+            Situacao::Arquivado => "arquivado",
+        }
+    }
+
+    pub fn eh_ativo(&self) -> bool {
+        *self == Situacao::Ativo
+    }
+}
+
+/// Entrada do histórico de movimentos; o saldo de cada código é a soma delas.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Historico {
+    pub codigo: String,
+    pub quantidade: i32,
+    pub operacao: String,
+    pub timestamp: String,
+    /// Kit que originou a baixa, nas entradas `BAIXA_KIT` dos componentes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kit: Option<String>,
+    /// Forma de pagamento da VENDA (dinheiro, cartao, pix, credito).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pagamento: Option<String>,
+    /// Número da nota emitida para esta VENDA.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nota: Option<u32>,
+    /// Cotação usada na COMPRA de um produto com custo em moeda estrangeira.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cambio: Option<f64>,
+    /// Promoção aplicada na VENDA e o desconto (%) concedido.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub promocao: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub desconto: Option<f64>,
+    /// Motivo do AJUSTE, cuja quantidade é a diferença com sinal.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub motivo: Option<String>,
+    /// Usuário do sistema que lançou o AJUSTE.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usuario: Option<String>,
+    /// Loja que fez o lançamento, nos recebidos pela sincronização; os
+    /// lançamentos desta máquina ficam sem origem.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origem: Option<String>,
+    /// Número do lançamento na loja de origem, crescente. Separa na
+    /// sincronização lançamentos iguais feitos no mesmo segundo; os antigos
+    /// ficam sem.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sequencia: Option<u64>,
+    /// Custo unitário: o pago na COMPRA lançada a partir da NF-e do
+    /// fornecedor ou, nas demais, o do cadastro (em reais) na data.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custo: Option<f64>,
+    /// Preço de tabela na data do lançamento; a VENDA vale por ele mesmo
+    /// que o preço do produto mude depois.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preco: Option<f64>,
+    /// NF-e de compra (`numero/serie`) e seu emitente.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nfe: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fornecedor: Option<String>,
+    /// Número do recebimento que lançou esta COMPRA junto com as demais da
+    /// mesma entrega.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recebimento: Option<u32>,
+    /// Cliente cadastrado atendido na VENDA.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cliente: Option<String>,
+    /// Etiquetas livres aplicadas em lote no histórico (ex.: "black friday").
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl Historico {
+    /// Fração do preço de tabela efetivamente cobrada.
+    pub fn fator_preco(&self) -> f64 {
+        1.0 - self.desconto.unwrap_or(0.0) / 100.0
+    }
+
+    /// Preço de tabela da entrada: o gravado no lançamento ou, nas entradas
+    /// de antes dele existir, o atual do produto.
+    pub fn preco_tabela(&self, r: Option<&Relogio>) -> f64 {
+        self.preco.or(r.and_then(|r| r.preco)).unwrap_or(0.0)
+    }
+
+    /// Sentido do movimento no estoque: 1 entrada, -1 saída, 0 quando a
+    /// operação fixa o saldo (cadastro).
+    pub fn sentido(&self) -> i32 {
+        match self.operacao.as_str() {
+            "COMPRA" => 1,
+            "VENDA" | "BAIXA_KIT" => -1,
+            "AJUSTE" => self.quantidade.signum(),
+            _ => 0,
+        }
+    }
+
+    /// Operação com ▲/▼, para não depender só da cor, no idioma das telas.
+    pub(crate) fn operacao_marcada(&self, idioma: Idioma) -> String {
+        let operacao = i18n::t(idioma, &self.operacao);
+        match self.sentido() {
+            1 => format!("▲ {}", operacao),
+            -1 => format!("▼ {}", operacao),
+            _ => format!("• {}", operacao),
+        }
+    }
+
+    pub fn tem_tag(&self, tag: &str) -> bool {
+        let tag = tag.to_lowercase();
+        self.tags.iter().any(|t| t.to_lowercase() == tag)
+    }
+
+    /// Quantidade com o sinal do movimento (`+4`, `-2`).
+    pub(crate) fn quantidade_marcada(&self, numeros: FormatoNumero) -> String {
+        let qtd = numeros.inteiro(self.quantidade.abs() as i64);
+        match self.sentido() {
+            1 => format!("+{}", qtd),
+            -1 => format!("-{}", qtd),
+            _ => qtd,
+        }
+    }
+}
+
+/// Item que compõe um kit, com a quantidade consumida por kit vendido.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Componente {
+    pub codigo: String,
+    pub quantidade: i32,
+}
+
+/// Custo unitário das COMPRAS do código, pelo que ficou gravado em cada
+/// uma: a média ponderada pelas unidades ou o da compra mais recente. `None`
+/// se nenhuma compra registrou custo.
+pub fn custo_de_compra(
+    historico: &[Historico],
+    codigo: &str,
+    referencia: CustoReferencia,
+) -> Option<f64> {
+    let compras = historico
+        .iter()
+        .filter(|h| h.operacao == "COMPRA" && h.codigo == codigo && h.quantidade > 0)
+        .filter_map(|h| Some((h, h.custo?)));
+    match referencia {
+        CustoReferencia::Medio => {
+            let (unidades, total) = compras.fold((0i64, 0.0), |(u, t), (h, custo)| {
+                (u + h.quantidade as i64, t + custo * h.quantidade as f64)
+            });
+            (unidades > 0).then(|| total / unidades as f64)
+        }
+        CustoReferencia::Ultimo => compras
+            .max_by(|a, b| a.0.timestamp.cmp(&b.0.timestamp))
+            .map(|(_, custo)| custo),
+    }
+}
+
+/// Código interno do produto com esse EAN, para aceitar o código de barras
+/// lido pelo leitor onde se digita o código.
+pub fn codigo_por_ean<'a>(relogios: &'a HashMap<String, Relogio>, ean: &str) -> Option<&'a str> {
+    relogios
+        .values()
+        .find(|r| r.ean.as_deref() == Some(ean))
+        .map(|r| r.codigo.as_str())
+}
+
+/// Usuário do sistema operacional, para assinar ajustes, exclusões e recados.
+pub fn operador() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "operador".into())
+}
+
+/// Unidades de cada componente por kit, somando o componente que aparece
+/// mais de uma vez na lista.
+fn por_componente(r: &Relogio) -> Vec<(&str, i32)> {
+    let mut total: Vec<(&str, i32)> = vec![];
+    for c in &r.componentes {
+        match total.iter_mut().find(|(codigo, _)| *codigo == c.codigo) {
+            Some((_, q)) => *q = q.saturating_add(c.quantidade),
+            None => total.push((&c.codigo, c.quantidade)),
+        }
+    }
+    total
+}
+
+/// Estoque disponível: o próprio saldo, ou quantos kits os componentes montam.
+pub fn quantidade_disponivel(relogios: &HashMap<String, Relogio>, r: &Relogio) -> i32 {
+    if r.componentes.is_empty() {
+        return r.quantidade;
+    }
+    por_componente(r)
+        .into_iter()
+        .map(|(codigo, por_kit)| {
+            let saldo = relogios.get(codigo).map_or(0, |p| p.quantidade);
+            if por_kit > 0 {
+                saldo / por_kit
+            } else {
+                i32::MAX
+            }
+        })
+        .min()
+        .unwrap_or(0)
+        .max(0)
+}
+
+/// Operação recusada por violar uma regra do estoque.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErroDominio {
+    CodigoVazio,
+    CodigoComEspaco(String),
+    QuantidadeNegativa(i32),
+    /// Compra e venda movimentam ao menos uma unidade.
+    MovimentoVazio,
+    /// Mais unidades do que o configurado para uma única operação.
+    AcimaDoLimite {
+        quantidade: i32,
+        maximo: i32,
+    },
+    /// O saldo sairia da faixa de um `i32`.
+    Estouro(String),
+    NaoEncontrado(String),
+    /// Kits não têm saldo próprio: o estoque vem dos componentes.
+    Kit(String),
+    EstoqueInsuficiente {
+        codigo: String,
+        disponivel: i32,
+    },
+    DataFutura(String),
+    /// Tamanho fora de 8/12/13/14 dígitos ou dígito verificador errado.
+    EanInvalido(String),
+    SemAlteracao {
+        codigo: String,
+        quantidade: i32,
+    },
+    /// O código novo de uma renomeação já pertence a outro produto.
+    CodigoEmUso(String),
+    /// Venda acima do `limite_por_venda` do produto sem a senha.
+    LimitePorVenda {
+        codigo: String,
+        quantidade: i32,
+        limite: i32,
+    },
+    /// Venda abaixo do custo com `venda_abaixo_custo = "bloquear"`, sem a
+    /// senha.
+    AbaixoDoCusto {
+        codigo: String,
+        preco: f64,
+        custo: f64,
+    },
+    /// Produto sem saldo e sem entrega a caminho que cubra a venda.
+    Esgotado(String),
+}
+
+impl fmt::Display for ErroDominio {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErroDominio::CodigoVazio => write!(f, "Informe o código do relógio."),
+            ErroDominio::CodigoComEspaco(c) => write!(f, "Código com espaço: '{}'.", c),
+            ErroDominio::QuantidadeNegativa(q) => {
+                write!(f, "O estoque não pode ficar negativo ({}).", q)
+            }
+            ErroDominio::MovimentoVazio => write!(f, "Informe ao menos uma unidade."),
+            ErroDominio::AcimaDoLimite { quantidade, maximo } => write!(
+                f,
+                "{} unidades passam do limite de {} por operação.",
+                quantidade, maximo
+            ),
+            ErroDominio::Estouro(c) => write!(f, "O saldo de {} passaria do máximo possível.", c),
+            ErroDominio::NaoEncontrado(c) => write!(f, "Relógio {} não encontrado!", c),
+            ErroDominio::Kit(c) => write!(f, "{} é um kit: movimente os componentes.", c),
+            ErroDominio::EstoqueInsuficiente { codigo, disponivel } => write!(
+                f,
+                "Não há estoque suficiente de {} (disponível: {})!",
+                codigo, disponivel
+            ),
+            ErroDominio::DataFutura(t) => write!(f, "Lançamento no futuro: {}.", t),
+            ErroDominio::EanInvalido(e) => write!(f, "EAN inválido: '{}'.", e),
+            ErroDominio::SemAlteracao { codigo, quantidade } => {
+                write!(f, "{} já tem {} unidades.", codigo, quantidade)
+            }
+            ErroDominio::CodigoEmUso(c) => write!(f, "O código {} já existe.", c),
+            ErroDominio::LimitePorVenda { codigo, limite, .. } => write!(
+                f,
+                "Limite de {} un. por venda para {} (acrescente !senha para liberar)",
+                limite, codigo
+            ),
+            ErroDominio::AbaixoDoCusto {
+                codigo,
+                preco,
+                custo,
+            } => write!(
+                f,
+                "Venda bloqueada, {} abaixo do custo: {:.2} < {:.2} (acrescente !senha para liberar)",
+                codigo, preco, custo
+            ),
+            ErroDominio::Esgotado(c) => write!(f, "{} sem estoque.", c),
+        }
+    }
+}
+
+impl std::error::Error for ErroDominio {}
+
+/// Código de um produto: não vazio e sem espaços (é separado por espaço na
+/// digitação e por `;` nos relatórios).
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Codigo(String);
+
+impl Codigo {
+    pub fn novo(texto: &str) -> Result<Codigo, ErroDominio> {
+        let texto = texto.trim();
+        if texto.is_empty() {
+            return Err(ErroDominio::CodigoVazio);
+        }
+        if texto.contains(char::is_whitespace) {
+            return Err(ErroDominio::CodigoComEspaco(texto.to_string()));
+        }
+        Ok(Codigo(texto.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Codigo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+/// Identidade do produto em todo o sistema: chave do estoque, do histórico,
+/// do kardex e da API. Só nasce de um [`Codigo`] válido, e não se confunde
+/// com um EAN ou com o código do fornecedor.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ProdutoId(Codigo);
+
+impl ProdutoId {
+    pub fn novo(texto: &str) -> Result<ProdutoId, ErroDominio> {
+        Codigo::novo(texto).map(ProdutoId)
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl From<Codigo> for ProdutoId {
+    fn from(codigo: Codigo) -> ProdutoId {
+        ProdutoId(codigo)
+    }
+}
+
+impl fmt::Display for ProdutoId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+/// Código de barras de fábrica (EAN-8, UPC-A, EAN-13 ou GTIN-14) com o
+/// dígito verificador conferido.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Ean(String);
+
+impl Ean {
+    pub fn novo(texto: &str) -> Result<Ean, ErroDominio> {
+        let texto = texto.trim();
+        let digitos: Vec<u32> = texto.chars().filter_map(|c| c.to_digit(10)).collect();
+        if digitos.len() != texto.len() || ![8, 12, 13, 14].contains(&digitos.len()) {
+            return Err(ErroDominio::EanInvalido(texto.to_string()));
+        }
+        // Pesos 3 e 1 alternados a partir do dígito à esquerda do verificador
+        let (verificador, corpo) = digitos.split_last().expect("ao menos 8 dígitos");
+        let soma: u32 = corpo
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(i, d)| if i % 2 == 0 { d * 3 } else { *d })
+            .sum();
+        if (10 - soma % 10) % 10 != *verificador {
+            return Err(ErroDominio::EanInvalido(texto.to_string()));
+        }
+        Ok(Ean(texto.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Unidades em estoque ou movimentadas; nunca negativa.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Quantidade(i32);
+
+impl Quantidade {
+    pub const ZERO: Quantidade = Quantidade(0);
+
+    pub fn nova(valor: i32) -> Result<Quantidade, ErroDominio> {
+        if valor < 0 {
+            return Err(ErroDominio::QuantidadeNegativa(valor));
+        }
+        Ok(Quantidade(valor))
+    }
+
+    /// Quantidade de uma compra ou venda: ao menos uma unidade.
+    pub fn movimento(valor: i32) -> Result<Quantidade, ErroDominio> {
+        match Quantidade::nova(valor)? {
+            Quantidade(0) => Err(ErroDominio::MovimentoVazio),
+            q => Ok(q),
+        }
+    }
+
+    /// Recusa movimentos acima de `maximo` unidades (0 = sem limite), que
+    /// costumam ser erro de digitação.
+    pub fn ate(self, maximo: i32) -> Result<Quantidade, ErroDominio> {
+        if maximo > 0 && self.0 > maximo {
+            return Err(ErroDominio::AcimaDoLimite {
+                quantidade: self.0,
+                maximo,
+            });
+        }
+        Ok(self)
+    }
+
+    pub fn valor(self) -> i32 {
+        self.0
+    }
+}
+
+/// Onde a venda foi feita: só a do balcão abre a gaveta.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Canal {
+    Balcao,
+    Api,
+}
+
+/// Venda pedida no balcão ou pela API, com o que as regras precisam saber
+/// de fora do estoque.
+#[derive(Debug, Clone)]
+pub struct PedidoVenda {
+    pub codigo: ProdutoId,
+    pub quantidade: Quantidade,
+    pub pagamento: Option<String>,
+    pub canal: Canal,
+    /// Senha de administrador conferida: libera o limite por venda e a
+    /// venda abaixo do custo.
+    pub liberado: bool,
+    pub cliente: Option<String>,
+    /// Promoção vigente e seu desconto (%).
+    pub promocao: Option<String>,
+    pub desconto: Option<f64>,
+    /// Custo do cadastro em reais, para quando nenhuma compra gravou o seu.
+    pub custo_cadastro: Option<f64>,
+    /// Unidades dos pedidos de compra abertos ainda não pré-vendidas.
+    pub a_caminho: i32,
+}
+
+/// Regra que a venda passou com ressalva: liberada pela senha ou só avisada.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Ressalva {
+    LimiteLiberado {
+        limite: i32,
+    },
+    AbaixoDoCusto {
+        preco: f64,
+        custo: f64,
+        liberada: bool,
+    },
+}
+
+/// O que a venda deixou para o chamador fazer fora do estoque.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VendaFeita {
+    pub ressalvas: Vec<Ressalva>,
+    /// Unidades que saem da próxima entrega (pré-venda), a agendar.
+    pub a_receber: i32,
+    pub abre_gaveta: bool,
+}
+
+/// Operações que mudam o saldo, sempre lançando o histórico junto, de modo
+/// que o estoque bata com a reprodução do histórico (kardex, arquivamento).
+pub struct Inventario<'a> {
+    relogios: &'a mut HashMap<String, Relogio>,
+    historico: &'a mut Vec<Historico>,
+    /// Próxima `Historico::sequencia` desta loja, achada no primeiro
+    /// lançamento.
+    proxima_sequencia: Option<u64>,
+}
+
+impl<'a> Inventario<'a> {
+    pub fn new(
+        relogios: &'a mut HashMap<String, Relogio>,
+        historico: &'a mut Vec<Historico>,
+    ) -> Self {
+        Inventario {
+            relogios,
+            historico,
+            proxima_sequencia: None,
+        }
+    }
+
+    /// Acrescenta a entrada e devolve sua posição, para o chamador completar
+    /// pagamento, promoção, câmbio etc.
+    fn lanca(
+        &mut self,
+        codigo: &ProdutoId,
+        operacao: &str,
+        quantidade: i32,
+        quando: NaiveDateTime,
+    ) -> Result<usize, ErroDominio> {
+        let timestamp = quando.format(FORMATO_TIMESTAMP).to_string();
+        if quando > Local::now().naive_local() {
+            return Err(ErroDominio::DataFutura(timestamp));
+        }
+        // Custo e preço da data, para valorizar a entrada depois
+        let (custo, preco) = self
+            .relogios
+            .get(codigo.as_str())
+            .map_or((None, None), |r| {
+                (r.custo.filter(|_| r.moeda.is_none()), r.preco)
+            });
+        let historico = &*self.historico;
+        let sequencia = *self.proxima_sequencia.get_or_insert_with(|| {
+            historico
+                .iter()
+                .filter(|h| h.origem.is_none())
+                .filter_map(|h| h.sequencia)
+                .max()
+                .map_or(1, |s| s + 1)
+        });
+        self.proxima_sequencia = Some(sequencia + 1);
+        self.historico.push(Historico {
+            codigo: codigo.to_string(),
+            quantidade,
+            operacao: operacao.into(),
+            timestamp,
+            custo,
+            preco,
+            sequencia: Some(sequencia),
+            ..Default::default()
+        });
+        Ok(self.historico.len() - 1)
+    }
+
+    /// Cadastra o código (ou redefine seu saldo) com `quantidade` unidades.
+    pub fn cadastra(
+        &mut self,
+        codigo: &ProdutoId,
+        quantidade: Quantidade,
+        quando: NaiveDateTime,
+    ) -> Result<&mut Historico, ErroDominio> {
+        let i = self.lanca(codigo, "CADASTRO", quantidade.valor(), quando)?;
+        self.relogios
+            .entry(codigo.to_string())
+            .or_insert_with(|| Relogio {
+                codigo: codigo.to_string(),
+                ..Default::default()
+            })
+            .quantidade = quantidade.valor();
+        Ok(&mut self.historico[i])
+    }
+
+    /// Entrada de mercadoria; códigos novos são criados na hora.
+    pub fn compra(
+        &mut self,
+        codigo: &ProdutoId,
+        quantidade: Quantidade,
+        quando: NaiveDateTime,
+    ) -> Result<&mut Historico, ErroDominio> {
+        if self
+            .relogios
+            .get(codigo.as_str())
+            .is_some_and(|r| !r.componentes.is_empty())
+        {
+            return Err(ErroDominio::Kit(codigo.to_string()));
+        }
+        let saldo = self
+            .relogios
+            .get(codigo.as_str())
+            .map_or(0, |r| r.quantidade)
+            .checked_add(quantidade.valor())
+            .ok_or_else(|| ErroDominio::Estouro(codigo.to_string()))?;
+        let i = self.lanca(codigo, "COMPRA", quantidade.valor(), quando)?;
+        self.relogios
+            .entry(codigo.to_string())
+            .or_insert_with(|| Relogio {
+                codigo: codigo.to_string(),
+                ..Default::default()
+            })
+            .quantidade = saldo;
+        Ok(&mut self.historico[i])
+    }
+
+    /// Saída por venda, só do que há em estoque. Um kit baixa cada
+    /// componente com uma entrada `BAIXA_KIT` ligada à `VENDA` devolvida.
+    pub fn vende(
+        &mut self,
+        codigo: &ProdutoId,
+        quantidade: Quantidade,
+        quando: NaiveDateTime,
+    ) -> Result<&mut Historico, ErroDominio> {
+        let r = self
+            .relogios
+            .get(codigo.as_str())
+            .ok_or_else(|| ErroDominio::NaoEncontrado(codigo.to_string()))?;
+        let disponivel = quantidade_disponivel(self.relogios, r);
+        if disponivel < quantidade.valor() {
+            return Err(ErroDominio::EstoqueInsuficiente {
+                codigo: codigo.to_string(),
+                disponivel,
+            });
+        }
+        // Confere as baixas antes de lançar, para não deixar a venda pela
+        // metade; o componente repetido no kit baixa uma vez só, somado
+        let componentes = por_componente(r)
+            .into_iter()
+            .map(|(componente, por_kit)| {
+                let baixa = por_kit
+                    .checked_mul(quantidade.valor())
+                    .ok_or_else(|| ErroDominio::Estouro(componente.to_string()))?;
+                let saldo = self.relogios.get(componente).map_or(0, |p| p.quantidade);
+                if saldo < baixa {
+                    return Err(ErroDominio::EstoqueInsuficiente {
+                        codigo: componente.to_string(),
+                        disponivel: saldo,
+                    });
+                }
+                Ok((componente.to_string(), baixa))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let i = self.lanca(codigo, "VENDA", quantidade.valor(), quando)?;
+        if componentes.is_empty() {
+            if let Some(r) = self.relogios.get_mut(codigo.as_str()) {
+                r.quantidade -= quantidade.valor();
+            }
+        }
+        for (componente, baixa) in componentes {
+            if let Some(p) = self.relogios.get_mut(&componente) {
+                p.quantidade -= baixa;
+            }
+            let j = self.lanca(&ProdutoId(Codigo(componente)), "BAIXA_KIT", baixa, quando)?;
+            self.historico[j].kit = Some(codigo.to_string());
+        }
+        Ok(&mut self.historico[i])
+    }
+
+    /// Corrige o saldo para `nova` (contagem física), lançando a diferença
+    /// com sinal como AJUSTE. Devolve a diferença.
+    pub fn ajusta(
+        &mut self,
+        codigo: &ProdutoId,
+        nova: Quantidade,
+        motivo: &str,
+        quando: NaiveDateTime,
+    ) -> Result<i32, ErroDominio> {
+        let r = self
+            .relogios
+            .get(codigo.as_str())
+            .ok_or_else(|| ErroDominio::NaoEncontrado(codigo.to_string()))?;
+        if !r.componentes.is_empty() {
+            return Err(ErroDominio::Kit(codigo.to_string()));
+        }
+        let diferenca = nova.valor() - r.quantidade;
+        if diferenca == 0 {
+            return Err(ErroDominio::SemAlteracao {
+                codigo: codigo.to_string(),
+                quantidade: nova.valor(),
+            });
+        }
+        let i = self.lanca(codigo, "AJUSTE", diferenca, quando)?;
+        self.historico[i].motivo = Some(motivo.to_string());
+        self.historico[i].usuario = Some(operador());
+        if let Some(r) = self.relogios.get_mut(codigo.as_str()) {
+            r.quantidade = nova.valor();
+        }
+        Ok(diferenca)
+    }
+
+    /// Troca o código do produto no cadastro, nos kits que o usam e em todo
+    /// o histórico. Devolve quantas entradas do histórico mudaram.
+    pub fn renomeia(&mut self, de: &ProdutoId, para: &ProdutoId) -> Result<usize, ErroDominio> {
+        if self.relogios.contains_key(para.as_str()) {
+            return Err(ErroDominio::CodigoEmUso(para.to_string()));
+        }
+        let mut r = self
+            .relogios
+            .remove(de.as_str())
+            .ok_or_else(|| ErroDominio::NaoEncontrado(de.to_string()))?;
+        r.codigo = para.to_string();
+        self.relogios.insert(para.to_string(), r);
+        for c in self
+            .relogios
+            .values_mut()
+            .flat_map(|kit| kit.componentes.iter_mut())
+            .filter(|c| c.codigo == de.as_str())
+        {
+            c.codigo = para.to_string();
+        }
+        let mut n = 0;
+        for h in self.historico.iter_mut() {
+            if h.codigo == de.as_str() {
+                h.codigo = para.to_string();
+                n += 1;
+            }
+            if h.kit.as_deref() == Some(de.as_str()) {
+                h.kit = Some(para.to_string());
+            }
+        }
+        Ok(n)
+    }
+
+    /// Regras da venda antes do estoque: o limite por venda do produto e a
+    /// venda abaixo do custo (médio ou da última compra, ver
+    /// `custo_referencia`), ambas liberáveis pela senha.
+    pub fn confere_venda(
+        &self,
+        pedido: &PedidoVenda,
+        config: &Config,
+    ) -> Result<Vec<Ressalva>, ErroDominio> {
+        let mut ressalvas = vec![];
+        let Some(r) = self.relogios.get(pedido.codigo.as_str()) else {
+            return Ok(ressalvas);
+        };
+        let quantidade = pedido.quantidade.valor();
+        if let Some(limite) = r.limite_por_venda.filter(|l| quantidade > *l) {
+            if !pedido.liberado {
+                return Err(ErroDominio::LimitePorVenda {
+                    codigo: r.codigo.clone(),
+                    quantidade,
+                    limite,
+                });
+            }
+            ressalvas.push(Ressalva::LimiteLiberado { limite });
+        }
+        let preco = r
+            .preco
+            .map(|p| p * (1.0 - pedido.desconto.unwrap_or(0.0) / 100.0));
+        let custo = custo_de_compra(self.historico, &r.codigo, config.custo_referencia)
+            .or(pedido.custo_cadastro);
+        let (Some(preco), Some(custo)) = (preco, custo) else {
+            return Ok(ressalvas);
+        };
+        if preco < custo {
+            match config.venda_abaixo_custo {
+                RegraMargem::Permitir => {}
+                RegraMargem::Bloquear if !pedido.liberado => {
+                    return Err(ErroDominio::AbaixoDoCusto {
+                        codigo: r.codigo.clone(),
+                        preco,
+                        custo,
+                    })
+                }
+                regra => ressalvas.push(Ressalva::AbaixoDoCusto {
+                    preco,
+                    custo,
+                    liberada: regra == RegraMargem::Bloquear,
+                }),
+            }
+        }
+        Ok(ressalvas)
+    }
+
+    /// Venda do balcão ou da API: confere as regras e vende o que há. Do
+    /// produto sob encomenda, o que falta fica para a próxima entrega
+    /// (`a_receber`) quando os pedidos de compra o cobrem; o saldo nunca
+    /// fica negativo.
+    pub fn registra_venda(
+        &mut self,
+        pedido: &PedidoVenda,
+        config: &Config,
+        quando: NaiveDateTime,
+    ) -> Result<VendaFeita, ErroDominio> {
+        let ressalvas = self.confere_venda(pedido, config)?;
+        let quantidade = pedido.quantidade.ate(config.quantidade_maxima)?.valor();
+        let r = self
+            .relogios
+            .get(pedido.codigo.as_str())
+            .ok_or_else(|| ErroDominio::NaoEncontrado(pedido.codigo.to_string()))?;
+        let a_receber = match quantidade - r.quantidade.max(0) {
+            falta
+                if r.situacao == Situacao::Encomenda
+                    && r.componentes.is_empty()
+                    && falta > 0
+                    && falta <= pedido.a_caminho =>
+            {
+                falta
+            }
+            _ => 0,
+        };
+        if a_receber == 0 && r.componentes.is_empty() && r.quantidade <= 0 {
+            return Err(ErroDominio::Esgotado(r.codigo.clone()));
+        }
+        if a_receber < quantidade {
+            let venda = self.vende(&pedido.codigo, Quantidade(quantidade - a_receber), quando)?;
+            venda.pagamento = pedido.pagamento.clone();
+            venda.cliente = pedido.cliente.clone();
+            venda.promocao = pedido.promocao.clone();
+            venda.desconto = pedido.desconto;
+        }
+        Ok(VendaFeita {
+            ressalvas,
+            a_receber,
+            abre_gaveta: pedido.canal == Canal::Balcao
+                && pedido.pagamento.as_deref() == Some("dinheiro"),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arquivo;
+    use chrono::Duration;
+
+    fn agora() -> NaiveDateTime {
+        Local::now().naive_local()
+    }
+
+    #[test]
+    fn tipos_recusam_valores_invalidos() {
+        assert_eq!(Codigo::novo("  "), Err(ErroDominio::CodigoVazio));
+        assert!(matches!(
+            Codigo::novo("R 001"),
+            Err(ErroDominio::CodigoComEspaco(_))
+        ));
+        assert_eq!(Codigo::novo(" R001 ").unwrap().as_str(), "R001");
+        assert_eq!(ProdutoId::novo(" "), Err(ErroDominio::CodigoVazio));
+        assert_eq!(
+            ProdutoId::from(Codigo::novo("R001").unwrap()),
+            ProdutoId::novo("R001").unwrap()
+        );
+        assert_eq!(
+            Quantidade::nova(-1),
+            Err(ErroDominio::QuantidadeNegativa(-1))
+        );
+        assert_eq!(Quantidade::nova(0).map(Quantidade::valor), Ok(0));
+        assert_eq!(Quantidade::movimento(0), Err(ErroDominio::MovimentoVazio));
+        assert_eq!(
+            Quantidade::nova(5001).unwrap().ate(5000),
+            Err(ErroDominio::AcimaDoLimite {
+                quantidade: 5001,
+                maximo: 5000
+            })
+        );
+        assert!(Quantidade::nova(i32::MAX).unwrap().ate(0).is_ok());
+        for valido in [
+            "4006381333931",
+            "96385074",
+            "036000291452",
+            "10012345678902",
+        ] {
+            assert_eq!(Ean::novo(valido).unwrap().as_str(), valido);
+        }
+        for invalido in ["4006381333932", "400638133393", "40063813339a1", ""] {
+            assert!(Ean::novo(invalido).is_err(), "{}", invalido);
+        }
+    }
+
+    #[test]
+    fn inventario_mantem_saldo_igual_ao_historico() {
+        let (mut relogios, mut historico) = (HashMap::new(), vec![]);
+        let mut inv = Inventario::new(&mut relogios, &mut historico);
+        let r1 = ProdutoId::novo("R1").unwrap();
+        let r2 = ProdutoId::novo("R2").unwrap();
+        let q = |n| Quantidade::nova(n).unwrap();
+        inv.cadastra(&r1, q(5), agora()).unwrap();
+        inv.compra(&r2, q(2), agora()).unwrap();
+        assert_eq!(
+            inv.vende(&r1, q(6), agora()).unwrap_err(),
+            ErroDominio::EstoqueInsuficiente {
+                codigo: "R1".into(),
+                disponivel: 5
+            }
+        );
+        inv.vende(&r1, q(2), agora()).unwrap().pagamento = Some("pix".into());
+        assert_eq!(inv.ajusta(&r2, q(1), "quebra", agora()), Ok(-1));
+        let amanha = agora() + Duration::days(1);
+        assert!(matches!(
+            inv.compra(&r1, q(1), amanha),
+            Err(ErroDominio::DataFutura(_))
+        ));
+        // Sem o limite por operação, compras grandes repetidas não estouram
+        assert_eq!(
+            inv.compra(&r1, q(i32::MAX), agora()).unwrap_err(),
+            ErroDominio::Estouro("R1".into())
+        );
+
+        // Kit de um R1 + um R2: só um pode ser montado
+        inv.relogios.insert(
+            "K".into(),
+            Relogio {
+                codigo: "K".into(),
+                componentes: vec![
+                    Componente {
+                        codigo: "R1".into(),
+                        quantidade: 1,
+                    },
+                    Componente {
+                        codigo: "R2".into(),
+                        quantidade: 1,
+                    },
+                ],
+                ..Default::default()
+            },
+        );
+        let kit = ProdutoId::novo("K").unwrap();
+        assert!(inv.vende(&kit, q(2), agora()).is_err());
+        assert_eq!(
+            inv.compra(&kit, q(1), agora()).unwrap_err(),
+            ErroDominio::Kit("K".into())
+        );
+        inv.vende(&kit, q(1), agora()).unwrap();
+        // R2 zerado: nada de saldo negativo
+        assert_eq!(
+            inv.vende(&r2, q(1), agora()).unwrap_err(),
+            ErroDominio::EstoqueInsuficiente {
+                codigo: "R2".into(),
+                disponivel: 0
+            }
+        );
+
+        // Componentes que não cobrem a baixa recusam a venda inteira
+        inv.relogios.insert(
+            "KG".into(),
+            Relogio {
+                codigo: "KG".into(),
+                componentes: vec![Componente {
+                    codigo: "R1".into(),
+                    quantidade: 1_000_000,
+                }],
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            inv.vende(&ProdutoId::novo("KG").unwrap(), q(5000), agora())
+                .unwrap_err(),
+            ErroDominio::EstoqueInsuficiente {
+                codigo: "KG".into(),
+                disponivel: 0
+            }
+        );
+
+        assert_eq!(historico.len(), 7);
+        assert_eq!(historico[2].pagamento.as_deref(), Some("pix"));
+        for r in arquivo::saldos(&historico, &relogios) {
+            assert_eq!(r.quantidade, relogios[&r.codigo].quantidade, "{}", r.codigo);
+        }
+        assert_eq!(
+            (relogios["R1"].quantidade, relogios["R2"].quantidade),
+            (2, 0)
+        );
+    }
+
+    #[test]
+    fn kit_com_componente_repetido_soma_a_baixa() {
+        let (mut relogios, mut historico) = (HashMap::new(), vec![]);
+        let um = Componente {
+            codigo: "R1".into(),
+            quantidade: 1,
+        };
+        relogios.insert(
+            "K".into(),
+            Relogio {
+                codigo: "K".into(),
+                componentes: vec![um.clone(), um],
+                ..Default::default()
+            },
+        );
+        relogios.insert(
+            "R1".into(),
+            Relogio::novo("R1", Quantidade::nova(3).unwrap()),
+        );
+        assert_eq!(quantidade_disponivel(&relogios, &relogios["K"]), 1);
+        let mut inv = Inventario::new(&mut relogios, &mut historico);
+        let kit = ProdutoId::novo("K").unwrap();
+        assert!(inv
+            .vende(&kit, Quantidade::nova(2).unwrap(), agora())
+            .is_err());
+        inv.vende(&kit, Quantidade::nova(1).unwrap(), agora())
+            .unwrap();
+        assert!(inv
+            .vende(&kit, Quantidade::nova(1).unwrap(), agora())
+            .is_err());
+        assert_eq!(relogios["R1"].quantidade, 1);
+        assert_eq!(
+            historico.iter().map(|h| h.quantidade).collect::<Vec<_>>(),
+            [1, 2]
+        );
+    }
+
+    #[test]
+    fn renomeia_cadastro_kits_e_historico() {
+        let (mut relogios, mut historico) = (HashMap::new(), vec![]);
+        relogios.insert(
+            "K".into(),
+            Relogio {
+                codigo: "K".into(),
+                componentes: vec![Componente {
+                    codigo: "R1".into(),
+                    quantidade: 2,
+                }],
+                ..Default::default()
+            },
+        );
+        let mut inv = Inventario::new(&mut relogios, &mut historico);
+        let c = |t| ProdutoId::novo(t).unwrap();
+        inv.compra(&c("R1"), Quantidade::nova(4).unwrap(), agora())
+            .unwrap();
+        inv.vende(&c("K"), Quantidade::nova(1).unwrap(), agora())
+            .unwrap();
+        assert_eq!(
+            inv.renomeia(&c("R1"), &c("K")),
+            Err(ErroDominio::CodigoEmUso("K".into()))
+        );
+        assert_eq!(inv.renomeia(&c("R1"), &c("R01")), Ok(2));
+        assert_eq!(inv.renomeia(&c("K"), &c("KIT")), Ok(1));
+        assert!(!relogios.contains_key("R1"));
+        assert_eq!(relogios["R01"].codigo, "R01");
+        assert_eq!(relogios["KIT"].componentes[0].codigo, "R01");
+        assert_eq!(historico[2].kit.as_deref(), Some("KIT"));
+        assert_eq!(relogios["R01"].quantidade, 2);
+    }
+
+    fn pedido(codigo: &str, quantidade: i32, canal: Canal) -> PedidoVenda {
+        PedidoVenda {
+            codigo: ProdutoId::novo(codigo).unwrap(),
+            quantidade: Quantidade::movimento(quantidade).unwrap(),
+            pagamento: Some("dinheiro".into()),
+            canal,
+            liberado: false,
+            cliente: None,
+            promocao: None,
+            desconto: None,
+            custo_cadastro: None,
+            a_caminho: 0,
+        }
+    }
+
+    #[test]
+    fn venda_confere_limite_margem_e_gaveta() {
+        let (mut relogios, mut historico) = (HashMap::new(), vec![]);
+        let mut r = Relogio::novo("R1", Quantidade::nova(10).unwrap());
+        r.preco = Some(100.0);
+        r.limite_por_venda = Some(2);
+        relogios.insert("R1".into(), r);
+        let mut config = Config {
+            venda_abaixo_custo: RegraMargem::Bloquear,
+            ..Config::default()
+        };
+        let mut inv = Inventario::new(&mut relogios, &mut historico);
+        let mut p = pedido("R1", 3, Canal::Balcao);
+        assert!(matches!(
+            inv.registra_venda(&p, &config, agora()),
+            Err(ErroDominio::LimitePorVenda { limite: 2, .. })
+        ));
+        p.liberado = true;
+        let feita = inv.registra_venda(&p, &config, agora()).unwrap();
+        assert_eq!(feita.ressalvas, [Ressalva::LimiteLiberado { limite: 2 }]);
+        assert!(feita.abre_gaveta);
+
+        // Custo do cadastro acima do preço; a promoção não conta a favor
+        let mut p = pedido("R1", 1, Canal::Api);
+        p.custo_cadastro = Some(90.0);
+        p.desconto = Some(20.0);
+        assert_eq!(
+            inv.registra_venda(&p, &config, agora()),
+            Err(ErroDominio::AbaixoDoCusto {
+                codigo: "R1".into(),
+                preco: 80.0,
+                custo: 90.0
+            })
+        );
+        config.venda_abaixo_custo = RegraMargem::Avisar;
+        let feita = inv.registra_venda(&p, &config, agora()).unwrap();
+        assert_eq!(
+            feita.ressalvas,
+            [Ressalva::AbaixoDoCusto {
+                preco: 80.0,
+                custo: 90.0,
+                liberada: false
+            }]
+        );
+        // Pela API a gaveta fica fechada
+        assert!(!feita.abre_gaveta);
+        assert_eq!(relogios["R1"].quantidade, 6);
+        assert_eq!(historico[1].desconto, Some(20.0));
+    }
+
+    #[test]
+    fn pre_venda_so_com_entrega_a_caminho() {
+        let (mut relogios, mut historico) = (HashMap::new(), vec![]);
+        let mut r = Relogio::novo("R1", Quantidade::nova(1).unwrap());
+        r.situacao = Situacao::Encomenda;
+        relogios.insert("R1".into(), r);
+        let config = Config::default();
+        let mut inv = Inventario::new(&mut relogios, &mut historico);
+        let mut p = pedido("R1", 3, Canal::Balcao);
+        p.a_caminho = 2;
+        let feita = inv.registra_venda(&p, &config, agora()).unwrap();
+        assert_eq!(feita.a_receber, 2);
+        p.quantidade = Quantidade::movimento(1).unwrap();
+        p.a_caminho = 0;
+        assert_eq!(
+            inv.registra_venda(&p, &config, agora()),
+            Err(ErroDominio::Esgotado("R1".into()))
+        );
+        assert_eq!(relogios["R1"].quantidade, 0);
+        assert_eq!(historico.len(), 1);
+    }
+}
@@ -0,0 +1,83 @@
+use crate::config::FormatoEtiqueta;
+use crate::numeros::FormatoNumero;
+use crate::persistencia::na_pasta_dados;
+use crate::Relogio;
+use std::fs;
+use std::io::{self, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+const DIRETORIO_ETIQUETAS: &str = "etiquetas";
+
+/// Remove caracteres que a impressora interpretaria como comandos.
+fn limpa(texto: &str) -> String {
+    texto
+        .chars()
+        .map(|c| if matches!(c, '^' | '~' | '"') { ' ' } else { c })
+        .collect()
+}
+
+/// Etiqueta com código, descrição, código de barras (Code 128) e preço.
+pub fn gera(r: &Relogio, formato: FormatoEtiqueta, numeros: FormatoNumero) -> String {
+    copias(r, 1, formato, numeros)
+}
+
+/// A mesma etiqueta repetida `n` vezes pela própria impressora.
+fn copias(r: &Relogio, n: i32, formato: FormatoEtiqueta, numeros: FormatoNumero) -> String {
+    let pq = if n > 1 {
+        format!("^PQ{}\n", n)
+    } else {
+        String::new()
+    };
+    let codigo = limpa(&r.codigo);
+    let descricao = limpa(&r.descricao);
+    let preco = r.preco.map(|p| numeros.moeda(p)).unwrap_or_default();
+    match formato {
+        FormatoEtiqueta::Zpl => format!(
+            "^XA\n^CF0,30\n^FO30,20^FD{codigo}^FS\n^CF0,22\n^FO30,55^FD{descricao}^FS\n\
+             ^BY2,2,60\n^FO30,85^BCN,60,Y,N,N^FD{codigo}^FS\n^CF0,34\n^FO30,180^FD{preco}^FS\n{pq}^XZ\n"
+        ),
+        FormatoEtiqueta::Epl => format!(
+            "N\nA30,20,0,3,1,1,N,\"{codigo}\"\nA30,50,0,2,1,1,N,\"{descricao}\"\n\
+             B30,80,0,1,2,4,60,B,\"{codigo}\"\nA30,175,0,4,1,1,N,\"{preco}\"\nP{n}\n"
+        ),
+    }
+}
+
+/// Lote pronto para a impressora com uma etiqueta por unidade de cada
+/// produto, na ordem dada.
+pub fn lote(itens: &[(&Relogio, i32)], formato: FormatoEtiqueta, numeros: FormatoNumero) -> String {
+    itens
+        .iter()
+        .filter(|(_, n)| *n > 0)
+        .map(|(r, n)| copias(r, *n, formato, numeros))
+        .collect()
+}
+
+/// Grava o lote em `etiquetas/<nome>.zpl` (ou `.epl`) e devolve o caminho.
+pub fn grava_lote(nome: &str, payload: &str, formato: FormatoEtiqueta) -> io::Result<String> {
+    let pasta = na_pasta_dados(DIRETORIO_ETIQUETAS);
+    fs::create_dir_all(&pasta)?;
+    let extensao = match formato {
+        FormatoEtiqueta::Zpl => "zpl",
+        FormatoEtiqueta::Epl => "epl",
+    };
+    let caminho = pasta.join(format!("{}.{}", nome, extensao));
+    fs::write(&caminho, payload)?;
+    Ok(caminho.display().to_string())
+}
+
+/// Envia o payload cru para a impressora (porta 9100 se não informada).
+pub fn envia(endereco: &str, payload: &str) -> io::Result<()> {
+    let endereco = if endereco.contains(':') {
+        endereco.to_string()
+    } else {
+        format!("{}:9100", endereco)
+    };
+    let addr = endereco
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "endereço inválido"))?;
+    let mut stream = TcpStream::connect_timeout(&addr, Duration::from_secs(3))?;
+    stream.write_all(payload.as_bytes())
+}
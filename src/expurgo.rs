@@ -0,0 +1,207 @@
+use crate::dominio::{self, FORMATO_TIMESTAMP};
+use crate::persistencia::Registros;
+use crate::{Historico, Relogio};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Confere o código com um padrão curinga (`*` qualquer trecho, `?` um
+/// caractere), sem diferenciar maiúsculas. Sem curinga, só o código exato.
+pub fn casa(padrao: &str, codigo: &str) -> bool {
+    fn casa_chars(p: &[char], c: &[char]) -> bool {
+        match p.split_first() {
+            None => c.is_empty(),
+            Some(('*', resto)) => (0..=c.len()).any(|i| casa_chars(resto, &c[i..])),
+            Some(('?', resto)) => !c.is_empty() && casa_chars(resto, &c[1..]),
+            Some((x, resto)) => c.first() == Some(x) && casa_chars(resto, &c[1..]),
+        }
+    }
+    let p: Vec<char> = padrao.to_uppercase().chars().collect();
+    let c: Vec<char> = codigo.to_uppercase().chars().collect();
+    casa_chars(&p, &c)
+}
+
+/// O que um expurgo apagaria.
+#[derive(Debug, Default, PartialEq)]
+pub struct Expurgo {
+    /// Códigos que saem (do cadastro e/ou só do histórico) e quantas
+    /// entradas do histórico cada um leva junto.
+    pub codigos: Vec<(String, usize)>,
+    /// Códigos que casam mas ficam por serem componentes de um kit que não
+    /// sai, com o kit.
+    pub em_kits: Vec<(String, String)>,
+}
+
+impl Expurgo {
+    pub fn entradas(&self) -> usize {
+        self.codigos.iter().map(|(_, n)| n).sum()
+    }
+}
+
+/// Levanta o que casa com o padrão, no cadastro e no histórico (entradas de
+/// códigos já excluídos também saem). Não mexe em nada.
+pub fn planeja(
+    padrao: &str,
+    relogios: &HashMap<String, Relogio>,
+    historico: &[Historico],
+) -> Expurgo {
+    planeja_por(|c| casa(padrao, c), relogios, historico)
+}
+
+/// O mesmo levantamento para um único código, sem curingas.
+pub fn do_codigo(
+    codigo: &str,
+    relogios: &HashMap<String, Relogio>,
+    historico: &[Historico],
+) -> Expurgo {
+    planeja_por(|c| c == codigo, relogios, historico)
+}
+
+fn planeja_por(
+    sai: impl Fn(&str) -> bool,
+    relogios: &HashMap<String, Relogio>,
+    historico: &[Historico],
+) -> Expurgo {
+    let mut codigos: BTreeMap<String, usize> = relogios
+        .keys()
+        .filter(|c| sai(c))
+        .map(|c| (c.clone(), 0))
+        .collect();
+    for h in historico.iter().filter(|h| sai(&h.codigo)) {
+        *codigos.entry(h.codigo.clone()).or_default() += 1;
+    }
+    let mut em_kits: Vec<(String, String)> = relogios
+        .values()
+        .filter(|kit| !codigos.contains_key(&kit.codigo))
+        .flat_map(|kit| {
+            kit.componentes
+                .iter()
+                .filter(|c| codigos.contains_key(&c.codigo))
+                .map(|c| (c.codigo.clone(), kit.codigo.clone()))
+        })
+        .collect();
+    em_kits.sort();
+    for (codigo, _) in &em_kits {
+        codigos.remove(codigo);
+    }
+    Expurgo {
+        codigos: codigos.into_iter().collect(),
+        em_kits,
+    }
+}
+
+/// Código excluído, com o saldo e o custo que tinha: o histórico dele sai
+/// junto, então é o que sobra para a conferência dos ajustes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Exclusao {
+    pub timestamp: String,
+    pub codigo: String,
+    #[serde(default)]
+    pub descricao: String,
+    pub quantidade: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub custo: Option<f64>,
+    pub usuario: String,
+    /// Entradas do histórico apagadas com o código.
+    pub entradas: usize,
+}
+
+/// Apaga os códigos do plano do cadastro, do histórico, dos favoritos, da
+/// lista de espera e das referências de fornecedor, registrando cada um nas
+/// exclusões. Não grava.
+pub fn aplica(
+    plano: &Expurgo,
+    relogios: &mut HashMap<String, Relogio>,
+    historico: &mut Vec<Historico>,
+    registros: &mut Registros,
+    quando: NaiveDateTime,
+) {
+    let timestamp = quando.format(FORMATO_TIMESTAMP).to_string();
+    for (codigo, entradas) in &plano.codigos {
+        let r = relogios.get(codigo);
+        registros.exclusoes.push(Exclusao {
+            timestamp: timestamp.clone(),
+            codigo: codigo.clone(),
+            descricao: r.map(|r| r.descricao.clone()).unwrap_or_default(),
+            quantidade: r.map_or(0, |r| r.quantidade()),
+            custo: r.and_then(|r| r.custo.filter(|_| r.moeda.is_none())),
+            usuario: dominio::operador(),
+            entradas: *entradas,
+        });
+    }
+    let sai = |codigo: &str| plano.codigos.iter().any(|(c, _)| c == codigo);
+    relogios.retain(|c, _| !sai(c));
+    historico.retain(|h| !sai(&h.codigo));
+    registros.favoritos.retain(|c| !sai(c));
+    registros.lista_espera.retain(|e| !sai(&e.codigo));
+    registros.referencias_fornecedor.retain(|r| !sai(&r.codigo));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dominio::Componente;
+    use crate::dominio::Quantidade;
+
+    #[test]
+    fn padrao_kits_e_historico_orfao() {
+        assert!(casa("teste*", "TESTE-01"));
+        assert!(casa("R0?9", "r019"));
+        assert!(!casa("R01", "R011"));
+        assert!(!casa("*x", "XA"));
+
+        let mut relogios = HashMap::new();
+        for codigo in ["TESTE1", "TESTE2", "R001", "KIT"] {
+            relogios.insert(codigo.to_string(), {
+                let mut r = Relogio::novo(codigo, Quantidade::nova(2).unwrap());
+                r.custo = Some(10.0);
+                r
+            });
+        }
+        relogios.get_mut("KIT").unwrap().componentes = vec![Componente {
+            codigo: "TESTE2".into(),
+            quantidade: 1,
+        }];
+        let entrada = |codigo: &str| Historico {
+            codigo: codigo.into(),
+            ..Default::default()
+        };
+        let mut historico = vec![
+            entrada("TESTE1"),
+            entrada("R001"),
+            entrada("TESTE1"),
+            entrada("TESTE9"),
+        ];
+        let plano = planeja("teste*", &relogios, &historico);
+        assert_eq!(
+            plano.codigos,
+            [("TESTE1".to_string(), 2), ("TESTE9".to_string(), 1)]
+        );
+        assert_eq!(plano.em_kits, [("TESTE2".to_string(), "KIT".to_string())]);
+        assert_eq!(plano.entradas(), 3);
+
+        let mut registros = Registros {
+            favoritos: vec!["TESTE1".into(), "R001".into()],
+            ..Default::default()
+        };
+        let quando =
+            NaiveDateTime::parse_from_str("2024-05-02 10:00:00", FORMATO_TIMESTAMP).unwrap();
+        aplica(
+            &plano,
+            &mut relogios,
+            &mut historico,
+            &mut registros,
+            quando,
+        );
+        assert_eq!(relogios.len(), 3);
+        assert_eq!(historico.len(), 1);
+        assert_eq!(registros.favoritos, ["R001"]);
+        let e = &registros.exclusoes;
+        assert_eq!(e.len(), 2);
+        assert_eq!(
+            (e[0].quantidade, e[0].custo, e[0].entradas),
+            (2, Some(10.0), 2)
+        );
+        assert_eq!((e[1].codigo.as_str(), e[1].quantidade), ("TESTE9", 0));
+    }
+}
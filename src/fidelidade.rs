@@ -0,0 +1,304 @@
+use crate::numeros::FormatoNumero;
+use crate::{credito, notas, Espera};
+use chrono::{Datelike, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Dias sem comprar a partir dos quais o cliente conta como inativo.
+pub const DIAS_INATIVO: i64 = 180;
+
+/// Cliente identificado nas notas de venda, com o que já comprou.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cliente {
+    pub nome: String,
+    pub documento: String,
+    /// Contato deixado na lista de espera, se houver.
+    pub contato: String,
+    pub compras: usize,
+    pub valor: f64,
+    pub primeira: String,
+    pub ultima: String,
+    /// Dias desde a última compra.
+    pub dias: i64,
+}
+
+/// Clientes das notas com destinatário, pelo CPF/CNPJ ou, sem ele, pelo
+/// nome. Os que compraram há mais tempo vêm primeiro.
+pub fn clientes(notas: &[notas::NotaFiscal], espera: &[Espera], hoje: NaiveDate) -> Vec<Cliente> {
+    let mut por_chave: HashMap<String, Cliente> = HashMap::new();
+    for n in notas.iter().filter(|n| !n.cliente_nome.trim().is_empty()) {
+        let chave = if n.cliente_documento.is_empty() {
+            credito::chave(&n.cliente_nome)
+        } else {
+            n.cliente_documento.clone()
+        };
+        let data = n.timestamp.get(..10).unwrap_or(&n.timestamp).to_string();
+        let c = por_chave.entry(chave).or_insert_with(|| Cliente {
+            nome: n.cliente_nome.trim().to_string(),
+            documento: n.cliente_documento.clone(),
+            contato: String::new(),
+            compras: 0,
+            valor: 0.0,
+            primeira: data.clone(),
+            ultima: data.clone(),
+            dias: 0,
+        });
+        c.compras += 1;
+        c.valor += n.total();
+        c.primeira = c.primeira.clone().min(data.clone());
+        if data > c.ultima {
+            c.ultima = data;
+            // O nome da nota mais recente vale
+            c.nome = n.cliente_nome.trim().to_string();
+        }
+    }
+    let mut lista: Vec<Cliente> = por_chave.into_values().collect();
+    for c in &mut lista {
+        c.dias =
+            NaiveDate::parse_from_str(&c.ultima, "%Y-%m-%d").map_or(0, |d| (hoje - d).num_days());
+        if let Some(e) = espera
+            .iter()
+            .rev()
+            .find(|e| credito::chave(&e.cliente) == credito::chave(&c.nome))
+        {
+            c.contato = e.contato.clone();
+        }
+    }
+    lista.sort_by(|a, b| b.dias.cmp(&a.dias).then_with(|| a.nome.cmp(&b.nome)));
+    lista
+}
+
+/// Recompra, valor médio por cliente (LTV) e recência.
+pub fn resumo(clientes: &[Cliente], inativo: i64, formato: FormatoNumero) -> String {
+    let total = clientes.len();
+    let recorrentes = clientes.iter().filter(|c| c.compras > 1).count();
+    let inativos = clientes.iter().filter(|c| c.dias >= inativo).count();
+    let valor: f64 = clientes.iter().map(|c| c.valor).sum();
+    let media = |v: f64| if total == 0 { 0.0 } else { v / total as f64 };
+    let mut out = format!("Clientes identificados{:>18}\n", total);
+    out.push_str(&format!(
+        "Compraram de novo{:>23}\n",
+        format!(
+            "{} ({}%)",
+            recorrentes,
+            formato.numero(100.0 * media(recorrentes as f64), 1)
+        )
+    ));
+    out.push_str(&format!(
+        "Valor médio por cliente{:>17}\n",
+        formato.moeda(media(valor))
+    ));
+    out.push_str(&format!(
+        "Dias desde a última compra{:>14}\n",
+        formato.numero(media(clientes.iter().map(|c| c.dias as f64).sum()), 0)
+    ));
+    out.push_str(&format!(
+        "Inativos (≥ {} dias){:>20}\n\n",
+        inativo, inativos
+    ));
+    out.push_str(&format!(
+        "{:<28}{:>8}{:>16}{:>12}{:>8}\n",
+        "Cliente", "compras", "valor", "última", "dias"
+    ));
+    for c in clientes {
+        out.push_str(&format!(
+            "{:<28}{:>8}{:>16}{:>12}{:>8}\n",
+            c.nome,
+            c.compras,
+            formato.moeda(c.valor),
+            c.ultima,
+            c.dias
+        ));
+    }
+    out
+}
+
+/// CSV dos clientes sem comprar há `inativo` dias ou mais, para a campanha
+/// de retorno.
+pub fn inativos_csv(clientes: &[Cliente], inativo: i64, formato: FormatoNumero) -> String {
+    let mut out = String::from("Cliente;CPF/CNPJ;Contato;Compras;Valor;Última compra;Dias\n");
+    for c in clientes.iter().filter(|c| c.dias >= inativo) {
+        out.push_str(&format!(
+            "{};{};{};{};{};{};{}\n",
+            c.nome.replace(';', ","),
+            c.documento,
+            c.contato.replace(';', ","),
+            c.compras,
+            formato.numero(c.valor, 2),
+            c.ultima,
+            c.dias
+        ));
+    }
+    out
+}
+
+/// Aniversário de um cliente, guardado só como dia e mês.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Aniversario {
+    pub cliente: String,
+    /// `MM-DD`.
+    pub dia: String,
+}
+
+/// Lê `DD/MM`, `DD/MM/AAAA` ou `AAAA-MM-DD` e devolve `MM-DD`.
+pub fn interpreta_aniversario(texto: &str) -> Option<String> {
+    let texto = texto.trim();
+    let data = NaiveDate::parse_from_str(texto, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(texto, "%d/%m/%Y"))
+        // Ano bissexto para aceitar 29/02
+        .or_else(|_| NaiveDate::parse_from_str(&format!("{}/2000", texto), "%d/%m/%Y"))
+        .ok()?;
+    Some(data.format("%m-%d").to_string())
+}
+
+/// Guarda o aniversário do cliente (pelo nome, sem caixa e espaços extras),
+/// trocando o anterior; `None` apaga.
+pub fn define_aniversario(lista: &mut Vec<Aniversario>, cliente: &str, dia: Option<String>) {
+    let chave = credito::chave(cliente);
+    lista.retain(|a| credito::chave(&a.cliente) != chave);
+    if let Some(dia) = dia {
+        lista.push(Aniversario {
+            cliente: cliente.trim().to_string(),
+            dia,
+        });
+    }
+}
+
+/// Próxima data do aniversário a partir de `hoje` (29/02 cai em 28/02 nos
+/// anos comuns).
+fn proxima_data(dia: &str, hoje: NaiveDate) -> Option<NaiveDate> {
+    let (mes, d) = dia.split_once('-')?;
+    let (mes, d): (u32, u32) = (mes.parse().ok()?, d.parse().ok()?);
+    let no_ano = |ano| {
+        NaiveDate::from_ymd_opt(ano, mes, d).or_else(|| NaiveDate::from_ymd_opt(ano, mes, d - 1))
+    };
+    let data = no_ano(hoje.year())?;
+    if data >= hoje {
+        Some(data)
+    } else {
+        no_ano(hoje.year() + 1)
+    }
+}
+
+/// Aniversários nos próximos `dias` dias dos clientes que já gastaram ao
+/// menos `minimo`, do mais próximo ao mais distante.
+pub fn proximos_aniversarios<'a>(
+    aniversarios: &[Aniversario],
+    clientes: &'a [Cliente],
+    hoje: NaiveDate,
+    dias: i64,
+    minimo: f64,
+) -> Vec<(NaiveDate, &'a Cliente)> {
+    let mut lista: Vec<(NaiveDate, &Cliente)> = aniversarios
+        .iter()
+        .filter_map(|a| {
+            let c = clientes
+                .iter()
+                .find(|c| credito::chave(&c.nome) == credito::chave(&a.cliente))
+                .filter(|c| c.valor >= minimo)?;
+            let data = proxima_data(&a.dia, hoje).filter(|d| (*d - hoje).num_days() <= dias)?;
+            Some((data, c))
+        })
+        .collect();
+    lista.sort_by(|a, b| a.0.cmp(&b.0).then_with(|| a.1.nome.cmp(&b.1.nome)));
+    lista
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nota(cliente: &str, documento: &str, data: &str, valor: f64) -> notas::NotaFiscal {
+        notas::NotaFiscal {
+            numero: 1,
+            timestamp: format!("{} 10:00:00", data),
+            cliente_nome: cliente.into(),
+            cliente_documento: documento.into(),
+            itens: vec![notas::ItemNota {
+                codigo: "R001".into(),
+                descricao: "Casio".into(),
+                quantidade: 1,
+                preco_unitario: valor,
+            }],
+            aliquota: 0.0,
+        }
+    }
+
+    #[test]
+    fn recompra_valor_e_inativos() {
+        let notas = vec![
+            nota("Ana Souza", "12345678909", "2024-01-10", 300.0),
+            nota("Ana S.", "12345678909", "2024-06-01", 200.0),
+            nota("bruno lima", "", "2023-11-01", 150.0),
+            nota("Bruno  Lima", "", "2023-12-01", 50.0),
+            nota("Carla", "", "2024-06-20", 90.0),
+            nota("", "", "2024-06-20", 999.0),
+        ];
+        let espera = vec![Espera {
+            codigo: "R002".into(),
+            cliente: "Bruno Lima".into(),
+            contato: "(11) 99999-0000".into(),
+            timestamp: "2023-12-01 10:00:00".into(),
+        }];
+        let hoje = NaiveDate::from_ymd_opt(2024, 7, 1).unwrap();
+        let lista = clientes(&notas, &espera, hoje);
+        assert_eq!(lista.len(), 3);
+        assert_eq!(
+            (lista[0].nome.as_str(), lista[0].compras, lista[0].dias),
+            ("Bruno  Lima", 2, 213)
+        );
+        assert_eq!((lista[1].nome.as_str(), lista[1].valor), ("Ana S.", 500.0));
+
+        let texto = resumo(&lista, DIAS_INATIVO, FormatoNumero::default());
+        assert!(texto.contains("2 (66,7%)"));
+        let csv = inativos_csv(&lista, DIAS_INATIVO, FormatoNumero::default());
+        assert_eq!(csv.lines().count(), 2);
+        assert!(csv.contains("Bruno  Lima;;(11) 99999-0000;2;200,00;2023-12-01;213"));
+    }
+
+    #[test]
+    fn aniversarios_proximos_de_quem_gastou() {
+        assert_eq!(interpreta_aniversario("29/02").as_deref(), Some("02-29"));
+        assert_eq!(
+            interpreta_aniversario("1985-07-03").as_deref(),
+            Some("07-03")
+        );
+        assert_eq!(interpreta_aniversario("31/04"), None);
+
+        let notas = vec![
+            nota("Ana Souza", "", "2024-01-10", 3000.0),
+            nota("Bruno Lima", "", "2024-01-10", 200.0),
+            nota("Carla", "", "2024-01-10", 1500.0),
+        ];
+        let hoje = NaiveDate::from_ymd_opt(2025, 2, 25).unwrap();
+        let lista = clientes(&notas, &[], hoje);
+        let mut aniversarios = vec![];
+        for (cliente, dia) in [
+            ("ana souza", "29/02"),
+            ("Bruno Lima", "26/02"),
+            ("Carla", "24/02"),
+        ] {
+            define_aniversario(&mut aniversarios, cliente, interpreta_aniversario(dia));
+        }
+        define_aniversario(
+            &mut aniversarios,
+            "ANA SOUZA",
+            interpreta_aniversario("27/02"),
+        );
+        assert_eq!(aniversarios.len(), 3);
+
+        let proximos = proximos_aniversarios(&aniversarios, &lista, hoje, 7, 1000.0);
+        assert_eq!(proximos.len(), 1);
+        assert_eq!(proximos[0].1.nome, "Ana Souza");
+        assert_eq!(proximos[0].0, NaiveDate::from_ymd_opt(2025, 2, 27).unwrap());
+        // Carla fez ontem: só no ano que vem
+        assert_eq!(
+            proximos_aniversarios(&aniversarios, &lista, hoje, 365, 1000.0)[1].0,
+            NaiveDate::from_ymd_opt(2026, 2, 24).unwrap()
+        );
+        assert_eq!(
+            proxima_data("02-29", hoje),
+            NaiveDate::from_ymd_opt(2025, 2, 28)
+        );
+    }
+}
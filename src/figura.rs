@@ -0,0 +1,460 @@
+use crate::numeros::FormatoNumero;
+use std::io;
+
+/// Um quadro do gráfico da tela: título e valor por rótulo, em barras ou
+/// em linha.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Painel {
+    pub titulo: String,
+    pub pontos: Vec<(String, f64)>,
+    pub linha: bool,
+}
+
+/// Dados dos quadros em CSV (`;`), um valor por linha.
+pub fn csv(paineis: &[Painel], formato: FormatoNumero) -> String {
+    let mut out = String::from("Gráfico;Rótulo;Valor\n");
+    for p in paineis {
+        for (rotulo, valor) in &p.pontos {
+            out.push_str(&format!(
+                "{};{};{}\n",
+                p.titulo.replace(';', ","),
+                rotulo.replace(';', ","),
+                formato.numero(*valor, 2)
+            ));
+        }
+    }
+    out
+}
+
+const LARGURA: u32 = 960;
+const ALTURA_PAINEL: u32 = 320;
+const MARGEM_ESQUERDA: f64 = 110.0;
+const MARGEM_DIREITA: f64 = 30.0;
+const MARGEM_TOPO: f64 = 46.0;
+const MARGEM_BASE: f64 = 40.0;
+/// Largura de um caractere da fonte do PNG (5 px + 1 de espaço, em dobro).
+const LARGURA_LETRA: f64 = 12.0;
+
+const FUNDO: usize = 0;
+const TEXTO: usize = 1;
+const EIXO: usize = 2;
+const GRADE: usize = 3;
+/// Branco, preto, cinza, cinza claro e as cores das séries.
+const PALETA: [[u8; 3]; 8] = [
+    [255, 255, 255],
+    [33, 33, 33],
+    [120, 120, 120],
+    [225, 225, 225],
+    [31, 119, 180],
+    [255, 127, 14],
+    [44, 160, 44],
+    [214, 39, 40],
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Ancora {
+    Inicio,
+    Meio,
+    Fim,
+}
+
+/// Desenho independente do formato de saída, em pixels.
+#[derive(Debug, Clone, PartialEq)]
+enum Forma {
+    Retangulo {
+        x: f64,
+        y: f64,
+        largura: f64,
+        altura: f64,
+        cor: usize,
+    },
+    Linha {
+        pontos: Vec<(f64, f64)>,
+        cor: usize,
+    },
+    Texto {
+        x: f64,
+        y: f64,
+        texto: String,
+        ancora: Ancora,
+        cor: usize,
+    },
+}
+
+/// Empilha os quadros de cima para baixo, com eixos, grade e rótulos.
+fn desenha(titulo: &str, paineis: &[Painel], formato: FormatoNumero) -> (u32, u32, Vec<Forma>) {
+    let altura = 40 + ALTURA_PAINEL * paineis.len().max(1) as u32;
+    let mut formas = vec![Forma::Texto {
+        x: LARGURA as f64 / 2.0,
+        y: 26.0,
+        texto: titulo.to_string(),
+        ancora: Ancora::Meio,
+        cor: TEXTO,
+    }];
+    for (i, p) in paineis.iter().enumerate() {
+        let topo = 40.0 + (i as u32 * ALTURA_PAINEL) as f64;
+        let (x0, x1) = (MARGEM_ESQUERDA, LARGURA as f64 - MARGEM_DIREITA);
+        let (y0, y1) = (
+            topo + MARGEM_TOPO,
+            topo + ALTURA_PAINEL as f64 - MARGEM_BASE,
+        );
+        let cor = 4 + i % 4;
+        formas.push(Forma::Texto {
+            x: x0,
+            y: topo + 24.0,
+            texto: p.titulo.clone(),
+            ancora: Ancora::Inicio,
+            cor: TEXTO,
+        });
+        let maximo = p.pontos.iter().map(|(_, v)| *v).fold(0.0, f64::max);
+        let maximo = if maximo > 0.0 { maximo * 1.1 } else { 1.0 };
+        let y_de = |v: f64| y1 - (v / maximo) * (y1 - y0);
+        for fracao in [0.0, 0.5, 1.0] {
+            let y = y_de(maximo * fracao);
+            formas.push(Forma::Linha {
+                pontos: vec![(x0, y), (x1, y)],
+                cor: GRADE,
+            });
+            formas.push(Forma::Texto {
+                x: x0 - 8.0,
+                y: y + 5.0,
+                texto: formato.numero(maximo * fracao, 0),
+                ancora: Ancora::Fim,
+                cor: EIXO,
+            });
+        }
+        formas.push(Forma::Linha {
+            pontos: vec![(x0, y0), (x0, y1), (x1, y1)],
+            cor: EIXO,
+        });
+        let n = p.pontos.len().max(1);
+        let vaga = (x1 - x0) / n as f64;
+        // Pula rótulos do eixo x quando não cabem lado a lado
+        let maior = p
+            .pontos
+            .iter()
+            .map(|(r, _)| r.chars().count())
+            .max()
+            .unwrap_or(1);
+        let passo = ((maior as f64 + 1.0) * LARGURA_LETRA / vaga)
+            .ceil()
+            .max(1.0) as usize;
+        let mut linha = vec![];
+        for (j, (rotulo, valor)) in p.pontos.iter().enumerate() {
+            let centro = x0 + vaga * (j as f64 + 0.5);
+            if p.linha {
+                linha.push((centro, y_de(*valor)));
+            } else {
+                let largura = (vaga * 0.7).max(1.0);
+                formas.push(Forma::Retangulo {
+                    x: centro - largura / 2.0,
+                    y: y_de(*valor),
+                    largura,
+                    altura: y1 - y_de(*valor),
+                    cor,
+                });
+            }
+            if j % passo == 0 {
+                formas.push(Forma::Texto {
+                    x: centro,
+                    y: y1 + 22.0,
+                    texto: rotulo.clone(),
+                    ancora: Ancora::Meio,
+                    cor: TEXTO,
+                });
+            }
+        }
+        if p.linha {
+            formas.push(Forma::Linha { pontos: linha, cor });
+        }
+    }
+    (LARGURA, altura, formas)
+}
+
+fn escapa_xml(texto: &str) -> String {
+    texto
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn cor_svg(cor: usize) -> String {
+    let [r, g, b] = PALETA[cor];
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// Gráfico vetorial, para slides e documentos.
+pub fn svg(titulo: &str, paineis: &[Painel], formato: FormatoNumero) -> String {
+    let (largura, altura, formas) = desenha(titulo, paineis, formato);
+    let mut out = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\" font-family=\"Helvetica, Arial, sans-serif\" font-size=\"14\">\n<rect width=\"100%\" height=\"100%\" fill=\"{2}\"/>\n",
+        largura,
+        altura,
+        cor_svg(FUNDO)
+    );
+    for forma in formas {
+        match forma {
+            Forma::Retangulo {
+                x,
+                y,
+                largura,
+                altura,
+                cor,
+            } => out.push_str(&format!(
+                "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\"/>\n",
+                x,
+                y,
+                largura,
+                altura,
+                cor_svg(cor)
+            )),
+            Forma::Linha { pontos, cor } => {
+                let pontos: Vec<String> = pontos
+                    .iter()
+                    .map(|(x, y)| format!("{:.1},{:.1}", x, y))
+                    .collect();
+                out.push_str(&format!(
+                    "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"2\"/>\n",
+                    pontos.join(" "),
+                    cor_svg(cor)
+                ));
+            }
+            Forma::Texto {
+                x,
+                y,
+                texto,
+                ancora,
+                cor,
+            } => {
+                let ancora = match ancora {
+                    Ancora::Inicio => "start",
+                    Ancora::Meio => "middle",
+                    Ancora::Fim => "end",
+                };
+                out.push_str(&format!(
+                    "<text x=\"{:.1}\" y=\"{:.1}\" text-anchor=\"{}\" fill=\"{}\">{}</text>\n",
+                    x,
+                    y,
+                    ancora,
+                    cor_svg(cor),
+                    escapa_xml(&texto)
+                ));
+            }
+        }
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+/// Fonte 5x7 (uma linha por byte, bit 4 à esquerda) para os rótulos do
+/// PNG. Acentos e minúsculas caem na maiúscula sem acento.
+const FONTE: &[(char, [u8; 7])] = &[
+    (' ', [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+    ('0', [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E]),
+    ('1', [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E]),
+    ('2', [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F]),
+    ('3', [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E]),
+    ('4', [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02]),
+    ('5', [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E]),
+    ('6', [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E]),
+    ('7', [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08]),
+    ('8', [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E]),
+    ('9', [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C]),
+    ('A', [0x0E, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11]),
+    ('B', [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E]),
+    ('C', [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E]),
+    ('D', [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C]),
+    ('E', [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F]),
+    ('F', [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10]),
+    ('G', [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F]),
+    ('H', [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11]),
+    ('I', [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E]),
+    ('J', [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C]),
+    ('K', [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11]),
+    ('L', [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F]),
+    ('M', [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11]),
+    ('N', [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11]),
+    ('O', [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E]),
+    ('P', [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10]),
+    ('Q', [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D]),
+    ('R', [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11]),
+    ('S', [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E]),
+    ('T', [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04]),
+    ('U', [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E]),
+    ('V', [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04]),
+    ('W', [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A]),
+    ('X', [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11]),
+    ('Y', [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04]),
+    ('Z', [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F]),
+    ('.', [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C]),
+    (',', [0x00, 0x00, 0x00, 0x00, 0x0C, 0x04, 0x08]),
+    (':', [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00]),
+    ('-', [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00]),
+    ('+', [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00]),
+    ('/', [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00]),
+    ('%', [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03]),
+    ('$', [0x04, 0x0F, 0x14, 0x0E, 0x05, 0x1E, 0x04]),
+    ('(', [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02]),
+    (')', [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08]),
+    ('?', [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04]),
+];
+
+fn glifo(c: char) -> [u8; 7] {
+    let c = match c.to_uppercase().next().unwrap_or(c) {
+        'Á' | 'À' | 'Â' | 'Ã' | 'Ä' => 'A',
+        'É' | 'È' | 'Ê' => 'E',
+        'Í' | 'Î' => 'I',
+        'Ó' | 'Ô' | 'Õ' | 'Ö' => 'O',
+        'Ú' | 'Ü' => 'U',
+        'Ç' => 'C',
+        '—' | '–' => '-',
+        c => c,
+    };
+    FONTE
+        .iter()
+        .find(|(f, _)| *f == c)
+        .or_else(|| FONTE.iter().find(|(f, _)| *f == '?'))
+        .map(|(_, g)| *g)
+        .unwrap_or_default()
+}
+
+/// Imagem em memória com um índice da paleta por pixel.
+struct Tela {
+    largura: u32,
+    altura: u32,
+    pixels: Vec<u8>,
+}
+
+impl Tela {
+    fn ponto(&mut self, x: i64, y: i64, cor: usize) {
+        if (0..self.largura as i64).contains(&x) && (0..self.altura as i64).contains(&y) {
+            self.pixels[(y as u32 * self.largura + x as u32) as usize] = cor as u8;
+        }
+    }
+
+    fn retangulo(&mut self, x: f64, y: f64, largura: f64, altura: f64, cor: usize) {
+        for py in y.round() as i64..(y + altura).round() as i64 {
+            for px in x.round() as i64..(x + largura).round() as i64 {
+                self.ponto(px, py, cor);
+            }
+        }
+    }
+
+    /// Segmento com 2 px de espessura.
+    fn segmento(&mut self, (x0, y0): (f64, f64), (x1, y1): (f64, f64), cor: usize) {
+        let passos = (x1 - x0).abs().max((y1 - y0).abs()).ceil().max(1.0) as i64;
+        for i in 0..=passos {
+            let t = i as f64 / passos as f64;
+            let (x, y) = (x0 + (x1 - x0) * t, y0 + (y1 - y0) * t);
+            self.retangulo(x - 1.0, y - 1.0, 2.0, 2.0, cor);
+        }
+    }
+
+    /// Texto em dobro, com `y` na linha de base como no SVG.
+    fn texto(&mut self, x: f64, y: f64, texto: &str, ancora: Ancora, cor: usize) {
+        let largura = texto.chars().count() as f64 * LARGURA_LETRA;
+        let mut x = match ancora {
+            Ancora::Inicio => x,
+            Ancora::Meio => x - largura / 2.0,
+            Ancora::Fim => x - largura,
+        };
+        let topo = y - 14.0;
+        for c in texto.chars() {
+            for (linha, bits) in glifo(c).iter().enumerate() {
+                for coluna in 0..5 {
+                    if bits & (0x10 >> coluna) != 0 {
+                        let px = x + coluna as f64 * 2.0;
+                        let py = topo + linha as f64 * 2.0;
+                        self.retangulo(px, py, 2.0, 2.0, cor);
+                    }
+                }
+            }
+            x += LARGURA_LETRA;
+        }
+    }
+}
+
+/// O mesmo gráfico em PNG (cores indexadas), para quem não abre SVG.
+pub fn png(titulo: &str, paineis: &[Painel], formato: FormatoNumero) -> io::Result<Vec<u8>> {
+    let (largura, altura, formas) = desenha(titulo, paineis, formato);
+    let mut tela = Tela {
+        largura,
+        altura,
+        pixels: vec![FUNDO as u8; (largura * altura) as usize],
+    };
+    for forma in formas {
+        match forma {
+            Forma::Retangulo {
+                x,
+                y,
+                largura,
+                altura,
+                cor,
+            } => tela.retangulo(x, y, largura, altura, cor),
+            Forma::Linha { pontos, cor } => {
+                for par in pontos.windows(2) {
+                    tela.segmento(par[0], par[1], cor);
+                }
+            }
+            Forma::Texto {
+                x,
+                y,
+                texto,
+                ancora,
+                cor,
+            } => tela.texto(x, y, &texto, ancora, cor),
+        }
+    }
+
+    let mut png = vec![];
+    let mut codificador = png::Encoder::new(&mut png, largura, altura);
+    codificador.set_color(png::ColorType::Indexed);
+    codificador.set_depth(png::BitDepth::Eight);
+    codificador.set_palette(PALETA.concat());
+    let mut escritor = codificador.write_header()?;
+    escritor.write_image_data(&tela.pixels)?;
+    escritor.finish()?;
+    Ok(png)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_svg_e_png_do_mesmo_grafico() {
+        let paineis = vec![
+            Painel {
+                titulo: "Vendas por categoria".into(),
+                pontos: vec![("Mergulho".into(), 1500.0), ("Social".into(), 749.5)],
+                linha: false,
+            },
+            Painel {
+                titulo: "Valor do estoque".into(),
+                pontos: vec![("01/25".into(), 10.0), ("02/25".into(), 12.0)],
+                linha: true,
+            },
+        ];
+        let formato = FormatoNumero::default();
+        let csv = csv(&paineis, formato);
+        assert!(csv.contains("Vendas por categoria;Social;749,50\n"));
+        assert_eq!(csv.lines().count(), 5);
+
+        let svg = svg("Relógios & cia", &paineis, formato);
+        assert!(svg.contains("Relógios &amp; cia"));
+        assert_eq!(svg.matches("<rect").count(), 3);
+        assert!(svg.contains("1.650"));
+
+        let png = png("Vendas", &paineis, formato).unwrap();
+        let mut leitor = png::Decoder::new(std::io::Cursor::new(png))
+            .read_info()
+            .unwrap();
+        let info = leitor.info();
+        assert_eq!((info.width, info.height), (960, 680));
+        assert_eq!(info.color_type, png::ColorType::Indexed);
+        let mut pixels = vec![0; leitor.output_buffer_size().unwrap()];
+        leitor.next_frame(&mut pixels).unwrap();
+        assert_eq!(pixels[0], FUNDO as u8);
+    }
+}
@@ -0,0 +1,88 @@
+use crate::numeros::FormatoNumero;
+use crate::{contabil, credito, despesas, Historico, Registros, Relogio};
+use std::collections::{BTreeMap, HashMap};
+
+fn no_periodo(timestamp: &str, inicio: &str, fim: &str) -> bool {
+    let data = timestamp.get(..10).unwrap_or("");
+    data >= inicio && data <= fim
+}
+
+/// Relatório financeiro do período `[inicio, fim]`: vendas por forma de
+/// pagamento, compras, descontos, despesas operacionais e a movimentação de
+/// créditos da loja.
+pub fn relatorio(
+    historico: &[Historico],
+    relogios: &HashMap<String, Relogio>,
+    registros: &Registros,
+    inicio: &str,
+    fim: &str,
+    formato: FormatoNumero,
+) -> String {
+    let linha = |out: &mut String, rotulo: &str, valor: f64| {
+        out.push_str(&format!("{:<24}{:>16}\n", rotulo, formato.moeda(valor)));
+    };
+    let mut vendas: BTreeMap<String, f64> = BTreeMap::new();
+    let (mut compras, mut descontos) = (0.0, 0.0);
+    for h in historico
+        .iter()
+        .filter(|h| no_periodo(&h.timestamp, inicio, fim))
+    {
+        let r = relogios.get(&h.codigo);
+        let Some(valor) = contabil::valor(h, r) else {
+            continue;
+        };
+        if h.operacao == "VENDA" {
+            let pagamento = h.pagamento.clone().unwrap_or_else(|| "dinheiro".into());
+            *vendas.entry(pagamento).or_insert(0.0) += valor;
+            descontos += h.preco_tabela(r) * h.quantidade as f64 * (1.0 - h.fator_preco());
+        } else {
+            compras -= valor;
+        }
+    }
+    let creditos: Vec<&credito::MovimentoCredito> = registros
+        .creditos
+        .iter()
+        .filter(|m| no_periodo(&m.timestamp, inicio, fim))
+        .collect();
+    let emitidos: f64 = creditos
+        .iter()
+        .filter(|m| m.valor > 0.0)
+        .map(|m| m.valor)
+        .sum();
+    let resgatados: f64 = -creditos
+        .iter()
+        .filter(|m| m.valor < 0.0)
+        .map(|m| m.valor)
+        .sum::<f64>();
+
+    let receita: f64 = vendas.values().sum();
+    let mut out = format!("Relatório financeiro de {} a {}\n\nVendas\n", inicio, fim);
+    for (pagamento, valor) in &vendas {
+        linha(&mut out, &format!("  {}", pagamento), *valor);
+    }
+    linha(&mut out, "  total", receita);
+    linha(&mut out, "Descontos concedidos", descontos);
+    linha(&mut out, "Compras (custo)", compras);
+    linha(&mut out, "Resultado bruto", receita - compras);
+    let despesas = despesas::por_categoria(&registros.despesas, inicio, fim);
+    out.push_str("\nDespesas\n");
+    for (categoria, valor) in &despesas {
+        linha(&mut out, &format!("  {}", categoria), *valor);
+    }
+    let total_despesas: f64 = despesas.values().sum();
+    linha(&mut out, "  total", total_despesas);
+    linha(
+        &mut out,
+        "Resultado líquido",
+        receita - compras - total_despesas,
+    );
+    out.push('\n');
+    linha(&mut out, "Créditos emitidos", emitidos);
+    linha(&mut out, "Créditos resgatados", resgatados);
+    linha(
+        &mut out,
+        "Créditos em aberto",
+        credito::passivo(&registros.creditos),
+    );
+    out
+}
@@ -0,0 +1,240 @@
+use crate::{contabil, credito, Historico, Relogio};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Fornecedor cadastrado, identificado pelo nome (sem caixa e espaços
+/// extras, como os clientes).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Fornecedor {
+    pub nome: String,
+    /// Telefone, e-mail ou o vendedor que atende a loja.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub contato: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub observacoes: String,
+}
+
+/// Lê `nome;contato;observações`; só o nome é obrigatório.
+pub fn interpreta(texto: &str) -> Result<Fornecedor, String> {
+    let mut campos = texto.splitn(3, ';').map(str::trim);
+    let nome = campos.next().unwrap_or_default();
+    if nome.is_empty() {
+        return Err("Formato: nome;contato;observações (só o nome é obrigatório)".into());
+    }
+    Ok(Fornecedor {
+        nome: nome.split_whitespace().collect::<Vec<_>>().join(" "),
+        contato: campos.next().unwrap_or_default().to_string(),
+        observacoes: campos.next().unwrap_or_default().to_string(),
+    })
+}
+
+/// Inclui o fornecedor ou atualiza o de mesmo nome, mantendo a ordem
+/// alfabética. Devolve `true` quando é novo.
+pub fn cadastra(fornecedores: &mut Vec<Fornecedor>, novo: Fornecedor) -> bool {
+    let antes = fornecedores.len();
+    fornecedores.retain(|f| credito::chave(&f.nome) != credito::chave(&novo.nome));
+    let eh_novo = fornecedores.len() == antes;
+    fornecedores.push(novo);
+    fornecedores.sort_by_key(|f| credito::chave(&f.nome));
+    eh_novo
+}
+
+/// Fornecedor digitado: o nome inteiro ou o começo de um só nome
+/// (`seiko` acha "Seiko do Brasil").
+pub fn acha<'a>(fornecedores: &'a [Fornecedor], termo: &str) -> Result<&'a Fornecedor, String> {
+    let termo = credito::chave(termo);
+    if let Some(f) = fornecedores
+        .iter()
+        .find(|f| credito::chave(&f.nome) == termo)
+    {
+        return Ok(f);
+    }
+    match fornecedores
+        .iter()
+        .filter(|f| credito::chave(&f.nome).starts_with(&termo))
+        .collect::<Vec<_>>()
+        .as_slice()
+    {
+        [f] => Ok(f),
+        [] => Err(format!("Fornecedor '{}' não cadastrado.", termo)),
+        varios => Err(format!(
+            "'{}' serve para mais de um fornecedor: {}.",
+            termo,
+            varios
+                .iter()
+                .map(|f| f.nome.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+/// Compras lançadas com o fornecedor, na ordem do histórico.
+pub fn compras<'a>(historico: &'a [Historico], nome: &str) -> Vec<&'a Historico> {
+    let chave = credito::chave(nome);
+    historico
+        .iter()
+        .filter(|h| h.operacao == "COMPRA")
+        .filter(|h| {
+            h.fornecedor
+                .as_deref()
+                .is_some_and(|f| credito::chave(f) == chave)
+        })
+        .collect()
+}
+
+/// Resumo das compras: lotes, unidades, valor pago e data da última
+/// (AAAA-MM-DD).
+pub fn resumo(
+    historico: &[Historico],
+    relogios: &HashMap<String, Relogio>,
+    nome: &str,
+) -> (usize, i32, f64, Option<String>) {
+    let compras = compras(historico, nome);
+    let unidades = compras.iter().map(|h| h.quantidade).sum();
+    let total = compras
+        .iter()
+        .filter_map(|h| contabil::valor(h, relogios.get(&h.codigo)))
+        .map(|v| -v)
+        .sum();
+    let ultima = compras
+        .iter()
+        .map(|h| h.timestamp.get(..10).unwrap_or(&h.timestamp))
+        .max()
+        .map(str::to_string);
+    (compras.len(), unidades, total, ultima)
+}
+
+/// Código que um fornecedor usa para um produto, ligado ao código interno.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Referencia {
+    pub fornecedor: String,
+    pub codigo_fornecedor: String,
+    pub codigo: String,
+}
+
+/// Inclui a referência, trocando a que já houver para o mesmo fornecedor e
+/// código do fornecedor.
+pub fn define(referencias: &mut Vec<Referencia>, nova: Referencia) {
+    referencias.retain(|r| {
+        !(r.fornecedor.eq_ignore_ascii_case(&nova.fornecedor)
+            && r.codigo_fornecedor == nova.codigo_fornecedor)
+    });
+    referencias.push(nova);
+    referencias.sort_by(|a, b| {
+        a.fornecedor
+            .to_lowercase()
+            .cmp(&b.fornecedor.to_lowercase())
+            .then_with(|| a.codigo_fornecedor.cmp(&b.codigo_fornecedor))
+    });
+}
+
+/// Código interno para o código do fornecedor. `origem` é o que identifica o
+/// fornecedor no arquivo recebido (nome do arquivo, emitente da nota): vale a
+/// referência de um fornecedor cujo nome aparece nela. Sem nenhuma, vale o
+/// código usado por todos os fornecedores que têm esse código.
+pub fn resolve<'a>(
+    referencias: &'a [Referencia],
+    origem: &str,
+    codigo_fornecedor: &str,
+) -> Option<&'a str> {
+    let origem = origem.to_lowercase();
+    let mesmas: Vec<&Referencia> = referencias
+        .iter()
+        .filter(|r| r.codigo_fornecedor == codigo_fornecedor)
+        .collect();
+    if let Some(r) = mesmas
+        .iter()
+        .find(|r| origem.contains(&r.fornecedor.to_lowercase()))
+    {
+        return Some(&r.codigo);
+    }
+    let primeira = mesmas.first()?;
+    mesmas
+        .iter()
+        .all(|r| r.codigo == primeira.codigo)
+        .then_some(primeira.codigo.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn referencia(fornecedor: &str, codigo_fornecedor: &str, codigo: &str) -> Referencia {
+        Referencia {
+            fornecedor: fornecedor.into(),
+            codigo_fornecedor: codigo_fornecedor.into(),
+            codigo: codigo.into(),
+        }
+    }
+
+    #[test]
+    fn cadastra_acha_pelo_comeco_e_soma_as_compras() {
+        let mut fornecedores = vec![];
+        assert!(cadastra(
+            &mut fornecedores,
+            interpreta("Seiko  do Brasil;(11) 3333-0000;entrega às terças").unwrap()
+        ));
+        assert!(cadastra(&mut fornecedores, interpreta("Casio").unwrap()));
+        assert!(cadastra(
+            &mut fornecedores,
+            interpreta("Casa das Pulseiras").unwrap()
+        ));
+        assert!(!cadastra(
+            &mut fornecedores,
+            interpreta("Seiko do Brasil;vendas@seiko.com").unwrap()
+        ));
+        assert!(interpreta(";x").is_err());
+        assert_eq!(fornecedores.len(), 3);
+        assert_eq!(fornecedores[2].contato, "vendas@seiko.com");
+
+        assert_eq!(
+            acha(&fornecedores, "seiko").unwrap().nome,
+            "Seiko do Brasil"
+        );
+        assert_eq!(acha(&fornecedores, "CASIO").unwrap().nome, "Casio");
+        assert!(acha(&fornecedores, "cas")
+            .unwrap_err()
+            .contains("Casa das Pulseiras, Casio"));
+        assert!(acha(&fornecedores, "Orient").is_err());
+
+        let compra = |fornecedor: Option<&str>, quantidade, timestamp: &str| Historico {
+            codigo: "R001".into(),
+            quantidade,
+            operacao: "COMPRA".into(),
+            timestamp: timestamp.into(),
+            custo: Some(50.0),
+            fornecedor: fornecedor.map(str::to_string),
+            ..Default::default()
+        };
+        let historico = vec![
+            compra(Some("Seiko do Brasil"), 4, "2024-03-01 10:00:00"),
+            compra(Some("Casio"), 1, "2024-03-02 10:00:00"),
+            compra(Some("SEIKO DO BRASIL"), 2, "2024-04-10 15:00:00"),
+            compra(None, 9, "2024-04-11 15:00:00"),
+        ];
+        let (lotes, unidades, total, ultima) =
+            resumo(&historico, &HashMap::new(), "Seiko do Brasil");
+        assert_eq!((lotes, unidades, total), (2, 6, 300.0));
+        assert_eq!(ultima.as_deref(), Some("2024-04-10"));
+    }
+
+    #[test]
+    fn resolve_pelo_fornecedor_da_origem() {
+        let mut refs = vec![];
+        define(&mut refs, referencia("Seiko", "SRPD55", "R002"));
+        define(&mut refs, referencia("Casio", "A168", "R001"));
+        define(&mut refs, referencia("Atacado", "A168", "R009"));
+        define(&mut refs, referencia("seiko", "SRPD55", "R005"));
+        assert_eq!(refs.len(), 3);
+        assert_eq!(
+            resolve(&refs, "tabela_seiko_maio.csv", "SRPD55"),
+            Some("R005")
+        );
+        assert_eq!(resolve(&refs, "outro.csv", "SRPD55"), Some("R005"));
+        assert_eq!(resolve(&refs, "CASIO BRASIL LTDA", "A168"), Some("R001"));
+        // Dois fornecedores, dois códigos internos: sem a origem, não adivinha
+        assert_eq!(resolve(&refs, "outro.csv", "A168"), None);
+        assert_eq!(resolve(&refs, "casio.csv", "X1"), None);
+    }
+}
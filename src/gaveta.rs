@@ -0,0 +1,17 @@
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+
+/// ESC p m t1 t2: pulso no pino 2 da gaveta (25 ms ligado, 250 ms desligado).
+const PULSO_GAVETA: [u8; 5] = [0x1B, 0x70, 0x00, 0x19, 0xFA];
+
+/// Abre a gaveta de dinheiro enviando o pulso ESC/POS pela porta serial.
+pub fn abrir(porta: &str) -> io::Result<()> {
+    let caminho = if cfg!(windows) && !porta.starts_with(r"\\.\") {
+        format!(r"\\.\{}", porta)
+    } else {
+        porta.to_string()
+    };
+    let mut f = OpenOptions::new().write(true).open(caminho)?;
+    f.write_all(&PULSO_GAVETA)?;
+    f.flush()
+}
@@ -0,0 +1,191 @@
+use crate::dominio::{Ean, Quantidade};
+use crate::{codigo_por_ean, Relogio};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+/// Campos aceitos como GTIN na importação, do mais genérico ao específico.
+const CAMPOS_GTIN: &[&str] = &["gtin", "gtin13", "gtin14", "gtin12", "gtin8"];
+
+/// Catálogo como `ItemList` de `Product` do Schema.org, com o GTIN e a
+/// oferta em reais, no formato que lojas virtuais e marketplaces leem.
+pub fn exporta(relogios: &HashMap<String, Relogio>) -> Value {
+    let mut lista: Vec<&Relogio> = relogios.values().collect();
+    lista.sort_by(|a, b| a.codigo.cmp(&b.codigo));
+    let itens: Vec<Value> = lista
+        .into_iter()
+        .map(|r| {
+            let mut p = json!({
+                "@type": "Product",
+                "sku": r.codigo,
+                "name": r.descricao,
+            });
+            if let Some(ean) = &r.ean {
+                p["gtin"] = json!(ean);
+            }
+            if let Some(marca) = &r.marca {
+                p["brand"] = json!({ "@type": "Brand", "name": marca });
+            }
+            if let Some(categoria) = &r.categoria {
+                p["category"] = json!(categoria);
+            }
+            let mut oferta = json!({
+                "@type": "Offer",
+                "priceCurrency": "BRL",
+                "inventoryLevel": { "@type": "QuantitativeValue", "value": r.quantidade() },
+            });
+            if let Some(preco) = r.preco {
+                oferta["price"] = json!(preco);
+            }
+            p["offers"] = oferta;
+            p
+        })
+        .collect();
+    json!({
+        "@context": "https://schema.org",
+        "@type": "ItemList",
+        "itemListElement": itens,
+    })
+}
+
+fn texto(v: &Value) -> Option<String> {
+    match v {
+        Value::String(s) if !s.trim().is_empty() => Some(s.trim().to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        // Brand como objeto `{"name": ...}`
+        Value::Object(o) => o.get("name").and_then(texto),
+        _ => None,
+    }
+}
+
+fn numero(v: &Value) -> Option<f64> {
+    v.as_f64()
+        .or_else(|| v.as_str().and_then(|s| s.trim().parse().ok()))
+}
+
+/// Produto lido do JSON: código interno (sku, ou o GTIN quando não há),
+/// GTIN, descrição, marca, categoria e preço.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Produto {
+    pub codigo: String,
+    pub ean: Option<String>,
+    pub descricao: Option<String>,
+    pub marca: Option<String>,
+    pub categoria: Option<String>,
+    pub preco: Option<f64>,
+}
+
+/// Lê um `ItemList` (itens soltos ou em `ListItem.item`), uma lista de
+/// `Product` ou um `Product` só. GTINs com dígito verificador errado são
+/// descartados, e itens sem sku nem GTIN ignorados.
+pub fn le(json: &Value) -> Vec<Produto> {
+    let itens: Vec<&Value> = match json {
+        Value::Array(a) => a.iter().collect(),
+        Value::Object(o) => match o.get("itemListElement") {
+            Some(Value::Array(a)) => a.iter().collect(),
+            _ => vec![json],
+        },
+        _ => vec![],
+    };
+    itens
+        .into_iter()
+        .map(|i| i.get("item").unwrap_or(i))
+        .filter_map(|p| {
+            let ean = CAMPOS_GTIN
+                .iter()
+                .filter_map(|c| p.get(*c).and_then(texto))
+                .find_map(|t| Ean::novo(&t).ok())
+                .map(|e| e.as_str().to_string());
+            let codigo = p.get("sku").and_then(texto).or_else(|| ean.clone())?;
+            let oferta = match p.get("offers") {
+                Some(Value::Array(a)) => a.first(),
+                o => o,
+            };
+            Some(Produto {
+                codigo,
+                ean,
+                descricao: p.get("name").or_else(|| p.get("model")).and_then(texto),
+                marca: p.get("brand").and_then(texto),
+                categoria: p.get("category").and_then(texto),
+                preco: oferta.and_then(|o| o.get("price")).and_then(numero),
+            })
+        })
+        .collect()
+}
+
+/// Aplica os produtos lidos: campos presentes sobrescrevem os locais, o
+/// estoque não muda (entra só por compra). Devolve (atualizados, criados).
+pub fn aplica(relogios: &mut HashMap<String, Relogio>, produtos: &[Produto]) -> (usize, usize) {
+    let (mut atualizados, mut criados) = (0, 0);
+    for p in produtos {
+        // Produto já cadastrado com outro código interno, achado pelo GTIN
+        let codigo = p
+            .ean
+            .as_deref()
+            .and_then(|e| codigo_por_ean(relogios, e))
+            .unwrap_or(&p.codigo)
+            .to_string();
+        if relogios.contains_key(&codigo) {
+            atualizados += 1;
+        } else {
+            criados += 1;
+        }
+        let r = relogios
+            .entry(codigo.clone())
+            .or_insert_with(|| Relogio::novo(codigo, Quantidade::ZERO));
+        if let Some(d) = &p.descricao {
+            r.descricao = d.clone();
+        }
+        r.ean = p.ean.clone().or(r.ean.take());
+        r.marca = p.marca.clone().or(r.marca.take());
+        r.categoria = p.categoria.clone().or(r.categoria.take());
+        r.preco = p.preco.or(r.preco);
+    }
+    (atualizados, criados)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exporta_e_importa_de_volta() {
+        let mut relogios = HashMap::new();
+        relogios.insert("R001".to_string(), {
+            let mut r = Relogio::novo("R001", Quantidade::nova(4).unwrap());
+            r.descricao = "Casio Vintage".into();
+            r.marca = Some("Casio".into());
+            r.ean = Some("4971850436126".into());
+            r.preco = Some(249.9);
+            r
+        });
+        let json = exporta(&relogios);
+        let item = &json["itemListElement"][0];
+        assert_eq!(item["gtin"], "4971850436126");
+        assert_eq!(item["brand"]["name"], "Casio");
+        assert_eq!(item["offers"]["inventoryLevel"]["value"], 4);
+
+        let mut destino = HashMap::new();
+        assert_eq!(aplica(&mut destino, &le(&json)), (0, 1));
+        let r = &destino["R001"];
+        assert_eq!(r.preco, Some(249.9));
+        assert_eq!(r.marca.as_deref(), Some("Casio"));
+        // Estoque não vem pelo catálogo
+        assert_eq!(r.quantidade(), 0);
+
+        // Outra ferramenta: sem sku, GTIN-13, marca em texto e preço em texto
+        let externo = serde_json::json!([{
+            "@type": "Product",
+            "gtin13": "4971850436126",
+            "model": "A168",
+            "brand": "Casio",
+            "offers": [{ "price": "259.90" }]
+        }]);
+        assert_eq!(aplica(&mut destino, &le(&externo)), (1, 0));
+        assert_eq!(destino["R001"].descricao, "A168");
+        assert_eq!(destino["R001"].preco, Some(259.9));
+
+        // GTIN com dígito verificador errado não vira código nem EAN
+        let errado = serde_json::json!({ "sku": "R002", "gtin": "4971850436127" });
+        assert_eq!(le(&errado)[0].ean, None);
+    }
+}
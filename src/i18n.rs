@@ -0,0 +1,198 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// Idioma das telas. Só muda o que é exibido: os tipos de operação
+/// gravados no histórico (`COMPRA`, `VENDA`...) ficam sempre em português.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum Idioma {
+    #[default]
+    #[serde(rename = "pt-BR")]
+    PtBr,
+    #[serde(rename = "en-US")]
+    EnUs,
+}
+
+impl Idioma {
+    pub fn parse(texto: &str) -> Option<Idioma> {
+        match texto.trim().to_lowercase().replace('_', "-").as_str() {
+            "pt-br" | "pt" => Some(Idioma::PtBr),
+            "en-us" | "en" => Some(Idioma::EnUs),
+            _ => None,
+        }
+    }
+
+    pub fn nome(self) -> &'static str {
+        match self {
+            Idioma::PtBr => "pt-BR",
+            Idioma::EnUs => "en-US",
+        }
+    }
+}
+
+/// Tabela de textos: o original em português (a chave, como está no
+/// código) e a tradução. Texto sem tradução aparece em português.
+const INGLES: &[(&str, &str)] = &[
+    // Tela principal
+    (
+        "Bem-vindo ao Sistema de Relógios (Estoque)!",
+        "Welcome to the Watch Inventory System!",
+    ),
+    (
+        "Favoritos (1–9 vende, P prende/solta)",
+        "Favorites (1–9 sells, P pins/unpins)",
+    ),
+    (
+        "Nenhum: P prende a linha atual do Estoque",
+        "None: P pins the current Inventory row",
+    ),
+    ("Logs (Últimas Mensagens)", "Log (Latest Messages)"),
+    ("Mensagens (Histórico Completo)", "Messages (Full History)"),
+    // Hotkeys
+    ("Hotkeys ([?] ajuda):", "Hotkeys ([?] help):"),
+    (" [C] Cadastro", " [C] Register"),
+    (" [B] Buscar", " [B] Search"),
+    (
+        " [H] Histórico (↑/↓ rola, ←/→ abas)",
+        " [H] History (↑/↓ scroll, ←/→ tabs)",
+    ),
+    (" [G] Gráfico", " [G] Charts"),
+    (" [I] Importar catálogo", " [I] Import catalog"),
+    (
+        " [L] Etiqueta (Shift+L: pendentes)",
+        " [L] Label (Shift+L: pending)",
+    ),
+    (" [S] Configurações", " [S] Settings"),
+    (" [W] Enviar p/ Google Sheets", " [W] Push to Google Sheets"),
+    (" [M] Preço de mercado", " [M] Market price"),
+    (" [K] Admin (API)", " [K] Admin (API)"),
+    (" [T] Estoque em data passada", " [T] Stock on a past date"),
+    (" [F] Destaque / [Q] Quiosque", " [F] Showcase / [Q] Kiosk"),
+    (
+        " [N] Notificações/[E] Recado",
+        " [N] Notifications/[E] Note",
+    ),
+    (" [Espaço]/[O] Comparar", " [Space]/[O] Compare"),
+    (" [R] Usados recentemente", " [R] Recently used"),
+    (" [U] Fornecedores", " [U] Suppliers"),
+    (" [J] Receber mercadoria", " [J] Receive goods"),
+    (" [Y] Pedidos pendentes", " [Y] Open orders"),
+    (" [Z] Orçamentos", " [Z] Quotes"),
+    (" [@] Clientes", " [@] Customers"),
+    (" [~] Mostrar arquivados", " [~] Show archived"),
+    (" [|] Estoque + histórico", " [|] Inventory + history"),
+    (" [:] Console de consultas", " [:] Query console"),
+    (
+        " [+]/[-]/[=] Ajustar quantidade",
+        " [+]/[-]/[=] Adjust quantity",
+    ),
+    (" [D] Excluir / [F2] Renomear", " [D] Delete / [F2] Rename"),
+    (" [ENTER] Selecionar registro", " [ENTER] Select record"),
+    (" [A] Adicionar/Comprar", " [A] Add/Purchase"),
+    (" [V] Vender", " [V] Sell"),
+    (" [ESC] Cancelar Seleção", " [ESC] Cancel selection"),
+    (" [X] Sair", " [X] Quit"),
+    ("(Selecionado)", "(Selected)"),
+    (" [P] Pesquisar Histórico", " [P] Search history"),
+    (" [Y] Anos arquivados", " [Y] Archived years"),
+    (" [Espaço]/[*] Marcar", " [Space]/[*] Mark"),
+    (" [T] Etiquetar / [F] Filtrar", " [T] Tag / [F] Filter"),
+    (" [E] Exportar CSV", " [E] Export CSV"),
+    // Ajuda (?)
+    (
+        "Ajuda (↑/↓ rola, outra tecla fecha)",
+        "Help (↑/↓ scrolls, any other key closes)",
+    ),
+    // Estoque
+    ("Estoque", "Inventory"),
+    ("Estoque em", "Inventory on"),
+    (
+        "(somente leitura, T volta ao atual)",
+        "(read only, T goes back to today)",
+    ),
+    ("códigos", "codes"),
+    ("(sem cadastro)", "(not registered)"),
+    ("Estoque × alvo dos favoritos", "Favorites' stock × target"),
+    (
+        "Nenhum favorito com alvo: Relogio alvo <codigo> <un>",
+        "No favorite has a target: Relogio alvo <code> <units>",
+    ),
+    ("nunca vendido", "never sold"),
+    ("última venda", "last sale"),
+    // Títulos de colunas
+    ("CÓDIGO", "CODE"),
+    ("QTD", "QTY"),
+    ("PEDIDO", "ORDER"),
+    ("DESCRIÇÃO", "DESCRIPTION"),
+    ("PREÇO", "PRICE"),
+    ("CUSTO", "COST"),
+    ("CATEGORIA", "CATEGORY"),
+    ("MARCA", "BRAND"),
+    ("EAN", "EAN"),
+    ("TIMESTAMP", "TIMESTAMP"),
+    ("OPERACAO", "OPERATION"),
+    ("OPERAÇÃO", "OPERATION"),
+    ("ETIQUETAS", "TAGS"),
+    ("PAGAMENTO", "PAYMENT"),
+    ("FORNECEDOR", "SUPPLIER"),
+    ("MOTIVO", "REASON"),
+    ("CLIENTE", "CUSTOMER"),
+    ("DATA", "DATE"),
+    // Histórico
+    ("Todos", "All"),
+    ("Compras", "Purchases"),
+    ("Vendas", "Sales"),
+    ("Aquisições", "Acquisitions"),
+    ("Filtros", "Filters"),
+    ("Histórico", "History"),
+    ("etiqueta", "tag"),
+    ("marcadas", "marked"),
+    ("Pesquisa no Histórico", "History search"),
+    (
+        "Pressione P para pesquisar no histórico",
+        "Press P to search the history",
+    ),
+    ("Filtrar histórico por código:", "Filter history by code:"),
+    (
+        "Filtrar por etiqueta (vazio mostra todas)",
+        "Filter by tag (empty shows all)",
+    ),
+    // Operações (só o rótulo; o gravado não muda)
+    ("COMPRA", "PURCHASE"),
+    ("VENDA", "SALE"),
+    ("CADASTRO", "NEW"),
+    ("AJUSTE", "ADJUST"),
+    ("BAIXA_KIT", "KIT_OUT"),
+    ("SALDO_INICIAL", "OPENING"),
+];
+
+/// O texto no idioma pedido; `texto` é o original em português.
+pub fn t(idioma: Idioma, texto: &str) -> &str {
+    static TABELA: OnceLock<HashMap<&str, &str>> = OnceLock::new();
+    match idioma {
+        Idioma::PtBr => texto,
+        Idioma::EnUs => TABELA
+            .get_or_init(|| INGLES.iter().copied().collect())
+            .get(texto)
+            .copied()
+            .unwrap_or(texto),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn traduz_e_cai_no_original() {
+        assert_eq!(t(Idioma::EnUs, "VENDA"), "SALE");
+        assert_eq!(t(Idioma::PtBr, "VENDA"), "VENDA");
+        assert_eq!(t(Idioma::EnUs, "sem tradução"), "sem tradução");
+        assert_eq!(Idioma::parse("EN_us"), Some(Idioma::EnUs));
+        assert_eq!(Idioma::parse("fr"), None);
+        // Chave repetida esconderia uma das traduções
+        let chaves: HashSet<&str> = INGLES.iter().map(|(pt, _)| *pt).collect();
+        assert_eq!(chaves.len(), INGLES.len());
+    }
+}
@@ -0,0 +1,208 @@
+use crate::persistencia::na_pasta_dados;
+use crate::{arquivo, Historico};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+
+const CAMINHO_KARDEX: &str = "kardex.json";
+
+/// Movimento de um código em um dia: saldo de abertura, entradas, saídas e
+/// saldo de fechamento.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct DiaKardex {
+    pub abertura: i32,
+    pub entradas: i32,
+    pub saidas: i32,
+    pub fechamento: i32,
+}
+
+/// Razão diário de estoque, mantido em `kardex.json` e atualizado só com as
+/// entradas novas do histórico a cada gravação.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Kardex {
+    /// Entradas do histórico já processadas e o timestamp da última, para
+    /// perceber quando o histórico foi reescrito (arquivamento).
+    processadas: usize,
+    ultima: Option<String>,
+    /// Código -> data (AAAA-MM-DD) -> movimento do dia.
+    pub dias: BTreeMap<String, BTreeMap<String, DiaKardex>>,
+}
+
+impl Kardex {
+    pub fn carrega() -> Kardex {
+        fs::read_to_string(na_pasta_dados(CAMINHO_KARDEX))
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn salva(&self) {
+        if let Ok(j) = serde_json::to_string(self) {
+            let _ = fs::write(na_pasta_dados(CAMINHO_KARDEX), j);
+        }
+    }
+
+    /// Lança uma entrada; `false` se ela é anterior ao último dia já lançado
+    /// do código e o razão precisa ser refeito.
+    fn lanca(&mut self, h: &Historico) -> bool {
+        let Some(data) = h.timestamp.get(..10) else {
+            return true;
+        };
+        let por_dia = self.dias.entry(h.codigo.clone()).or_default();
+        let ultimo = por_dia.iter().next_back();
+        if ultimo.is_some_and(|(d, _)| d.as_str() > data) {
+            return false;
+        }
+        let saldo = ultimo.map_or(0, |(_, m)| m.fechamento);
+        let dia = por_dia.entry(data.to_string()).or_insert(DiaKardex {
+            abertura: saldo,
+            fechamento: saldo,
+            ..Default::default()
+        });
+        match h.operacao.as_str() {
+            "COMPRA" => {
+                dia.entradas += h.quantidade;
+                dia.fechamento += h.quantidade;
+            }
+            "VENDA" | "BAIXA_KIT" => {
+                dia.saidas += h.quantidade;
+                dia.fechamento -= h.quantidade;
+            }
+            "AJUSTE" => {
+                if h.quantidade >= 0 {
+                    dia.entradas += h.quantidade;
+                } else {
+                    dia.saidas -= h.quantidade;
+                }
+                dia.fechamento += h.quantidade;
+            }
+            // Cadastro e saldo inicial fixam a quantidade; a diferença entra
+            // como ajuste
+            "CADASTRO" | arquivo::OP_SALDO_INICIAL => {
+                let diferenca = h.quantidade - dia.fechamento;
+                if diferenca >= 0 {
+                    dia.entradas += diferenca;
+                } else {
+                    dia.saidas -= diferenca;
+                }
+                dia.fechamento = h.quantidade;
+            }
+            _ => {}
+        }
+        true
+    }
+
+    /// Refaz o razão do zero, incluindo os anos arquivados.
+    pub fn reconstroi(historico: &[Historico]) -> Kardex {
+        let mut todas: Vec<Historico> = arquivo::anos_arquivados()
+            .into_iter()
+            .flat_map(arquivo::carrega_ano)
+            .collect();
+        todas.extend_from_slice(historico);
+        todas.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+        let mut k = Kardex::default();
+        for h in &todas {
+            k.lanca(h);
+        }
+        k.processadas = historico.len();
+        k.ultima = historico.last().map(|h| h.timestamp.clone());
+        k
+    }
+
+    /// Lança as entradas novas do histórico, ou refaz tudo se o histórico
+    /// já processado mudou.
+    pub fn atualiza(&mut self, historico: &[Historico]) {
+        let reescrito = self.processadas > historico.len()
+            || (self.processadas > 0
+                && historico.get(self.processadas - 1).map(|h| &h.timestamp)
+                    != self.ultima.as_ref());
+        let incremental = !reescrito && historico[self.processadas..].iter().all(|h| self.lanca(h));
+        if !incremental {
+            *self = Kardex::reconstroi(historico);
+            return;
+        }
+        self.processadas = historico.len();
+        self.ultima = historico.last().map(|h| h.timestamp.clone());
+    }
+
+    /// Saldo de um código no fim do dia `data` (AAAA-MM-DD); `None` se o
+    /// código ainda não tinha movimento até lá.
+    pub fn saldo_em(&self, codigo: &str, data: &str) -> Option<i32> {
+        self.dias
+            .get(codigo)
+            .and_then(|d| d.range(..=data.to_string()).next_back())
+            .map(|(_, m)| m.fechamento)
+    }
+
+    /// Extrato tipo kardex em CSV (`;`) dos dias com movimento no período,
+    /// de um código ou de todos.
+    pub fn extrato_csv(&self, codigo: Option<&str>, inicio: &str, fim: &str) -> (String, usize) {
+        let mut csv = String::from("data;codigo;saldo_inicial;entradas;saidas;saldo_final\n");
+        let mut n = 0;
+        for (cod, dias) in &self.dias {
+            if codigo.is_some_and(|c| c != cod) {
+                continue;
+            }
+            for (data, m) in dias.range(inicio.to_string()..=fim.to_string()) {
+                csv.push_str(&format!(
+                    "{};{};{};{};{};{}\n",
+                    data, cod, m.abertura, m.entradas, m.saidas, m.fechamento
+                ));
+                n += 1;
+            }
+        }
+        (csv, n)
+    }
+}
+
+/// Atualiza o `kardex.json` com o histórico recém-gravado.
+pub fn atualiza_arquivo(historico: &[Historico]) {
+    let mut k = Kardex::carrega();
+    k.atualiza(historico);
+    k.salva();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn h(codigo: &str, operacao: &str, quantidade: i32, timestamp: &str) -> Historico {
+        Historico {
+            codigo: codigo.into(),
+            quantidade,
+            operacao: operacao.into(),
+            timestamp: timestamp.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn incremental_igual_a_reconstrucao() {
+        let mut historico = vec![
+            h("R1", "CADASTRO", 5, "2024-01-02 10:00:00"),
+            h("R1", "VENDA", 2, "2024-01-02 15:00:00"),
+            h("R1", "COMPRA", 4, "2024-01-05 09:00:00"),
+        ];
+        let mut k = Kardex::default();
+        k.atualiza(&historico);
+        historico.push(h("R1", "VENDA", 1, "2024-01-05 18:00:00"));
+        historico.push(h("R1", "VENDA", 3, "2024-01-07 11:00:00"));
+        historico.push(h("R1", "AJUSTE", -1, "2024-01-07 17:00:00"));
+        k.atualiza(&historico);
+
+        let dias = &k.dias["R1"];
+        assert_eq!(
+            dias["2024-01-05"],
+            DiaKardex {
+                abertura: 3,
+                entradas: 4,
+                saidas: 1,
+                fechamento: 6
+            }
+        );
+        assert_eq!(k.saldo_em("R1", "2024-01-06"), Some(6));
+        assert_eq!(k.saldo_em("R1", "2024-01-07"), Some(2));
+        assert_eq!(k.saldo_em("R1", "2023-12-31"), None);
+        assert_eq!(k.dias, Kardex::reconstroi(&historico).dias);
+    }
+}
@@ -0,0 +1,36 @@
+/// Altura, em linhas, dos caracteres grandes.
+pub const ALTURA: usize = 5;
+
+/// Desenho 3x5 de cada caractere suportado; os demais viram espaço.
+fn glifo(c: char) -> [&'static str; ALTURA] {
+    match c {
+        '0' => ["███", "█ █", "█ █", "█ █", "███"],
+        '1' => [" █ ", "██ ", " █ ", " █ ", "███"],
+        '2' => ["███", "  █", "███", "█  ", "███"],
+        '3' => ["███", "  █", "███", "  █", "███"],
+        '4' => ["█ █", "█ █", "███", "  █", "  █"],
+        '5' => ["███", "█  ", "███", "  █", "███"],
+        '6' => ["███", "█  ", "███", "█ █", "███"],
+        '7' => ["███", "  █", "  █", "  █", "  █"],
+        '8' => ["███", "█ █", "███", "█ █", "███"],
+        '9' => ["███", "█ █", "███", "  █", "███"],
+        ',' => ["   ", "   ", "   ", " █ ", "█  "],
+        '.' => ["   ", "   ", "   ", "   ", " █ "],
+        '-' => ["   ", "   ", "███", "   ", "   "],
+        _ => ["   ", "   ", "   ", "   ", "   "],
+    }
+}
+
+/// Texto em dígitos grandes, uma `String` por linha, com uma coluna entre
+/// os caracteres.
+pub fn grande(texto: &str) -> Vec<String> {
+    (0..ALTURA)
+        .map(|linha| {
+            texto
+                .chars()
+                .map(|c| glifo(c)[linha])
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect()
+}
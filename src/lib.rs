@@ -0,0 +1,89 @@
+mod agenda;
+mod agendadas;
+mod ajuda;
+mod alteracoes;
+mod analise;
+mod arquivo;
+mod atualizacao;
+mod auditoria;
+mod balanco;
+mod caixa;
+mod cambio;
+mod carga;
+mod catalogo;
+mod clientes;
+mod colunas;
+mod comandos;
+mod config;
+mod consulta;
+mod contabil;
+mod credito;
+mod csv;
+mod despesas;
+/// Produtos, histórico, as regras de venda e as que mantêm o saldo
+/// coerente com o histórico.
+pub mod dominio;
+mod etiquetas;
+mod expurgo;
+mod fidelidade;
+mod figura;
+mod financeiro;
+mod fornecedores;
+mod gaveta;
+mod gs1;
+mod i18n;
+mod kardex;
+mod letreiro;
+mod mercado;
+mod modelos;
+mod nfe;
+mod notas;
+mod notificacoes;
+mod numeros;
+mod orcamentos;
+mod pdf;
+mod pedidos;
+/// Leitura e gravação do `estoque.json`.
+pub mod persistencia;
+mod planilha;
+mod promocoes;
+mod recebimento;
+mod recentes;
+mod relatorios;
+mod reposicao;
+mod retencao;
+mod rolagem;
+mod servidor;
+mod sincronia;
+mod sqlite;
+mod tema;
+mod tokens;
+/// Interface de terminal (`App`): telas, teclas e as ações disparadas por elas.
+pub mod ui;
+
+pub use dominio::{Historico, Relogio};
+pub use ui::App;
+
+// Os módulos usam os tipos pelo caminho curto (`crate::Relogio`).
+use dominio::{codigo_por_ean, quantidade_disponivel};
+use persistencia::{load_from_file, Espera, Registros};
+
+/// Ponto de entrada do binário: `--update`, `--serve [endereço]`, um comando
+/// de linha de comando ou, sem argumentos, a interface. `--data <pasta>`,
+/// em qualquer posição, escolhe a pasta dos dados.
+pub fn executa(args: &[String]) -> Result<(), Box<dyn std::error::Error>> {
+    if args.first().map(|a| a.as_str()) == Some("--update") {
+        return atualizacao::atualizar();
+    }
+    let (pasta, args) = atualizacao::separa_pasta_dados(args)?;
+    let args = args.as_slice();
+    atualizacao::usa_diretorio_dados(pasta)?;
+    if args.first().map(|a| a.as_str()) == Some("--serve") {
+        let endereco = args.get(1).map_or("127.0.0.1:8080", |a| a.as_str());
+        return servidor::servir(endereco).map_err(|e| e as Box<dyn std::error::Error>);
+    }
+    if !args.is_empty() {
+        return comandos::executa(args);
+    }
+    ui::executa()
+}
@@ -0,0 +1,89 @@
+use crate::numeros::FormatoNumero;
+use serde_json::Value;
+
+/// Campos reconhecidos como preço nas respostas da API.
+const CAMPOS_PRECO: &[&str] = &["price", "preco", "valor", "amount"];
+
+/// Resumo das ofertas encontradas para um modelo.
+#[derive(Debug, Clone)]
+pub struct PrecosMercado {
+    pub minimo: f64,
+    pub mediana: f64,
+    pub maximo: f64,
+    pub ofertas: usize,
+}
+
+/// Recolhe preços de qualquer formato razoável: lista de números, ou objetos
+/// com `price`/`preco`/`valor`/`amount` em qualquer nível.
+fn extrai_precos(v: &Value, formato: FormatoNumero, precos: &mut Vec<f64>) {
+    match v {
+        Value::Array(itens) => {
+            for item in itens {
+                match item.as_f64() {
+                    Some(p) => precos.push(p),
+                    None => extrai_precos(item, formato, precos),
+                }
+            }
+        }
+        Value::Object(obj) => {
+            for (chave, valor) in obj {
+                let preco = valor
+                    .as_f64()
+                    .or_else(|| valor.as_str().and_then(|t| formato.interpreta(t)));
+                match preco {
+                    Some(p) if CAMPOS_PRECO.contains(&chave.to_lowercase().as_str()) => {
+                        precos.push(p)
+                    }
+                    _ => extrai_precos(valor, formato, precos),
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn resumo(mut precos: Vec<f64>) -> Option<PrecosMercado> {
+    precos.retain(|p| p.is_finite() && *p > 0.0);
+    if precos.is_empty() {
+        return None;
+    }
+    precos.sort_by(|a, b| a.total_cmp(b));
+    let n = precos.len();
+    let mediana = if n.is_multiple_of(2) {
+        (precos[n / 2 - 1] + precos[n / 2]) / 2.0
+    } else {
+        precos[n / 2]
+    };
+    Some(PrecosMercado {
+        minimo: precos[0],
+        mediana,
+        maximo: precos[n - 1],
+        ofertas: n,
+    })
+}
+
+/// Consulta a API configurada; `{codigo}` no modelo de URL vira o código.
+/// Preços que vêm como texto são lidos no `formato` configurado.
+pub fn consulta(
+    url_modelo: &str,
+    codigo: &str,
+    formato: FormatoNumero,
+) -> Result<Option<PrecosMercado>, String> {
+    let codigo_url: String = codigo
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect();
+    let url = url_modelo.replace("{codigo}", &codigo_url);
+    let json: Value = ureq::get(&url)
+        .timeout(std::time::Duration::from_secs(10))
+        .call()
+        .map_err(|e| e.to_string())?
+        .into_json()
+        .map_err(|e| e.to_string())?;
+    let mut precos = vec![];
+    extrai_precos(&json, formato, &mut precos);
+    Ok(resumo(precos))
+}
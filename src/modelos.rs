@@ -0,0 +1,189 @@
+use crate::numeros::FormatoNumero;
+use crate::persistencia::na_pasta_dados;
+use crate::{contabil, Registros, Relogio};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use tera::{Context, Tera, Value};
+
+/// Pasta onde o usuário deixa os modelos (`*.tera`).
+pub const PASTA_MODELOS: &str = "modelos";
+
+/// Venda do período como os modelos a veem, já com descrição e valor.
+#[derive(Serialize)]
+struct Venda<'a> {
+    timestamp: &'a str,
+    codigo: &'a str,
+    descricao: &'a str,
+    quantidade: i32,
+    pagamento: &'a str,
+    valor: f64,
+    nota: Option<u32>,
+}
+
+/// Nomes dos modelos disponíveis na pasta, em ordem.
+pub fn lista() -> Vec<String> {
+    let mut nomes: Vec<String> = fs::read_dir(na_pasta_dados(PASTA_MODELOS))
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|e| e.file_name().into_string().ok())
+        .filter(|n| n.ends_with(".tera"))
+        .collect();
+    nomes.sort();
+    nomes
+}
+
+/// Dados oferecidos a todo modelo: `hoje`, `inicio`, `fim`, `relogios`
+/// (ordenados por código), `vendas` e `notas` do período e `totais`.
+pub fn contexto(
+    relogios: &HashMap<String, Relogio>,
+    historico: &[crate::Historico],
+    registros: &Registros,
+    hoje: &str,
+    inicio: &str,
+    fim: &str,
+) -> Context {
+    let no_periodo = |timestamp: &str| {
+        let dia = timestamp.get(..10).unwrap_or(timestamp);
+        dia >= inicio && dia <= fim
+    };
+    let mut lista: Vec<&Relogio> = relogios.values().collect();
+    lista.sort_by(|a, b| a.codigo.cmp(&b.codigo));
+    let vendas: Vec<Venda> = historico
+        .iter()
+        .filter(|h| h.operacao == "VENDA" && no_periodo(&h.timestamp))
+        .map(|h| {
+            let r = relogios.get(&h.codigo);
+            Venda {
+                timestamp: &h.timestamp,
+                codigo: &h.codigo,
+                descricao: r.map(|r| r.descricao.as_str()).unwrap_or(""),
+                quantidade: h.quantidade,
+                pagamento: h.pagamento.as_deref().unwrap_or("dinheiro"),
+                valor: contabil::valor(h, r).unwrap_or(0.0),
+                nota: h.nota,
+            }
+        })
+        .collect();
+    let notas: Vec<_> = registros
+        .notas
+        .iter()
+        .filter(|n| no_periodo(&n.timestamp))
+        .collect();
+
+    let mut ctx = Context::new();
+    ctx.insert("hoje", hoje);
+    ctx.insert("inicio", inicio);
+    ctx.insert("fim", fim);
+    ctx.insert("relogios", &lista);
+    ctx.insert(
+        "totais",
+        &serde_json::json!({
+            "vendas": vendas.len(),
+            "unidades": vendas.iter().map(|v| v.quantidade).sum::<i32>(),
+            "receita": vendas.iter().map(|v| v.valor).sum::<f64>(),
+            "estoque": lista.iter().map(|r| r.quantidade()).sum::<i32>(),
+        }),
+    );
+    ctx.insert("vendas", &vendas);
+    ctx.insert("notas", &notas);
+    ctx
+}
+
+/// Renderiza o texto de um modelo. Além dos filtros do Tera há `moeda`
+/// (`R$ 1.234,50`) e `numero` (`casas`, padrão 2) no formato configurado.
+pub fn renderiza_texto(
+    nome: &str,
+    texto: &str,
+    ctx: &Context,
+    formato: FormatoNumero,
+) -> Result<String, String> {
+    let mut tera = Tera::default();
+    tera.add_raw_template(nome, texto)
+        .map_err(|e| erro_completo(nome, &e))?;
+    tera.register_filter(
+        "moeda",
+        move |v: &Value, _: &HashMap<String, Value>| -> tera::Result<Value> {
+            Ok(Value::String(formato.moeda(v.as_f64().unwrap_or(0.0))))
+        },
+    );
+    tera.register_filter(
+        "numero",
+        move |v: &Value, args: &HashMap<String, Value>| -> tera::Result<Value> {
+            let casas = args.get("casas").and_then(Value::as_u64).unwrap_or(2);
+            Ok(Value::String(
+                formato.numero(v.as_f64().unwrap_or(0.0), casas as usize),
+            ))
+        },
+    );
+    tera.render(nome, ctx).map_err(|e| erro_completo(nome, &e))
+}
+
+/// Lê `modelos/<nome>` (com ou sem `.tera`) e o renderiza.
+pub fn renderiza(nome: &str, ctx: &Context, formato: FormatoNumero) -> Result<String, String> {
+    let arquivo = if nome.ends_with(".tera") {
+        nome.to_string()
+    } else {
+        format!("{}.tera", nome)
+    };
+    let caminho = na_pasta_dados(PASTA_MODELOS).join(&arquivo);
+    let texto =
+        fs::read_to_string(&caminho).map_err(|e| format!("{}: {}", caminho.display(), e))?;
+    renderiza_texto(&arquivo, &texto, ctx, formato)
+}
+
+/// O Tera guarda a causa real (linha, variável faltando) nos erros internos.
+fn erro_completo(nome: &str, e: &tera::Error) -> String {
+    let mut msg = format!("{}: {}", nome, e);
+    let mut causa = std::error::Error::source(e);
+    while let Some(c) = causa {
+        msg.push_str(&format!(": {}", c));
+        causa = c.source();
+    }
+    msg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dominio::Quantidade;
+    use crate::Historico;
+
+    #[test]
+    fn renderiza_vendas_do_periodo_com_filtros() {
+        let relogios: HashMap<String, Relogio> = [{
+            let mut r = Relogio::novo("R001", Quantidade::nova(3).unwrap());
+            r.descricao = "Casio".into();
+            r.preco = Some(1249.9);
+            r
+        }]
+        .into_iter()
+        .map(|r| (r.codigo.clone(), r))
+        .collect();
+        let historico: Vec<Historico> = [("2024-01-15 11:20:00", 2), ("2024-02-01 10:00:00", 1)]
+            .into_iter()
+            .map(|(timestamp, quantidade)| Historico {
+                codigo: "R001".into(),
+                quantidade,
+                operacao: "VENDA".into(),
+                timestamp: timestamp.into(),
+                ..Default::default()
+            })
+            .collect();
+        let ctx = contexto(
+            &relogios,
+            &historico,
+            &Registros::default(),
+            "2024-01-31",
+            "2024-01-01",
+            "2024-01-31",
+        );
+        let modelo = "{% for v in vendas %}{{ v.codigo }} x{{ v.quantidade }} \
+                      {{ v.valor | moeda }}\n{% endfor %}\
+                      {{ totais.vendas }} venda(s), {{ relogios.0.preco | numero(casas=1) }}";
+        let texto = renderiza_texto("teste", modelo, &ctx, FormatoNumero::PtBr).unwrap();
+        assert_eq!(texto, "R001 x2 R$ 2.499,80\n1 venda(s), 1.249,9");
+        assert!(renderiza_texto("teste", "{{ nada }}", &ctx, FormatoNumero::PtBr).is_err());
+    }
+}
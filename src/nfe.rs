@@ -0,0 +1,236 @@
+use crate::dominio::{Ean, ErroDominio, Inventario, ProdutoId, Quantidade};
+use crate::{codigo_por_ean, fornecedores, Historico, Relogio};
+use chrono::NaiveDateTime;
+use roxmltree::{Document, Node};
+use std::collections::HashMap;
+
+/// Item (`det`) da NF-e de compra, como o fornecedor o descreve.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item {
+    pub codigo_fornecedor: String,
+    pub ean: Option<String>,
+    pub descricao: String,
+    pub quantidade: f64,
+    pub custo_unitario: f64,
+}
+
+/// NF-e recebida do fornecedor: número, série, emitente e itens.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NotaCompra {
+    pub numero: String,
+    pub serie: String,
+    pub emitente: String,
+    pub cnpj: String,
+    pub itens: Vec<Item>,
+}
+
+impl NotaCompra {
+    /// Identificação gravada nas COMPRAs: `numero/serie`.
+    pub fn identificacao(&self) -> String {
+        format!("{}/{}", self.numero, self.serie)
+    }
+}
+
+/// Primeiro descendente com a tag, ignorando o namespace do portal.
+fn filho<'a, 'i>(no: Node<'a, 'i>, tag: &str) -> Option<Node<'a, 'i>> {
+    no.descendants().find(|n| n.tag_name().name() == tag)
+}
+
+fn texto(no: Node, tag: &str) -> Option<String> {
+    filho(no, tag)
+        .and_then(|n| n.text())
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty())
+}
+
+/// Lê o XML da NF-e (`nfeProc` autorizado ou só a `NFe`).
+pub fn le(xml: &str) -> Result<NotaCompra, String> {
+    let doc = Document::parse(xml).map_err(|e| format!("XML inválido: {}", e))?;
+    let inf = filho(doc.root(), "infNFe").ok_or("O arquivo não é uma NF-e (falta infNFe).")?;
+    let ide = filho(inf, "ide").ok_or("NF-e sem identificação (ide).")?;
+    let emit = filho(inf, "emit").ok_or("NF-e sem emitente (emit).")?;
+    let mut itens = vec![];
+    for det in inf.children().filter(|n| n.tag_name().name() == "det") {
+        let Some(prod) = filho(det, "prod") else {
+            continue;
+        };
+        let numero = |tag: &str| {
+            texto(prod, tag)
+                .and_then(|t| t.parse::<f64>().ok())
+                .ok_or_else(|| format!("Item {}: {} inválido.", itens.len() + 1, tag))
+        };
+        itens.push(Item {
+            codigo_fornecedor: texto(prod, "cProd").unwrap_or_default(),
+            // "SEM GTIN" e códigos com dígito errado ficam de fora
+            ean: texto(prod, "cEAN")
+                .and_then(|e| Ean::novo(&e).ok())
+                .map(|e| e.as_str().to_string()),
+            descricao: texto(prod, "xProd").unwrap_or_default(),
+            quantidade: numero("qCom")?,
+            custo_unitario: numero("vUnCom")?,
+        });
+    }
+    Ok(NotaCompra {
+        numero: texto(ide, "nNF").ok_or("NF-e sem número (nNF).")?,
+        serie: texto(ide, "serie").unwrap_or_else(|| "1".into()),
+        emitente: texto(emit, "xNome").unwrap_or_default(),
+        cnpj: texto(emit, "CNPJ")
+            .or_else(|| texto(emit, "CPF"))
+            .unwrap_or_default(),
+        itens,
+    })
+}
+
+/// Código interno do item: pela tabela de fornecedores (o emitente é a
+/// origem), pelo EAN ou, por fim, o próprio código do fornecedor se já for
+/// um código cadastrado.
+pub fn casa(
+    item: &Item,
+    nota: &NotaCompra,
+    relogios: &HashMap<String, Relogio>,
+    referencias: &[fornecedores::Referencia],
+) -> Option<String> {
+    let origem = format!("{} {}", nota.emitente, nota.cnpj);
+    fornecedores::resolve(referencias, &origem, &item.codigo_fornecedor)
+        .or_else(|| {
+            item.ean
+                .as_deref()
+                .and_then(|e| codigo_por_ean(relogios, e))
+        })
+        .or_else(|| {
+            relogios
+                .contains_key(&item.codigo_fornecedor)
+                .then_some(item.codigo_fornecedor.as_str())
+        })
+        .map(str::to_string)
+}
+
+/// Itens lançados e os que ficaram de fora, com o motivo.
+#[derive(Debug, Default)]
+pub struct Resultado {
+    pub lancados: Vec<(String, i32, f64)>,
+    pub ignorados: Vec<(Item, String)>,
+}
+
+/// Lança uma COMPRA por item casado, com o custo unitário e a nota anexados,
+/// e atualiza o custo dos produtos em reais. Recusa nota já importada. Não
+/// grava.
+pub fn importa(
+    nota: &NotaCompra,
+    relogios: &mut HashMap<String, Relogio>,
+    historico: &mut Vec<Historico>,
+    referencias: &[fornecedores::Referencia],
+    quando: NaiveDateTime,
+) -> Result<Resultado, String> {
+    let identificacao = nota.identificacao();
+    if historico.iter().any(|h| {
+        h.nfe.as_deref() == Some(identificacao.as_str())
+            && h.fornecedor.as_deref() == Some(nota.emitente.as_str())
+    }) {
+        return Err(format!(
+            "A NF-e {} de {} já foi importada.",
+            identificacao, nota.emitente
+        ));
+    }
+    let mut resultado = Resultado::default();
+    for item in &nota.itens {
+        let Some(codigo) = casa(item, nota, relogios, referencias) else {
+            resultado
+                .ignorados
+                .push((item.clone(), "sem código interno".into()));
+            continue;
+        };
+        if item.quantidade.fract() != 0.0 {
+            resultado
+                .ignorados
+                .push((item.clone(), "quantidade fracionada".into()));
+            continue;
+        }
+        let lancado = ProdutoId::novo(&codigo).and_then(|c| {
+            let mut inventario = Inventario::new(relogios, historico);
+            let h =
+                inventario.compra(&c, Quantidade::movimento(item.quantidade as i32)?, quando)?;
+            h.custo = Some(item.custo_unitario);
+            h.nfe = Some(identificacao.clone());
+            h.fornecedor = Some(nota.emitente.clone());
+            Ok::<_, ErroDominio>(())
+        });
+        if let Err(e) = lancado {
+            resultado.ignorados.push((item.clone(), e.to_string()));
+            continue;
+        }
+        if let Some(r) = relogios.get_mut(&codigo).filter(|r| r.moeda.is_none()) {
+            r.custo = Some(item.custo_unitario);
+        }
+        resultado
+            .lancados
+            .push((codigo, item.quantidade as i32, item.custo_unitario));
+    }
+    Ok(resultado)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Local;
+
+    const XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<nfeProc xmlns="http://www.portalfiscal.inf.br/nfe" versao="4.00">
+  <NFe><infNFe Id="NFe35240512345678000199550010000045211000045217" versao="4.00">
+    <ide><serie>1</serie><nNF>4521</nNF></ide>
+    <emit><CNPJ>12345678000199</CNPJ><xNome>SEIKO DO BRASIL LTDA</xNome></emit>
+    <det nItem="1"><prod>
+      <cProd>SRPD55</cProd><cEAN>SEM GTIN</cEAN><xProd>SEIKO 5 SPORTS</xProd>
+      <qCom>2.0000</qCom><vUnCom>880.5000000000</vUnCom>
+    </prod></det>
+    <det nItem="2"><prod>
+      <cProd>X-77</cProd><cEAN>4006381333931</cEAN><xProd>ORIENT BAMBINO</xProd>
+      <qCom>1.0000</qCom><vUnCom>690.00</vUnCom>
+    </prod></det>
+    <det nItem="3"><prod>
+      <cProd>PULS-20</cProd><cEAN>SEM GTIN</cEAN><xProd>PULSEIRA COURO</xProd>
+      <qCom>5.0000</qCom><vUnCom>30.00</vUnCom>
+    </prod></det>
+  </infNFe></NFe>
+</nfeProc>"#;
+
+    #[test]
+    fn importa_itens_casados_com_custo_e_nota() {
+        let nota = le(XML).unwrap();
+        assert_eq!(nota.identificacao(), "4521/1");
+        assert_eq!(nota.itens.len(), 3);
+        assert_eq!(nota.itens[0].ean, None);
+
+        let mut relogios = HashMap::new();
+        for (codigo, ean) in [("R002", None), ("R003", Some("4006381333931"))] {
+            relogios.insert(codigo.to_string(), {
+                let mut r = Relogio::novo(codigo, Quantidade::ZERO);
+                r.ean = ean.map(str::to_string);
+                r
+            });
+        }
+        let referencias = vec![fornecedores::Referencia {
+            fornecedor: "Seiko".into(),
+            codigo_fornecedor: "SRPD55".into(),
+            codigo: "R002".into(),
+        }];
+        let mut historico = vec![];
+        let agora = Local::now().naive_local();
+        let r = importa(&nota, &mut relogios, &mut historico, &referencias, agora).unwrap();
+        assert_eq!(
+            r.lancados,
+            [
+                ("R002".to_string(), 2, 880.5),
+                ("R003".to_string(), 1, 690.0)
+            ]
+        );
+        assert_eq!(r.ignorados.len(), 1);
+        assert_eq!(relogios["R002"].quantidade(), 2);
+        assert_eq!(relogios["R002"].custo, Some(880.5));
+        assert_eq!(historico[0].nfe.as_deref(), Some("4521/1"));
+        assert_eq!(historico[1].custo, Some(690.0));
+
+        assert!(importa(&nota, &mut relogios, &mut historico, &referencias, agora).is_err());
+        assert_eq!(historico.len(), 2);
+    }
+}
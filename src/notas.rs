@@ -0,0 +1,141 @@
+use crate::numeros::FormatoNumero;
+use crate::pdf;
+use crate::persistencia::na_pasta_dados;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+const DIRETORIO_NOTAS: &str = "notas";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemNota {
+    pub codigo: String,
+    pub descricao: String,
+    pub quantidade: i32,
+    pub preco_unitario: f64,
+}
+
+/// Nota de venda numerada sequencialmente, com os campos que a NF-e pede.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotaFiscal {
+    pub numero: u32,
+    pub timestamp: String,
+    pub cliente_nome: String,
+    /// CPF ou CNPJ do cliente, só dígitos.
+    pub cliente_documento: String,
+    pub itens: Vec<ItemNota>,
+    /// Alíquota aproximada de tributos (%), conforme a Lei 12.741.
+    pub aliquota: f64,
+}
+
+impl NotaFiscal {
+    pub fn total(&self) -> f64 {
+        self.itens
+            .iter()
+            .map(|i| i.preco_unitario * i.quantidade as f64)
+            .sum()
+    }
+
+    pub fn caminho(&self) -> String {
+        na_pasta_dados(DIRETORIO_NOTAS)
+            .join(format!("nota_{:06}.pdf", self.numero))
+            .display()
+            .to_string()
+    }
+
+    fn linhas(&self, formato: FormatoNumero) -> Vec<String> {
+        let mut linhas = vec![
+            format!("# NOTA DE VENDA Nº {:06}", self.numero),
+            format!("Emissão: {}", self.timestamp),
+            String::new(),
+            "# Destinatário".to_string(),
+            format!(
+                "Nome: {}",
+                if self.cliente_nome.is_empty() {
+                    "Consumidor final"
+                } else {
+                    &self.cliente_nome
+                }
+            ),
+            format!("CPF/CNPJ: {}", formata_documento(&self.cliente_documento)),
+            String::new(),
+            "# Itens".to_string(),
+            format!(
+                "{:<16} {:<30} {:>5} {:>14} {:>14}",
+                "Código", "Descrição", "Qtd", "Unitário", "Total"
+            ),
+        ];
+        for i in &self.itens {
+            linhas.push(format!(
+                "{:<16} {:<30} {:>5} {:>14} {:>14}",
+                i.codigo,
+                i.descricao.chars().take(30).collect::<String>(),
+                formato.inteiro(i.quantidade as i64),
+                formato.moeda(i.preco_unitario),
+                formato.moeda(i.preco_unitario * i.quantidade as f64)
+            ));
+        }
+        let total = self.total();
+        linhas.push(String::new());
+        linhas.push(format!("# Total: {}", formato.moeda(total)));
+        linhas.push(format!(
+            "Tributos aproximados ({}%): {}",
+            formato.numero(self.aliquota, 2),
+            formato.moeda(total * self.aliquota / 100.0)
+        ));
+        linhas
+    }
+
+    /// Grava (ou regrava, na reimpressão) o PDF em `notas/`.
+    pub fn gravar_pdf(&self, formato: FormatoNumero) -> std::io::Result<String> {
+        fs::create_dir_all(na_pasta_dados(DIRETORIO_NOTAS))?;
+        let caminho = self.caminho();
+        fs::write(&caminho, pdf::documento(&self.linhas(formato)))?;
+        Ok(caminho)
+    }
+}
+
+fn digito_verificador(digitos: &[u32], pesos: &[u32]) -> u32 {
+    let soma: u32 = digitos.iter().zip(pesos).map(|(d, p)| d * p).sum();
+    match soma % 11 {
+        0 | 1 => 0,
+        r => 11 - r,
+    }
+}
+
+/// Valida CPF (11 dígitos) ou CNPJ (14 dígitos) pelos dígitos verificadores
+/// e devolve só os dígitos.
+pub fn valida_documento(doc: &str) -> Option<String> {
+    let digitos: Vec<u32> = doc.chars().filter_map(|c| c.to_digit(10)).collect();
+    if digitos.windows(2).all(|w| w[0] == w[1]) {
+        return None;
+    }
+    let ok = match digitos.len() {
+        11 => {
+            let pesos: Vec<u32> = (2..=11).rev().collect();
+            digito_verificador(&digitos[..9], &pesos[1..]) == digitos[9]
+                && digito_verificador(&digitos[..10], &pesos) == digitos[10]
+        }
+        14 => {
+            let pesos = [6, 5, 4, 3, 2, 9, 8, 7, 6, 5, 4, 3, 2];
+            digito_verificador(&digitos[..12], &pesos[1..]) == digitos[12]
+                && digito_verificador(&digitos[..13], &pesos) == digitos[13]
+        }
+        _ => false,
+    };
+    ok.then(|| digitos.iter().map(|d| d.to_string()).collect())
+}
+
+fn formata_documento(doc: &str) -> String {
+    match doc.len() {
+        11 => format!("{}.{}.{}-{}", &doc[..3], &doc[3..6], &doc[6..9], &doc[9..]),
+        14 => format!(
+            "{}.{}.{}/{}-{}",
+            &doc[..2],
+            &doc[2..5],
+            &doc[5..8],
+            &doc[8..12],
+            &doc[12..]
+        ),
+        _ => "-".into(),
+    }
+}
@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+
+/// Quantas notificações ficam guardadas; as mais antigas saem primeiro.
+const MAXIMO: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Tipo {
+    EstoqueBaixo,
+    Sincronia,
+    ListaEspera,
+    Lembrete,
+    Aniversario,
+    Pedido,
+}
+
+impl Tipo {
+    pub fn rotulo(self) -> &'static str {
+        match self {
+            Tipo::EstoqueBaixo => "Estoque",
+            Tipo::Sincronia => "Sincronia",
+            Tipo::ListaEspera => "Espera",
+            Tipo::Lembrete => "Lembrete",
+            Tipo::Aniversario => "Aniversário",
+            Tipo::Pedido => "Pedido",
+        }
+    }
+}
+
+/// Aviso do sistema guardado até ser visto na central de notificações.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notificacao {
+    pub tipo: Tipo,
+    /// Identifica avisos gerados de novo a cada abertura (lembrete do dia,
+    /// conflito pendente) para não repeti-los; vazia nos avisos de evento.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub chave: String,
+    pub texto: String,
+    pub timestamp: String,
+    #[serde(default)]
+    pub lida: bool,
+}
+
+/// Acrescenta a notificação, a menos que já exista uma com a mesma chave.
+/// Devolve se foi acrescentada.
+pub fn adiciona(lista: &mut Vec<Notificacao>, nova: Notificacao) -> bool {
+    if !nova.chave.is_empty() && lista.iter().any(|n| n.chave == nova.chave) {
+        return false;
+    }
+    lista.push(nova);
+    if lista.len() > MAXIMO {
+        // Descarta primeiro as já lidas
+        let excesso = lista.len() - MAXIMO;
+        let mut lidas = 0;
+        lista.retain(|n| {
+            let sai = n.lida && lidas < excesso;
+            lidas += usize::from(sai);
+            !sai
+        });
+        let resto = lista.len().saturating_sub(MAXIMO);
+        lista.drain(..resto);
+    }
+    true
+}
+
+pub fn nao_lidas(lista: &[Notificacao]) -> usize {
+    lista.iter().filter(|n| !n.lida).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn aviso(chave: &str, lida: bool) -> Notificacao {
+        Notificacao {
+            tipo: Tipo::Lembrete,
+            chave: chave.into(),
+            texto: "revisar vitrine".into(),
+            timestamp: "2024-05-01 09:00:00".into(),
+            lida,
+        }
+    }
+
+    #[test]
+    fn nao_repete_chave_e_descarta_lidas_antes() {
+        let mut lista = vec![];
+        assert!(adiciona(&mut lista, aviso("lembrete-1", false)));
+        assert!(!adiciona(&mut lista, aviso("lembrete-1", false)));
+        assert!(adiciona(&mut lista, aviso("", false)));
+        assert!(adiciona(&mut lista, aviso("", false)));
+        assert_eq!(nao_lidas(&lista), 3);
+
+        lista[1].lida = true;
+        for _ in 0..MAXIMO - 2 {
+            adiciona(&mut lista, aviso("", false));
+        }
+        assert_eq!(lista.len(), MAXIMO);
+        assert!(lista.iter().all(|n| !n.lida));
+        assert_eq!(lista[0].chave, "lembrete-1");
+    }
+}
@@ -0,0 +1,145 @@
+use serde::{Deserialize, Serialize};
+
+/// Convenção regional de separadores para exibir e ler números.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum FormatoNumero {
+    /// `1.234,56`
+    #[default]
+    #[serde(rename = "pt-BR")]
+    PtBr,
+    /// `1,234.56`
+    #[serde(rename = "en-US")]
+    EnUs,
+}
+
+impl FormatoNumero {
+    pub fn parse(texto: &str) -> Option<Self> {
+        match texto.trim().to_lowercase().as_str() {
+            "pt-br" | "pt" => Some(FormatoNumero::PtBr),
+            "en-us" | "en" => Some(FormatoNumero::EnUs),
+            _ => None,
+        }
+    }
+
+    pub fn nome(self) -> &'static str {
+        match self {
+            FormatoNumero::PtBr => "pt-BR",
+            FormatoNumero::EnUs => "en-US",
+        }
+    }
+
+    /// Separadores de milhar e decimal.
+    fn separadores(self) -> (char, char) {
+        match self {
+            FormatoNumero::PtBr => ('.', ','),
+            FormatoNumero::EnUs => (',', '.'),
+        }
+    }
+
+    fn agrupa(self, digitos: &str) -> String {
+        let (milhar, _) = self.separadores();
+        let mut out = String::new();
+        for (i, c) in digitos.chars().enumerate() {
+            if i > 0 && (digitos.len() - i).is_multiple_of(3) {
+                out.push(milhar);
+            }
+            out.push(c);
+        }
+        out
+    }
+
+    /// Número com `casas` decimais e separador de milhar: `1.234,50`.
+    pub fn numero(self, valor: f64, casas: usize) -> String {
+        // Somas vazias e arredondamentos podem dar -0,00
+        let texto = format!("{:.*}", casas, valor + 0.0);
+        let (sinal, texto) = match texto.strip_prefix('-') {
+            Some(t) if t.chars().any(|c| c.is_ascii_digit() && c != '0') => ("-", t),
+            Some(t) => ("", t),
+            None => ("", texto.as_str()),
+        };
+        let (inteira, fracao) = texto.split_once('.').unwrap_or((texto, ""));
+        let mut out = format!("{}{}", sinal, self.agrupa(inteira));
+        if !fracao.is_empty() {
+            out.push(self.separadores().1);
+            out.push_str(fracao);
+        }
+        out
+    }
+
+    /// Quantidade inteira com separador de milhar: `1.234`.
+    pub fn inteiro(self, valor: i64) -> String {
+        let sinal = if valor < 0 { "-" } else { "" };
+        format!(
+            "{}{}",
+            sinal,
+            self.agrupa(&valor.unsigned_abs().to_string())
+        )
+    }
+
+    /// Valor em reais: `R$ 1.234,50`.
+    pub fn moeda(self, valor: f64) -> String {
+        format!("R$ {}", self.numero(valor, 2))
+    }
+
+    /// Tira os separadores de milhar quando o texto os usa em grupos de três
+    /// dígitos; senão (ex.: "12.5" em pt-BR) o ponto é tratado como decimal.
+    fn normaliza(self, texto: &str) -> String {
+        let (milhar, decimal) = self.separadores();
+        let texto = texto.trim().trim_start_matches("R$").trim();
+        let (inteira, fracao) = match texto.split_once(decimal) {
+            Some((i, f)) => (i, Some(f)),
+            None => (texto, None),
+        };
+        let grupos: Vec<&str> = inteira.split(milhar).collect();
+        let agrupado = grupos.len() > 1
+            && grupos[1..].iter().all(|g| g.len() == 3)
+            && !grupos[0].trim_start_matches('-').is_empty();
+        let inteira = if agrupado {
+            grupos.concat()
+        } else if fracao.is_none() {
+            inteira.replace(milhar, ".")
+        } else {
+            inteira.to_string()
+        };
+        match fracao {
+            Some(f) => format!("{}.{}", inteira, f),
+            None => inteira,
+        }
+    }
+
+    /// Lê um número digitado no formato regional (`1.234,56`), aceitando
+    /// também o ponto decimal sem milhar (`12.5`).
+    pub fn interpreta(self, texto: &str) -> Option<f64> {
+        self.normaliza(texto).parse().ok()
+    }
+
+    /// Lê uma quantidade inteira, com ou sem separador de milhar.
+    pub fn interpreta_inteiro(self, texto: &str) -> Option<i32> {
+        self.normaliza(texto).parse().ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formata_e_le_de_volta_nos_dois_formatos() {
+        let br = FormatoNumero::PtBr;
+        let us = FormatoNumero::EnUs;
+        assert_eq!(br.moeda(1234567.5), "R$ 1.234.567,50");
+        assert_eq!(us.moeda(1234567.5), "R$ 1,234,567.50");
+        assert_eq!(br.moeda(-0.001), "R$ 0,00");
+        assert_eq!(br.moeda(-12.5), "R$ -12,50");
+        assert_eq!(br.inteiro(-1234), "-1.234");
+        assert_eq!(us.inteiro(999), "999");
+        assert_eq!(br.interpreta("1.234,56"), Some(1234.56));
+        assert_eq!(br.interpreta("R$ 1.234"), Some(1234.0));
+        assert_eq!(br.interpreta("12.5"), Some(12.5));
+        assert_eq!(us.interpreta("1,234.56"), Some(1234.56));
+        assert_eq!(us.interpreta("12,5"), Some(12.5));
+        assert_eq!(br.interpreta_inteiro("1.234"), Some(1234));
+        assert_eq!(us.interpreta_inteiro("1,234"), Some(1234));
+        assert_eq!(br.interpreta_inteiro("abc"), None);
+    }
+}
@@ -0,0 +1,222 @@
+use crate::Relogio;
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Dias até o retorno quando o orçamento é criado sem data.
+pub const RETORNO_PADRAO: i64 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Situacao {
+    Aberto,
+    Ganho,
+    Perdido,
+}
+
+/// Orçamento passado a um cliente, acompanhado até virar venda ou ser
+/// perdido.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Orcamento {
+    pub numero: u32,
+    pub cliente: String,
+    #[serde(default)]
+    pub contato: String,
+    /// Código e quantidade.
+    pub itens: Vec<(String, i32)>,
+    /// Valor pelos preços do dia do orçamento.
+    pub valor: f64,
+    /// Data do orçamento (AAAA-MM-DD).
+    pub data: String,
+    /// Próximo contato combinado (AAAA-MM-DD).
+    pub retorno: String,
+    pub situacao: Situacao,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub motivo: Option<String>,
+    /// Data em que foi ganho ou perdido.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fechado: Option<String>,
+}
+
+/// Data de retorno: em N dias, `DD/MM` (a próxima) ou `AAAA-MM-DD`.
+pub fn interpreta_retorno(texto: &str, hoje: NaiveDate) -> Option<NaiveDate> {
+    let texto = texto.trim();
+    if let Ok(dias) = texto.parse::<i64>() {
+        return (dias >= 0).then(|| hoje + Duration::days(dias));
+    }
+    if let Ok(data) = NaiveDate::parse_from_str(texto, "%Y-%m-%d") {
+        return Some(data);
+    }
+    let data = NaiveDate::parse_from_str(&format!("{}/{}", texto, hoje.year()), "%d/%m/%Y").ok()?;
+    if data >= hoje {
+        Some(data)
+    } else {
+        data.with_year(hoje.year() + 1)
+    }
+}
+
+/// Cria o orçamento a partir de `cliente;contato;codigo:qtd ...[;retorno]`,
+/// com o valor pelos preços atuais. Devolve o número.
+pub fn cria(
+    lista: &mut Vec<Orcamento>,
+    texto: &str,
+    relogios: &HashMap<String, Relogio>,
+    hoje: NaiveDate,
+) -> Result<u32, String> {
+    const USO: &str =
+        "Formato: cliente;contato;codigo:qtd [codigo:qtd ...][;retorno em dias ou DD/MM]";
+    let campos: Vec<&str> = texto.split(';').map(str::trim).collect();
+    let (Some(cliente), Some(contato), Some(produtos)) = (
+        campos.first().filter(|c| !c.is_empty()),
+        campos.get(1),
+        campos.get(2),
+    ) else {
+        return Err(USO.into());
+    };
+    let mut itens: Vec<(String, i32)> = vec![];
+    let mut valor = 0.0;
+    for termo in produtos.split_whitespace() {
+        let (codigo, qtd) = termo.split_once(':').unwrap_or((termo, "1"));
+        let qtd = qtd
+            .parse::<i32>()
+            .ok()
+            .filter(|q| *q > 0)
+            .ok_or_else(|| format!("Quantidade inválida em {}.", termo))?;
+        let r = relogios
+            .get(codigo)
+            .ok_or_else(|| format!("Relógio {} não encontrado!", codigo))?;
+        valor += r.preco.unwrap_or(0.0) * qtd as f64;
+        itens.push((codigo.to_string(), qtd));
+    }
+    if itens.is_empty() {
+        return Err(USO.into());
+    }
+    let retorno = match campos.get(3).filter(|r| !r.is_empty()) {
+        Some(r) => interpreta_retorno(r, hoje).ok_or("Retorno inválido: use dias ou DD/MM.")?,
+        None => hoje + Duration::days(RETORNO_PADRAO),
+    };
+    let numero = lista.iter().map(|o| o.numero).max().unwrap_or(0) + 1;
+    lista.push(Orcamento {
+        numero,
+        cliente: cliente.to_string(),
+        contato: contato.to_string(),
+        itens,
+        valor,
+        data: hoje.format("%Y-%m-%d").to_string(),
+        retorno: retorno.format("%Y-%m-%d").to_string(),
+        situacao: Situacao::Aberto,
+        motivo: None,
+        fechado: None,
+    });
+    Ok(numero)
+}
+
+/// Orçamentos em aberto na ordem de ligação: retornos vencidos e de hoje
+/// primeiro, do mais atrasado ao mais novo.
+pub fn fila(lista: &[Orcamento]) -> Vec<&Orcamento> {
+    let mut abertos: Vec<&Orcamento> = lista
+        .iter()
+        .filter(|o| o.situacao == Situacao::Aberto)
+        .collect();
+    abertos.sort_by(|a, b| a.retorno.cmp(&b.retorno).then(a.numero.cmp(&b.numero)));
+    abertos
+}
+
+/// Registra o desfecho (`motivo` só nos perdidos).
+pub fn fecha(o: &mut Orcamento, situacao: Situacao, motivo: Option<String>, hoje: NaiveDate) {
+    o.situacao = situacao;
+    o.motivo = motivo.filter(|m| !m.trim().is_empty());
+    o.fechado = Some(hoje.format("%Y-%m-%d").to_string());
+}
+
+/// Orçamentos por mês de criação: criados, ganhos, perdidos e a taxa de
+/// conversão (ganhos sobre os já decididos).
+#[derive(Debug, Default, PartialEq)]
+pub struct Mes {
+    pub criados: usize,
+    pub ganhos: usize,
+    pub perdidos: usize,
+    pub valor_ganho: f64,
+}
+
+impl Mes {
+    pub fn conversao(&self) -> Option<f64> {
+        let decididos = self.ganhos + self.perdidos;
+        (decididos > 0).then(|| 100.0 * self.ganhos as f64 / decididos as f64)
+    }
+}
+
+pub fn por_mes(lista: &[Orcamento]) -> BTreeMap<String, Mes> {
+    let mut meses: BTreeMap<String, Mes> = BTreeMap::new();
+    for o in lista {
+        let m = meses
+            .entry(o.data.get(..7).unwrap_or(&o.data).to_string())
+            .or_default();
+        m.criados += 1;
+        match o.situacao {
+            Situacao::Ganho => {
+                m.ganhos += 1;
+                m.valor_ganho += o.valor;
+            }
+            Situacao::Perdido => m.perdidos += 1,
+            Situacao::Aberto => {}
+        }
+    }
+    meses
+}
+
+/// Motivos de perda mais comuns, com a contagem.
+pub fn motivos_de_perda(lista: &[Orcamento]) -> Vec<(String, usize)> {
+    let mut contagem: BTreeMap<String, usize> = BTreeMap::new();
+    for o in lista.iter().filter(|o| o.situacao == Situacao::Perdido) {
+        let motivo = o
+            .motivo
+            .as_deref()
+            .map_or("(sem motivo)".to_string(), str::to_lowercase);
+        *contagem.entry(motivo).or_default() += 1;
+    }
+    let mut lista: Vec<(String, usize)> = contagem.into_iter().collect();
+    lista.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    lista
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dominio::Quantidade;
+
+    #[test]
+    fn fila_desfecho_e_conversao() {
+        let mut relogios = HashMap::new();
+        relogios.insert("R001".to_string(), {
+            let mut r = Relogio::novo("R001", Quantidade::ZERO);
+            r.preco = Some(250.0);
+            r
+        });
+        let hoje = NaiveDate::from_ymd_opt(2024, 12, 30).unwrap();
+        assert_eq!(
+            interpreta_retorno("02/01", hoje),
+            NaiveDate::from_ymd_opt(2025, 1, 2)
+        );
+        let mut lista = vec![];
+        assert_eq!(
+            cria(&mut lista, "Ana;1199;R001:2;05/01", &relogios, hoje),
+            Ok(1)
+        );
+        assert_eq!(cria(&mut lista, "Bruno;;R001", &relogios, hoje), Ok(2));
+        assert!(cria(&mut lista, "Carla;;R404:1", &relogios, hoje).is_err());
+        assert!(cria(&mut lista, ";;R001:1", &relogios, hoje).is_err());
+        assert_eq!(lista[0].valor, 500.0);
+        assert_eq!(lista[1].retorno, "2025-01-02");
+        assert_eq!(fila(&lista)[0].numero, 2);
+
+        fecha(&mut lista[1], Situacao::Perdido, Some("Preço".into()), hoje);
+        assert_eq!(fila(&lista).len(), 1);
+        cria(&mut lista, "Davi;;R001:1;0", &relogios, hoje).unwrap();
+        fecha(&mut lista[2], Situacao::Ganho, None, hoje);
+        let meses = por_mes(&lista);
+        let dezembro = &meses["2024-12"];
+        assert_eq!((dezembro.criados, dezembro.valor_ganho), (3, 250.0));
+        assert_eq!(dezembro.conversao(), Some(50.0));
+        assert_eq!(motivos_de_perda(&lista), [("preço".to_string(), 1)]);
+    }
+}
@@ -0,0 +1,71 @@
+/// Converte para WinAnsi (Latin-1) e escapa os delimitadores de string do PDF.
+fn texto_pdf(texto: &str) -> Vec<u8> {
+    let mut out = vec![];
+    for c in texto.chars() {
+        let b = if (c as u32) < 256 { c as u8 } else { b'?' };
+        if matches!(b, b'(' | b')' | b'\\') {
+            out.push(b'\\');
+        }
+        out.push(b);
+    }
+    out
+}
+
+/// Monta um PDF de uma página A4 (Helvetica) com as linhas dadas, de cima
+/// para baixo. Linhas iniciadas por `#` saem em negrito e maiores.
+pub fn documento(linhas: &[String]) -> Vec<u8> {
+    let mut conteudo: Vec<u8> = b"BT\n".to_vec();
+    let mut y = 800;
+    for linha in linhas {
+        let (fonte, tamanho, texto) = match linha.strip_prefix('#') {
+            Some(t) => ("F2", 14, t.trim_start()),
+            None => ("F1", 10, linha.as_str()),
+        };
+        conteudo.extend(format!("/{} {} Tf 1 0 0 1 50 {} Tm (", fonte, tamanho, y).bytes());
+        conteudo.extend(texto_pdf(texto));
+        conteudo.extend(b") Tj\n");
+        y -= tamanho + 4;
+    }
+    conteudo.extend(b"ET\n");
+
+    let objetos: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        b"<< /Type /Pages /Kids [3 0 R] /Count 1 >>".to_vec(),
+        b"<< /Type /Page /Parent 2 0 R /MediaBox [0 0 595 842] \
+           /Resources << /Font << /F1 4 0 R /F2 5 0 R >> >> /Contents 6 0 R >>"
+            .to_vec(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>"
+            .to_vec(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>"
+            .to_vec(),
+        [
+            format!("<< /Length {} >>\nstream\n", conteudo.len()).into_bytes(),
+            conteudo,
+            b"endstream".to_vec(),
+        ]
+        .concat(),
+    ];
+
+    let mut pdf: Vec<u8> = b"%PDF-1.4\n".to_vec();
+    let mut offsets = vec![];
+    for (i, obj) in objetos.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend(format!("{} 0 obj\n", i + 1).bytes());
+        pdf.extend(obj);
+        pdf.extend(b"\nendobj\n");
+    }
+    let xref = pdf.len();
+    pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objetos.len() + 1).bytes());
+    for o in offsets {
+        pdf.extend(format!("{:010} 00000 n \n", o).bytes());
+    }
+    pdf.extend(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objetos.len() + 1,
+            xref
+        )
+        .bytes(),
+    );
+    pdf
+}
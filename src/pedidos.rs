@@ -0,0 +1,598 @@
+use crate::numeros::FormatoNumero;
+use crate::Historico;
+use chrono::{Duration, Months, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Linha do pedido de compra: o que foi pedido e o que já chegou.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ItemPedido {
+    pub codigo: String,
+    pub quantidade: i32,
+    #[serde(default)]
+    pub recebido: i32,
+    /// Unidades que o fornecedor não vai mais entregar.
+    #[serde(default)]
+    pub cancelado: i32,
+}
+
+impl ItemPedido {
+    pub fn falta(&self) -> i32 {
+        (self.quantidade - self.recebido - self.cancelado).max(0)
+    }
+}
+
+/// Parte de um pedido que chegou numa entrega.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entrega {
+    pub data: String,
+    pub codigo: String,
+    pub quantidade: i32,
+}
+
+/// Pedido de compra feito a um fornecedor. Fica aberto enquanto faltar
+/// alguma unidade; fecha sozinho quando a última chega.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PedidoCompra {
+    pub numero: u32,
+    pub fornecedor: String,
+    /// Data do pedido (AAAA-MM-DD), base do tempo em aberto.
+    pub data: String,
+    pub itens: Vec<ItemPedido>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub entregas: Vec<Entrega>,
+    /// Data em que foi completado (ou teve o saldo cancelado).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fechado: Option<String>,
+}
+
+impl PedidoCompra {
+    fn fecha_se_completo(&mut self, data: &str) {
+        if self.fechado.is_none() && self.itens.iter().all(|i| i.falta() == 0) {
+            self.fechado = Some(data.to_string());
+        }
+    }
+}
+
+/// Lê `fornecedor codigo:qtd [codigo:qtd ...]`; o fornecedor pode ter
+/// espaços. Códigos repetidos somam.
+pub fn interpreta(texto: &str) -> Result<(String, Vec<ItemPedido>), String> {
+    const USO: &str = "Formato: fornecedor codigo:qtd [codigo:qtd ...]";
+    let mut fornecedor = vec![];
+    let mut itens: Vec<ItemPedido> = vec![];
+    for termo in texto.split_whitespace() {
+        let Some((codigo, qtd)) = termo.split_once(':') else {
+            fornecedor.push(termo);
+            continue;
+        };
+        let quantidade = qtd
+            .parse::<i32>()
+            .ok()
+            .filter(|q| *q > 0)
+            .ok_or_else(|| format!("Quantidade inválida em {}.", termo))?;
+        match itens.iter_mut().find(|i| i.codigo == codigo) {
+            Some(i) => i.quantidade += quantidade,
+            None => itens.push(ItemPedido {
+                codigo: codigo.to_string(),
+                quantidade,
+                recebido: 0,
+                cancelado: 0,
+            }),
+        }
+    }
+    if fornecedor.is_empty() || itens.is_empty() {
+        return Err(USO.into());
+    }
+    Ok((fornecedor.join(" "), itens))
+}
+
+/// Registra o pedido e devolve o número dele.
+pub fn abre(
+    pedidos: &mut Vec<PedidoCompra>,
+    fornecedor: &str,
+    itens: Vec<ItemPedido>,
+    data: &str,
+) -> u32 {
+    let numero = pedidos.iter().map(|p| p.numero).max().unwrap_or(0) + 1;
+    pedidos.push(PedidoCompra {
+        numero,
+        fornecedor: fornecedor.to_string(),
+        data: data.to_string(),
+        itens,
+        entregas: vec![],
+        fechado: None,
+    });
+    numero
+}
+
+/// Frequência de um pedido recorrente.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Cadencia {
+    Semanal,
+    Quinzenal,
+    Mensal,
+}
+
+impl Cadencia {
+    pub fn interpreta(texto: &str) -> Option<Cadencia> {
+        match texto.to_lowercase().as_str() {
+            "semanal" => Some(Cadencia::Semanal),
+            "quinzenal" => Some(Cadencia::Quinzenal),
+            "mensal" => Some(Cadencia::Mensal),
+            _ => None,
+        }
+    }
+
+    pub fn rotulo(self) -> &'static str {
+        match self {
+            Cadencia::Semanal => "semanal",
+            Cadencia::Quinzenal => "quinzenal",
+            Cadencia::Mensal => "mensal",
+        }
+    }
+
+    /// Ciclo seguinte a `data`.
+    pub fn seguinte(self, data: NaiveDate) -> NaiveDate {
+        match self {
+            Cadencia::Semanal => data + Duration::days(7),
+            Cadencia::Quinzenal => data + Duration::days(14),
+            Cadencia::Mensal => data + Months::new(1),
+        }
+    }
+}
+
+/// Pedido que se repete (ex.: 20 pulseiras do mesmo fornecedor todo mês).
+/// A cada ciclo vira um rascunho, que só vira pedido quando confirmado.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Recorrente {
+    pub numero: u32,
+    pub fornecedor: String,
+    pub itens: Vec<ItemPedido>,
+    pub cadencia: Cadencia,
+    /// Próximo ciclo (AAAA-MM-DD).
+    pub proxima: String,
+}
+
+/// Pedido gerado por um recorrente, esperando confirmação.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rascunho {
+    pub recorrente: u32,
+    /// Ciclo a que se refere (AAAA-MM-DD).
+    pub ciclo: String,
+    pub fornecedor: String,
+    pub itens: Vec<ItemPedido>,
+}
+
+/// Lê `cadencia fornecedor codigo:qtd ...`; sem cadência na frente devolve
+/// `None` (é um pedido comum).
+pub fn interpreta_recorrente(
+    texto: &str,
+) -> Result<Option<(Cadencia, String, Vec<ItemPedido>)>, String> {
+    let (primeiro, resto) = texto.trim().split_once(' ').unwrap_or((texto.trim(), ""));
+    let Some(cadencia) = Cadencia::interpreta(primeiro) else {
+        return Ok(None);
+    };
+    let (fornecedor, itens) = interpreta(resto)?;
+    Ok(Some((cadencia, fornecedor, itens)))
+}
+
+/// Registra o recorrente, com o primeiro ciclo um período depois de `hoje`.
+pub fn cria_recorrente(
+    recorrentes: &mut Vec<Recorrente>,
+    cadencia: Cadencia,
+    fornecedor: &str,
+    itens: Vec<ItemPedido>,
+    hoje: NaiveDate,
+) -> u32 {
+    let numero = recorrentes.iter().map(|r| r.numero).max().unwrap_or(0) + 1;
+    recorrentes.push(Recorrente {
+        numero,
+        fornecedor: fornecedor.to_string(),
+        itens,
+        cadencia,
+        proxima: cadencia.seguinte(hoje).format("%Y-%m-%d").to_string(),
+    });
+    numero
+}
+
+/// Gera o rascunho de cada recorrente cujo ciclo chegou e avança o próximo
+/// ciclo. Ciclos vencidos com o programa fechado viram um só rascunho, do
+/// último ciclo, que também substitui um rascunho anterior ainda sem
+/// resposta. Devolve os rascunhos novos.
+pub fn gera_rascunhos(
+    recorrentes: &mut [Recorrente],
+    rascunhos: &mut Vec<Rascunho>,
+    hoje: NaiveDate,
+) -> Vec<Rascunho> {
+    let mut novos = vec![];
+    for r in recorrentes.iter_mut() {
+        let Ok(mut ciclo) = NaiveDate::parse_from_str(&r.proxima, "%Y-%m-%d") else {
+            continue;
+        };
+        if ciclo > hoje {
+            continue;
+        }
+        while r.cadencia.seguinte(ciclo) <= hoje {
+            ciclo = r.cadencia.seguinte(ciclo);
+        }
+        r.proxima = r.cadencia.seguinte(ciclo).format("%Y-%m-%d").to_string();
+        rascunhos.retain(|x| x.recorrente != r.numero);
+        let rascunho = Rascunho {
+            recorrente: r.numero,
+            ciclo: ciclo.format("%Y-%m-%d").to_string(),
+            fornecedor: r.fornecedor.clone(),
+            itens: r.itens.clone(),
+        };
+        rascunhos.push(rascunho.clone());
+        novos.push(rascunho);
+    }
+    novos
+}
+
+/// Confirma o rascunho na posição `indice` como pedido (com a data de
+/// `hoje`) ou, com `confirma` falso, pula o ciclo. Devolve o número do
+/// pedido aberto.
+pub fn responde_rascunho(
+    rascunhos: &mut Vec<Rascunho>,
+    pedidos: &mut Vec<PedidoCompra>,
+    indice: usize,
+    confirma: bool,
+    hoje: &str,
+) -> Option<u32> {
+    if indice >= rascunhos.len() {
+        return None;
+    }
+    let r = rascunhos.remove(indice);
+    confirma.then(|| abre(pedidos, &r.fornecedor, r.itens, hoje))
+}
+
+/// Abate a entrega dos pedidos abertos, do mais antigo para o mais novo.
+/// Com `origem` (fornecedor digitado, emitente da nota), só conta pedidos de
+/// um fornecedor cujo nome aparece nela. Unidades além do pedido ficam de
+/// fora. Devolve os números dos pedidos que fecharam.
+pub fn recebe(
+    pedidos: &mut [PedidoCompra],
+    origem: Option<&str>,
+    codigo: &str,
+    quantidade: i32,
+    data: &str,
+) -> Vec<u32> {
+    let origem = origem.map(str::to_lowercase);
+    let mut resta = quantidade;
+    let mut fechados = vec![];
+    for p in pedidos.iter_mut().filter(|p| {
+        p.fechado.is_none()
+            && origem
+                .as_ref()
+                .is_none_or(|o| o.contains(&p.fornecedor.to_lowercase()))
+    }) {
+        if resta <= 0 {
+            break;
+        }
+        let Some(item) = p.itens.iter_mut().find(|i| i.codigo == codigo) else {
+            continue;
+        };
+        let parte = item.falta().min(resta);
+        if parte == 0 {
+            continue;
+        }
+        item.recebido += parte;
+        resta -= parte;
+        p.entregas.push(Entrega {
+            data: data.to_string(),
+            codigo: codigo.to_string(),
+            quantidade: parte,
+        });
+        p.fecha_se_completo(data);
+        if p.fechado.is_some() {
+            fechados.push(p.numero);
+        }
+    }
+    fechados
+}
+
+/// Desiste do que falta de um item (o fornecedor não vai entregar).
+/// Devolve se o pedido fechou com isso.
+pub fn cancela_saldo(pedidos: &mut [PedidoCompra], numero: u32, codigo: &str, data: &str) -> bool {
+    let Some(p) = pedidos.iter_mut().find(|p| p.numero == numero) else {
+        return false;
+    };
+    if let Some(item) = p.itens.iter_mut().find(|i| i.codigo == codigo) {
+        item.cancelado += item.falta();
+    }
+    p.fecha_se_completo(data);
+    p.fechado.is_some()
+}
+
+/// Unidades de cada código que ainda faltam chegar de pedidos abertos.
+pub fn em_pedido(pedidos: &[PedidoCompra]) -> HashMap<String, i32> {
+    let mut total: HashMap<String, i32> = HashMap::new();
+    for item in pedidos
+        .iter()
+        .filter(|p| p.fechado.is_none())
+        .flat_map(|p| &p.itens)
+    {
+        *total.entry(item.codigo.clone()).or_default() += item.falta();
+    }
+    total.retain(|_, q| *q > 0);
+    total
+}
+
+/// Item em falta de um pedido aberto.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pendencia {
+    pub numero: u32,
+    pub fornecedor: String,
+    pub codigo: String,
+    pub quantidade: i32,
+    pub recebido: i32,
+    /// Dias desde o pedido.
+    pub dias: i64,
+}
+
+/// Itens em falta, os mais antigos primeiro.
+pub fn pendencias(pedidos: &[PedidoCompra], hoje: NaiveDate) -> Vec<Pendencia> {
+    let mut lista: Vec<Pendencia> = pedidos
+        .iter()
+        .filter(|p| p.fechado.is_none())
+        .flat_map(|p| {
+            let dias =
+                NaiveDate::parse_from_str(&p.data, "%Y-%m-%d").map_or(0, |d| (hoje - d).num_days());
+            p.itens
+                .iter()
+                .filter(|i| i.falta() > 0)
+                .map(move |i| Pendencia {
+                    numero: p.numero,
+                    fornecedor: p.fornecedor.clone(),
+                    codigo: i.codigo.clone(),
+                    quantidade: i.quantidade,
+                    recebido: i.recebido,
+                    dias,
+                })
+        })
+        .collect();
+    lista.sort_by(|a, b| b.dias.cmp(&a.dias).then(a.numero.cmp(&b.numero)));
+    lista
+}
+
+fn dias_entre(inicio: &str, fim: &str) -> Option<i64> {
+    let data = |t: &str| NaiveDate::parse_from_str(t.get(..10)?, "%Y-%m-%d").ok();
+    Some((data(fim)? - data(inicio)?).num_days())
+}
+
+/// Desempenho dos fornecedores para decidir o próximo pedido: prazo médio
+/// de entrega (por unidade entregue), taxa de atendimento dos pedidos já
+/// fechados e o que ainda falta; depois, a evolução do custo unitário de
+/// cada produto por fornecedor nas compras com custo (NF-e).
+pub fn desempenho(
+    pedidos: &[PedidoCompra],
+    historico: &[Historico],
+    formato: FormatoNumero,
+) -> String {
+    #[derive(Default)]
+    struct Totais {
+        nome: String,
+        pedidos: usize,
+        dias_unidades: i64,
+        entregues: i64,
+        pedidas_fechados: i64,
+        recebidas_fechados: i64,
+        faltam: i64,
+    }
+    let mut por_fornecedor: BTreeMap<String, Totais> = BTreeMap::new();
+    for p in pedidos {
+        let t = por_fornecedor
+            .entry(p.fornecedor.to_lowercase())
+            .or_insert_with(|| Totais {
+                nome: p.fornecedor.clone(),
+                ..Default::default()
+            });
+        t.pedidos += 1;
+        for e in &p.entregas {
+            if let Some(dias) = dias_entre(&p.data, &e.data) {
+                t.dias_unidades += dias * e.quantidade as i64;
+                t.entregues += e.quantidade as i64;
+            }
+        }
+        for i in &p.itens {
+            t.faltam += i.falta() as i64;
+            if p.fechado.is_some() {
+                t.pedidas_fechados += i.quantidade as i64;
+                t.recebidas_fechados += i.recebido as i64;
+            }
+        }
+    }
+    let mut out = format!(
+        "Fornecedores\n{:<24}{:>8}{:>14}{:>14}{:>10}\n",
+        "", "pedidos", "prazo médio", "atendimento", "faltam"
+    );
+    for t in por_fornecedor.values() {
+        let prazo = match t.entregues {
+            0 => "—".to_string(),
+            n => format!("{} d", formato.numero(t.dias_unidades as f64 / n as f64, 1)),
+        };
+        let atendimento = match t.pedidas_fechados {
+            0 => "—".to_string(),
+            n => format!(
+                "{}%",
+                formato.numero(100.0 * t.recebidas_fechados as f64 / n as f64, 0)
+            ),
+        };
+        out.push_str(&format!(
+            "{:<24}{:>8}{:>14}{:>14}{:>10}\n",
+            t.nome,
+            t.pedidos,
+            prazo,
+            atendimento,
+            formato.inteiro(t.faltam)
+        ));
+    }
+
+    // (codigo, fornecedor) -> custos na ordem das compras
+    let mut custos: BTreeMap<(String, String), Vec<f64>> = BTreeMap::new();
+    for h in historico.iter().filter(|h| h.operacao == "COMPRA") {
+        if let (Some(custo), Some(fornecedor)) = (h.custo, &h.fornecedor) {
+            custos
+                .entry((h.codigo.clone(), fornecedor.clone()))
+                .or_default()
+                .push(custo);
+        }
+    }
+    out.push_str(&format!(
+        "\nCusto por produto (* menor último custo)\n{:<12}{:<24}{:>8}{:>14}{:>14}{:>10}\n",
+        "", "", "compras", "primeiro", "último", "variação"
+    ));
+    let menor: BTreeMap<&str, f64> = custos.iter().fold(BTreeMap::new(), |mut m, ((c, _), v)| {
+        let ultimo = v[v.len() - 1];
+        let e = m.entry(c.as_str()).or_insert(ultimo);
+        *e = e.min(ultimo);
+        m
+    });
+    for ((codigo, fornecedor), v) in &custos {
+        let (primeiro, ultimo) = (v[0], v[v.len() - 1]);
+        let variacao = if primeiro > 0.0 {
+            format!("{}%", formato.numero(100.0 * (ultimo / primeiro - 1.0), 1))
+        } else {
+            "—".into()
+        };
+        let marca = if menor.get(codigo.as_str()) == Some(&ultimo)
+            && custos.keys().filter(|(c, _)| c == codigo).count() > 1
+        {
+            "*"
+        } else {
+            ""
+        };
+        out.push_str(&format!(
+            "{:<12}{:<24}{:>8}{:>14}{:>14}{:>10}\n",
+            format!("{}{}", codigo, marca),
+            fornecedor,
+            v.len(),
+            formato.moeda(primeiro),
+            formato.moeda(ultimo),
+            variacao
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn entrega_parcial_deixa_saldo_e_fecha_ao_completar() {
+        let mut pedidos = vec![];
+        let (fornecedor, itens) = interpreta("Seiko Brasil R002:5 R003:2 R002:1").unwrap();
+        assert_eq!(itens[0].quantidade, 6);
+        assert_eq!(abre(&mut pedidos, &fornecedor, itens, "2024-03-01"), 1);
+        let (_, itens) = interpreta("Seiko Brasil R002:4").unwrap();
+        abre(&mut pedidos, "Seiko Brasil", itens, "2024-03-10");
+        assert!(interpreta("R002:5").is_err());
+        assert!(interpreta("Seiko R002:0").is_err());
+
+        // Entrega de outro fornecedor não abate
+        assert!(recebe(&mut pedidos, Some("Casio"), "R002", 6, "2024-03-12").is_empty());
+        assert!(recebe(
+            &mut pedidos,
+            Some("SEIKO BRASIL LTDA"),
+            "R003",
+            2,
+            "2024-03-12"
+        )
+        .is_empty());
+        let hoje = NaiveDate::from_ymd_opt(2024, 3, 15).unwrap();
+        assert_eq!(pendencias(&pedidos, hoje).len(), 2);
+
+        // 8 unidades: 6 fecham o primeiro pedido, 2 ficam no segundo
+        assert_eq!(recebe(&mut pedidos, None, "R002", 8, "2024-03-15"), [1]);
+        assert_eq!(pedidos[0].fechado.as_deref(), Some("2024-03-15"));
+        let p = pendencias(&pedidos, hoje);
+        assert_eq!(
+            (p.len(), p[0].numero, p[0].recebido, p[0].dias),
+            (1, 2, 2, 5)
+        );
+
+        assert!(cancela_saldo(&mut pedidos, 2, "R002", "2024-03-16"));
+        assert!(pendencias(&pedidos, hoje).is_empty());
+    }
+
+    #[test]
+    fn recorrente_vira_rascunho_a_cada_ciclo() {
+        let hoje = NaiveDate::from_ymd_opt(2024, 1, 31).unwrap();
+        let Ok(Some((cadencia, fornecedor, itens))) =
+            interpreta_recorrente("mensal Pulseiras SP PULS:20")
+        else {
+            panic!("recorrente não reconhecido");
+        };
+        assert_eq!(interpreta_recorrente("Seiko R002:1"), Ok(None));
+        let mut recorrentes = vec![];
+        cria_recorrente(&mut recorrentes, cadencia, &fornecedor, itens, hoje);
+        assert_eq!(recorrentes[0].proxima, "2024-02-29");
+
+        let mut rascunhos = vec![];
+        assert!(gera_rascunhos(&mut recorrentes, &mut rascunhos, hoje).is_empty());
+        // Fechado por dois ciclos: um só rascunho, do último
+        let depois = NaiveDate::from_ymd_opt(2024, 4, 5).unwrap();
+        let novos = gera_rascunhos(&mut recorrentes, &mut rascunhos, depois);
+        assert_eq!(novos[0].ciclo, "2024-03-29");
+        assert_eq!(recorrentes[0].proxima, "2024-04-29");
+        assert!(gera_rascunhos(&mut recorrentes, &mut rascunhos, depois).is_empty());
+
+        let mut pedidos = vec![];
+        assert_eq!(
+            responde_rascunho(&mut rascunhos, &mut pedidos, 0, true, "2024-04-05"),
+            Some(1)
+        );
+        assert_eq!(pedidos[0].itens[0].quantidade, 20);
+        assert!(rascunhos.is_empty());
+        let maio = NaiveDate::from_ymd_opt(2024, 4, 29).unwrap();
+        gera_rascunhos(&mut recorrentes, &mut rascunhos, maio);
+        assert_eq!(
+            responde_rascunho(&mut rascunhos, &mut pedidos, 0, false, "2024-04-29"),
+            None
+        );
+        assert_eq!((rascunhos.len(), pedidos.len()), (0, 1));
+    }
+
+    #[test]
+    fn desempenho_prazo_atendimento_e_custo() {
+        let mut pedidos = vec![];
+        let (_, itens) = interpreta("Seiko R002:10").unwrap();
+        abre(&mut pedidos, "Seiko", itens, "2024-03-01");
+        let (_, itens) = interpreta("Casio R001:4").unwrap();
+        abre(&mut pedidos, "Casio", itens, "2024-03-01");
+        recebe(&mut pedidos, Some("seiko"), "R002", 6, "2024-03-11");
+        recebe(&mut pedidos, Some("seiko"), "R002", 4, "2024-03-21");
+        recebe(&mut pedidos, Some("casio"), "R001", 2, "2024-03-05");
+        cancela_saldo(&mut pedidos, 2, "R001", "2024-03-30");
+
+        let compra = |fornecedor: &str, custo| Historico {
+            codigo: "R002".into(),
+            quantidade: 1,
+            operacao: "COMPRA".into(),
+            timestamp: "2024-03-11 10:00:00".into(),
+            custo: Some(custo),
+            fornecedor: Some(fornecedor.into()),
+            ..Default::default()
+        };
+        let historico = vec![
+            compra("Seiko", 100.0),
+            compra("Atacado", 105.0),
+            compra("Seiko", 110.0),
+        ];
+        let texto = desempenho(&pedidos, &historico, FormatoNumero::default());
+        let linha = |inicio: &str| {
+            texto
+                .lines()
+                .find(|l| l.starts_with(inicio))
+                .unwrap()
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+        assert_eq!(linha("Seiko"), "Seiko 1 14,0 d 100% 0");
+        assert_eq!(linha("Casio"), "Casio 1 4,0 d 50% 0");
+        assert!(linha("R002*").contains("Atacado"));
+        assert!(linha("R002 ").ends_with("10,0%"));
+    }
+}
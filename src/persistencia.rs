@@ -0,0 +1,420 @@
+use crate::dominio::{Historico, Relogio};
+use crate::{
+    agenda, agendadas, alteracoes, caixa, clientes, credito, despesas, expurgo, fidelidade,
+    fornecedores, kardex, notas, notificacoes, orcamentos, pedidos, promocoes, sincronia,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+const CAMINHO_ESTOQUE: &str = "estoque.json";
+const CAMINHO_TRAVA: &str = "estoque.lock";
+
+static PASTA_DADOS: OnceLock<PathBuf> = OnceLock::new();
+
+/// Passa a ler e gravar os arquivos de dados em `pasta`, sem trocar a pasta
+/// atual: caminhos digitados pelo usuário continuam relativos a ela. Só a
+/// primeira chamada vale.
+pub(crate) fn define_pasta_dados(pasta: PathBuf) {
+    let _ = PASTA_DADOS.set(pasta);
+}
+
+/// Trava exclusiva sobre os dados, do sistema operacional: some sozinha se
+/// o processo morrer. A interface a segura enquanto está aberta, porque
+/// grava o que tem na memória por cima do `estoque.json`; o servidor, só
+/// durante cada venda pela API.
+pub struct Trava {
+    _arquivo: fs::File,
+}
+
+/// Tenta tomar a trava sem esperar; `None` se outro processo a segura.
+pub fn trava() -> io::Result<Option<Trava>> {
+    let arquivo = fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(na_pasta_dados(CAMINHO_TRAVA))?;
+    match arquivo.try_lock() {
+        Ok(()) => Ok(Some(Trava { _arquivo: arquivo })),
+        Err(fs::TryLockError::WouldBlock) => Ok(None),
+        Err(fs::TryLockError::Error(e)) => Err(e),
+    }
+}
+
+/// Caminho de um arquivo de dados (`estoque.json`, `config.toml`, `notas/`...)
+/// na pasta de dados; sem uma definida, na pasta atual.
+pub(crate) fn na_pasta_dados(nome: impl AsRef<Path>) -> PathBuf {
+    match PASTA_DADOS.get() {
+        Some(pasta) => pasta.join(nome),
+        None => nome.as_ref().to_path_buf(),
+    }
+}
+
+/// Cliente aguardando a reposição de um modelo sem estoque.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Espera {
+    pub(crate) codigo: String,
+    pub(crate) cliente: String,
+    pub(crate) contato: String,
+    pub(crate) timestamp: String,
+}
+
+/// Recado deixado para o próximo turno, exibido ao abrir o programa.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Recado {
+    pub(crate) autor: String,
+    pub(crate) mensagem: String,
+    pub(crate) timestamp: String,
+    #[serde(default)]
+    pub(crate) lido: bool,
+}
+
+/// Venda acima do limite por venda, bloqueada ou liberada pelo administrador.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct TentativaLimite {
+    pub(crate) timestamp: String,
+    pub(crate) codigo: String,
+    pub(crate) quantidade: i32,
+    pub(crate) limite: i32,
+    pub(crate) liberada: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Persist {
+    relogios: Vec<Relogio>,
+    historico: Vec<Historico>,
+    #[serde(flatten)]
+    registros: Registros,
+}
+
+/// Demais cadastros persistidos junto com o estoque.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Registros {
+    pub(crate) notas: Vec<notas::NotaFiscal>,
+    pub(crate) lembretes: Vec<agenda::Lembrete>,
+    pub(crate) promocoes: Vec<promocoes::Promocao>,
+    pub(crate) tentativas_limite: Vec<TentativaLimite>,
+    pub(crate) lista_espera: Vec<Espera>,
+    pub(crate) creditos: Vec<credito::MovimentoCredito>,
+    pub(crate) caixa: Vec<caixa::MovimentoCaixa>,
+    pub(crate) despesas: Vec<despesas::Despesa>,
+    pub(crate) recados: Vec<Recado>,
+    /// Códigos presos às teclas 1–9 para venda rápida.
+    pub(crate) favoritos: Vec<String>,
+    /// Vendas duplicadas entre lojas aguardando conferência.
+    pub(crate) conflitos_sincronia: Vec<sincronia::Conflito>,
+    /// Códigos dos fornecedores ligados aos códigos internos.
+    pub(crate) referencias_fornecedor: Vec<fornecedores::Referencia>,
+    pub(crate) fornecedores: Vec<fornecedores::Fornecedor>,
+    pub(crate) notificacoes: Vec<notificacoes::Notificacao>,
+    pub(crate) pedidos_compra: Vec<pedidos::PedidoCompra>,
+    pub(crate) pedidos_recorrentes: Vec<pedidos::Recorrente>,
+    /// Ciclos de pedidos recorrentes esperando confirmação.
+    pub(crate) rascunhos_pedido: Vec<pedidos::Rascunho>,
+    pub(crate) aniversarios: Vec<fidelidade::Aniversario>,
+    pub(crate) orcamentos: Vec<orcamentos::Orcamento>,
+    /// Vendas e compras com data de efeito futura.
+    pub(crate) operacoes_agendadas: Vec<agendadas::Agendada>,
+    pub(crate) clientes: Vec<clientes::Cliente>,
+    /// Códigos excluídos, para o relatório de ajustes.
+    pub(crate) exclusoes: Vec<expurgo::Exclusao>,
+    /// Histórico já arquivado cujo `historico_<ano>.json` ainda não foi
+    /// gravado (ver `arquivo::grava`).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub(crate) historico_a_arquivar: Vec<Historico>,
+}
+
+impl Registros {
+    /// Troca o código `de` por `para` onde ele ainda vale: favoritos, lista
+    /// de espera, referências de fornecedor, pedidos (abertos, recorrentes e
+    /// rascunhos), operações agendadas e orçamentos. Notas emitidas e
+    /// exclusões ficam como foram registradas.
+    pub(crate) fn renomeia(&mut self, de: &str, para: &str) {
+        let troca = |c: &mut String| {
+            if c == de {
+                *c = para.to_string();
+            }
+        };
+        self.favoritos.iter_mut().for_each(troca);
+        self.lista_espera
+            .iter_mut()
+            .for_each(|e| troca(&mut e.codigo));
+        self.referencias_fornecedor
+            .iter_mut()
+            .for_each(|r| troca(&mut r.codigo));
+        for p in &mut self.pedidos_compra {
+            p.itens.iter_mut().for_each(|i| troca(&mut i.codigo));
+            p.entregas.iter_mut().for_each(|e| troca(&mut e.codigo));
+        }
+        self.pedidos_recorrentes
+            .iter_mut()
+            .flat_map(|p| p.itens.iter_mut())
+            .chain(
+                self.rascunhos_pedido
+                    .iter_mut()
+                    .flat_map(|r| r.itens.iter_mut()),
+            )
+            .for_each(|i| troca(&mut i.codigo));
+        self.operacoes_agendadas
+            .iter_mut()
+            .for_each(|a| troca(&mut a.codigo));
+        self.orcamentos
+            .iter_mut()
+            .flat_map(|o| o.itens.iter_mut())
+            .for_each(|(c, _)| troca(c));
+    }
+}
+
+fn com_sufixo(caminho: &Path, sufixo: &str) -> PathBuf {
+    let mut nome = caminho.as_os_str().to_owned();
+    nome.push(sufixo);
+    PathBuf::from(nome)
+}
+
+/// Grava num temporário ao lado e o renomeia por cima de `caminho`, de modo
+/// que uma queda no meio deixe o arquivo antigo inteiro. A versão anterior
+/// fica em `<caminho>.bak`.
+pub(crate) fn grava_atomico(caminho: &Path, conteudo: &[u8]) -> io::Result<()> {
+    let temporario = com_sufixo(caminho, ".tmp");
+    let mut f = fs::File::create(&temporario)?;
+    f.write_all(conteudo)?;
+    f.sync_all()?;
+    if caminho.exists() {
+        let bak = com_sufixo(caminho, ".bak");
+        let _ = fs::remove_file(&bak);
+        // O link mantém o conteúdo antigo depois do rename; sem suporte a
+        // links (FAT, pastas de rede), copia.
+        fs::hard_link(caminho, &bak).or_else(|_| fs::copy(caminho, &bak).map(|_| ()))?;
+    }
+    fs::rename(&temporario, caminho)
+}
+
+/// Lê o arquivo ou, se ele estiver ilegível, o `.bak`. O ilegível é
+/// renomeado para `.corrompido`, para não virar o backup da próxima
+/// gravação.
+fn le_com_backup(caminho: &Path) -> Option<Persist> {
+    let le = |c: &Path| {
+        fs::read_to_string(c)
+            .ok()
+            .and_then(|data| serde_json::from_str::<Persist>(&data).ok())
+    };
+    if let Some(p) = le(caminho) {
+        return Some(p);
+    }
+    let p = le(&com_sufixo(caminho, ".bak"))?;
+    if caminho.exists() {
+        let _ = fs::rename(caminho, com_sufixo(caminho, ".corrompido"));
+    }
+    Some(p)
+}
+
+fn soma(conteudo: &[u8]) -> String {
+    Sha256::digest(conteudo)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Guarda a soma e uma cópia do que o programa acabou de gravar, para
+/// reconhecer na abertura uma edição feita por fora.
+fn registra_gravacao(caminho: &Path, conteudo: &[u8]) -> io::Result<()> {
+    fs::write(com_sufixo(caminho, ".gravado"), conteudo)?;
+    fs::write(com_sufixo(caminho, ".sha256"), soma(conteudo))
+}
+
+/// Alterações feitas no `estoque.json` por fora do programa (à mão, por uma
+/// ferramenta de sincronia) desde a última gravação dele.
+pub(crate) struct EdicaoExterna {
+    pub(crate) resumo: Vec<String>,
+    anterior: Option<Persist>,
+}
+
+impl EdicaoExterna {
+    pub(crate) fn tem_anterior(&self) -> bool {
+        self.anterior.is_some()
+    }
+
+    /// Os dados como o programa os gravou pela última vez, se a cópia
+    /// daquela gravação estiver inteira.
+    pub(crate) fn anterior(self) -> Option<(HashMap<String, Relogio>, Vec<Historico>, Registros)> {
+        self.anterior.map(|p| {
+            let relogios = p
+                .relogios
+                .into_iter()
+                .map(|r| (r.codigo.clone(), r))
+                .collect();
+            (relogios, p.historico, p.registros)
+        })
+    }
+}
+
+/// Compara o arquivo com a soma da última gravação. Sem soma (dados de uma
+/// versão anterior) ou com o arquivo ilegível (caso do `.bak`), não acusa.
+fn confere(caminho: &Path) -> Option<EdicaoExterna> {
+    let gravada = fs::read_to_string(com_sufixo(caminho, ".sha256")).ok()?;
+    let gravada = gravada.trim();
+    let atual = fs::read(caminho).ok()?;
+    if soma(&atual) == gravada {
+        return None;
+    }
+    let depois: serde_json::Value = serde_json::from_slice(&atual).ok()?;
+    let antes: Option<serde_json::Value> = fs::read(com_sufixo(caminho, ".gravado"))
+        .ok()
+        .filter(|c| soma(c) == gravada)
+        .and_then(|c| serde_json::from_slice(&c).ok());
+    let resumo = match &antes {
+        Some(antes) => {
+            let resumo = alteracoes::resumo(antes, &depois);
+            if resumo.is_empty() {
+                vec!["Só a formatação mudou; os dados são os mesmos.".into()]
+            } else {
+                resumo
+            }
+        }
+        None => vec!["Sem a cópia da última gravação, não há com o que comparar.".into()],
+    };
+    Some(EdicaoExterna {
+        resumo,
+        anterior: antes.and_then(|v| serde_json::from_value(v).ok()),
+    })
+}
+
+/// Edição externa do `estoque.json` da pasta atual, se houver.
+pub(crate) fn edicao_externa() -> Option<EdicaoExterna> {
+    confere(&na_pasta_dados(CAMINHO_ESTOQUE))
+}
+
+/// Copia o `estoque.json` editado por fora para `estoque.json.externo`
+/// antes de ele ser desfeito.
+pub(crate) fn guarda_edicao_externa() -> io::Result<PathBuf> {
+    let caminho = na_pasta_dados(CAMINHO_ESTOQUE);
+    let destino = com_sufixo(&caminho, ".externo");
+    fs::copy(&caminho, &destino)?;
+    Ok(destino)
+}
+
+/// Lê o `estoque.json` da pasta atual (ou o `.bak`, se ele estiver
+/// corrompido); sem nenhum, começa vazio.
+pub fn load_from_file() -> (HashMap<String, Relogio>, Vec<Historico>, Registros) {
+    let mut relogios: HashMap<String, Relogio> = HashMap::new();
+    let mut historico: Vec<Historico> = vec![];
+    let mut registros = Registros::default();
+
+    if let Some(json) = le_com_backup(&na_pasta_dados(CAMINHO_ESTOQUE)) {
+        relogios = json
+            .relogios
+            .into_iter()
+            .map(|r| (r.codigo.clone(), r))
+            .collect();
+        historico = json.historico;
+        registros = json.registros;
+    }
+    (relogios, historico, registros)
+}
+
+/// Grava o `estoque.json` (sem truncar o atual antes de o novo estar
+/// completo), registra a gravação e atualiza o kardex.
+pub fn save_to_file(
+    relogios: &HashMap<String, Relogio>,
+    historico: &[Historico],
+    registros: &Registros,
+) {
+    let _ = salva(relogios, historico, registros);
+}
+
+/// Como [`save_to_file`], mas devolve o erro da gravação do `estoque.json`.
+pub(crate) fn salva(
+    relogios: &HashMap<String, Relogio>,
+    historico: &[Historico],
+    registros: &Registros,
+) -> io::Result<()> {
+    let r: Vec<Relogio> = relogios.values().cloned().collect();
+    let p = Persist {
+        relogios: r,
+        historico: historico.to_vec(),
+        registros: registros.clone(),
+    };
+    let j = serde_json::to_string_pretty(&p)?;
+    let caminho = na_pasta_dados(CAMINHO_ESTOQUE);
+    let gravado = grava_atomico(&caminho, j.as_bytes());
+    if gravado.is_ok() {
+        let _ = registra_gravacao(&caminho, j.as_bytes());
+    }
+    kardex::atualiza_arquivo(historico);
+    gravado
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dominio::Quantidade;
+
+    #[test]
+    fn grava_com_backup_e_le_o_backup_se_corromper() {
+        let pasta = std::env::temp_dir().join(format!("relogio_persist_{}", std::process::id()));
+        fs::create_dir_all(&pasta).unwrap();
+        let caminho = pasta.join("estoque.json");
+        let versao = |codigo: &str| {
+            serde_json::to_vec(&Persist {
+                relogios: vec![Relogio::novo(codigo, Quantidade::ZERO)],
+                historico: vec![],
+                registros: Registros::default(),
+            })
+            .unwrap()
+        };
+        grava_atomico(&caminho, &versao("R001")).unwrap();
+        assert!(!com_sufixo(&caminho, ".bak").exists());
+        grava_atomico(&caminho, &versao("R002")).unwrap();
+        assert!(!com_sufixo(&caminho, ".tmp").exists());
+        assert_eq!(le_com_backup(&caminho).unwrap().relogios[0].codigo, "R002");
+
+        // Gravação interrompida por fora (ex.: disco cheio) no meio do arquivo
+        fs::write(&caminho, "{\"relogios\": [").unwrap();
+        assert_eq!(le_com_backup(&caminho).unwrap().relogios[0].codigo, "R001");
+        assert!(com_sufixo(&caminho, ".corrompido").exists());
+        fs::remove_dir_all(&pasta).unwrap();
+    }
+
+    #[test]
+    fn acusa_edicao_feita_fora_do_programa() {
+        let pasta = std::env::temp_dir().join(format!("relogio_edicao_{}", std::process::id()));
+        fs::create_dir_all(&pasta).unwrap();
+        let caminho = pasta.join("estoque.json");
+        let versao = |quantidade: i32| {
+            serde_json::to_vec_pretty(&Persist {
+                relogios: vec![Relogio::novo("R001", Quantidade::nova(quantidade).unwrap())],
+                historico: vec![],
+                registros: Registros::default(),
+            })
+            .unwrap()
+        };
+        // Dados de antes da soma existir não são acusados
+        fs::write(&caminho, versao(4)).unwrap();
+        assert!(confere(&caminho).is_none());
+
+        grava_atomico(&caminho, &versao(4)).unwrap();
+        registra_gravacao(&caminho, &versao(4)).unwrap();
+        assert!(confere(&caminho).is_none());
+
+        fs::write(&caminho, versao(40)).unwrap();
+        let edicao = confere(&caminho).unwrap();
+        assert_eq!(edicao.resumo, ["R001: quantidade 4 → 40."]);
+        let (relogios, _, _) = edicao.anterior().unwrap();
+        assert_eq!(relogios["R001"].quantidade(), 4);
+
+        // Só espaços: acusa, mas diz que os dados são os mesmos
+        let mut espacado = versao(4);
+        espacado.push(b'\n');
+        fs::write(&caminho, espacado).unwrap();
+        assert_eq!(
+            confere(&caminho).unwrap().resumo,
+            ["Só a formatação mudou; os dados são os mesmos."]
+        );
+        fs::remove_dir_all(&pasta).unwrap();
+    }
+}
@@ -0,0 +1,62 @@
+use serde_json::{json, Value};
+
+const URL_TOKEN: &str = "https://oauth2.googleapis.com/token";
+const URL_SHEETS: &str = "https://sheets.googleapis.com/v4/spreadsheets";
+
+/// Credenciais OAuth (aplicativo instalado) e planilha de destino.
+#[derive(Debug, Clone)]
+pub struct Destino {
+    pub planilha_id: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub refresh_token: String,
+}
+
+fn token_de_acesso(d: &Destino) -> Result<String, String> {
+    let resp: Value = ureq::post(URL_TOKEN)
+        .send_form(&[
+            ("client_id", d.client_id.as_str()),
+            ("client_secret", d.client_secret.as_str()),
+            ("refresh_token", d.refresh_token.as_str()),
+            ("grant_type", "refresh_token"),
+        ])
+        .map_err(|e| format!("OAuth: {}", e))?
+        .into_json()
+        .map_err(|e| format!("OAuth: {}", e))?;
+    resp["access_token"]
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| "OAuth: resposta sem access_token".into())
+}
+
+/// Substitui o conteúdo das abas `Estoque` e `Vendas` pelas linhas dadas
+/// (a primeira linha de cada uma é o cabeçalho). Os valores vão como estão
+/// (`RAW`): uma descrição começando com `=` não vira fórmula na planilha, e
+/// números só são números se vierem como números JSON.
+pub fn envia(d: &Destino, estoque: Vec<Vec<Value>>, vendas: Vec<Vec<Value>>) -> Result<(), String> {
+    let token = token_de_acesso(d)?;
+    let auth = format!("Bearer {}", token);
+
+    ureq::post(&format!(
+        "{}/{}/values:batchClear",
+        URL_SHEETS, d.planilha_id
+    ))
+    .set("Authorization", &auth)
+    .send_json(json!({ "ranges": ["Estoque", "Vendas"] }))
+    .map_err(|e| format!("Sheets: {}", e))?;
+
+    ureq::post(&format!(
+        "{}/{}/values:batchUpdate",
+        URL_SHEETS, d.planilha_id
+    ))
+    .set("Authorization", &auth)
+    .send_json(json!({
+        "valueInputOption": "RAW",
+        "data": [
+            { "range": "Estoque!A1", "values": estoque },
+            { "range": "Vendas!A1", "values": vendas },
+        ],
+    }))
+    .map_err(|e| format!("Sheets: {}", e))?;
+    Ok(())
+}
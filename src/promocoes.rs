@@ -0,0 +1,63 @@
+use crate::{Historico, Relogio};
+use serde::{Deserialize, Serialize};
+
+/// Desconto percentual sobre uma categoria ou tag, válido entre `inicio` e
+/// `fim` (AAAA-MM-DD, inclusive).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Promocao {
+    pub nome: String,
+    /// Categoria do produto ou tag `#alvo` na descrição.
+    pub alvo: String,
+    pub desconto: f64,
+    pub inicio: String,
+    pub fim: String,
+}
+
+impl Promocao {
+    pub fn vigente(&self, data: &str) -> bool {
+        data >= self.inicio.as_str() && data <= self.fim.as_str()
+    }
+
+    pub fn abrange(&self, r: &Relogio) -> bool {
+        let alvo = self.alvo.to_lowercase();
+        r.categoria
+            .as_ref()
+            .is_some_and(|c| c.to_lowercase() == alvo)
+            || r.descricao.split_whitespace().any(|p| {
+                p.strip_prefix('#')
+                    .is_some_and(|t| t.to_lowercase() == alvo)
+            })
+    }
+}
+
+/// Maior desconto vigente na data para o produto.
+pub fn ativa<'a>(promocoes: &'a [Promocao], r: &Relogio, data: &str) -> Option<&'a Promocao> {
+    promocoes
+        .iter()
+        .filter(|p| p.vigente(data) && p.abrange(r))
+        .max_by(|a, b| a.desconto.total_cmp(&b.desconto))
+}
+
+/// Por promoção: vendas, unidades e total concedido em descontos.
+pub fn relatorio(
+    historico: &[Historico],
+    relogios: &std::collections::HashMap<String, Relogio>,
+) -> Vec<(String, usize, i32, f64)> {
+    let mut linhas: Vec<(String, usize, i32, f64)> = vec![];
+    for h in historico.iter().filter(|h| h.operacao == "VENDA") {
+        let Some(nome) = &h.promocao else {
+            continue;
+        };
+        let preco = h.preco_tabela(relogios.get(&h.codigo));
+        let concedido = preco * h.quantidade as f64 * h.desconto.unwrap_or(0.0) / 100.0;
+        match linhas.iter_mut().find(|l| &l.0 == nome) {
+            Some(l) => {
+                l.1 += 1;
+                l.2 += h.quantidade;
+                l.3 += concedido;
+            }
+            None => linhas.push((nome.clone(), 1, h.quantidade, concedido)),
+        }
+    }
+    linhas
+}
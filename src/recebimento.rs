@@ -0,0 +1,154 @@
+use crate::dominio::{ErroDominio, Inventario, ProdutoId, Quantidade};
+use crate::{Historico, Relogio};
+use chrono::NaiveDateTime;
+use std::collections::HashMap;
+
+/// Etapas do assistente de recebimento.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Etapa {
+    Fornecedor,
+    Itens,
+    Revisao,
+}
+
+/// Entrega em conferência: nada é lançado até a confirmação.
+#[derive(Debug, Clone)]
+pub struct Recebimento {
+    pub etapa: Etapa,
+    pub fornecedor: Option<String>,
+    /// Código e unidades, na ordem em que foram lidos; leituras repetidas
+    /// do mesmo código somam na mesma linha.
+    pub itens: Vec<(String, i32)>,
+}
+
+impl Recebimento {
+    pub fn novo() -> Self {
+        Recebimento {
+            etapa: Etapa::Fornecedor,
+            fornecedor: None,
+            itens: vec![],
+        }
+    }
+
+    pub fn adiciona(&mut self, codigo: &str, quantidade: i32) {
+        match self.itens.iter_mut().find(|(c, _)| c == codigo) {
+            Some((_, q)) => *q += quantidade,
+            None => self.itens.push((codigo.to_string(), quantidade)),
+        }
+        self.itens.retain(|(_, q)| *q > 0);
+    }
+
+    pub fn unidades(&self) -> i32 {
+        self.itens.iter().map(|(_, q)| q).sum()
+    }
+
+    /// Custo total pelos custos cadastrados (itens sem custo não contam).
+    pub fn custo(&self, relogios: &HashMap<String, Relogio>) -> f64 {
+        self.itens
+            .iter()
+            .filter_map(|(c, q)| Some(relogios.get(c)?.custo? * *q as f64))
+            .sum()
+    }
+}
+
+/// Próximo número de recebimento.
+fn proximo_numero(historico: &[Historico]) -> u32 {
+    historico
+        .iter()
+        .filter_map(|h| h.recebimento)
+        .max()
+        .unwrap_or(0)
+        + 1
+}
+
+/// Códigos e unidades lançados no recebimento `numero`, na ordem do
+/// histórico.
+pub fn itens(historico: &[Historico], numero: u32) -> Vec<(String, i32)> {
+    let mut itens: Vec<(String, i32)> = vec![];
+    for h in historico.iter().filter(|h| h.recebimento == Some(numero)) {
+        match itens.iter_mut().find(|(c, _)| *c == h.codigo) {
+            Some((_, q)) => *q += h.quantidade,
+            None => itens.push((h.codigo.clone(), h.quantidade)),
+        }
+    }
+    itens
+}
+
+/// Lança a entrega inteira como um recebimento: uma COMPRA por item, todas
+/// com o mesmo horário, número e fornecedor. Confere tudo antes, de modo que
+/// um item inválido não deixa a entrega pela metade. Não grava.
+pub fn lanca(
+    rec: &Recebimento,
+    relogios: &mut HashMap<String, Relogio>,
+    historico: &mut Vec<Historico>,
+    quando: NaiveDateTime,
+) -> Result<u32, ErroDominio> {
+    if rec.itens.is_empty() {
+        return Err(ErroDominio::MovimentoVazio);
+    }
+    let mut itens = vec![];
+    for (codigo, qtd) in &rec.itens {
+        let c = ProdutoId::novo(codigo)?;
+        let r = relogios
+            .get(codigo)
+            .ok_or_else(|| ErroDominio::NaoEncontrado(codigo.clone()))?;
+        if !r.componentes.is_empty() {
+            return Err(ErroDominio::Kit(codigo.clone()));
+        }
+        itens.push((c, Quantidade::movimento(*qtd)?));
+    }
+    let numero = proximo_numero(historico);
+    let mut inventario = Inventario::new(relogios, historico);
+    for (c, q) in itens {
+        let h = inventario.compra(&c, q, quando)?;
+        h.recebimento = Some(numero);
+        h.fornecedor = rec.fornecedor.clone();
+    }
+    Ok(numero)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Local;
+
+    #[test]
+    fn lanca_tudo_ou_nada() {
+        let mut relogios = HashMap::new();
+        for codigo in ["R1", "R2"] {
+            relogios.insert(codigo.to_string(), {
+                let mut r = Relogio::novo(codigo, Quantidade::ZERO);
+                r.custo = Some(10.0);
+                r
+            });
+        }
+        let mut rec = Recebimento::novo();
+        rec.fornecedor = Some("Casio".into());
+        rec.adiciona("R1", 1);
+        rec.adiciona("R2", 2);
+        rec.adiciona("R1", 1);
+        assert_eq!(rec.itens, [("R1".to_string(), 2), ("R2".to_string(), 2)]);
+        assert_eq!((rec.unidades(), rec.custo(&relogios)), (4, 40.0));
+
+        let mut historico = vec![];
+        let agora = Local::now().naive_local();
+        rec.adiciona("R9", 1);
+        assert_eq!(
+            lanca(&rec, &mut relogios, &mut historico, agora),
+            Err(ErroDominio::NaoEncontrado("R9".into()))
+        );
+        assert!(historico.is_empty());
+        assert_eq!(relogios["R1"].quantidade(), 0);
+
+        rec.adiciona("R9", -1);
+        assert_eq!(lanca(&rec, &mut relogios, &mut historico, agora), Ok(1));
+        assert_eq!(lanca(&rec, &mut relogios, &mut historico, agora), Ok(2));
+        assert_eq!(relogios["R1"].quantidade(), 4);
+        assert_eq!(itens(&historico, 2), rec.itens);
+        assert!(itens(&historico, 3).is_empty());
+        assert!(historico
+            .iter()
+            .all(|h| h.fornecedor.as_deref() == Some("Casio")
+                && h.timestamp == historico[0].timestamp));
+    }
+}
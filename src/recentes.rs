@@ -0,0 +1,26 @@
+use crate::persistencia::na_pasta_dados;
+use std::fs;
+
+const CAMINHO_RECENTES: &str = "recentes.json";
+/// Quantos códigos a lista guarda.
+pub const MAXIMO: usize = 10;
+
+pub fn carrega() -> Vec<String> {
+    fs::read_to_string(na_pasta_dados(CAMINHO_RECENTES))
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+pub fn salva(recentes: &[String]) {
+    if let Ok(j) = serde_json::to_string_pretty(recentes) {
+        let _ = fs::write(na_pasta_dados(CAMINHO_RECENTES), j);
+    }
+}
+
+/// Põe `codigo` no topo da lista, sem repetir e sem passar do máximo.
+pub fn registra(recentes: &mut Vec<String>, codigo: &str) {
+    recentes.retain(|c| c != codigo);
+    recentes.insert(0, codigo.to_string());
+    recentes.truncate(MAXIMO);
+}
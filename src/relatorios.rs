@@ -0,0 +1,337 @@
+use crate::expurgo::Exclusao;
+use crate::numeros::FormatoNumero;
+use crate::{contabil, financeiro, pdf, Historico, Registros, Relogio};
+use chrono::{Datelike, Duration, Months, NaiveDate};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Dias para trás conferidos a cada rodada, para cobrir dias em que o
+/// programa não foi aberto (fim de semana, feriado).
+const DIAS_RETROATIVOS: i64 = 7;
+
+/// Vendas de um dia em CSV (`;`), uma linha por venda.
+pub fn vendas_do_dia_csv(
+    historico: &[Historico],
+    relogios: &HashMap<String, Relogio>,
+    data: NaiveDate,
+    formato: FormatoNumero,
+) -> String {
+    let dia = data.format("%Y-%m-%d").to_string();
+    let mut csv = String::from("timestamp;codigo;descricao;quantidade;pagamento;valor\n");
+    for h in historico
+        .iter()
+        .filter(|h| h.operacao == "VENDA" && h.timestamp.starts_with(&dia))
+    {
+        let r = relogios.get(&h.codigo);
+        let valor = contabil::valor(h, r).unwrap_or(0.0);
+        csv.push_str(&format!(
+            "{};{};{};{};{};{}\n",
+            h.timestamp,
+            h.codigo,
+            r.map(|r| r.descricao.replace(';', ",")).unwrap_or_default(),
+            h.quantidade,
+            h.pagamento.as_deref().unwrap_or("dinheiro"),
+            formato.numero(valor, 2)
+        ));
+    }
+    csv
+}
+
+/// Texto livre numa célula do CSV, sem o separador.
+fn celula(texto: &str) -> String {
+    texto.replace(';', ",").replace('\n', " ")
+}
+
+/// Estoque em CSV (`;`), um código por linha e em ordem de código. Kits
+/// saem com o saldo montável a partir dos componentes.
+pub fn estoque_csv(relogios: &HashMap<String, Relogio>, formato: FormatoNumero) -> String {
+    let mut csv =
+        String::from("codigo;descricao;categoria;marca;ean;quantidade;moeda;custo;preco\n");
+    let mut codigos: Vec<&String> = relogios.keys().collect();
+    codigos.sort();
+    let valor = |v: Option<f64>| v.map(|v| formato.numero(v, 2)).unwrap_or_default();
+    for r in codigos.into_iter().map(|c| &relogios[c]) {
+        csv.push_str(&format!(
+            "{};{};{};{};{};{};{};{};{}\n",
+            r.codigo,
+            celula(&r.descricao),
+            celula(r.categoria.as_deref().unwrap_or_default()),
+            celula(r.marca.as_deref().unwrap_or_default()),
+            r.ean.as_deref().unwrap_or_default(),
+            crate::dominio::quantidade_disponivel(relogios, r),
+            r.moeda.as_deref().unwrap_or("BRL"),
+            valor(r.custo),
+            valor(r.preco)
+        ));
+    }
+    csv
+}
+
+/// Entradas do histórico em CSV (`;`), na ordem recebida. O valor é o das
+/// vendas e compras, como na contabilidade; nas demais fica vazio.
+pub fn historico_csv(
+    entradas: &[Historico],
+    relogios: &HashMap<String, Relogio>,
+    formato: FormatoNumero,
+) -> String {
+    let mut csv = String::from(
+        "timestamp;operacao;codigo;descricao;quantidade;pagamento;valor;motivo;fornecedor;etiquetas\n",
+    );
+    for h in entradas {
+        let r = relogios.get(&h.codigo);
+        csv.push_str(&format!(
+            "{};{};{};{};{};{};{};{};{};{}\n",
+            h.timestamp,
+            h.operacao,
+            h.codigo,
+            r.map(|r| celula(&r.descricao)).unwrap_or_default(),
+            h.quantidade,
+            h.pagamento.as_deref().unwrap_or_default(),
+            contabil::valor(h, r)
+                .map(|v| formato.numero(v, 2))
+                .unwrap_or_default(),
+            celula(h.motivo.as_deref().unwrap_or_default()),
+            celula(h.fornecedor.as_deref().unwrap_or_default()),
+            celula(&h.tags.join(", "))
+        ));
+    }
+    csv
+}
+
+/// Ajustes de saldo e exclusões de código entre `de` e `ate` (inclusive),
+/// em CSV (`;`) e em ordem de data, para a contabilidade. O impacto é a
+/// quantidade com sinal vezes o custo da data; a exclusão entra com o saldo
+/// que o código tinha, como saída. Fecha com a linha de total.
+pub fn ajustes_csv(
+    historico: &[Historico],
+    relogios: &HashMap<String, Relogio>,
+    exclusoes: &[Exclusao],
+    de: NaiveDate,
+    ate: NaiveDate,
+    formato: FormatoNumero,
+) -> String {
+    let no_periodo = |timestamp: &str| {
+        NaiveDate::parse_from_str(timestamp.get(..10).unwrap_or_default(), "%Y-%m-%d")
+            .is_ok_and(|d| d >= de && d <= ate)
+    };
+    let linha = |timestamp: &str,
+                 operacao: &str,
+                 codigo: &str,
+                 descricao: &str,
+                 usuario: &str,
+                 motivo: &str,
+                 quantidade: i32,
+                 custo: Option<f64>| {
+        let impacto = custo.map(|c| quantidade as f64 * c);
+        let texto = format!(
+            "{};{};{};{};{};{};{:+};{};{}\n",
+            timestamp,
+            operacao,
+            codigo,
+            celula(descricao),
+            celula(usuario),
+            celula(motivo),
+            quantidade,
+            custo.map(|c| formato.numero(c, 2)).unwrap_or_default(),
+            impacto.map(|v| formato.numero(v, 2)).unwrap_or_default()
+        );
+        (timestamp.to_string(), texto, impacto.unwrap_or(0.0))
+    };
+    let mut linhas: Vec<(String, String, f64)> = historico
+        .iter()
+        .filter(|h| h.operacao == "AJUSTE" && no_periodo(&h.timestamp))
+        .map(|h| {
+            let r = relogios.get(&h.codigo);
+            linha(
+                &h.timestamp,
+                "AJUSTE",
+                &h.codigo,
+                r.map_or("", |r| r.descricao.as_str()),
+                h.usuario.as_deref().unwrap_or_default(),
+                h.motivo.as_deref().unwrap_or_default(),
+                h.quantidade,
+                h.custo.or(r.and_then(|r| r.custo)),
+            )
+        })
+        .collect();
+    linhas.extend(
+        exclusoes
+            .iter()
+            .filter(|e| no_periodo(&e.timestamp))
+            .map(|e| {
+                linha(
+                    &e.timestamp,
+                    "EXCLUSAO",
+                    &e.codigo,
+                    &e.descricao,
+                    &e.usuario,
+                    &format!("código excluído com {} entradas do histórico", e.entradas),
+                    -e.quantidade,
+                    e.custo,
+                )
+            }),
+    );
+    linhas.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut csv = String::from(
+        "timestamp;operacao;codigo;descricao;usuario;motivo;quantidade;custo;impacto\n",
+    );
+    for (_, texto, _) in &linhas {
+        csv.push_str(texto);
+    }
+    let total: f64 = linhas.iter().map(|(_, _, impacto)| impacto).sum();
+    csv.push_str(&format!("Total;;;;;;;;{}\n", formato.numero(total, 2)));
+    csv
+}
+
+/// Relatório financeiro do mês que começa em `primeiro`, em PDF.
+pub fn mensal_pdf(
+    historico: &[Historico],
+    relogios: &HashMap<String, Relogio>,
+    registros: &Registros,
+    primeiro: NaiveDate,
+    formato: FormatoNumero,
+) -> Vec<u8> {
+    let ultimo = primeiro
+        .checked_add_months(Months::new(1))
+        .and_then(|d| d.pred_opt())
+        .unwrap_or(primeiro);
+    let texto = financeiro::relatorio(
+        historico,
+        relogios,
+        registros,
+        &primeiro.to_string(),
+        &ultimo.to_string(),
+        formato,
+    );
+    let mut linhas = vec![format!("# Relatório mensal {}", primeiro.format("%m/%Y"))];
+    linhas.extend(texto.lines().map(str::to_string));
+    pdf::documento(&linhas)
+}
+
+/// Grava na `pasta` os relatórios que ainda não existem: o CSV de vendas de
+/// cada dia encerrado da última semana que teve vendas, o PDF do mês
+/// anterior e, se ele teve ajustes ou exclusões, o CSV deles. Devolve os
+/// nomes dos arquivos criados.
+pub fn gera_pendentes(
+    pasta: &str,
+    historico: &[Historico],
+    relogios: &HashMap<String, Relogio>,
+    registros: &Registros,
+    hoje: NaiveDate,
+    formato: FormatoNumero,
+) -> Result<Vec<String>, String> {
+    let pasta = Path::new(pasta);
+    fs::create_dir_all(pasta).map_err(|e| format!("{}: {}", pasta.display(), e))?;
+    let mut criados = vec![];
+    let mut grava = |nome: String, conteudo: &[u8]| -> Result<(), String> {
+        let caminho = pasta.join(&nome);
+        if !caminho.exists() {
+            fs::write(&caminho, conteudo).map_err(|e| format!("{}: {}", caminho.display(), e))?;
+            criados.push(nome);
+        }
+        Ok(())
+    };
+    for dias in 1..=DIAS_RETROATIVOS {
+        let data = hoje - Duration::days(dias);
+        let dia = data.format("%Y-%m-%d").to_string();
+        if historico
+            .iter()
+            .any(|h| h.operacao == "VENDA" && h.timestamp.starts_with(&dia))
+        {
+            let csv = vendas_do_dia_csv(historico, relogios, data, formato);
+            grava(format!("vendas_{}.csv", dia), csv.as_bytes())?;
+        }
+    }
+    let mes_anterior = hoje
+        .with_day(1)
+        .and_then(|d| d.checked_sub_months(Months::new(1)));
+    if let Some(primeiro) = mes_anterior {
+        let pdf = mensal_pdf(historico, relogios, registros, primeiro, formato);
+        grava(format!("relatorio_{}.pdf", primeiro.format("%Y-%m")), &pdf)?;
+        let mes = primeiro.format("%Y-%m").to_string();
+        if historico
+            .iter()
+            .any(|h| h.operacao == "AJUSTE" && h.timestamp.starts_with(&mes))
+            || registros
+                .exclusoes
+                .iter()
+                .any(|e| e.timestamp.starts_with(&mes))
+        {
+            let ultimo = hoje.with_day(1).and_then(|d| d.pred_opt()).unwrap_or(hoje);
+            let csv = ajustes_csv(
+                historico,
+                relogios,
+                &registros.exclusoes,
+                primeiro,
+                ultimo,
+                formato,
+            );
+            grava(format!("ajustes_{}.csv", mes), csv.as_bytes())?;
+        }
+    }
+    Ok(criados)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dominio::Quantidade;
+
+    #[test]
+    fn ajustes_e_exclusoes_do_periodo_com_impacto() {
+        let mut relogios = HashMap::new();
+        relogios.insert("R001".to_string(), {
+            let mut r = Relogio::novo("R001", Quantidade::ZERO);
+            r.descricao = "Clássico; aço".into();
+            r.custo = Some(80.0);
+            r
+        });
+        let entrada = |operacao: &str, quantidade: i32, timestamp: &str| Historico {
+            codigo: "R001".into(),
+            quantidade,
+            operacao: operacao.into(),
+            timestamp: timestamp.into(),
+            ..Default::default()
+        };
+        let historico = vec![
+            Historico {
+                motivo: Some("quebra".into()),
+                usuario: Some("ana".into()),
+                custo: Some(75.0),
+                ..entrada("AJUSTE", -2, "2024-05-10 09:00:00")
+            },
+            entrada("VENDA", 1, "2024-05-11 10:00:00"),
+            entrada("AJUSTE", 1, "2024-05-20 18:00:00"),
+            entrada("AJUSTE", 5, "2024-06-01 08:00:00"),
+        ];
+        let exclusoes = vec![Exclusao {
+            timestamp: "2024-05-15 12:00:00".into(),
+            codigo: "T1".into(),
+            descricao: "Teste".into(),
+            quantidade: 3,
+            custo: Some(10.0),
+            usuario: "bia".into(),
+            entradas: 4,
+        }];
+        let maio = |dia| NaiveDate::from_ymd_opt(2024, 5, dia).unwrap();
+        let csv = ajustes_csv(
+            &historico,
+            &relogios,
+            &exclusoes,
+            maio(1),
+            maio(31),
+            FormatoNumero::PtBr,
+        );
+        let linhas: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            linhas[1..],
+            [
+                "2024-05-10 09:00:00;AJUSTE;R001;Clássico, aço;ana;quebra;-2;75,00;-150,00",
+                "2024-05-15 12:00:00;EXCLUSAO;T1;Teste;bia;código excluído com 4 entradas do histórico;-3;10,00;-30,00",
+                "2024-05-20 18:00:00;AJUSTE;R001;Clássico, aço;;;+1;80,00;80,00",
+                "Total;;;;;;;;-100,00",
+            ]
+        );
+    }
+}
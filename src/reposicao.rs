@@ -0,0 +1,142 @@
+use crate::pedidos::{self, PedidoCompra};
+use crate::{Historico, Relogio};
+use std::collections::HashMap;
+
+/// Produto que chegou ao ponto de pedido e quanto pedir dele.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sugestao {
+    pub codigo: String,
+    pub saldo: i32,
+    /// Unidades que faltam chegar de pedidos abertos.
+    pub em_pedido: i32,
+    pub minimo: i32,
+    pub alvo: i32,
+    /// Alvo menos o saldo menos o que já foi pedido.
+    pub quantidade: i32,
+    /// Fornecedor da última compra com fornecedor, para o rascunho.
+    pub fornecedor: Option<String>,
+}
+
+/// Produtos com alvo cujo saldo mais o já pedido está no mínimo ou abaixo
+/// (sem mínimo, qualquer unidade abaixo do alvo), por fornecedor e código.
+/// Kits ficam de fora: a reposição é dos componentes.
+pub fn sugestoes(
+    relogios: &HashMap<String, Relogio>,
+    historico: &[Historico],
+    pedidos: &[PedidoCompra],
+) -> Vec<Sugestao> {
+    let em_pedido = pedidos::em_pedido(pedidos);
+    let mut fornecedores: HashMap<&str, &str> = HashMap::new();
+    for h in historico.iter().filter(|h| h.operacao == "COMPRA") {
+        if let Some(f) = &h.fornecedor {
+            fornecedores.insert(&h.codigo, f);
+        }
+    }
+    let mut lista: Vec<Sugestao> = relogios
+        .values()
+        .filter(|r| r.componentes.is_empty())
+        .filter_map(|r| {
+            let alvo = r.estoque_alvo?;
+            let minimo = r.estoque_minimo.unwrap_or(alvo - 1).min(alvo - 1);
+            let pedido = em_pedido.get(&r.codigo).copied().unwrap_or(0);
+            if r.quantidade() + pedido > minimo {
+                return None;
+            }
+            Some(Sugestao {
+                codigo: r.codigo.clone(),
+                saldo: r.quantidade(),
+                em_pedido: pedido,
+                minimo,
+                alvo,
+                quantidade: alvo - r.quantidade() - pedido,
+                fornecedor: fornecedores.get(r.codigo.as_str()).map(|f| f.to_string()),
+            })
+        })
+        .collect();
+    lista.sort_by(|a, b| {
+        a.fornecedor
+            .is_none()
+            .cmp(&b.fornecedor.is_none())
+            .then_with(|| a.fornecedor.cmp(&b.fornecedor))
+            .then_with(|| a.codigo.cmp(&b.codigo))
+    });
+    lista
+}
+
+/// Rascunho do pedido (`fornecedor codigo:qtd ...`, o formato do modo
+/// Pedidos) com todas as sugestões do mesmo fornecedor de `escolhida`. Sem
+/// fornecedor conhecido, só a escolhida, seguida de espaço para digitar o
+/// nome (o fornecedor pode vir em qualquer posição).
+pub fn rascunho(lista: &[Sugestao], escolhida: &Sugestao) -> String {
+    let itens: Vec<String> = lista
+        .iter()
+        .filter(|s| match &escolhida.fornecedor {
+            Some(f) => s.fornecedor.as_ref() == Some(f),
+            None => s.codigo == escolhida.codigo,
+        })
+        .map(|s| format!("{}:{}", s.codigo, s.quantidade))
+        .collect();
+    match &escolhida.fornecedor {
+        Some(f) => format!("{} {}", f, itens.join(" ")),
+        None => format!("{} ", itens.join(" ")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dominio::Quantidade;
+    use crate::pedidos::ItemPedido;
+
+    #[test]
+    fn pede_o_que_falta_para_o_alvo_descontando_pedidos_abertos() {
+        let mut relogios = HashMap::new();
+        for (codigo, quantidade, minimo, alvo) in [
+            ("R001", 2, Some(3), Some(10)),
+            ("R002", 4, Some(3), Some(10)),
+            ("R003", 1, None, Some(4)),
+            ("R004", 0, None, None),
+            ("R005", 1, Some(2), Some(6)),
+        ] {
+            relogios.insert(codigo.to_string(), {
+                let mut r = Relogio::novo(codigo, Quantidade::nova(quantidade).unwrap());
+                r.estoque_minimo = minimo;
+                r.estoque_alvo = alvo;
+                r
+            });
+        }
+        let historico = vec![Historico {
+            codigo: "R001".into(),
+            quantidade: 5,
+            operacao: "COMPRA".into(),
+            fornecedor: Some("Casio".into()),
+            ..Default::default()
+        }];
+        // R005 já tem 3 a caminho: 1 + 3 passa do mínimo
+        let mut abertos = vec![];
+        pedidos::abre(
+            &mut abertos,
+            "Seiko",
+            vec![ItemPedido {
+                codigo: "R005".into(),
+                quantidade: 3,
+                recebido: 0,
+                cancelado: 0,
+            }],
+            "2024-05-01",
+        );
+        let lista = sugestoes(&relogios, &historico, &abertos);
+        let codigos: Vec<(&str, i32)> = lista
+            .iter()
+            .map(|s| (s.codigo.as_str(), s.quantidade))
+            .collect();
+        assert_eq!(codigos, [("R001", 8), ("R003", 3)]);
+        assert_eq!(rascunho(&lista, &lista[0]), "Casio R001:8");
+        assert_eq!(rascunho(&lista, &lista[1]), "R003:3 ");
+
+        abertos[0].itens[0].cancelado = 3;
+        let lista = sugestoes(&relogios, &historico, &abertos);
+        let r005 = lista.iter().find(|s| s.codigo == "R005").unwrap();
+        assert_eq!((r005.em_pedido, r005.quantidade), (0, 5));
+    }
+}
@@ -0,0 +1,139 @@
+use crate::config::Config;
+use crate::persistencia::Registros;
+use crate::{arquivo, Historico, Relogio};
+use chrono::{Duration, NaiveDate};
+use std::collections::HashMap;
+
+/// O que foi podado na abertura.
+#[derive(Debug, Default, PartialEq)]
+pub struct Poda {
+    pub notificacoes: usize,
+    pub recados: usize,
+    /// Entradas do histórico movidas para `historico_<ano>.json`.
+    pub arquivadas: usize,
+}
+
+impl Poda {
+    pub fn vazia(&self) -> bool {
+        *self == Poda::default()
+    }
+
+    /// Resumo para o log de mensagens.
+    pub fn resumo(&self) -> String {
+        let mut partes = vec![];
+        if self.notificacoes > 0 {
+            partes.push(format!("{} notificações lidas", self.notificacoes));
+        }
+        if self.recados > 0 {
+            partes.push(format!("{} recados lidos", self.recados));
+        }
+        if self.arquivadas > 0 {
+            partes.push(format!(
+                "{} entradas do histórico arquivadas",
+                self.arquivadas
+            ));
+        }
+        format!("Retenção: {}.", partes.join(", "))
+    }
+}
+
+/// Aplica as regras de retenção da configuração (0 desliga cada uma):
+/// notificações e recados já lidos com mais de `retencao_avisos_dias` saem,
+/// e o histórico com `retencao_historico_anos` anos ou mais é arquivado como
+/// no comando `arquivar`. Não grava nada: o arquivado fica pendente até
+/// `arquivo::grava`.
+pub fn aplica(
+    config: &Config,
+    registros: &mut Registros,
+    historico: &mut Vec<Historico>,
+    relogios: &HashMap<String, Relogio>,
+    hoje: NaiveDate,
+) -> Poda {
+    let mut poda = Poda::default();
+    if config.retencao_avisos_dias > 0 {
+        let corte = (hoje - Duration::days(config.retencao_avisos_dias))
+            .format("%Y-%m-%d")
+            .to_string();
+        let antigo = |lido: bool, timestamp: &str| lido && timestamp < corte.as_str();
+        let antes = registros.notificacoes.len();
+        registros
+            .notificacoes
+            .retain(|n| !antigo(n.lida, &n.timestamp));
+        poda.notificacoes = antes - registros.notificacoes.len();
+        let antes = registros.recados.len();
+        registros.recados.retain(|r| !antigo(r.lido, &r.timestamp));
+        poda.recados = antes - registros.recados.len();
+    }
+    if config.retencao_historico_anos > 0 {
+        poda.arquivadas = arquivo::arquivar(
+            historico,
+            &mut registros.historico_a_arquivar,
+            relogios,
+            config.retencao_historico_anos,
+        );
+    }
+    poda
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::notificacoes::{Notificacao, Tipo};
+    use crate::persistencia::Recado;
+
+    #[test]
+    fn so_poda_o_que_foi_lido_e_passou_do_prazo() {
+        let notificacao = |timestamp: &str, lida| Notificacao {
+            tipo: Tipo::Lembrete,
+            chave: String::new(),
+            texto: "aviso".into(),
+            timestamp: timestamp.into(),
+            lida,
+        };
+        let mut registros = Registros {
+            notificacoes: vec![
+                notificacao("2025-01-10 09:00:00", true),
+                notificacao("2025-01-10 09:00:00", false),
+                notificacao("2025-03-01 09:00:00", true),
+            ],
+            recados: vec![Recado {
+                autor: "Ana".into(),
+                mensagem: "troca de bateria".into(),
+                timestamp: "2025-02-01 18:00:00".into(),
+                lido: true,
+            }],
+            ..Default::default()
+        };
+        let mut historico = vec![];
+        let hoje = NaiveDate::from_ymd_opt(2025, 3, 10).unwrap();
+
+        let relogios = HashMap::new();
+        let desligada = aplica(
+            &Config::default(),
+            &mut registros,
+            &mut historico,
+            &relogios,
+            hoje,
+        );
+        assert!(desligada.vazia());
+
+        let config = Config {
+            retencao_avisos_dias: 30,
+            ..Default::default()
+        };
+        let poda = aplica(&config, &mut registros, &mut historico, &relogios, hoje);
+        assert_eq!(
+            poda,
+            Poda {
+                notificacoes: 1,
+                recados: 1,
+                arquivadas: 0
+            }
+        );
+        assert_eq!(registros.notificacoes.len(), 2);
+        assert_eq!(
+            poda.resumo(),
+            "Retenção: 1 notificações lidas, 1 recados lidos."
+        );
+    }
+}
@@ -0,0 +1,80 @@
+use std::ops::Range;
+
+/// Seleção e deslocamento de uma lista rolável. A altura da página vem da
+/// área em que a lista foi desenhada por último, então a seleção continua
+/// visível e o fatiamento nunca passa do fim mesmo após redimensionar.
+#[derive(Debug, Clone, Default)]
+pub struct ScrollState {
+    pub offset: usize,
+    pub selected: usize,
+    pagina: usize,
+}
+
+impl ScrollState {
+    pub fn reinicia(&mut self) {
+        self.offset = 0;
+        self.selected = 0;
+    }
+
+    pub fn sobe(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+        self.offset = self.offset.min(self.selected);
+    }
+
+    pub fn desce(&mut self, total: usize) {
+        if self.selected + 1 < total {
+            self.selected += 1;
+            let pagina = self.pagina.max(1);
+            if self.selected >= self.offset + pagina {
+                self.offset = self.selected + 1 - pagina;
+            }
+        }
+    }
+
+    /// Mantém seleção e deslocamento dentro de uma lista com `total` itens.
+    pub fn limita(&mut self, total: usize) {
+        self.selected = self.selected.min(total.saturating_sub(1));
+        self.offset = self.offset.min(self.selected);
+    }
+
+    /// Registra a altura disponível (em linhas) e devolve o intervalo de
+    /// itens a desenhar, sempre contendo a seleção.
+    pub fn visiveis(&mut self, total: usize, altura: usize) -> Range<usize> {
+        self.pagina = altura.max(1);
+        self.limita(total);
+        if self.selected >= self.offset + self.pagina {
+            self.offset = self.selected + 1 - self.pagina;
+        }
+        self.offset = self.offset.min(total.saturating_sub(self.pagina));
+        self.offset..(self.offset + self.pagina).min(total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selecao_continua_visivel_ao_encolher_a_pagina() {
+        let mut s = ScrollState::default();
+        s.visiveis(50, 20);
+        for _ in 0..30 {
+            s.desce(50);
+        }
+        assert_eq!(s.visiveis(50, 20), 11..31);
+        let r = s.visiveis(50, 3);
+        assert!(r.contains(&30) && r.len() == 3);
+    }
+
+    #[test]
+    fn lista_encolhida_nao_passa_do_fim() {
+        let mut s = ScrollState::default();
+        s.visiveis(10, 4);
+        for _ in 0..9 {
+            s.desce(10);
+        }
+        assert_eq!(s.visiveis(2, 4), 0..2);
+        assert_eq!(s.selected, 1);
+        assert_eq!(s.visiveis(0, 4), 0..0);
+    }
+}
@@ -0,0 +1,757 @@
+use crate::auditoria::{self, Acesso};
+use crate::dominio::{Canal, Situacao};
+use crate::persistencia::{self, na_pasta_dados};
+use crate::tokens::{self, Escopo};
+use crate::{
+    analise, cambio, config, load_from_file, quantidade_disponivel, App, Historico, Relogio,
+};
+use rust_embed::RustEmbed;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{self, BufWriter, Cursor, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, SyncSender, TryRecvError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+use std::{fs, thread};
+use tiny_http::{HTTPVersion, Header, Method, Response};
+use tungstenite::protocol::Role;
+use tungstenite::{Message, WebSocket};
+use utoipa::openapi::security::{Http, HttpAuthScheme, SecurityScheme};
+use utoipa::{Modify, OpenApi, ToSchema};
+
+/// Intervalo entre verificações de mudança no `estoque.json`.
+const INTERVALO_VIGIA: Duration = Duration::from_secs(1);
+
+/// Espera máxima por cada leitura do cliente.
+const ESPERA_LEITURA: Duration = Duration::from_secs(5);
+/// Prazo para a requisição inteira chegar, mesmo que aos poucos.
+const PRAZO_REQUISICAO: Duration = Duration::from_secs(10);
+/// Cliente que não recebe uma escrita nesse tempo é desconectado.
+const ESPERA_ESCRITA: Duration = Duration::from_secs(5);
+/// No WebSocket, intervalo em que a conexão para de ler o cliente para
+/// enviar os eventos da fila.
+const INTERVALO_WEBSOCKET: Duration = Duration::from_millis(250);
+/// Maiores cabeçalho e corpo aceitos numa requisição.
+const LIMITE_CABECALHO: usize = 16 * 1024;
+const LIMITE_CORPO: usize = 1024 * 1024;
+/// Eventos aguardando envio por cliente; quem acumula mais que isso é lento
+/// demais e sai da lista.
+const FILA_EVENTOS: usize = 64;
+
+/// Painel web (HTML/JS/CSS) embutido no executável.
+#[derive(RustEmbed)]
+#[folder = "web/"]
+struct Ativos;
+
+type Resposta = Response<Cursor<Vec<u8>>>;
+
+/// Requisição lida direto do socket. O tiny_http só monta as respostas: com
+/// o `TcpStream` em mãos, as esperas de leitura e escrita continuam valendo
+/// depois que a conexão vira WebSocket.
+struct Requisicao {
+    metodo: Method,
+    url: String,
+    cabecalhos: Vec<Header>,
+    corpo: Vec<u8>,
+    ip: Option<SocketAddr>,
+}
+
+impl Requisicao {
+    fn method(&self) -> &Method {
+        &self.metodo
+    }
+
+    fn url(&self) -> &str {
+        &self.url
+    }
+
+    fn headers(&self) -> &[Header] {
+        &self.cabecalhos
+    }
+
+    fn remote_addr(&self) -> Option<&SocketAddr> {
+        self.ip.as_ref()
+    }
+}
+
+fn invalida(motivo: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, motivo)
+}
+
+/// Lê uma requisição (cabeçalho e corpo pelo `Content-Length`) dentro de
+/// `PRAZO_REQUISICAO`. Cada conexão leva uma requisição só.
+fn le_requisicao(stream: &mut TcpStream) -> io::Result<Requisicao> {
+    let inicio = Instant::now();
+    let mut lido = vec![];
+    let mut pedaco = [0; 4096];
+    loop {
+        if inicio.elapsed() > PRAZO_REQUISICAO {
+            return Err(io::ErrorKind::TimedOut.into());
+        }
+        let n = stream.read(&mut pedaco)?;
+        if n == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        lido.extend_from_slice(&pedaco[..n]);
+        let mut campos = [httparse::EMPTY_HEADER; 64];
+        let mut pedido = httparse::Request::new(&mut campos);
+        let tamanho = match pedido.parse(&lido) {
+            Ok(httparse::Status::Complete(tamanho)) => tamanho,
+            Ok(httparse::Status::Partial) if lido.len() < LIMITE_CABECALHO => continue,
+            Ok(httparse::Status::Partial) => return Err(invalida("cabeçalho grande demais")),
+            Err(e) => return Err(invalida(&e.to_string())),
+        };
+        let metodo = pedido
+            .method
+            .and_then(|m| m.parse().ok())
+            .ok_or_else(|| invalida("método"))?;
+        let url = pedido.path.unwrap_or("/").to_string();
+        let cabecalhos: Vec<Header> = pedido
+            .headers
+            .iter()
+            .filter_map(|h| Header::from_bytes(h.name.as_bytes(), h.value).ok())
+            .collect();
+        let comprimento = cabecalhos
+            .iter()
+            .find(|h| h.field.equiv("Content-Length"))
+            .map(|h| h.value.as_str().trim().parse::<usize>())
+            .transpose()
+            .map_err(|_| invalida("Content-Length"))?
+            .unwrap_or(0);
+        if comprimento > LIMITE_CORPO {
+            return Err(invalida("corpo grande demais"));
+        }
+        let mut corpo = lido.split_off(tamanho);
+        corpo.truncate(comprimento);
+        while corpo.len() < comprimento {
+            if inicio.elapsed() > PRAZO_REQUISICAO {
+                return Err(io::ErrorKind::TimedOut.into());
+            }
+            let n = stream.read(&mut pedaco)?;
+            if n == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+            let falta = comprimento - corpo.len();
+            corpo.extend_from_slice(&pedaco[..n.min(falta)]);
+        }
+        return Ok(Requisicao {
+            metodo,
+            url,
+            cabecalhos,
+            corpo,
+            ip: stream.peer_addr().ok(),
+        });
+    }
+}
+
+/// Escreve a resposta e fecha a conexão.
+fn envia<R: Read>(stream: &mut TcpStream, resposta: Response<R>) -> io::Result<()> {
+    let mut saida = BufWriter::new(stream);
+    resposta
+        .with_header(cabecalho("Connection", "close"))
+        .raw_print(&mut saida, HTTPVersion(1, 1), &[], false, None)?;
+    saida.flush()
+}
+
+/// Vendas pela API recebidas ao mesmo tempo, cada uma em sua conexão.
+static VENDAS: Mutex<()> = Mutex::new(());
+
+/// Fila de eventos de cada WebSocket aberto; cada conexão tem sua thread.
+type Clientes = Arc<Mutex<Vec<SyncSender<String>>>>;
+
+#[derive(Serialize, PartialEq, ToSchema)]
+struct ItemEstoque {
+    codigo: String,
+    descricao: String,
+    /// Unidades disponíveis; para kits, quantos podem ser montados.
+    quantidade: i32,
+    preco: Option<f64>,
+    kit: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+struct VendasDia {
+    /// AAAA-MM-DD
+    data: String,
+    vendas: usize,
+    unidades: i32,
+    /// Receita em reais, já com descontos de promoção.
+    receita: f64,
+}
+
+/// Documento OpenAPI das rotas da API, servido em `/docs/openapi.json`.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "WatchManager",
+        description = "API do modo servidor (`Relogio --serve`). As rotas exigem um token \
+                       (`Relogio token criar`) em `Authorization: Bearer` ou `?token=`. \
+                       O WebSocket `/ws` envia eventos `estoque` e `venda` em JSON."
+    ),
+    paths(estoque, vendas_diarias, registra_venda),
+    modifiers(&SegurancaToken),
+    security(("token" = []))
+)]
+struct Documentacao;
+
+struct SegurancaToken;
+
+impl Modify for SegurancaToken {
+    fn modify(&self, doc: &mut utoipa::openapi::OpenApi) {
+        doc.components
+            .get_or_insert_with(Default::default)
+            .add_security_scheme(
+                "token",
+                SecurityScheme::Http(Http::new(HttpAuthScheme::Bearer)),
+            );
+    }
+}
+
+fn cabecalho(nome: &str, valor: &str) -> Header {
+    Header::from_bytes(nome.as_bytes(), valor.as_bytes()).expect("cabeçalho ASCII")
+}
+
+fn json<T: Serialize>(valor: &T) -> Resposta {
+    let corpo = serde_json::to_vec(valor).unwrap_or_default();
+    Response::from_data(corpo).with_header(cabecalho("Content-Type", "application/json"))
+}
+
+fn tipo_conteudo(caminho: &str) -> &'static str {
+    match caminho.rsplit('.').next() {
+        Some("html") => "text/html; charset=utf-8",
+        Some("js") => "text/javascript; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("svg") => "image/svg+xml",
+        _ => "application/octet-stream",
+    }
+}
+
+fn ativo(caminho: &str) -> Option<Resposta> {
+    let caminho = match caminho.trim_start_matches('/') {
+        "" => "index.html",
+        c => c,
+    };
+    let arquivo = Ativos::get(caminho)?;
+    Some(
+        Response::from_data(arquivo.data.into_owned())
+            .with_header(cabecalho("Content-Type", tipo_conteudo(caminho))),
+    )
+}
+
+/// Eventos enviados pelo WebSocket `/ws`.
+#[derive(Serialize)]
+#[serde(tag = "tipo", rename_all = "lowercase")]
+enum Evento<'a> {
+    /// Itens cujo estoque (ou cadastro) mudou.
+    Estoque { itens: Vec<&'a ItemEstoque> },
+    Venda {
+        codigo: &'a str,
+        quantidade: i32,
+        timestamp: &'a str,
+        pagamento: Option<&'a str>,
+    },
+}
+
+fn itens_estoque(relogios: &HashMap<String, Relogio>) -> Vec<ItemEstoque> {
+    let mut itens: Vec<ItemEstoque> = relogios
+        .values()
+        .filter(|r| r.situacao != Situacao::Arquivado)
+        .map(|r| ItemEstoque {
+            codigo: r.codigo.clone(),
+            descricao: r.descricao.clone(),
+            quantidade: quantidade_disponivel(relogios, r),
+            preco: r.preco,
+            kit: !r.componentes.is_empty(),
+        })
+        .collect();
+    itens.sort_by(|a, b| a.codigo.cmp(&b.codigo));
+    itens
+}
+
+/// Estoque atual de todos os produtos, ordenado por código.
+#[utoipa::path(
+    get,
+    path = "/api/estoque",
+    responses(
+        (status = 200, body = Vec<ItemEstoque>),
+        (status = 401, description = "Token ausente ou inválido"),
+        (status = 429, description = "Limite de requisições excedido")
+    )
+)]
+fn estoque() -> Resposta {
+    let (relogios, _, _) = load_from_file();
+    json(&itens_estoque(&relogios))
+}
+
+/// Vendas agregadas por dia.
+#[utoipa::path(
+    get,
+    path = "/api/vendas/diario",
+    responses(
+        (status = 200, body = Vec<VendasDia>),
+        (status = 401, description = "Token ausente ou inválido"),
+        (status = 429, description = "Limite de requisições excedido")
+    )
+)]
+fn vendas_diarias() -> Resposta {
+    let (relogios, historico, _) = load_from_file();
+    let dias: Vec<VendasDia> = analise::vendas_por_dia(&historico, &relogios)
+        .into_iter()
+        .map(|(data, vendas, unidades, receita)| VendasDia {
+            data,
+            vendas,
+            unidades,
+            receita,
+        })
+        .collect();
+    json(&dias)
+}
+
+#[derive(Deserialize, ToSchema)]
+struct PedidoVenda {
+    codigo: String,
+    quantidade: i32,
+    /// dinheiro (padrão), cartao ou pix.
+    pagamento: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct ResultadoVenda {
+    ok: bool,
+    /// Mensagens da operação, como na tela de Venda.
+    mensagens: Vec<String>,
+}
+
+/// Registra uma venda pelas mesmas regras da tela de Venda (limites, margem,
+/// promoções) e devolve as mensagens geradas.
+#[utoipa::path(
+    post,
+    path = "/api/vendas",
+    request_body = PedidoVenda,
+    responses(
+        (status = 201, description = "Venda registrada", body = ResultadoVenda),
+        (status = 400, description = "Pedido inválido"),
+        (status = 401, description = "Token ausente ou inválido"),
+        (status = 403, description = "Token sem escopo de escrita"),
+        (status = 409, description = "Venda recusada (estoque, limite, margem)", body = ResultadoVenda),
+        (status = 423, description = "Estoque aberto na interface; feche-a para vender pela API"),
+        (status = 429, description = "Limite de requisições excedido")
+    )
+)]
+fn registra_venda(req: &Requisicao) -> Resposta {
+    let pedido: PedidoVenda = match serde_json::from_slice(&req.corpo) {
+        Ok(p) => p,
+        Err(e) => {
+            return Response::from_string(format!("JSON inválido: {}", e)).with_status_code(400)
+        }
+    };
+    let pagamento = pedido.pagamento.unwrap_or_else(|| "dinheiro".into());
+    if pedido.quantidade <= 0 || !["dinheiro", "cartao", "pix"].contains(&pagamento.as_str()) {
+        return Response::from_string("Quantidade ou forma de pagamento inválida")
+            .with_status_code(400);
+    }
+    // Uma venda por vez neste processo; a trava do arquivo afasta a interface
+    let _vez = VENDAS.lock().unwrap_or_else(|e| e.into_inner());
+    let _trava = match persistencia::trava() {
+        Ok(Some(trava)) => trava,
+        Ok(None) => {
+            return Response::from_string(
+                "Estoque aberto na interface; feche-a para vender pela API",
+            )
+            .with_status_code(423)
+        }
+        Err(e) => return Response::from_string(e.to_string()).with_status_code(500),
+    };
+    let (relogios, historico, registros) = load_from_file();
+    let mut app = App::com_dados(
+        relogios,
+        historico,
+        registros,
+        config::Config::carregar(),
+        cambio::carrega_cache(),
+    );
+    let msgs = app.mensagens.len();
+    let vendeu = app.vender_relogio(
+        pedido.codigo,
+        pedido.quantidade,
+        Some(pagamento),
+        false,
+        Canal::Api,
+    );
+    let resposta = json(&ResultadoVenda {
+        ok: vendeu,
+        mensagens: app.mensagens[msgs..].to_vec(),
+    });
+    if vendeu {
+        resposta.with_status_code(201)
+    } else {
+        resposta.with_status_code(409)
+    }
+}
+
+/// Token enviado em `Authorization: Bearer` ou, para o navegador e o
+/// WebSocket, no parâmetro `?token=`.
+fn token_da_requisicao(req: &Requisicao) -> Option<String> {
+    req.headers()
+        .iter()
+        .find(|h| h.field.equiv("Authorization"))
+        .and_then(|h| h.value.as_str().strip_prefix("Bearer ").map(str::to_string))
+        .or_else(|| {
+            req.url()
+                .split_once('?')?
+                .1
+                .split('&')
+                .find_map(|p| p.strip_prefix("token=").map(str::to_string))
+        })
+}
+
+/// Janela de um minuto por token; ao estourar o limite, devolve em quantos
+/// segundos a janela reabre.
+struct Limitador {
+    por_minuto: u32,
+    janelas: HashMap<String, (Instant, u32)>,
+}
+
+impl Limitador {
+    fn new(por_minuto: u32) -> Limitador {
+        Limitador {
+            por_minuto,
+            janelas: HashMap::new(),
+        }
+    }
+
+    fn permite(&mut self, chave: &str, agora: Instant) -> Result<(), u64> {
+        if self.por_minuto == 0 {
+            return Ok(());
+        }
+        let janela = self.janelas.entry(chave.to_string()).or_insert((agora, 0));
+        let decorrido = agora.duration_since(janela.0);
+        if decorrido >= Duration::from_secs(60) {
+            *janela = (agora, 0);
+        }
+        if janela.1 >= self.por_minuto {
+            return Err(60 - decorrido.as_secs().min(59));
+        }
+        janela.1 += 1;
+        Ok(())
+    }
+}
+
+/// Nome do token se ele cobre o escopo exigido e está dentro do limite;
+/// senão a resposta de erro (401 sem token válido, 403 com escopo
+/// insuficiente, 429 acima do limite) junto do nome, quando conhecido.
+fn autoriza(
+    req: &Requisicao,
+    exigido: Escopo,
+    limitador: &Mutex<Limitador>,
+) -> Result<String, (Option<String>, Resposta)> {
+    let lista = tokens::carrega();
+    let token = token_da_requisicao(req).and_then(|v| tokens::autentica(&lista, &v).cloned());
+    let Some(t) = token else {
+        return Err((
+            None,
+            Response::from_string("Token ausente ou inválido")
+                .with_status_code(401)
+                .with_header(cabecalho("WWW-Authenticate", "Bearer")),
+        ));
+    };
+    if !t.escopo.permite(exigido) {
+        return Err((
+            Some(t.nome),
+            Response::from_string("Token sem permissão de escrita").with_status_code(403),
+        ));
+    }
+    let permitido = limitador
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .permite(&t.nome, Instant::now());
+    if let Err(espera) = permitido {
+        return Err((
+            Some(t.nome),
+            Response::from_string("Limite de requisições excedido")
+                .with_status_code(429)
+                .with_header(cabecalho("Retry-After", &espera.to_string())),
+        ));
+    }
+    Ok(t.nome)
+}
+
+fn escopo_da_rota(req: &Requisicao) -> Option<Escopo> {
+    let caminho = req.url().split('?').next().unwrap_or("/");
+    match (req.method(), caminho) {
+        (Method::Post, _) => Some(Escopo::Escrita),
+        (_, "/ws") => Some(Escopo::Leitura),
+        (_, c) if c.starts_with("/api/") => Some(Escopo::Leitura),
+        _ => None,
+    }
+}
+
+fn responde(req: &Requisicao) -> Resposta {
+    let caminho = req.url().split('?').next().unwrap_or("/").to_string();
+    match (req.method(), caminho.as_str()) {
+        (Method::Get, "/api/estoque") => estoque(),
+        (Method::Get, "/api/vendas/diario") => vendas_diarias(),
+        (Method::Post, "/api/vendas") => registra_venda(req),
+        (Method::Get, "/docs/openapi.json") => json(&Documentacao::openapi()),
+        (Method::Get, "/docs") => ativo("docs.html").expect("docs.html embutido"),
+        (Method::Get, c) => ativo(c)
+            .unwrap_or_else(|| Response::from_string("Não encontrado").with_status_code(404)),
+        _ => Response::from_string("Método não permitido").with_status_code(405),
+    }
+}
+
+fn chave_websocket(req: &Requisicao) -> Option<String> {
+    let cabecalho = |nome: &'static str| {
+        req.headers()
+            .iter()
+            .find(|h| h.field.equiv(nome))
+            .map(|h| h.value.as_str().to_string())
+    };
+    let upgrade = cabecalho("Upgrade")?;
+    let caminho = req.url().split('?').next().unwrap_or("/");
+    (caminho == "/ws" && upgrade.eq_ignore_ascii_case("websocket"))
+        .then(|| cabecalho("Sec-WebSocket-Key"))
+        .flatten()
+}
+
+/// Conclui o handshake e passa a conexão para uma thread própria, que
+/// recebe os eventos por uma fila.
+fn aceita_websocket(mut stream: TcpStream, chave: &str, clientes: &Clientes) -> io::Result<()> {
+    let aceite = tungstenite::handshake::derive_accept_key(chave.as_bytes());
+    let mut saida = BufWriter::new(&mut stream);
+    Response::empty(101)
+        .with_header(cabecalho("Sec-WebSocket-Accept", &aceite))
+        .raw_print(&mut saida, HTTPVersion(1, 1), &[], false, Some("websocket"))?;
+    saida.flush()?;
+    drop(saida);
+    stream.set_read_timeout(Some(INTERVALO_WEBSOCKET))?;
+    let ws = WebSocket::from_raw_socket(stream, Role::Server, None);
+    let (tx, rx) = mpsc::sync_channel(FILA_EVENTOS);
+    if let Ok(mut c) = clientes.lock() {
+        c.push(tx);
+    }
+    thread::spawn(move || atende_websocket(ws, rx));
+    Ok(())
+}
+
+/// Alterna entre ler o cliente (o tungstenite responde sozinho a ping e
+/// close) e enviar os eventos da fila, até a conexão fechar, uma escrita
+/// esgotar `ESPERA_ESCRITA` ou o cliente sair da lista.
+fn atende_websocket(mut ws: WebSocket<TcpStream>, eventos: Receiver<String>) {
+    loop {
+        match ws.read() {
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e))
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) => {}
+            Err(_) => return,
+        }
+        loop {
+            match eventos.try_recv() {
+                Ok(texto) => {
+                    if ws.send(Message::text(texto)).is_err() {
+                        return;
+                    }
+                }
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => {
+                    let _ = ws.close(None);
+                    let _ = ws.flush();
+                    return;
+                }
+            }
+        }
+        // Envia o pong ou a resposta ao close que a leitura deixou na fila
+        if ws.flush().is_err() {
+            return;
+        }
+    }
+}
+
+/// Põe o evento na fila de cada cliente sem esperar por nenhum: quem já
+/// saiu ou está com a fila cheia é removido.
+fn transmite(clientes: &Clientes, evento: &Evento) {
+    let Ok(texto) = serde_json::to_string(evento) else {
+        return;
+    };
+    if let Ok(mut c) = clientes.lock() {
+        c.retain(|tx| tx.try_send(texto.clone()).is_ok());
+    }
+}
+
+/// Relê o `estoque.json` quando ele muda e transmite o que mudou: itens de
+/// estoque alterados e as vendas novas no histórico.
+fn vigia(clientes: Clientes) {
+    let modificado = || {
+        fs::metadata(na_pasta_dados("estoque.json"))
+            .and_then(|m| m.modified())
+            .ok()
+    };
+    let (relogios, historico, _) = load_from_file();
+    let mut ultima: Option<SystemTime> = modificado();
+    let mut itens = itens_estoque(&relogios);
+    let mut total_historico = historico.len();
+    loop {
+        thread::sleep(INTERVALO_VIGIA);
+        let atual = modificado();
+        if atual == ultima {
+            continue;
+        }
+        ultima = atual;
+        let (relogios, historico, _) = load_from_file();
+        let novos = itens_estoque(&relogios);
+        let mudaram: Vec<&ItemEstoque> = novos.iter().filter(|n| !itens.contains(n)).collect();
+        if !mudaram.is_empty() {
+            transmite(&clientes, &Evento::Estoque { itens: mudaram });
+        }
+        // Histórico menor que antes (arquivamento): nada de novo a anunciar
+        let vendas: Vec<&Historico> = historico
+            .get(total_historico..)
+            .unwrap_or_default()
+            .iter()
+            .filter(|h| h.operacao == "VENDA")
+            .collect();
+        for h in vendas {
+            transmite(
+                &clientes,
+                &Evento::Venda {
+                    codigo: &h.codigo,
+                    quantidade: h.quantidade,
+                    timestamp: &h.timestamp,
+                    pagamento: h.pagamento.as_deref(),
+                },
+            );
+        }
+        itens = novos;
+        total_historico = historico.len();
+    }
+}
+
+/// Serve o painel e a API em `endereco` (ex.: 127.0.0.1:8080, ou
+/// 0.0.0.0:8080 para a rede da loja); a API e o
+/// WebSocket exigem um token (ver `tokens`).
+/// Os dados são relidos do `estoque.json` a cada requisição, e as mudanças
+/// são transmitidas pelo WebSocket `/ws`.
+pub fn servir(endereco: &str) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let servidor = TcpListener::bind(endereco)?;
+    let clientes: Clientes = Arc::default();
+    let c = clientes.clone();
+    thread::spawn(move || vigia(c));
+    println!("Painel disponível em http://{}/", endereco);
+    if tokens::carrega().is_empty() {
+        println!("Nenhum token de API: crie um com `Relogio token criar <nome> leitura`.");
+    }
+    let limitador = Arc::new(Mutex::new(Limitador::new(
+        config::Config::carregar().api_limite_por_minuto,
+    )));
+    // Cada conexão em sua thread: uma requisição lenta não segura as outras
+    for conexao in servidor.incoming() {
+        let Ok(stream) = conexao else {
+            continue;
+        };
+        let (limitador, clientes) = (limitador.clone(), clientes.clone());
+        thread::spawn(move || atende(stream, &limitador, &clientes));
+    }
+    Ok(())
+}
+
+/// Lê a requisição da conexão, confere o token quando a rota exige e
+/// responde (ou passa a conexão para o WebSocket).
+fn atende(mut stream: TcpStream, limitador: &Mutex<Limitador>, clientes: &Clientes) {
+    let esperas = stream
+        .set_read_timeout(Some(ESPERA_LEITURA))
+        .and_then(|_| stream.set_write_timeout(Some(ESPERA_ESCRITA)));
+    if esperas.is_err() {
+        return;
+    }
+    let req = match le_requisicao(&mut stream) {
+        Ok(req) => req,
+        Err(e) if e.kind() == io::ErrorKind::InvalidData => {
+            let resposta = Response::from_string("Requisição inválida").with_status_code(400);
+            let _ = envia(&mut stream, resposta);
+            return;
+        }
+        // Conexão fechada ou lenta demais
+        Err(_) => return,
+    };
+    // Arquivos do painel são públicos e ficam fora do log
+    let Some(exigido) = escopo_da_rota(&req) else {
+        let _ = envia(&mut stream, responde(&req));
+        return;
+    };
+    let mut acesso = Acesso {
+        timestamp: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        token: None,
+        ip: req
+            .remote_addr()
+            .map(|a| a.ip().to_string())
+            .unwrap_or_default(),
+        metodo: req.method().to_string(),
+        caminho: req.url().split('?').next().unwrap_or("/").to_string(),
+        status: 0,
+    };
+    match autoriza(&req, exigido, limitador) {
+        Err((nome, negado)) => {
+            acesso.token = nome;
+            acesso.status = negado.status_code().0;
+            let _ = envia(&mut stream, negado);
+        }
+        Ok(nome) => {
+            acesso.token = Some(nome);
+            if let Some(chave) = chave_websocket(&req) {
+                acesso.status = 101;
+                let _ = aceita_websocket(stream, &chave, clientes);
+            } else {
+                let resposta = responde(&req);
+                acesso.status = resposta.status_code().0;
+                let _ = envia(&mut stream, resposta);
+            }
+        }
+    }
+    auditoria::registra(&acesso);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transmite_descarta_clientes_fechados_ou_lentos() {
+        let (ativo, rx_ativo) = mpsc::sync_channel(2);
+        let (lento, _rx_lento) = mpsc::sync_channel(0);
+        let (fechado, rx_fechado) = mpsc::sync_channel(2);
+        drop(rx_fechado);
+        let clientes: Clientes = Arc::new(Mutex::new(vec![ativo, lento, fechado]));
+        transmite(&clientes, &Evento::Estoque { itens: vec![] });
+        assert_eq!(clientes.lock().unwrap().len(), 1);
+        assert!(rx_ativo.try_recv().unwrap().contains("estoque"));
+    }
+
+    #[test]
+    fn limitador_bloqueia_acima_do_limite_ate_a_janela_virar() {
+        let mut l = Limitador::new(2);
+        let t0 = Instant::now();
+        assert!(l.permite("a", t0).is_ok());
+        assert!(l.permite("a", t0).is_ok());
+        assert_eq!(l.permite("a", t0 + Duration::from_secs(20)), Err(40));
+        // Cada token tem a própria janela
+        assert!(l.permite("b", t0).is_ok());
+        assert!(l.permite("a", t0 + Duration::from_secs(60)).is_ok());
+    }
+
+    #[test]
+    fn documentacao_lista_as_rotas_da_api() {
+        let doc = serde_json::to_value(Documentacao::openapi()).unwrap();
+        for rota in ["/api/estoque", "/api/vendas/diario", "/api/vendas"] {
+            assert!(doc["paths"][rota].is_object(), "{} fora do OpenAPI", rota);
+        }
+        assert!(doc["components"]["schemas"]["PedidoVenda"].is_object());
+        assert_eq!(
+            doc["components"]["securitySchemes"]["token"]["scheme"],
+            "bearer"
+        );
+    }
+}
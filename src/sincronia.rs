@@ -0,0 +1,290 @@
+use crate::arquivo::{self, OP_SALDO_INICIAL};
+use crate::{Historico, Relogio};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+/// Tudo o que uma loja manda para a outra: o histórico completo, com a
+/// origem de cada lançamento, e o cadastro dos produtos.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Pacote {
+    pub loja: String,
+    pub gerado: String,
+    pub relogios: Vec<Relogio>,
+    pub historico: Vec<Historico>,
+}
+
+/// Código cujo saldo ficou negativo ao juntar as duas lojas: ambas venderam
+/// a mesma unidade enquanto estavam sem conexão.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Conflito {
+    pub codigo: String,
+    pub saldo: i32,
+    /// Vendas que deixaram o saldo negativo: "timestamp loja quantidade".
+    pub vendas: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct Resultado {
+    pub lancamentos_novos: usize,
+    pub produtos_novos: usize,
+    pub conflitos: Vec<Conflito>,
+}
+
+/// Identidade de um lançamento entre as lojas. O histórico só cresce, então
+/// juntar dois históricos é a união dessas chaves (um G-Set). A sequência
+/// separa vendas iguais no mesmo segundo; lançamentos antigos, sem ela,
+/// contam pelo resto da chave.
+type Chave = (
+    String,
+    Option<u64>,
+    String,
+    String,
+    String,
+    i32,
+    Option<String>,
+);
+
+fn chave(h: &Historico, loja_padrao: &str) -> Chave {
+    (
+        h.origem.clone().unwrap_or_else(|| loja_padrao.to_string()),
+        h.sequencia,
+        h.timestamp.clone(),
+        h.codigo.clone(),
+        h.operacao.clone(),
+        h.quantidade,
+        h.kit.clone(),
+    )
+}
+
+/// Pacote desta loja; lançamentos sem origem são daqui.
+pub fn exporta(
+    loja: &str,
+    relogios: &HashMap<String, Relogio>,
+    historico: &[Historico],
+    gerado: &str,
+) -> Pacote {
+    let mut lista: Vec<Relogio> = relogios.values().cloned().collect();
+    lista.sort_by(|a, b| a.codigo.cmp(&b.codigo));
+    Pacote {
+        loja: loja.to_string(),
+        gerado: gerado.to_string(),
+        relogios: lista,
+        historico: historico
+            .iter()
+            .map(|h| Historico {
+                origem: Some(h.origem.clone().unwrap_or_else(|| loja.to_string())),
+                ..h.clone()
+            })
+            .collect(),
+    }
+}
+
+/// Junta o pacote da outra loja ao histórico local e refaz o saldo dos
+/// códigos afetados a partir do histórico. Aplicar o mesmo pacote de novo
+/// não muda nada.
+///
+/// Saldos iniciais da outra loja e lançamentos dela anteriores ao saldo
+/// inicial local do código são ignorados: esse passado já está resumido aqui.
+pub fn mescla(
+    loja: &str,
+    relogios: &mut HashMap<String, Relogio>,
+    historico: &mut Vec<Historico>,
+    pacote: &Pacote,
+) -> Resultado {
+    let mut resultado = Resultado::default();
+    let mut conhecidas: HashSet<Chave> = historico.iter().map(|h| chave(h, loja)).collect();
+    let mut corte: HashMap<&str, &str> = HashMap::new();
+    for h in historico.iter().filter(|h| h.operacao == OP_SALDO_INICIAL) {
+        let c = corte.entry(&h.codigo).or_insert(&h.timestamp);
+        if h.timestamp.as_str() > *c {
+            *c = &h.timestamp;
+        }
+    }
+    let novos: Vec<Historico> = pacote
+        .historico
+        .iter()
+        .filter(|h| h.operacao != OP_SALDO_INICIAL)
+        .filter(|h| {
+            corte
+                .get(h.codigo.as_str())
+                .is_none_or(|c| h.timestamp.as_str() >= *c)
+        })
+        .filter(|h| conhecidas.insert(chave(h, &pacote.loja)))
+        .map(|h| Historico {
+            origem: Some(h.origem.clone().unwrap_or_else(|| pacote.loja.clone())),
+            ..h.clone()
+        })
+        .collect();
+    resultado.lancamentos_novos = novos.len();
+
+    for r in &pacote.relogios {
+        if !relogios.contains_key(&r.codigo) {
+            relogios.insert(r.codigo.clone(), r.clone());
+            resultado.produtos_novos += 1;
+        }
+    }
+    if novos.is_empty() {
+        return resultado;
+    }
+
+    let afetados: HashSet<String> = novos.iter().map(|h| h.codigo.clone()).collect();
+    historico.extend(novos);
+    historico.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+
+    for r in arquivo::saldos(historico, relogios) {
+        let Some(local) = relogios.get_mut(&r.codigo) else {
+            continue;
+        };
+        if afetados.contains(&r.codigo) && local.componentes.is_empty() {
+            local.define_quantidade(r.quantidade());
+        }
+    }
+    resultado.conflitos = conflitos(historico, &afetados, loja);
+    resultado
+}
+
+/// Reproduz o histórico dos códigos e aponta as vendas feitas com saldo
+/// insuficiente. Só conta como conflito o código vendido pelas duas lojas;
+/// saldo negativo vindo de uma loja só é problema antigo dela.
+fn conflitos(historico: &[Historico], codigos: &HashSet<String>, loja: &str) -> Vec<Conflito> {
+    let mut saldo: HashMap<&str, i32> = HashMap::new();
+    let mut por_codigo: HashMap<&str, Vec<String>> = HashMap::new();
+    let mut lojas: HashMap<&str, HashSet<&str>> = HashMap::new();
+    for h in historico.iter().filter(|h| codigos.contains(&h.codigo)) {
+        if h.operacao == "VENDA" || h.operacao == "BAIXA_KIT" {
+            lojas
+                .entry(&h.codigo)
+                .or_default()
+                .insert(h.origem.as_deref().unwrap_or(loja));
+        }
+        let s = saldo.entry(&h.codigo).or_insert(0);
+        match h.operacao.as_str() {
+            "CADASTRO" | OP_SALDO_INICIAL => *s = h.quantidade,
+            "COMPRA" | "AJUSTE" => *s += h.quantidade,
+            "VENDA" | "BAIXA_KIT" => {
+                *s -= h.quantidade;
+                if *s < 0 {
+                    por_codigo.entry(&h.codigo).or_default().push(format!(
+                        "{} {} {}",
+                        h.timestamp,
+                        h.origem.as_deref().unwrap_or(loja),
+                        h.quantidade
+                    ));
+                }
+            }
+            _ => {}
+        }
+    }
+    let mut lista: Vec<Conflito> = por_codigo
+        .into_iter()
+        .filter(|(codigo, _)| lojas.get(codigo).is_some_and(|l| l.len() > 1))
+        .map(|(codigo, vendas)| Conflito {
+            codigo: codigo.to_string(),
+            saldo: saldo[codigo],
+            vendas,
+        })
+        .collect();
+    lista.sort_by(|a, b| a.codigo.cmp(&b.codigo));
+    lista
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dominio::Quantidade;
+
+    fn h(codigo: &str, operacao: &str, quantidade: i32, timestamp: &str) -> Historico {
+        Historico {
+            codigo: codigo.into(),
+            quantidade,
+            operacao: operacao.into(),
+            timestamp: timestamp.into(),
+            ..Default::default()
+        }
+    }
+
+    fn estoque(codigo: &str, quantidade: i32) -> HashMap<String, Relogio> {
+        [(
+            codigo.to_string(),
+            Relogio::novo(codigo, Quantidade::nova(quantidade).unwrap()),
+        )]
+        .into_iter()
+        .collect()
+    }
+
+    #[test]
+    fn duas_lojas_vendem_a_ultima_unidade_sem_conexao() {
+        // A cadastra e manda para B; cada uma vende a unidade offline
+        let mut hist_a = vec![h("R1", "CADASTRO", 1, "2024-03-01 09:00:00")];
+        let mut rel_a = estoque("R1", 1);
+        let (mut rel_b, mut hist_b) = (HashMap::new(), vec![]);
+        let pacote_a = exporta("centro", &rel_a, &hist_a, "2024-03-01 10:00:00");
+        mescla("shopping", &mut rel_b, &mut hist_b, &pacote_a);
+        assert_eq!(rel_b["R1"].quantidade(), 1);
+
+        hist_a.push(h("R1", "VENDA", 1, "2024-03-02 11:00:00"));
+        rel_a.get_mut("R1").unwrap().define_quantidade(0);
+        hist_b.push(h("R1", "VENDA", 1, "2024-03-02 15:00:00"));
+        rel_b.get_mut("R1").unwrap().define_quantidade(0);
+
+        let pacote_b = exporta("shopping", &rel_b, &hist_b, "2024-03-03 08:00:00");
+        let r = mescla("centro", &mut rel_a, &mut hist_a, &pacote_b);
+        assert_eq!((r.lancamentos_novos, r.produtos_novos), (1, 0));
+        assert_eq!(rel_a["R1"].quantidade(), -1);
+        assert_eq!(
+            r.conflitos,
+            vec![Conflito {
+                codigo: "R1".into(),
+                saldo: -1,
+                vendas: vec!["2024-03-02 15:00:00 shopping 1".into()],
+            }]
+        );
+        // As duas vendas ficam; a venda local continua sem origem
+        assert_eq!(hist_a.len(), 3);
+        assert_eq!(hist_a[1].origem, None);
+
+        // Reaplicar é inócuo, e B chega ao mesmo histórico
+        assert_eq!(
+            mescla("centro", &mut rel_a, &mut hist_a, &pacote_b).lancamentos_novos,
+            0
+        );
+        let pacote_a = exporta("centro", &rel_a, &hist_a, "2024-03-03 09:00:00");
+        mescla("shopping", &mut rel_b, &mut hist_b, &pacote_a);
+        assert_eq!(hist_b.len(), 3);
+        assert_eq!(rel_b["R1"].quantidade(), -1);
+    }
+
+    #[test]
+    fn vendas_iguais_no_mesmo_segundo_nao_viram_uma() {
+        use crate::dominio::{Inventario, ProdutoId, Quantidade};
+        let (mut rel_a, mut hist_a) = (estoque("R1", 5), vec![]);
+        let quando = chrono::NaiveDateTime::parse_from_str(
+            "2024-03-02 11:00:00",
+            crate::dominio::FORMATO_TIMESTAMP,
+        )
+        .unwrap();
+        let r1 = ProdutoId::novo("R1").unwrap();
+        let um = Quantidade::movimento(1).unwrap();
+        Inventario::new(&mut rel_a, &mut hist_a)
+            .vende(&r1, um, quando)
+            .unwrap();
+        Inventario::new(&mut rel_a, &mut hist_a)
+            .vende(&r1, um, quando)
+            .unwrap();
+        assert_eq!(
+            hist_a.iter().map(|h| h.sequencia).collect::<Vec<_>>(),
+            [Some(1), Some(2)]
+        );
+
+        let mut rel_b = estoque("R1", 5);
+        let mut hist_b = vec![];
+        let pacote = exporta("centro", &rel_a, &hist_a, "2024-03-02 12:00:00");
+        let r = mescla("shopping", &mut rel_b, &mut hist_b, &pacote);
+        assert_eq!(r.lancamentos_novos, 2);
+        assert_eq!(hist_b.len(), 2);
+        assert_eq!(
+            mescla("shopping", &mut rel_b, &mut hist_b, &pacote).lancamentos_novos,
+            0
+        );
+    }
+}
@@ -0,0 +1,340 @@
+use crate::{credito, Historico, Registros, Relogio};
+use rusqlite::{params, Connection, Transaction};
+use std::collections::HashMap;
+use std::fs;
+
+/// Tabelas normalizadas: clientes e fornecedores viram linhas próprias,
+/// referenciadas por id em vez de repetir o nome.
+const ESQUEMA: &str = "
+CREATE TABLE produtos (
+    codigo TEXT PRIMARY KEY,
+    descricao TEXT NOT NULL,
+    quantidade INTEGER NOT NULL,
+    custo REAL,
+    preco REAL,
+    moeda TEXT,
+    categoria TEXT,
+    marca TEXT,
+    ean TEXT,
+    unidades_por_pacote INTEGER,
+    limite_por_venda INTEGER,
+    estoque_alvo INTEGER,
+    estoque_minimo INTEGER,
+    situacao TEXT NOT NULL
+);
+CREATE TABLE componentes (
+    kit TEXT NOT NULL REFERENCES produtos(codigo),
+    componente TEXT NOT NULL,
+    quantidade INTEGER NOT NULL
+);
+CREATE TABLE clientes (
+    id INTEGER PRIMARY KEY,
+    nome TEXT NOT NULL,
+    documento TEXT
+);
+CREATE TABLE fornecedores (
+    id INTEGER PRIMARY KEY,
+    nome TEXT NOT NULL
+);
+CREATE TABLE referencias_fornecedor (
+    fornecedor_id INTEGER NOT NULL REFERENCES fornecedores(id),
+    codigo_fornecedor TEXT NOT NULL,
+    produto TEXT NOT NULL
+);
+CREATE TABLE historico (
+    id INTEGER PRIMARY KEY,
+    produto TEXT NOT NULL,
+    operacao TEXT NOT NULL,
+    quantidade INTEGER NOT NULL,
+    timestamp TEXT NOT NULL,
+    data TEXT NOT NULL,
+    kit TEXT,
+    pagamento TEXT,
+    nota INTEGER,
+    promocao TEXT,
+    desconto REAL,
+    motivo TEXT,
+    origem TEXT,
+    custo REAL,
+    preco REAL,
+    nfe TEXT,
+    fornecedor_id INTEGER REFERENCES fornecedores(id)
+);
+CREATE TABLE historico_tags (
+    historico_id INTEGER NOT NULL REFERENCES historico(id),
+    tag TEXT NOT NULL
+);
+CREATE TABLE notas (
+    numero INTEGER PRIMARY KEY,
+    timestamp TEXT NOT NULL,
+    cliente_id INTEGER REFERENCES clientes(id),
+    aliquota REAL NOT NULL
+);
+CREATE TABLE itens_nota (
+    nota INTEGER NOT NULL REFERENCES notas(numero),
+    produto TEXT NOT NULL,
+    descricao TEXT NOT NULL,
+    quantidade INTEGER NOT NULL,
+    preco_unitario REAL NOT NULL
+);
+CREATE TABLE creditos (
+    id INTEGER PRIMARY KEY,
+    cliente_id INTEGER NOT NULL REFERENCES clientes(id),
+    valor REAL NOT NULL,
+    motivo TEXT NOT NULL,
+    timestamp TEXT NOT NULL
+);
+CREATE TABLE lista_espera (
+    id INTEGER PRIMARY KEY,
+    cliente_id INTEGER NOT NULL REFERENCES clientes(id),
+    produto TEXT NOT NULL,
+    contato TEXT NOT NULL,
+    timestamp TEXT NOT NULL
+);
+CREATE INDEX historico_produto ON historico(produto, data);
+";
+
+/// Ids atribuídos por nome (sem caixa e sem espaços extras), criando a linha
+/// na primeira vez.
+struct Cadastro {
+    tabela: &'static str,
+    ids: HashMap<String, i64>,
+}
+
+impl Cadastro {
+    fn new(tabela: &'static str) -> Self {
+        Cadastro {
+            tabela,
+            ids: HashMap::new(),
+        }
+    }
+
+    fn id(&mut self, tx: &Transaction, nome: &str) -> rusqlite::Result<i64> {
+        let chave = credito::chave(nome);
+        if let Some(id) = self.ids.get(&chave) {
+            return Ok(*id);
+        }
+        tx.execute(
+            &format!("INSERT INTO {} (nome) VALUES (?1)", self.tabela),
+            params![nome.trim()],
+        )?;
+        let id = tx.last_insert_rowid();
+        self.ids.insert(chave, id);
+        Ok(id)
+    }
+}
+
+/// Grava tudo num arquivo SQLite novo (substitui o existente). Devolve o
+/// número de lançamentos do histórico exportados.
+pub fn exporta(
+    caminho: &str,
+    relogios: &HashMap<String, Relogio>,
+    historico: &[Historico],
+    registros: &Registros,
+) -> rusqlite::Result<usize> {
+    let _ = fs::remove_file(caminho);
+    let mut conn = Connection::open(caminho)?;
+    conn.execute_batch(ESQUEMA)?;
+    let tx = conn.transaction()?;
+
+    let mut lista: Vec<&Relogio> = relogios.values().collect();
+    lista.sort_by(|a, b| a.codigo.cmp(&b.codigo));
+    for r in lista {
+        tx.execute(
+            "INSERT INTO produtos VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+            params![
+                r.codigo,
+                r.descricao,
+                r.quantidade(),
+                r.custo,
+                r.preco,
+                r.moeda,
+                r.categoria,
+                r.marca,
+                r.ean,
+                r.unidades_por_pacote,
+                r.limite_por_venda,
+                r.estoque_alvo,
+                r.estoque_minimo,
+                r.situacao.rotulo()
+            ],
+        )?;
+        for c in &r.componentes {
+            tx.execute(
+                "INSERT INTO componentes VALUES (?1, ?2, ?3)",
+                params![r.codigo, c.codigo, c.quantidade],
+            )?;
+        }
+    }
+
+    let mut fornecedores = Cadastro::new("fornecedores");
+    for f in &registros.referencias_fornecedor {
+        let id = fornecedores.id(&tx, &f.fornecedor)?;
+        tx.execute(
+            "INSERT INTO referencias_fornecedor VALUES (?1, ?2, ?3)",
+            params![id, f.codigo_fornecedor, f.codigo],
+        )?;
+    }
+
+    for (i, h) in historico.iter().enumerate() {
+        let fornecedor = match &h.fornecedor {
+            Some(nome) => Some(fornecedores.id(&tx, nome)?),
+            None => None,
+        };
+        tx.execute(
+            "INSERT INTO historico VALUES
+             (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+            params![
+                i as i64 + 1,
+                h.codigo,
+                h.operacao,
+                h.quantidade,
+                h.timestamp,
+                h.timestamp.get(..10).unwrap_or(&h.timestamp),
+                h.kit,
+                h.pagamento,
+                h.nota,
+                h.promocao,
+                h.desconto,
+                h.motivo,
+                h.origem,
+                h.custo,
+                h.preco,
+                h.nfe,
+                fornecedor
+            ],
+        )?;
+        for tag in &h.tags {
+            tx.execute(
+                "INSERT INTO historico_tags VALUES (?1, ?2)",
+                params![i as i64 + 1, tag],
+            )?;
+        }
+    }
+
+    let mut clientes = Cadastro::new("clientes");
+    for n in &registros.notas {
+        let cliente = match n.cliente_nome.trim() {
+            "" => None,
+            nome => {
+                let id = clientes.id(&tx, nome)?;
+                if !n.cliente_documento.is_empty() {
+                    tx.execute(
+                        "UPDATE clientes SET documento = ?1 WHERE id = ?2",
+                        params![n.cliente_documento, id],
+                    )?;
+                }
+                Some(id)
+            }
+        };
+        tx.execute(
+            "INSERT INTO notas VALUES (?1, ?2, ?3, ?4)",
+            params![n.numero, n.timestamp, cliente, n.aliquota],
+        )?;
+        for item in &n.itens {
+            tx.execute(
+                "INSERT INTO itens_nota VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    n.numero,
+                    item.codigo,
+                    item.descricao,
+                    item.quantidade,
+                    item.preco_unitario
+                ],
+            )?;
+        }
+    }
+    for m in &registros.creditos {
+        let id = clientes.id(&tx, &m.cliente)?;
+        tx.execute(
+            "INSERT INTO creditos (cliente_id, valor, motivo, timestamp) VALUES (?1, ?2, ?3, ?4)",
+            params![id, m.valor, m.motivo, m.timestamp],
+        )?;
+    }
+    for e in &registros.lista_espera {
+        let id = clientes.id(&tx, &e.cliente)?;
+        tx.execute(
+            "INSERT INTO lista_espera (cliente_id, produto, contato, timestamp)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![id, e.codigo, e.contato, e.timestamp],
+        )?;
+    }
+    tx.commit()?;
+    Ok(historico.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::credito::MovimentoCredito;
+    use crate::dominio::Quantidade;
+
+    #[test]
+    fn exporta_tabelas_ligadas_por_id() {
+        let mut relogios = HashMap::new();
+        relogios.insert("R001".to_string(), {
+            let mut r = Relogio::novo("R001", Quantidade::nova(3).unwrap());
+            r.preco = Some(100.0);
+            r
+        });
+        let historico = vec![
+            Historico {
+                codigo: "R001".into(),
+                quantidade: 5,
+                operacao: "COMPRA".into(),
+                timestamp: "2024-03-01 10:00:00".into(),
+                fornecedor: Some("Casio".into()),
+                ..Default::default()
+            },
+            Historico {
+                codigo: "R001".into(),
+                quantidade: 2,
+                operacao: "VENDA".into(),
+                timestamp: "2024-03-02 15:30:00".into(),
+                tags: vec!["black friday".into()],
+                ..Default::default()
+            },
+        ];
+        let mut registros = Registros::default();
+        for valor in [50.0, -20.0] {
+            registros.creditos.push(MovimentoCredito {
+                cliente: if valor > 0.0 {
+                    "Ana  Souza"
+                } else {
+                    "ana souza"
+                }
+                .into(),
+                valor,
+                motivo: "devolução".into(),
+                timestamp: "2024-03-02 16:00:00".into(),
+            });
+        }
+        let caminho =
+            std::env::temp_dir().join(format!("relogio_teste_{}.sqlite", std::process::id()));
+        let caminho = caminho.to_str().unwrap();
+        assert_eq!(exporta(caminho, &relogios, &historico, &registros), Ok(2));
+
+        let conn = Connection::open(caminho).unwrap();
+        let consulta =
+            |sql: &str| -> String { conn.query_row(sql, [], |r| r.get::<_, String>(0)).unwrap() };
+        assert_eq!(
+            consulta(
+                "SELECT f.nome FROM historico h JOIN fornecedores f ON f.id = h.fornecedor_id"
+            ),
+            "Casio"
+        );
+        assert_eq!(
+            consulta(
+                "SELECT data || ' ' || tag FROM historico JOIN historico_tags ON historico_id = id"
+            ),
+            "2024-03-02 black friday"
+        );
+        // Mesmo cliente escrito de dois jeitos: uma linha só
+        assert_eq!(
+            consulta("SELECT count(DISTINCT cliente_id) || ' ' || sum(valor) FROM creditos"),
+            "1 30.0"
+        );
+        drop(conn);
+        let _ = fs::remove_file(caminho);
+    }
+}
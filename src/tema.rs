@@ -0,0 +1,177 @@
+use crate::config::{Config, Paleta, Tema};
+use ratatui::style::Color;
+use std::str::FromStr;
+
+/// Cores de cada papel nas telas. Vêm da `paleta` escolhida, com as
+/// entradas/saídas do `tema` e as trocas da tabela `[cores]` por cima.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Cores {
+    /// Fundo e texto comum; `Reset` deixa os do terminal.
+    pub fundo: Color,
+    pub texto: Color,
+    /// Linha selecionada.
+    pub selecao_fundo: Color,
+    pub selecao_texto: Color,
+    /// Logo, avisos, bordas de popups e o item escolhido.
+    pub destaque: Color,
+    /// Abas ativas, foco e a série principal dos gráficos.
+    pub realce: Color,
+    /// Produtos em promoção e o que está marcado.
+    pub marcado: Color,
+    /// Exclusões e saldos zerados.
+    pub alerta: Color,
+    /// Lidos, séries de comparação e o fundo dos medidores.
+    pub apagado: Color,
+    /// Compras e vendas (barras, histórico, medidores).
+    pub entrada: Color,
+    pub saida: Color,
+}
+
+/// Papéis aceitos na tabela `[cores]` do `config.toml`.
+pub const PAPEIS: &[&str] = &[
+    "fundo",
+    "texto",
+    "selecao_fundo",
+    "selecao_texto",
+    "destaque",
+    "realce",
+    "marcado",
+    "alerta",
+    "apagado",
+    "entrada",
+    "saida",
+];
+
+impl Cores {
+    fn da_paleta(paleta: Paleta) -> Cores {
+        match paleta {
+            Paleta::Escuro => Cores {
+                fundo: Color::Reset,
+                texto: Color::White,
+                selecao_fundo: Color::White,
+                selecao_texto: Color::Black,
+                destaque: Color::Yellow,
+                realce: Color::Cyan,
+                marcado: Color::Magenta,
+                alerta: Color::Red,
+                apagado: Color::DarkGray,
+                entrada: Color::Green,
+                saida: Color::Red,
+            },
+            // Pinta o próprio fundo, para não depender do terminal
+            Paleta::Claro => Cores {
+                fundo: Color::White,
+                texto: Color::Black,
+                selecao_fundo: Color::Blue,
+                selecao_texto: Color::White,
+                destaque: Color::Blue,
+                realce: Color::Rgb(0, 95, 135),
+                marcado: Color::Magenta,
+                alerta: Color::Red,
+                apagado: Color::Gray,
+                entrada: Color::Rgb(0, 128, 0),
+                saida: Color::Red,
+            },
+            Paleta::AltoContraste => Cores {
+                fundo: Color::Black,
+                texto: Color::White,
+                selecao_fundo: Color::LightYellow,
+                selecao_texto: Color::Black,
+                destaque: Color::LightYellow,
+                realce: Color::LightCyan,
+                marcado: Color::LightMagenta,
+                alerta: Color::LightRed,
+                apagado: Color::Gray,
+                entrada: Color::LightGreen,
+                saida: Color::LightRed,
+            },
+        }
+    }
+
+    fn papel(&mut self, nome: &str) -> Option<&mut Color> {
+        Some(match nome {
+            "fundo" => &mut self.fundo,
+            "texto" => &mut self.texto,
+            "selecao_fundo" => &mut self.selecao_fundo,
+            "selecao_texto" => &mut self.selecao_texto,
+            "destaque" => &mut self.destaque,
+            "realce" => &mut self.realce,
+            "marcado" => &mut self.marcado,
+            "alerta" => &mut self.alerta,
+            "apagado" => &mut self.apagado,
+            "entrada" => &mut self.entrada,
+            "saida" => &mut self.saida,
+            _ => return None,
+        })
+    }
+
+    /// Cores da configuração e os avisos das trocas de `[cores]` ignoradas
+    /// (papel ou cor desconhecidos).
+    pub fn da_config(config: &Config) -> (Cores, Vec<String>) {
+        let mut cores = Cores::da_paleta(config.paleta);
+        match config.tema {
+            Tema::Padrao => {}
+            // Paleta de Okabe-Ito
+            Tema::Daltonico => {
+                cores.entrada = Color::Rgb(0, 114, 178);
+                cores.saida = Color::Rgb(230, 159, 0);
+            }
+            Tema::Monocromatico => {
+                cores.entrada = cores.texto;
+                cores.saida = cores.apagado;
+            }
+        }
+        let mut avisos = vec![];
+        for (papel, valor) in &config.cores {
+            let Some(destino) = cores.papel(papel) else {
+                avisos.push(format!(
+                    "[cores]: papel desconhecido '{}' (use {}).",
+                    papel,
+                    PAPEIS.join(", ")
+                ));
+                continue;
+            };
+            match Color::from_str(valor.trim()) {
+                Ok(cor) => *destino = cor,
+                Err(_) => avisos.push(format!(
+                    "[cores]: cor inválida '{}' para {} (nome como \"lightblue\", \"#rrggbb\" ou 0–255).",
+                    valor, papel
+                )),
+            }
+        }
+        (cores, avisos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn paleta_tema_e_trocas_da_config() {
+        let mut config = Config {
+            paleta: Paleta::Claro,
+            tema: Tema::Daltonico,
+            ..Default::default()
+        };
+        config.cores.insert("destaque".into(), "#ff8800".into());
+        config
+            .cores
+            .insert("selecao_texto".into(), "lightcyan".into());
+        config.cores.insert("borda".into(), "red".into());
+        config.cores.insert("alerta".into(), "vermelho".into());
+        let (cores, avisos) = Cores::da_config(&config);
+        assert_eq!(cores.fundo, Color::White);
+        assert_eq!(cores.entrada, Color::Rgb(0, 114, 178));
+        assert_eq!(cores.destaque, Color::Rgb(255, 136, 0));
+        assert_eq!(cores.selecao_texto, Color::LightCyan);
+        assert_eq!(cores.alerta, Color::Red);
+        assert_eq!(avisos.len(), 2);
+        assert!(avisos[0].contains("cor inválida 'vermelho'"));
+        assert!(avisos[1].contains("papel desconhecido 'borda'"));
+        assert_eq!(
+            Cores::da_config(&Config::default()).0,
+            Cores::da_paleta(Paleta::Escuro)
+        );
+    }
+}
@@ -0,0 +1,86 @@
+use crate::persistencia::na_pasta_dados;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+
+const CAMINHO_TOKENS: &str = "tokens.json";
+
+/// O que um token pode fazer na API: só consultar ou também registrar vendas.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Escopo {
+    Leitura,
+    Escrita,
+}
+
+impl Escopo {
+    pub fn parse(s: &str) -> Option<Escopo> {
+        match s.to_lowercase().as_str() {
+            "leitura" => Some(Escopo::Leitura),
+            "escrita" => Some(Escopo::Escrita),
+            _ => None,
+        }
+    }
+
+    pub fn nome(self) -> &'static str {
+        match self {
+            Escopo::Leitura => "leitura",
+            Escopo::Escrita => "escrita",
+        }
+    }
+
+    /// Escrita inclui leitura.
+    pub fn permite(self, exigido: Escopo) -> bool {
+        self == Escopo::Escrita || exigido == Escopo::Leitura
+    }
+}
+
+/// Token de acesso à API. Só o hash fica gravado; o valor aparece uma vez,
+/// na criação.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Token {
+    pub nome: String,
+    pub hash: String,
+    pub escopo: Escopo,
+    pub criado: String,
+}
+
+pub fn carrega() -> Vec<Token> {
+    fs::read_to_string(na_pasta_dados(CAMINHO_TOKENS))
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+pub fn salva(tokens: &[Token]) -> Result<(), String> {
+    let j = serde_json::to_string_pretty(tokens).map_err(|e| e.to_string())?;
+    fs::write(na_pasta_dados(CAMINHO_TOKENS), j).map_err(|e| e.to_string())
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn hash(valor: &str) -> String {
+    hex(&Sha256::digest(valor.as_bytes()))
+}
+
+/// Cria um token com nome único e devolve o valor a entregar ao cliente.
+pub fn cria(tokens: &mut Vec<Token>, nome: &str, escopo: Escopo) -> Result<String, String> {
+    if tokens.iter().any(|t| t.nome == nome) {
+        return Err(format!("Já existe um token chamado {}.", nome));
+    }
+    let valor = hex(&rand::random::<[u8; 20]>());
+    tokens.push(Token {
+        nome: nome.to_string(),
+        hash: hash(&valor),
+        escopo,
+        criado: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+    });
+    Ok(valor)
+}
+
+pub fn autentica<'a>(tokens: &'a [Token], valor: &str) -> Option<&'a Token> {
+    let h = hash(valor);
+    tokens.iter().find(|t| t.hash == h)
+}
@@ -0,0 +1,9748 @@
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime};
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::text::{Line, Span};
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{
+        Block, Borders, Cell, Clear, Gauge, List, ListItem, Paragraph, Row, Table, Tabs, Wrap,
+    },
+    Frame, Terminal,
+};
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use crate::dominio::{
+    codigo_por_ean, quantidade_disponivel, Canal, Componente, Historico, Inventario, PedidoVenda,
+    ProdutoId, Quantidade, Relogio, Ressalva, Situacao,
+};
+use crate::persistencia::{
+    self, load_from_file, save_to_file, EdicaoExterna, Espera, Recado, Registros, TentativaLimite,
+};
+use crate::rolagem::ScrollState;
+use crate::{
+    agenda, agendadas, ajuda, analise, arquivo, auditoria, balanco, cambio, catalogo, clientes,
+    colunas, config, consulta, contabil, credito, dominio, etiquetas, expurgo, fidelidade, figura,
+    fornecedores, gaveta, i18n, kardex, letreiro, mercado, notas, notificacoes, numeros,
+    orcamentos, pedidos, planilha, promocoes, recebimento, recentes, relatorios, reposicao,
+    retencao, tema, tokens,
+};
+
+/// Formas de pagamento aceitas na Venda; a primeira é o padrão.
+const FORMAS_PAGAMENTO: &[&str] = &["dinheiro", "cartao", "pix", "credito"];
+
+/// Tempo sem digitação antes de recalcular a busca.
+const BUSCA_DEBOUNCE: Duration = Duration::from_millis(120);
+/// Quantidade máxima de resultados mantidos/renderizados na busca.
+const MAX_RESULTADOS_BUSCA: usize = 200;
+/// Menor terminal (colunas, linhas) em que o layout cabe sem se sobrepor.
+const TAMANHO_MINIMO: (u16, u16) = (60, 24);
+/// Motivo gravado nos AJUSTEs feitos com +/- no Estoque.
+const MOTIVO_AJUSTE_RAPIDO: &str = "correção rápida no Estoque";
+/// Motivo dos AJUSTEs com `=` quando o operador não informa outro.
+const MOTIVO_CONTAGEM: &str = "contagem";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Modo {
+    Cadastro,
+    Buscar,
+    Historico,
+    Estoques,
+    Grafico,
+    Compra,
+    Venda,
+    ImportarCatalogo,
+    MapearCsv,
+    Configuracoes,
+    EmitirNota,
+    ListaEspera,
+    ResgateCredito,
+    Admin,
+    EstoqueEm,
+    Quiosque,
+    Recado,
+    Comparacao,
+    Recentes,
+    Contagem,
+    Fornecedores,
+    Clientes,
+    Console,
+    Notificacoes,
+    Recebimento,
+    Pedidos,
+    Orcamentos,
+    Balanco,
+    Renomear,
+    EdicaoExterna,
+}
+
+enum HistoricoTab {
+    Todos,
+    Compras,
+    Vendas,
+    Aquisicoes,
+}
+
+impl HistoricoTab {
+    fn titles() -> &'static [&'static str] {
+        &["Todos", "Compras", "Vendas", "Aquisições"]
+    }
+    fn next(&self) -> HistoricoTab {
+        match self {
+            HistoricoTab::Todos => HistoricoTab::Compras,
+            HistoricoTab::Compras => HistoricoTab::Vendas,
+            HistoricoTab::Vendas => HistoricoTab::Aquisicoes,
+            HistoricoTab::Aquisicoes => HistoricoTab::Todos,
+        }
+    }
+    fn prev(&self) -> HistoricoTab {
+        match self {
+            HistoricoTab::Todos => HistoricoTab::Aquisicoes,
+            HistoricoTab::Compras => HistoricoTab::Todos,
+            HistoricoTab::Vendas => HistoricoTab::Compras,
+            HistoricoTab::Aquisicoes => HistoricoTab::Vendas,
+        }
+    }
+}
+
+/// O que está sendo digitado no histórico.
+#[derive(Clone, Copy, PartialEq)]
+enum EntradaHistorico {
+    /// Código a filtrar, com sugestões (P).
+    Pesquisa,
+    /// Etiqueta a aplicar nas entradas marcadas (T).
+    Etiqueta,
+    /// Etiqueta pela qual filtrar a lista (F).
+    FiltroEtiqueta,
+}
+
+/// O que está sendo digitado na fila de orçamentos.
+#[derive(Clone, Copy, PartialEq)]
+enum EntradaOrcamento {
+    /// Orçamento novo (N).
+    Novo,
+    /// Motivo da perda do selecionado (P).
+    Perda,
+    /// Nova data de retorno do selecionado (A).
+    Retorno,
+}
+
+/// Visões do modo Gráfico, alternadas com ←/→.
+#[derive(Clone, Copy, PartialEq)]
+enum GraficoTab {
+    Diario,
+    MesAMes,
+    ValorEstoque,
+    Categorias,
+    Marcas,
+    Produtos,
+    Padrao,
+}
+
+impl GraficoTab {
+    fn titles() -> &'static [&'static str] {
+        &[
+            "Diário",
+            "Meses",
+            "Valor",
+            "Categorias",
+            "Marcas",
+            "Produtos",
+            "Dia/hora",
+        ]
+    }
+    fn next(&self) -> GraficoTab {
+        match self {
+            GraficoTab::Diario => GraficoTab::MesAMes,
+            GraficoTab::MesAMes => GraficoTab::ValorEstoque,
+            GraficoTab::ValorEstoque => GraficoTab::Categorias,
+            GraficoTab::Categorias => GraficoTab::Marcas,
+            GraficoTab::Marcas => GraficoTab::Produtos,
+            GraficoTab::Produtos => GraficoTab::Padrao,
+            GraficoTab::Padrao => GraficoTab::Diario,
+        }
+    }
+    fn prev(&self) -> GraficoTab {
+        match self {
+            GraficoTab::Diario => GraficoTab::Padrao,
+            GraficoTab::MesAMes => GraficoTab::Diario,
+            GraficoTab::ValorEstoque => GraficoTab::MesAMes,
+            GraficoTab::Categorias => GraficoTab::ValorEstoque,
+            GraficoTab::Marcas => GraficoTab::Categorias,
+            GraficoTab::Produtos => GraficoTab::Marcas,
+            GraficoTab::Padrao => GraficoTab::Produtos,
+        }
+    }
+    /// O que a quebra de vendas agrupa nas abas de categoria, marca e produto.
+    fn grupo(&self) -> &'static str {
+        match self {
+            GraficoTab::Marcas => "marca",
+            GraficoTab::Produtos => "produto",
+            _ => "categoria",
+        }
+    }
+    fn indice(&self) -> usize {
+        match self {
+            GraficoTab::Diario => 0,
+            GraficoTab::MesAMes => 1,
+            GraficoTab::ValorEstoque => 2,
+            GraficoTab::Categorias => 3,
+            GraficoTab::Marcas => 4,
+            GraficoTab::Produtos => 5,
+            GraficoTab::Padrao => 6,
+        }
+    }
+}
+
+/// Períodos da quebra por categoria/marca e do padrão por dia/hora (rótulo, dias; `None` = tudo).
+/// Favoritos cabem nas teclas de 1 a 9.
+const MAXIMO_FAVORITOS: usize = 9;
+
+/// Visões alternadas pela demonstração com o balcão parado.
+const PAGINAS_DEMONSTRACAO: [GraficoTab; 3] =
+    [GraficoTab::Diario, GraficoTab::Produtos, GraficoTab::Padrao];
+
+/// Demonstração em curso e a tela a que ela volta na primeira tecla.
+struct Demonstracao {
+    pagina: usize,
+    troca: Instant,
+    modo: Modo,
+    tab: GraficoTab,
+}
+
+/// Códigos parecidos com um que não existe, oferecidos na Compra e na Venda;
+/// Tab troca o código digitado pelo próximo da lista.
+struct Sugestao {
+    /// Entrada em que foi oferecida: na Compra, Enter de novo sem mudar nada
+    /// cadastra o código novo.
+    input: String,
+    candidatos: Vec<String>,
+    proximo: usize,
+}
+
+const DIAS_SEMANA: [&str; 7] = ["Seg", "Ter", "Qua", "Qui", "Sex", "Sáb", "Dom"];
+const MESES: [&str; 12] = [
+    "Jan", "Fev", "Mar", "Abr", "Mai", "Jun", "Jul", "Ago", "Set", "Out", "Nov", "Dez",
+];
+
+/// Só o intervalo de horas em que houve venda, para caber na tela.
+fn horas_com_venda(horas: &[u64; 24]) -> std::ops::RangeInclusive<usize> {
+    let primeira = horas.iter().position(|v| *v > 0).unwrap_or(9);
+    let ultima = horas
+        .iter()
+        .rposition(|v| *v > 0)
+        .unwrap_or(18)
+        .max(primeira);
+    primeira..=ultima
+}
+
+const PERIODOS_GRAFICO: [(&str, Option<i64>); 5] = [
+    ("7 dias", Some(7)),
+    ("30 dias", Some(30)),
+    ("90 dias", Some(90)),
+    ("12 meses", Some(365)),
+    ("todo o histórico", None),
+];
+
+/// Estado da interface: os dados carregados, o modo atual e o que cada tela
+/// mostra. As ações confirmadas gravam o `estoque.json` da pasta atual.
+pub struct App {
+    config: config::Config,
+    relogios: HashMap<String, Relogio>,
+    pub(crate) historico: Vec<Historico>,
+    registros: Registros,
+    modo: Modo,
+    input: String,
+    pub(crate) mensagens: Vec<String>,
+    historico_filtrado: Option<Vec<Historico>>,
+    editing: bool,
+    estoques_list: Vec<Relogio>,
+    estoques_scroll: ScrollState,
+    // Visão somente leitura do estoque reconstruído numa data passada
+    estoque_em: Option<(NaiveDate, HashMap<String, Relogio>)>,
+    historico_scroll: ScrollState,
+    historico_tab: HistoricoTab,
+    grafico_tab: GraficoTab,
+    // Valor do estoque por período (rótulo, valor), recalculado ao abrir a visão
+    grafico_mensal: bool,
+    // Na visão por mês, ↑/↓ troca os 12 meses pelo acumulado do mês corrente
+    grafico_acumulado: bool,
+    grafico_valor: Vec<(String, f64)>,
+    // Índice em PERIODOS_GRAFICO da quebra por categoria/marca
+    grafico_periodo: usize,
+
+    cadastro_list: Vec<Relogio>,
+    cadastro_scroll: ScrollState,
+
+    buscar_results: Vec<(String, i32, usize)>,
+    buscar_scroll: ScrollState,
+    buscar_total: usize,
+    // Estado da busca incremental: última consulta calculada, códigos que
+    // ainda casam com ela e instante da última tecla ainda não processada.
+    busca_ultima_query: String,
+    busca_candidatos: Vec<String>,
+    busca_pendente: Option<Instant>,
+
+    chosen_relogio: Option<String>,
+    chosen_operation: Option<char>,
+
+    // Novos campos para pesquisa no histórico
+    historico_codigos_unicos: Vec<String>,
+    historico_search_results: Vec<(String, usize)>,
+    historico_search_selected: usize, // índice na lista de sugestões
+
+    // Ano arquivado carregado sob demanda no histórico
+    historico_ano_arquivo: Option<i32>,
+    // Entrada aberta no popup de detalhes do histórico
+    historico_detalhe: Option<Historico>,
+    historico_entrada: EntradaHistorico,
+    // Posições em `historico` marcadas com Espaço para etiquetar em lote
+    historico_marcados: Vec<usize>,
+    historico_etiqueta: Option<String>,
+
+    // Revisão da importação de catálogo do distribuidor
+    importacao: Vec<catalogo::Alteracao>,
+    importacao_scroll: ScrollState,
+    // Etapa de mapeamento de colunas do CSV, antes da revisão
+    importacao_csv: Option<catalogo::ArquivoCsv>,
+    mapeamento_campo: usize,
+
+    config_selected: usize,
+    // Trocar uma senha de administrador já definida pede a atual antes
+    confere_senha_atual: bool,
+
+    // Tela Admin: tokens da API e últimos acessos registrados pelo servidor
+    admin_tokens: Vec<tokens::Token>,
+    admin_acessos: Vec<auditoria::Acesso>,
+    admin_scroll: ScrollState,
+
+    // Envio para o Google Sheets em segundo plano
+    planilha_rx: Option<mpsc::Receiver<Result<(), String>>>,
+    planilha_ultimo_envio: Instant,
+    // Códigos vistos ou operados por último (gravados ao sair)
+    recentes: Vec<String>,
+    recentes_scroll: ScrollState,
+    fornecedores_scroll: ScrollState,
+    // Tab no modo Fornecedores troca os códigos pelo cadastro de fornecedores
+    fornecedores_cadastro: bool,
+    clientes_scroll: ScrollState,
+    // Cliente escolhido no modo Clientes; as vendas seguintes ficam no nome dele
+    cliente_atendido: Option<String>,
+    /// Resultado da última consulta do console (:).
+    console_resultado: Option<consulta::Tabela>,
+    console_scroll: ScrollState,
+    notificacoes_scroll: ScrollState,
+    /// Entrega em conferência no assistente de recebimento (J).
+    recebimento: Option<recebimento::Recebimento>,
+    recebimento_scroll: ScrollState,
+    pedidos_scroll: ScrollState,
+    // Tab no modo Pedidos troca as pendências pela sugestão de reposição
+    pedidos_sugestao: bool,
+    // Produtos arquivados aparecem nas listas e na busca (~)
+    mostrar_arquivados: bool,
+    // Estoque e histórico do item lado a lado (|), com o foco trocado por Tab
+    painel_dividido: bool,
+    foco_historico: bool,
+    painel_historico_scroll: ScrollState,
+    orcamentos_scroll: ScrollState,
+    orcamento_entrada: EntradaOrcamento,
+    // Códigos marcados com Espaço no Estoque para comparar lado a lado
+    comparar: Vec<String>,
+    // Recados não lidos exibidos na abertura até o operador confirmar
+    recados_abertos: Vec<Recado>,
+    // Tela atual do quiosque e quando ela apareceu
+    quiosque_pagina: usize,
+    quiosque_troca: Instant,
+    // Última tecla, para a demonstração começar com o balcão parado
+    ultima_tecla: Instant,
+    demonstracao: Option<Demonstracao>,
+
+    // Dia da última geração de relatórios na pasta configurada
+    relatorios_dia: Option<chrono::NaiveDate>,
+
+    // Consulta de preço de mercado em segundo plano
+    mercado_rx: Option<mpsc::Receiver<(String, ResultadoMercado)>>,
+
+    // Cotações por moeda (cache diário) e atualização em segundo plano
+    cambio: HashMap<String, cambio::Cotacao>,
+    cambio_rx: Option<mpsc::Receiver<CotacoesRecebidas>>,
+
+    // Modelo sem estoque cuja lista de espera está sendo preenchida
+    espera_codigo: Option<String>,
+    // "Você quis dizer" da Compra/Venda com código inexistente
+    sugestao: Option<Sugestao>,
+    // Código cuja contagem (=) está sendo digitada
+    contagem_codigo: Option<String>,
+    // Código cuja exclusão (D) aguarda confirmação no popup
+    exclusao: Option<String>,
+    // Código sendo renomeado (F2)
+    renomear_codigo: Option<String>,
+    // Primeira linha visível da ajuda (?), quando aberta
+    ajuda: Option<u16>,
+    /// Arquivo do app de contagem em conferência (`=` com um .csv).
+    balanco: Option<balanco::Balanco>,
+    balanco_scroll: ScrollState,
+    // Venda paga com crédito aguardando o nome do cliente: código, qtd, liberada
+    venda_credito: Option<(String, i32, bool)>,
+    /// Cores das telas, montadas da configuração.
+    cores: tema::Cores,
+    /// `estoque.json` alterado fora do programa, aguardando aceite.
+    edicao_externa: Option<EdicaoExterna>,
+    edicao_externa_scroll: ScrollState,
+}
+
+type ResultadoMercado = Result<Option<mercado::PrecosMercado>, String>;
+type CotacoesRecebidas = Vec<(String, Result<f64, String>)>;
+
+impl App {
+    /// Carrega os dados da pasta atual e faz o que a abertura do programa faz
+    /// (agendadas, pedidos recorrentes, notificações). Se o `estoque.json`
+    /// foi alterado por fora, abre antes o resumo das alterações.
+    pub fn carrega() -> Self {
+        let edicao = persistencia::edicao_externa();
+        let (relogios, hist, registros) = load_from_file();
+        let mut app = Self::com_dados(
+            relogios,
+            hist,
+            registros,
+            config::Config::carregar(),
+            cambio::carrega_cache(),
+        );
+        app.atualizar_cambio();
+        app.recentes = recentes::carrega();
+        match edicao {
+            Some(edicao) => {
+                app.edicao_externa = Some(edicao);
+                app.modo = Modo::EdicaoExterna;
+                app.editing = true;
+            }
+            None => app.abertura(),
+        }
+        app
+    }
+
+    /// O que gravar na abertura; espera o aceite de uma edição externa.
+    fn abertura(&mut self) {
+        self.aplica_retencao(Local::now().date_naive());
+        self.processa_agendadas(Local::now().naive_local());
+        self.gera_rascunhos_recorrentes(Local::now().date_naive());
+        self.notificacoes_de_abertura(Local::now().date_naive());
+        self.recados_abertos = self
+            .registros
+            .recados
+            .iter()
+            .filter(|r| !r.lido)
+            .cloned()
+            .collect();
+    }
+
+    /// App sobre um estoque em memória, com a configuração padrão.
+    pub fn com_estoque(relogios: HashMap<String, Relogio>, historico: Vec<Historico>) -> Self {
+        Self::com_dados(
+            relogios,
+            historico,
+            Registros::default(),
+            config::Config::default(),
+            HashMap::new(),
+        )
+    }
+
+    pub fn relogios(&self) -> &HashMap<String, Relogio> {
+        &self.relogios
+    }
+
+    pub fn historico(&self) -> &[Historico] {
+        &self.historico
+    }
+
+    /// Mensagens mostradas no rodapé, da mais antiga à mais nova.
+    pub fn mensagens(&self) -> &[String] {
+        &self.mensagens
+    }
+
+    /// Monta o app a partir de dados já carregados, sem tocar em arquivos.
+    pub(crate) fn com_dados(
+        relogios: HashMap<String, Relogio>,
+        hist: Vec<Historico>,
+        registros: Registros,
+        config: config::Config,
+        cambio: HashMap<String, cambio::Cotacao>,
+    ) -> Self {
+        // Extrair códigos únicos do histórico
+        let mut cod_set: HashSet<String> = HashSet::new();
+        for h in &hist {
+            cod_set.insert(h.codigo.clone());
+        }
+        let mut historico_codigos_unicos: Vec<String> = cod_set.into_iter().collect();
+        historico_codigos_unicos.sort();
+
+        let (cores, avisos_cores) = tema::Cores::da_config(&config);
+        let boas_vindas = i18n::t(config.idioma, "Bem-vindo ao Sistema de Relógios (Estoque)!");
+        let mut app = Self {
+            config,
+            relogios,
+            historico: hist,
+            registros,
+            modo: Modo::Estoques,
+            input: String::new(),
+            mensagens: vec![boas_vindas.into()],
+            historico_filtrado: None,
+            editing: false,
+            estoques_list: vec![],
+            estoques_scroll: ScrollState::default(),
+            estoque_em: None,
+            historico_scroll: ScrollState::default(),
+            historico_tab: HistoricoTab::Todos,
+            grafico_tab: GraficoTab::Diario,
+            grafico_mensal: true,
+            grafico_acumulado: false,
+            grafico_valor: vec![],
+            grafico_periodo: 1,
+            cadastro_list: vec![],
+            cadastro_scroll: ScrollState::default(),
+            buscar_results: vec![],
+            buscar_scroll: ScrollState::default(),
+            buscar_total: 0,
+            busca_ultima_query: String::new(),
+            busca_candidatos: vec![],
+            busca_pendente: None,
+            chosen_relogio: None,
+            chosen_operation: None,
+            historico_codigos_unicos,
+            historico_search_results: vec![],
+            historico_search_selected: 0,
+            historico_ano_arquivo: None,
+            historico_detalhe: None,
+            historico_entrada: EntradaHistorico::Pesquisa,
+            historico_marcados: vec![],
+            historico_etiqueta: None,
+            importacao: vec![],
+            importacao_scroll: ScrollState::default(),
+            importacao_csv: None,
+            mapeamento_campo: 0,
+            config_selected: 0,
+            confere_senha_atual: false,
+            admin_tokens: vec![],
+            admin_acessos: vec![],
+            admin_scroll: ScrollState::default(),
+            planilha_rx: None,
+            planilha_ultimo_envio: Instant::now(),
+            recentes: vec![],
+            recentes_scroll: ScrollState::default(),
+            fornecedores_scroll: ScrollState::default(),
+            fornecedores_cadastro: false,
+            clientes_scroll: ScrollState::default(),
+            cliente_atendido: None,
+            console_resultado: None,
+            console_scroll: ScrollState::default(),
+            notificacoes_scroll: ScrollState::default(),
+            recebimento: None,
+            recebimento_scroll: ScrollState::default(),
+            pedidos_scroll: ScrollState::default(),
+            pedidos_sugestao: false,
+            mostrar_arquivados: false,
+            painel_dividido: false,
+            foco_historico: false,
+            painel_historico_scroll: ScrollState::default(),
+            orcamentos_scroll: ScrollState::default(),
+            orcamento_entrada: EntradaOrcamento::Novo,
+            comparar: vec![],
+            recados_abertos: vec![],
+            quiosque_pagina: 0,
+            quiosque_troca: Instant::now(),
+            ultima_tecla: Instant::now(),
+            demonstracao: None,
+            relatorios_dia: None,
+            mercado_rx: None,
+            cambio,
+            cambio_rx: None,
+            espera_codigo: None,
+            sugestao: None,
+            contagem_codigo: None,
+            exclusao: None,
+            renomear_codigo: None,
+            ajuda: None,
+            balanco: None,
+            balanco_scroll: ScrollState::default(),
+            venda_credito: None,
+            cores,
+            edicao_externa: None,
+            edicao_externa_scroll: ScrollState::default(),
+        };
+        app.mensagens.extend(avisos_cores);
+        app.atualiza_estoques_list();
+        app.atualiza_cadastro_list();
+        app
+    }
+
+    fn salvar(&self) {
+        save_to_file(&self.relogios, &self.historico, &self.registros);
+    }
+
+    /// Arquivados só entram nas listas e na busca com `~` ligado.
+    fn listado(&self, r: &Relogio) -> bool {
+        self.mostrar_arquivados || r.situacao != Situacao::Arquivado
+    }
+
+    fn atualiza_estoques_list(&mut self) {
+        let mut lista: Vec<Relogio> = self
+            .relogios
+            .values()
+            .filter(|r| self.listado(r))
+            .cloned()
+            .collect();
+        lista.sort_by(|a, b| a.codigo.cmp(&b.codigo));
+        self.estoques_list = lista;
+        self.estoques_scroll.limita(self.estoques_list.len());
+    }
+
+    fn atualiza_cadastro_list(&mut self) {
+        let mut lista: Vec<Relogio> = self
+            .relogios
+            .values()
+            .filter(|r| self.listado(r))
+            .cloned()
+            .collect();
+        lista.sort_by(|a, b| a.codigo.cmp(&b.codigo));
+        self.cadastro_list = lista;
+        self.cadastro_scroll.limita(self.cadastro_list.len());
+    }
+
+    /// Após redimensionar, traz seleções e deslocamentos de volta às listas;
+    /// a página de cada uma é refeita no próximo desenho.
+    fn ajusta_rolagem(&mut self) {
+        self.estoques_scroll.limita(self.estoques_list.len());
+        self.cadastro_scroll.limita(self.cadastro_list.len());
+        self.buscar_scroll.limita(self.buscar_results.len());
+        self.importacao_scroll.limita(self.importacao.len());
+        self.admin_scroll.limita(self.admin_acessos.len());
+        let total = self.get_historico_atual_vec().len();
+        self.historico_scroll.limita(total);
+    }
+
+    fn agenda_busca(&mut self) {
+        self.busca_pendente = Some(Instant::now());
+    }
+
+    /// Tempo restante até a busca agendada poder rodar, se houver uma.
+    fn busca_restante(&self) -> Option<Duration> {
+        self.busca_pendente
+            .map(|t| BUSCA_DEBOUNCE.saturating_sub(t.elapsed()))
+    }
+
+    fn processa_busca_pendente(&mut self) {
+        if self.busca_restante() == Some(Duration::ZERO) {
+            self.busca_pendente = None;
+            self.atualizar_busca_results();
+        }
+    }
+
+    fn atualizar_busca_results(&mut self) {
+        let query = self.input.clone();
+        // Código de barras lido: vai direto ao produto
+        if let Some(r) = codigo_por_ean(&self.relogios, query.trim()).map(|c| &self.relogios[c]) {
+            self.buscar_results = vec![(
+                r.codigo.clone(),
+                quantidade_disponivel(&self.relogios, r),
+                0,
+            )];
+            self.buscar_total = 1;
+            self.busca_candidatos.clear();
+            self.busca_ultima_query.clear();
+            self.buscar_scroll.limita(1);
+            return;
+        }
+        // Se a consulta só cresceu, os candidatos só podem diminuir: filtra a
+        // lista anterior em vez de varrer o catálogo inteiro.
+        let estreitar = !self.busca_ultima_query.is_empty()
+            && query.starts_with(&self.busca_ultima_query)
+            && !self.busca_candidatos.is_empty();
+        let candidatos: Vec<String> = if estreitar {
+            self.busca_candidatos
+                .iter()
+                .filter(|c| contem_subsequencia(c, &query))
+                .cloned()
+                .collect()
+        } else {
+            self.relogios
+                .keys()
+                .filter(|c| contem_subsequencia(c, &query))
+                .cloned()
+                .collect()
+        };
+        self.busca_candidatos = candidatos;
+        self.busca_ultima_query = query.clone();
+
+        // Sem nenhum candidato, cai para a distância sobre tudo (erros de digitação).
+        let mut results = if self.busca_candidatos.is_empty() {
+            self.busca_relogios(&query)
+        } else {
+            self.pontua_candidatos(self.busca_candidatos.iter(), &query)
+        };
+        results.retain(|(c, _, _)| self.relogios.get(c).is_some_and(|r| self.listado(r)));
+        self.buscar_total = results.len();
+        results.truncate(MAX_RESULTADOS_BUSCA);
+        self.buscar_results = results;
+        self.buscar_scroll.limita(self.buscar_results.len());
+    }
+
+    fn atualizar_historico_search_results(&mut self) {
+        // Calcula a distância para cada código com base em self.input
+        let query = self.input.trim();
+        let mut res: Vec<(String, usize)> = self
+            .historico_codigos_unicos
+            .iter()
+            .map(|c| {
+                let dist = levenshtein_distance(c, query);
+                (c.clone(), dist)
+            })
+            .collect();
+        res.sort_by_key(|(_, d)| *d);
+        self.historico_search_results = res;
+        if !self.historico_search_results.is_empty() {
+            if self.historico_search_selected >= self.historico_search_results.len() {
+                self.historico_search_selected = self.historico_search_results.len() - 1;
+            }
+        } else {
+            self.historico_search_selected = 0;
+        }
+    }
+
+    fn cadastrar_relogio(
+        &mut self,
+        codigo: String,
+        qtd: i32,
+        pacote: Option<i32>,
+        (custo, preco): Precos,
+    ) {
+        let resultado = ProdutoId::novo(&codigo).and_then(|c| {
+            Inventario::new(&mut self.relogios, &mut self.historico).cadastra(
+                &c,
+                Quantidade::nova(qtd)?.ate(self.config.quantidade_maxima)?,
+                Local::now().naive_local(),
+            )?;
+            Ok(c)
+        });
+        let codigo = match resultado {
+            Ok(c) => c.to_string(),
+            Err(e) => {
+                self.mensagens.push(e.to_string());
+                return;
+            }
+        };
+        if let Some(r) = self.relogios.get_mut(&codigo) {
+            r.unidades_por_pacote = pacote.or(r.unidades_por_pacote);
+            r.custo = custo.or(r.custo);
+            r.preco = preco.or(r.preco);
+        }
+        self.mensagens.push(format!(
+            "Relógio {} cadastrado com {} unidades",
+            codigo, qtd
+        ));
+        self.atualiza_estoques_list();
+        self.atualiza_cadastro_list();
+        self.salvar();
+    }
+
+    /// Troca o custo e/ou o preço de venda. As entradas já lançadas guardam o
+    /// preço da época e não mudam.
+    fn define_precos(&mut self, codigo: &str, (custo, preco): Precos) -> bool {
+        let Some(r) = self.relogios.get_mut(codigo) else {
+            self.mensagens
+                .push(format!("Relógio {} não encontrado!", codigo));
+            return false;
+        };
+        r.custo = custo.or(r.custo);
+        r.preco = preco.or(r.preco);
+        let numeros = self.config.formato_numeros;
+        let moeda = |v: Option<f64>| v.map_or("-".into(), |v| numeros.moeda(v));
+        self.mensagens.push(format!(
+            "Relógio {}: custo {} | preço {}",
+            codigo,
+            moeda(r.custo),
+            moeda(r.preco)
+        ));
+        self.atualiza_estoques_list();
+        self.atualiza_cadastro_list();
+        true
+    }
+
+    /// Enter no cadastro: `codigo quantidade [caixa]`, um kit (`codigo kit
+    /// COMP:qtd ...`) ou só `codigo custo=X preco=Y`, com os preços opcionais.
+    fn confirmar_cadastro(&mut self) {
+        let (resto, precos) = separa_precos(&self.input, self.config.formato_numeros);
+        let parts: Vec<&str> = resto.split_whitespace().collect();
+        let precos = match precos {
+            Ok(precos) => precos,
+            Err(e) => {
+                self.mensagens.push(e);
+                return;
+            }
+        };
+        if parts.len() > 2 && parts[1].eq_ignore_ascii_case("kit") {
+            match parse_componentes(&parts[2..]) {
+                Some(comps) => self.cadastrar_kit(parts[0].to_string(), comps),
+                None => self
+                    .mensagens
+                    .push("Formato do kit: codigo kit COMP:qtd COMP:qtd".into()),
+            }
+            return;
+        }
+        if parts.len() == 1 && precos != (None, None) {
+            if self.define_precos(parts[0], precos) {
+                self.salvar();
+            }
+            return;
+        }
+        let movimento =
+            le_movimento(&parts, self.config.formato_numeros, USO_CADASTRO).and_then(|m| {
+                match m.extra.map(|p| p.parse::<i32>()) {
+                    None => Ok((m, None)),
+                    Some(Ok(p)) if p > 0 => Ok((m, Some(p))),
+                    Some(_) => Err("Tamanho de caixa inválido!".to_string()),
+                }
+            });
+        match movimento {
+            Ok((m, pacote)) => {
+                self.cadastrar_relogio(m.codigo.to_string(), m.quantidade, pacote, precos)
+            }
+            Err(e) => self.mensagens.push(e),
+        }
+    }
+
+    /// Converte "2 caixas" em unidades usando o tamanho de pacote do produto.
+    fn quantidade_em_unidades(
+        &self,
+        codigo: &str,
+        qtd: i32,
+        unidade: Option<&str>,
+    ) -> Result<i32, String> {
+        match unidade.map(|u| u.to_lowercase()).as_deref() {
+            None | Some("un" | "unid" | "unidade" | "unidades") => Ok(qtd),
+            Some("cx" | "caixa" | "caixas" | "pct" | "pacote" | "pacotes") => {
+                let pacote = self
+                    .relogios
+                    .get(codigo)
+                    .and_then(|r| r.unidades_por_pacote)
+                    .ok_or_else(|| format!("{} não tem tamanho de caixa definido.", codigo))?;
+                qtd.checked_mul(pacote)
+                    .ok_or_else(|| "Quantidade grande demais!".to_string())
+            }
+            Some(u) => Err(format!("Unidade desconhecida: {}", u)),
+        }
+    }
+
+    /// Estoque disponível: o próprio saldo, ou quantos kits os componentes montam.
+    fn quantidade_disponivel(&self, r: &Relogio) -> i32 {
+        quantidade_disponivel(&self.relogios, r)
+    }
+
+    fn cadastrar_kit(&mut self, codigo: String, componentes: Vec<Componente>) {
+        if let Some(c) = componentes
+            .iter()
+            .find(|c| c.codigo == codigo || !self.relogios.contains_key(&c.codigo))
+        {
+            self.mensagens
+                .push(format!("Componente {} inválido para o kit!", c.codigo));
+            return;
+        }
+        let r = self
+            .relogios
+            .entry(codigo.clone())
+            .or_insert_with(|| Relogio::novo(codigo.clone(), Quantidade::ZERO));
+        r.define_quantidade(0);
+        r.componentes = componentes;
+        self.mensagens.push(format!(
+            "Kit {} cadastrado com {} componentes",
+            codigo,
+            r.componentes.len()
+        ));
+        self.atualiza_estoques_list();
+        self.atualiza_cadastro_list();
+        self.salvar();
+    }
+
+    /// Venda no balcão ou pela API, com as regras e as decisões de estoque
+    /// do domínio (`Inventario::registra_venda`); diz se ela saiu.
+    pub(crate) fn vender_relogio(
+        &mut self,
+        codigo: String,
+        qtd: i32,
+        pagamento: Option<String>,
+        liberado: bool,
+        canal: Canal,
+    ) -> bool {
+        let pedido = match self.pedido_de_venda(&codigo, qtd, pagamento, liberado, canal) {
+            Ok(pedido) => pedido,
+            Err(e) => {
+                self.mensagens.push(e.to_string());
+                return false;
+            }
+        };
+        let eh_kit = self
+            .relogios
+            .get(&codigo)
+            .is_some_and(|r| !r.componentes.is_empty());
+        // Saldos de quem sai do estoque: o próprio código ou os componentes
+        let saldos: Vec<(String, i32)> = self.relogios.get(&codigo).map_or(vec![], |r| {
+            if r.componentes.is_empty() {
+                vec![(r.codigo.clone(), r.quantidade())]
+            } else {
+                r.componentes
+                    .iter()
+                    .filter_map(|c| self.relogios.get(&c.codigo))
+                    .map(|p| (p.codigo.clone(), p.quantidade()))
+                    .collect()
+            }
+        });
+        let agora = Local::now().naive_local();
+        let resultado = Inventario::new(&mut self.relogios, &mut self.historico).registra_venda(
+            &pedido,
+            &self.config,
+            agora,
+        );
+        let feita = match resultado {
+            Ok(feita) => feita,
+            Err(e) => {
+                self.relata_recusa(&pedido, e);
+                return false;
+            }
+        };
+        self.relata_ressalvas(&pedido, &feita.ressalvas);
+        if feita.a_receber > 0 {
+            agendadas::agenda(
+                &mut self.registros.operacoes_agendadas,
+                agendadas::Tipo::Venda,
+                &codigo,
+                feita.a_receber,
+                pedido.pagamento.clone(),
+                agora.date(),
+                agora,
+            );
+        }
+        let aviso = pedido
+            .promocao
+            .as_ref()
+            .zip(pedido.desconto)
+            .map(|(nome, desconto)| format!(" ({} -{}%)", nome, desconto))
+            .unwrap_or_default();
+        self.mensagens.push(format!(
+            "Vendido {} unidades do {} {}{}",
+            qtd,
+            if eh_kit { "kit" } else { "relógio" },
+            codigo,
+            aviso
+        ));
+        if feita.a_receber > 0 {
+            self.mensagens.push(format!(
+                "Pré-venda: {} un. de {} saem da próxima entrega.",
+                feita.a_receber, codigo
+            ));
+        }
+        self.avisa_estoque_baixo(saldos);
+        self.atualiza_estoques_list();
+        self.atualiza_cadastro_list();
+        self.salvar();
+        if feita.abre_gaveta {
+            self.abrir_gaveta();
+        }
+        true
+    }
+
+    /// Pedido de venda com a promoção vigente, o cliente atendido, o custo do
+    /// cadastro e o que os pedidos de compra ainda trazem sem pré-venda.
+    fn pedido_de_venda(
+        &self,
+        codigo: &str,
+        qtd: i32,
+        pagamento: Option<String>,
+        liberado: bool,
+        canal: Canal,
+    ) -> Result<PedidoVenda, dominio::ErroDominio> {
+        let r = self.relogios.get(codigo);
+        let promo = r.and_then(|r| self.promocao_ativa(r));
+        let a_caminho = pedidos::em_pedido(&self.registros.pedidos_compra)
+            .get(codigo)
+            .copied()
+            .unwrap_or(0)
+            + agendadas::pendente(&self.registros.operacoes_agendadas, codigo).min(0);
+        Ok(PedidoVenda {
+            codigo: ProdutoId::novo(codigo)?,
+            quantidade: Quantidade::movimento(qtd)?,
+            pagamento,
+            canal,
+            liberado,
+            cliente: self.cliente_atendido.clone(),
+            promocao: promo.map(|p| p.nome.clone()),
+            desconto: promo.map(|p| p.desconto),
+            custo_cadastro: r.and_then(|r| self.custo_em_reais(r)),
+            a_caminho,
+        })
+    }
+
+    /// Confere limite e margem da venda agendada, que só sai na data.
+    fn confere_venda(&mut self, codigo: &str, qtd: i32, liberado: bool) -> bool {
+        let pedido = match self.pedido_de_venda(codigo, qtd, None, liberado, Canal::Balcao) {
+            Ok(pedido) => pedido,
+            Err(e) => {
+                self.mensagens.push(e.to_string());
+                return false;
+            }
+        };
+        let conferida = Inventario::new(&mut self.relogios, &mut self.historico)
+            .confere_venda(&pedido, &self.config);
+        match conferida {
+            Ok(ressalvas) => {
+                self.relata_ressalvas(&pedido, &ressalvas);
+                true
+            }
+            Err(e) => {
+                self.relata_recusa(&pedido, e);
+                false
+            }
+        }
+    }
+
+    /// Mensagem da venda recusada. A tentativa acima do limite por venda fica
+    /// registrada; a do produto esgotado, no balcão, abre a lista de espera.
+    fn relata_recusa(&mut self, pedido: &PedidoVenda, erro: dominio::ErroDominio) {
+        match erro {
+            dominio::ErroDominio::LimitePorVenda { limite, .. } => {
+                self.registra_tentativa(pedido, limite);
+                self.mensagens.push(erro.to_string());
+                self.salvar();
+            }
+            dominio::ErroDominio::AbaixoDoCusto { preco, custo, .. } => {
+                let aviso = self.aviso_de_custo(pedido, preco, custo);
+                self.mensagens.push(format!(
+                    "Venda bloqueada, {} (acrescente !senha para liberar)",
+                    aviso
+                ));
+            }
+            dominio::ErroDominio::Esgotado(codigo) if pedido.canal == Canal::Balcao => {
+                self.mensagens.push(format!(
+                    "{} sem estoque. Informe nome;contato para a lista de espera.",
+                    codigo
+                ));
+                self.espera_codigo = Some(codigo);
+            }
+            e => self.mensagens.push(e.to_string()),
+        }
+    }
+
+    /// Mensagens das regras que a venda passou com ressalva.
+    fn relata_ressalvas(&mut self, pedido: &PedidoVenda, ressalvas: &[Ressalva]) {
+        for ressalva in ressalvas {
+            let texto = match *ressalva {
+                Ressalva::LimiteLiberado { limite } => {
+                    self.registra_tentativa(pedido, limite);
+                    format!(
+                        "Liberado pelo administrador: {} un. de {} (limite {})",
+                        pedido.quantidade.valor(),
+                        pedido.codigo,
+                        limite
+                    )
+                }
+                Ressalva::AbaixoDoCusto {
+                    preco,
+                    custo,
+                    liberada,
+                } => {
+                    let aviso = self.aviso_de_custo(pedido, preco, custo);
+                    if liberada {
+                        format!("Liberado pelo administrador: {}", aviso)
+                    } else {
+                        format!("Atenção: {}", aviso)
+                    }
+                }
+            };
+            self.mensagens.push(texto);
+        }
+    }
+
+    /// Toda venda acima do limite por venda, liberada ou não, fica registrada.
+    fn registra_tentativa(&mut self, pedido: &PedidoVenda, limite: i32) {
+        self.registros.tentativas_limite.push(TentativaLimite {
+            timestamp: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            codigo: pedido.codigo.to_string(),
+            quantidade: pedido.quantidade.valor(),
+            limite,
+            liberada: pedido.liberado,
+        });
+    }
+
+    fn aviso_de_custo(&self, pedido: &PedidoVenda, preco: f64, custo: f64) -> String {
+        format!(
+            "{} abaixo do custo: {} < {}",
+            pedido.codigo,
+            self.config.formato_numeros.moeda(preco),
+            self.config.formato_numeros.moeda(custo)
+        )
+    }
+
+    /// Preço cobrado hoje, já com o desconto da promoção vigente.
+    fn preco_de_venda(&self, r: &Relogio) -> Option<f64> {
+        let desconto = self.promocao_ativa(r).map_or(0.0, |p| p.desconto);
+        r.preco.map(|p| p * (1.0 - desconto / 100.0))
+    }
+
+    /// Preço de venda e custo de referência, quando ambos são conhecidos.
+    fn preco_e_custo(&self, codigo: &str) -> Option<(f64, f64)> {
+        let r = self.relogios.get(codigo)?;
+        Some((self.preco_de_venda(r)?, self.custo_de_referencia(r)?))
+    }
+
+    /// Custo das compras (médio ou último, ver `custo_referencia`) ou, sem
+    /// compra com custo registrado, o do cadastro em reais.
+    fn custo_de_referencia(&self, r: &Relogio) -> Option<f64> {
+        dominio::custo_de_compra(&self.historico, &r.codigo, self.config.custo_referencia)
+            .or_else(|| self.custo_em_reais(r))
+    }
+
+    /// Confere `!senha` digitado na operação contra a senha de administrador.
+    /// Sem senha configurada, nada é liberado.
+    fn senha_confere(&self, token: Option<&str>) -> bool {
+        match (token, self.config.senha_admin.as_deref()) {
+            (Some(t), Some(senha)) => t == senha,
+            _ => false,
+        }
+    }
+
+    /// Totais do que a tabela de Estoque lista: códigos, unidades e valor a
+    /// preço e a custo (em reais). Kits contam como código, mas suas unidades
+    /// já estão nos componentes.
+    fn totais_estoque(&self) -> (usize, i64, f64, f64) {
+        let (mut unidades, mut preco, mut custo) = (0, 0.0, 0.0);
+        for r in &self.estoques_list {
+            let qtd = match &self.estoque_em {
+                Some((_, saldos)) => saldos.get(&r.codigo),
+                None => Some(r),
+            }
+            .filter(|s| s.componentes.is_empty())
+            .map_or(0, |s| s.quantidade());
+            unidades += qtd as i64;
+            preco += qtd as f64 * r.preco.unwrap_or(0.0);
+            custo += qtd as f64 * self.custo_em_reais(r).unwrap_or(0.0);
+        }
+        (self.estoques_list.len(), unidades, preco, custo)
+    }
+
+    /// Promoção de maior desconto vigente hoje para o produto.
+    fn promocao_ativa(&self, r: &Relogio) -> Option<&promocoes::Promocao> {
+        let hoje = Local::now().format("%Y-%m-%d").to_string();
+        promocoes::ativa(&self.registros.promocoes, r, &hoje)
+    }
+
+    /// Aciona a gaveta de dinheiro, se houver uma porta configurada.
+    fn abrir_gaveta(&mut self) {
+        if let Some(porta) = self.config.gaveta_porta.clone() {
+            if let Err(e) = gaveta::abrir(&porta) {
+                self.mensagens
+                    .push(format!("Falha ao abrir a gaveta em {}: {}", porta, e));
+            }
+        }
+    }
+
+    fn comprar_relogio(&mut self, codigo: String, qtd: i32, fornecedor: Option<String>) {
+        let reposto = self
+            .relogios
+            .get(&codigo)
+            .is_some_and(|r| r.quantidade() <= 0 && r.quantidade() + qtd > 0);
+        let cambio = self
+            .relogios
+            .get(&codigo)
+            .and_then(|r| r.moeda.as_ref())
+            .and_then(|m| self.cambio.get(m))
+            .map(|c| c.taxa);
+        let origem = fornecedor
+            .as_ref()
+            .map(|f| format!(" de {}", f))
+            .unwrap_or_default();
+        let resultado = ProdutoId::novo(&codigo).and_then(|c| {
+            let quantidade = Quantidade::movimento(qtd)?.ate(self.config.quantidade_maxima)?;
+            let mut inventario = Inventario::new(&mut self.relogios, &mut self.historico);
+            let compra = inventario.compra(&c, quantidade, Local::now().naive_local())?;
+            compra.cambio = cambio;
+            compra.fornecedor = fornecedor;
+            Ok(())
+        });
+        if let Err(e) = resultado {
+            self.mensagens.push(e.to_string());
+            return;
+        }
+        self.mensagens.push(format!(
+            "Adicionado {} unidades do relógio {}{}",
+            qtd, codigo, origem
+        ));
+        if self
+            .relogios
+            .get(&codigo)
+            .is_some_and(|r| r.situacao == Situacao::Descontinuado)
+        {
+            self.mensagens.push(format!(
+                "Atenção: {} está descontinuado; confira se a compra é mesmo dele.",
+                codigo
+            ));
+        }
+        if reposto {
+            self.avisa_lista_espera(&codigo);
+        }
+        self.atualiza_estoques_list();
+        self.atualiza_cadastro_list();
+        self.salvar();
+        // Pré-vendas esperando esta entrada
+        self.processa_agendadas(Local::now().naive_local());
+    }
+
+    /// Deixa a venda ou compra pendente até `data`, sem mexer no estoque.
+    fn agendar(
+        &mut self,
+        tipo: agendadas::Tipo,
+        codigo: String,
+        qtd: i32,
+        pagamento: Option<String>,
+        data: NaiveDate,
+    ) {
+        if !self.relogios.contains_key(&codigo) {
+            self.mensagens
+                .push(format!("Relógio {} não encontrado!", codigo));
+            return;
+        }
+        if let Err(e) =
+            Quantidade::movimento(qtd).and_then(|q| q.ate(self.config.quantidade_maxima))
+        {
+            self.mensagens.push(e.to_string());
+            return;
+        }
+        let numero = agendadas::agenda(
+            &mut self.registros.operacoes_agendadas,
+            tipo,
+            &codigo,
+            qtd,
+            pagamento,
+            data,
+            Local::now().naive_local(),
+        );
+        self.mensagens.push(format!(
+            "{:?} nº {} agendada: {} un. de {} em {} (pendente até lá)",
+            tipo,
+            numero,
+            qtd,
+            codigo,
+            data.format("%d/%m/%Y")
+        ));
+        self.atualiza_estoques_list();
+        self.salvar();
+    }
+
+    /// Poda o que passou do prazo configurado e conta no log.
+    fn aplica_retencao(&mut self, hoje: NaiveDate) {
+        let poda = retencao::aplica(
+            &self.config,
+            &mut self.registros,
+            &mut self.historico,
+            &self.relogios,
+            hoje,
+        );
+        // Pendentes de uma abertura anterior que não conseguiu gravar também
+        // vão agora.
+        if poda.vazia() && self.registros.historico_a_arquivar.is_empty() {
+            return;
+        }
+        if let Err(e) = arquivo::grava(&self.relogios, &self.historico, &mut self.registros) {
+            self.mensagens
+                .push(format!("Retenção: erro ao arquivar o histórico: {}", e));
+        } else if !poda.vazia() {
+            self.mensagens.push(poda.resumo());
+        }
+    }
+
+    /// Descarta as mensagens mais antigas além de `limite_mensagens`.
+    fn limita_mensagens(&mut self) {
+        let limite = self.config.limite_mensagens;
+        if limite > 0 && self.mensagens.len() > limite {
+            let excesso = self.mensagens.len() - limite;
+            self.mensagens.drain(..excesso);
+        }
+    }
+
+    /// Lança as vendas e compras agendadas cuja data chegou; as que falham
+    /// ficam para a próxima abertura (ou entrada de mercadoria).
+    fn processa_agendadas(&mut self, agora: NaiveDateTime) {
+        let feitas = agendadas::processa(
+            &mut self.registros.operacoes_agendadas,
+            &mut self.relogios,
+            &mut self.historico,
+            agora,
+        );
+        if feitas.is_empty() {
+            return;
+        }
+        for (a, resultado) in feitas {
+            self.mensagens.push(match resultado {
+                Ok(()) => format!(
+                    "{:?} agendada nº {} lançada: {} un. de {}",
+                    a.tipo, a.numero, a.quantidade, a.codigo
+                ),
+                Err(e) => format!(
+                    "{:?} agendada nº {} continua pendente: {}",
+                    a.tipo, a.numero, e
+                ),
+            });
+        }
+        self.atualiza_estoques_list();
+        self.atualiza_cadastro_list();
+        self.salvar();
+    }
+
+    /// Registra "nome;contato" na lista de espera do modelo sem estoque.
+    fn entrar_lista_espera(&mut self) {
+        let Some(codigo) = self.espera_codigo.take() else {
+            return;
+        };
+        let (cliente, contato) = self.input.split_once(';').unwrap_or((&self.input, ""));
+        if cliente.trim().is_empty() {
+            self.mensagens.push("Informe o nome do cliente.".into());
+            return;
+        }
+        self.registros.lista_espera.push(Espera {
+            codigo: codigo.clone(),
+            cliente: cliente.trim().to_string(),
+            contato: contato.trim().to_string(),
+            timestamp: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        });
+        let posicao = self
+            .registros
+            .lista_espera
+            .iter()
+            .filter(|e| e.codigo == codigo)
+            .count();
+        self.mensagens.push(format!(
+            "{} entrou na lista de espera de {} ({}º)",
+            cliente.trim(),
+            codigo,
+            posicao
+        ));
+        self.salvar();
+    }
+
+    /// Registra "autor;mensagem" para o próximo turno (sem autor, usa o
+    /// usuário do sistema).
+    fn deixar_recado(&mut self) {
+        let (autor, mensagem) = match self.input.split_once(';') {
+            Some((autor, mensagem)) => (autor.trim().to_string(), mensagem.trim()),
+            None => (dominio::operador(), self.input.trim()),
+        };
+        if mensagem.is_empty() {
+            self.mensagens.push("Recado vazio, nada registrado.".into());
+            return;
+        }
+        self.registros.recados.push(Recado {
+            autor: autor.clone(),
+            mensagem: mensagem.to_string(),
+            timestamp: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            lido: false,
+        });
+        self.mensagens.push(format!(
+            "Recado de {} guardado para o próximo turno.",
+            autor
+        ));
+        self.salvar();
+    }
+
+    /// Guarda o aviso na central de notificações (N) e o mostra no log.
+    fn notifica(&mut self, tipo: notificacoes::Tipo, chave: String, texto: String) {
+        let nova = notificacoes::Notificacao {
+            tipo,
+            chave,
+            texto: texto.clone(),
+            timestamp: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            lida: false,
+        };
+        if notificacoes::adiciona(&mut self.registros.notificacoes, nova) {
+            self.mensagens.push(texto);
+        }
+    }
+
+    /// Conflitos de sincronização pendentes, lembretes da última semana e
+    /// aniversários de bons clientes, uma vez cada.
+    fn notificacoes_de_abertura(&mut self, hoje: NaiveDate) {
+        let conflitos: Vec<(String, i32)> = self
+            .registros
+            .conflitos_sincronia
+            .iter()
+            .map(|c| (c.codigo.clone(), c.saldo))
+            .collect();
+        for (codigo, saldo) in conflitos {
+            self.notifica(
+                notificacoes::Tipo::Sincronia,
+                format!("sincronia-{}-{}", codigo, saldo),
+                format!(
+                    "Conflito de sincronização em {} (saldo {}): confira com `Relogio sincronizar conflitos`.",
+                    codigo, saldo
+                ),
+            );
+        }
+        let inicio = (hoje - chrono::Duration::days(7))
+            .format("%Y-%m-%d")
+            .to_string();
+        let dia = hoje;
+        let hoje = hoje.format("%Y-%m-%d").to_string();
+        let lembretes: Vec<agenda::Lembrete> = self
+            .registros
+            .lembretes
+            .iter()
+            .filter(|l| l.data >= inicio && l.data <= hoje)
+            .cloned()
+            .collect();
+        for l in lembretes {
+            self.notifica(
+                notificacoes::Tipo::Lembrete,
+                format!("lembrete-{}-{}", l.data, l.titulo),
+                format!(
+                    "Lembrete de {}: {}",
+                    Self::formata_data_ddmm(&l.data),
+                    l.titulo
+                ),
+            );
+        }
+        self.avisa_aniversarios(dia);
+    }
+
+    /// Aniversários da próxima semana entre os clientes que já gastaram o
+    /// mínimo configurado.
+    fn avisa_aniversarios(&mut self, hoje: NaiveDate) {
+        let clientes =
+            fidelidade::clientes(&self.registros.notas, &self.registros.lista_espera, hoje);
+        let avisos: Vec<(String, String)> = fidelidade::proximos_aniversarios(
+            &self.registros.aniversarios,
+            &clientes,
+            hoje,
+            7,
+            self.config.aniversario_gasto_minimo,
+        )
+        .into_iter()
+        .map(|(data, c)| {
+            let quando = match (data - hoje).num_days() {
+                0 => "hoje".to_string(),
+                1 => "amanhã".to_string(),
+                _ => format!("em {}", data.format("%d/%m")),
+            };
+            (
+                format!("aniversario-{}-{}", credito::chave(&c.nome), data),
+                format!(
+                    "{} faz aniversário {} (já comprou {}){}.",
+                    c.nome,
+                    quando,
+                    self.config.formato_numeros.moeda(c.valor),
+                    if c.contato.is_empty() {
+                        String::new()
+                    } else {
+                        format!(": {}", c.contato)
+                    }
+                ),
+            )
+        })
+        .collect();
+        for (chave, texto) in avisos {
+            self.notifica(notificacoes::Tipo::Aniversario, chave, texto);
+        }
+    }
+
+    /// Avisa os códigos que zeraram ou ficaram abaixo do estoque alvo com a
+    /// última saída; `antes` traz o saldo de cada um antes dela.
+    fn avisa_estoque_baixo(&mut self, antes: Vec<(String, i32)>) {
+        for (codigo, anterior) in antes {
+            let Some(r) = self.relogios.get(&codigo) else {
+                continue;
+            };
+            let texto = if r.quantidade() <= 0 && anterior > 0 {
+                format!("{} esgotou.", codigo)
+            } else if let Some(alvo) = r
+                .estoque_alvo
+                .filter(|a| r.quantidade() < *a && anterior >= *a)
+            {
+                format!(
+                    "{} abaixo do estoque alvo: {} de {} un.",
+                    codigo,
+                    r.quantidade(),
+                    alvo
+                )
+            } else {
+                continue;
+            };
+            self.notifica(notificacoes::Tipo::EstoqueBaixo, String::new(), texto);
+        }
+    }
+
+    fn abrir_recebimento(&mut self) {
+        self.entra_modo_insercao(Modo::Recebimento);
+        self.recebimento = Some(recebimento::Recebimento::novo());
+        self.recebimento_scroll.reinicia();
+    }
+
+    /// Teclas do assistente: fornecedor, leitura dos itens (Enter vazio vai
+    /// para a revisão) e revisão (Enter lança, Del tira a linha).
+    fn recebimento_tecla(&mut self, code: KeyCode) {
+        let Some(etapa) = self.recebimento.as_ref().map(|r| r.etapa) else {
+            return;
+        };
+        match (etapa, code) {
+            (recebimento::Etapa::Revisao, KeyCode::Esc) => {
+                self.set_etapa_recebimento(recebimento::Etapa::Itens);
+            }
+            (_, KeyCode::Esc) => {
+                self.recebimento = None;
+                self.sai_modo_insercao();
+                self.mensagens
+                    .push("Recebimento cancelado; nada foi lançado.".into());
+            }
+            (recebimento::Etapa::Fornecedor, KeyCode::Enter) => {
+                let fornecedor = self.input.trim().to_string();
+                if let Some(r) = self.recebimento.as_mut() {
+                    r.fornecedor = Some(fornecedor).filter(|f| !f.is_empty());
+                }
+                self.set_etapa_recebimento(recebimento::Etapa::Itens);
+            }
+            (recebimento::Etapa::Itens, KeyCode::Enter) if self.input.trim().is_empty() => {
+                let vazio = self.recebimento.as_ref().is_none_or(|r| r.itens.is_empty());
+                if !vazio {
+                    self.set_etapa_recebimento(recebimento::Etapa::Revisao);
+                }
+            }
+            (recebimento::Etapa::Itens, KeyCode::Enter) => self.ler_item_recebimento(),
+            (recebimento::Etapa::Revisao, KeyCode::Enter) => self.confirmar_recebimento(),
+            (recebimento::Etapa::Revisao, KeyCode::Up) => self.recebimento_scroll.sobe(),
+            (recebimento::Etapa::Revisao, KeyCode::Down) => {
+                let total = self.recebimento.as_ref().map_or(0, |r| r.itens.len());
+                self.recebimento_scroll.desce(total);
+            }
+            (recebimento::Etapa::Revisao, KeyCode::Delete | KeyCode::Backspace) => {
+                let i = self.recebimento_scroll.selected;
+                if let Some(r) = self.recebimento.as_mut().filter(|r| i < r.itens.len()) {
+                    r.itens.remove(i);
+                    self.recebimento_scroll.limita(r.itens.len());
+                    if r.itens.is_empty() {
+                        r.etapa = recebimento::Etapa::Itens;
+                    }
+                }
+            }
+            (recebimento::Etapa::Revisao, _) => {}
+            (_, KeyCode::Backspace) => {
+                self.input.pop();
+            }
+            (_, KeyCode::Char(ch)) => self.input.push(ch),
+            _ => {}
+        }
+    }
+
+    fn set_etapa_recebimento(&mut self, etapa: recebimento::Etapa) {
+        if let Some(r) = self.recebimento.as_mut() {
+            r.etapa = etapa;
+        }
+        self.input.clear();
+        self.recebimento_scroll.reinicia();
+    }
+
+    /// Lê `codigo [quantidade]` (padrão 1; negativa desconta uma leitura
+    /// errada). Aceita o EAN e o código do fornecedor da entrega.
+    fn ler_item_recebimento(&mut self) {
+        let entrada = self.input.clone();
+        self.input.clear();
+        let mut partes = entrada.split_whitespace();
+        let termo = partes.next().unwrap_or_default();
+        let qtd = match partes.next() {
+            None => Some(1),
+            Some(q) => self.config.formato_numeros.interpreta_inteiro(q),
+        };
+        let Some(qtd) = qtd.filter(|q| *q != 0) else {
+            self.mensagens.push("Quantidade inválida!".into());
+            return;
+        };
+        let fornecedor = self
+            .recebimento
+            .as_ref()
+            .and_then(|r| r.fornecedor.clone())
+            .unwrap_or_default();
+        let codigo = codigo_por_ean(&self.relogios, termo)
+            .or_else(|| {
+                fornecedores::resolve(&self.registros.referencias_fornecedor, &fornecedor, termo)
+                    .filter(|_| !fornecedor.is_empty())
+            })
+            .unwrap_or(termo)
+            .to_string();
+        match self.relogios.get(&codigo) {
+            None => self.mensagens.push(format!(
+                "{} não cadastrado: cadastre (C) antes de receber.",
+                codigo
+            )),
+            Some(r) if !r.componentes.is_empty() => self
+                .mensagens
+                .push(format!("{} é um kit: receba os componentes.", codigo)),
+            Some(_) => {
+                if let Some(rec) = self.recebimento.as_mut() {
+                    rec.adiciona(&codigo, qtd);
+                    let na_entrega = rec
+                        .itens
+                        .iter()
+                        .find(|(c, _)| *c == codigo)
+                        .map_or(0, |(_, q)| *q);
+                    self.mensagens
+                        .push(format!("{}: {} un. na entrega.", codigo, na_entrega));
+                }
+            }
+        }
+    }
+
+    /// Lança a entrega revisada de uma vez e grava.
+    fn confirmar_recebimento(&mut self) {
+        let Some(rec) = self.recebimento.clone() else {
+            return;
+        };
+        let zerados: Vec<String> = rec
+            .itens
+            .iter()
+            .filter(|(c, _)| self.relogios.get(c).is_some_and(|r| r.quantidade() <= 0))
+            .map(|(c, _)| c.clone())
+            .collect();
+        match recebimento::lanca(
+            &rec,
+            &mut self.relogios,
+            &mut self.historico,
+            Local::now().naive_local(),
+        ) {
+            Ok(numero) => {
+                self.mensagens.push(format!(
+                    "Recebimento nº {} lançado: {} itens, {} unidades.",
+                    numero,
+                    rec.itens.len(),
+                    rec.unidades()
+                ));
+                for codigo in zerados {
+                    self.avisa_lista_espera(&codigo);
+                }
+                self.abate_pedidos(rec.fornecedor.as_deref(), &rec.itens);
+                self.exportar_etiquetas_recebimento(numero, &rec.itens);
+                self.recebimento = None;
+                self.sai_modo_insercao();
+                self.atualiza_estoques_list();
+                self.atualiza_cadastro_list();
+                self.salvar();
+                self.processa_agendadas(Local::now().naive_local());
+            }
+            Err(e) => self.mensagens.push(e.to_string()),
+        }
+    }
+
+    /// Grava o lote de etiquetas só do que chegou, uma por unidade, para
+    /// imprimir quando as peças forem para a vitrine.
+    fn exportar_etiquetas_recebimento(&mut self, numero: u32, itens: &[(String, i32)]) {
+        let lote: Vec<(&Relogio, i32)> = itens
+            .iter()
+            .filter_map(|(c, q)| Some((self.relogios.get(c)?, *q)))
+            .collect();
+        let payload = etiquetas::lote(
+            &lote,
+            self.config.formato_etiqueta,
+            self.config.formato_numeros,
+        );
+        match etiquetas::grava_lote(
+            &format!("recebimento_{}", numero),
+            &payload,
+            self.config.formato_etiqueta,
+        ) {
+            Ok(caminho) => self
+                .mensagens
+                .push(format!("Etiquetas da entrega em {}", caminho)),
+            Err(e) => self
+                .mensagens
+                .push(format!("Falha ao gravar as etiquetas: {}", e)),
+        }
+    }
+
+    /// Abate a entrega dos pedidos de compra abertos e avisa os que fecharam
+    /// e o que ainda falta dos itens recebidos.
+    fn abate_pedidos(&mut self, fornecedor: Option<&str>, itens: &[(String, i32)]) {
+        let hoje = Local::now().date_naive();
+        let data = hoje.format("%Y-%m-%d").to_string();
+        for (codigo, qtd) in itens {
+            for numero in pedidos::recebe(
+                &mut self.registros.pedidos_compra,
+                fornecedor,
+                codigo,
+                *qtd,
+                &data,
+            ) {
+                self.mensagens
+                    .push(format!("Pedido nº {} recebido por completo.", numero));
+            }
+        }
+        let faltam: i32 = pedidos::pendencias(&self.registros.pedidos_compra, hoje)
+            .iter()
+            .filter(|p| itens.iter().any(|(c, _)| *c == p.codigo))
+            .map(|p| p.quantidade - p.recebido)
+            .sum();
+        if faltam > 0 {
+            self.mensagens.push(format!(
+                "Ainda faltam {} unidades desses itens em pedidos abertos (Y).",
+                faltam
+            ));
+        }
+    }
+
+    fn abrir_pedidos(&mut self) {
+        self.modo = Modo::Pedidos;
+        self.editing = false;
+        self.input.clear();
+        self.pedidos_scroll.reinicia();
+    }
+
+    /// Registra o pedido digitado como `fornecedor codigo:qtd ...` ou, com
+    /// a cadência na frente, o pedido recorrente. Não grava.
+    fn abrir_pedido_compra(&mut self) -> Result<String, String> {
+        let recorrente = pedidos::interpreta_recorrente(&self.input)?;
+        let (fornecedor, itens) = match &recorrente {
+            Some((_, fornecedor, itens)) => (fornecedor.clone(), itens.clone()),
+            None => pedidos::interpreta(&self.input)?,
+        };
+        if let Some(i) = itens
+            .iter()
+            .find(|i| !self.relogios.contains_key(&i.codigo))
+        {
+            return Err(format!("Relógio {} não encontrado!", i.codigo));
+        }
+        if let Some((cadencia, _, _)) = recorrente {
+            let numero = pedidos::cria_recorrente(
+                &mut self.registros.pedidos_recorrentes,
+                cadencia,
+                &fornecedor,
+                itens,
+                Local::now().date_naive(),
+            );
+            let proxima = &self.registros.pedidos_recorrentes.last().unwrap().proxima;
+            return Ok(format!(
+                "Pedido recorrente nº {} ({}) a {}: primeiro rascunho em {}.",
+                numero,
+                cadencia.rotulo(),
+                fornecedor,
+                Self::formata_data_ddmm(proxima)
+            ));
+        }
+        let unidades: i32 = itens.iter().map(|i| i.quantidade).sum();
+        let numero = pedidos::abre(
+            &mut self.registros.pedidos_compra,
+            &fornecedor,
+            itens,
+            &Local::now().format("%Y-%m-%d").to_string(),
+        );
+        Ok(format!(
+            "Pedido nº {} a {}: {} unidades.",
+            numero, fornecedor, unidades
+        ))
+    }
+
+    /// Transforma em rascunho os pedidos recorrentes cujo ciclo chegou e
+    /// avisa na central de notificações.
+    fn gera_rascunhos_recorrentes(&mut self, hoje: NaiveDate) {
+        let novos = pedidos::gera_rascunhos(
+            &mut self.registros.pedidos_recorrentes,
+            &mut self.registros.rascunhos_pedido,
+            hoje,
+        );
+        if novos.is_empty() {
+            return;
+        }
+        for r in novos {
+            self.notifica(
+                notificacoes::Tipo::Pedido,
+                format!("recorrente-{}-{}", r.recorrente, r.ciclo),
+                format!(
+                    "Pedido recorrente nº {} a {} pronto: confirme ou pule em Y.",
+                    r.recorrente, r.fornecedor
+                ),
+            );
+        }
+        self.salvar();
+    }
+
+    /// Confirma como pedido (ou pula) o rascunho recorrente mais antigo.
+    fn responder_rascunho(&mut self, confirma: bool) {
+        let Some(r) = self.registros.rascunhos_pedido.first().cloned() else {
+            self.mensagens
+                .push("Nenhum rascunho de pedido recorrente.".into());
+            return;
+        };
+        let hoje = Local::now().format("%Y-%m-%d").to_string();
+        match pedidos::responde_rascunho(
+            &mut self.registros.rascunhos_pedido,
+            &mut self.registros.pedidos_compra,
+            0,
+            confirma,
+            &hoje,
+        ) {
+            Some(numero) => self.mensagens.push(format!(
+                "Pedido nº {} a {} aberto pelo recorrente nº {}.",
+                numero, r.fornecedor, r.recorrente
+            )),
+            None => self.mensagens.push(format!(
+                "Ciclo de {} do recorrente nº {} pulado.",
+                Self::formata_data_ddmm(&r.ciclo),
+                r.recorrente
+            )),
+        }
+        self.salvar();
+    }
+
+    fn sugestoes_reposicao(&self) -> Vec<reposicao::Sugestao> {
+        reposicao::sugestoes(
+            &self.relogios,
+            &self.historico,
+            &self.registros.pedidos_compra,
+        )
+    }
+
+    /// N na sugestão de reposição: abre a digitação do pedido já preenchida
+    /// com o que sugerir para o fornecedor da linha selecionada.
+    fn rascunho_reposicao(&mut self) {
+        let lista = self.sugestoes_reposicao();
+        let Some(s) = lista.get(self.pedidos_scroll.selected) else {
+            self.mensagens
+                .push("Nada a repor: defina alvo e mínimo com Relogio alvo.".into());
+            return;
+        };
+        self.input = reposicao::rascunho(&lista, s);
+        self.editing = true;
+    }
+
+    /// Cancela o que falta da pendência selecionada.
+    fn cancelar_pendencia(&mut self) {
+        let hoje = Local::now().date_naive();
+        let lista = pedidos::pendencias(&self.registros.pedidos_compra, hoje);
+        let Some(p) = lista.get(self.pedidos_scroll.selected) else {
+            return;
+        };
+        let fechou = pedidos::cancela_saldo(
+            &mut self.registros.pedidos_compra,
+            p.numero,
+            &p.codigo,
+            &hoje.format("%Y-%m-%d").to_string(),
+        );
+        self.mensagens.push(format!(
+            "Saldo de {} no pedido nº {} cancelado{}.",
+            p.codigo,
+            p.numero,
+            if fechou { "; pedido fechado" } else { "" }
+        ));
+        self.pedidos_scroll.limita(lista.len() - 1);
+        self.salvar();
+    }
+
+    fn abrir_orcamentos(&mut self) {
+        self.modo = Modo::Orcamentos;
+        self.editing = false;
+        self.input.clear();
+        self.orcamentos_scroll.reinicia();
+    }
+
+    fn digitar_orcamento(&mut self, entrada: EntradaOrcamento) {
+        if entrada != EntradaOrcamento::Novo && self.orcamento_selecionado().is_none() {
+            return;
+        }
+        self.orcamento_entrada = entrada;
+        self.editing = true;
+        self.input.clear();
+    }
+
+    /// Número do orçamento selecionado na fila.
+    fn orcamento_selecionado(&self) -> Option<u32> {
+        orcamentos::fila(&self.registros.orcamentos)
+            .get(self.orcamentos_scroll.selected)
+            .map(|o| o.numero)
+    }
+
+    /// Aplica o que foi digitado na fila de orçamentos. Não grava.
+    fn confirmar_orcamento(&mut self) -> Result<String, String> {
+        let hoje = Local::now().date_naive();
+        if self.orcamento_entrada == EntradaOrcamento::Novo {
+            let numero = orcamentos::cria(
+                &mut self.registros.orcamentos,
+                &self.input,
+                &self.relogios,
+                hoje,
+            )?;
+            return Ok(format!("Orçamento nº {} registrado.", numero));
+        }
+        let numero = self
+            .orcamento_selecionado()
+            .ok_or("Nenhum orçamento selecionado.")?;
+        let entrada = self.orcamento_entrada;
+        let texto = self.input.trim().to_string();
+        let o = self
+            .registros
+            .orcamentos
+            .iter_mut()
+            .find(|o| o.numero == numero)
+            .ok_or("Nenhum orçamento selecionado.")?;
+        if entrada == EntradaOrcamento::Perda {
+            orcamentos::fecha(o, orcamentos::Situacao::Perdido, Some(texto), hoje);
+            self.orcamentos_scroll
+                .limita(orcamentos::fila(&self.registros.orcamentos).len());
+            return Ok(format!("Orçamento nº {} perdido.", numero));
+        }
+        let data = orcamentos::interpreta_retorno(&texto, hoje)
+            .ok_or("Retorno inválido: use dias ou DD/MM.")?;
+        o.retorno = data.format("%Y-%m-%d").to_string();
+        Ok(format!(
+            "Retorno do orçamento nº {} em {}.",
+            numero,
+            data.format("%d/%m")
+        ))
+    }
+
+    fn ganhar_orcamento(&mut self) {
+        let Some(numero) = self.orcamento_selecionado() else {
+            return;
+        };
+        if let Some(o) = self
+            .registros
+            .orcamentos
+            .iter_mut()
+            .find(|o| o.numero == numero)
+        {
+            orcamentos::fecha(
+                o,
+                orcamentos::Situacao::Ganho,
+                None,
+                Local::now().date_naive(),
+            );
+        }
+        self.mensagens
+            .push(format!("Orçamento nº {} ganho.", numero));
+        self.orcamentos_scroll
+            .limita(orcamentos::fila(&self.registros.orcamentos).len());
+        self.salvar();
+    }
+
+    fn abrir_notificacoes(&mut self) {
+        self.modo = Modo::Notificacoes;
+        self.editing = false;
+        self.input.clear();
+        self.notificacoes_scroll.reinicia();
+    }
+
+    /// Sai da central dando as notificações exibidas como lidas.
+    fn fechar_notificacoes(&mut self) {
+        let mut mudou = false;
+        for n in self.registros.notificacoes.iter_mut().filter(|n| !n.lida) {
+            n.lida = true;
+            mudou = true;
+        }
+        self.modo = Modo::Estoques;
+        if mudou {
+            self.salvar();
+        }
+    }
+
+    /// Fecha o aviso de abertura e marca os recados exibidos como lidos.
+    fn ciente_recados(&mut self) {
+        for aberto in self.recados_abertos.drain(..) {
+            if let Some(r) = self
+                .registros
+                .recados
+                .iter_mut()
+                .find(|r| r.timestamp == aberto.timestamp && r.autor == aberto.autor)
+            {
+                r.lido = true;
+            }
+        }
+        self.salvar();
+    }
+
+    /// Lista, em ordem de chegada, quem aguardava o modelo que voltou ao estoque.
+    fn avisa_lista_espera(&mut self, codigo: &str) {
+        let clientes: Vec<String> = self
+            .registros
+            .lista_espera
+            .iter()
+            .filter(|e| e.codigo == codigo)
+            .enumerate()
+            .map(|(i, e)| format!("{}. {} ({})", i + 1, e.cliente, e.contato))
+            .collect();
+        if !clientes.is_empty() {
+            self.notifica(
+                notificacoes::Tipo::ListaEspera,
+                String::new(),
+                format!(
+                    "{} voltou ao estoque! Lista de espera: {}",
+                    codigo,
+                    clientes.join(", ")
+                ),
+            );
+        }
+    }
+
+    /// Conclui a venda paga com crédito da loja do cliente digitado.
+    fn resgatar_credito(&mut self) {
+        let Some((codigo, qtd, liberado)) = self.venda_credito.take() else {
+            return;
+        };
+        let cliente = self.input.trim().to_string();
+        let Some(r) = self.relogios.get(&codigo) else {
+            self.mensagens.push("Relógio não encontrado!".into());
+            return;
+        };
+        let total = self.preco_de_venda(r).unwrap_or(0.0) * qtd as f64;
+        let saldo = credito::saldo(&self.registros.creditos, &cliente);
+        if cliente.is_empty() || saldo + 0.005 < total {
+            self.mensagens.push(format!(
+                "Crédito insuficiente para '{}': saldo {}, venda {}",
+                cliente,
+                self.config.formato_numeros.moeda(saldo),
+                self.config.formato_numeros.moeda(total)
+            ));
+            return;
+        }
+        let antes = self.historico.len();
+        self.vender_relogio(
+            codigo.clone(),
+            qtd,
+            Some("credito".into()),
+            liberado,
+            Canal::Balcao,
+        );
+        let Some(valor) = self
+            .historico
+            .get(antes)
+            .and_then(|h| contabil::valor(h, self.relogios.get(&h.codigo)))
+        else {
+            return;
+        };
+        self.registros.creditos.push(credito::MovimentoCredito {
+            cliente: cliente.clone(),
+            valor: -valor,
+            motivo: format!("Venda {} x {}", qtd, codigo),
+            timestamp: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        });
+        self.mensagens.push(format!(
+            "Crédito restante de {}: {}",
+            cliente,
+            self.config.formato_numeros.moeda(saldo - valor)
+        ));
+        self.salvar();
+    }
+
+    fn get_historico_atual_vec(&self) -> Vec<Historico> {
+        let base = if let Some(ref h) = self.historico_filtrado {
+            h
+        } else {
+            &self.historico
+        };
+        base.iter()
+            .filter(|h| match self.historico_tab {
+                HistoricoTab::Todos => true,
+                HistoricoTab::Compras => h.operacao == "COMPRA",
+                HistoricoTab::Vendas => h.operacao == "VENDA",
+                HistoricoTab::Aquisicoes => h.operacao == "CADASTRO",
+            })
+            .filter(|h| {
+                self.historico_etiqueta
+                    .as_ref()
+                    .is_none_or(|t| h.tem_tag(t))
+            })
+            .cloned()
+            .collect()
+    }
+
+    fn get_historico_por_codigo(&self, codigo: &str) -> Vec<Historico> {
+        self.historico
+            .iter()
+            .filter(|h| h.codigo == codigo)
+            .cloned()
+            .collect()
+    }
+
+    /// Movimentos da linha atual do Estoque, do mais recente ao mais antigo,
+    /// para o painel dividido.
+    fn historico_do_selecionado(&self) -> Vec<Historico> {
+        let Some(r) = self.estoques_list.get(self.estoques_scroll.selected) else {
+            return vec![];
+        };
+        let mut lista = self.get_historico_por_codigo(&r.codigo);
+        lista.reverse();
+        lista
+    }
+
+    /// Entrada digitada com o primeiro termo trocado pelo código interno
+    /// quando é o EAN de um produto.
+    fn input_com_ean(&self) -> String {
+        let termo = self.input.split_whitespace().next().unwrap_or("");
+        match codigo_por_ean(&self.relogios, termo) {
+            Some(codigo) => self.input.trim_start().replacen(termo, codigo, 1),
+            None => self.input.clone(),
+        }
+    }
+
+    /// Na Compra ou Venda de um código que não existe, oferece os mais
+    /// parecidos e mantém a entrada aberta; `true` segura o Enter.
+    fn oferece_sugestao(&mut self) -> bool {
+        let input = self.input_com_ean();
+        let Some(codigo) = input.split_whitespace().next() else {
+            return false;
+        };
+        let insistiu = self.modo == Modo::Compra
+            && self
+                .sugestao
+                .as_ref()
+                .is_some_and(|s| s.input == self.input);
+        if self.relogios.contains_key(codigo) || insistiu {
+            self.sugestao = None;
+            return false;
+        }
+        let candidatos: Vec<String> = self
+            .busca_relogios(codigo)
+            .into_iter()
+            .take(3)
+            .map(|(c, _, _)| c)
+            .collect();
+        if candidatos.is_empty() {
+            self.sugestao = None;
+            return false;
+        }
+        let novo = if self.modo == Modo::Compra {
+            ", Enter de novo cadastra o novo"
+        } else {
+            ""
+        };
+        self.mensagens.push(format!(
+            "Relógio {} não encontrado. Você quis dizer {}? (Tab troca o código{})",
+            codigo,
+            candidatos.join(" ou "),
+            novo
+        ));
+        self.sugestao = Some(Sugestao {
+            input: self.input.clone(),
+            candidatos,
+            proximo: 0,
+        });
+        true
+    }
+
+    /// Linha de apoio da Compra/Venda enquanto há sugestão.
+    fn linha_sugestao(&self) -> Option<String> {
+        let s = self.sugestao.as_ref()?;
+        Some(format!(
+            "Você quis dizer {}? Tab troca o código",
+            s.candidatos.join(" ou ")
+        ))
+    }
+
+    /// Troca o código digitado pela próxima sugestão.
+    fn aplica_sugestao(&mut self) {
+        let Some(s) = &mut self.sugestao else {
+            return;
+        };
+        let codigo = &s.candidatos[s.proximo % s.candidatos.len()];
+        s.proximo += 1;
+        self.input = match self.input.trim_start().split_once(' ') {
+            Some((_, resto)) => format!("{} {}", codigo, resto),
+            None => codigo.clone(),
+        };
+    }
+
+    fn entra_modo_insercao(&mut self, modo: Modo) {
+        self.modo = modo;
+        self.input.clear();
+        self.busca_ultima_query.clear();
+        self.busca_candidatos.clear();
+        self.busca_pendente = None;
+        self.editing = true;
+        self.historico_filtrado = None;
+    }
+
+    fn sai_modo_insercao(&mut self) {
+        self.modo = Modo::Estoques;
+        self.input.clear();
+        self.editing = false;
+        self.historico_filtrado = None;
+    }
+
+    fn busca_relogios(&self, query: &str) -> Vec<(String, i32, usize)> {
+        let limite = query.chars().count() / 2 + 1;
+        self.pontua_codigos(self.relogios.keys(), |c| {
+            levenshtein_limitado(c, query, limite)
+        })
+    }
+
+    /// Pontua os candidatos, que contêm a consulta como subsequência (sem
+    /// diferenciar maiúsculas). Para eles a distância não passa da diferença
+    /// de tamanho, basta apagar o que sobra, então a banda com esse limite
+    /// dá a distância exata.
+    fn pontua_candidatos<'a>(
+        &self,
+        codigos: impl Iterator<Item = &'a String>,
+        query: &str,
+    ) -> Vec<(String, i32, usize)> {
+        let query = query.to_lowercase();
+        let tamanho = query.chars().count();
+        self.pontua_codigos(codigos, |c| {
+            let c = c.to_lowercase();
+            let limite = c.chars().count().saturating_sub(tamanho);
+            levenshtein_limitado(&c, &query, limite)
+        })
+    }
+
+    /// Pontua os códigos em paralelo pela `distancia` (em banda), deixando
+    /// de fora os que ela descarta.
+    fn pontua_codigos<'a>(
+        &self,
+        codigos: impl Iterator<Item = &'a String>,
+        distancia: impl Fn(&str) -> Option<usize> + Sync,
+    ) -> Vec<(String, i32, usize)> {
+        let codigos: Vec<&String> = codigos.collect();
+        let relogios = &self.relogios;
+        let mut resultados: Vec<(String, i32, usize)> = codigos
+            .par_iter()
+            .filter_map(|c| {
+                let r = relogios.get(*c)?;
+                let dist = distancia(&r.codigo)?;
+                Some((r.codigo.clone(), quantidade_disponivel(relogios, r), dist))
+            })
+            .collect();
+        resultados.par_sort_unstable_by(|a, b| a.2.cmp(&b.2).then_with(|| a.0.cmp(&b.0)));
+        resultados
+    }
+
+    pub(crate) fn formata_data_ddmm(data: &str) -> String {
+        let parts: Vec<&str> = data.split('-').collect();
+        if parts.len() == 3 {
+            let dia = parts[2];
+            let mes = parts[1];
+            format!("{}/{}", dia, mes)
+        } else {
+            data.to_string()
+        }
+    }
+
+    /// Período escolhido com ↑/↓ nas quebras e no padrão por dia/hora.
+    fn periodo_grafico(&self) -> (&'static str, Option<NaiveDate>) {
+        let (rotulo, dias) = PERIODOS_GRAFICO[self.grafico_periodo];
+        let desde = dias.map(|d| Local::now().date_naive() - chrono::Duration::days(d - 1));
+        (rotulo, desde)
+    }
+
+    fn vendas_por_grupo_grafico(&self) -> Vec<(String, f64)> {
+        let tab = self.grafico_tab;
+        analise::vendas_por_grupo(
+            &self.historico,
+            &self.relogios,
+            |r| match tab {
+                GraficoTab::Marcas => r.marca.clone(),
+                GraficoTab::Produtos => Some(r.codigo.clone()),
+                _ => r.categoria.clone(),
+            },
+            self.periodo_grafico().1,
+        )
+    }
+
+    /// Os quadros da aba atual do gráfico, como estão na tela.
+    fn paineis_do_grafico(&self) -> Vec<figura::Painel> {
+        let painel = |titulo: String, pontos: Vec<(String, f64)>| figura::Painel {
+            titulo,
+            pontos,
+            linha: false,
+        };
+        let (rotulo, desde) = self.periodo_grafico();
+        match self.grafico_tab {
+            GraficoTab::Diario => {
+                let dias = self.agrupamento_por_dia();
+                vec![
+                    painel(
+                        "Vendas (últimos 7 dias)".into(),
+                        dias.iter()
+                            .map(|(d, v, _)| (d.clone(), *v as f64))
+                            .collect(),
+                    ),
+                    painel(
+                        "Compras (últimos 7 dias)".into(),
+                        dias.iter()
+                            .map(|(d, _, c)| (d.clone(), *c as f64))
+                            .collect(),
+                    ),
+                ]
+            }
+            GraficoTab::MesAMes if self.grafico_acumulado => {
+                let (atual, passado) = analise::acumulado_do_mes(
+                    &self.historico,
+                    &self.relogios,
+                    Local::now().date_naive(),
+                );
+                let serie = |titulo: &str, dias: &[(i32, f64)], receita: bool| figura::Painel {
+                    linha: true,
+                    ..painel(
+                        titulo.into(),
+                        dias.iter()
+                            .enumerate()
+                            .map(|(i, (u, r))| {
+                                ((i + 1).to_string(), if receita { *r } else { *u as f64 })
+                            })
+                            .collect(),
+                    )
+                };
+                vec![
+                    serie("Receita acumulada, mês atual", &atual, true),
+                    serie("Receita acumulada, mês anterior", &passado, true),
+                    serie("Unidades acumuladas, mês atual", &atual, false),
+                    serie("Unidades acumuladas, mês anterior", &passado, false),
+                ]
+            }
+            GraficoTab::MesAMes => {
+                let meses = analise::vendas_mes_a_mes(
+                    &self.historico,
+                    &self.relogios,
+                    Local::now().date_naive(),
+                );
+                let mes = |d: &NaiveDate| d.format("%m/%y").to_string();
+                vec![
+                    figura::Painel {
+                        linha: true,
+                        ..painel(
+                            "Vendas por mês, últimos 12 meses".into(),
+                            meses.iter().map(|(d, v, _)| (mes(d), *v)).collect(),
+                        )
+                    },
+                    figura::Painel {
+                        linha: true,
+                        ..painel(
+                            "Vendas por mês, um ano antes".into(),
+                            meses
+                                .iter()
+                                .map(|(d, _, v)| {
+                                    (mes(&d.with_year(d.year() - 1).unwrap_or(*d)), *v)
+                                })
+                                .collect(),
+                        )
+                    },
+                ]
+            }
+            GraficoTab::ValorEstoque => vec![figura::Painel {
+                linha: true,
+                ..painel(
+                    format!(
+                        "Valor do estoque ao custo, {}",
+                        if self.grafico_mensal {
+                            "por mês"
+                        } else {
+                            "por semana"
+                        }
+                    ),
+                    self.grafico_valor.clone(),
+                )
+            }],
+            GraficoTab::Categorias | GraficoTab::Marcas | GraficoTab::Produtos => vec![painel(
+                format!("Vendas por {}, {}", self.grafico_tab.grupo(), rotulo),
+                self.vendas_por_grupo_grafico(),
+            )],
+            GraficoTab::Padrao => {
+                let (semana, horas) = analise::padrao_de_vendas(&self.historico, desde);
+                vec![
+                    painel(
+                        format!("Unidades por dia da semana, {}", rotulo),
+                        DIAS_SEMANA
+                            .iter()
+                            .zip(semana)
+                            .map(|(d, v)| (d.to_string(), v as f64))
+                            .collect(),
+                    ),
+                    painel(
+                        "Unidades por hora do dia".into(),
+                        horas_com_venda(&horas)
+                            .map(|h| (format!("{:02}h", h), horas[h] as f64))
+                            .collect(),
+                    ),
+                ]
+            }
+        }
+    }
+
+    /// `E` no gráfico: grava os dados da aba em CSV e o desenho em SVG e PNG.
+    fn exportar_grafico(&mut self) {
+        let paineis = self.paineis_do_grafico();
+        let numeros = self.config.formato_numeros;
+        let aba = GraficoTab::titles()[self.grafico_tab.indice()];
+        let titulo = format!("{} — {}", aba, Local::now().format("%d/%m/%Y"));
+        let base = format!(
+            "grafico_{}_{}",
+            match self.grafico_tab {
+                GraficoTab::Diario => "diario",
+                GraficoTab::MesAMes if self.grafico_acumulado => "acumulado_mes",
+                GraficoTab::MesAMes => "mes_a_mes",
+                GraficoTab::ValorEstoque => "valor_estoque",
+                GraficoTab::Categorias => "categorias",
+                GraficoTab::Marcas => "marcas",
+                GraficoTab::Produtos => "produtos",
+                GraficoTab::Padrao => "dia_hora",
+            },
+            Local::now().format("%Y-%m-%d_%H%M")
+        );
+        let gravado = fs::write(format!("{}.csv", base), figura::csv(&paineis, numeros))
+            .and_then(|_| {
+                fs::write(
+                    format!("{}.svg", base),
+                    figura::svg(&titulo, &paineis, numeros),
+                )
+            })
+            .and_then(|_| figura::png(&titulo, &paineis, numeros))
+            .and_then(|png| fs::write(format!("{}.png", base), png));
+        match gravado {
+            Ok(()) => self
+                .mensagens
+                .push(format!("Gráfico exportado em {}.csv, .svg e .png", base)),
+            Err(e) => self
+                .mensagens
+                .push(format!("Falha ao exportar o gráfico: {}", e)),
+        }
+    }
+
+    /// Estoque e histórico (na aba, filtro e etiqueta em uso) em CSV.
+    fn csvs_para_exportar(&self) -> (String, String) {
+        let numeros = self.config.formato_numeros;
+        (
+            relatorios::estoque_csv(&self.relogios, numeros),
+            relatorios::historico_csv(&self.get_historico_atual_vec(), &self.relogios, numeros),
+        )
+    }
+
+    fn exportar_csv(&mut self) {
+        let (estoque, historico) = self.csvs_para_exportar();
+        let entradas = historico.lines().count() - 1;
+        match fs::write("estoque.csv", estoque).and_then(|_| fs::write("historico.csv", historico))
+        {
+            Ok(()) => self.mensagens.push(format!(
+                "Exportados estoque.csv ({} códigos) e historico.csv ({} entradas).",
+                self.relogios.len(),
+                entradas
+            )),
+            Err(e) => self
+                .mensagens
+                .push(format!("Falha ao exportar os CSV: {}", e)),
+        }
+    }
+
+    fn agrupamento_por_dia(&self) -> Vec<(String, usize, usize)> {
+        let mut mapa: HashMap<String, (usize, usize)> = HashMap::new();
+
+        for h in &self.historico {
+            if let Some(space_pos) = h.timestamp.find(' ') {
+                let data_str = &h.timestamp[..space_pos];
+                let entry = mapa.entry(data_str.to_string()).or_insert((0, 0));
+                match h.operacao.as_str() {
+                    "VENDA" => entry.0 += 1,
+                    "COMPRA" => entry.1 += 1,
+                    _ => {}
+                }
+            }
+        }
+
+        let mut vet: Vec<(String, (usize, usize))> = mapa.into_iter().collect();
+        vet.sort_by(|a, b| a.0.cmp(&b.0));
+        let len = vet.len();
+        let start = len.saturating_sub(7);
+        vet[start..]
+            .iter()
+            .map(|(k, (v, c))| {
+                let curta = Self::formata_data_ddmm(k);
+                (curta, *v, *c)
+            })
+            .collect()
+    }
+
+    fn grafico_tab_troca(&mut self, proxima: bool) {
+        self.grafico_tab = if proxima {
+            self.grafico_tab.next()
+        } else {
+            self.grafico_tab.prev()
+        };
+        self.atualiza_grafico_valor();
+    }
+
+    /// Valor do estoque ao custo (em reais; sem custo, pelo preço) no fim de
+    /// cada um dos últimos 12 meses ou semanas, a partir do kardex.
+    fn atualiza_grafico_valor(&mut self) {
+        if self.grafico_tab != GraficoTab::ValorEstoque {
+            return;
+        }
+        let mut k = kardex::Kardex::carrega();
+        k.atualiza(&self.historico);
+        let fins = analise::fins_de_periodo(Local::now().date_naive(), self.grafico_mensal, 12);
+        self.grafico_valor = fins
+            .iter()
+            .map(|fim| {
+                let data = fim.format("%Y-%m-%d").to_string();
+                let valor: f64 = self
+                    .relogios
+                    .values()
+                    .filter_map(|r| {
+                        let unitario = self.custo_em_reais(r).or(r.preco)?;
+                        Some(k.saldo_em(&r.codigo, &data).unwrap_or(0).max(0) as f64 * unitario)
+                    })
+                    .sum();
+                let rotulo = if self.grafico_mensal {
+                    fim.format("%m/%y").to_string()
+                } else {
+                    Self::formata_data_ddmm(&data)
+                };
+                (rotulo, valor)
+            })
+            .collect();
+    }
+
+    /// Reconstrói o estoque no fim do dia digitado (AAAA-MM-DD ou
+    /// DD/MM/AAAA) repetindo o histórico, para consulta sem alterar nada.
+    fn consultar_estoque_em(&mut self) {
+        let texto = self.input.trim();
+        let Some(data) = NaiveDate::parse_from_str(texto, "%Y-%m-%d")
+            .or_else(|_| NaiveDate::parse_from_str(texto, "%d/%m/%Y"))
+            .ok()
+        else {
+            self.mensagens
+                .push(format!("Data inválida: '{}' (use DD/MM/AAAA).", texto));
+            return;
+        };
+        if data >= Local::now().date_naive() {
+            self.mensagens
+                .push("Informe uma data passada; hoje é o estoque atual.".into());
+            return;
+        }
+        let mut k = kardex::Kardex::carrega();
+        k.atualiza(&self.historico);
+        let dia = data.format("%Y-%m-%d").to_string();
+        let mut saldos: HashMap<String, Relogio> = self
+            .relogios
+            .values()
+            .filter_map(|r| {
+                let mut naquele_dia = r.clone();
+                naquele_dia.define_quantidade(k.saldo_em(&r.codigo, &dia)?);
+                Some((r.codigo.clone(), naquele_dia))
+            })
+            .collect();
+        // Kits não têm saldo próprio: existem se algum componente já existia
+        for r in self.relogios.values().filter(|r| !r.componentes.is_empty()) {
+            if r.componentes.iter().any(|c| saldos.contains_key(&c.codigo)) {
+                saldos.insert(r.codigo.clone(), r.clone());
+            }
+        }
+        self.mensagens.push(format!(
+            "Estoque em {}: {} modelos. Somente leitura; T ou ESC volta ao atual.",
+            data.format("%d/%m/%Y"),
+            saldos.len()
+        ));
+        self.estoque_em = Some((data, saldos));
+    }
+
+    fn historico_select_up(&mut self) {
+        self.historico_scroll.sobe();
+    }
+
+    fn historico_select_down(&mut self) {
+        let total = self.get_historico_atual_vec().len();
+        self.historico_scroll.desce(total);
+    }
+
+    fn historico_tab_next(&mut self) {
+        self.historico_tab = self.historico_tab.next();
+        self.historico_scroll.reinicia();
+    }
+
+    fn historico_tab_prev(&mut self) {
+        self.historico_tab = self.historico_tab.prev();
+        self.historico_scroll.reinicia();
+    }
+
+    fn cadastro_select_up(&mut self) {
+        self.cadastro_scroll.sobe();
+    }
+
+    fn cadastro_select_down(&mut self) {
+        let total = self.cadastro_list.len();
+        self.cadastro_scroll.desce(total);
+    }
+
+    fn buscar_select_up(&mut self) {
+        self.buscar_scroll.sobe();
+    }
+
+    fn buscar_select_down(&mut self) {
+        let total = self.buscar_results.len();
+        self.buscar_scroll.desce(total);
+    }
+
+    fn historico_search_up(&mut self) {
+        if self.historico_search_selected > 0 {
+            self.historico_search_selected -= 1;
+        }
+    }
+
+    fn historico_search_down(&mut self) {
+        if !self.historico_search_results.is_empty()
+            && self.historico_search_selected + 1 < self.historico_search_results.len()
+        {
+            self.historico_search_selected += 1;
+        }
+    }
+
+    fn selecionar_registro(&mut self, codigo: String) {
+        recentes::registra(&mut self.recentes, &codigo);
+        self.chosen_relogio = Some(codigo.clone());
+        self.chosen_operation = None;
+        self.mensagens.push(format!(
+            "Registro {} selecionado. Aperte A ou V para escolher operação.",
+            codigo
+        ));
+    }
+
+    fn cancelar_selecao(&mut self) {
+        self.chosen_relogio = None;
+        self.chosen_operation = None;
+        self.mensagens.push("Seleção cancelada.".into());
+    }
+
+    fn escolher_operacao(&mut self, op: char) {
+        if self.estoque_em.is_some() {
+            self.mensagens
+                .push("Estoque em data passada é somente leitura.".into());
+            return;
+        }
+        if let Some(cod) = &self.chosen_relogio {
+            recentes::registra(&mut self.recentes, cod);
+            self.chosen_operation = Some(op);
+            self.mensagens
+                .push(format!("Operação '{}' selecionada para {}", op, cod));
+            if op == 'A' {
+                self.modo = Modo::Compra;
+                self.editing = true;
+                self.input = format!("{} ", cod);
+            } else if op == 'V' {
+                self.modo = Modo::Venda;
+                self.editing = true;
+                self.input = format!("{} ", cod);
+            }
+        }
+    }
+
+    fn filtrar_historico(&mut self, codigo: &str) {
+        self.historico_ano_arquivo = None;
+        if let (_, Some(termo)) = separa_fornecedor(codigo) {
+            self.filtrar_historico_por_fornecedor(termo);
+        } else if codigo.is_empty() {
+            self.historico_filtrado = None;
+            self.mensagens
+                .push("Filtro removido. Mostrando todo o histórico.".into());
+        } else {
+            let hist = self.get_historico_por_codigo(codigo);
+            if hist.is_empty() {
+                self.mensagens
+                    .push("Nenhum histórico encontrado para esse código!".into());
+                self.historico_filtrado = None;
+            } else {
+                self.historico_filtrado = Some(hist);
+                self.mensagens
+                    .push(format!("Histórico filtrado por {} exibido", codigo));
+            }
+        }
+        self.historico_scroll.reinicia();
+    }
+
+    /// `fornecedor=nome` no filtro do histórico: as compras do fornecedor
+    /// (cadastrado, pelo começo do nome, ou só citado nas compras).
+    fn filtrar_historico_por_fornecedor(&mut self, termo: &str) {
+        let nome = fornecedores::acha(&self.registros.fornecedores, termo)
+            .map_or_else(|_| termo.to_string(), |f| f.nome.clone());
+        let hist: Vec<Historico> = fornecedores::compras(&self.historico, &nome)
+            .into_iter()
+            .cloned()
+            .collect();
+        if hist.is_empty() {
+            self.mensagens
+                .push(format!("Nenhuma compra do fornecedor {}!", nome));
+            self.historico_filtrado = None;
+        } else {
+            self.mensagens.push(format!(
+                "Histórico filtrado pelo fornecedor {} exibido",
+                nome
+            ));
+            self.historico_filtrado = Some(hist);
+        }
+    }
+
+    /// Lê "arquivo.csv [limite%]" do input e abre o mapeamento de colunas.
+    fn carregar_catalogo(&mut self) {
+        let parts: Vec<&str> = self.input.split_whitespace().collect();
+        let limite = match parts.get(1).map(|l| {
+            self.config
+                .formato_numeros
+                .interpreta(l.trim_end_matches('%'))
+        }) {
+            None => 10.0,
+            Some(Some(l)) => l,
+            Some(None) => {
+                self.mensagens.push("Limite inválido!".into());
+                return;
+            }
+        };
+        let Some(caminho) = parts.first() else {
+            self.mensagens
+                .push("Informe o arquivo CSV do catálogo.".into());
+            return;
+        };
+        match fs::read(caminho) {
+            Ok(bytes) => {
+                self.importacao_csv = Some(catalogo::ArquivoCsv {
+                    caminho: caminho.to_string(),
+                    mapeamento: catalogo::Mapeamento::detecta(&bytes, self.config.formato_numeros),
+                    bytes,
+                    limite,
+                });
+                self.mapeamento_campo = 0;
+                self.modo = Modo::MapearCsv;
+                self.input.clear();
+            }
+            Err(e) => self
+                .mensagens
+                .push(format!("Não foi possível ler {}: {}", caminho, e)),
+        }
+    }
+
+    /// Aplica o mapeamento escolhido e passa para a revisão da importação.
+    fn confirmar_mapeamento(&mut self) {
+        let Some(arquivo) = self.importacao_csv.take() else {
+            return;
+        };
+        let (mut linhas, erros) = catalogo::le_mapeado(&arquivo.bytes, &arquivo.mapeamento);
+        let mut convertidos = 0;
+        for l in &mut linhas {
+            let referencias = &self.registros.referencias_fornecedor;
+            if let Some(codigo) = fornecedores::resolve(referencias, &arquivo.caminho, &l.codigo) {
+                l.codigo = codigo.to_string();
+                convertidos += 1;
+            }
+        }
+        if convertidos > 0 {
+            self.mensagens.push(format!(
+                "{} códigos do fornecedor convertidos para os internos.",
+                convertidos
+            ));
+        }
+        for e in erros.iter().take(5) {
+            self.mensagens.push(e.clone());
+        }
+        if erros.len() > 5 {
+            self.mensagens
+                .push(format!("... mais {} linhas com erro", erros.len() - 5));
+        }
+        self.importacao = catalogo::compara(&self.relogios, linhas, arquivo.limite);
+        self.importacao_scroll.reinicia();
+        let sinalizados = self.importacao.iter().filter(|a| a.sinalizado).count();
+        self.mensagens.push(format!(
+            "{} itens lidos, {} com aumento de custo acima de {}% aguardando revisão.",
+            self.importacao.len(),
+            sinalizados,
+            arquivo.limite
+        ));
+        self.modo = Modo::ImportarCatalogo;
+        self.editing = false;
+    }
+
+    /// Teclas da etapa de mapeamento: ↑/↓ campo, ←/→ coluna, S separador,
+    /// E codificação, C cabeçalho.
+    fn mapeamento_tecla(&mut self, code: KeyCode) {
+        let Some(arquivo) = self.importacao_csv.as_mut() else {
+            return;
+        };
+        let m = &mut arquivo.mapeamento;
+        let colunas = catalogo::linhas_brutas(&arquivo.bytes, m)
+            .iter()
+            .map(|(_, c)| c.len())
+            .max()
+            .unwrap_or(0);
+        match code {
+            KeyCode::Up => self.mapeamento_campo = self.mapeamento_campo.saturating_sub(1),
+            KeyCode::Down => {
+                self.mapeamento_campo = (self.mapeamento_campo + 1).min(catalogo::CAMPOS.len() - 1)
+            }
+            KeyCode::Left => m.move_coluna(self.mapeamento_campo, -1, colunas),
+            KeyCode::Right => m.move_coluna(self.mapeamento_campo, 1, colunas),
+            KeyCode::Char('s') | KeyCode::Char('S') => m.alterna_separador(),
+            KeyCode::Char('e') | KeyCode::Char('E') => m.alterna_codificacao(),
+            KeyCode::Char('c') | KeyCode::Char('C') => m.cabecalho = !m.cabecalho,
+            _ => {}
+        }
+    }
+
+    fn importacao_select_up(&mut self) {
+        self.importacao_scroll.sobe();
+    }
+
+    fn importacao_select_down(&mut self) {
+        let total = self.importacao.len();
+        self.importacao_scroll.desce(total);
+    }
+
+    fn importacao_alterna(&mut self) {
+        if let Some(alt) = self.importacao.get_mut(self.importacao_scroll.selected) {
+            alt.aceito = !alt.aceito;
+        }
+    }
+
+    /// Relatório do que `aplicar_catalogo` faria, sem alterar o estoque.
+    fn simular_catalogo(&mut self) {
+        let mut copia = self.relogios.clone();
+        let (atualizados, criados) = catalogo::aplica(&mut copia, &self.importacao);
+        let recusados = self.importacao.iter().filter(|a| !a.aceito).count();
+        let conflitos = self.importacao.iter().filter(|a| a.sinalizado).count();
+        self.mensagens.push(format!(
+            "Simulação: {} novos, {} atualizados, {} conflitos (custo acima do limite), {} recusados. Nada foi gravado.",
+            criados, atualizados, conflitos, recusados
+        ));
+    }
+
+    fn aplicar_catalogo(&mut self) {
+        let (atualizados, criados) = catalogo::aplica(&mut self.relogios, &self.importacao);
+        let recusados = self.importacao.iter().filter(|a| !a.aceito).count();
+        self.mensagens.push(format!(
+            "Catálogo aplicado: {} atualizados, {} novos, {} recusados.",
+            atualizados, criados, recusados
+        ));
+        self.importacao.clear();
+        self.atualiza_estoques_list();
+        self.atualiza_cadastro_list();
+        self.salvar();
+    }
+
+    /// Abre (ou recarrega) a tela Admin com os tokens e o log de acessos.
+    fn abrir_admin(&mut self) {
+        self.admin_tokens = tokens::carrega();
+        self.admin_acessos = auditoria::recentes(auditoria::MAXIMO_RECENTES);
+        self.admin_scroll.reinicia();
+        self.modo = Modo::Admin;
+        self.input.clear();
+    }
+
+    /// Marca ou desmarca o produto em foco como destaque do quiosque.
+    fn alterna_destaque(&mut self) {
+        let Some(r) = self
+            .codigo_em_foco()
+            .and_then(|codigo| self.relogios.get_mut(&codigo))
+        else {
+            return;
+        };
+        r.destaque = !r.destaque;
+        self.mensagens.push(if r.destaque {
+            format!("{} em destaque no quiosque.", r.codigo)
+        } else {
+            format!("{} fora do quiosque.", r.codigo)
+        });
+        self.atualiza_estoques_list();
+        self.salvar();
+    }
+
+    /// Marca ou desmarca a linha atual do Estoque para a comparação.
+    fn alterna_comparacao(&mut self) {
+        let Some(codigo) = self
+            .estoques_list
+            .get(self.estoques_scroll.selected)
+            .map(|r| r.codigo.clone())
+        else {
+            return;
+        };
+        if let Some(i) = self.comparar.iter().position(|c| *c == codigo) {
+            self.comparar.remove(i);
+        } else {
+            self.comparar.push(codigo);
+        }
+        self.mensagens.push(format!(
+            "{} marcados p/ comparar: {} (O abre)",
+            self.comparar.len(),
+            self.comparar.join(", ")
+        ));
+    }
+
+    fn abrir_comparacao(&mut self) {
+        if self.comparar.len() < 2 {
+            self.mensagens
+                .push("Marque ao menos dois modelos com Espaço no Estoque.".into());
+            return;
+        }
+        self.modo = Modo::Comparacao;
+        self.editing = false;
+    }
+
+    /// Prende a linha atual do Estoque na próxima tecla livre (1–9), ou
+    /// solta se já estava presa.
+    fn alterna_favorito(&mut self) {
+        let Some(codigo) = self
+            .estoques_list
+            .get(self.estoques_scroll.selected)
+            .map(|r| r.codigo.clone())
+        else {
+            return;
+        };
+        if let Some(i) = self.registros.favoritos.iter().position(|c| *c == codigo) {
+            self.registros.favoritos.remove(i);
+            self.mensagens
+                .push(format!("{} removido dos favoritos.", codigo));
+        } else if self.registros.favoritos.len() >= MAXIMO_FAVORITOS {
+            self.mensagens.push(format!(
+                "Já há {} favoritos; solte um com P antes.",
+                MAXIMO_FAVORITOS
+            ));
+            return;
+        } else {
+            self.registros.favoritos.push(codigo.clone());
+            self.mensagens.push(format!(
+                "{} preso na tecla {}.",
+                codigo,
+                self.registros.favoritos.len()
+            ));
+        }
+        self.salvar();
+    }
+
+    /// Corrige o saldo de `codigo` para `nova` unidades, lançando a diferença
+    /// como AJUSTE. Não grava; devolve a diferença.
+    fn ajusta_estoque(&mut self, codigo: &str, nova: i32, motivo: &str) -> Result<i32, String> {
+        let diferenca = ProdutoId::novo(codigo)
+            .and_then(|c| {
+                Inventario::new(&mut self.relogios, &mut self.historico).ajusta(
+                    &c,
+                    Quantidade::nova(nova)?,
+                    motivo,
+                    Local::now().naive_local(),
+                )
+            })
+            .map_err(|e| e.to_string())?;
+        self.mensagens.push(format!(
+            "Ajuste de {}: {:+} ({}), agora {} unidades.",
+            codigo, diferenca, motivo, nova
+        ));
+        self.atualiza_estoques_list();
+        self.atualiza_cadastro_list();
+        Ok(diferenca)
+    }
+
+    /// `+`/`-` no Estoque: soma ou tira uma unidade da linha selecionada.
+    fn ajuste_rapido(&mut self, delta: i32) {
+        if self.estoque_em.is_some() {
+            self.mensagens
+                .push("Estoque em data passada é somente leitura.".into());
+            return;
+        }
+        let Some(r) = self.estoques_list.get(self.estoques_scroll.selected) else {
+            return;
+        };
+        let (codigo, nova) = (r.codigo.clone(), r.quantidade() + delta);
+        match self.ajusta_estoque(&codigo, nova, MOTIVO_AJUSTE_RAPIDO) {
+            Ok(_) => self.salvar(),
+            Err(e) => self.mensagens.push(e),
+        }
+    }
+
+    /// `=` no Estoque: pede a contagem física da linha selecionada.
+    fn abrir_contagem(&mut self) {
+        if self.estoque_em.is_some() {
+            self.mensagens
+                .push("Estoque em data passada é somente leitura.".into());
+            return;
+        }
+        let Some(r) = self.estoques_list.get(self.estoques_scroll.selected) else {
+            return;
+        };
+        self.contagem_codigo = Some(r.codigo.clone());
+        self.entra_modo_insercao(Modo::Contagem);
+    }
+
+    /// Enter na contagem: `quantidade [motivo]`.
+    fn confirmar_contagem(&mut self) {
+        let Some(codigo) = self.contagem_codigo.take() else {
+            return;
+        };
+        let texto = self.input.trim().to_string();
+        let (qtd, motivo) = texto.split_once(' ').unwrap_or((&texto, ""));
+        let Some(nova) = self.config.formato_numeros.interpreta_inteiro(qtd) else {
+            self.mensagens
+                .push(format!("Quantidade inválida: '{}'.", qtd));
+            return;
+        };
+        let motivo = match motivo.trim() {
+            "" => MOTIVO_CONTAGEM,
+            m => m,
+        };
+        match self.ajusta_estoque(&codigo, nova, motivo) {
+            Ok(_) => self.salvar(),
+            Err(e) => self.mensagens.push(e),
+        }
+    }
+
+    /// `D` no Estoque: abre a confirmação de exclusão da linha selecionada.
+    fn pedir_exclusao(&mut self) {
+        if self.estoque_em.is_some() {
+            self.mensagens
+                .push("Estoque em data passada é somente leitura.".into());
+            return;
+        }
+        let Some(r) = self.estoques_list.get(self.estoques_scroll.selected) else {
+            return;
+        };
+        let plano = expurgo::do_codigo(&r.codigo, &self.relogios, &self.historico);
+        if let Some((codigo, kit)) = plano.em_kits.first() {
+            self.mensagens.push(format!(
+                "{} é componente do kit {}: tire-o do kit antes de excluir.",
+                codigo, kit
+            ));
+            return;
+        }
+        self.exclusao = Some(r.codigo.clone());
+    }
+
+    /// `S` no popup: exclui o código e o histórico dele. Não grava.
+    fn excluir(&mut self, codigo: &str) -> usize {
+        let plano = expurgo::do_codigo(codigo, &self.relogios, &self.historico);
+        expurgo::aplica(
+            &plano,
+            &mut self.relogios,
+            &mut self.historico,
+            &mut self.registros,
+            Local::now().naive_local(),
+        );
+        self.comparar.retain(|c| c != codigo);
+        self.historico_codigos_unicos.retain(|c| c != codigo);
+        if self.chosen_relogio.as_deref() == Some(codigo) {
+            self.cancelar_selecao();
+        }
+        self.mensagens.push(format!(
+            "{} excluído com {} entradas do histórico.",
+            codigo,
+            plano.entradas()
+        ));
+        self.atualiza_estoques_list();
+        self.atualiza_cadastro_list();
+        plano.entradas()
+    }
+
+    fn confirmar_exclusao(&mut self) {
+        if let Some(codigo) = self.exclusao.take() {
+            self.excluir(&codigo);
+            self.salvar();
+        }
+    }
+
+    /// `?`: abre a ajuda já na seção da tela atual.
+    fn abrir_ajuda(&mut self) {
+        let tela = match self.modo {
+            Modo::Cadastro => "Cadastro (C)",
+            Modo::Compra => "Compra (A)",
+            Modo::Venda => "Venda (V)",
+            Modo::Buscar => "Busca (B)",
+            Modo::Historico => "Histórico (H)",
+            Modo::Grafico => "Gráfico (G)",
+            Modo::Contagem | Modo::Balanco => "Contagem (=)",
+            Modo::ImportarCatalogo | Modo::MapearCsv => "Importar catálogo (I)",
+            Modo::EstoqueEm => "Estoque em data passada (T)",
+            Modo::Recado => "Recado (E)",
+            Modo::Renomear => "Renomear (F2)",
+            Modo::Notificacoes => "Notificações (N)",
+            Modo::Comparacao => "Comparação (Espaço/O)",
+            Modo::Recentes => "Usados recentemente (R)",
+            Modo::Clientes => "Clientes (@)",
+            Modo::Fornecedores => "Fornecedores (U)",
+            Modo::Recebimento => "Receber mercadoria (J)",
+            Modo::Pedidos => "Pedidos pendentes (Y)",
+            Modo::Orcamentos => "Orçamentos (Z)",
+            Modo::Console => "Console (:)",
+            Modo::ResgateCredito | Modo::ListaEspera | Modo::EmitirNota => {
+                "Venda no crédito, lista de espera e nota"
+            }
+            Modo::Configuracoes => "Configurações (S)",
+            Modo::Estoques | Modo::Admin | Modo::Quiosque | Modo::EdicaoExterna => {
+                "Estoque (tela inicial)"
+            }
+        };
+        // Cada seção ocupa o título, uma linha por tecla e uma em branco
+        let linha: usize = ajuda::SECOES
+            .iter()
+            .take_while(|s| s.tela != tela)
+            .map(|s| s.teclas.len() + 2)
+            .sum();
+        self.ajuda = Some(linha as u16);
+    }
+
+    /// Com a ajuda aberta, `↑/↓`/`PgUp/PgDn` rolam e qualquer outra tecla fecha.
+    fn ajuda_tecla(&mut self, code: KeyCode) {
+        let Some(topo) = self.ajuda else {
+            return;
+        };
+        let ultima = ajuda_linhas().saturating_sub(1) as u16;
+        self.ajuda = match code {
+            KeyCode::Up => Some(topo.saturating_sub(1)),
+            KeyCode::Down => Some((topo + 1).min(ultima)),
+            KeyCode::PageUp => Some(topo.saturating_sub(10)),
+            KeyCode::PageDown => Some((topo + 10).min(ultima)),
+            KeyCode::Home => Some(0),
+            _ => None,
+        };
+    }
+
+    /// `F2` no Estoque: pede o novo código da linha selecionada.
+    fn abrir_renomear(&mut self) {
+        if self.estoque_em.is_some() {
+            self.mensagens
+                .push("Estoque em data passada é somente leitura.".into());
+            return;
+        }
+        let Some(r) = self.estoques_list.get(self.estoques_scroll.selected) else {
+            return;
+        };
+        let codigo = r.codigo.clone();
+        self.entra_modo_insercao(Modo::Renomear);
+        self.input = codigo.clone();
+        self.renomear_codigo = Some(codigo);
+    }
+
+    /// Troca `de` por `para` no cadastro, no histórico, nos kits e nos
+    /// registros que apontam para o código (ver `Registros::renomeia`),
+    /// inclusive os recentes. Não grava; devolve quantas entradas do
+    /// histórico mudaram.
+    fn renomear(&mut self, de: &str, para: &str) -> Result<usize, String> {
+        let n = ProdutoId::novo(de)
+            .and_then(|de| {
+                Inventario::new(&mut self.relogios, &mut self.historico)
+                    .renomeia(&de, &ProdutoId::novo(para)?)
+            })
+            .map_err(|e| e.to_string())?;
+        let para = para.trim();
+        self.registros.renomeia(de, para);
+        let troca = |c: &mut String| {
+            if c == de {
+                *c = para.to_string();
+            }
+        };
+        self.comparar.iter_mut().for_each(troca);
+        self.recentes.iter_mut().for_each(troca);
+        self.historico_codigos_unicos.iter_mut().for_each(troca);
+        if let Some(c) = self.chosen_relogio.as_mut() {
+            troca(c);
+        }
+        self.mensagens.push(format!(
+            "{} agora é {} ({} entradas do histórico atualizadas).",
+            de, para, n
+        ));
+        self.atualiza_estoques_list();
+        self.atualiza_cadastro_list();
+        Ok(n)
+    }
+
+    /// Enter na renomeação. O kardex é refeito porque o histórico mudou sem
+    /// crescer.
+    fn confirmar_renomear(&mut self) {
+        let Some(de) = self.renomear_codigo.take() else {
+            return;
+        };
+        let para = self.input.trim().to_string();
+        if para == de {
+            return;
+        }
+        match self.renomear(&de, &para) {
+            Ok(_) => {
+                self.salvar();
+                recentes::salva(&self.recentes);
+                kardex::Kardex::reconstroi(&self.historico).salva();
+            }
+            Err(e) => self.mensagens.push(e),
+        }
+    }
+
+    /// Enter na contagem com o caminho de um .csv: abre a conferência do
+    /// arquivo do app de contagem.
+    fn abrir_balanco(&mut self) {
+        let arquivo = self.input.trim().to_string();
+        self.contagem_codigo = None;
+        self.input.clear();
+        let lido = fs::read(&arquivo)
+            .map_err(|e| format!("Falha ao ler {}: {}", arquivo, e))
+            .and_then(|bytes| balanco::le(&bytes, &self.relogios, self.config.formato_numeros));
+        match lido {
+            Ok(contagem) => {
+                self.balanco = Some(balanco::Balanco {
+                    arquivo,
+                    contagem,
+                    completo: false,
+                });
+                self.balanco_scroll.reinicia();
+                self.modo = Modo::Balanco;
+                self.editing = true;
+            }
+            Err(e) => {
+                self.mensagens.push(e);
+                self.modo = Modo::Estoques;
+                self.editing = false;
+            }
+        }
+    }
+
+    fn edicao_externa_tecla(&mut self, code: KeyCode) {
+        let total = self.edicao_externa.as_ref().map_or(0, |e| e.resumo.len());
+        match code {
+            KeyCode::Up => self.edicao_externa_scroll.sobe(),
+            KeyCode::Down => self.edicao_externa_scroll.desce(total),
+            KeyCode::Enter => {
+                self.edicao_externa = None;
+                self.modo = Modo::Estoques;
+                self.editing = false;
+                self.mensagens
+                    .push("Alterações feitas fora do programa aceitas.".into());
+                self.salvar();
+                self.abertura();
+            }
+            KeyCode::Char('d') | KeyCode::Char('D') => self.desfazer_edicao_externa(),
+            _ => {}
+        }
+    }
+
+    /// Volta aos dados da última gravação do programa; o arquivo editado
+    /// fica em `estoque.json.externo`.
+    fn desfazer_edicao_externa(&mut self) {
+        if !self
+            .edicao_externa
+            .as_ref()
+            .is_some_and(EdicaoExterna::tem_anterior)
+        {
+            self.mensagens
+                .push("Sem a cópia da última gravação não há como desfazer; Enter aceita.".into());
+            return;
+        }
+        let copia = match persistencia::guarda_edicao_externa() {
+            Ok(copia) => copia,
+            Err(e) => {
+                self.mensagens
+                    .push(format!("Falha ao guardar o arquivo editado: {}", e));
+                return;
+            }
+        };
+        let Some((relogios, historico, registros)) =
+            self.edicao_externa.take().and_then(EdicaoExterna::anterior)
+        else {
+            return;
+        };
+        self.relogios = relogios;
+        self.historico = historico;
+        self.registros = registros;
+        let codigos: HashSet<String> = self.historico.iter().map(|h| h.codigo.clone()).collect();
+        self.historico_codigos_unicos = codigos.into_iter().collect();
+        self.historico_codigos_unicos.sort();
+        self.atualiza_estoques_list();
+        self.atualiza_cadastro_list();
+        self.modo = Modo::Estoques;
+        self.editing = false;
+        self.mensagens.push(format!(
+            "Alterações desfeitas; o arquivo editado ficou em {}.",
+            copia.display()
+        ));
+        self.salvar();
+        self.abertura();
+    }
+
+    fn balanco_tecla(&mut self, code: KeyCode) {
+        let Some(b) = self.balanco.as_mut() else {
+            return;
+        };
+        match code {
+            KeyCode::Char('c') | KeyCode::Char('C') => {
+                b.completo = !b.completo;
+                self.balanco_scroll.reinicia();
+            }
+            KeyCode::Up => self.balanco_scroll.sobe(),
+            KeyCode::Down => {
+                let total = balanco::concilia(&b.contagem, &self.relogios, b.completo)
+                    .divergentes()
+                    .count();
+                self.balanco_scroll.desce(total);
+            }
+            KeyCode::Enter => self.confirmar_balanco(),
+            KeyCode::Esc => {
+                self.balanco = None;
+                self.modo = Modo::Estoques;
+                self.editing = false;
+                self.mensagens
+                    .push("Balanço cancelado; nada foi ajustado.".into());
+            }
+            _ => {}
+        }
+    }
+
+    /// Lança os AJUSTEs do balanço, grava o relatório de divergências ao
+    /// lado do arquivo de contagem e salva.
+    fn confirmar_balanco(&mut self) {
+        let Some(b) = self.balanco.take() else {
+            return;
+        };
+        let conciliacao = balanco::concilia(&b.contagem, &self.relogios, b.completo);
+        let relatorio = balanco::relatorio_csv(&conciliacao, self.config.formato_numeros);
+        let agora = Local::now();
+        match balanco::ajusta(
+            &conciliacao,
+            &mut self.relogios,
+            &mut self.historico,
+            agora.naive_local(),
+        ) {
+            Ok(n) => {
+                let destino = std::path::Path::new(&b.arquivo).with_file_name(format!(
+                    "divergencias_{}.csv",
+                    agora.format("%Y-%m-%d_%H%M")
+                ));
+                let gravado = match fs::write(&destino, relatorio) {
+                    Ok(()) => format!("relatório em {}", destino.display()),
+                    Err(e) => format!("falha ao gravar o relatório: {}", e),
+                };
+                self.mensagens
+                    .push(format!("Balanço: {} ajustes lançados; {}.", n, gravado));
+                self.modo = Modo::Estoques;
+                self.editing = false;
+                self.atualiza_estoques_list();
+                self.atualiza_cadastro_list();
+                self.salvar();
+            }
+            Err(e) => {
+                self.mensagens.push(e.to_string());
+                self.balanco = Some(b);
+            }
+        }
+    }
+
+    /// Abre a Venda já preenchida com o favorito da tecla `n` (1–9).
+    fn vender_favorito(&mut self, n: usize) {
+        let Some(codigo) = self.registros.favoritos.get(n - 1).cloned() else {
+            self.mensagens
+                .push(format!("Nenhum favorito na tecla {} (P prende).", n));
+            return;
+        };
+        self.chosen_relogio = Some(codigo);
+        self.escolher_operacao('V');
+    }
+
+    fn abrir_recentes(&mut self) {
+        if self.recentes.is_empty() {
+            self.mensagens
+                .push("Nenhum produto usado recentemente.".into());
+            return;
+        }
+        self.modo = Modo::Recentes;
+        self.editing = false;
+        self.recentes_scroll.reinicia();
+    }
+
+    fn abrir_fornecedores(&mut self) {
+        self.modo = Modo::Fornecedores;
+        self.editing = false;
+        self.input.clear();
+        self.fornecedores_scroll.reinicia();
+    }
+
+    /// Inclui a referência digitada como `fornecedor codigo_fornecedor codigo`
+    /// (o fornecedor pode ter espaços). Não grava.
+    fn adicionar_referencia(&mut self) -> Result<String, String> {
+        let mut partes = self.input.rsplitn(3, char::is_whitespace);
+        let (Some(codigo), Some(codigo_fornecedor), Some(fornecedor)) =
+            (partes.next(), partes.next(), partes.next().map(str::trim))
+        else {
+            return Err("Formato: fornecedor codigo_do_fornecedor codigo_interno".into());
+        };
+        if !self.relogios.contains_key(codigo) {
+            return Err(format!("Relógio {} não encontrado!", codigo));
+        }
+        fornecedores::define(
+            &mut self.registros.referencias_fornecedor,
+            fornecedores::Referencia {
+                fornecedor: fornecedor.to_string(),
+                codigo_fornecedor: codigo_fornecedor.to_string(),
+                codigo: codigo.to_string(),
+            },
+        );
+        Ok(format!(
+            "{} de {} agora é {}.",
+            codigo_fornecedor, fornecedor, codigo
+        ))
+    }
+
+    fn remover_referencia(&mut self) {
+        let i = self.fornecedores_scroll.selected;
+        if i < self.registros.referencias_fornecedor.len() {
+            let r = self.registros.referencias_fornecedor.remove(i);
+            self.salvar();
+            self.mensagens.push(format!(
+                "Referência {} de {} removida.",
+                r.codigo_fornecedor, r.fornecedor
+            ));
+            self.fornecedores_scroll
+                .limita(self.registros.referencias_fornecedor.len());
+        }
+    }
+
+    /// Inclui ou atualiza o fornecedor digitado como `nome;contato;observações`.
+    /// Não grava.
+    fn adicionar_fornecedor(&mut self) -> Result<String, String> {
+        let fornecedor = fornecedores::interpreta(&self.input)?;
+        let nome = fornecedor.nome.clone();
+        Ok(
+            if fornecedores::cadastra(&mut self.registros.fornecedores, fornecedor) {
+                format!("Fornecedor {} cadastrado.", nome)
+            } else {
+                format!("Fornecedor {} atualizado.", nome)
+            },
+        )
+    }
+
+    fn remover_fornecedor(&mut self) {
+        let i = self.fornecedores_scroll.selected;
+        if i < self.registros.fornecedores.len() {
+            let f = self.registros.fornecedores.remove(i);
+            self.salvar();
+            self.mensagens.push(format!(
+                "Fornecedor {} removido; as compras continuam com o nome.",
+                f.nome
+            ));
+            self.fornecedores_scroll
+                .limita(self.registros.fornecedores.len());
+        }
+    }
+
+    fn abrir_clientes(&mut self) {
+        self.modo = Modo::Clientes;
+        self.editing = false;
+        self.input.clear();
+        self.clientes_scroll.reinicia();
+    }
+
+    /// Inclui ou atualiza o cliente digitado como `nome;telefone;observações`.
+    /// Não grava.
+    fn adicionar_cliente(&mut self) -> Result<String, String> {
+        let cliente = clientes::interpreta(&self.input)?;
+        let nome = cliente.nome.clone();
+        Ok(if clientes::define(&mut self.registros.clientes, cliente) {
+            format!("Cliente {} cadastrado.", nome)
+        } else {
+            format!("Cliente {} atualizado.", nome)
+        })
+    }
+
+    fn remover_cliente(&mut self) {
+        let i = self.clientes_scroll.selected;
+        if i < self.registros.clientes.len() {
+            let c = self.registros.clientes.remove(i);
+            self.salvar();
+            self.mensagens.push(format!(
+                "Cliente {} removido; as vendas continuam com o nome.",
+                c.nome
+            ));
+            self.clientes_scroll.limita(self.registros.clientes.len());
+        }
+    }
+
+    /// Enter no modo Clientes: passa a atender o cliente selecionado (as
+    /// próximas vendas ficam no nome dele) ou, se já era ele, encerra.
+    fn alterna_cliente_atendido(&mut self) {
+        let Some(c) = self.registros.clientes.get(self.clientes_scroll.selected) else {
+            return;
+        };
+        if self.cliente_atendido.as_deref() == Some(c.nome.as_str()) {
+            self.cliente_atendido = None;
+            self.mensagens
+                .push(format!("Atendimento de {} encerrado.", c.nome));
+        } else {
+            self.cliente_atendido = Some(c.nome.clone());
+            self.mensagens.push(format!(
+                "Atendendo {}: as próximas vendas ficam no nome do cliente (Enter de novo encerra).",
+                c.nome
+            ));
+            self.modo = Modo::Estoques;
+        }
+    }
+
+    /// Executa a consulta digitada no console; com `> arquivo.csv` no fim,
+    /// também exporta o resultado.
+    fn executar_consulta(&mut self) {
+        let (texto, destino) = match self.input.rsplit_once(" > ") {
+            Some((t, d)) => (t.to_string(), Some(d.trim().to_string())),
+            None => (self.input.clone(), None),
+        };
+        match consulta::executa(&texto, &self.historico, &self.relogios) {
+            Ok(tabela) => {
+                match destino {
+                    Some(d) => match fs::write(&d, tabela.csv()) {
+                        Ok(()) => self.mensagens.push(format!(
+                            "{} linhas exportadas para {}.",
+                            tabela.linhas.len(),
+                            d
+                        )),
+                        Err(e) => self
+                            .mensagens
+                            .push(format!("Não foi possível gravar {}: {}", d, e)),
+                    },
+                    None => self
+                        .mensagens
+                        .push(format!("{} linhas.", tabela.linhas.len())),
+                }
+                self.console_resultado = Some(tabela);
+                self.console_scroll.reinicia();
+            }
+            Err(e) => self.mensagens.push(e),
+        }
+    }
+
+    fn abrir_quiosque(&mut self) {
+        self.modo = Modo::Quiosque;
+        self.editing = false;
+        self.input.clear();
+        self.quiosque_pagina = 0;
+        self.quiosque_troca = Instant::now();
+    }
+
+    /// Telas do quiosque (rótulo, valor): vendas de hoje e cada destaque.
+    fn paginas_quiosque(&self) -> Vec<(String, String)> {
+        let numeros = self.config.formato_numeros;
+        let hoje = Local::now().format("%Y-%m-%d").to_string();
+        let vendas: Vec<&Historico> = self
+            .historico
+            .iter()
+            .filter(|h| h.operacao == "VENDA" && h.timestamp.starts_with(&hoje))
+            .collect();
+        let total: f64 = vendas
+            .iter()
+            .filter_map(|h| contabil::valor(h, self.relogios.get(&h.codigo)))
+            .sum();
+        let unidades: i32 = vendas.iter().map(|h| h.quantidade).sum();
+        let mut paginas = vec![(
+            format!(
+                "Vendas de hoje (R$) — {} unidades",
+                numeros.inteiro(unidades as i64)
+            ),
+            numeros.numero(total, 2),
+        )];
+        paginas.extend(self.estoques_list.iter().filter(|r| r.destaque).map(|r| {
+            (
+                format!("{} — {} (em estoque)", r.codigo, r.descricao),
+                numeros.inteiro(self.quantidade_disponivel(r) as i64),
+            )
+        }));
+        paginas
+    }
+
+    /// Avança o quiosque e relê os dados do disco, para refletir vendas
+    /// feitas em outro terminal ou pela API.
+    fn verifica_quiosque(&mut self) {
+        let intervalo = Duration::from_secs(self.config.quiosque_intervalo_seg.max(1));
+        if self.modo != Modo::Quiosque || self.quiosque_troca.elapsed() < intervalo {
+            return;
+        }
+        let (relogios, historico, registros) = load_from_file();
+        self.relogios = relogios;
+        self.historico = historico;
+        self.registros = registros;
+        self.atualiza_estoques_list();
+        self.quiosque_pagina += 1;
+        self.quiosque_troca = Instant::now();
+    }
+
+    /// Sem teclas por `demonstracao_seg` segundos, o monitor do balcão passa
+    /// a alternar os últimos dias, os mais vendidos e o padrão por dia/hora
+    /// no mesmo intervalo.
+    fn verifica_demonstracao(&mut self) {
+        let intervalo = Duration::from_secs(self.config.demonstracao_seg);
+        if intervalo.is_zero() {
+            return;
+        }
+        match &mut self.demonstracao {
+            Some(d) if d.troca.elapsed() >= intervalo => {
+                d.pagina += 1;
+                d.troca = Instant::now();
+                self.grafico_tab = PAGINAS_DEMONSTRACAO[d.pagina % PAGINAS_DEMONSTRACAO.len()];
+            }
+            Some(_) => {}
+            None if !self.editing
+                && self.modo != Modo::Quiosque
+                && self.ultima_tecla.elapsed() >= intervalo =>
+            {
+                self.demonstracao = Some(Demonstracao {
+                    pagina: 0,
+                    troca: Instant::now(),
+                    modo: self.modo.clone(),
+                    tab: self.grafico_tab,
+                });
+                self.modo = Modo::Grafico;
+                self.grafico_tab = PAGINAS_DEMONSTRACAO[0];
+            }
+            None => {}
+        }
+    }
+
+    /// Volta à tela em que o operador estava.
+    fn encerra_demonstracao(&mut self) {
+        if let Some(d) = self.demonstracao.take() {
+            self.modo = d.modo;
+            self.grafico_tab = d.tab;
+        }
+    }
+
+    /// Código sob o cursor: o registro selecionado ou a linha atual do Estoque.
+    fn codigo_em_foco(&self) -> Option<String> {
+        self.chosen_relogio.clone().or_else(|| match self.modo {
+            Modo::Estoques => self
+                .estoques_list
+                .get(self.estoques_scroll.selected)
+                .map(|r| r.codigo.clone()),
+            Modo::Buscar => self
+                .buscar_results
+                .get(self.buscar_scroll.selected)
+                .map(|r| r.0.clone()),
+            _ => None,
+        })
+    }
+
+    /// Envia etiquetas para a impressora configurada e marca os produtos.
+    fn imprimir_etiquetas(&mut self, codigos: Vec<String>) {
+        let Some(endereco) = self.config.impressora_etiquetas.clone() else {
+            self.mensagens
+                .push("Configure impressora_etiquetas em config.toml para imprimir.".into());
+            return;
+        };
+        let payload: String = codigos
+            .iter()
+            .filter_map(|c| self.relogios.get(c))
+            .map(|r| etiquetas::gera(r, self.config.formato_etiqueta, self.config.formato_numeros))
+            .collect();
+        if payload.is_empty() {
+            self.mensagens
+                .push("Nenhuma etiqueta para imprimir.".into());
+            return;
+        }
+        match etiquetas::envia(&endereco, &payload) {
+            Ok(()) => {
+                for c in &codigos {
+                    if let Some(r) = self.relogios.get_mut(c) {
+                        r.etiqueta_impressa = true;
+                    }
+                }
+                self.mensagens.push(format!(
+                    "{} etiqueta(s) enviada(s) para {}",
+                    codigos.len(),
+                    endereco
+                ));
+                self.salvar();
+            }
+            Err(e) => self
+                .mensagens
+                .push(format!("Falha ao imprimir em {}: {}", endereco, e)),
+        }
+    }
+
+    fn imprimir_etiqueta_selecionada(&mut self) {
+        match self.codigo_em_foco() {
+            Some(cod) => self.imprimir_etiquetas(vec![cod]),
+            None => self.mensagens.push("Nenhum relógio selecionado.".into()),
+        }
+    }
+
+    fn imprimir_etiquetas_pendentes(&mut self) {
+        let mut pendentes: Vec<String> = self
+            .relogios
+            .values()
+            .filter(|r| !r.etiqueta_impressa)
+            .map(|r| r.codigo.clone())
+            .collect();
+        pendentes.sort();
+        self.imprimir_etiquetas(pendentes);
+    }
+
+    fn config_select_up(&mut self) {
+        self.config_selected = self.config_selected.saturating_sub(1);
+    }
+
+    fn config_select_down(&mut self) {
+        if self.config_selected + 1 < config::CHAVES.len() {
+            self.config_selected += 1;
+        }
+    }
+
+    fn editar_config(&mut self) {
+        let chave = config::CHAVES[self.config_selected];
+        // Segredos começam em branco, para não aparecerem nem na edição
+        self.input = if config::secreta(chave) {
+            String::new()
+        } else {
+            self.config.valor(chave)
+        };
+        self.confere_senha_atual = chave == "senha_admin" && self.config.senha_admin.is_some();
+        self.editing = true;
+    }
+
+    fn salvar_config(&mut self) {
+        let chave = config::CHAVES[self.config_selected];
+        if self.confere_senha_atual {
+            if self.senha_confere(Some(&self.input)) {
+                self.confere_senha_atual = false;
+                self.input.clear();
+                return;
+            }
+            self.mensagens
+                .push("Senha de administrador incorreta!".into());
+            self.input.clear();
+            self.confere_senha_atual = false;
+            self.editing = false;
+            return;
+        }
+        // O segredo não vem na edição: Enter sem digitar nada o mantém
+        if config::secreta(chave) && self.input.is_empty() {
+            self.mensagens
+                .push(format!("Configuração {} mantida.", chave));
+            self.editing = false;
+            return;
+        }
+        let resultado = self
+            .config
+            .define(chave, &self.input)
+            .and_then(|_| self.config.salvar());
+        match resultado {
+            Ok(()) => self
+                .mensagens
+                .push(format!("Configuração {} salva.", chave)),
+            Err(e) => self.mensagens.push(e),
+        }
+        self.cores = tema::Cores::da_config(&self.config).0;
+        self.input.clear();
+        self.editing = false;
+    }
+
+    fn abrir_detalhe_historico(&mut self) {
+        let data = self.get_historico_atual_vec();
+        self.historico_detalhe = data.get(self.historico_scroll.selected).cloned();
+    }
+
+    /// Posição da entrada em `self.historico` (as listas exibidas são cópias).
+    fn indice_historico(&self, h: &Historico) -> Option<usize> {
+        self.historico.iter().position(|x| {
+            x.timestamp == h.timestamp
+                && x.codigo == h.codigo
+                && x.operacao == h.operacao
+                && x.quantidade == h.quantidade
+        })
+    }
+
+    /// Espaço: marca ou desmarca a entrada selecionada.
+    fn alterna_marca_historico(&mut self) {
+        let data = self.get_historico_atual_vec();
+        let Some(i) = data
+            .get(self.historico_scroll.selected)
+            .and_then(|h| self.indice_historico(h))
+        else {
+            return;
+        };
+        match self.historico_marcados.iter().position(|&m| m == i) {
+            Some(p) => {
+                self.historico_marcados.remove(p);
+            }
+            None => self.historico_marcados.push(i),
+        }
+    }
+
+    /// `*`: marca todas as entradas visíveis (aba e filtros atuais), ou
+    /// desmarca se já estavam todas marcadas.
+    fn marca_todos_historico(&mut self) {
+        let visiveis: Vec<usize> = self
+            .get_historico_atual_vec()
+            .iter()
+            .filter_map(|h| self.indice_historico(h))
+            .collect();
+        if visiveis.iter().all(|i| self.historico_marcados.contains(i)) {
+            self.historico_marcados.retain(|i| !visiveis.contains(i));
+        } else {
+            for i in visiveis {
+                if !self.historico_marcados.contains(&i) {
+                    self.historico_marcados.push(i);
+                }
+            }
+        }
+    }
+
+    /// Aplica a etiqueta nas entradas marcadas (ou só na selecionada);
+    /// `-etiqueta` a remove. Devolve quantas entradas foram alteradas.
+    fn etiquetar_historico(&mut self, texto: &str) -> usize {
+        let (remover, tag) = match texto.trim().strip_prefix('-') {
+            Some(t) => (true, t.trim()),
+            None => (false, texto.trim()),
+        };
+        if tag.is_empty() {
+            return 0;
+        }
+        let alvos = if self.historico_marcados.is_empty() {
+            let data = self.get_historico_atual_vec();
+            data.get(self.historico_scroll.selected)
+                .and_then(|h| self.indice_historico(h))
+                .into_iter()
+                .collect()
+        } else {
+            std::mem::take(&mut self.historico_marcados)
+        };
+        let aplica = |h: &mut Historico| {
+            if remover {
+                let antes = h.tags.len();
+                h.tags.retain(|t| t.to_lowercase() != tag.to_lowercase());
+                h.tags.len() != antes
+            } else if h.tem_tag(tag) {
+                false
+            } else {
+                h.tags.push(tag.to_string());
+                true
+            }
+        };
+        let mut alterados = 0;
+        for &i in &alvos {
+            if let Some(h) = self.historico.get_mut(i) {
+                if aplica(h) {
+                    alterados += 1;
+                }
+            }
+        }
+        // A lista filtrada por código é uma cópia: atualiza junto
+        if self.historico_ano_arquivo.is_none() {
+            if let Some(mut filtrado) = self.historico_filtrado.take() {
+                for h in filtrado.iter_mut() {
+                    if self.indice_historico(h).is_some_and(|i| alvos.contains(&i)) {
+                        aplica(h);
+                    }
+                }
+                self.historico_filtrado = Some(filtrado);
+            }
+        }
+        alterados
+    }
+
+    /// Enter no campo de etiqueta ou de filtro por etiqueta.
+    fn confirma_entrada_historico(&mut self) {
+        let texto = self.input.trim().to_string();
+        match self.historico_entrada {
+            EntradaHistorico::Etiqueta if self.historico_ano_arquivo.is_some() => {
+                self.mensagens
+                    .push("Anos arquivados são somente leitura.".into());
+            }
+            EntradaHistorico::Etiqueta => {
+                let n = self.etiquetar_historico(&texto);
+                if n > 0 {
+                    self.salvar();
+                }
+                self.mensagens.push(format!(
+                    "Etiqueta \"{}\": {} entrada(s) alterada(s).",
+                    texto, n
+                ));
+            }
+            EntradaHistorico::FiltroEtiqueta => {
+                self.historico_etiqueta = (!texto.is_empty()).then_some(texto);
+                self.historico_scroll.reinicia();
+            }
+            EntradaHistorico::Pesquisa => {}
+        }
+        self.historico_entrada = EntradaHistorico::Pesquisa;
+        self.input.clear();
+        self.editing = false;
+    }
+
+    /// `N` no detalhe de uma VENDA: reimprime a nota ou pede os dados do cliente.
+    fn nota_da_venda(&mut self) {
+        let Some(h) = self.historico_detalhe.clone() else {
+            return;
+        };
+        if h.operacao != "VENDA" {
+            self.mensagens
+                .push("Notas só podem ser emitidas para vendas.".into());
+            return;
+        }
+        let existente = h
+            .nota
+            .and_then(|n| self.registros.notas.iter().find(|nf| nf.numero == n));
+        match existente {
+            Some(nf) => match nf.gravar_pdf(self.config.formato_numeros) {
+                Ok(caminho) => self
+                    .mensagens
+                    .push(format!("Nota reimpressa em {}", caminho)),
+                Err(e) => self
+                    .mensagens
+                    .push(format!("Falha ao gravar a nota: {}", e)),
+            },
+            None => {
+                self.modo = Modo::EmitirNota;
+                self.editing = true;
+                self.input.clear();
+            }
+        }
+    }
+
+    /// Emite a nota da venda aberta no detalhe a partir de "nome;CPF/CNPJ".
+    fn emitir_nota(&mut self) {
+        let Some(h) = self.historico_detalhe.clone() else {
+            return;
+        };
+        let mut campos = self.input.split(';');
+        let (nome, doc, aniversario) = (
+            campos.next().unwrap_or_default(),
+            campos.next().unwrap_or_default(),
+            campos.next().unwrap_or_default().trim(),
+        );
+        let aniversario = match aniversario {
+            "" => None,
+            texto => match fidelidade::interpreta_aniversario(texto) {
+                Some(dia) if !nome.trim().is_empty() => Some(dia),
+                _ => {
+                    self.mensagens
+                        .push("Aniversário inválido (use DD/MM, com o nome)!".into());
+                    return;
+                }
+            },
+        };
+        let documento = if doc.trim().is_empty() {
+            String::new()
+        } else {
+            match notas::valida_documento(doc) {
+                Some(d) => d,
+                None => {
+                    self.mensagens.push("CPF/CNPJ inválido!".into());
+                    return;
+                }
+            }
+        };
+        let produto = self.relogios.get(&h.codigo);
+        let nota = notas::NotaFiscal {
+            numero: self
+                .registros
+                .notas
+                .iter()
+                .map(|n| n.numero)
+                .max()
+                .unwrap_or(0)
+                + 1,
+            timestamp: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            cliente_nome: nome.trim().to_string(),
+            cliente_documento: documento,
+            itens: vec![notas::ItemNota {
+                codigo: h.codigo.clone(),
+                descricao: produto.map(|r| r.descricao.clone()).unwrap_or_default(),
+                quantidade: h.quantidade,
+                preco_unitario: h.preco_tabela(produto) * h.fator_preco(),
+            }],
+            aliquota: self.config.aliquota_imposto,
+        };
+        match nota.gravar_pdf(self.config.formato_numeros) {
+            Ok(caminho) => {
+                if let Some(i) = self.indice_historico(&h) {
+                    self.historico[i].nota = Some(nota.numero);
+                }
+                if let Some(d) = self.historico_detalhe.as_mut() {
+                    d.nota = Some(nota.numero);
+                }
+                self.mensagens
+                    .push(format!("Nota {:06} emitida em {}", nota.numero, caminho));
+                if aniversario.is_some() {
+                    fidelidade::define_aniversario(
+                        &mut self.registros.aniversarios,
+                        &nota.cliente_nome,
+                        aniversario,
+                    );
+                }
+                self.registros.notas.push(nota);
+                self.salvar();
+            }
+            Err(e) => self
+                .mensagens
+                .push(format!("Falha ao gravar a nota: {}", e)),
+        }
+    }
+
+    /// Dispara o envio do estoque e do resumo de vendas para o Sheets numa
+    /// thread, para não travar a interface.
+    fn enviar_planilha(&mut self) {
+        if self.planilha_rx.is_some() {
+            self.mensagens
+                .push("Envio para o Google Sheets já em andamento.".into());
+            return;
+        }
+        let Some(destino) = self.config.destino_planilha() else {
+            self.mensagens
+                .push("Configure as chaves sheets_* em Configurações.".into());
+            return;
+        };
+        let centavos = |v: f64| serde_json::json!((v * 100.0).round() / 100.0);
+        let mut estoque = vec![["Código", "Descrição", "Quantidade", "Preço"]
+            .map(serde_json::Value::from)
+            .to_vec()];
+        for r in &self.estoques_list {
+            estoque.push(vec![
+                r.codigo.clone().into(),
+                r.descricao.clone().into(),
+                self.quantidade_disponivel(r).into(),
+                r.preco.map_or("".into(), centavos),
+            ]);
+        }
+        let mut vendas = vec![["Data", "Vendas", "Unidades", "Receita"]
+            .map(serde_json::Value::from)
+            .to_vec()];
+        for (data, n, unidades, receita) in analise::vendas_por_dia(&self.historico, &self.relogios)
+        {
+            vendas.push(vec![
+                data.into(),
+                n.into(),
+                unidades.into(),
+                centavos(receita),
+            ]);
+        }
+
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let _ = tx.send(planilha::envia(&destino, estoque, vendas));
+        });
+        self.planilha_rx = Some(rx);
+        self.planilha_ultimo_envio = Instant::now();
+        self.mensagens
+            .push("Enviando dados para o Google Sheets...".into());
+    }
+
+    /// Recolhe o resultado do envio e dispara o envio agendado.
+    fn verifica_planilha(&mut self) {
+        if let Some(rx) = &self.planilha_rx {
+            match rx.try_recv() {
+                Ok(resultado) => {
+                    self.planilha_rx = None;
+                    match resultado {
+                        Ok(()) => self.mensagens.push("Google Sheets atualizado.".into()),
+                        Err(e) => self
+                            .mensagens
+                            .push(format!("Falha no envio ao Google Sheets: {}", e)),
+                    }
+                }
+                Err(mpsc::TryRecvError::Disconnected) => self.planilha_rx = None,
+                Err(mpsc::TryRecvError::Empty) => {}
+            }
+        }
+        let intervalo = Duration::from_secs(self.config.sheets_intervalo_min * 60);
+        if !intervalo.is_zero()
+            && self.planilha_rx.is_none()
+            && self.planilha_ultimo_envio.elapsed() >= intervalo
+            && self.config.destino_planilha().is_some()
+        {
+            self.enviar_planilha();
+        }
+    }
+
+    /// Uma vez por dia, grava na pasta de relatórios os arquivos que faltam.
+    fn verifica_relatorios(&mut self) {
+        let hoje = Local::now().date_naive();
+        let Some(pasta) = self.config.relatorios_pasta.clone() else {
+            return;
+        };
+        if self.relatorios_dia == Some(hoje) {
+            return;
+        }
+        self.relatorios_dia = Some(hoje);
+        match relatorios::gera_pendentes(
+            &pasta,
+            &self.historico,
+            &self.relogios,
+            &self.registros,
+            hoje,
+            self.config.formato_numeros,
+        ) {
+            Ok(criados) if criados.is_empty() => {}
+            Ok(criados) => self.mensagens.push(format!(
+                "Relatórios gravados em {}: {}",
+                pasta,
+                criados.join(", ")
+            )),
+            Err(e) => self
+                .mensagens
+                .push(format!("Falha ao gravar relatórios: {}", e)),
+        }
+    }
+
+    fn consultar_mercado(&mut self) {
+        let Some(url) = self.config.preco_mercado_url.clone() else {
+            self.mensagens
+                .push("Configure preco_mercado_url em Configurações.".into());
+            return;
+        };
+        let Some(codigo) = self.codigo_em_foco() else {
+            self.mensagens.push("Nenhum relógio selecionado.".into());
+            return;
+        };
+        let (tx, rx) = mpsc::channel();
+        let cod = codigo.clone();
+        let formato = self.config.formato_numeros;
+        std::thread::spawn(move || {
+            let resultado = mercado::consulta(&url, &cod, formato);
+            let _ = tx.send((cod, resultado));
+        });
+        self.mercado_rx = Some(rx);
+        self.mensagens
+            .push(format!("Consultando preço de mercado de {}...", codigo));
+    }
+
+    fn verifica_mercado(&mut self) {
+        let Some(Ok((codigo, resultado))) = self.mercado_rx.as_ref().map(|rx| rx.try_recv()) else {
+            return;
+        };
+        self.mercado_rx = None;
+        let meu = self
+            .relogios
+            .get(&codigo)
+            .and_then(|r| r.preco)
+            .map(|p| self.config.formato_numeros.moeda(p))
+            .unwrap_or_else(|| "sem preço".into());
+        match resultado {
+            Ok(Some(p)) => self.mensagens.push(format!(
+                "{}: seu preço {} | mercado: mín {}, mediana {}, máx {} ({} ofertas)",
+                codigo,
+                meu,
+                self.config.formato_numeros.moeda(p.minimo),
+                self.config.formato_numeros.moeda(p.mediana),
+                self.config.formato_numeros.moeda(p.maximo),
+                p.ofertas
+            )),
+            Ok(None) => self
+                .mensagens
+                .push(format!("Nenhuma oferta de mercado para {}.", codigo)),
+            Err(e) => self
+                .mensagens
+                .push(format!("Falha na consulta de mercado: {}", e)),
+        }
+    }
+
+    /// Busca em segundo plano as cotações que ainda não são de hoje.
+    fn atualizar_cambio(&mut self) {
+        let hoje = Local::now().format("%Y-%m-%d").to_string();
+        let mut moedas: Vec<String> = self
+            .relogios
+            .values()
+            .filter_map(|r| r.moeda.clone())
+            .filter(|m| self.cambio.get(m).is_none_or(|c| c.data != hoje))
+            .collect();
+        moedas.sort();
+        moedas.dedup();
+        if moedas.is_empty() {
+            return;
+        }
+        let url = self.config.cambio_url.clone();
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            let cotacoes = moedas
+                .into_iter()
+                .map(|m| {
+                    let taxa = cambio::busca(&url, &m);
+                    (m, taxa)
+                })
+                .collect();
+            let _ = tx.send(cotacoes);
+        });
+        self.cambio_rx = Some(rx);
+    }
+
+    fn verifica_cambio(&mut self) {
+        let Some(Ok(cotacoes)) = self.cambio_rx.as_ref().map(|rx| rx.try_recv()) else {
+            return;
+        };
+        self.cambio_rx = None;
+        let hoje = Local::now().format("%Y-%m-%d").to_string();
+        for (moeda, resultado) in cotacoes {
+            match resultado {
+                Ok(taxa) => {
+                    self.cambio.insert(
+                        moeda,
+                        cambio::Cotacao {
+                            data: hoje.clone(),
+                            taxa,
+                        },
+                    );
+                }
+                Err(e) => {
+                    let aviso = match self.cambio.get(&moeda) {
+                        Some(c) => format!("usando cotação de {}", c.data),
+                        None => "sem cotação disponível".into(),
+                    };
+                    self.mensagens
+                        .push(format!("Câmbio {} indisponível ({}): {}", moeda, e, aviso));
+                }
+            }
+        }
+        cambio::salva_cache(&self.cambio);
+    }
+
+    /// Custo convertido para reais pela cotação mais recente.
+    fn custo_em_reais(&self, r: &Relogio) -> Option<f64> {
+        let custo = r.custo?;
+        match &r.moeda {
+            None => Some(custo),
+            Some(m) => self.cambio.get(m).map(|c| custo * c.taxa),
+        }
+    }
+
+    /// Linha de apoio da Compra: custo em reais e preço sugerido pela margem.
+    fn resumo_custo(&self, codigo: &str) -> Option<String> {
+        let r = self.relogios.get(codigo)?;
+        let reais = self.custo_em_reais(r)?;
+        let sugerido = reais * (1.0 + self.config.margem_sugerida / 100.0);
+        let origem = match (&r.moeda, r.moeda.as_ref().and_then(|m| self.cambio.get(m))) {
+            (Some(m), Some(c)) => format!(
+                "{} {} x {} ({}) = ",
+                m,
+                self.config
+                    .formato_numeros
+                    .numero(r.custo.unwrap_or(0.0), 2),
+                self.config.formato_numeros.numero(c.taxa, 4),
+                c.data
+            ),
+            _ => String::new(),
+        };
+        Some(format!(
+            "Custo: {}{} | Preço sugerido: {}",
+            origem,
+            self.config.formato_numeros.moeda(reais),
+            self.config.formato_numeros.moeda(sugerido)
+        ))
+    }
+
+    fn proximo_ano_arquivado(&mut self) {
+        let anos = arquivo::anos_arquivados();
+        if anos.is_empty() {
+            self.mensagens
+                .push("Nenhum ano arquivado encontrado.".into());
+            return;
+        }
+        let proximo = match self.historico_ano_arquivo {
+            Some(atual) => anos.iter().find(|a| **a > atual).copied(),
+            None => anos.first().copied(),
+        };
+        self.historico_ano_arquivo = proximo;
+        match proximo {
+            Some(ano) => {
+                self.historico_filtrado = Some(arquivo::carrega_ano(ano));
+                self.mensagens
+                    .push(format!("Exibindo histórico arquivado de {}", ano));
+            }
+            None => {
+                self.historico_filtrado = None;
+                self.mensagens.push("Voltando ao histórico atual.".into());
+            }
+        }
+        self.historico_scroll.reinicia();
+    }
+}
+
+/// Linhas do texto da ajuda: título, teclas e uma em branco por seção.
+fn ajuda_linhas() -> usize {
+    ajuda::SECOES.iter().map(|s| s.teclas.len() + 2).sum()
+}
+
+/// Retângulo centralizado ocupando a porcentagem dada da área.
+fn area_central(pct_x: u16, pct_y: u16, area: Rect) -> Rect {
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - pct_y) / 2),
+            Constraint::Percentage(pct_y),
+            Constraint::Percentage((100 - pct_y) / 2),
+        ])
+        .split(area);
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - pct_x) / 2),
+            Constraint::Percentage(pct_x),
+            Constraint::Percentage((100 - pct_x) / 2),
+        ])
+        .split(vertical[1])[1]
+}
+
+/// Cores de entrada (compra) e de saída (venda, falha) do tema.
+/// Mês abreviado com o ano, como `Out/26`.
+fn rotulo_mes(data: NaiveDate) -> String {
+    format!("{}/{:02}", MESES[data.month0() as usize], data.year() % 100)
+}
+
+/// Linha da série atual sobre a de comparação (em cinza), com legenda; o
+/// eixo Y vai de zero ao maior valor das duas.
+fn grafico_comparativo<'a>(
+    cores: tema::Cores,
+    titulo: String,
+    (nome_atual, atual): (String, &'a [(f64, f64)]),
+    (nome_anterior, anterior): (String, &'a [(f64, f64)]),
+    rotulos_x: Vec<Span<'a>>,
+    rotulo_y: impl Fn(f64) -> String,
+) -> ratatui::widgets::Chart<'a> {
+    let todos = || atual.iter().chain(anterior);
+    let maximo = todos().map(|(_, y)| *y).fold(0.0, f64::max).max(1.0);
+    let ultimo_x = todos().map(|(x, _)| *x).fold(0.0, f64::max).max(1.0);
+    let serie = |nome: String, cor: Color, pontos: &'a [(f64, f64)]| {
+        ratatui::widgets::Dataset::default()
+            .name(nome)
+            .marker(ratatui::symbols::Marker::Braille)
+            .graph_type(ratatui::widgets::GraphType::Line)
+            .style(Style::default().fg(cor))
+            .data(pontos)
+    };
+    ratatui::widgets::Chart::new(vec![
+        serie(nome_anterior, cores.apagado, anterior),
+        serie(nome_atual, cores.realce, atual),
+    ])
+    .block(Block::default().borders(Borders::ALL).title(titulo))
+    .legend_position(Some(ratatui::widgets::LegendPosition::TopLeft))
+    .hidden_legend_constraints((Constraint::Ratio(1, 3), Constraint::Ratio(1, 2)))
+    .x_axis(
+        ratatui::widgets::Axis::default()
+            .bounds([0.0, ultimo_x])
+            .labels(rotulos_x),
+    )
+    .y_axis(
+        ratatui::widgets::Axis::default()
+            .bounds([0.0, maximo * 1.05])
+            .labels(
+                [0.0, maximo / 2.0, maximo]
+                    .iter()
+                    .map(|v| Span::raw(rotulo_y(*v)))
+                    .collect::<Vec<Span>>(),
+            ),
+    )
+}
+
+/// Interpreta componentes de kit no formato `CODIGO:qtd` (qtd padrão 1).
+/// Um `*` por caractere, para senhas e segredos na tela.
+fn oculta(texto: &str) -> String {
+    "*".repeat(texto.chars().count())
+}
+
+/// A entrada da venda com o `!senha` trocado por asteriscos.
+fn oculta_senha(input: &str) -> String {
+    let mut saida = String::with_capacity(input.len());
+    let mut na_senha = false;
+    for c in input.chars() {
+        if c.is_whitespace() {
+            na_senha = false;
+        } else if c == '!' && (saida.is_empty() || saida.ends_with(char::is_whitespace)) {
+            na_senha = true;
+            saida.push(c);
+            continue;
+        }
+        saida.push(if na_senha { '*' } else { c });
+    }
+    saida
+}
+
+fn parse_componentes(tokens: &[&str]) -> Option<Vec<Componente>> {
+    tokens
+        .iter()
+        .map(|t| {
+            let (codigo, qtd) = t.split_once(':').unwrap_or((t, "1"));
+            let quantidade: i32 = qtd.parse().ok().filter(|q| *q > 0)?;
+            (!codigo.is_empty()).then(|| Componente {
+                codigo: codigo.to_string(),
+                quantidade,
+            })
+        })
+        .collect()
+}
+
+/// Verifica se todos os caracteres de `query` aparecem em `codigo`, na ordem
+/// (sem diferenciar maiúsculas/minúsculas).
+fn contem_subsequencia(codigo: &str, query: &str) -> bool {
+    let mut chars = codigo.chars().flat_map(char::to_lowercase);
+    query
+        .chars()
+        .flat_map(char::to_lowercase)
+        .all(|q| chars.any(|c| c == q))
+}
+
+/// Posições (em caracteres) de `codigo` que casam com a consulta, na mesma
+/// varredura de `contem_subsequencia`. Resultados vindos da distância casam
+/// só em parte.
+fn posicoes_da_busca(codigo: &str, query: &str) -> Vec<usize> {
+    let mut query = query.chars().flat_map(char::to_lowercase).peekable();
+    let mut posicoes = vec![];
+    for (i, c) in codigo.chars().enumerate() {
+        let Some(q) = query.peek() else {
+            break;
+        };
+        if c.to_lowercase().next() == Some(*q) {
+            posicoes.push(i);
+            query.next();
+        }
+    }
+    posicoes
+}
+
+/// Texto com os caracteres que casaram com a busca em `destaque`.
+fn realca(texto: &str, query: &str, destaque: Style) -> Vec<Span<'static>> {
+    let posicoes = posicoes_da_busca(texto, query);
+    let mut spans = vec![];
+    let (mut trecho, mut casando) = (String::new(), false);
+    for (i, c) in texto.chars().enumerate() {
+        let casa = posicoes.contains(&i);
+        if casa != casando && !trecho.is_empty() {
+            let estilo = if casando { destaque } else { Style::default() };
+            spans.push(Span::styled(std::mem::take(&mut trecho), estilo));
+        }
+        casando = casa;
+        trecho.push(c);
+    }
+    if !trecho.is_empty() {
+        spans.push(Span::styled(
+            trecho,
+            if casando { destaque } else { Style::default() },
+        ));
+    }
+    spans
+}
+
+fn levenshtein_distance(a: &str, b: &str) -> usize {
+    let mut costs = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        costs[0] = i + 1;
+        let mut corner = i;
+        for (j, cb) in b.chars().enumerate() {
+            let upper = costs[j + 1];
+            if ca == cb {
+                costs[j + 1] = corner;
+            } else {
+                let t = corner.min(upper).min(costs[j]) + 1;
+                costs[j + 1] = t;
+            }
+            corner = upper;
+        }
+    }
+    costs[b.len()]
+}
+
+/// Distância de Levenshtein calculada só dentro da banda `|i - j| <= max`,
+/// abortando assim que nenhuma célula da linha couber no limite.
+fn levenshtein_limitado(a: &str, b: &str, max: usize) -> Option<usize> {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+    let fora = max + 1;
+    let mut anterior: Vec<usize> = (0..=b.len()).map(|j| j.min(fora)).collect();
+    let mut atual = vec![fora; b.len() + 1];
+    for i in 1..=a.len() {
+        atual.fill(fora);
+        atual[0] = i.min(fora);
+        let inicio = i.saturating_sub(max).max(1);
+        let fim = (i + max).min(b.len());
+        let mut menor = atual[0];
+        for j in inicio..=fim {
+            let custo = usize::from(a[i - 1] != b[j - 1]);
+            let d = (anterior[j - 1] + custo)
+                .min(anterior[j] + 1)
+                .min(atual[j - 1] + 1)
+                .min(fora);
+            atual[j] = d;
+            menor = menor.min(d);
+        }
+        if menor > max {
+            return None;
+        }
+        std::mem::swap(&mut anterior, &mut atual);
+    }
+    let d = anterior[b.len()];
+    (d <= max).then_some(d)
+}
+
+/// Formatos de `codigo quantidade [extra]`, repetidos nas mensagens de erro.
+const USO_CADASTRO: &str = "codigo quantidade [caixa] [custo=X preco=Y]";
+const USO_COMPRA: &str = "codigo quantidade [caixas] [@data] [fornecedor=nome]";
+const USO_VENDA: &str = "codigo quantidade [pagamento] [@data]";
+
+/// `codigo quantidade [extra]` digitado no Cadastro, na Compra e na Venda.
+#[derive(Debug, PartialEq)]
+struct Movimento<'a> {
+    codigo: &'a str,
+    quantidade: i32,
+    extra: Option<&'a str>,
+}
+
+/// Confere as partes já separadas da entrada: exatamente o código, a
+/// quantidade (no formato de números configurado) e no máximo um extra.
+fn le_movimento<'a>(
+    partes: &[&'a str],
+    formato: numeros::FormatoNumero,
+    uso: &str,
+) -> Result<Movimento<'a>, String> {
+    match partes {
+        [] => Err(format!("Informe o código e a quantidade: {}", uso)),
+        [codigo] => Err(format!("Falta a quantidade de {}: {}", codigo, uso)),
+        [_, _, _, sobra, ..] => Err(format!("Sobrou '{}' na entrada: {}", sobra, uso)),
+        [codigo, qtd, extra @ ..] => Ok(Movimento {
+            codigo,
+            quantidade: formato
+                .interpreta_inteiro(qtd)
+                .ok_or_else(|| format!("Quantidade inválida: '{}'.", qtd))?,
+            extra: extra.first().copied(),
+        }),
+    }
+}
+
+/// Custo e preço de venda digitados; ausente mantém o atual.
+type Precos = (Option<f64>, Option<f64>);
+
+/// Tira do cadastro digitado os `custo=` e `preco=` (no formato de números
+/// configurado).
+fn separa_precos(input: &str, formato: numeros::FormatoNumero) -> (String, Result<Precos, String>) {
+    let mut precos = (None, None);
+    let mut resto = vec![];
+    for parte in input.split_whitespace() {
+        let campo = match parte.split_once('=') {
+            Some((c, v)) if c.eq_ignore_ascii_case("custo") => Some((&mut precos.0, v)),
+            Some((c, v)) if c.eq_ignore_ascii_case("preco") || c.eq_ignore_ascii_case("preço") => {
+                Some((&mut precos.1, v))
+            }
+            _ => None,
+        };
+        let Some((destino, valor)) = campo else {
+            resto.push(parte);
+            continue;
+        };
+        match formato.interpreta(valor).filter(|v| *v >= 0.0) {
+            Some(v) => *destino = Some(v),
+            None => {
+                return (
+                    resto.join(" "),
+                    Err(format!("Valor inválido em {}.", parte)),
+                )
+            }
+        }
+    }
+    (resto.join(" "), Ok(precos))
+}
+
+/// Tira da compra digitada o `fornecedor=` (ou `forn=`), que pode ser só o
+/// começo do nome cadastrado.
+fn separa_fornecedor(input: &str) -> (String, Option<&str>) {
+    let mut fornecedor = None;
+    let mut resto = vec![];
+    for parte in input.split_whitespace() {
+        match parte.split_once('=') {
+            Some((c, f))
+                if c.eq_ignore_ascii_case("fornecedor") || c.eq_ignore_ascii_case("forn") =>
+            {
+                fornecedor = Some(f)
+            }
+            _ => resto.push(parte),
+        }
+    }
+    (resto.join(" "), fornecedor)
+}
+
+/// Tira da operação digitada a data de efeito `@DD/MM` (ou `@AAAA-MM-DD`,
+/// `@N` dias). Datas até hoje contam como operação imediata.
+fn separa_data_efeito(input: &str, hoje: NaiveDate) -> (String, Result<Option<NaiveDate>, String>) {
+    let (datas, resto): (Vec<&str>, Vec<&str>) =
+        input.split_whitespace().partition(|p| p.starts_with('@'));
+    let efeito = match datas.first() {
+        None => Ok(None),
+        Some(d) => orcamentos::interpreta_retorno(&d[1..], hoje)
+            .map(|data| (data > hoje).then_some(data))
+            .ok_or_else(|| format!("Data de efeito inválida: {}", d)),
+    };
+    (resto.join(" "), efeito)
+}
+
+impl App {
+    /// Desenha a tela do modo atual.
+    pub fn render(&mut self, f: &mut Frame) {
+        let size = f.area();
+        let cores = self.cores;
+        let idioma = self.config.idioma;
+        let t = move |texto: &'static str| i18n::t(idioma, texto);
+        let fundo = Style::default().bg(cores.fundo).fg(cores.texto);
+        f.render_widget(Block::default().style(fundo), size);
+        if size.width < TAMANHO_MINIMO.0 || size.height < TAMANHO_MINIMO.1 {
+            let aviso = format!(
+                "Terminal pequeno demais ({}x{}).\nAumente para pelo menos {}x{}.\n\n[X] Sair",
+                size.width, size.height, TAMANHO_MINIMO.0, TAMANHO_MINIMO.1
+            );
+            f.render_widget(Paragraph::new(aviso).wrap(Wrap { trim: true }), size);
+            return;
+        }
+        if self.modo == Modo::Quiosque {
+            let paginas = self.paginas_quiosque();
+            let atual = self.quiosque_pagina % paginas.len();
+            let (rotulo, valor) = &paginas[atual];
+            let grande = letreiro::grande(valor);
+            let espaco = (size.height as usize).saturating_sub(grande.len() + 6) / 2;
+            let mut linhas = vec![Line::from(""); espaco];
+            linhas.push(Line::from(Span::styled(
+                rotulo.clone(),
+                Style::default().add_modifier(Modifier::BOLD),
+            )));
+            linhas.push(Line::from(""));
+            linhas.extend(grande.into_iter().map(|l| {
+                Line::from(Span::styled(
+                    l,
+                    Style::default()
+                        .fg(cores.destaque)
+                        .add_modifier(Modifier::BOLD),
+                ))
+            }));
+            linhas.push(Line::from(""));
+            linhas.push(Line::from(format!(
+                "{}/{} — ←/→ troca, ESC sai{}",
+                atual + 1,
+                paginas.len(),
+                if paginas.len() == 1 {
+                    " (F no Estoque marca destaques)"
+                } else {
+                    ""
+                }
+            )));
+            f.render_widget(
+                Paragraph::new(linhas)
+                    .alignment(Alignment::Center)
+                    .block(Block::default().borders(Borders::ALL).title("Quiosque")),
+                size,
+            );
+            return;
+        }
+        let vertical_layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints(
+                [
+                    Constraint::Length(5),
+                    Constraint::Min(5),
+                    Constraint::Length(5),
+                    Constraint::Length(5),
+                ]
+                .as_ref(),
+            )
+            .split(size);
+
+        let logo = r#" 
+   ____      _          _     
+  / ___| ___| | ___  __| |___ 
+ | |  _ / _ \ |/ _ \/ _` / __|
+ | |_| |  __/ |  __/ (_| \__ \
+  \____|\___|_|\___|\__,_|___/
+"#;
+
+        let logo_par = Paragraph::new(logo).style(
+            Style::default()
+                .fg(cores.destaque)
+                .add_modifier(Modifier::BOLD),
+        );
+        let topo = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Length(32), Constraint::Min(10)])
+            .split(vertical_layout[0]);
+        f.render_widget(logo_par, topo[0]);
+        {
+            let mut favoritos: Vec<Span> = self
+                .registros
+                .favoritos
+                .iter()
+                .enumerate()
+                .flat_map(|(i, codigo)| {
+                    [
+                        Span::styled(
+                            format!(" {} ", i + 1),
+                            Style::default().fg(cores.selecao_texto).bg(cores.destaque),
+                        ),
+                        Span::raw(format!(" {}  ", codigo)),
+                    ]
+                })
+                .collect();
+            if favoritos.is_empty() {
+                favoritos.push(Span::raw(t("Nenhum: P prende a linha atual do Estoque")));
+            }
+            f.render_widget(
+                Paragraph::new(Line::from(favoritos))
+                    .wrap(Wrap { trim: true })
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .title(t("Favoritos (1–9 vende, P prende/solta)")),
+                    ),
+                topo[1],
+            );
+        }
+
+        let horizontal_layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Min(20), Constraint::Length(30)].as_ref())
+            .split(vertical_layout[1]);
+
+        let mut hotkeys_vec = vec![
+            t("Hotkeys ([?] ajuda):").to_string(),
+            t(" [C] Cadastro").to_string(),
+            t(" [B] Buscar").to_string(),
+            t(" [H] Histórico (↑/↓ rola, ←/→ abas)").to_string(),
+            t(" [G] Gráfico").to_string(),
+            t(" [I] Importar catálogo").to_string(),
+            t(" [L] Etiqueta (Shift+L: pendentes)").to_string(),
+            t(" [S] Configurações").to_string(),
+            t(" [W] Enviar p/ Google Sheets").to_string(),
+            t(" [M] Preço de mercado").to_string(),
+            t(" [K] Admin (API)").to_string(),
+            t(" [T] Estoque em data passada").to_string(),
+            t(" [F] Destaque / [Q] Quiosque").to_string(),
+            t(" [N] Notificações/[E] Recado").to_string(),
+            t(" [Espaço]/[O] Comparar").to_string(),
+            t(" [R] Usados recentemente").to_string(),
+            t(" [U] Fornecedores").to_string(),
+            t(" [J] Receber mercadoria").to_string(),
+            t(" [Y] Pedidos pendentes").to_string(),
+            t(" [Z] Orçamentos").to_string(),
+            t(" [@] Clientes").to_string(),
+            t(" [~] Mostrar arquivados").to_string(),
+            t(" [|] Estoque + histórico").to_string(),
+            t(" [:] Console de consultas").to_string(),
+            t(" [+]/[-]/[=] Ajustar quantidade").to_string(),
+            t(" [D] Excluir / [F2] Renomear").to_string(),
+            t(" [ENTER] Selecionar registro").to_string(),
+            t(" [A] Adicionar/Comprar").to_string(),
+            t(" [V] Vender").to_string(),
+            t(" [ESC] Cancelar Seleção").to_string(),
+            t(" [X] Sair").to_string(),
+        ];
+
+        if let Some(cod) = &self.chosen_relogio {
+            for line in hotkeys_vec.iter_mut() {
+                if line.contains("[A]") && self.chosen_operation == Some('A') {
+                    *line = format!(
+                        "{} -> {} {}",
+                        t(" [A] Adicionar/Comprar"),
+                        cod,
+                        t("(Selecionado)")
+                    );
+                } else if line.contains("[V]") && self.chosen_operation == Some('V') {
+                    *line = format!("{} -> {} {}", t(" [V] Vender"), cod, t("(Selecionado)"));
+                }
+            }
+        }
+
+        // Historico: adicionar hotkey [P] Pesquisar
+        if self.modo == Modo::Historico {
+            hotkeys_vec.insert(5, t(" [P] Pesquisar Histórico").to_string());
+            hotkeys_vec.insert(6, t(" [Y] Anos arquivados").to_string());
+            hotkeys_vec.insert(7, t(" [Espaço]/[*] Marcar").to_string());
+            hotkeys_vec.insert(8, t(" [T] Etiquetar / [F] Filtrar").to_string());
+            hotkeys_vec.insert(9, t(" [E] Exportar CSV").to_string());
+        }
+
+        let hotkeys_items: Vec<ListItem> = hotkeys_vec
+            .iter()
+            .map(|x| {
+                let mut style = Style::default();
+                if let Some(op) = self.chosen_operation {
+                    if (x.contains("[A]") && op == 'A') || (x.contains("[V]") && op == 'V') {
+                        style = Style::default()
+                            .fg(cores.destaque)
+                            .add_modifier(Modifier::BOLD);
+                    }
+                }
+                if self.chosen_relogio.is_some() && x.contains("[ENTER]") {
+                    style = style.add_modifier(Modifier::BOLD);
+                }
+                ListItem::new(x.clone()).style(style)
+            })
+            .collect();
+        let hotkeys_list =
+            List::new(hotkeys_items).block(Block::default().borders(Borders::ALL).title("Hotkeys"));
+        f.render_widget(hotkeys_list, horizontal_layout[1]);
+
+        let main_area = horizontal_layout[0];
+        match self.modo {
+            Modo::Estoques => {
+                // Favoritos com estoque alvo, medidos abaixo da tabela
+                let medidos: Vec<&Relogio> = if self.config.painel_medidores {
+                    self.registros
+                        .favoritos
+                        .iter()
+                        .filter_map(|c| self.relogios.get(c))
+                        .filter(|r| r.estoque_alvo.is_some())
+                        .collect()
+                } else {
+                    vec![]
+                };
+                let (area, area_medidores) = if self.config.painel_medidores {
+                    let altura = (medidos.len().max(1) as u16 + 2).min(main_area.height / 2);
+                    let partes = Layout::default()
+                        .direction(Direction::Vertical)
+                        .constraints([Constraint::Min(5), Constraint::Length(altura)])
+                        .split(main_area);
+                    (partes[0], Some(partes[1]))
+                } else {
+                    (main_area, None)
+                };
+                if let Some(painel) = area_medidores {
+                    let bloco = Block::default()
+                        .borders(Borders::ALL)
+                        .title(t("Estoque × alvo dos favoritos"));
+                    let dentro = bloco.inner(painel);
+                    f.render_widget(bloco, painel);
+                    if medidos.is_empty() {
+                        f.render_widget(
+                            Paragraph::new(t(
+                                "Nenhum favorito com alvo: Relogio alvo <codigo> <un>",
+                            )),
+                            dentro,
+                        );
+                    }
+                    let linhas = Layout::default()
+                        .direction(Direction::Vertical)
+                        .constraints(vec![Constraint::Length(1); medidos.len()])
+                        .split(dentro);
+                    let (cor_ok, cor_baixo) = (cores.entrada, cores.saida);
+                    let numeros = self.config.formato_numeros;
+                    for (r, linha) in medidos.iter().zip(linhas.iter()) {
+                        let alvo = r.estoque_alvo.unwrap_or(1).max(1);
+                        let qtd = self.quantidade_disponivel(r);
+                        let razao = (qtd.max(0) as f64 / alvo as f64).min(1.0);
+                        let cor = if qtd >= alvo {
+                            cor_ok
+                        } else if razao < 0.5 {
+                            cor_baixo
+                        } else {
+                            cores.destaque
+                        };
+                        f.render_widget(
+                            Gauge::default()
+                                .gauge_style(Style::default().fg(cor).bg(cores.apagado))
+                                .ratio(razao)
+                                .label(format!(
+                                    "{} {}/{}",
+                                    r.codigo,
+                                    numeros.inteiro(qtd as i64),
+                                    numeros.inteiro(alvo as i64)
+                                )),
+                            *linha,
+                        );
+                    }
+                }
+                let (area, area_historico) = if self.painel_dividido {
+                    let partes = Layout::default()
+                        .direction(Direction::Horizontal)
+                        .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
+                        .split(area);
+                    (partes[0], Some(partes[1]))
+                } else {
+                    (area, None)
+                };
+                // Borda destacada no painel que recebe ↑/↓
+                let foco = |ativo: bool| {
+                    if self.painel_dividido && ativo {
+                        Style::default().fg(cores.realce)
+                    } else {
+                        Style::default()
+                    }
+                };
+                let colunas_estoque =
+                    colunas::ou_padrao(colunas::Tabela::Estoque, &self.config.colunas_estoque);
+                // Bordas, cabeçalho e a linha fixa de totais
+                let altura = area.height.saturating_sub(4) as usize;
+                let visiveis = self
+                    .estoques_scroll
+                    .visiveis(self.estoques_list.len(), altura);
+                let inicio = visiveis.start;
+                let visible_data = &self.estoques_list[visiveis];
+                let em_pedido = pedidos::em_pedido(&self.registros.pedidos_compra);
+                let visible_rows = visible_data.iter().enumerate().map(|(i, r)| {
+                    let real_index = inicio + i;
+                    let mut base_style = Style::default();
+                    if real_index == self.estoques_scroll.selected {
+                        base_style = base_style.bg(cores.selecao_fundo).fg(cores.selecao_texto);
+                    }
+                    if let Some(selected_cod) = &self.chosen_relogio {
+                        if selected_cod == &r.codigo {
+                            base_style = base_style.bg(cores.destaque).fg(cores.selecao_texto);
+                        }
+                    }
+
+                    let numeros = self.config.formato_numeros;
+                    let qtd = match &self.estoque_em {
+                        Some((_, saldos)) => match saldos.get(&r.codigo) {
+                            None => format!("— {}", t("(sem cadastro)")),
+                            Some(s) if s.componentes.is_empty() => {
+                                numeros.inteiro(s.quantidade() as i64)
+                            }
+                            Some(s) => format!(
+                                "{} (kit)",
+                                numeros.inteiro(quantidade_disponivel(saldos, s) as i64)
+                            ),
+                        },
+                        None if r.componentes.is_empty() => numeros.inteiro(r.quantidade() as i64),
+                        None => format!(
+                            "{} (kit)",
+                            numeros.inteiro(self.quantidade_disponivel(r) as i64)
+                        ),
+                    };
+                    let pendente =
+                        agendadas::pendente(&self.registros.operacoes_agendadas, &r.codigo);
+                    let qtd = if pendente != 0 && self.estoque_em.is_none() {
+                        format!("{} ({:+} pendente)", qtd, pendente)
+                    } else {
+                        qtd
+                    };
+                    let codigo = match self.promocao_ativa(r) {
+                        Some(p) => {
+                            if real_index != self.estoques_scroll.selected {
+                                base_style = base_style.fg(cores.marcado);
+                            }
+                            format!("{}  -{}% {}", r.codigo, p.desconto, p.nome)
+                        }
+                        None => r.codigo.clone(),
+                    };
+                    let codigo = if r.destaque {
+                        format!("★ {}", codigo)
+                    } else {
+                        codigo
+                    };
+                    let codigo = if r.situacao.eh_ativo() {
+                        codigo
+                    } else {
+                        format!("{} ({})", codigo, r.situacao.rotulo())
+                    };
+                    let codigo = if self.comparar.contains(&r.codigo) {
+                        base_style = base_style.add_modifier(Modifier::UNDERLINED);
+                        format!("◆ {}", codigo)
+                    } else {
+                        codigo
+                    };
+                    let celulas = colunas_estoque.iter().map(|c| {
+                        Cell::from(match c.campo {
+                            "codigo" => codigo.clone(),
+                            "qtd" => qtd.clone(),
+                            "pedido" => em_pedido
+                                .get(&r.codigo)
+                                .filter(|_| self.estoque_em.is_none())
+                                .map(|q| format!("+{}", numeros.inteiro(*q as i64)))
+                                .unwrap_or_default(),
+                            "descricao" => r.descricao.clone(),
+                            "preco" => r.preco.map(|p| numeros.moeda(p)).unwrap_or_default(),
+                            "custo" => r.custo.map(|c| numeros.moeda(c)).unwrap_or_default(),
+                            "categoria" => r.categoria.clone().unwrap_or_default(),
+                            "marca" => r.marca.clone().unwrap_or_default(),
+                            "ean" => r.ean.clone().unwrap_or_default(),
+                            _ => String::new(),
+                        })
+                    });
+                    Row::new(celulas).style(base_style)
+                });
+                let numeros = self.config.formato_numeros;
+                let (skus, unidades, valor_preco, valor_custo) = self.totais_estoque();
+                // Só os códigos que o filtro deixa na tabela
+                let a_caminho: i64 = self
+                    .estoques_list
+                    .iter()
+                    .filter_map(|r| em_pedido.get(&r.codigo))
+                    .map(|q| *q as i64)
+                    .sum();
+                let totais = colunas_estoque.iter().map(|c| match c.campo {
+                    "codigo" => format!("Σ {} {}", numeros.inteiro(skus as i64), t("códigos")),
+                    "qtd" => format!("{} un", numeros.inteiro(unidades)),
+                    "pedido" if a_caminho > 0 && self.estoque_em.is_none() => {
+                        format!("+{}", numeros.inteiro(a_caminho))
+                    }
+                    "preco" if valor_preco > 0.0 => numeros.moeda(valor_preco),
+                    "custo" if valor_custo > 0.0 => numeros.moeda(valor_custo),
+                    _ => String::new(),
+                });
+                // Preço e custo nunca são cortados: a coluna tem ao menos a
+                // largura do maior valor (linhas filtradas, total e título)
+                let largura_moeda = |c: &colunas::Coluna| {
+                    let (total, valor): (f64, fn(&Relogio) -> Option<f64>) = match c.campo {
+                        "preco" => (valor_preco, |r| r.preco),
+                        _ => (valor_custo, |r| r.custo),
+                    };
+                    self.estoques_list
+                        .iter()
+                        .filter_map(valor)
+                        .chain(Some(total).filter(|v| *v > 0.0))
+                        .map(|v| numeros.moeda(v).chars().count())
+                        .chain(Some(t(c.titulo).chars().count()))
+                        .max()
+                        .unwrap_or(0) as u16
+                };
+                let table = Table::new(
+                    visible_rows,
+                    colunas_estoque.iter().map(|c| match c.campo {
+                        "preco" | "custo" => Constraint::Min(largura_moeda(c)),
+                        // Proporcional: porcentagens somando 100 mais o
+                        // espaço entre colunas não cabem
+                        _ => Constraint::Fill(c.largura),
+                    }),
+                )
+                .header(
+                    Row::new(colunas_estoque.iter().map(|c| t(c.titulo))).style(
+                        Style::default()
+                            .fg(cores.texto)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                )
+                .footer(Row::new(totais).style(Style::default().add_modifier(Modifier::BOLD)))
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(foco(!self.foco_historico))
+                        .title(match &self.estoque_em {
+                            Some((data, _)) => format!(
+                                "{} {} {}",
+                                t("Estoque em"),
+                                data.format("%d/%m/%Y"),
+                                t("(somente leitura, T volta ao atual)")
+                            ),
+                            None => t("Estoque").to_string(),
+                        }),
+                );
+                f.render_widget(table, area);
+                if let Some(area_historico) = area_historico {
+                    let movimentos = self.historico_do_selecionado();
+                    let codigo = self
+                        .estoques_list
+                        .get(self.estoques_scroll.selected)
+                        .map_or("", |r| r.codigo.as_str());
+                    let ultima_venda = movimentos
+                        .iter()
+                        .find(|h| h.operacao == "VENDA")
+                        .and_then(|h| h.timestamp.get(..10))
+                        .map_or(t("nunca vendido").to_string(), |d| {
+                            format!(
+                                "{} {}/{}",
+                                t("última venda"),
+                                Self::formata_data_ddmm(d),
+                                &d[..4]
+                            )
+                        });
+                    let (cor_entrada, cor_saida) = (cores.entrada, cores.saida);
+                    let altura = area_historico.height.saturating_sub(3) as usize;
+                    let visiveis = self
+                        .painel_historico_scroll
+                        .visiveis(movimentos.len(), altura);
+                    let inicio = visiveis.start;
+                    let linhas = movimentos[visiveis].iter().enumerate().map(|(i, h)| {
+                        let mut estilo = match h.sentido() {
+                            1 => Style::default().fg(cor_entrada),
+                            -1 => Style::default().fg(cor_saida),
+                            _ => Style::default(),
+                        };
+                        if self.foco_historico
+                            && inicio + i == self.painel_historico_scroll.selected
+                        {
+                            estilo = estilo.add_modifier(Modifier::REVERSED);
+                        }
+                        Row::new(vec![
+                            h.timestamp.get(..16).unwrap_or(&h.timestamp).to_string(),
+                            h.operacao_marcada(self.config.idioma),
+                            h.quantidade_marcada(self.config.formato_numeros),
+                        ])
+                        .style(estilo)
+                    });
+                    let tabela = Table::new(
+                        linhas,
+                        &[
+                            Constraint::Length(17),
+                            Constraint::Min(10),
+                            Constraint::Length(7),
+                        ],
+                    )
+                    .header(
+                        Row::new(vec![t("DATA"), t("OPERAÇÃO"), t("QTD")])
+                            .style(Style::default().add_modifier(Modifier::BOLD)),
+                    )
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .border_style(foco(self.foco_historico))
+                            .title(format!("{} · {}", codigo, ultima_venda)),
+                    );
+                    f.render_widget(tabela, area_historico);
+                }
+            }
+            Modo::Historico => {
+                let titles = HistoricoTab::titles();
+                let tab_index = match self.historico_tab {
+                    HistoricoTab::Todos => 0,
+                    HistoricoTab::Compras => 1,
+                    HistoricoTab::Vendas => 2,
+                    HistoricoTab::Aquisicoes => 3,
+                };
+                let tab_titles: Vec<Span> = titles
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &titulo)| {
+                        let style = if i == tab_index {
+                            Style::default()
+                                .fg(cores.realce)
+                                .add_modifier(Modifier::BOLD)
+                        } else {
+                            Style::default()
+                        };
+                        Span::styled(t(titulo), style)
+                    })
+                    .collect();
+                let tabs = Tabs::new(tab_titles)
+                    .block(Block::default().borders(Borders::ALL).title(t("Filtros")))
+                    .highlight_style(Style::default().add_modifier(Modifier::BOLD));
+
+                let hist_layout = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Length(3), Constraint::Min(5)].as_ref())
+                    .split(main_area);
+
+                // Se estivermos editando (pesquisando histórico), mostrar input
+                let info = if self.editing && self.modo == Modo::Historico {
+                    format!("{} {}", t("Filtrar histórico por código:"), self.input)
+                } else {
+                    t("Pressione P para pesquisar no histórico").into()
+                };
+
+                let p = Paragraph::new(info).block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(t("Pesquisa no Histórico")),
+                );
+                f.render_widget(p, hist_layout[0]);
+
+                // Mostrar abas
+                f.render_widget(tabs, hist_layout[0]);
+
+                let data = self.get_historico_atual_vec();
+                let altura = hist_layout[1].height.saturating_sub(3) as usize;
+                let visiveis = self.historico_scroll.visiveis(data.len(), altura);
+                let inicio = visiveis.start;
+                let visible_data = &data[visiveis];
+
+                let mut titulo = t("Histórico").to_string();
+                if let Some(etiqueta) = &self.historico_etiqueta {
+                    titulo.push_str(&format!(" · {} \"{}\"", t("etiqueta"), etiqueta));
+                }
+                if !self.historico_marcados.is_empty() {
+                    titulo.push_str(&format!(
+                        " · {} {}",
+                        self.historico_marcados.len(),
+                        t("marcadas")
+                    ));
+                }
+
+                let colunas_historico =
+                    colunas::ou_padrao(colunas::Tabela::Historico, &self.config.colunas_historico);
+                let (cor_entrada, cor_saida) = (cores.entrada, cores.saida);
+                let visible_rows = visible_data.iter().enumerate().map(|(i, h)| {
+                    let real_index = inicio + i;
+                    let oper_style = match h.operacao.as_str() {
+                        "COMPRA" => Style::default().fg(cor_entrada),
+                        "VENDA" => Style::default().fg(cor_saida),
+                        "CADASTRO" => Style::default().fg(cores.destaque),
+                        _ => Style::default().fg(cores.texto),
+                    };
+                    let marcado = self
+                        .indice_historico(h)
+                        .is_some_and(|i| self.historico_marcados.contains(&i));
+                    let mut row_style = if real_index == self.historico_scroll.selected {
+                        oper_style.add_modifier(Modifier::REVERSED)
+                    } else {
+                        oper_style
+                    };
+                    if marcado {
+                        row_style = row_style.add_modifier(Modifier::UNDERLINED);
+                    }
+                    let celulas = colunas_historico.iter().map(|c| {
+                        Cell::from(match c.campo {
+                            "data" => h.timestamp.clone(),
+                            "operacao" => h.operacao_marcada(self.config.idioma),
+                            "qtd" => h.quantidade_marcada(self.config.formato_numeros),
+                            "codigo" if marcado => format!("◆ {}", h.codigo),
+                            "codigo" => h.codigo.clone(),
+                            "etiquetas" => h.tags.join(", "),
+                            "descricao" => self
+                                .relogios
+                                .get(&h.codigo)
+                                .map(|r| r.descricao.clone())
+                                .unwrap_or_default(),
+                            "pagamento" => h.pagamento.clone().unwrap_or_default(),
+                            "fornecedor" => h.fornecedor.clone().unwrap_or_default(),
+                            "motivo" => h.motivo.clone().unwrap_or_default(),
+                            "cliente" => h.cliente.clone().unwrap_or_default(),
+                            _ => String::new(),
+                        })
+                    });
+                    Row::new(celulas).style(row_style)
+                });
+
+                let table = Table::new(
+                    visible_rows,
+                    colunas_historico
+                        .iter()
+                        .map(|c| Constraint::Fill(c.largura)),
+                )
+                .header(
+                    Row::new(colunas_historico.iter().map(|c| t(c.titulo))).style(
+                        Style::default()
+                            .fg(cores.texto)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                )
+                .block(Block::default().borders(Borders::ALL).title(titulo));
+
+                f.render_widget(table, hist_layout[1]);
+
+                if self.editing && self.historico_entrada != EntradaHistorico::Pesquisa {
+                    let area = Layout::default()
+                        .direction(Direction::Vertical)
+                        .constraints([Constraint::Length(3), Constraint::Min(0)])
+                        .split(hist_layout[1])[0];
+                    let rotulo = if self.historico_entrada == EntradaHistorico::Etiqueta {
+                        match self.historico_marcados.len() {
+                            0 => "Etiqueta p/ a entrada selecionada (-etiqueta remove)".to_string(),
+                            n => format!("Etiqueta p/ {} entradas marcadas (-etiqueta remove)", n),
+                        }
+                    } else {
+                        t("Filtrar por etiqueta (vazio mostra todas)").to_string()
+                    };
+                    f.render_widget(Clear, area);
+                    f.render_widget(Block::default().style(fundo), area);
+                    f.render_widget(
+                        Paragraph::new(format!("{}_", self.input))
+                            .block(Block::default().borders(Borders::ALL).title(rotulo)),
+                        area,
+                    );
+                }
+
+                // Se estiver editando a busca no histórico, mostrar sugestões
+                if self.editing && self.historico_entrada == EntradaHistorico::Pesquisa {
+                    let suggest_area = {
+                        let chunks = Layout::default()
+                            .direction(Direction::Vertical)
+                            .constraints([Constraint::Length(3), Constraint::Min(5)].as_ref())
+                            .split(hist_layout[1]);
+                        chunks[0]
+                    };
+
+                    let suggestion_items: Vec<ListItem> = self
+                        .historico_search_results
+                        .iter()
+                        .enumerate()
+                        .map(|(i, (cod, dist))| {
+                            let mut style = Style::default();
+                            let mut destaque = Style::default().fg(cores.realce);
+                            if i == self.historico_search_selected {
+                                style = style.bg(cores.selecao_fundo).fg(cores.selecao_texto);
+                                destaque = Style::default();
+                            }
+                            let destaque =
+                                destaque.add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+                            let mut spans = realca(cod, self.input.trim(), destaque);
+                            spans.push(Span::raw(format!(" (dist={})", dist)));
+                            ListItem::new(Line::from(spans)).style(style)
+                        })
+                        .collect();
+
+                    let suggestion_list = List::new(suggestion_items)
+                        .block(Block::default().borders(Borders::ALL).title("Sugestões"));
+                    f.render_widget(suggestion_list, suggest_area);
+                }
+            }
+            Modo::Cadastro => {
+                let titulo = "Cadastrar Relógio";
+                let instrucao = "codigo qtd [un/cx] [custo=X preco=Y] ou codigo kit COMP:qtd";
+                let cad_layout = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Length(3), Constraint::Min(5)].as_ref())
+                    .split(main_area);
+
+                let p = Paragraph::new(format!(
+                    "{}: {}\nEnter p/confirmar cadastro se input.",
+                    instrucao, self.input
+                ))
+                .block(Block::default().borders(Borders::ALL).title(titulo));
+                f.render_widget(p, cad_layout[0]);
+
+                let altura = cad_layout[1].height.saturating_sub(3) as usize;
+                let visiveis = self
+                    .cadastro_scroll
+                    .visiveis(self.cadastro_list.len(), altura);
+                let inicio = visiveis.start;
+                let visible_data = &self.cadastro_list[visiveis];
+                let visible_rows = visible_data.iter().enumerate().map(|(i, r)| {
+                    let real_index = inicio + i;
+                    let mut base_style = Style::default();
+                    if real_index == self.cadastro_scroll.selected {
+                        base_style = base_style.bg(cores.selecao_fundo).fg(cores.selecao_texto);
+                    }
+                    if let Some(selected_cod) = &self.chosen_relogio {
+                        if selected_cod == &r.codigo {
+                            base_style = base_style.bg(cores.destaque).fg(cores.selecao_texto);
+                        }
+                    }
+                    Row::new(vec![
+                        Cell::from(r.codigo.clone()),
+                        Cell::from(self.config.formato_numeros.inteiro(r.quantidade() as i64)),
+                        Cell::from(
+                            r.unidades_por_pacote
+                                .map(|p| p.to_string())
+                                .unwrap_or_default(),
+                        ),
+                        Cell::from(
+                            r.custo
+                                .map(|c| self.config.formato_numeros.moeda(c))
+                                .unwrap_or_default(),
+                        ),
+                        Cell::from(
+                            r.preco
+                                .map(|p| self.config.formato_numeros.moeda(p))
+                                .unwrap_or_default(),
+                        ),
+                    ])
+                    .style(base_style)
+                });
+                let table = Table::new(
+                    visible_rows,
+                    &[
+                        Constraint::Percentage(30),
+                        Constraint::Percentage(15),
+                        Constraint::Percentage(15),
+                        Constraint::Percentage(20),
+                        Constraint::Percentage(20),
+                    ],
+                )
+                .header(
+                    Row::new(vec!["CÓDIGO", "QTD", "UN/CX", "CUSTO", "PREÇO"]).style(
+                        Style::default()
+                            .fg(cores.texto)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                )
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Relógios Cadastrados"),
+                );
+                f.render_widget(table, cad_layout[1]);
+            }
+            Modo::Buscar => {
+                let titulo = "Buscar Relógio";
+                let instrucao = "Digite código p/buscar, selecione resultado e Enter p/opções";
+                let search_layout = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Length(3), Constraint::Min(5)].as_ref())
+                    .split(main_area);
+
+                let p = Paragraph::new(format!("{}: {}\nESC p/ cancelar", instrucao, self.input))
+                    .block(Block::default().borders(Borders::ALL).title(titulo));
+                f.render_widget(p, search_layout[0]);
+
+                if !self.input.is_empty() {
+                    let altura = search_layout[1].height.saturating_sub(3) as usize;
+                    let visiveis = self
+                        .buscar_scroll
+                        .visiveis(self.buscar_results.len(), altura);
+                    let inicio = visiveis.start;
+                    let visible_data = &self.buscar_results[visiveis];
+
+                    let visible_rows =
+                        visible_data
+                            .iter()
+                            .enumerate()
+                            .map(|(i, (cod, qtd, dist))| {
+                                let real_index = inicio + i;
+                                let mut base_style = Style::default();
+                                if real_index == self.buscar_scroll.selected {
+                                    base_style =
+                                        base_style.bg(cores.selecao_fundo).fg(cores.selecao_texto);
+                                }
+                                if let Some(selected_cod) = &self.chosen_relogio {
+                                    if selected_cod == cod {
+                                        base_style =
+                                            base_style.bg(cores.destaque).fg(cores.selecao_texto);
+                                    }
+                                }
+                                let promo =
+                                    self.relogios.get(cod).and_then(|r| self.promocao_ativa(r));
+                                if promo.is_some() && real_index != self.buscar_scroll.selected {
+                                    base_style = base_style.fg(cores.marcado);
+                                }
+                                // Caracteres que casaram com a busca
+                                let destaque = if real_index == self.buscar_scroll.selected {
+                                    Style::default()
+                                } else {
+                                    Style::default().fg(cores.realce)
+                                }
+                                .add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+                                let mut rotulo = realca(cod, &self.input, destaque);
+                                if let Some(p) = promo {
+                                    rotulo.push(Span::raw(format!("  -{}%", p.desconto)));
+                                }
+                                Row::new(vec![
+                                    Cell::from(Line::from(rotulo)),
+                                    Cell::from(self.config.formato_numeros.inteiro(*qtd as i64)),
+                                    Cell::from(dist.to_string()),
+                                ])
+                                .style(base_style)
+                            });
+                    let table = Table::new(
+                        visible_rows,
+                        &[
+                            Constraint::Percentage(50),
+                            Constraint::Percentage(20),
+                            Constraint::Percentage(30),
+                        ],
+                    )
+                    .header(
+                        Row::new(vec!["CÓDIGO", "QTD", "DIST"]).style(
+                            Style::default()
+                                .fg(cores.texto)
+                                .add_modifier(Modifier::BOLD),
+                        ),
+                    )
+                    .block(Block::default().borders(Borders::ALL).title(format!(
+                        "Resultados ({} de {})",
+                        self.buscar_results.len(),
+                        self.buscar_total
+                    )));
+                    f.render_widget(table, search_layout[1]);
+                } else {
+                    let info = Paragraph::new("Digite algo para buscar.")
+                        .block(Block::default().borders(Borders::ALL).title("Resultados"));
+                    f.render_widget(info, search_layout[1]);
+                }
+            }
+            Modo::Grafico => {
+                let tab_titles: Vec<Span> = GraficoTab::titles()
+                    .iter()
+                    .enumerate()
+                    .map(|(i, &t)| {
+                        let style = if i == self.grafico_tab.indice() {
+                            Style::default()
+                                .fg(cores.realce)
+                                .add_modifier(Modifier::BOLD)
+                        } else {
+                            Style::default()
+                        };
+                        Span::styled(t, style)
+                    })
+                    .collect();
+                let tabs =
+                    Tabs::new(tab_titles).block(Block::default().borders(Borders::ALL).title(
+                        if self.demonstracao.is_some() {
+                            "Demonstração (qualquer tecla volta)"
+                        } else {
+                            "Visão (←/→, E exporta)"
+                        },
+                    ));
+                let graf_area = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Length(3), Constraint::Min(5)].as_ref())
+                    .split(main_area);
+                f.render_widget(tabs, graf_area[0]);
+                let main_area = graf_area[1];
+
+                if self.grafico_tab == GraficoTab::Padrao {
+                    let (rotulo, desde) = self.periodo_grafico();
+                    let (semana, horas) = analise::padrao_de_vendas(&self.historico, desde);
+                    let dados_semana: Vec<(&str, u64)> =
+                        DIAS_SEMANA.iter().copied().zip(semana).collect();
+                    let faixa = horas_com_venda(&horas);
+                    let rotulos_hora: Vec<String> =
+                        faixa.clone().map(|h| format!("{:02}", h)).collect();
+                    let dados_hora: Vec<(&str, u64)> = rotulos_hora
+                        .iter()
+                        .map(|r| r.as_str())
+                        .zip(horas[faixa].iter().copied())
+                        .collect();
+                    let layout = Layout::default()
+                        .direction(Direction::Vertical)
+                        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                        .split(main_area);
+                    let chart_semana = ratatui::widgets::BarChart::default()
+                        .block(Block::default().borders(Borders::ALL).title(format!(
+                            "Unidades por dia da semana, {} (↑/↓ período)",
+                            rotulo
+                        )))
+                        .data(&dados_semana)
+                        .bar_width(4)
+                        .bar_style(Style::default().fg(cores.realce));
+                    let chart_hora = ratatui::widgets::BarChart::default()
+                        .block(
+                            Block::default()
+                                .borders(Borders::ALL)
+                                .title("Unidades por hora do dia"),
+                        )
+                        .data(&dados_hora)
+                        .bar_width(2)
+                        .bar_style(Style::default().fg(cores.destaque));
+                    f.render_widget(chart_semana, layout[0]);
+                    f.render_widget(chart_hora, layout[1]);
+                } else if matches!(
+                    self.grafico_tab,
+                    GraficoTab::Categorias | GraficoTab::Marcas | GraficoTab::Produtos
+                ) {
+                    let (rotulo, _) = self.periodo_grafico();
+                    let tab = self.grafico_tab;
+                    let grupos = self.vendas_por_grupo_grafico();
+                    let total: f64 = grupos.iter().map(|(_, v)| v).sum();
+                    // Barra proporcional à fatia de cada grupo na receita
+                    let largura = main_area.width.saturating_sub(40) as f64;
+                    let cores = [
+                        cores.realce,
+                        cores.marcado,
+                        cores.destaque,
+                        cores.entrada,
+                        cores.texto,
+                    ];
+                    let linhas: Vec<Row> = grupos
+                        .iter()
+                        .enumerate()
+                        .map(|(i, (nome, valor))| {
+                            let fatia = if total > 0.0 { valor / total } else { 0.0 };
+                            let barra = "█".repeat((fatia * largura).round() as usize);
+                            Row::new(vec![
+                                Cell::from(nome.clone()),
+                                Cell::from(barra)
+                                    .style(Style::default().fg(cores[i % cores.len()])),
+                                Cell::from(format!(
+                                    "{:>5}%",
+                                    self.config.formato_numeros.numero(fatia * 100.0, 1)
+                                )),
+                                Cell::from(self.config.formato_numeros.moeda(*valor)),
+                            ])
+                        })
+                        .collect();
+                    let grupo = tab.grupo();
+                    let tabela = Table::new(
+                        linhas,
+                        &[
+                            Constraint::Length(14),
+                            Constraint::Min(5),
+                            Constraint::Length(6),
+                            Constraint::Length(14),
+                        ],
+                    )
+                    .block(Block::default().borders(Borders::ALL).title(format!(
+                        "Vendas por {}, {} (↑/↓ período) — total {}",
+                        grupo,
+                        rotulo,
+                        self.config.formato_numeros.moeda(total)
+                    )));
+                    f.render_widget(tabela, main_area);
+                } else if self.grafico_tab == GraficoTab::MesAMes && self.grafico_acumulado {
+                    let hoje = Local::now().date_naive();
+                    let (atual, passado) =
+                        analise::acumulado_do_mes(&self.historico, &self.relogios, hoje);
+                    let mes_passado = hoje
+                        .with_day(1)
+                        .and_then(|d| d.checked_sub_months(chrono::Months::new(1)))
+                        .unwrap_or(hoje);
+                    let pontos = |dias: &[(i32, f64)], receita: bool| -> Vec<(f64, f64)> {
+                        dias.iter()
+                            .enumerate()
+                            .map(|(i, (u, r))| (i as f64, if receita { *r } else { *u as f64 }))
+                            .collect()
+                    };
+                    let (receita_atual, receita_passada) =
+                        (pontos(&atual, true), pontos(&passado, true));
+                    let (unidades_atual, unidades_passada) =
+                        (pontos(&atual, false), pontos(&passado, false));
+                    let ultimo_dia = atual.len().max(passado.len());
+                    let rotulos_x = || {
+                        [1, ultimo_dia.div_ceil(2), ultimo_dia]
+                            .iter()
+                            .map(|d| Span::raw(d.to_string()))
+                            .collect::<Vec<Span>>()
+                    };
+                    // Mesmo dia do mês passado (ou o último, em meses mais curtos)
+                    let hoje_atual = atual.last().copied().unwrap_or_default();
+                    let hoje_passado = passado
+                        .get(atual.len().saturating_sub(1))
+                        .or(passado.last())
+                        .copied()
+                        .unwrap_or_default();
+                    let numeros = self.config.formato_numeros;
+                    let ritmo = if hoje_passado.1 > 0.0 {
+                        let variacao = (hoje_atual.1 / hoje_passado.1 - 1.0) * 100.0;
+                        format!(
+                            " ({}{}%)",
+                            if variacao >= 0.0 { "+" } else { "" },
+                            numeros.numero(variacao, 1)
+                        )
+                    } else {
+                        String::new()
+                    };
+                    let layout = Layout::default()
+                        .direction(Direction::Vertical)
+                        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+                        .split(main_area);
+                    f.render_widget(
+                        grafico_comparativo(
+                            cores,
+                            format!(
+                                "Receita no mês: {} × {} até o dia {}{}",
+                                numeros.moeda(hoje_atual.1),
+                                numeros.moeda(hoje_passado.1),
+                                atual.len(),
+                                ritmo
+                            ),
+                            (rotulo_mes(hoje), &receita_atual),
+                            (rotulo_mes(mes_passado), &receita_passada),
+                            rotulos_x(),
+                            |v| numeros.moeda(v),
+                        ),
+                        layout[0],
+                    );
+                    f.render_widget(
+                        grafico_comparativo(
+                            cores,
+                            format!(
+                                "Unidades no mês: {} × {} (↑/↓ 12 meses)",
+                                hoje_atual.0, hoje_passado.0
+                            ),
+                            (rotulo_mes(hoje), &unidades_atual),
+                            (rotulo_mes(mes_passado), &unidades_passada),
+                            rotulos_x(),
+                            |v| format!("{:.0}", v),
+                        ),
+                        layout[1],
+                    );
+                } else if self.grafico_tab == GraficoTab::MesAMes {
+                    let meses = analise::vendas_mes_a_mes(
+                        &self.historico,
+                        &self.relogios,
+                        Local::now().date_naive(),
+                    );
+                    let atual: Vec<(f64, f64)> = meses
+                        .iter()
+                        .enumerate()
+                        .map(|(i, (_, v, _))| (i as f64, *v))
+                        .collect();
+                    let anterior: Vec<(f64, f64)> = meses
+                        .iter()
+                        .enumerate()
+                        .map(|(i, (_, _, v))| (i as f64, *v))
+                        .collect();
+                    let rotulos_x: Vec<Span> = [0, 6, 11]
+                        .iter()
+                        .filter_map(|i| meses.get(*i))
+                        .map(|(d, _, _)| Span::raw(rotulo_mes(*d)))
+                        .collect();
+                    let ano = |(d, _, _): &(NaiveDate, f64, f64)| d.year();
+                    let (primeiro, ultimo) = (
+                        meses.first().map(ano).unwrap_or_default(),
+                        meses.last().map(ano).unwrap_or_default(),
+                    );
+                    let numeros = self.config.formato_numeros;
+                    f.render_widget(
+                        grafico_comparativo(
+                            cores,
+                            "Vendas por mês: últimos 12 meses × ano anterior (↑/↓ mês atual)"
+                                .into(),
+                            (format!("{}–{}", primeiro, ultimo), &atual),
+                            (format!("{}–{}", primeiro - 1, ultimo - 1), &anterior),
+                            rotulos_x,
+                            |v| numeros.moeda(v),
+                        ),
+                        main_area,
+                    );
+                } else if self.grafico_tab == GraficoTab::ValorEstoque {
+                    let maximo = self
+                        .grafico_valor
+                        .iter()
+                        .map(|(_, v)| *v)
+                        .fold(0.0, f64::max)
+                        .max(1.0);
+                    let pontos: Vec<(f64, f64)> = self
+                        .grafico_valor
+                        .iter()
+                        .enumerate()
+                        .map(|(i, (_, v))| (i as f64, *v))
+                        .collect();
+                    let n = pontos.len().max(1);
+                    let rotulos_x: Vec<Span> = [0, n / 2, n - 1]
+                        .iter()
+                        .filter_map(|i| self.grafico_valor.get(*i))
+                        .map(|(r, _)| Span::raw(r.clone()))
+                        .collect();
+                    let rotulos_y: Vec<Span> = [0.0, maximo / 2.0, maximo]
+                        .iter()
+                        .map(|v| Span::raw(self.config.formato_numeros.moeda(*v)))
+                        .collect();
+                    let serie = ratatui::widgets::Dataset::default()
+                        .marker(ratatui::symbols::Marker::Braille)
+                        .graph_type(ratatui::widgets::GraphType::Line)
+                        .style(Style::default().fg(cores.realce))
+                        .data(&pontos);
+                    let periodo = if self.grafico_mensal {
+                        "por mês"
+                    } else {
+                        "por semana"
+                    };
+                    let chart = ratatui::widgets::Chart::new(vec![serie])
+                        .block(Block::default().borders(Borders::ALL).title(format!(
+                            "Valor do estoque ao custo, {} (↑/↓ mês/semana)",
+                            periodo
+                        )))
+                        .x_axis(
+                            ratatui::widgets::Axis::default()
+                                .bounds([0.0, (n - 1).max(1) as f64])
+                                .labels(rotulos_x),
+                        )
+                        .y_axis(
+                            ratatui::widgets::Axis::default()
+                                .bounds([0.0, maximo * 1.05])
+                                .labels(rotulos_y),
+                        );
+                    f.render_widget(chart, main_area);
+                } else {
+                    let dia_data = self.agrupamento_por_dia();
+
+                    // Monta os dados em formato (&str, u64) para o BarChart
+                    let vendas_data: Vec<(&str, u64)> = dia_data
+                        .iter()
+                        .map(|(d, v, _c)| (d.as_str(), *v as u64))
+                        .collect();
+                    let compras_data: Vec<(&str, u64)> = dia_data
+                        .iter()
+                        .map(|(d, _v, c)| (d.as_str(), *c as u64))
+                        .collect();
+
+                    let (cor_entrada, cor_saida) = (cores.entrada, cores.saida);
+                    let graf_layout = Layout::default()
+                        .direction(Direction::Horizontal)
+                        .constraints(
+                            [Constraint::Percentage(50), Constraint::Percentage(50)].as_ref(),
+                        )
+                        .split(main_area);
+                    let vendas_chart = ratatui::widgets::BarChart::default()
+                        .block(
+                            Block::default()
+                                .borders(Borders::ALL)
+                                .title("▼ Vendas (Últimos 7 dias)"),
+                        )
+                        .data(&vendas_data)
+                        .bar_width(5)
+                        .bar_style(Style::default().fg(cor_saida))
+                        .value_style(
+                            Style::default()
+                                .fg(cores.texto)
+                                .add_modifier(Modifier::BOLD),
+                        );
+
+                    let compras_chart = ratatui::widgets::BarChart::default()
+                        .block(
+                            Block::default()
+                                .borders(Borders::ALL)
+                                .title("▲ Compras (Últimos 7 dias)"),
+                        )
+                        .data(&compras_data)
+                        .bar_width(5)
+                        .bar_style(Style::default().fg(cor_entrada))
+                        .value_style(
+                            Style::default()
+                                .fg(cores.texto)
+                                .add_modifier(Modifier::BOLD),
+                        );
+
+                    f.render_widget(vendas_chart, graf_layout[0]);
+                    f.render_widget(compras_chart, graf_layout[1]);
+                }
+            }
+            Modo::ImportarCatalogo => {
+                let imp_layout = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Length(3), Constraint::Min(5)].as_ref())
+                    .split(main_area);
+
+                let instrucao = if self.editing {
+                    format!("Arquivo CSV [limite %]: {}", self.input)
+                } else {
+                    "Espaço aceita/recusa, D simula, Enter aplica, Esc cancela".to_string()
+                };
+                let p = Paragraph::new(instrucao).block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Importar Catálogo do Distribuidor"),
+                );
+                f.render_widget(p, imp_layout[0]);
+
+                let altura = imp_layout[1].height.saturating_sub(3) as usize;
+                let visiveis = self
+                    .importacao_scroll
+                    .visiveis(self.importacao.len(), altura);
+                let inicio = visiveis.start;
+                let visible_data = &self.importacao[visiveis];
+                let visible_rows = visible_data.iter().enumerate().map(|(i, a)| {
+                    let real_index = inicio + i;
+                    let mut base_style = if a.sinalizado {
+                        Style::default().fg(cores.destaque)
+                    } else {
+                        Style::default()
+                    };
+                    if real_index == self.importacao_scroll.selected {
+                        base_style = base_style.add_modifier(Modifier::REVERSED);
+                    }
+                    let numeros = self.config.formato_numeros;
+                    let anterior = a
+                        .custo_anterior
+                        .map(|c| numeros.numero(c, 2))
+                        .unwrap_or_else(|| "-".into());
+                    let variacao = match a.variacao() {
+                        Some(v) if v >= 0.0 => format!("+{}%", numeros.numero(v, 1)),
+                        Some(v) => format!("{}%", numeros.numero(v, 1)),
+                        None if a.novo => "novo".into(),
+                        None => "-".into(),
+                    };
+                    Row::new(vec![
+                        Cell::from(if a.aceito { "[x]" } else { "[ ]" }),
+                        Cell::from(a.codigo.clone()),
+                        Cell::from(a.descricao.clone()),
+                        Cell::from(anterior),
+                        Cell::from(numeros.numero(a.custo_novo, 2)),
+                        Cell::from(variacao),
+                    ])
+                    .style(base_style)
+                });
+                let table = Table::new(
+                    visible_rows,
+                    &[
+                        Constraint::Length(4),
+                        Constraint::Percentage(20),
+                        Constraint::Percentage(35),
+                        Constraint::Percentage(15),
+                        Constraint::Percentage(15),
+                        Constraint::Percentage(15),
+                    ],
+                )
+                .header(
+                    Row::new(vec![
+                        "OK",
+                        "CÓDIGO",
+                        "DESCRIÇÃO",
+                        "CUSTO ANT.",
+                        "CUSTO NOVO",
+                        "VAR.",
+                    ])
+                    .style(
+                        Style::default()
+                            .fg(cores.texto)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                )
+                .block(Block::default().borders(Borders::ALL).title("Revisão"));
+                f.render_widget(table, imp_layout[1]);
+            }
+            Modo::Compra => {
+                let instrucao = "Digite codigo quantidade [caixas] [fornecedor=nome], Enter p/ confirmar, Esc p/ cancelar";
+                let custo = self
+                    .linha_sugestao()
+                    .or_else(|| {
+                        self.input
+                            .split_whitespace()
+                            .next()
+                            .and_then(|c| self.resumo_custo(c))
+                    })
+                    .unwrap_or_default();
+                let p = Paragraph::new(format!("{}: {}\n{}", instrucao, self.input, custo)).block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Adicionar Estoque"),
+                );
+                f.render_widget(p, main_area);
+            }
+            Modo::Configuracoes => {
+                let rows = config::CHAVES.iter().enumerate().map(|(i, chave)| {
+                    let secreta = config::secreta(chave);
+                    let valor = if self.editing && i == self.config_selected {
+                        let rotulo = if self.confere_senha_atual {
+                            "senha atual: "
+                        } else {
+                            ""
+                        };
+                        format!("{}{}_", rotulo, oculta(&self.input))
+                    } else if secreta {
+                        oculta(&self.config.valor(chave))
+                    } else {
+                        self.config.valor(chave)
+                    };
+                    let style = if i == self.config_selected {
+                        Style::default()
+                            .bg(cores.selecao_fundo)
+                            .fg(cores.selecao_texto)
+                    } else {
+                        Style::default()
+                    };
+                    Row::new(vec![Cell::from(*chave), Cell::from(valor)]).style(style)
+                });
+                let table = Table::new(
+                    rows,
+                    &[Constraint::Percentage(40), Constraint::Percentage(60)],
+                )
+                .header(
+                    Row::new(vec!["CHAVE", "VALOR"]).style(
+                        Style::default()
+                            .fg(cores.texto)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                )
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Configurações (Enter edita/salva, Esc sai)"),
+                );
+                f.render_widget(table, main_area);
+            }
+            Modo::MapearCsv => {
+                if let Some(arquivo) = &self.importacao_csv {
+                    let m = &arquivo.mapeamento;
+                    let layout = Layout::default()
+                        .direction(Direction::Vertical)
+                        .constraints([
+                            Constraint::Length(4),
+                            Constraint::Length(catalogo::CAMPOS.len() as u16 + 2),
+                            Constraint::Min(4),
+                        ])
+                        .split(main_area);
+                    let separador = match m.separador {
+                        '\t' => "tab".to_string(),
+                        s => s.to_string(),
+                    };
+                    let opcoes = Paragraph::new(format!(
+                        "Separador: {} [S]  Codificação: {} [E]  Cabeçalho: {} [C]\n\
+                     ↑/↓ campo, ←/→ coluna, Enter revisa, Esc cancela",
+                        separador,
+                        m.codificacao.nome(),
+                        if m.cabecalho { "sim" } else { "não" }
+                    ))
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .title(format!("Mapear colunas: {}", arquivo.caminho)),
+                    );
+                    f.render_widget(opcoes, layout[0]);
+
+                    let brutas = catalogo::linhas_brutas(&arquivo.bytes, m);
+                    let nomes: Vec<String> = match brutas.first() {
+                        Some((_, campos)) if m.cabecalho => campos.clone(),
+                        _ => vec![],
+                    };
+                    let campos = catalogo::CAMPOS.iter().enumerate().map(|(i, campo)| {
+                        let coluna = match m.colunas[i] {
+                            Some(c) => match nomes.get(c) {
+                                Some(nome) => format!("{} ({})", c + 1, nome),
+                                None => (c + 1).to_string(),
+                            },
+                            None => "-".into(),
+                        };
+                        let style = if i == self.mapeamento_campo {
+                            Style::default()
+                                .bg(cores.selecao_fundo)
+                                .fg(cores.selecao_texto)
+                        } else {
+                            Style::default()
+                        };
+                        Row::new(vec![campo.to_string(), coluna]).style(style)
+                    });
+                    let tabela_campos = Table::new(
+                        campos,
+                        &[Constraint::Percentage(30), Constraint::Percentage(70)],
+                    )
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .title("Campo → coluna"),
+                    );
+                    f.render_widget(tabela_campos, layout[1]);
+
+                    // Prévia das primeiras linhas com o campo atribuído a cada coluna
+                    let total = brutas
+                        .iter()
+                        .map(|(_, c)| c.len())
+                        .max()
+                        .unwrap_or(0)
+                        .min(8);
+                    let titulo_coluna = |c: usize| {
+                        let campo = m.colunas.iter().position(|x| *x == Some(c));
+                        match campo {
+                            Some(i) => format!("{}:{}", c + 1, catalogo::CAMPOS[i]),
+                            None => (c + 1).to_string(),
+                        }
+                    };
+                    let pula = usize::from(m.cabecalho);
+                    let previa = brutas.iter().skip(pula).take(5).map(|(_, campos)| {
+                        Row::new((0..total).map(|c| campos.get(c).cloned().unwrap_or_default()))
+                    });
+                    let larguras = vec![Constraint::Ratio(1, total.max(1) as u32); total];
+                    let tabela_previa = Table::new(previa, larguras)
+                        .header(
+                            Row::new((0..total).map(titulo_coluna))
+                                .style(Style::default().add_modifier(Modifier::BOLD)),
+                        )
+                        .block(Block::default().borders(Borders::ALL).title("Prévia"));
+                    f.render_widget(tabela_previa, layout[2]);
+                }
+            }
+            Modo::Admin => {
+                let layout = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([
+                        Constraint::Length((self.admin_tokens.len() as u16 + 3).min(10)),
+                        Constraint::Min(3),
+                    ])
+                    .split(main_area);
+                let hoje = chrono::Local::now().format("%Y-%m-%d").to_string();
+                let linhas_tokens = self.admin_tokens.iter().map(|t| {
+                    let do_dia = self.admin_acessos.iter().filter(|a| {
+                        a.token.as_ref() == Some(&t.nome) && a.timestamp.starts_with(&hoje)
+                    });
+                    let (total, negados) = do_dia.fold((0, 0), |(n, neg), a| {
+                        (n + 1, neg + usize::from(a.status >= 400))
+                    });
+                    Row::new(vec![
+                        t.nome.clone(),
+                        t.escopo.nome().to_string(),
+                        t.criado.clone(),
+                        total.to_string(),
+                        negados.to_string(),
+                    ])
+                });
+                let tabela_tokens = Table::new(
+                    linhas_tokens,
+                    &[
+                        Constraint::Percentage(25),
+                        Constraint::Percentage(15),
+                        Constraint::Percentage(30),
+                        Constraint::Percentage(15),
+                        Constraint::Percentage(15),
+                    ],
+                )
+                .header(
+                    Row::new(vec!["TOKEN", "ESCOPO", "CRIADO", "HOJE", "NEGADOS"])
+                        .style(Style::default().add_modifier(Modifier::BOLD)),
+                )
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Tokens da API (Relogio token criar/revogar)"),
+                );
+                f.render_widget(tabela_tokens, layout[0]);
+
+                let altura = layout[1].height.saturating_sub(3) as usize;
+                let visiveis = self.admin_scroll.visiveis(self.admin_acessos.len(), altura);
+                let inicio = visiveis.start;
+                let linhas = self.admin_acessos[visiveis]
+                    .iter()
+                    .enumerate()
+                    .map(|(i, a)| {
+                        let mut style = if a.status >= 400 {
+                            Style::default().fg(cores.saida)
+                        } else {
+                            Style::default()
+                        };
+                        if inicio + i == self.admin_scroll.selected {
+                            style = style.bg(cores.selecao_fundo).fg(cores.selecao_texto);
+                        }
+                        Row::new(vec![
+                            a.timestamp.clone(),
+                            a.token.clone().unwrap_or_else(|| "-".into()),
+                            a.ip.clone(),
+                            format!("{} {}", a.metodo, a.caminho),
+                            if a.status >= 400 {
+                                format!("✗ {}", a.status)
+                            } else {
+                                a.status.to_string()
+                            },
+                        ])
+                        .style(style)
+                    });
+                let tabela = Table::new(
+                    linhas,
+                    &[
+                        Constraint::Length(19),
+                        Constraint::Percentage(15),
+                        Constraint::Percentage(20),
+                        Constraint::Percentage(35),
+                        Constraint::Length(6),
+                    ],
+                )
+                .header(
+                    Row::new(vec!["QUANDO", "TOKEN", "IP", "REQUISIÇÃO", "STATUS"])
+                        .style(Style::default().add_modifier(Modifier::BOLD)),
+                )
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Acessos recentes (K recarrega, Esc sai)"),
+                );
+                f.render_widget(tabela, layout[1]);
+            }
+            Modo::Venda => {
+                let instrucao = "Digite codigo quantidade [dinheiro|cartao|pix|credito], Enter p/ confirmar, Esc p/ cancelar";
+                let margem = self
+                    .input
+                    .split_whitespace()
+                    .next()
+                    .and_then(|c| self.preco_e_custo(c))
+                    .map(|(preco, custo)| {
+                        let alerta = if preco < custo {
+                            "  ABAIXO DO CUSTO"
+                        } else {
+                            ""
+                        };
+                        format!(
+                            "Preço: {} | Custo: {}{}",
+                            self.config.formato_numeros.moeda(preco),
+                            self.config.formato_numeros.moeda(custo),
+                            alerta
+                        )
+                    })
+                    .or_else(|| self.linha_sugestao())
+                    .unwrap_or_default();
+                let titulo = match &self.cliente_atendido {
+                    Some(nome) => format!("Vender Relógio — cliente: {}", nome),
+                    None => "Vender Relógio".to_string(),
+                };
+                let p = Paragraph::new(format!(
+                    "{}: {}\n{}",
+                    instrucao,
+                    oculta_senha(&self.input),
+                    margem
+                ))
+                .block(Block::default().borders(Borders::ALL).title(titulo));
+                f.render_widget(p, main_area);
+            }
+            Modo::ResgateCredito => {
+                let instrucao =
+                    "Digite o nome do cliente, Enter p/ pagar com crédito, Esc p/ cancelar";
+                let saldo = credito::saldo(&self.registros.creditos, &self.input);
+                let p = Paragraph::new(format!(
+                    "{}: {}\nSaldo: {}",
+                    instrucao,
+                    self.input,
+                    self.config.formato_numeros.moeda(saldo)
+                ))
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Pagamento com Crédito"),
+                );
+                f.render_widget(p, main_area);
+            }
+            Modo::Recado => {
+                let instrucao =
+                    "Digite autor;recado p/ o próximo turno, Enter p/ guardar, Esc p/ cancelar";
+                let p = Paragraph::new(format!("{}: {}\n", instrucao, self.input))
+                    .wrap(Wrap { trim: true })
+                    .block(Block::default().borders(Borders::ALL).title("Recado"));
+                f.render_widget(p, main_area);
+            }
+            Modo::Recebimento => {
+                let Some(rec) = &self.recebimento else {
+                    return;
+                };
+                let layout = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([
+                        Constraint::Length(3),
+                        Constraint::Min(3),
+                        Constraint::Length(3),
+                    ])
+                    .split(main_area);
+                let (titulo, instrucao) = match rec.etapa {
+                    recebimento::Etapa::Fornecedor => (
+                        "1/3 Fornecedor",
+                        format!("Fornecedor (opcional), Enter segue: {}", self.input),
+                    ),
+                    recebimento::Etapa::Itens => (
+                        "2/3 Itens",
+                        format!(
+                            "Leia ou digite codigo [qtd]; Enter vazio revisa: {}",
+                            self.input
+                        ),
+                    ),
+                    recebimento::Etapa::Revisao => (
+                        "3/3 Revisão",
+                        "Enter lança tudo, Del tira a linha, Esc volta aos itens".to_string(),
+                    ),
+                };
+                f.render_widget(
+                    Paragraph::new(instrucao).block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .title(format!("Receber Mercadoria — {} (Esc cancela)", titulo)),
+                    ),
+                    layout[0],
+                );
+                let formato = self.config.formato_numeros;
+                let altura = layout[1].height.saturating_sub(3) as usize;
+                let visiveis = self.recebimento_scroll.visiveis(rec.itens.len(), altura);
+                let inicio = visiveis.start;
+                let revisao = rec.etapa == recebimento::Etapa::Revisao;
+                let linhas = rec.itens[visiveis].iter().enumerate().map(|(i, (c, q))| {
+                    let r = self.relogios.get(c);
+                    let atual = r.map_or(0, |r| r.quantidade());
+                    let style = if revisao && inicio + i == self.recebimento_scroll.selected {
+                        Style::default()
+                            .bg(cores.selecao_fundo)
+                            .fg(cores.selecao_texto)
+                    } else {
+                        Style::default()
+                    };
+                    Row::new(vec![
+                        c.clone(),
+                        r.map(|r| r.descricao.clone()).unwrap_or_default(),
+                        formato.inteiro(*q as i64),
+                        format!(
+                            "{} → {}",
+                            formato.inteiro(atual as i64),
+                            formato.inteiro((atual + q) as i64)
+                        ),
+                    ])
+                    .style(style)
+                });
+                f.render_widget(
+                    Table::new(
+                        linhas,
+                        [
+                            Constraint::Percentage(20),
+                            Constraint::Percentage(45),
+                            Constraint::Percentage(10),
+                            Constraint::Percentage(25),
+                        ],
+                    )
+                    .header(
+                        Row::new(vec!["CÓDIGO", "DESCRIÇÃO", "QTD", "ESTOQUE"])
+                            .style(Style::default().add_modifier(Modifier::BOLD)),
+                    )
+                    .block(Block::default().borders(Borders::ALL).title(format!(
+                        "Entrega{}",
+                        rec.fornecedor
+                            .as_ref()
+                            .map(|f| format!(" de {}", f))
+                            .unwrap_or_default()
+                    ))),
+                    layout[1],
+                );
+                f.render_widget(
+                    Paragraph::new(format!(
+                        "{} itens, {} unidades, custo {}",
+                        rec.itens.len(),
+                        formato.inteiro(rec.unidades() as i64),
+                        formato.moeda(rec.custo(&self.relogios))
+                    ))
+                    .block(Block::default().borders(Borders::ALL).title("Totais")),
+                    layout[2],
+                );
+            }
+            Modo::Pedidos if self.pedidos_sugestao => {
+                let layout = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Min(3), Constraint::Length(3)])
+                    .split(main_area);
+                let lista = self.sugestoes_reposicao();
+                let formato = self.config.formato_numeros;
+                let altura = layout[0].height.saturating_sub(3) as usize;
+                let visiveis = self.pedidos_scroll.visiveis(lista.len(), altura);
+                let inicio = visiveis.start;
+                let linhas = lista[visiveis].iter().enumerate().map(|(i, s)| {
+                    let style = if inicio + i == self.pedidos_scroll.selected {
+                        Style::default()
+                            .bg(cores.selecao_fundo)
+                            .fg(cores.selecao_texto)
+                    } else if s.saldo <= 0 {
+                        Style::default().fg(cores.alerta)
+                    } else {
+                        Style::default()
+                    };
+                    Row::new(vec![
+                        s.codigo.clone(),
+                        formato.inteiro(s.saldo as i64),
+                        formato.inteiro(s.em_pedido as i64),
+                        format!("{}/{}", s.minimo, s.alvo),
+                        formato.inteiro(s.quantidade as i64),
+                        s.fornecedor.clone().unwrap_or_else(|| "?".into()),
+                    ])
+                    .style(style)
+                });
+                let tabela = Table::new(
+                    linhas,
+                    &[
+                        Constraint::Percentage(18),
+                        Constraint::Percentage(12),
+                        Constraint::Percentage(12),
+                        Constraint::Percentage(16),
+                        Constraint::Percentage(12),
+                        Constraint::Percentage(30),
+                    ],
+                )
+                .header(
+                    Row::new(vec![
+                        "CÓDIGO",
+                        "SALDO",
+                        "PEDIDO",
+                        "MÍN/ALVO",
+                        "PEDIR",
+                        "FORNECEDOR",
+                    ])
+                    .style(Style::default().add_modifier(Modifier::BOLD)),
+                )
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Reposição (N rascunho do pedido, Tab pendências, Esc volta)"),
+                );
+                f.render_widget(tabela, layout[0]);
+                let entrada = if self.editing {
+                    format!("fornecedor codigo:qtd [codigo:qtd ...]: {}", self.input)
+                } else {
+                    format!(
+                        "{} produtos no ponto de pedido; pedir = alvo - saldo - já pedido.",
+                        lista.len()
+                    )
+                };
+                f.render_widget(
+                    Paragraph::new(entrada).block(Block::default().borders(Borders::ALL)),
+                    layout[1],
+                );
+            }
+            Modo::Pedidos => {
+                let rascunhos = &self.registros.rascunhos_pedido;
+                let altura_rascunhos = if rascunhos.is_empty() {
+                    0
+                } else {
+                    rascunhos.len().min(4) as u16 + 2
+                };
+                let partes = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Length(altura_rascunhos), Constraint::Min(6)])
+                    .split(main_area);
+                if !rascunhos.is_empty() {
+                    let linhas: Vec<Line> = rascunhos
+                        .iter()
+                        .map(|r| {
+                            let itens: Vec<String> = r
+                                .itens
+                                .iter()
+                                .map(|i| format!("{}:{}", i.codigo, i.quantidade))
+                                .collect();
+                            Line::from(format!(
+                                "Recorrente nº {}  ciclo {}  {}  {}",
+                                r.recorrente,
+                                Self::formata_data_ddmm(&r.ciclo),
+                                r.fornecedor,
+                                itens.join(" ")
+                            ))
+                        })
+                        .collect();
+                    f.render_widget(
+                        Paragraph::new(linhas).block(Block::default().borders(Borders::ALL).title(
+                            "Rascunhos de pedidos recorrentes (C confirma o primeiro, K pula)",
+                        )),
+                        partes[0],
+                    );
+                }
+                let layout = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Min(3), Constraint::Length(3)])
+                    .split(partes[1]);
+                let lista =
+                    pedidos::pendencias(&self.registros.pedidos_compra, Local::now().date_naive());
+                let prazo = self.config.prazo_reposicao_dias;
+                let formato = self.config.formato_numeros;
+                let altura = layout[0].height.saturating_sub(3) as usize;
+                let visiveis = self.pedidos_scroll.visiveis(lista.len(), altura);
+                let inicio = visiveis.start;
+                let linhas = lista[visiveis].iter().enumerate().map(|(i, p)| {
+                    // Atrasado: passou do prazo de reposição configurado
+                    let style = if inicio + i == self.pedidos_scroll.selected {
+                        Style::default()
+                            .bg(cores.selecao_fundo)
+                            .fg(cores.selecao_texto)
+                    } else if p.dias > prazo {
+                        Style::default().fg(cores.alerta)
+                    } else {
+                        Style::default()
+                    };
+                    Row::new(vec![
+                        p.numero.to_string(),
+                        p.fornecedor.clone(),
+                        p.codigo.clone(),
+                        format!(
+                            "{}/{}",
+                            formato.inteiro(p.recebido as i64),
+                            formato.inteiro(p.quantidade as i64)
+                        ),
+                        formato.inteiro((p.quantidade - p.recebido) as i64),
+                        format!("{} d", p.dias),
+                    ])
+                    .style(style)
+                });
+                let tabela = Table::new(
+                    linhas,
+                    &[
+                        Constraint::Percentage(10),
+                        Constraint::Percentage(30),
+                        Constraint::Percentage(18),
+                        Constraint::Percentage(16),
+                        Constraint::Percentage(12),
+                        Constraint::Percentage(14),
+                    ],
+                )
+                .header(
+                    Row::new(vec![
+                        "Nº",
+                        "FORNECEDOR",
+                        "CÓDIGO",
+                        "CHEGOU",
+                        "FALTA",
+                        "ABERTO",
+                    ])
+                    .style(Style::default().add_modifier(Modifier::BOLD)),
+                )
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Pedidos pendentes (N novo, D cancela, Tab reposição, Esc volta)"),
+                );
+                f.render_widget(tabela, layout[0]);
+                let entrada = if self.editing {
+                    format!(
+                        "[semanal|quinzenal|mensal] fornecedor codigo:qtd [codigo:qtd ...]: {}",
+                        self.input
+                    )
+                } else {
+                    format!(
+                        "{} pendências; em vermelho, abertas há mais de {} dias.",
+                        lista.len(),
+                        prazo
+                    )
+                };
+                f.render_widget(
+                    Paragraph::new(entrada).block(Block::default().borders(Borders::ALL)),
+                    layout[1],
+                );
+            }
+            Modo::Orcamentos => {
+                let layout = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Min(3), Constraint::Length(3)])
+                    .split(main_area);
+                let fila = orcamentos::fila(&self.registros.orcamentos);
+                let hoje = Local::now().format("%Y-%m-%d").to_string();
+                let formato = self.config.formato_numeros;
+                let altura = layout[0].height.saturating_sub(3) as usize;
+                let visiveis = self.orcamentos_scroll.visiveis(fila.len(), altura);
+                let inicio = visiveis.start;
+                let linhas = fila[visiveis].iter().enumerate().map(|(i, o)| {
+                    let style = if inicio + i == self.orcamentos_scroll.selected {
+                        Style::default()
+                            .bg(cores.selecao_fundo)
+                            .fg(cores.selecao_texto)
+                    } else if o.retorno <= hoje {
+                        Style::default()
+                            .fg(cores.destaque)
+                            .add_modifier(Modifier::BOLD)
+                    } else {
+                        Style::default()
+                    };
+                    let atraso = chrono::NaiveDate::parse_from_str(&o.retorno, "%Y-%m-%d")
+                        .map_or(0, |d| (Local::now().date_naive() - d).num_days());
+                    let retorno = if o.retorno < hoje {
+                        format!("atrasado {} d", atraso)
+                    } else if o.retorno == hoje {
+                        "hoje".to_string()
+                    } else {
+                        Self::formata_data_ddmm(&o.retorno)
+                    };
+                    Row::new(vec![
+                        o.numero.to_string(),
+                        o.cliente.clone(),
+                        o.contato.clone(),
+                        o.itens
+                            .iter()
+                            .map(|(c, q)| format!("{}x{}", q, c))
+                            .collect::<Vec<_>>()
+                            .join(" "),
+                        formato.moeda(o.valor),
+                        retorno,
+                    ])
+                    .style(style)
+                });
+                let tabela = Table::new(
+                    linhas,
+                    &[
+                        Constraint::Percentage(5),
+                        Constraint::Percentage(18),
+                        Constraint::Percentage(18),
+                        Constraint::Percentage(21),
+                        Constraint::Percentage(16),
+                        Constraint::Percentage(22),
+                    ],
+                )
+                .header(
+                    Row::new(vec![
+                        "Nº", "CLIENTE", "CONTATO", "ITENS", "VALOR", "RETORNO",
+                    ])
+                    .style(Style::default().add_modifier(Modifier::BOLD)),
+                )
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Orçamentos (N novo, G ganho, P perdido, A adia, Esc volta)"),
+                );
+                f.render_widget(tabela, layout[0]);
+                let entrada = if self.editing {
+                    let rotulo = match self.orcamento_entrada {
+                        EntradaOrcamento::Novo => {
+                            "cliente;contato;codigo:qtd ...;retorno (dias ou DD/MM)"
+                        }
+                        EntradaOrcamento::Perda => "Motivo da perda",
+                        EntradaOrcamento::Retorno => "Novo retorno (dias ou DD/MM)",
+                    };
+                    format!("{}: {}", rotulo, self.input)
+                } else {
+                    let ligar = fila.iter().filter(|o| o.retorno <= hoje).count();
+                    let conversao = orcamentos::por_mes(&self.registros.orcamentos)
+                        .get(&hoje[..7])
+                        .and_then(|m| m.conversao());
+                    format!(
+                        "{} para ligar hoje; conversão do mês: {}",
+                        ligar,
+                        conversao.map_or("—".into(), |c| format!("{}%", formato.numero(c, 0)))
+                    )
+                };
+                f.render_widget(
+                    Paragraph::new(entrada).block(Block::default().borders(Borders::ALL)),
+                    layout[1],
+                );
+            }
+            Modo::Notificacoes => {
+                let lista = &self.registros.notificacoes;
+                let altura = main_area.height.saturating_sub(3) as usize;
+                let visiveis = self.notificacoes_scroll.visiveis(lista.len(), altura);
+                // Mais recentes primeiro
+                let linhas = visiveis.map(|i| {
+                    let n = &lista[lista.len() - 1 - i];
+                    let mut style = if n.lida {
+                        Style::default().fg(cores.apagado)
+                    } else {
+                        Style::default().add_modifier(Modifier::BOLD)
+                    };
+                    if i == self.notificacoes_scroll.selected {
+                        style = style.bg(cores.selecao_fundo).fg(cores.selecao_texto);
+                    }
+                    Row::new(vec![
+                        if n.lida { " " } else { "●" }.to_string(),
+                        n.timestamp.clone(),
+                        n.tipo.rotulo().to_string(),
+                        n.texto.clone(),
+                    ])
+                    .style(style)
+                });
+                let titulo = format!(
+                    "Notificações ({} não lidas) — D apaga, E recado, Esc volta",
+                    notificacoes::nao_lidas(lista)
+                );
+                let tabela = Table::new(
+                    linhas,
+                    [
+                        Constraint::Length(1),
+                        Constraint::Length(19),
+                        Constraint::Length(9),
+                        Constraint::Min(10),
+                    ],
+                )
+                .header(
+                    Row::new(vec!["", "QUANDO", "TIPO", "AVISO"])
+                        .style(Style::default().add_modifier(Modifier::BOLD)),
+                )
+                .block(Block::default().borders(Borders::ALL).title(titulo));
+                f.render_widget(tabela, main_area);
+            }
+            Modo::Console => {
+                let layout = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Length(3), Constraint::Min(3)])
+                    .split(main_area);
+                f.render_widget(
+                    Paragraph::new(format!("> {}", self.input)).block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .title("Console (Enter executa, `> arquivo.csv` exporta, Esc sai)"),
+                    ),
+                    layout[0],
+                );
+                let formato = self.config.formato_numeros;
+                let bloco = Block::default().borders(Borders::ALL);
+                match &self.console_resultado {
+                    Some(tabela) => {
+                        let altura = layout[1].height.saturating_sub(3) as usize;
+                        let visiveis = self.console_scroll.visiveis(tabela.linhas.len(), altura);
+                        let linhas = tabela.linhas[visiveis].iter().map(|l| {
+                            Row::new(l.iter().map(|c| c.exibe(formato)).collect::<Vec<_>>())
+                        });
+                        let larguras = vec![Constraint::Fill(1); tabela.colunas.len()];
+                        let titulo = format!("Resultado ({} linhas, ↑/↓ rola)", tabela.linhas.len());
+                        f.render_widget(
+                            Table::new(linhas, larguras)
+                                .header(
+                                    Row::new(tabela.colunas.clone())
+                                        .style(Style::default().add_modifier(Modifier::BOLD)),
+                                )
+                                .block(bloco.title(titulo)),
+                            layout[1],
+                        );
+                    }
+                    None => f.render_widget(
+                        Paragraph::new(
+                            "list | count | sum/avg/min/max qty|value\n\
+                             [where campo (=, !=, <, >, <=, >=, like) valor [and ...]] [by campo]\n\n\
+                             Campos: op, code, date, month, year, pay, tag, shop, supplier, brand, category, qty, value\n\
+                             Ex.: sum qty where op=VENDA and code like \"SEIKO*\" and month=2024-05",
+                        )
+                        .block(bloco.title("Consultas sobre o histórico")),
+                        layout[1],
+                    ),
+                }
+            }
+            Modo::Contagem => {
+                let codigo = self.contagem_codigo.as_deref().unwrap_or_default();
+                let atual = self.relogios.get(codigo).map_or(0, |r| r.quantidade());
+                let instrucao = format!(
+                    "Sistema: {} un. Digite a quantidade contada [motivo] ou o .csv do app de contagem, Enter p/ ajustar, Esc p/ cancelar",
+                    self.config.formato_numeros.inteiro(atual as i64)
+                );
+                let p = Paragraph::new(format!("{}: {}\n", instrucao, self.input))
+                    .wrap(Wrap { trim: true })
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .title(format!("Contagem - {}", codigo)),
+                    );
+                f.render_widget(p, main_area);
+            }
+            Modo::Renomear => {
+                let codigo = self.renomear_codigo.as_deref().unwrap_or_default();
+                let instrucao = "Digite o novo código (o histórico acompanha), Enter p/ renomear, Esc p/ cancelar";
+                let p = Paragraph::new(format!("{}: {}\n", instrucao, self.input))
+                    .wrap(Wrap { trim: true })
+                    .block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .title(format!("Renomear - {}", codigo)),
+                    );
+                f.render_widget(p, main_area);
+            }
+            Modo::EdicaoExterna => {
+                let Some(edicao) = &self.edicao_externa else {
+                    return;
+                };
+                let layout = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Min(3), Constraint::Length(4)])
+                    .split(main_area);
+                let altura = layout[0].height.saturating_sub(2) as usize;
+                let visiveis = self
+                    .edicao_externa_scroll
+                    .visiveis(edicao.resumo.len(), altura);
+                let linhas: Vec<Line> = edicao.resumo[visiveis]
+                    .iter()
+                    .map(|l| Line::from(l.as_str()))
+                    .collect();
+                let lista = Paragraph::new(linhas).block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("estoque.json alterado fora do programa"),
+                );
+                f.render_widget(lista, layout[0]);
+                let ajuda = Paragraph::new(
+                    "Enter aceita as alterações. D desfaz: volta à última gravação do programa \
+                     e guarda o arquivo editado em estoque.json.externo.",
+                )
+                .wrap(Wrap { trim: true })
+                .block(Block::default().borders(Borders::ALL));
+                f.render_widget(ajuda, layout[1]);
+            }
+            Modo::Balanco => {
+                let Some(b) = &self.balanco else {
+                    return;
+                };
+                let conciliacao = balanco::concilia(&b.contagem, &self.relogios, b.completo);
+                let formato = self.config.formato_numeros;
+                let layout = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Min(3), Constraint::Length(4)])
+                    .split(main_area);
+                let divergentes: Vec<&balanco::Divergencia> = conciliacao.divergentes().collect();
+                let (cor_entrada, cor_saida) = (cores.entrada, cores.saida);
+                let altura = layout[0].height.saturating_sub(3) as usize;
+                let visiveis = self.balanco_scroll.visiveis(divergentes.len(), altura);
+                let inicio = visiveis.start;
+                let linhas = divergentes[visiveis].iter().enumerate().map(|(i, d)| {
+                    let style = if inicio + i == self.balanco_scroll.selected {
+                        Style::default()
+                            .bg(cores.selecao_fundo)
+                            .fg(cores.selecao_texto)
+                    } else {
+                        Style::default().fg(if d.diferenca() > 0 {
+                            cor_entrada
+                        } else {
+                            cor_saida
+                        })
+                    };
+                    Row::new(vec![
+                        d.codigo.clone(),
+                        d.descricao.clone(),
+                        formato.inteiro(d.sistema as i64),
+                        formato.inteiro(d.contado as i64),
+                        format!("{:+}", d.diferenca()),
+                        formato.moeda(d.valor),
+                    ])
+                    .style(style)
+                });
+                let tabela = Table::new(
+                    linhas,
+                    &[
+                        Constraint::Percentage(14),
+                        Constraint::Percentage(34),
+                        Constraint::Percentage(11),
+                        Constraint::Percentage(11),
+                        Constraint::Percentage(10),
+                        Constraint::Percentage(20),
+                    ],
+                )
+                .header(
+                    Row::new(vec![
+                        "CÓDIGO",
+                        "DESCRIÇÃO",
+                        "SISTEMA",
+                        "CONTADO",
+                        "DIF.",
+                        "A CUSTO",
+                    ])
+                    .style(Style::default().add_modifier(Modifier::BOLD)),
+                )
+                .block(Block::default().borders(Borders::ALL).title(format!(
+                    "Balanço {} ({})",
+                    std::path::Path::new(&b.arquivo)
+                        .file_name()
+                        .map_or(b.arquivo.clone(), |n| n.to_string_lossy().into_owned()),
+                    if b.completo { "completo" } else { "parcial" }
+                )));
+                f.render_widget(tabela, layout[0]);
+                let total: f64 = divergentes.iter().map(|d| d.valor).sum();
+                let mut resumo = format!(
+                    "{} conferidos, {} divergentes ({} a custo)",
+                    conciliacao.itens.len(),
+                    divergentes.len(),
+                    formato.moeda(total)
+                );
+                if !conciliacao.desconhecidos.is_empty() {
+                    resumo.push_str(&format!(
+                        "; fora do cadastro: {}",
+                        conciliacao.desconhecidos.join(", ")
+                    ));
+                }
+                if !conciliacao.nao_contados.is_empty() {
+                    resumo.push_str(&format!(
+                        "; não contados: {}",
+                        conciliacao.nao_contados.len()
+                    ));
+                }
+                f.render_widget(
+                    Paragraph::new(format!(
+                        "{}\nEnter lança os ajustes, C alterna parcial/completo, Esc cancela",
+                        resumo
+                    ))
+                    .wrap(Wrap { trim: true })
+                    .block(Block::default().borders(Borders::ALL)),
+                    layout[1],
+                );
+            }
+            Modo::EstoqueEm => {
+                let instrucao =
+                    "Data (DD/MM/AAAA ou AAAA-MM-DD), Enter p/ consultar, Esc p/ cancelar";
+                let p = Paragraph::new(format!("{}: {}\n", instrucao, self.input)).block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Estoque em data passada"),
+                );
+                f.render_widget(p, main_area);
+            }
+            Modo::ListaEspera => {
+                let instrucao =
+                    "Digite nome do cliente;contato, Enter p/ registrar, Esc p/ cancelar";
+                let titulo = format!(
+                    "Lista de Espera - {}",
+                    self.espera_codigo.as_deref().unwrap_or_default()
+                );
+                let p = Paragraph::new(format!("{}: {}\n", instrucao, self.input))
+                    .block(Block::default().borders(Borders::ALL).title(titulo));
+                f.render_widget(p, main_area);
+            }
+            Modo::EmitirNota => {
+                let instrucao =
+                    "Digite nome do cliente;CPF/CNPJ;aniversário DD/MM (opcionais), Enter p/ emitir, Esc p/ cancelar";
+                let p = Paragraph::new(format!("{}: {}\n", instrucao, self.input))
+                    .block(Block::default().borders(Borders::ALL).title("Emitir Nota"));
+                f.render_widget(p, main_area);
+            }
+            // Desenhado em tela cheia no início
+            Modo::Quiosque => {}
+            Modo::Clientes => {
+                let layout = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Min(3), Constraint::Length(3)])
+                    .split(main_area);
+                let lista = &self.registros.clientes;
+                let altura = layout[0].height.saturating_sub(3) as usize;
+                let visiveis = self.clientes_scroll.visiveis(lista.len(), altura);
+                let inicio = visiveis.start;
+                let numeros = self.config.formato_numeros;
+                let linhas = lista[visiveis].iter().enumerate().map(|(i, c)| {
+                    let (vendas, total, ultima) =
+                        clientes::resumo(&self.historico, &self.relogios, &c.nome);
+                    let mut style = if inicio + i == self.clientes_scroll.selected {
+                        Style::default()
+                            .bg(cores.selecao_fundo)
+                            .fg(cores.selecao_texto)
+                    } else {
+                        Style::default()
+                    };
+                    if self.cliente_atendido.as_deref() == Some(c.nome.as_str()) {
+                        style = style.add_modifier(Modifier::BOLD);
+                    }
+                    Row::new(vec![
+                        c.nome.clone(),
+                        c.telefone.clone(),
+                        vendas.to_string(),
+                        numeros.moeda(total),
+                        ultima
+                            .map(|d| Self::formata_data_ddmm(&d))
+                            .unwrap_or_default(),
+                        c.observacoes.clone(),
+                    ])
+                    .style(style)
+                });
+                let tabela = Table::new(
+                    linhas,
+                    &[
+                        Constraint::Percentage(22),
+                        Constraint::Percentage(16),
+                        Constraint::Percentage(8),
+                        Constraint::Percentage(16),
+                        Constraint::Percentage(10),
+                        Constraint::Percentage(28),
+                    ],
+                )
+                .header(
+                    Row::new(vec![
+                        "NOME", "TELEFONE", "VENDAS", "TOTAL", "ÚLTIMA", "OBS.",
+                    ])
+                    .style(Style::default().add_modifier(Modifier::BOLD)),
+                )
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Clientes (N novo, D remove, Enter atende, Esc volta)"),
+                );
+                f.render_widget(tabela, layout[0]);
+                let entrada = if self.editing {
+                    format!("nome;telefone;observações: {}", self.input)
+                } else if let Some(nome) = &self.cliente_atendido {
+                    format!("Atendendo {}: as vendas ficam no nome do cliente.", nome)
+                } else {
+                    "Escolha um cliente e Enter para lançar as próximas vendas no nome dele.".into()
+                };
+                f.render_widget(
+                    Paragraph::new(entrada).block(Block::default().borders(Borders::ALL)),
+                    layout[1],
+                );
+            }
+            Modo::Fornecedores if self.fornecedores_cadastro => {
+                let layout = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Min(3), Constraint::Length(3)])
+                    .split(main_area);
+                let cadastro = &self.registros.fornecedores;
+                let altura = layout[0].height.saturating_sub(3) as usize;
+                let visiveis = self.fornecedores_scroll.visiveis(cadastro.len(), altura);
+                let inicio = visiveis.start;
+                let formato = self.config.formato_numeros;
+                let linhas = cadastro[visiveis].iter().enumerate().map(|(i, f)| {
+                    let (lotes, unidades, total, ultima) =
+                        fornecedores::resumo(&self.historico, &self.relogios, &f.nome);
+                    let style = if inicio + i == self.fornecedores_scroll.selected {
+                        Style::default()
+                            .bg(cores.selecao_fundo)
+                            .fg(cores.selecao_texto)
+                    } else {
+                        Style::default()
+                    };
+                    Row::new(vec![
+                        f.nome.clone(),
+                        f.contato.clone(),
+                        lotes.to_string(),
+                        unidades.to_string(),
+                        formato.moeda(total),
+                        ultima
+                            .map(|d| Self::formata_data_ddmm(&d))
+                            .unwrap_or_default(),
+                        f.observacoes.clone(),
+                    ])
+                    .style(style)
+                });
+                let tabela = Table::new(
+                    linhas,
+                    &[
+                        Constraint::Percentage(20),
+                        Constraint::Percentage(18),
+                        Constraint::Percentage(8),
+                        Constraint::Percentage(8),
+                        Constraint::Percentage(16),
+                        Constraint::Percentage(10),
+                        Constraint::Percentage(20),
+                    ],
+                )
+                .header(
+                    Row::new(vec![
+                        "NOME", "CONTATO", "LOTES", "UNID.", "PAGO", "ÚLTIMA", "OBS.",
+                    ])
+                    .style(Style::default().add_modifier(Modifier::BOLD)),
+                )
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Fornecedores: cadastro (N novo, D remove, Tab códigos, Esc volta)"),
+                );
+                f.render_widget(tabela, layout[0]);
+                let entrada = if self.editing {
+                    format!("nome;contato;observações: {}", self.input)
+                } else {
+                    "Compras com fornecedor=nome na Compra; filtre o histórico com fornecedor=nome."
+                        .into()
+                };
+                f.render_widget(
+                    Paragraph::new(entrada).block(Block::default().borders(Borders::ALL)),
+                    layout[1],
+                );
+            }
+            Modo::Fornecedores => {
+                let layout = Layout::default()
+                    .direction(Direction::Vertical)
+                    .constraints([Constraint::Min(3), Constraint::Length(3)])
+                    .split(main_area);
+                let referencias = &self.registros.referencias_fornecedor;
+                let altura = layout[0].height.saturating_sub(3) as usize;
+                let visiveis = self.fornecedores_scroll.visiveis(referencias.len(), altura);
+                let inicio = visiveis.start;
+                let linhas = referencias[visiveis].iter().enumerate().map(|(i, r)| {
+                    let descricao = self
+                        .relogios
+                        .get(&r.codigo)
+                        .map_or("(removido)", |p| p.descricao.as_str());
+                    let style = if inicio + i == self.fornecedores_scroll.selected {
+                        Style::default()
+                            .bg(cores.selecao_fundo)
+                            .fg(cores.selecao_texto)
+                    } else {
+                        Style::default()
+                    };
+                    Row::new(vec![
+                        r.fornecedor.clone(),
+                        r.codigo_fornecedor.clone(),
+                        r.codigo.clone(),
+                        descricao.to_string(),
+                    ])
+                    .style(style)
+                });
+                let tabela = Table::new(
+                    linhas,
+                    &[
+                        Constraint::Percentage(25),
+                        Constraint::Percentage(20),
+                        Constraint::Percentage(15),
+                        Constraint::Percentage(40),
+                    ],
+                )
+                .header(
+                    Row::new(vec!["FORNECEDOR", "CÓD. FORN.", "CÓDIGO", "DESCRIÇÃO"])
+                        .style(Style::default().add_modifier(Modifier::BOLD)),
+                )
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title("Fornecedores: códigos (N nova, D remove, Tab cadastro, Esc volta)"),
+                );
+                f.render_widget(tabela, layout[0]);
+                let entrada = if self.editing {
+                    format!("fornecedor codigo_do_fornecedor codigo: {}", self.input)
+                } else {
+                    "Códigos dos fornecedores usados ao importar catálogos e notas.".into()
+                };
+                f.render_widget(
+                    Paragraph::new(entrada).block(Block::default().borders(Borders::ALL)),
+                    layout[1],
+                );
+            }
+            Modo::Recentes => {
+                let itens: Vec<ListItem> = self
+                    .recentes
+                    .iter()
+                    .enumerate()
+                    .map(|(i, codigo)| {
+                        let descricao = self
+                            .relogios
+                            .get(codigo)
+                            .map(|r| {
+                                format!(
+                                    "{}  ({} un.)",
+                                    r.descricao,
+                                    self.config
+                                        .formato_numeros
+                                        .inteiro(self.quantidade_disponivel(r) as i64)
+                                )
+                            })
+                            .unwrap_or_else(|| "(removido)".into());
+                        let style = if i == self.recentes_scroll.selected {
+                            Style::default()
+                                .bg(cores.selecao_fundo)
+                                .fg(cores.selecao_texto)
+                        } else {
+                            Style::default()
+                        };
+                        ListItem::new(format!("{:<16} {}", codigo, descricao)).style(style)
+                    })
+                    .collect();
+                f.render_widget(
+                    List::new(itens).block(
+                        Block::default()
+                            .borders(Borders::ALL)
+                            .title("Usados recentemente (Enter seleciona, Esc volta)"),
+                    ),
+                    main_area,
+                );
+            }
+            Modo::Comparacao => {
+                let numeros = self.config.formato_numeros;
+                let hoje = Local::now().date_naive();
+                let velocidades = analise::velocidade_vendas(&self.historico, 30, hoje);
+                let modelos: Vec<&Relogio> = self
+                    .comparar
+                    .iter()
+                    .filter_map(|c| self.relogios.get(c))
+                    .collect();
+                let colunas = Layout::default()
+                    .direction(Direction::Horizontal)
+                    .constraints(vec![
+                        Constraint::Ratio(1, modelos.len().max(1) as u32);
+                        modelos.len()
+                    ])
+                    .split(main_area);
+                for (r, area) in modelos.iter().zip(colunas.iter()) {
+                    let partes = Layout::default()
+                        .direction(Direction::Vertical)
+                        .constraints([
+                            Constraint::Length(9),
+                            Constraint::Length(5),
+                            Constraint::Min(3),
+                        ])
+                        .split(*area);
+                    let custo = self.custo_em_reais(r);
+                    let margem = match (r.preco, custo) {
+                        (Some(p), Some(c)) if p > 0.0 => {
+                            format!("{}%", numeros.numero((p - c) / p * 100.0, 1))
+                        }
+                        _ => "-".into(),
+                    };
+                    let ultima = self
+                        .historico
+                        .iter()
+                        .rev()
+                        .find(|h| h.operacao == "VENDA" && h.codigo == r.codigo)
+                        .map(|h| h.timestamp.get(..10).unwrap_or(&h.timestamp).to_string())
+                        .unwrap_or_else(|| "nunca".into());
+                    let moeda = |v: Option<f64>| v.map(|v| numeros.moeda(v)).unwrap_or("-".into());
+                    let linhas = [
+                        r.descricao.clone(),
+                        format!(
+                            "Estoque:      {}",
+                            numeros.inteiro(self.quantidade_disponivel(r) as i64)
+                        ),
+                        format!(
+                            "Vendas/dia:   {}",
+                            numeros.numero(velocidades.get(&r.codigo).copied().unwrap_or(0.0), 2)
+                        ),
+                        format!("Preço:        {}", moeda(r.preco)),
+                        format!("Custo:        {}", moeda(custo)),
+                        format!("Margem:       {}", margem),
+                        format!("Última venda: {}", ultima),
+                    ];
+                    f.render_widget(
+                        Paragraph::new(linhas.join("\n")).block(
+                            Block::default()
+                                .borders(Borders::ALL)
+                                .title(r.codigo.clone()),
+                        ),
+                        partes[0],
+                    );
+                    let semanas = analise::vendas_semanais(&self.historico, &r.codigo, hoje, 12);
+                    f.render_widget(
+                        ratatui::widgets::Sparkline::default()
+                            .block(
+                                Block::default()
+                                    .borders(Borders::ALL)
+                                    .title("Vendas, 12 semanas"),
+                            )
+                            .data(&semanas)
+                            .style(Style::default().fg(cores.realce)),
+                        partes[1],
+                    );
+                    let precos: Vec<u64> = analise::precos_praticados(&self.historico, r)
+                        .iter()
+                        .map(|p| p.round() as u64)
+                        .collect();
+                    let faixa = match (precos.iter().min(), precos.iter().max()) {
+                        (Some(min), Some(max)) => format!(
+                            "Preço praticado {}–{}",
+                            numeros.inteiro(*min as i64),
+                            numeros.inteiro(*max as i64)
+                        ),
+                        _ => "Preço praticado: sem vendas".into(),
+                    };
+                    f.render_widget(
+                        ratatui::widgets::Sparkline::default()
+                            .block(Block::default().borders(Borders::ALL).title(faixa))
+                            .data(&precos)
+                            .style(Style::default().fg(cores.destaque)),
+                        partes[2],
+                    );
+                }
+            }
+        }
+
+        if let (Modo::Historico, Some(h)) = (&self.modo, &self.historico_detalhe) {
+            let area = area_central(60, 60, main_area);
+            let mut linhas = vec![
+                format!("Código:    {}", h.codigo),
+                format!("Operação:  {}", h.operacao_marcada(self.config.idioma)),
+                format!(
+                    "Quantidade: {}",
+                    h.quantidade_marcada(self.config.formato_numeros)
+                ),
+                format!("Data:      {}", h.timestamp),
+            ];
+            if let Some(pag) = &h.pagamento {
+                linhas.push(format!("Pagamento: {}", pag));
+            }
+            if let Some(cliente) = &h.cliente {
+                linhas.push(format!("Cliente:   {}", cliente));
+            }
+            if let Some(kit) = &h.kit {
+                linhas.push(format!("Kit:       {}", kit));
+            }
+            if let Some(n) = h.nota {
+                linhas.push(format!("Nota:      {:06}", n));
+            }
+            if let Some(m) = &h.motivo {
+                linhas.push(format!("Motivo:    {}", m));
+            }
+            if !h.tags.is_empty() {
+                linhas.push(format!("Etiquetas: {}", h.tags.join(", ")));
+            }
+            linhas.push(String::new());
+            if h.operacao == "VENDA" {
+                linhas.push(if h.nota.is_some() {
+                    "[N] Reimprimir nota  [Esc] Fechar".into()
+                } else {
+                    "[N] Emitir nota  [Esc] Fechar".into()
+                });
+            } else {
+                linhas.push("[Esc] Fechar".into());
+            }
+            f.render_widget(Clear, area);
+            f.render_widget(Block::default().style(fundo), area);
+            f.render_widget(
+                Paragraph::new(linhas.join("\n"))
+                    .block(Block::default().borders(Borders::ALL).title("Detalhes")),
+                area,
+            );
+        }
+
+        if !self.recados_abertos.is_empty() {
+            let area = area_central(70, 60, main_area);
+            let mut linhas = vec![];
+            for r in &self.recados_abertos {
+                linhas.push(Line::from(Span::styled(
+                    format!("{} — {}", r.timestamp, r.autor),
+                    Style::default().add_modifier(Modifier::BOLD),
+                )));
+                linhas.push(Line::from(r.mensagem.clone()));
+                linhas.push(Line::from(""));
+            }
+            linhas.push(Line::from("[Enter] Ciente"));
+            f.render_widget(Clear, area);
+            f.render_widget(Block::default().style(fundo), area);
+            f.render_widget(
+                Paragraph::new(linhas).wrap(Wrap { trim: true }).block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(cores.destaque))
+                        .title("Recados do turno anterior"),
+                ),
+                area,
+            );
+        }
+
+        if let Some(codigo) = &self.exclusao {
+            let area = area_central(60, 60, main_area);
+            let descricao = self
+                .relogios
+                .get(codigo)
+                .map_or("", |r| r.descricao.as_str());
+            let entradas = self
+                .historico
+                .iter()
+                .filter(|h| h.codigo == *codigo)
+                .count();
+            let texto = format!(
+                "Excluir {} {}?\n\nSaem também {} entradas do histórico.\n\n[S] Excluir  [Esc] Cancelar",
+                codigo, descricao, entradas
+            );
+            f.render_widget(Clear, area);
+            f.render_widget(Block::default().style(fundo), area);
+            f.render_widget(
+                Paragraph::new(texto).wrap(Wrap { trim: true }).block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(cores.alerta))
+                        .title("Excluir relógio"),
+                ),
+                area,
+            );
+        }
+
+        let logs_area = vertical_layout[2];
+        let qtd_logs = 5;
+        let total_msg = self.mensagens.len();
+        let start_log = total_msg.saturating_sub(qtd_logs);
+        let visible_logs = &self.mensagens[start_log..];
+        let logs_items: Vec<ListItem> = visible_logs
+            .iter()
+            .map(|m| ListItem::new(m.as_str()))
+            .collect();
+        let mut bloco_logs = Block::default()
+            .borders(Borders::ALL)
+            .title(t("Logs (Últimas Mensagens)"));
+        let nao_lidas = notificacoes::nao_lidas(&self.registros.notificacoes);
+        if nao_lidas > 0 {
+            bloco_logs = bloco_logs.title(
+                Line::from(Span::styled(
+                    format!(" ● {} notificações não lidas (N) ", nao_lidas),
+                    Style::default().fg(cores.selecao_texto).bg(cores.destaque),
+                ))
+                .right_aligned(),
+            );
+        }
+        let lista_logs = List::new(logs_items).block(bloco_logs);
+        f.render_widget(lista_logs, logs_area);
+
+        let msgs_area = vertical_layout[3];
+        let msgs: Vec<ListItem> = self
+            .mensagens
+            .iter()
+            .map(|m| ListItem::new(m.as_str()))
+            .collect();
+        let rodape = List::new(msgs).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(t("Mensagens (Histórico Completo)")),
+        );
+        f.render_widget(rodape, msgs_area);
+
+        if let Some(topo) = self.ajuda {
+            // Por cima de tudo, logs inclusive
+            let area = area_central(80, 80, size);
+            let mut linhas = vec![];
+            for s in ajuda::SECOES {
+                linhas.push(Line::from(Span::styled(
+                    t(s.tela),
+                    Style::default()
+                        .fg(cores.realce)
+                        .add_modifier(Modifier::BOLD),
+                )));
+                for (tecla, descricao) in s.teclas {
+                    linhas.push(Line::from(vec![
+                        Span::styled(
+                            format!("  {:<23}", t(tecla)),
+                            Style::default().fg(cores.destaque),
+                        ),
+                        Span::raw(t(descricao)),
+                    ]));
+                }
+                linhas.push(Line::from(""));
+            }
+            f.render_widget(Clear, area);
+            f.render_widget(Block::default().style(fundo), area);
+            f.render_widget(
+                Paragraph::new(linhas).scroll((topo, 0)).block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(cores.destaque))
+                        .title(t("Ajuda (↑/↓ rola, outra tecla fecha)")),
+                ),
+                area,
+            );
+        }
+    }
+
+    /// Trata uma tecla como o laço principal; retorna `false` quando o
+    /// usuário pede para sair.
+    pub fn handle_key(&mut self, k: KeyEvent) -> bool {
+        if k.kind != KeyEventKind::Press {
+            return true;
+        }
+        self.ultima_tecla = Instant::now();
+        // A tecla que interrompe a demonstração só acorda a tela
+        if self.demonstracao.is_some() {
+            self.encerra_demonstracao();
+            return true;
+        }
+        if self.editing {
+            match self.modo {
+                Modo::Cadastro => match k.code {
+                    KeyCode::Enter => {
+                        self.confirmar_cadastro();
+                        self.sai_modo_insercao();
+                    }
+                    KeyCode::Esc => {
+                        self.sai_modo_insercao();
+                    }
+                    KeyCode::Backspace => {
+                        self.input.pop();
+                    }
+                    KeyCode::Char(ch) => {
+                        self.input.push(ch);
+                    }
+                    _ => {}
+                },
+                Modo::Buscar => match k.code {
+                    KeyCode::Enter => {
+                        if self.busca_pendente.take().is_some() {
+                            self.atualizar_busca_results();
+                        }
+                        self.editing = false;
+                    }
+                    KeyCode::Esc => {
+                        self.sai_modo_insercao();
+                    }
+                    KeyCode::Backspace => {
+                        self.input.pop();
+                        self.agenda_busca();
+                    }
+                    KeyCode::Char(ch) => {
+                        self.input.push(ch);
+                        self.agenda_busca();
+                    }
+                    _ => {}
+                },
+                Modo::Historico if self.historico_entrada != EntradaHistorico::Pesquisa => {
+                    match k.code {
+                        KeyCode::Enter => self.confirma_entrada_historico(),
+                        KeyCode::Esc => {
+                            self.historico_entrada = EntradaHistorico::Pesquisa;
+                            self.input.clear();
+                            self.editing = false;
+                        }
+                        KeyCode::Backspace => {
+                            self.input.pop();
+                        }
+                        KeyCode::Char(ch) => self.input.push(ch),
+                        _ => {}
+                    }
+                }
+                Modo::Historico => {
+                    // Editando filtro no histórico
+                    match k.code {
+                        KeyCode::Enter => {
+                            // Ao apertar Enter, filtra pelo código selecionado
+                            if !self.historico_search_results.is_empty() {
+                                let (cod, _) = self.historico_search_results
+                                    [self.historico_search_selected]
+                                    .clone();
+                                self.filtrar_historico(&cod);
+                            } else {
+                                // Se não há resultados, filtra pelo input
+                                let cod = self.input.trim().to_string();
+                                self.filtrar_historico(&cod);
+                            }
+                            self.editing = false;
+                            self.input.clear();
+                        }
+                        KeyCode::Esc => {
+                            // Cancela filtro
+                            self.input.clear();
+                            self.editing = false;
+                            self.filtrar_historico("");
+                        }
+                        KeyCode::Backspace => {
+                            self.input.pop();
+                            self.atualizar_historico_search_results();
+                        }
+                        KeyCode::Char(ch) => {
+                            self.input.push(ch);
+                            self.atualizar_historico_search_results();
+                        }
+                        KeyCode::Up => {
+                            // Navega nas sugestões
+                            self.historico_search_up();
+                        }
+                        KeyCode::Down => {
+                            self.historico_search_down();
+                        }
+                        _ => {}
+                    }
+                }
+                Modo::Compra => match k.code {
+                    KeyCode::Enter => {
+                        if self.oferece_sugestao() {
+                            return true;
+                        }
+                        let input_com_ean = self.input_com_ean();
+                        let (input, fornecedor) = separa_fornecedor(&input_com_ean);
+                        let (input, efeito) = separa_data_efeito(&input, Local::now().date_naive());
+                        let parts: Vec<&str> = input.split_whitespace().collect();
+                        let movimento = efeito.and_then(|efeito| {
+                            let m = le_movimento(&parts, self.config.formato_numeros, USO_COMPRA)?;
+                            let qtd =
+                                self.quantidade_em_unidades(m.codigo, m.quantidade, m.extra)?;
+                            let fornecedor = match fornecedor {
+                                None => None,
+                                Some(_) if efeito.is_some() => {
+                                    return Err("Compras agendadas não levam fornecedor.".into())
+                                }
+                                Some(f) => Some(
+                                    fornecedores::acha(&self.registros.fornecedores, f)?
+                                        .nome
+                                        .clone(),
+                                ),
+                            };
+                            Ok((m.codigo.to_string(), qtd, fornecedor, efeito))
+                        });
+                        match movimento {
+                            Err(e) => self.mensagens.push(e),
+                            Ok((codigo, qtd, _, Some(data))) => {
+                                self.agendar(agendadas::Tipo::Compra, codigo, qtd, None, data)
+                            }
+                            Ok((codigo, qtd, fornecedor, None)) => {
+                                self.comprar_relogio(codigo, qtd, fornecedor)
+                            }
+                        }
+                        self.modo = Modo::Estoques;
+                        self.editing = false;
+                        self.input.clear();
+                        self.chosen_relogio = None;
+                        self.chosen_operation = None;
+                    }
+                    KeyCode::Tab => self.aplica_sugestao(),
+                    KeyCode::Esc => {
+                        self.modo = Modo::Estoques;
+                        self.editing = false;
+                        self.input.clear();
+                        self.sugestao = None;
+                        self.chosen_relogio = None;
+                        self.chosen_operation = None;
+                    }
+                    KeyCode::Backspace => {
+                        self.input.pop();
+                    }
+                    KeyCode::Char(ch) => {
+                        self.input.push(ch);
+                    }
+                    _ => {}
+                },
+                Modo::Venda => match k.code {
+                    KeyCode::Enter => {
+                        if self.oferece_sugestao() {
+                            return true;
+                        }
+                        let (input, efeito) =
+                            separa_data_efeito(&self.input_com_ean(), Local::now().date_naive());
+                        let (liberacao, parts): (Vec<&str>, Vec<&str>) =
+                            input.split_whitespace().partition(|p| p.starts_with('!'));
+                        let token = liberacao.first().map(|t| &t[1..]);
+                        let liberado = self.senha_confere(token);
+                        if token.is_some() && !liberado {
+                            self.mensagens
+                                .push("Senha de administrador incorreta!".into());
+                        }
+                        let venda = efeito.and_then(|efeito| {
+                            let m = le_movimento(&parts, self.config.formato_numeros, USO_VENDA)?;
+                            let pagamento = match m.extra {
+                                None => "dinheiro".to_string(),
+                                Some(f) => FORMAS_PAGAMENTO
+                                    .iter()
+                                    .find(|p| p.eq_ignore_ascii_case(f))
+                                    .map(|p| p.to_string())
+                                    .ok_or(
+                                        "Forma de pagamento inválida (dinheiro, cartao, pix, credito)!",
+                                    )?,
+                            };
+                            Ok((m.codigo.to_string(), m.quantidade, pagamento, efeito))
+                        });
+                        match venda {
+                            Err(e) => self.mensagens.push(e),
+                            Ok((_, _, pag, Some(_))) if pag == "credito" => self
+                                .mensagens
+                                .push("Venda a crédito não pode ser agendada.".into()),
+                            Ok((codigo, qtd, pag, None)) if pag == "credito" => {
+                                self.venda_credito = Some((codigo, qtd, liberado))
+                            }
+                            Ok((codigo, qtd, pag, Some(data))) => {
+                                if self.confere_venda(&codigo, qtd, liberado) {
+                                    self.agendar(
+                                        agendadas::Tipo::Venda,
+                                        codigo,
+                                        qtd,
+                                        Some(pag),
+                                        data,
+                                    )
+                                }
+                            }
+                            Ok((codigo, qtd, pag, None)) => {
+                                self.vender_relogio(
+                                    codigo,
+                                    qtd,
+                                    Some(pag),
+                                    liberado,
+                                    Canal::Balcao,
+                                );
+                            }
+                        }
+                        self.modo = Modo::Estoques;
+                        self.editing = false;
+                        self.input.clear();
+                        self.chosen_relogio = None;
+                        self.chosen_operation = None;
+                        if self.espera_codigo.is_some() {
+                            self.modo = Modo::ListaEspera;
+                            self.editing = true;
+                        } else if self.venda_credito.is_some() {
+                            self.modo = Modo::ResgateCredito;
+                            self.editing = true;
+                        }
+                    }
+                    KeyCode::Tab => self.aplica_sugestao(),
+                    KeyCode::Esc => {
+                        self.modo = Modo::Estoques;
+                        self.editing = false;
+                        self.input.clear();
+                        self.sugestao = None;
+                        self.chosen_relogio = None;
+                        self.chosen_operation = None;
+                    }
+                    KeyCode::Backspace => {
+                        self.input.pop();
+                    }
+                    KeyCode::Char(ch) => {
+                        self.input.push(ch);
+                    }
+                    _ => {}
+                },
+                Modo::ResgateCredito => match k.code {
+                    KeyCode::Enter => {
+                        self.resgatar_credito();
+                        self.modo = Modo::Estoques;
+                        self.editing = false;
+                        self.input.clear();
+                    }
+                    KeyCode::Esc => {
+                        self.venda_credito = None;
+                        self.modo = Modo::Estoques;
+                        self.editing = false;
+                        self.input.clear();
+                    }
+                    KeyCode::Backspace => {
+                        self.input.pop();
+                    }
+                    KeyCode::Char(ch) => {
+                        self.input.push(ch);
+                    }
+                    _ => {}
+                },
+                Modo::Recado => match k.code {
+                    KeyCode::Enter => {
+                        self.deixar_recado();
+                        self.sai_modo_insercao();
+                    }
+                    KeyCode::Esc => {
+                        self.sai_modo_insercao();
+                    }
+                    KeyCode::Backspace => {
+                        self.input.pop();
+                    }
+                    KeyCode::Char(ch) => {
+                        self.input.push(ch);
+                    }
+                    _ => {}
+                },
+                Modo::EstoqueEm => match k.code {
+                    KeyCode::Enter => {
+                        self.consultar_estoque_em();
+                        self.modo = Modo::Estoques;
+                        self.editing = false;
+                        self.input.clear();
+                    }
+                    KeyCode::Esc => {
+                        self.modo = Modo::Estoques;
+                        self.editing = false;
+                        self.input.clear();
+                    }
+                    KeyCode::Backspace => {
+                        self.input.pop();
+                    }
+                    KeyCode::Char(ch) => {
+                        self.input.push(ch);
+                    }
+                    _ => {}
+                },
+                Modo::Balanco => self.balanco_tecla(k.code),
+                Modo::EdicaoExterna => self.edicao_externa_tecla(k.code),
+                Modo::Renomear => match k.code {
+                    KeyCode::Enter => {
+                        self.confirmar_renomear();
+                        self.sai_modo_insercao();
+                    }
+                    KeyCode::Esc => {
+                        self.renomear_codigo = None;
+                        self.sai_modo_insercao();
+                    }
+                    KeyCode::Backspace => {
+                        self.input.pop();
+                    }
+                    KeyCode::Char(ch) => {
+                        self.input.push(ch);
+                    }
+                    _ => {}
+                },
+                Modo::Contagem => match k.code {
+                    KeyCode::Enter if self.input.trim().to_lowercase().ends_with(".csv") => {
+                        self.abrir_balanco();
+                    }
+                    KeyCode::Enter => {
+                        self.confirmar_contagem();
+                        self.modo = Modo::Estoques;
+                        self.editing = false;
+                        self.input.clear();
+                    }
+                    KeyCode::Esc => {
+                        self.contagem_codigo = None;
+                        self.modo = Modo::Estoques;
+                        self.editing = false;
+                        self.input.clear();
+                    }
+                    KeyCode::Backspace => {
+                        self.input.pop();
+                    }
+                    KeyCode::Char(ch) => {
+                        self.input.push(ch);
+                    }
+                    _ => {}
+                },
+                Modo::Recebimento => self.recebimento_tecla(k.code),
+                Modo::Orcamentos => match k.code {
+                    KeyCode::Enter => {
+                        match self.confirmar_orcamento() {
+                            Ok(msg) => {
+                                self.salvar();
+                                self.mensagens.push(msg);
+                            }
+                            Err(e) => self.mensagens.push(e),
+                        }
+                        self.editing = false;
+                        self.input.clear();
+                    }
+                    KeyCode::Esc => {
+                        self.editing = false;
+                        self.input.clear();
+                    }
+                    KeyCode::Backspace => {
+                        self.input.pop();
+                    }
+                    KeyCode::Char(ch) => self.input.push(ch),
+                    _ => {}
+                },
+                Modo::Pedidos => match k.code {
+                    KeyCode::Enter => {
+                        match self.abrir_pedido_compra() {
+                            Ok(msg) => {
+                                self.salvar();
+                                self.mensagens.push(msg);
+                            }
+                            Err(e) => self.mensagens.push(e),
+                        }
+                        self.editing = false;
+                        self.input.clear();
+                    }
+                    KeyCode::Esc => {
+                        self.editing = false;
+                        self.input.clear();
+                    }
+                    KeyCode::Backspace => {
+                        self.input.pop();
+                    }
+                    KeyCode::Char(ch) => self.input.push(ch),
+                    _ => {}
+                },
+                Modo::Console => match k.code {
+                    KeyCode::Enter => self.executar_consulta(),
+                    KeyCode::Esc => self.sai_modo_insercao(),
+                    KeyCode::Up => self.console_scroll.sobe(),
+                    KeyCode::Down => {
+                        let total = self
+                            .console_resultado
+                            .as_ref()
+                            .map_or(0, |t| t.linhas.len());
+                        self.console_scroll.desce(total);
+                    }
+                    KeyCode::Backspace => {
+                        self.input.pop();
+                    }
+                    KeyCode::Char(ch) => {
+                        self.input.push(ch);
+                    }
+                    _ => {}
+                },
+                Modo::Clientes => match k.code {
+                    KeyCode::Enter => {
+                        match self.adicionar_cliente() {
+                            Ok(msg) => {
+                                self.salvar();
+                                self.mensagens.push(msg);
+                            }
+                            Err(e) => self.mensagens.push(e),
+                        }
+                        self.editing = false;
+                        self.input.clear();
+                    }
+                    KeyCode::Esc => {
+                        self.editing = false;
+                        self.input.clear();
+                    }
+                    KeyCode::Backspace => {
+                        self.input.pop();
+                    }
+                    KeyCode::Char(ch) => {
+                        self.input.push(ch);
+                    }
+                    _ => {}
+                },
+                Modo::Fornecedores => match k.code {
+                    KeyCode::Enter => {
+                        let resultado = if self.fornecedores_cadastro {
+                            self.adicionar_fornecedor()
+                        } else {
+                            self.adicionar_referencia()
+                        };
+                        match resultado {
+                            Ok(msg) => {
+                                self.salvar();
+                                self.mensagens.push(msg);
+                            }
+                            Err(e) => self.mensagens.push(e),
+                        }
+                        self.editing = false;
+                        self.input.clear();
+                    }
+                    KeyCode::Esc => {
+                        self.editing = false;
+                        self.input.clear();
+                    }
+                    KeyCode::Backspace => {
+                        self.input.pop();
+                    }
+                    KeyCode::Char(ch) => {
+                        self.input.push(ch);
+                    }
+                    _ => {}
+                },
+                Modo::ListaEspera => match k.code {
+                    KeyCode::Enter => {
+                        self.entrar_lista_espera();
+                        self.modo = Modo::Estoques;
+                        self.editing = false;
+                        self.input.clear();
+                    }
+                    KeyCode::Esc => {
+                        self.espera_codigo = None;
+                        self.modo = Modo::Estoques;
+                        self.editing = false;
+                        self.input.clear();
+                    }
+                    KeyCode::Backspace => {
+                        self.input.pop();
+                    }
+                    KeyCode::Char(ch) => {
+                        self.input.push(ch);
+                    }
+                    _ => {}
+                },
+                Modo::EmitirNota => match k.code {
+                    KeyCode::Enter => {
+                        self.emitir_nota();
+                        self.modo = Modo::Historico;
+                        self.editing = false;
+                        self.input.clear();
+                    }
+                    KeyCode::Esc => {
+                        self.modo = Modo::Historico;
+                        self.editing = false;
+                        self.input.clear();
+                    }
+                    KeyCode::Backspace => {
+                        self.input.pop();
+                    }
+                    KeyCode::Char(ch) => {
+                        self.input.push(ch);
+                    }
+                    _ => {}
+                },
+                Modo::Configuracoes => match k.code {
+                    KeyCode::Enter => {
+                        self.salvar_config();
+                    }
+                    KeyCode::Esc => {
+                        self.input.clear();
+                        self.confere_senha_atual = false;
+                        self.editing = false;
+                    }
+                    KeyCode::Backspace => {
+                        self.input.pop();
+                    }
+                    KeyCode::Char(ch) => {
+                        self.input.push(ch);
+                    }
+                    _ => {}
+                },
+                Modo::ImportarCatalogo => match k.code {
+                    KeyCode::Enter => {
+                        self.carregar_catalogo();
+                    }
+                    KeyCode::Esc => {
+                        self.sai_modo_insercao();
+                    }
+                    KeyCode::Backspace => {
+                        self.input.pop();
+                    }
+                    KeyCode::Char(ch) => {
+                        self.input.push(ch);
+                    }
+                    _ => {}
+                },
+                Modo::MapearCsv => match k.code {
+                    KeyCode::Enter => {
+                        self.confirmar_mapeamento();
+                    }
+                    KeyCode::Esc => {
+                        self.importacao_csv = None;
+                        self.sai_modo_insercao();
+                    }
+                    code => self.mapeamento_tecla(code),
+                },
+                _ => {}
+            }
+        } else {
+            match k.code {
+                _ if self.ajuda.is_some() => self.ajuda_tecla(k.code),
+                KeyCode::Char('s') | KeyCode::Char('S') if self.exclusao.is_some() => {
+                    self.confirmar_exclusao();
+                }
+                _ if self.exclusao.is_some() => {
+                    self.exclusao = None;
+                    self.mensagens.push("Exclusão cancelada.".into());
+                }
+                KeyCode::Char('x') => {
+                    return false;
+                }
+                KeyCode::Char('?') if self.modo != Modo::Quiosque => self.abrir_ajuda(),
+                KeyCode::Enter | KeyCode::Esc if !self.recados_abertos.is_empty() => {
+                    self.ciente_recados();
+                }
+                KeyCode::Char(c @ '1'..='9') if self.modo == Modo::Estoques => {
+                    self.vender_favorito(c as usize - '0' as usize);
+                }
+                KeyCode::Char('p') | KeyCode::Char('P') if self.modo == Modo::Estoques => {
+                    self.alterna_favorito();
+                }
+                KeyCode::Char('+') if self.modo == Modo::Estoques => {
+                    self.ajuste_rapido(1);
+                }
+                KeyCode::Char('-') if self.modo == Modo::Estoques => {
+                    self.ajuste_rapido(-1);
+                }
+                KeyCode::Char('=') if self.modo == Modo::Estoques => {
+                    self.abrir_contagem();
+                }
+                KeyCode::Char('d') | KeyCode::Char('D') | KeyCode::Delete
+                    if self.modo == Modo::Estoques =>
+                {
+                    self.pedir_exclusao();
+                }
+                KeyCode::F(2) if self.modo == Modo::Estoques => {
+                    self.abrir_renomear();
+                }
+                KeyCode::Char(' ') if self.modo == Modo::Estoques => {
+                    self.alterna_comparacao();
+                }
+                KeyCode::Char('o') | KeyCode::Char('O') if self.modo == Modo::Estoques => {
+                    self.abrir_comparacao();
+                }
+                KeyCode::Char('n') | KeyCode::Char('N') if self.modo == Modo::Estoques => {
+                    self.abrir_notificacoes();
+                }
+                KeyCode::Char('e') | KeyCode::Char('E')
+                    if self.modo == Modo::Estoques || self.modo == Modo::Notificacoes =>
+                {
+                    self.entra_modo_insercao(Modo::Recado);
+                }
+                KeyCode::Esc if self.modo == Modo::Notificacoes => {
+                    self.fechar_notificacoes();
+                }
+                KeyCode::Char('d') | KeyCode::Char('D') | KeyCode::Delete
+                    if self.modo == Modo::Notificacoes =>
+                {
+                    let lista = &mut self.registros.notificacoes;
+                    if let Some(i) = lista
+                        .len()
+                        .checked_sub(self.notificacoes_scroll.selected + 1)
+                    {
+                        lista.remove(i);
+                        self.notificacoes_scroll.limita(lista.len());
+                        self.salvar();
+                    }
+                }
+                KeyCode::Char('n') | KeyCode::Char('N') if self.modo == Modo::Orcamentos => {
+                    self.digitar_orcamento(EntradaOrcamento::Novo);
+                }
+                KeyCode::Char('p') | KeyCode::Char('P') if self.modo == Modo::Orcamentos => {
+                    self.digitar_orcamento(EntradaOrcamento::Perda);
+                }
+                KeyCode::Char('a') | KeyCode::Char('A') if self.modo == Modo::Orcamentos => {
+                    self.digitar_orcamento(EntradaOrcamento::Retorno);
+                }
+                KeyCode::Char('g') | KeyCode::Char('G') if self.modo == Modo::Orcamentos => {
+                    self.ganhar_orcamento();
+                }
+                KeyCode::Char('|') if self.modo == Modo::Estoques => {
+                    self.painel_dividido = !self.painel_dividido;
+                    self.foco_historico = false;
+                    self.painel_historico_scroll.reinicia();
+                }
+                KeyCode::Tab if self.modo == Modo::Estoques && self.painel_dividido => {
+                    self.foco_historico = !self.foco_historico;
+                }
+                KeyCode::Char('n') | KeyCode::Char('N')
+                    if self.modo == Modo::Pedidos && self.pedidos_sugestao =>
+                {
+                    self.rascunho_reposicao();
+                }
+                KeyCode::Char('n') | KeyCode::Char('N') if self.modo == Modo::Pedidos => {
+                    self.editing = true;
+                    self.input.clear();
+                }
+                KeyCode::Tab if self.modo == Modo::Pedidos => {
+                    self.pedidos_sugestao = !self.pedidos_sugestao;
+                    self.pedidos_scroll.reinicia();
+                }
+                KeyCode::Char('c') | KeyCode::Char('C') if self.modo == Modo::Pedidos => {
+                    self.responder_rascunho(true);
+                }
+                KeyCode::Char('k') | KeyCode::Char('K') if self.modo == Modo::Pedidos => {
+                    self.responder_rascunho(false);
+                }
+                KeyCode::Char('d') | KeyCode::Char('D') | KeyCode::Delete
+                    if self.modo == Modo::Pedidos && !self.pedidos_sugestao =>
+                {
+                    self.cancelar_pendencia();
+                }
+                KeyCode::Char('n') | KeyCode::Char('N') if self.modo == Modo::Clientes => {
+                    self.editing = true;
+                    self.input.clear();
+                }
+                KeyCode::Char('d') | KeyCode::Char('D') | KeyCode::Delete
+                    if self.modo == Modo::Clientes =>
+                {
+                    self.remover_cliente();
+                }
+                KeyCode::Char('n') | KeyCode::Char('N') if self.modo == Modo::Fornecedores => {
+                    self.editing = true;
+                    self.input.clear();
+                }
+                KeyCode::Char('d') | KeyCode::Char('D') | KeyCode::Delete
+                    if self.modo == Modo::Fornecedores =>
+                {
+                    if self.fornecedores_cadastro {
+                        self.remover_fornecedor();
+                    } else {
+                        self.remover_referencia();
+                    }
+                }
+                KeyCode::Tab if self.modo == Modo::Fornecedores => {
+                    self.fornecedores_cadastro = !self.fornecedores_cadastro;
+                    self.fornecedores_scroll.reinicia();
+                }
+                KeyCode::Esc if self.historico_detalhe.is_some() => {
+                    self.historico_detalhe = None;
+                }
+                KeyCode::Char('n') | KeyCode::Char('N') if self.historico_detalhe.is_some() => {
+                    self.nota_da_venda();
+                }
+                KeyCode::Esc => {
+                    self.cancelar_selecao();
+                    self.estoque_em = None;
+                    if self.modo == Modo::Estoques {
+                        self.comparar.clear();
+                    }
+                    self.modo = Modo::Estoques;
+                    self.historico_filtrado = None;
+                    self.historico_ano_arquivo = None;
+                    self.historico_marcados.clear();
+                    self.historico_etiqueta = None;
+                    self.input.clear();
+                    self.editing = false;
+                }
+                KeyCode::Char('c') => {
+                    self.entra_modo_insercao(Modo::Cadastro);
+                }
+                KeyCode::Char('b') => {
+                    self.entra_modo_insercao(Modo::Buscar);
+                    self.atualizar_busca_results();
+                }
+                KeyCode::Char('h') if self.modo != Modo::Historico => {
+                    self.modo = Modo::Historico;
+                    self.historico_detalhe = None;
+                    self.mensagens.push("Histórico: ↑/↓ rola, ←/→ abas, P p/pesquisar, Enter p/filtrar, ESC p/sair.".into());
+                    self.editing = false;
+                    self.input.clear();
+                    self.historico_scroll.reinicia();
+                    // Ao entrar no modo histórico, não estamos editando ainda.
+                }
+                KeyCode::Char('g') => {
+                    self.modo = Modo::Grafico;
+                    self.atualiza_grafico_valor();
+                }
+                KeyCode::Char('i') => {
+                    self.importacao.clear();
+                    self.entra_modo_insercao(Modo::ImportarCatalogo);
+                }
+                KeyCode::Char('s') => {
+                    self.modo = Modo::Configuracoes;
+                    self.input.clear();
+                }
+                KeyCode::Char('m') => {
+                    self.consultar_mercado();
+                }
+                KeyCode::Char('k') => {
+                    self.abrir_admin();
+                }
+                KeyCode::Char('r') | KeyCode::Char('R') => {
+                    self.abrir_recentes();
+                }
+                KeyCode::Char('q') | KeyCode::Char('Q') => {
+                    self.abrir_quiosque();
+                }
+                KeyCode::Char('u') | KeyCode::Char('U') => {
+                    self.abrir_fornecedores();
+                }
+                KeyCode::Char('@') => {
+                    self.abrir_clientes();
+                }
+                KeyCode::Char('~') if self.modo == Modo::Estoques => {
+                    self.mostrar_arquivados = !self.mostrar_arquivados;
+                    self.atualiza_estoques_list();
+                    self.atualiza_cadastro_list();
+                    self.mensagens.push(
+                        if self.mostrar_arquivados {
+                            "Mostrando também os produtos arquivados."
+                        } else {
+                            "Produtos arquivados ocultos."
+                        }
+                        .into(),
+                    );
+                }
+                KeyCode::Char(':') => {
+                    self.entra_modo_insercao(Modo::Console);
+                }
+                KeyCode::Char('j') | KeyCode::Char('J') => {
+                    self.abrir_recebimento();
+                }
+                KeyCode::Char('y') | KeyCode::Char('Y') if self.modo == Modo::Estoques => {
+                    self.abrir_pedidos();
+                }
+                KeyCode::Char('z') | KeyCode::Char('Z') if self.modo == Modo::Estoques => {
+                    self.abrir_orcamentos();
+                }
+                KeyCode::Char('f') | KeyCode::Char('F') if self.modo == Modo::Estoques => {
+                    self.alterna_destaque();
+                }
+                KeyCode::Left | KeyCode::Right if self.modo == Modo::Quiosque => {
+                    let total = self.paginas_quiosque().len();
+                    self.quiosque_pagina = if k.code == KeyCode::Left {
+                        (self.quiosque_pagina % total + total - 1) % total
+                    } else {
+                        self.quiosque_pagina + 1
+                    };
+                    self.quiosque_troca = Instant::now();
+                }
+                KeyCode::Char('t') | KeyCode::Char('T')
+                    if self.modo == Modo::Estoques && self.estoque_em.is_some() =>
+                {
+                    self.estoque_em = None;
+                    self.mensagens.push("De volta ao estoque atual.".into());
+                }
+                KeyCode::Char('t') | KeyCode::Char('T') if self.modo == Modo::Estoques => {
+                    self.entra_modo_insercao(Modo::EstoqueEm);
+                }
+                KeyCode::Char('w') => {
+                    self.enviar_planilha();
+                }
+                KeyCode::Char('l') => {
+                    self.imprimir_etiqueta_selecionada();
+                }
+                KeyCode::Char('L') => {
+                    self.imprimir_etiquetas_pendentes();
+                }
+                KeyCode::Char(' ') if self.modo == Modo::ImportarCatalogo => {
+                    self.importacao_alterna();
+                }
+                KeyCode::Char('d') | KeyCode::Char('D')
+                    if self.modo == Modo::ImportarCatalogo && !self.importacao.is_empty() =>
+                {
+                    self.simular_catalogo();
+                }
+                KeyCode::Left if self.modo == Modo::Historico => {
+                    self.historico_tab_prev();
+                }
+                KeyCode::Right if self.modo == Modo::Historico => {
+                    self.historico_tab_next();
+                }
+                KeyCode::Left if self.modo == Modo::Grafico => {
+                    self.grafico_tab_troca(false);
+                }
+                KeyCode::Char('e') | KeyCode::Char('E') if self.modo == Modo::Grafico => {
+                    self.exportar_grafico();
+                }
+                KeyCode::Right if self.modo == Modo::Grafico => {
+                    self.grafico_tab_troca(true);
+                }
+                KeyCode::Up | KeyCode::Down
+                    if self.modo == Modo::Grafico
+                        && self.grafico_tab == GraficoTab::ValorEstoque =>
+                {
+                    self.grafico_mensal = !self.grafico_mensal;
+                    self.atualiza_grafico_valor();
+                }
+                KeyCode::Up | KeyCode::Down
+                    if self.modo == Modo::Grafico && self.grafico_tab == GraficoTab::MesAMes =>
+                {
+                    self.grafico_acumulado = !self.grafico_acumulado;
+                }
+                KeyCode::Up if self.modo == Modo::Grafico => {
+                    self.grafico_periodo = self.grafico_periodo.saturating_sub(1);
+                }
+                KeyCode::Down if self.modo == Modo::Grafico => {
+                    self.grafico_periodo =
+                        (self.grafico_periodo + 1).min(PERIODOS_GRAFICO.len() - 1);
+                }
+                KeyCode::Up => match self.modo {
+                    Modo::Estoques if self.foco_historico => {
+                        self.painel_historico_scroll.sobe();
+                    }
+                    Modo::Estoques => {
+                        self.estoques_scroll.sobe();
+                        self.painel_historico_scroll.reinicia();
+                    }
+                    Modo::Historico => {
+                        self.historico_select_up();
+                    }
+                    Modo::Cadastro => {
+                        self.cadastro_select_up();
+                    }
+                    Modo::Buscar => {
+                        self.buscar_select_up();
+                    }
+                    Modo::ImportarCatalogo => {
+                        self.importacao_select_up();
+                    }
+                    Modo::Configuracoes => {
+                        self.config_select_up();
+                    }
+                    Modo::Admin => {
+                        self.admin_scroll.sobe();
+                    }
+                    Modo::Recentes => {
+                        self.recentes_scroll.sobe();
+                    }
+                    Modo::Fornecedores => {
+                        self.fornecedores_scroll.sobe();
+                    }
+                    Modo::Clientes => {
+                        self.clientes_scroll.sobe();
+                    }
+                    Modo::Notificacoes => {
+                        self.notificacoes_scroll.sobe();
+                    }
+                    Modo::Pedidos => {
+                        self.pedidos_scroll.sobe();
+                    }
+                    Modo::Orcamentos => {
+                        self.orcamentos_scroll.sobe();
+                    }
+                    _ => {}
+                },
+                KeyCode::Down => match self.modo {
+                    Modo::Estoques if self.foco_historico => {
+                        let total = self.historico_do_selecionado().len();
+                        self.painel_historico_scroll.desce(total);
+                    }
+                    Modo::Estoques => {
+                        self.estoques_scroll.desce(self.estoques_list.len());
+                        self.painel_historico_scroll.reinicia();
+                    }
+                    Modo::Historico => {
+                        self.historico_select_down();
+                    }
+                    Modo::Cadastro => {
+                        self.cadastro_select_down();
+                    }
+                    Modo::Buscar => {
+                        self.buscar_select_down();
+                    }
+                    Modo::ImportarCatalogo => {
+                        self.importacao_select_down();
+                    }
+                    Modo::Configuracoes => {
+                        self.config_select_down();
+                    }
+                    Modo::Admin => {
+                        self.admin_scroll.desce(self.admin_acessos.len());
+                    }
+                    Modo::Recentes => {
+                        self.recentes_scroll.desce(self.recentes.len());
+                    }
+                    Modo::Fornecedores => {
+                        let total = if self.fornecedores_cadastro {
+                            self.registros.fornecedores.len()
+                        } else {
+                            self.registros.referencias_fornecedor.len()
+                        };
+                        self.fornecedores_scroll.desce(total);
+                    }
+                    Modo::Clientes => {
+                        self.clientes_scroll.desce(self.registros.clientes.len());
+                    }
+                    Modo::Notificacoes => {
+                        self.notificacoes_scroll
+                            .desce(self.registros.notificacoes.len());
+                    }
+                    Modo::Pedidos => {
+                        let total = if self.pedidos_sugestao {
+                            self.sugestoes_reposicao().len()
+                        } else {
+                            pedidos::pendencias(
+                                &self.registros.pedidos_compra,
+                                Local::now().date_naive(),
+                            )
+                            .len()
+                        };
+                        self.pedidos_scroll.desce(total);
+                    }
+                    Modo::Orcamentos => {
+                        let total = orcamentos::fila(&self.registros.orcamentos).len();
+                        self.orcamentos_scroll.desce(total);
+                    }
+                    _ => {}
+                },
+                KeyCode::Enter => match self.modo {
+                    Modo::Clientes => self.alterna_cliente_atendido(),
+                    Modo::Cadastro if !self.input.trim().is_empty() => {
+                        self.confirmar_cadastro();
+                        self.input.clear();
+                    }
+                    Modo::Buscar if !self.buscar_results.is_empty() => {
+                        let (cod, _, _) = self.buscar_results[self.buscar_scroll.selected].clone();
+                        self.selecionar_registro(cod);
+                    }
+                    Modo::Estoques => {
+                        if let Some(r) = self.estoques_list.get(self.estoques_scroll.selected) {
+                            self.selecionar_registro(r.codigo.clone());
+                        }
+                    }
+                    Modo::Recentes => {
+                        if let Some(codigo) = self.recentes.get(self.recentes_scroll.selected) {
+                            self.selecionar_registro(codigo.clone());
+                        }
+                        self.modo = Modo::Estoques;
+                    }
+                    Modo::Configuracoes => {
+                        self.editar_config();
+                    }
+                    Modo::Historico => {
+                        self.abrir_detalhe_historico();
+                    }
+                    Modo::ImportarCatalogo if !self.importacao.is_empty() => {
+                        self.aplicar_catalogo();
+                        self.modo = Modo::Estoques;
+                    }
+                    _ => {}
+                },
+                KeyCode::Char('A') | KeyCode::Char('a')
+                    if self.chosen_relogio.is_some()
+                        && (self.modo == Modo::Estoques || self.modo == Modo::Buscar) =>
+                {
+                    self.escolher_operacao('A');
+                }
+                KeyCode::Char('V') | KeyCode::Char('v')
+                    if self.chosen_relogio.is_some()
+                        && (self.modo == Modo::Estoques || self.modo == Modo::Buscar) =>
+                {
+                    self.escolher_operacao('V');
+                }
+                KeyCode::Char('y') | KeyCode::Char('Y') if self.modo == Modo::Historico => {
+                    self.proximo_ano_arquivado();
+                }
+                // Apertar P no histórico para pesquisar
+                KeyCode::Char('p') | KeyCode::Char('P')
+                    if self.modo == Modo::Historico && !self.editing =>
+                {
+                    self.editing = true;
+                    self.historico_entrada = EntradaHistorico::Pesquisa;
+                    self.input.clear();
+                    self.atualizar_historico_search_results();
+                }
+                KeyCode::Char(' ') if self.modo == Modo::Historico => {
+                    self.alterna_marca_historico();
+                }
+                KeyCode::Char('*') if self.modo == Modo::Historico => {
+                    self.marca_todos_historico();
+                }
+                KeyCode::Char('e') | KeyCode::Char('E') if self.modo == Modo::Historico => {
+                    self.exportar_csv();
+                }
+                KeyCode::Char('t') | KeyCode::Char('T') if self.modo == Modo::Historico => {
+                    self.editing = true;
+                    self.historico_entrada = EntradaHistorico::Etiqueta;
+                    self.input.clear();
+                }
+                KeyCode::Char('f') | KeyCode::Char('F') if self.modo == Modo::Historico => {
+                    self.editing = true;
+                    self.historico_entrada = EntradaHistorico::FiltroEtiqueta;
+                    self.input = self.historico_etiqueta.clone().unwrap_or_default();
+                }
+                _ => {}
+            }
+        }
+        true
+    }
+
+    /// Renderiza a tela num buffer em memória, sem terminal.
+    pub fn render_to_buffer(&mut self, largura: u16, altura: u16) -> ratatui::buffer::Buffer {
+        let backend = ratatui::backend::TestBackend::new(largura, altura);
+        let mut terminal = Terminal::new(backend).expect("TestBackend não falha");
+        terminal
+            .draw(|f| self.render(f))
+            .expect("TestBackend não falha");
+        terminal.backend().buffer().clone()
+    }
+}
+
+/// Abre a interface no terminal até o usuário sair.
+pub fn executa() -> Result<(), Box<dyn std::error::Error>> {
+    // O servidor segura a trava só durante uma venda pela API
+    let mut trava = persistencia::trava()?;
+    for _ in 0..20 {
+        if trava.is_some() {
+            break;
+        }
+        std::thread::sleep(Duration::from_millis(100));
+        trava = persistencia::trava()?;
+    }
+    let Some(_trava) = trava else {
+        return Err("Os dados já estão abertos em outra janela do programa.".into());
+    };
+
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen)?;
+    let backend = CrosstermBackend::new(stdout);
+    let mut terminal = Terminal::new(backend)?;
+
+    let mut app = App::carrega();
+
+    loop {
+        terminal.draw(|f| app.render(f))?;
+
+        app.processa_busca_pendente();
+        app.verifica_planilha();
+        app.verifica_relatorios();
+        app.verifica_quiosque();
+        app.verifica_demonstracao();
+        app.verifica_mercado();
+        app.verifica_cambio();
+        app.limita_mensagens();
+        let espera = app
+            .busca_restante()
+            .unwrap_or(Duration::from_millis(100))
+            .min(Duration::from_millis(100));
+        if crossterm::event::poll(espera)? {
+            match event::read()? {
+                Event::Key(k) if !app.handle_key(k) => break,
+                Event::Resize(_, _) => app.ajusta_rolagem(),
+                _ => {}
+            }
+        }
+    }
+
+    recentes::salva(&app.recentes);
+    disable_raw_mode()?;
+    execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+    terminal.show_cursor()?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::KeyModifiers;
+    use std::path::PathBuf;
+
+    const LARGURA: u16 = 100;
+    const ALTURA: u16 = 32;
+
+    #[test]
+    fn exporta_csv_da_aba_em_uso() {
+        let mut app = app_de_teste();
+        tecla(&mut app, KeyCode::Char('h'));
+        tecla(&mut app, KeyCode::Right);
+        tecla(&mut app, KeyCode::Right);
+        let (estoque, historico) = app.csvs_para_exportar();
+        let estoque: Vec<&str> = estoque.lines().collect();
+        assert_eq!(estoque.len(), 4);
+        assert_eq!(
+            estoque[2],
+            "R002;Seiko 5 Automático;;;;0;BRL;850,00;1.499,00"
+        );
+        let historico: Vec<&str> = historico.lines().collect();
+        assert_eq!(historico.len(), 3);
+        assert!(historico[1..].iter().all(|l| l.contains(";VENDA;")));
+        assert!(
+            historico.contains(&"2024-01-12 16:00:00;VENDA;R002;Seiko 5 Automático;2;;2.998,00;;;")
+        );
+    }
+
+    fn app_de_teste() -> App {
+        let relogios: HashMap<String, Relogio> = [
+            (
+                "R001",
+                12,
+                "Casio Vintage #unissex",
+                Some(120.0),
+                Some(249.9),
+            ),
+            ("R002", 0, "Seiko 5 Automático", Some(850.0), Some(1499.0)),
+            ("R003", 3, "Orient Bambino", Some(700.0), None),
+        ]
+        .into_iter()
+        .map(|(codigo, quantidade, descricao, custo, preco)| {
+            let mut r = Relogio::novo(codigo, Quantidade::nova(quantidade).unwrap());
+            r.descricao = descricao.into();
+            r.custo = custo;
+            r.preco = preco;
+            (r.codigo.clone(), r)
+        })
+        .collect();
+        let historico = [
+            ("R001", 10, "CADASTRO", "2024-01-02 09:00:00"),
+            ("R002", 2, "CADASTRO", "2024-01-02 09:05:00"),
+            ("R001", 4, "COMPRA", "2024-01-10 14:30:00"),
+            ("R002", 2, "VENDA", "2024-01-12 16:00:00"),
+            ("R001", 2, "VENDA", "2024-01-15 11:20:00"),
+            ("R003", 3, "CADASTRO", "2024-01-20 10:00:00"),
+        ]
+        .into_iter()
+        .map(|(codigo, quantidade, operacao, timestamp)| Historico {
+            codigo: codigo.into(),
+            quantidade,
+            operacao: operacao.into(),
+            timestamp: timestamp.into(),
+            ..Default::default()
+        })
+        .collect();
+        App::com_dados(
+            relogios,
+            historico,
+            Registros::default(),
+            config::Config::default(),
+            HashMap::new(),
+        )
+    }
+
+    fn tecla(app: &mut App, code: KeyCode) -> bool {
+        app.handle_key(KeyEvent::new(code, KeyModifiers::NONE))
+    }
+
+    fn digita(app: &mut App, texto: &str) {
+        for c in texto.chars() {
+            tecla(app, KeyCode::Char(c));
+        }
+    }
+
+    fn tela(app: &mut App, largura: u16, altura: u16) -> String {
+        let buffer = app.render_to_buffer(largura, altura);
+        (0..altura)
+            .map(|y| {
+                let linha: String = (0..largura).map(|x| buffer[(x, y)].symbol()).collect();
+                linha.trim_end().to_string() + "\n"
+            })
+            .collect()
+    }
+
+    /// Compara a tela com `tests/golden/<nome>.txt`; com `ATUALIZAR_GOLDEN=1`
+    /// grava a tela atual como referência.
+    fn confere_golden(nome: &str, app: &mut App) {
+        let atual = tela(app, LARGURA, ALTURA);
+        let caminho = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/golden")
+            .join(format!("{}.txt", nome));
+        if std::env::var_os("ATUALIZAR_GOLDEN").is_some() {
+            fs::create_dir_all(caminho.parent().unwrap()).unwrap();
+            fs::write(&caminho, &atual).unwrap();
+            return;
+        }
+        let esperado = fs::read_to_string(&caminho).unwrap_or_else(|_| {
+            panic!("{} ausente; rode com ATUALIZAR_GOLDEN=1", caminho.display())
+        });
+        assert_eq!(
+            esperado, atual,
+            "tela '{}' mudou; confira e rode com ATUALIZAR_GOLDEN=1 se for intencional",
+            nome
+        );
+    }
+
+    #[test]
+    fn tela_estoques() {
+        let mut app = app_de_teste();
+        tecla(&mut app, KeyCode::Down);
+        confere_golden("estoques", &mut app);
+    }
+
+    #[test]
+    fn tela_estoque_em_data() {
+        let mut app = app_de_teste();
+        tecla(&mut app, KeyCode::Char('t'));
+        digita(&mut app, "12/01/2024");
+        tecla(&mut app, KeyCode::Enter);
+        confere_golden("estoque_em_data", &mut app);
+        tecla(&mut app, KeyCode::Char('t'));
+        assert!(app.estoque_em.is_none());
+    }
+
+    #[test]
+    fn tela_quiosque() {
+        let mut app = app_de_teste();
+        app.relogios.get_mut("R001").unwrap().destaque = true;
+        app.atualiza_estoques_list();
+        tecla(&mut app, KeyCode::Char('q'));
+        tecla(&mut app, KeyCode::Right);
+        confere_golden("quiosque", &mut app);
+        tecla(&mut app, KeyCode::Right);
+        assert_eq!(app.quiosque_pagina % app.paginas_quiosque().len(), 0);
+    }
+
+    #[test]
+    fn tela_recados_do_turno() {
+        let mut app = app_de_teste();
+        app.recados_abertos = vec![Recado {
+            autor: "Ana".into(),
+            mensagem: "Cliente do R002 volta sexta para retirar.".into(),
+            timestamp: "2024-01-20 18:00:00".into(),
+            lido: false,
+        }];
+        confere_golden("recados", &mut app);
+    }
+
+    #[test]
+    fn tela_comparacao() {
+        let mut app = app_de_teste();
+        tecla(&mut app, KeyCode::Char(' '));
+        tecla(&mut app, KeyCode::Down);
+        tecla(&mut app, KeyCode::Char(' '));
+        tecla(&mut app, KeyCode::Char('o'));
+        assert_eq!(app.modo, Modo::Comparacao);
+        confere_golden("comparacao", &mut app);
+    }
+
+    #[test]
+    fn favorito_abre_venda_preenchida() {
+        let mut app = app_de_teste();
+        app.registros.favoritos = vec!["R003".into(), "R001".into()];
+        tecla(&mut app, KeyCode::Char('2'));
+        assert_eq!(app.modo, Modo::Venda);
+        assert_eq!(app.input, "R001 ");
+        tecla(&mut app, KeyCode::Esc);
+        tecla(&mut app, KeyCode::Char('9'));
+        assert_eq!(app.modo, Modo::Estoques);
+    }
+
+    #[test]
+    fn tela_recentes() {
+        let mut app = app_de_teste();
+        tecla(&mut app, KeyCode::Down);
+        tecla(&mut app, KeyCode::Enter);
+        tecla(&mut app, KeyCode::Up);
+        tecla(&mut app, KeyCode::Enter);
+        tecla(&mut app, KeyCode::Char('r'));
+        tecla(&mut app, KeyCode::Down);
+        confere_golden("recentes", &mut app);
+        tecla(&mut app, KeyCode::Enter);
+        assert_eq!(app.chosen_relogio.as_deref(), Some("R002"));
+        assert_eq!(app.recentes, ["R002", "R001"]);
+    }
+
+    #[test]
+    fn tela_cadastro() {
+        let mut app = app_de_teste();
+        tecla(&mut app, KeyCode::Char('c'));
+        digita(&mut app, "R010 5");
+        confere_golden("cadastro", &mut app);
+    }
+
+    #[test]
+    fn entrada_incompleta_ou_com_sobra_da_erro_em_todo_modo() {
+        let br = numeros::FormatoNumero::PtBr;
+        assert_eq!(
+            le_movimento(&["R001", "1.200", "pix"], br, USO_VENDA),
+            Ok(Movimento {
+                codigo: "R001",
+                quantidade: 1200,
+                extra: Some("pix"),
+            })
+        );
+        assert!(le_movimento(&[], br, USO_VENDA)
+            .unwrap_err()
+            .starts_with("Informe o código"));
+        assert_eq!(
+            le_movimento(&["R001", "dois"], br, USO_VENDA),
+            Err("Quantidade inválida: 'dois'.".into())
+        );
+
+        let mut app = app_de_teste();
+        tecla(&mut app, KeyCode::Char('c'));
+        digita(&mut app, "R009");
+        tecla(&mut app, KeyCode::Enter);
+        assert!(!app.relogios.contains_key("R009"));
+        assert_eq!(
+            app.mensagens.last().unwrap(),
+            &format!("Falta a quantidade de R009: {}", USO_CADASTRO)
+        );
+
+        tecla(&mut app, KeyCode::Esc);
+        tecla(&mut app, KeyCode::Enter);
+        tecla(&mut app, KeyCode::Char('v'));
+        app.input = "R001".into();
+        tecla(&mut app, KeyCode::Enter);
+        assert!(app
+            .mensagens
+            .last()
+            .unwrap()
+            .starts_with("Falta a quantidade"));
+        assert_eq!(app.relogios["R001"].quantidade(), 12);
+
+        app.modo = Modo::Compra;
+        app.editing = true;
+        app.input = "R001 2 cx pix".into();
+        tecla(&mut app, KeyCode::Enter);
+        assert_eq!(
+            app.mensagens.last().unwrap(),
+            &format!("Sobrou 'pix' na entrada: {}", USO_COMPRA)
+        );
+        assert_eq!(app.relogios["R001"].quantidade(), 12);
+    }
+
+    #[test]
+    fn quantidade_absurda_ou_que_estoura_o_saldo_e_recusada() {
+        let mut app = app_de_teste();
+        let compra = |app: &mut App, input: &str| {
+            app.modo = Modo::Compra;
+            app.editing = true;
+            app.input = input.into();
+            tecla(app, KeyCode::Enter);
+            app.mensagens.last().unwrap().clone()
+        };
+        assert_eq!(
+            compra(&mut app, "R001 10.001"),
+            "10001 unidades passam do limite de 10000 por operação."
+        );
+        assert_eq!(
+            compra(&mut app, "R001 99999999999"),
+            "Quantidade inválida: '99999999999'."
+        );
+        app.config.quantidade_maxima = 0;
+        app.relogios
+            .get_mut("R001")
+            .unwrap()
+            .define_quantidade(i32::MAX - 1);
+        assert_eq!(
+            compra(&mut app, "R001 2"),
+            "O saldo de R001 passaria do máximo possível."
+        );
+        assert_eq!(app.relogios["R001"].quantidade(), i32::MAX - 1);
+        assert_eq!(app.historico.len(), app_de_teste().historico.len());
+    }
+
+    #[test]
+    fn venda_fica_no_nome_do_cliente_atendido() {
+        let mut app = app_de_teste();
+        tecla(&mut app, KeyCode::Char('@'));
+        assert_eq!(app.modo, Modo::Clientes);
+        tecla(&mut app, KeyCode::Char('n'));
+        app.input = "Maria Souza;11 98888-0000;gosta de Seiko".into();
+        assert_eq!(
+            app.adicionar_cliente(),
+            Ok("Cliente Maria Souza cadastrado.".into())
+        );
+        app.input.clear();
+        app.editing = false;
+        app.historico.push(Historico {
+            codigo: "R001".into(),
+            quantidade: 1,
+            operacao: "VENDA".into(),
+            timestamp: "2024-02-01 10:00:00".into(),
+            cliente: Some("maria souza".into()),
+            ..Default::default()
+        });
+        confere_golden("clientes", &mut app);
+
+        tecla(&mut app, KeyCode::Enter);
+        assert_eq!(app.cliente_atendido.as_deref(), Some("Maria Souza"));
+        assert_eq!(app.modo, Modo::Estoques);
+        tecla(&mut app, KeyCode::Char('@'));
+        tecla(&mut app, KeyCode::Enter);
+        assert!(app.cliente_atendido.is_none());
+    }
+
+    #[test]
+    fn compra_com_fornecedor_cadastrado_e_filtro_do_historico() {
+        assert_eq!(
+            separa_fornecedor("R001 2 Fornecedor=seiko cx"),
+            ("R001 2 cx".to_string(), Some("seiko"))
+        );
+        let mut app = app_de_teste();
+        for nome in ["Seiko do Brasil;(11) 3333-0000;entrega às terças", "Casio"] {
+            fornecedores::cadastra(
+                &mut app.registros.fornecedores,
+                fornecedores::interpreta(nome).unwrap(),
+            );
+        }
+        app.modo = Modo::Compra;
+        app.editing = true;
+        app.input = "R001 2 forn=orient".into();
+        tecla(&mut app, KeyCode::Enter);
+        assert_eq!(
+            app.mensagens.last().unwrap(),
+            "Fornecedor 'orient' não cadastrado."
+        );
+        assert_eq!(app.relogios["R001"].quantidade(), 12);
+
+        app.historico.push(Historico {
+            codigo: "R001".into(),
+            quantidade: 5,
+            operacao: "COMPRA".into(),
+            timestamp: "2024-02-01 10:00:00".into(),
+            custo: Some(120.0),
+            fornecedor: Some("SEIKO DO BRASIL".into()),
+            ..Default::default()
+        });
+        app.filtrar_historico("fornecedor=seiko");
+        assert_eq!(app.historico_filtrado.as_ref().map(Vec::len), Some(1));
+        assert_eq!(
+            app.mensagens.last().unwrap(),
+            "Histórico filtrado pelo fornecedor Seiko do Brasil exibido"
+        );
+
+        tecla(&mut app, KeyCode::Esc);
+        tecla(&mut app, KeyCode::Char('u'));
+        tecla(&mut app, KeyCode::Tab);
+        confere_golden("fornecedores_cadastro", &mut app);
+    }
+
+    #[test]
+    fn arquivados_somem_das_listas_ate_o_til() {
+        let mut app = app_de_teste();
+        app.relogios.get_mut("R002").unwrap().situacao = Situacao::Encomenda;
+        app.relogios.get_mut("R003").unwrap().situacao = Situacao::Arquivado;
+        app.atualiza_estoques_list();
+        assert_eq!(app.estoques_list.len(), 2);
+        app.input = "R003".into();
+        app.atualizar_busca_results();
+        assert_eq!(app.buscar_total, 0);
+        app.input.clear();
+        confere_golden("estoques_situacao", &mut app);
+
+        tecla(&mut app, KeyCode::Char('~'));
+        assert_eq!(app.estoques_list.len(), 3);
+        assert_eq!(
+            app.mensagens.last().unwrap(),
+            "Mostrando também os produtos arquivados."
+        );
+        app.input = "R003".into();
+        app.atualizar_busca_results();
+        assert_eq!(app.buscar_total, 1);
+    }
+
+    #[test]
+    fn sugestao_de_reposicao_vira_rascunho_de_pedido() {
+        let mut app = app_de_teste();
+        for (codigo, minimo, alvo) in [("R002", 1, 5), ("R003", 3, 6), ("R001", 4, 20)] {
+            let r = app.relogios.get_mut(codigo).unwrap();
+            r.estoque_minimo = Some(minimo);
+            r.estoque_alvo = Some(alvo);
+        }
+        tecla(&mut app, KeyCode::Char('y'));
+        tecla(&mut app, KeyCode::Tab);
+        confere_golden("reposicao", &mut app);
+
+        tecla(&mut app, KeyCode::Down);
+        tecla(&mut app, KeyCode::Char('n'));
+        assert!(app.editing);
+        assert_eq!(app.input, "R003:3 ");
+        app.input = "R002:5 R003:3 Casio".into();
+        assert!(app.abrir_pedido_compra().is_ok());
+        assert!(app.sugestoes_reposicao().is_empty());
+
+        // O pedido aberto aparece como "a caminho" no Estoque
+        app.editing = false;
+        app.input.clear();
+        tecla(&mut app, KeyCode::Esc);
+        confere_golden("estoques_em_pedido", &mut app);
+
+        // O total a caminho soma só o que o filtro deixa na tabela
+        let totais = |app: &mut App| {
+            tela(app, LARGURA, ALTURA)
+                .lines()
+                .find(|l| l.contains("Σ"))
+                .unwrap()
+                .to_string()
+        };
+        assert!(totais(&mut app).contains("+8"));
+        app.relogios.get_mut("R003").unwrap().situacao = Situacao::Arquivado;
+        app.atualiza_estoques_list();
+        let linha = totais(&mut app);
+        assert!(linha.contains("+5") && !linha.contains("+8"), "{}", linha);
+    }
+
+    #[test]
+    fn tela_buscar() {
+        let mut app = app_de_teste();
+        tecla(&mut app, KeyCode::Char('b'));
+        digita(&mut app, "R00");
+        app.atualizar_busca_results();
+        confere_golden("buscar", &mut app);
+    }
+
+    #[test]
+    fn candidatos_pontuados_em_banda_com_a_distancia_exata() {
+        let app = app_de_teste();
+        let codigos: Vec<String> = app.relogios.keys().cloned().collect();
+        for query in ["r", "R0", "r01", "R003"] {
+            let candidatos: Vec<&String> = codigos
+                .iter()
+                .filter(|c| contem_subsequencia(c, query))
+                .collect();
+            let pontuados = app.pontua_candidatos(candidatos.iter().copied(), query);
+            assert_eq!(pontuados.len(), candidatos.len(), "{}", query);
+            for (codigo, _, dist) in pontuados {
+                let exata = levenshtein_distance(&codigo.to_lowercase(), &query.to_lowercase());
+                assert_eq!(dist, exata, "{} / {}", codigo, query);
+            }
+        }
+    }
+
+    #[test]
+    fn busca_realca_o_que_casou() {
+        assert_eq!(posicoes_da_busca("R001-Casio", "r1c"), [0, 3, 5]);
+        // Da distância (erro de digitação) casa só o começo
+        assert_eq!(posicoes_da_busca("R003", "R08"), [0, 1]);
+
+        let mut app = app_de_teste();
+        tecla(&mut app, KeyCode::Char('b'));
+        digita(&mut app, "r3");
+        app.atualizar_busca_results();
+        let buffer = app.render_to_buffer(LARGURA, ALTURA);
+        let (x, y) = (0..ALTURA)
+            .flat_map(|y| (0..LARGURA).map(move |x| (x, y)))
+            .find(|(x, y)| buffer[(*x, *y)].symbol() == "R" && buffer[(*x + 3, *y)].symbol() == "3")
+            .unwrap();
+        let realcado = |x| buffer[(x, y)].modifier.contains(Modifier::UNDERLINED);
+        assert_eq!(
+            (realcado(x), realcado(x + 1), realcado(x + 3)),
+            (true, false, true)
+        );
+    }
+
+    #[test]
+    fn paleta_clara_pinta_fundo_e_selecao() {
+        let mut app = app_de_teste();
+        app.config.define("paleta", "Claro").unwrap();
+        assert!(app.config.define("paleta", "neon").is_err());
+        app.cores = tema::Cores::da_config(&app.config).0;
+        let buffer = app.render_to_buffer(LARGURA, ALTURA);
+        let (x, y) = (0..ALTURA)
+            .flat_map(|y| (0..LARGURA).map(move |x| (x, y)))
+            .find(|(x, y)| buffer[(*x, *y)].symbol() == "R" && buffer[(*x + 3, *y)].symbol() == "1")
+            .unwrap();
+        assert_eq!(buffer[(x, y)].bg, Color::Blue);
+        assert_eq!(buffer[(x, y)].fg, Color::White);
+        assert_eq!(buffer[(x, y + 1)].fg, Color::Black);
+        assert_eq!(buffer[(LARGURA - 1, ALTURA - 1)].bg, Color::White);
+    }
+
+    #[test]
+    fn telas_em_ingles_sem_mudar_o_gravado() {
+        let mut app = app_de_teste();
+        app.config.define("idioma", "en-US").unwrap();
+        tecla(&mut app, KeyCode::Down);
+        confere_golden("estoques_en", &mut app);
+        tecla(&mut app, KeyCode::Char('h'));
+        confere_golden("historico_en", &mut app);
+        assert!(app.historico.iter().any(|h| h.operacao == "VENDA"));
+    }
+
+    #[test]
+    fn ajuda_abre_na_tela_atual_e_fecha_com_qualquer_tecla() {
+        let mut app = app_de_teste();
+        digita(&mut app, "?");
+        assert_eq!(app.ajuda, Some(0));
+        confere_golden("ajuda", &mut app);
+        tecla(&mut app, KeyCode::Up);
+        assert_eq!(app.ajuda, Some(0));
+        // A tecla que fecha a ajuda não faz mais nada
+        tecla(&mut app, KeyCode::Char('h'));
+        assert!(app.ajuda.is_none());
+        assert!(app.modo == Modo::Estoques);
+
+        tecla(&mut app, KeyCode::Char('h'));
+        digita(&mut app, "?");
+        assert!(app.ajuda.is_some_and(|topo| topo > 0));
+        let texto = tela(&mut app, LARGURA, ALTURA);
+        assert!(texto.contains("Histórico (H)"));
+        assert!(!texto.contains("Estoque (tela inicial)"));
+        tecla(&mut app, KeyCode::Esc);
+        assert!(app.ajuda.is_none());
+        assert!(app.modo == Modo::Historico);
+    }
+
+    #[test]
+    fn ean_lido_vira_codigo_interno() {
+        let mut app = app_de_teste();
+        app.relogios.get_mut("R003").unwrap().ean = Some("4006381333931".into());
+        tecla(&mut app, KeyCode::Char('b'));
+        digita(&mut app, "4006381333931");
+        app.atualizar_busca_results();
+        assert_eq!(app.buscar_results, [("R003".to_string(), 3, 0)]);
+        app.input = "4006381333931 2 pix".into();
+        assert_eq!(app.input_com_ean(), "R003 2 pix");
+        app.input = "R001 1".into();
+        assert_eq!(app.input_com_ean(), "R001 1");
+    }
+
+    #[test]
+    fn operacao_com_data_fica_pendente() {
+        let hoje = NaiveDate::from_ymd_opt(2025, 3, 1).unwrap();
+        let (resto, efeito) = separa_data_efeito("R001 2 @08/03 pix", hoje);
+        assert_eq!(resto, "R001 2 pix");
+        assert_eq!(efeito, Ok(NaiveDate::from_ymd_opt(2025, 3, 8)));
+        assert_eq!(separa_data_efeito("R001 2 @0", hoje).1, Ok(None));
+        assert!(separa_data_efeito("R001 2 @32/03", hoje).1.is_err());
+
+        // Direto no registro: agendar pela tela gravaria o estoque.json
+        let mut app = app_de_teste();
+        let agora = Local::now().naive_local();
+        for (tipo, codigo, qtd) in [
+            (agendadas::Tipo::Venda, "R001", 2),
+            (agendadas::Tipo::Compra, "R002", 5),
+        ] {
+            agendadas::agenda(
+                &mut app.registros.operacoes_agendadas,
+                tipo,
+                codigo,
+                qtd,
+                None,
+                hoje,
+                agora,
+            );
+        }
+        assert_eq!(app.relogios["R001"].quantidade(), 12);
+        confere_golden("estoques_agendadas", &mut app);
+    }
+
+    #[test]
+    fn tela_historico_etiquetas() {
+        let mut app = app_de_teste();
+        tecla(&mut app, KeyCode::Char('h'));
+        tecla(&mut app, KeyCode::Char(' '));
+        tecla(&mut app, KeyCode::Down);
+        tecla(&mut app, KeyCode::Char(' '));
+        // Aplica direto: a tecla T gravaria o estoque.json do diretório
+        assert_eq!(app.etiquetar_historico("Black Friday"), 2);
+        assert!(app.historico_marcados.is_empty());
+        tecla(&mut app, KeyCode::Right);
+        tecla(&mut app, KeyCode::Right);
+        tecla(&mut app, KeyCode::Char('*'));
+        assert_eq!(app.historico_marcados.len(), 2);
+        tecla(&mut app, KeyCode::Left);
+        tecla(&mut app, KeyCode::Left);
+        tecla(&mut app, KeyCode::Char('f'));
+        digita(&mut app, "black friday");
+        tecla(&mut app, KeyCode::Enter);
+        assert_eq!(app.get_historico_atual_vec().len(), 2);
+        confere_golden("historico_etiquetas", &mut app);
+    }
+
+    #[test]
+    fn tela_estoques_com_medidores() {
+        let mut app = app_de_teste();
+        app.config.painel_medidores = true;
+        app.registros.favoritos = vec!["R001".into(), "R002".into(), "R003".into()];
+        for (codigo, alvo) in [("R001", 20), ("R003", 3)] {
+            app.relogios.get_mut(codigo).unwrap().estoque_alvo = Some(alvo);
+        }
+        confere_golden("estoques_medidores", &mut app);
+    }
+
+    #[test]
+    fn ajuste_rapido_e_contagem_lancam_ajuste() {
+        let mut app = app_de_teste();
+        // Direto na função: as teclas gravariam o estoque.json do diretório
+        assert_eq!(app.ajusta_estoque("R003", 4, MOTIVO_AJUSTE_RAPIDO), Ok(1));
+        assert_eq!(app.ajusta_estoque("R001", 9, "quebra na vitrine"), Ok(-3));
+        assert!(app
+            .ajusta_estoque("R002", -1, MOTIVO_AJUSTE_RAPIDO)
+            .is_err());
+        assert!(app.ajusta_estoque("R003", 4, MOTIVO_CONTAGEM).is_err());
+        assert_eq!(app.relogios["R001"].quantidade(), 9);
+        let ajuste = app.historico.last().unwrap();
+        assert_eq!(ajuste.operacao, "AJUSTE");
+        assert_eq!(ajuste.quantidade_marcada(app.config.formato_numeros), "-3");
+        let saldos = arquivo::saldos(&app.historico, &app.relogios);
+        let r001 = saldos.iter().find(|r| r.codigo == "R001").unwrap();
+        assert_eq!(r001.quantidade(), 9);
+
+        tecla(&mut app, KeyCode::Char('='));
+        assert_eq!(app.modo, Modo::Contagem);
+        assert_eq!(app.contagem_codigo.as_deref(), Some("R001"));
+        digita(&mut app, "7");
+        confere_golden("contagem", &mut app);
+    }
+
+    #[test]
+    fn venda_guarda_o_preco_da_epoca() {
+        let mut app = app_de_teste();
+        let numeros = app.config.formato_numeros;
+        let (resto, precos) = separa_precos("R001 custo=130 preco=1.299,90", numeros);
+        assert_eq!(
+            (resto.as_str(), precos),
+            ("R001", Ok((Some(130.0), Some(1299.9))))
+        );
+        assert!(separa_precos("R001 preco=abc", numeros).1.is_err());
+
+        Inventario::new(&mut app.relogios, &mut app.historico)
+            .vende(
+                &ProdutoId::novo("R001").unwrap(),
+                Quantidade::movimento(1).unwrap(),
+                Local::now().naive_local(),
+            )
+            .unwrap();
+        assert!(app.define_precos("R001", (None, Some(300.0))));
+        assert!(!app.define_precos("R404", (None, Some(1.0))));
+        let r001 = app.relogios.get("R001");
+        let venda = app.historico.last().unwrap();
+        assert_eq!((venda.custo, venda.preco), (Some(120.0), Some(249.9)));
+        assert_eq!(contabil::valor(venda, r001), Some(249.9));
+        // Entradas de antes do preço gravado usam o atual
+        assert_eq!(contabil::valor(&app.historico[4], r001), Some(600.0));
+    }
+
+    #[test]
+    fn colunas_configuradas_no_estoque_e_historico() {
+        let mut app = app_de_teste();
+        app.relogios.get_mut("R001").unwrap().categoria = Some("digital".into());
+        app.atualiza_estoques_list();
+        assert!(app
+            .config
+            .define("colunas_estoque", "codigo qtd dist")
+            .is_err());
+        app.config
+            .define("colunas_estoque", "codigo:30 preco:25 categoria qtd:15")
+            .unwrap();
+        confere_golden("estoques_colunas", &mut app);
+        app.config
+            .define("colunas_historico", "data:30 codigo:15 operacao:20 qtd")
+            .unwrap();
+        tecla(&mut app, KeyCode::Char('h'));
+        confere_golden("historico_colunas", &mut app);
+    }
+
+    #[test]
+    fn painel_dividido_mostra_historico_do_item() {
+        let mut app = app_de_teste();
+        tecla(&mut app, KeyCode::Char('|'));
+        assert!(app.painel_dividido);
+        confere_golden("estoques_dividido", &mut app);
+        tecla(&mut app, KeyCode::Tab);
+        tecla(&mut app, KeyCode::Down);
+        assert_eq!(
+            (
+                app.estoques_scroll.selected,
+                app.painel_historico_scroll.selected
+            ),
+            (0, 1)
+        );
+        tecla(&mut app, KeyCode::Tab);
+        tecla(&mut app, KeyCode::Down);
+        assert_eq!(
+            (
+                app.estoques_scroll.selected,
+                app.painel_historico_scroll.selected
+            ),
+            (1, 0)
+        );
+        assert_eq!(app.historico_do_selecionado()[0].operacao, "VENDA");
+    }
+
+    #[test]
+    fn tela_historico_e_detalhe() {
+        let mut app = app_de_teste();
+        tecla(&mut app, KeyCode::Char('h'));
+        tecla(&mut app, KeyCode::Right);
+        confere_golden("historico", &mut app);
+        tecla(&mut app, KeyCode::Enter);
+        confere_golden("historico_detalhe", &mut app);
+    }
+
+    #[test]
+    fn tela_grafico() {
+        let mut app = app_de_teste();
+        tecla(&mut app, KeyCode::Char('g'));
+        confere_golden("grafico", &mut app);
+    }
+
+    #[test]
+    fn tela_configuracoes() {
+        let mut app = app_de_teste();
+        tecla(&mut app, KeyCode::Char('s'));
+        tecla(&mut app, KeyCode::Down);
+        confere_golden("configuracoes", &mut app);
+    }
+
+    #[test]
+    fn senha_admin_oculta_e_pedida_antes_da_troca() {
+        let mut app = app_de_teste();
+        assert!(!app.senha_confere(Some("qualquer")));
+        app.config.senha_admin = Some("1234".into());
+        assert!(app.senha_confere(Some("1234")));
+        assert_eq!(oculta_senha("R001 1 !1234 pix"), "R001 1 !**** pix");
+
+        tecla(&mut app, KeyCode::Char('s'));
+        app.config_selected = config::CHAVES
+            .iter()
+            .position(|c| *c == "senha_admin")
+            .unwrap();
+        assert!(tela(&mut app, LARGURA, 60).contains("senha_admin                 ****"));
+        tecla(&mut app, KeyCode::Enter);
+        digita(&mut app, "0000");
+        // A lista de chaves passa da altura padrão
+        assert!(tela(&mut app, LARGURA, 60).contains("senha atual: ****_"));
+        tecla(&mut app, KeyCode::Enter);
+        assert_eq!(
+            app.mensagens.last().unwrap(),
+            "Senha de administrador incorreta!"
+        );
+        assert!(!app.editing);
+        assert_eq!(app.config.senha_admin.as_deref(), Some("1234"));
+
+        tecla(&mut app, KeyCode::Enter);
+        digita(&mut app, "1234");
+        tecla(&mut app, KeyCode::Enter);
+        assert!(app.editing && !app.confere_senha_atual);
+        assert!(app.input.is_empty());
+        tecla(&mut app, KeyCode::Enter);
+        assert_eq!(app.config.senha_admin.as_deref(), Some("1234"));
+
+        app.config.sheets_refresh_token = Some("1//segredo".into());
+        assert!(!tela(&mut app, LARGURA, 60).contains("segredo"));
+    }
+
+    #[test]
+    fn tela_venda() {
+        let mut app = app_de_teste();
+        tecla(&mut app, KeyCode::Enter);
+        tecla(&mut app, KeyCode::Char('v'));
+        digita(&mut app, " 1 pix");
+        confere_golden("venda", &mut app);
+    }
+
+    #[test]
+    fn exclui_e_renomeia_pelo_estoque() {
+        let mut app = app_de_teste();
+        tecla(&mut app, KeyCode::Down);
+        tecla(&mut app, KeyCode::Char('d'));
+        assert_eq!(app.exclusao.as_deref(), Some("R002"));
+        confere_golden("exclusao", &mut app);
+        tecla(&mut app, KeyCode::Esc);
+        assert!(app.exclusao.is_none());
+        assert!(app.relogios.contains_key("R002"));
+        assert_eq!(app.excluir("R002"), 2);
+        assert!(!app.relogios.contains_key("R002"));
+        assert!(app.historico.iter().all(|h| h.codigo != "R002"));
+
+        tecla(&mut app, KeyCode::Up);
+        tecla(&mut app, KeyCode::F(2));
+        assert_eq!(app.modo, Modo::Renomear);
+        assert_eq!(app.input, "R001");
+        assert!(app.renomear("R001", "R003").is_err());
+        app.registros.favoritos.push("R001".into());
+        app.recentes = vec!["R002".into(), "R001".into()];
+        app.registros
+            .pedidos_compra
+            .push(crate::pedidos::PedidoCompra {
+                numero: 1,
+                fornecedor: "Casio".into(),
+                data: "2025-01-10".into(),
+                itens: vec![crate::pedidos::ItemPedido {
+                    codigo: "R001".into(),
+                    quantidade: 2,
+                    recebido: 0,
+                    cancelado: 0,
+                }],
+                entregas: vec![],
+                fechado: None,
+            });
+        assert_eq!(app.renomear("R001", "R010"), Ok(3));
+        assert_eq!(app.relogios["R010"].quantidade(), 12);
+        assert_eq!(app.registros.favoritos, ["R010"]);
+        assert_eq!(app.recentes, ["R002", "R010"]);
+        assert_eq!(app.registros.pedidos_compra[0].itens[0].codigo, "R010");
+        assert!(app.historico_codigos_unicos.contains(&"R010".to_string()));
+        assert_eq!(app.estoques_list[0].codigo, "R003");
+    }
+
+    #[test]
+    fn log_de_mensagens_respeita_o_limite() {
+        let mut app = app_de_teste();
+        app.mensagens = (0..10).map(|i| i.to_string()).collect();
+        app.limita_mensagens();
+        assert_eq!(app.mensagens.len(), 10);
+        app.config.limite_mensagens = 3;
+        app.limita_mensagens();
+        assert_eq!(app.mensagens, ["7", "8", "9"]);
+    }
+
+    #[test]
+    fn margem_compara_com_o_custo_medio_ou_o_da_ultima_compra() {
+        let mut app = app_de_teste();
+        app.config.venda_abaixo_custo = config::RegraMargem::Bloquear;
+        for (quantidade, custo, timestamp) in [
+            (3, 230.0, "2024-02-01 10:00:00"),
+            (1, 260.0, "2024-03-01 10:00:00"),
+        ] {
+            app.historico.push(Historico {
+                codigo: "R001".into(),
+                quantidade,
+                operacao: "COMPRA".into(),
+                timestamp: timestamp.into(),
+                custo: Some(custo),
+                ..Default::default()
+            });
+        }
+        // Média (3 × 230 + 260) / 4 = 237,50, abaixo do preço de 249,90.
+        assert_eq!(app.preco_e_custo("R001"), Some((249.9, 237.5)));
+        assert!(app.confere_venda("R001", 1, false));
+        app.config.custo_referencia = config::CustoReferencia::Ultimo;
+        assert_eq!(app.preco_e_custo("R001"), Some((249.9, 260.0)));
+        assert!(!app.confere_venda("R001", 1, false));
+        assert!(app.mensagens.last().unwrap().starts_with("Venda bloqueada"));
+        // Sem compra com custo registrado vale o do cadastro.
+        assert_eq!(app.preco_e_custo("R002"), Some((1499.0, 850.0)));
+    }
+
+    #[test]
+    fn codigo_inexistente_sugere_os_parecidos() {
+        let mut app = app_de_teste();
+        tecla(&mut app, KeyCode::Enter);
+        tecla(&mut app, KeyCode::Char('v'));
+        app.input.clear();
+        digita(&mut app, "R01 1 pix");
+        tecla(&mut app, KeyCode::Enter);
+        assert_eq!(app.modo, Modo::Venda);
+        assert!(app
+            .mensagens
+            .last()
+            .unwrap()
+            .contains("Você quis dizer R001 ou R002 ou R003?"));
+        confere_golden("venda_sugestao", &mut app);
+        tecla(&mut app, KeyCode::Tab);
+        assert_eq!(app.input, "R001 1 pix");
+        tecla(&mut app, KeyCode::Tab);
+        assert_eq!(app.input, "R002 1 pix");
+        tecla(&mut app, KeyCode::Esc);
+        assert!(app.sugestao.is_none());
+
+        // Na Compra, o segundo Enter sem mudar nada cadastra o código novo
+        app.modo = Modo::Compra;
+        app.input = "R0001 2".into();
+        assert!(app.oferece_sugestao());
+        assert!(!app.oferece_sugestao());
+    }
+
+    #[test]
+    fn tela_compra() {
+        let mut app = app_de_teste();
+        tecla(&mut app, KeyCode::Enter);
+        tecla(&mut app, KeyCode::Char('a'));
+        confere_golden("compra", &mut app);
+    }
+
+    #[test]
+    fn tela_grafico_marcas() {
+        let mut app = app_de_teste();
+        for (codigo, marca) in [("R001", "Casio"), ("R002", "Seiko")] {
+            app.relogios.get_mut(codigo).unwrap().marca = Some(marca.into());
+        }
+        tecla(&mut app, KeyCode::Char('g'));
+        for _ in 0..3 {
+            tecla(&mut app, KeyCode::Left);
+        }
+        for _ in 0..PERIODOS_GRAFICO.len() {
+            tecla(&mut app, KeyCode::Down);
+        }
+        confere_golden("grafico_marcas", &mut app);
+
+        // A exportação leva os mesmos números da tela
+        let paineis = app.paineis_do_grafico();
+        assert_eq!(paineis.len(), 1);
+        assert_eq!(paineis[0].titulo, "Vendas por marca, todo o histórico");
+        assert_eq!(paineis[0].pontos, app.vendas_por_grupo_grafico());
+        assert!(paineis[0].pontos.iter().any(|(m, _)| m == "Casio"));
+    }
+
+    #[test]
+    fn grafico_compara_cada_mes_com_o_ano_anterior() {
+        let mut app = app_de_teste();
+        let hoje = Local::now().date_naive();
+        let ano_antes = hoje
+            .with_day(1)
+            .unwrap()
+            .with_year(hoje.year() - 1)
+            .unwrap();
+        for (data, quantidade) in [(hoje, 2), (ano_antes, 1)] {
+            app.historico.push(Historico {
+                codigo: "R001".into(),
+                quantidade,
+                operacao: "VENDA".into(),
+                timestamp: format!("{} 10:00:00", data),
+                ..Default::default()
+            });
+        }
+        tecla(&mut app, KeyCode::Char('g'));
+        tecla(&mut app, KeyCode::Right);
+        assert!(app.grafico_tab == GraficoTab::MesAMes);
+        let paineis = app.paineis_do_grafico();
+        assert_eq!(paineis.len(), 2);
+        assert_eq!(paineis[0].pontos.len(), 12);
+        assert_eq!(paineis[0].pontos[11].1, 499.8);
+        assert_eq!(paineis[1].pontos[11].1, 249.9);
+        assert_eq!(
+            paineis[1].pontos[11].0,
+            ano_antes.format("%m/%y").to_string()
+        );
+        assert!(paineis[0].pontos[..11].iter().all(|(_, v)| *v == 0.0));
+        assert!(tela(&mut app, LARGURA, ALTURA).contains("últimos 12 meses × ano anterior"));
+    }
+
+    #[test]
+    fn grafico_acumulado_do_mes_contra_o_anterior() {
+        let mut app = app_de_teste();
+        let hoje = Local::now().date_naive();
+        let mes_passado = hoje
+            .with_day(1)
+            .unwrap()
+            .checked_sub_months(chrono::Months::new(1))
+            .unwrap();
+        for (data, quantidade) in [(hoje, 2), (mes_passado, 1), (mes_passado, 1)] {
+            app.historico.push(Historico {
+                codigo: "R001".into(),
+                quantidade,
+                operacao: "VENDA".into(),
+                timestamp: format!("{} 10:00:00", data),
+                ..Default::default()
+            });
+        }
+        tecla(&mut app, KeyCode::Char('g'));
+        tecla(&mut app, KeyCode::Right);
+        tecla(&mut app, KeyCode::Down);
+        assert!(app.grafico_acumulado);
+        let paineis = app.paineis_do_grafico();
+        assert_eq!(paineis.len(), 4);
+        assert_eq!(paineis[0].pontos.len(), hoje.day() as usize);
+        assert_eq!(paineis[0].pontos.last().unwrap().1, 499.8);
+        assert!(paineis[1].pontos.iter().all(|(_, v)| *v == 499.8));
+        assert_eq!(paineis[3].pontos[0], ("1".to_string(), 2.0));
+        let tela = tela(&mut app, LARGURA, ALTURA);
+        assert!(tela.contains("Receita no mês: R$ 499,80 × R$ 499,80"));
+        assert!(tela.contains("(+0,0%)"));
+    }
+
+    #[test]
+    fn tela_grafico_dia_hora() {
+        let mut app = app_de_teste();
+        tecla(&mut app, KeyCode::Char('g'));
+        tecla(&mut app, KeyCode::Left);
+        for _ in 0..PERIODOS_GRAFICO.len() {
+            tecla(&mut app, KeyCode::Down);
+        }
+        confere_golden("grafico_dia_hora", &mut app);
+    }
+
+    #[test]
+    fn demonstracao_alterna_telas_com_o_balcao_parado() {
+        let mut app = app_de_teste();
+        app.config.demonstracao_seg = 30;
+        app.verifica_demonstracao();
+        assert!(app.demonstracao.is_none());
+        app.ultima_tecla = Instant::now().checked_sub(Duration::from_secs(31)).unwrap();
+        app.verifica_demonstracao();
+        assert_eq!(app.modo, Modo::Grafico);
+        assert!(app.grafico_tab == GraficoTab::Diario);
+        if let Some(d) = app.demonstracao.as_mut() {
+            d.troca = app.ultima_tecla;
+        }
+        app.verifica_demonstracao();
+        assert!(app.grafico_tab == GraficoTab::Produtos);
+        confere_golden("demonstracao", &mut app);
+        // A tecla só encerra: não vende, não troca de tela
+        tecla(&mut app, KeyCode::Char('v'));
+        assert_eq!(app.modo, Modo::Estoques);
+        assert!(app.demonstracao.is_none());
+    }
+
+    #[test]
+    fn tela_mapeamento_csv() {
+        let mut app = app_de_teste();
+        tecla(&mut app, KeyCode::Char('i'));
+        let bytes =
+            b"Descri\xe7\xe3o;Pre\xe7o;C\xf3digo\nRel\xf3gio a\xe7o;1.234,50;R010\n".to_vec();
+        app.importacao_csv = Some(catalogo::ArquivoCsv {
+            caminho: "fornecedor.csv".into(),
+            mapeamento: catalogo::Mapeamento::detecta(&bytes, app.config.formato_numeros),
+            bytes,
+            limite: 10.0,
+        });
+        app.modo = Modo::MapearCsv;
+        tecla(&mut app, KeyCode::Down);
+        confere_golden("mapeamento_csv", &mut app);
+        tecla(&mut app, KeyCode::Enter);
+        assert_eq!(app.modo, Modo::ImportarCatalogo);
+        assert_eq!(app.importacao[0].descricao, "Relógio aço");
+        tecla(&mut app, KeyCode::Char('d'));
+        assert!(!app.relogios.contains_key("R010"));
+        assert!(app
+            .mensagens
+            .last()
+            .unwrap()
+            .starts_with("Simulação: 1 novos, 0 atualizados"));
+    }
+
+    #[test]
+    fn tela_fornecedores() {
+        let mut app = app_de_teste();
+        tecla(&mut app, KeyCode::Char('u'));
+        tecla(&mut app, KeyCode::Char('n'));
+        // Inclui direto: o Enter gravaria o estoque.json do diretório
+        digita(&mut app, "Seiko Brasil SRPD55 R002");
+        assert!(app.adicionar_referencia().is_ok());
+        app.input = "Casio A168 R404".into();
+        assert!(app.adicionar_referencia().is_err());
+        app.input = "Casio A168 R001".into();
+        assert!(app.adicionar_referencia().is_ok());
+        app.input.clear();
+        app.editing = false;
+        tecla(&mut app, KeyCode::Down);
+        confere_golden("fornecedores", &mut app);
+
+        // O catálogo da Seiko chega com o código deles
+        let bytes = b"SRPD55;Seiko 5 Sports;900,00\n".to_vec();
+        app.importacao_csv = Some(catalogo::ArquivoCsv {
+            caminho: "tabela_seiko_brasil.csv".into(),
+            mapeamento: catalogo::Mapeamento::detecta(&bytes, app.config.formato_numeros),
+            bytes,
+            limite: 10.0,
+        });
+        app.confirmar_mapeamento();
+        assert_eq!(app.importacao[0].codigo, "R002");
+        assert!(!app.importacao[0].novo);
+    }
+
+    #[test]
+    fn tela_console() {
+        let mut app = app_de_teste();
+        tecla(&mut app, KeyCode::Char(':'));
+        digita(&mut app, "sum qty where op=VENDA by code");
+        tecla(&mut app, KeyCode::Enter);
+        confere_golden("console", &mut app);
+        assert_eq!(app.modo, Modo::Console);
+        tecla(&mut app, KeyCode::Esc);
+        assert_eq!(app.modo, Modo::Estoques);
+    }
+
+    #[test]
+    fn tela_notificacoes() {
+        let mut app = app_de_teste();
+        app.registros.lembretes.push(agenda::Lembrete {
+            data: "2024-01-20".into(),
+            titulo: "Revisar vitrine".into(),
+        });
+        app.registros.lembretes.push(agenda::Lembrete {
+            data: "2023-12-01".into(),
+            titulo: "Antigo demais".into(),
+        });
+        let hoje = NaiveDate::from_ymd_opt(2024, 1, 22).unwrap();
+        app.notificacoes_de_abertura(hoje);
+        app.notificacoes_de_abertura(hoje);
+        assert_eq!(app.registros.notificacoes.len(), 1);
+
+        // Venda levou R001 de 12 para 4 (alvo 5) e R003 de 3 para 0
+        app.relogios.get_mut("R001").unwrap().estoque_alvo = Some(5);
+        app.relogios.get_mut("R001").unwrap().define_quantidade(4);
+        app.relogios.get_mut("R003").unwrap().define_quantidade(0);
+        app.avisa_estoque_baixo(vec![("R001".into(), 12), ("R003".into(), 3)]);
+        assert_eq!(notificacoes::nao_lidas(&app.registros.notificacoes), 3);
+        for n in &mut app.registros.notificacoes {
+            n.timestamp = "2024-01-22 10:00:00".into();
+        }
+        tecla(&mut app, KeyCode::Char('n'));
+        assert_eq!(app.modo, Modo::Notificacoes);
+        confere_golden("notificacoes", &mut app);
+    }
+
+    #[test]
+    fn tela_recebimento() {
+        let mut app = app_de_teste();
+        app.registros
+            .referencias_fornecedor
+            .push(fornecedores::Referencia {
+                fornecedor: "Seiko".into(),
+                codigo_fornecedor: "SRPD55".into(),
+                codigo: "R002".into(),
+            });
+        tecla(&mut app, KeyCode::Char('j'));
+        assert_eq!(app.modo, Modo::Recebimento);
+        digita(&mut app, "Seiko");
+        tecla(&mut app, KeyCode::Enter);
+        for leitura in ["SRPD55 4", "R003", "R003", "R404", "R001 2", "R001 -2"] {
+            digita(&mut app, leitura);
+            tecla(&mut app, KeyCode::Enter);
+        }
+        let itens = &app.recebimento.as_ref().unwrap().itens;
+        assert_eq!(itens, &[("R002".to_string(), 4), ("R003".to_string(), 2)]);
+        tecla(&mut app, KeyCode::Enter);
+        tecla(&mut app, KeyCode::Down);
+        confere_golden("recebimento", &mut app);
+
+        // Nada entra no estoque antes da confirmação
+        tecla(&mut app, KeyCode::Esc);
+        tecla(&mut app, KeyCode::Esc);
+        assert_eq!(app.modo, Modo::Estoques);
+        assert_eq!(app.relogios["R002"].quantidade(), 0);
+    }
+
+    #[test]
+    fn tela_pedidos_pendentes() {
+        let mut app = app_de_teste();
+        tecla(&mut app, KeyCode::Char('y'));
+        assert_eq!(app.modo, Modo::Pedidos);
+        tecla(&mut app, KeyCode::Char('n'));
+        // Inclui direto: o Enter gravaria o estoque.json do diretório
+        digita(&mut app, "Seiko R002:5 R003:2");
+        assert!(app.abrir_pedido_compra().is_ok());
+        app.input = "Casio R404:1".into();
+        assert!(app.abrir_pedido_compra().is_err());
+        app.input = "Orient R003:4".into();
+        assert!(app.abrir_pedido_compra().is_ok());
+        app.input.clear();
+        app.editing = false;
+        let ha = |dias| {
+            (Local::now().date_naive() - chrono::Duration::days(dias))
+                .format("%Y-%m-%d")
+                .to_string()
+        };
+        app.registros.pedidos_compra[0].data = ha(12);
+        app.registros.pedidos_compra[1].data = ha(3);
+
+        // A entrega da Seiko veio incompleta
+        app.abate_pedidos(Some("Seiko"), &[("R002".into(), 2), ("R003".into(), 2)]);
+        confere_golden("pedidos", &mut app);
+        app.abate_pedidos(Some("Seiko"), &[("R002".into(), 3)]);
+        assert!(app.registros.pedidos_compra[0].fechado.is_some());
+        assert!(app.registros.pedidos_compra[1].fechado.is_none());
+    }
+
+    #[test]
+    fn pedido_recorrente_gera_rascunho() {
+        let mut app = app_de_teste();
+        tecla(&mut app, KeyCode::Char('y'));
+        // Direto: o Enter e a abertura gravariam o estoque.json do diretório
+        app.input = "mensal Pulseiras SP R001:20".into();
+        assert!(app.abrir_pedido_compra().is_ok());
+        assert!(app.registros.pedidos_compra.is_empty());
+        let proximo = Local::now().date_naive() + chrono::Duration::days(31);
+        pedidos::gera_rascunhos(
+            &mut app.registros.pedidos_recorrentes,
+            &mut app.registros.rascunhos_pedido,
+            proximo,
+        );
+        app.registros.rascunhos_pedido[0].ciclo = "2024-05-10".into();
+        app.input.clear();
+        confere_golden("pedidos_recorrentes", &mut app);
+    }
+
+    #[test]
+    fn tela_orcamentos() {
+        let mut app = app_de_teste();
+        tecla(&mut app, KeyCode::Char('z'));
+        assert_eq!(app.modo, Modo::Orcamentos);
+        tecla(&mut app, KeyCode::Char('n'));
+        // Confirma direto: o Enter gravaria o estoque.json do diretório
+        for texto in [
+            "Ana Souza;(11) 98888-0000;R001 R003:2;0",
+            "Bruno;bruno@mail.com;R002;0",
+            "Carla;;R001;2",
+        ] {
+            app.input = texto.into();
+            assert!(app.confirmar_orcamento().is_ok());
+        }
+        app.input = "Davi;;R404".into();
+        assert!(app.confirmar_orcamento().is_err());
+        app.registros.orcamentos[1].retorno = (Local::now().date_naive()
+            - chrono::Duration::days(2))
+        .format("%Y-%m-%d")
+        .to_string();
+
+        // Carla desistiu
+        app.editing = false;
+        tecla(&mut app, KeyCode::Down);
+        tecla(&mut app, KeyCode::Down);
+        tecla(&mut app, KeyCode::Char('p'));
+        assert!(app.editing);
+        app.input = "Achou mais barato".into();
+        assert!(app.confirmar_orcamento().is_ok());
+        app.input.clear();
+        app.editing = false;
+        tecla(&mut app, KeyCode::Up);
+        tecla(&mut app, KeyCode::Up);
+        confere_golden("orcamentos", &mut app);
+        assert_eq!(
+            orcamentos::motivos_de_perda(&app.registros.orcamentos),
+            [("achou mais barato".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn balanco_por_arquivo_do_celular() {
+        let mut app = app_de_teste();
+        let pasta = std::env::temp_dir().join(format!("relogio_balanco_{}", std::process::id()));
+        fs::create_dir_all(&pasta).unwrap();
+        let arquivo = pasta.join("contagem.csv");
+        fs::write(&arquivo, "code,counted_qty\nR001,10\nR002,1\nR999,4\n").unwrap();
+
+        tecla(&mut app, KeyCode::Char('='));
+        assert_eq!(app.modo, Modo::Contagem);
+        digita(&mut app, arquivo.to_str().unwrap());
+        tecla(&mut app, KeyCode::Enter);
+        assert_eq!(app.modo, Modo::Balanco);
+        confere_golden("balanco", &mut app);
+        // Completo: R003 não foi contado e vai a zero
+        tecla(&mut app, KeyCode::Char('c'));
+        let b = app.balanco.as_ref().unwrap();
+        let conciliacao = balanco::concilia(&b.contagem, &app.relogios, b.completo);
+        assert_eq!(conciliacao.divergentes().count(), 3);
+
+        // Esc não lança nada
+        tecla(&mut app, KeyCode::Esc);
+        assert_eq!(app.modo, Modo::Estoques);
+        assert_eq!(app.relogios["R001"].quantidade(), 12);
+        let _ = fs::remove_dir_all(&pasta);
+    }
+
+    #[test]
+    fn x_sai_e_demais_teclas_continuam() {
+        let mut app = app_de_teste();
+        assert!(tecla(&mut app, KeyCode::Char('g')));
+        assert!(!tecla(&mut app, KeyCode::Char('x')));
+    }
+
+    #[test]
+    fn terminal_pequeno_mostra_aviso() {
+        let mut app = app_de_teste();
+        let texto = tela(&mut app, 40, 12);
+        assert!(texto.contains("Terminal pequeno demais (40x12)"));
+        tecla(&mut app, KeyCode::Down);
+        app.ajusta_rolagem();
+        assert!(tela(&mut app, 60, 24).contains("Estoque"));
+    }
+
+    /// Todas as telas em terminais pequenos e estreitos, sem pânico.
+    #[test]
+    fn telas_nao_quebram_em_terminais_pequenos() {
+        let roteiros: &[&[KeyCode]] = &[
+            &[],
+            &[KeyCode::Char('c')],
+            &[KeyCode::Char('b'), KeyCode::Char('R')],
+            &[KeyCode::Char('h')],
+            &[KeyCode::Char('h'), KeyCode::Enter],
+            &[KeyCode::Char('g')],
+            &[KeyCode::Char('s')],
+            &[KeyCode::Char('k')],
+            &[KeyCode::Char('g'), KeyCode::Right],
+            &[KeyCode::Char('i')],
+            &[KeyCode::Enter, KeyCode::Char('v')],
+        ];
+        for roteiro in roteiros {
+            let mut app = app_de_teste();
+            for code in *roteiro {
+                tecla(&mut app, *code);
+            }
+            for _ in 0..10 {
+                tecla(&mut app, KeyCode::Down);
+            }
+            for (largura, altura) in [(20, 10), (60, 24), (61, 25), (80, 24), (200, 60)] {
+                tela(&mut app, largura, altura);
+            }
+        }
+    }
+}
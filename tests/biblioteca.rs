@@ -0,0 +1,32 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use relogio::dominio::{Inventario, ProdutoId, Quantidade};
+use relogio::{App, Historico, Relogio};
+use std::collections::HashMap;
+
+#[test]
+fn estoque_e_telas_sem_terminal() {
+    let mut relogios = HashMap::new();
+    relogios.insert("R001".to_string(), {
+        let mut r = Relogio::novo("R001", Quantidade::nova(5).unwrap());
+        r.preco = Some(100.0);
+        r
+    });
+    let mut historico: Vec<Historico> = vec![];
+    Inventario::new(&mut relogios, &mut historico)
+        .vende(
+            &ProdutoId::novo("R001").unwrap(),
+            Quantidade::movimento(2).unwrap(),
+            chrono::Local::now().naive_local(),
+        )
+        .unwrap();
+    assert_eq!(relogios["R001"].quantidade(), 3);
+    assert_eq!(historico[0].preco, Some(100.0));
+
+    let mut app = App::com_estoque(relogios, historico);
+    assert!(app.handle_key(KeyEvent::new(KeyCode::Char('h'), KeyModifiers::NONE)));
+    let buffer = app.render_to_buffer(100, 32);
+    let tela: String = buffer.content().iter().map(|c| c.symbol()).collect();
+    assert!(tela.contains("VENDA"));
+    assert_eq!(app.historico().len(), 1);
+    assert_eq!(app.relogios()["R001"].quantidade(), 3);
+}
@@ -0,0 +1,120 @@
+//! Loja de teste para os testes de integração: um `App` aberto sobre uma
+//! pasta temporária, como o programa abre a pasta de dados, e atalhos para
+//! digitar roteiros de teclas e conferir o que foi gravado.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use relogio::persistencia::{self, Registros};
+use relogio::{App, Historico, Relogio};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::{env, fs};
+
+/// Os dados ficam na pasta atual do processo, que é uma só: as lojas de um
+/// mesmo arquivo de testes abrem uma de cada vez.
+static PASTA_ATUAL: Mutex<()> = Mutex::new(());
+static SEQUENCIA: AtomicUsize = AtomicUsize::new(0);
+
+pub const LARGURA: u16 = 100;
+pub const ALTURA: u16 = 32;
+
+pub struct Loja {
+    pub app: App,
+    pub pasta: PathBuf,
+    anterior: PathBuf,
+    _vez: MutexGuard<'static, ()>,
+}
+
+impl Loja {
+    /// Loja vazia, com a configuração padrão.
+    pub fn nova() -> Loja {
+        Loja::com_arquivos(&[])
+    }
+
+    /// Loja cujos arquivos (`config.toml`, `estoque.json`...) já existem
+    /// antes da abertura.
+    pub fn com_arquivos(arquivos: &[(&str, &str)]) -> Loja {
+        // Um teste que falhou com a pasta trocada não impede os demais
+        let vez = PASTA_ATUAL.lock().unwrap_or_else(|e| e.into_inner());
+        let pasta = env::temp_dir().join(format!(
+            "relogio_loja_{}_{}",
+            std::process::id(),
+            SEQUENCIA.fetch_add(1, Ordering::SeqCst)
+        ));
+        let _ = fs::remove_dir_all(&pasta);
+        fs::create_dir_all(&pasta).unwrap();
+        for (nome, conteudo) in arquivos {
+            fs::write(pasta.join(nome), conteudo).unwrap();
+        }
+        let anterior = env::current_dir().unwrap();
+        env::set_current_dir(&pasta).unwrap();
+        Loja {
+            app: App::carrega(),
+            pasta,
+            anterior,
+            _vez: vez,
+        }
+    }
+
+    /// Fecha e abre de novo o programa sobre a mesma pasta.
+    pub fn reabre(&mut self) -> &mut Self {
+        self.app = App::carrega();
+        self
+    }
+
+    pub fn tecla(&mut self, code: KeyCode) -> &mut Self {
+        assert!(
+            self.app.handle_key(KeyEvent::new(code, KeyModifiers::NONE)),
+            "{:?} fechou o programa",
+            code
+        );
+        self
+    }
+
+    /// Cada caractere como uma tecla.
+    pub fn digita(&mut self, texto: &str) -> &mut Self {
+        for c in texto.chars() {
+            self.tecla(KeyCode::Char(c));
+        }
+        self
+    }
+
+    /// Digita e confirma com Enter.
+    pub fn envia(&mut self, texto: &str) -> &mut Self {
+        self.digita(texto).tecla(KeyCode::Enter)
+    }
+
+    /// A tela inteira como texto, uma linha por linha do terminal.
+    pub fn tela(&mut self) -> String {
+        let buffer = self.app.render_to_buffer(LARGURA, ALTURA);
+        (0..ALTURA)
+            .map(|y| {
+                let linha: String = (0..LARGURA).map(|x| buffer[(x, y)].symbol()).collect();
+                linha.trim_end().to_string() + "\n"
+            })
+            .collect()
+    }
+
+    pub fn ultima_mensagem(&self) -> &str {
+        self.app.mensagens().last().map_or("", |m| m.as_str())
+    }
+
+    /// O que está no `estoque.json` da loja.
+    pub fn gravado(&self) -> (HashMap<String, Relogio>, Vec<Historico>, Registros) {
+        persistencia::load_from_file()
+    }
+
+    /// Conteúdo de um arquivo da pasta da loja.
+    pub fn arquivo(&self, nome: impl AsRef<Path>) -> String {
+        let caminho = self.pasta.join(nome);
+        fs::read_to_string(&caminho).unwrap_or_else(|e| panic!("{}: {}", caminho.display(), e))
+    }
+}
+
+impl Drop for Loja {
+    fn drop(&mut self) {
+        let _ = env::set_current_dir(&self.anterior);
+        let _ = fs::remove_dir_all(&self.pasta);
+    }
+}
@@ -0,0 +1,206 @@
+mod comum;
+
+use comum::Loja;
+use crossterm::event::KeyCode;
+
+#[test]
+fn cadastra_vende_filtra_e_exporta() {
+    let mut loja = Loja::nova();
+    loja.digita("c").envia("R010 5 custo=120 preco=249,90");
+    loja.tecla(KeyCode::Esc);
+    loja.digita("c").envia("R020 2");
+    loja.tecla(KeyCode::Esc);
+    // Enter seleciona a primeira linha e V já traz o código
+    loja.tecla(KeyCode::Enter).digita("v").envia(" 2 pix");
+    assert_eq!(loja.app.relogios()["R010"].quantidade(), 3);
+
+    loja.digita("hp").envia("R010");
+    let tela = loja.tela();
+    assert!(tela.contains("VENDA"));
+    assert!(!tela.contains("R020"));
+    loja.digita("e");
+    assert!(loja.ultima_mensagem().starts_with("Exportados estoque.csv"));
+    let historico = loja.arquivo("historico.csv");
+    assert_eq!(historico.lines().count(), 3);
+    assert!(historico.contains(";VENDA;R010;;2;pix;499,80;"));
+    assert!(loja.arquivo("estoque.csv").contains("R020;;;;;2;BRL;;"));
+
+    // O que foi gravado volta igual na próxima abertura
+    let (relogios, historico, _) = loja.gravado();
+    assert_eq!(relogios["R010"].quantidade(), 3);
+    assert_eq!(historico.len(), 3);
+    loja.reabre();
+    assert_eq!(loja.app.relogios()["R010"].preco, Some(249.9));
+    assert_eq!(loja.app.historico().len(), 3);
+}
+
+#[test]
+fn edicao_externa_aparece_na_abertura() {
+    let mut loja = Loja::nova();
+    loja.digita("c").envia("R001 4");
+    loja.tecla(KeyCode::Esc);
+    let editado = loja
+        .arquivo("estoque.json")
+        .replace("\"quantidade\": 4", "\"quantidade\": 9");
+    std::fs::write(loja.pasta.join("estoque.json"), editado).unwrap();
+
+    loja.reabre();
+    assert!(loja.tela().contains("R001: quantidade 4 → 9."));
+    loja.tecla(KeyCode::Enter);
+    assert_eq!(loja.app.relogios()["R001"].quantidade(), 9);
+}
+
+#[test]
+fn trava_dos_dados_e_de_um_processo_por_vez() {
+    let _loja = Loja::nova();
+    let trava = relogio::persistencia::trava().unwrap();
+    assert!(trava.is_some());
+    assert!(relogio::persistencia::trava().unwrap().is_none());
+    drop(trava);
+    assert!(relogio::persistencia::trava().unwrap().is_some());
+}
+
+#[test]
+fn retencao_arquiva_kits_pelos_componentes_e_retoma_sem_duplicar() {
+    let cadastro = r#"{"codigo": "R1", "quantidade": 10, "operacao": "CADASTRO", "timestamp": "2020-01-05 10:00:00"}"#;
+    let venda = r#"{"codigo": "K", "quantidade": 1, "operacao": "VENDA", "timestamp": "2020-02-01 10:00:00"}"#;
+    let compra = r#"{"codigo": "R2", "quantidade": 3, "operacao": "COMPRA", "timestamp": "2019-03-01 10:00:00"}"#;
+    // Uma abertura anterior gravou historico_2019.json, mas caiu antes de
+    // tirar a compra das pendências.
+    let estoque = format!(
+        r#"{{
+  "relogios": [
+    {{"codigo": "R1", "quantidade": 8}},
+    {{"codigo": "K", "quantidade": 0, "componentes": [{{"codigo": "R1", "quantidade": 2}}]}}
+  ],
+  "historico": [{cadastro}, {venda}],
+  "historico_a_arquivar": [{compra}]
+}}"#
+    );
+    let ano = format!("[{compra}]");
+    let mut loja = Loja::com_arquivos(&[
+        ("config.toml", "retencao_historico_anos = 1\n"),
+        ("estoque.json", &estoque),
+        ("historico_2019.json", &ano),
+    ]);
+
+    let entradas = |loja: &Loja, ano: i32| {
+        serde_json::from_str::<Vec<relogio::Historico>>(
+            &loja.arquivo(format!("historico_{}.json", ano)),
+        )
+        .unwrap()
+        .len()
+    };
+    assert_eq!((entradas(&loja, 2019), entradas(&loja, 2020)), (1, 2));
+    assert!(!loja
+        .arquivo("estoque.json")
+        .contains("historico_a_arquivar"));
+    let (_, historico, _) = loja.gravado();
+    let saldos: Vec<(&str, i32)> = historico
+        .iter()
+        .map(|h| (h.codigo.as_str(), h.quantidade))
+        .collect();
+    // A venda do kit baixou 2 do componente, não 1 do próprio kit
+    assert!(saldos.contains(&("R1", 8)), "{:?}", saldos);
+    assert!(!saldos.contains(&("K", -1)), "{:?}", saldos);
+
+    loja.reabre();
+    assert_eq!((entradas(&loja, 2019), entradas(&loja, 2020)), (1, 2));
+}
+
+#[test]
+fn pre_venda_agenda_o_que_falta_sem_saldo_negativo() {
+    let estoque = r#"{
+  "relogios": [{"codigo": "R001", "quantidade": 1, "situacao": "encomenda"}],
+  "historico": [],
+  "pedidos_compra": [{"numero": 1, "fornecedor": "Casio", "data": "2024-01-01",
+    "itens": [{"codigo": "R001", "quantidade": 3}]}]
+}"#;
+    let mut loja = Loja::com_arquivos(&[("estoque.json", estoque)]);
+    loja.tecla(KeyCode::Enter).digita("v").envia(" 3 pix");
+    assert!(loja
+        .app
+        .mensagens()
+        .iter()
+        .any(|m| m == "Pré-venda: 2 un. de R001 saem da próxima entrega."));
+    assert_eq!(loja.app.relogios()["R001"].quantidade(), 0);
+    assert_eq!(loja.app.historico().len(), 1);
+    assert!(loja.tela().contains("0 (-2 pendente)"));
+
+    // O que vem a caminho já está todo pré-vendido
+    loja.tecla(KeyCode::Esc);
+    loja.tecla(KeyCode::Enter).digita("v").envia(" 2 pix");
+    assert!(loja.ultima_mensagem().starts_with("R001 sem estoque."));
+    assert_eq!(loja.app.relogios()["R001"].quantidade(), 0);
+
+    // A entrega baixa a pré-venda
+    loja.tecla(KeyCode::Esc);
+    loja.tecla(KeyCode::Enter).digita("a").envia(" 3");
+    assert_eq!(loja.app.relogios()["R001"].quantidade(), 1);
+    let vendas: Vec<i32> = loja
+        .app
+        .historico()
+        .iter()
+        .filter(|h| h.operacao == "VENDA")
+        .map(|h| h.quantidade)
+        .collect();
+    assert_eq!(vendas, [1, 2]);
+    assert!(!loja.tela().contains("pendente"));
+}
+
+#[test]
+fn venda_pela_api_nao_abre_a_gaveta() {
+    use std::io::{Read, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::{thread, time::Duration};
+
+    // A "porta serial" da gaveta é um arquivo que recebe o pulso ESC/POS
+    let tokens = r#"[{"nome": "site", "escopo": "escrita", "criado": "2024-01-01 00:00:00",
+  "hash": "3b2bc17dacb22167951688472048cf1f18a05ab6d5a2bc6681c9ac9cc3634a19"}]"#;
+    let mut loja = Loja::com_arquivos(&[
+        (
+            "estoque.json",
+            r#"{"relogios": [{"codigo": "R001", "quantidade": 5}], "historico": []}"#,
+        ),
+        ("config.toml", "gaveta_porta = \"gaveta\"\n"),
+        ("tokens.json", tokens),
+        ("gaveta", ""),
+    ]);
+    let endereco = TcpListener::bind("127.0.0.1:0")
+        .and_then(|l| l.local_addr())
+        .unwrap()
+        .to_string();
+    let args = vec!["--serve".to_string(), endereco.clone()];
+    thread::spawn(move || {
+        let _ = relogio::executa(&args);
+    });
+    let corpo = r#"{"codigo": "R001", "quantidade": 2, "pagamento": "dinheiro"}"#;
+    let mut conexao = (0..50)
+        .find_map(|_| {
+            TcpStream::connect(&endereco)
+                .map_err(|_| thread::sleep(Duration::from_millis(20)))
+                .ok()
+        })
+        .expect("servidor não subiu");
+    write!(
+        conexao,
+        "POST /api/vendas HTTP/1.1\r\nHost: {}\r\nAuthorization: Bearer segredo-de-teste\r\n\
+         Content-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+        endereco,
+        corpo.len(),
+        corpo
+    )
+    .unwrap();
+    let mut resposta = String::new();
+    conexao.read_to_string(&mut resposta).unwrap();
+    assert!(resposta.starts_with("HTTP/1.1 201"), "{}", resposta);
+    assert_eq!(loja.gravado().0["R001"].quantidade(), 3);
+    assert!(loja.arquivo("gaveta").is_empty());
+
+    // No balcão, a venda em dinheiro abre
+    loja.reabre();
+    loja.tecla(KeyCode::Enter).digita("v").envia(" 1");
+    assert_eq!(loja.app.relogios()["R001"].quantidade(), 2);
+    let pulso = std::fs::read(loja.pasta.join("gaveta")).unwrap();
+    assert_eq!(pulso, [0x1B, 0x70, 0x00, 0x19, 0xFA]);
+}
@@ -0,0 +1,101 @@
+const moeda = new Intl.NumberFormat("pt-BR", { style: "currency", currency: "BRL" });
+let estoque = [];
+
+// Token da API: vem de ?token= na primeira visita e fica guardado no navegador.
+const parametroToken = new URLSearchParams(location.search).get("token");
+if (parametroToken) localStorage.setItem("token", parametroToken);
+let token = localStorage.getItem("token") || "";
+
+async function api(caminho) {
+  const r = await fetch(caminho, { headers: { Authorization: "Bearer " + token } });
+  if (r.status === 401) {
+    token = prompt("Token de acesso à API:") || "";
+    localStorage.setItem("token", token);
+    throw new Error("token inválido");
+  }
+  return r.json();
+}
+
+function desenhaEstoque() {
+  const filtro = document.getElementById("filtro").value.toLowerCase();
+  const corpo = document.getElementById("estoque");
+  corpo.innerHTML = "";
+  for (const r of estoque) {
+    if (filtro && !(r.codigo + " " + r.descricao).toLowerCase().includes(filtro)) continue;
+    const tr = document.createElement("tr");
+    if (r.quantidade <= 0) tr.className = "zerado";
+    const celulas = [
+      r.codigo,
+      r.descricao,
+      r.quantidade + (r.kit ? " (kit)" : ""),
+      r.preco == null ? "" : moeda.format(r.preco),
+    ];
+    celulas.forEach((texto, i) => {
+      const td = document.createElement("td");
+      td.textContent = texto;
+      if (i >= 2) td.className = "num";
+      tr.appendChild(td);
+    });
+    corpo.appendChild(tr);
+  }
+}
+
+function desenhaGrafico(dias) {
+  const svg = document.getElementById("grafico");
+  const ultimos = dias.slice(-30);
+  const max = Math.max(1, ...ultimos.map((d) => d.unidades));
+  const largura = 600 / Math.max(ultimos.length, 1);
+  svg.innerHTML = ultimos
+    .map((d, i) => {
+      const altura = (d.unidades / max) * 190;
+      const x = i * largura;
+      return `<rect x="${x + 2}" y="${200 - altura}" width="${largura - 4}" height="${altura}">` +
+        `<title>${d.data}: ${d.unidades} un., ${moeda.format(d.receita)}</title></rect>` +
+        `<text x="${x + 2}" y="214">${d.data.slice(8)}</text>`;
+    })
+    .join("");
+}
+
+async function carrega() {
+  const [e, v] = await Promise.all([api("api/estoque"), api("api/vendas/diario")]);
+  estoque = e;
+  desenhaEstoque();
+  desenhaGrafico(v);
+  aviso("atualizado");
+}
+
+async function carregaVendas() {
+  desenhaGrafico(await api("api/vendas/diario"));
+}
+
+function aviso(texto) {
+  document.getElementById("atualizado").textContent =
+    texto + " às " + new Date().toLocaleTimeString("pt-BR");
+}
+
+// Eventos do servidor: itens de estoque alterados e vendas novas.
+function conecta() {
+  const protocolo = location.protocol === "https:" ? "wss://" : "ws://";
+  const ws = new WebSocket(protocolo + location.host + "/ws?token=" + encodeURIComponent(token));
+  ws.onmessage = (msg) => {
+    const evento = JSON.parse(msg.data);
+    if (evento.tipo === "estoque") {
+      for (const item of evento.itens) {
+        const i = estoque.findIndex((r) => r.codigo === item.codigo);
+        if (i >= 0) estoque[i] = item;
+        else estoque.push(item);
+      }
+      estoque.sort((a, b) => a.codigo.localeCompare(b.codigo));
+      desenhaEstoque();
+      aviso("estoque atualizado");
+    } else if (evento.tipo === "venda") {
+      carregaVendas();
+      aviso(`venda de ${evento.quantidade} x ${evento.codigo}`);
+    }
+  };
+  // Sem conexão, recarrega tudo e tenta de novo
+  ws.onclose = () => setTimeout(() => carrega().finally(conecta), 5000);
+}
+
+document.getElementById("filtro").addEventListener("input", desenhaEstoque);
+carrega().finally(conecta);